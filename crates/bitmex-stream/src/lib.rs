@@ -21,11 +21,11 @@ pub use anyhow::Error;
 ///
 /// It subscribes to the specified topics (comma-separated) and yields all messages.
 /// If the topics need authentication please use `subscribe_with_credentials` instead.
-pub fn subscribe<const N: usize>(
-    topics: [String; N],
+pub fn subscribe(
+    topics: impl IntoIterator<Item = String>,
     network: Network,
 ) -> impl Stream<Item = Result<String, Error>> + Unpin {
-    subscribe_impl(topics, network, None)
+    subscribe_impl(topics.into_iter().collect(), network, None)
 }
 
 /// Connects to the BitMex websocket API with authentication
@@ -33,12 +33,12 @@ pub fn subscribe<const N: usize>(
 /// It subscribes to the specified topics (comma-separated) and yields all messages.
 /// If invalid credentials have been provided but a topic was provided which needs authentication
 /// the stream will be closed.
-pub fn subscribe_with_credentials<const N: usize>(
-    topics: [String; N],
+pub fn subscribe_with_credentials(
+    topics: impl IntoIterator<Item = String>,
     network: Network,
     credentials: Credentials,
 ) -> impl Stream<Item = Result<String, Error>> + Unpin {
-    subscribe_impl(topics, network, Some(credentials))
+    subscribe_impl(topics.into_iter().collect(), network, Some(credentials))
 }
 
 /// Connects to the BitMex websocket API, subscribes to the specified topics (comma-separated) and
@@ -46,8 +46,8 @@ pub fn subscribe_with_credentials<const N: usize>(
 ///
 /// To keep the connection alive, a websocket `Ping` is sent every 5 seconds in case no other
 /// message was received in-between. This is according to BitMex's API documentation: https://www.bitmex.com/app/wsAPI#Heartbeats
-fn subscribe_impl<const N: usize>(
-    topics: [String; N],
+fn subscribe_impl(
+    topics: Vec<String>,
     network: Network,
     credentials: Option<Credentials>,
 ) -> impl Stream<Item = Result<String, Error>> + Unpin {
@@ -76,7 +76,7 @@ fn subscribe_impl<const N: usize>(
         }
         let _ = connection
                 .send(tungstenite::Message::try_from(Command::Subscribe(
-            topics.to_vec(),
+            topics,
         ))?)
         .await;
 
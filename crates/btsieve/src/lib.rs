@@ -1,4 +1,7 @@
+use anyhow::Result;
+use bitcoin::BlockHash;
 use bitcoin::Script;
+use bitcoin::Transaction;
 use bitcoin::Txid;
 use std::collections::hash_map::Entry;
 use std::collections::BTreeMap;
@@ -25,9 +28,15 @@ impl<E> State<E> {
         self.awaiting_status.len()
     }
 
-    /// Returns all scripts that we are currently monitoring.
-    pub fn monitoring_scripts(&self) -> impl Iterator<Item = &Script> + Clone {
-        self.awaiting_status.keys().map(|(_, script)| script)
+    /// Returns all scripts that we are currently monitoring together with the transactions we are
+    /// waiting for on each of them.
+    pub fn monitoring_scripts(&self) -> BTreeMap<Script, Vec<Txid>> {
+        let mut scripts = BTreeMap::<_, Vec<_>>::new();
+        for (txid, script) in self.awaiting_status.keys() {
+            scripts.entry(script.clone()).or_default().push(*txid);
+        }
+
+        scripts
     }
 
     pub fn monitor(&mut self, txid: Txid, script: Script, script_status: ScriptStatus, event: E) {
@@ -38,6 +47,41 @@ impl<E> State<E> {
     }
 }
 
+/// Access to the blockchain, e.g. through an Electrum server or a bitcoind node.
+///
+/// Calls may block, so they should not be made on an async executor directly.
+pub trait ChainBackend: Send + Sync + 'static {
+    /// The height of the tip of the blockchain.
+    fn tip_height(&self) -> Result<BlockHeight>;
+
+    /// The hash of the genesis block, used to check that we are on the expected network.
+    fn genesis_hash(&self) -> Result<BlockHash>;
+
+    /// The status of the transactions paying to or spending from `script`.
+    ///
+    /// `txids` are the transactions we are waiting for on `script`. Backends without an index
+    /// over scripts only return the status of these.
+    fn script_history(&self, script: &Script, txids: &[Txid]) -> Result<Vec<TxStatus>>;
+
+    /// Look up a transaction in the mempool or the blockchain.
+    fn transaction(&self, txid: &Txid) -> Result<Option<Transaction>>;
+
+    /// Broadcast a transaction.
+    fn broadcast(&self, tx: &Transaction) -> Result<Broadcast>;
+
+    /// Estimate the fee rate in satoshi per vbyte for a transaction to be confirmed within
+    /// `target_blocks` blocks, if the backend has enough data for an estimate.
+    fn estimate_fee_rate(&self, target_blocks: u16) -> Result<Option<f32>>;
+}
+
+/// The outcome of broadcasting a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Broadcast {
+    Published,
+    /// The transaction was already in the blockchain, e.g. because our counterparty published it.
+    AlreadyInChain,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct TxStatus {
     /// Confirmation height of the transaction.
@@ -375,6 +419,38 @@ mod tests {
         assert!(state.awaiting_status.is_empty());
     }
 
+    #[test]
+    fn monitoring_scripts_groups_transactions_by_script() {
+        let mut state = State::new(BlockHeight(0));
+        state.monitor(
+            txid1(),
+            script1(),
+            ScriptStatus::InMempool,
+            Event::FooFinality,
+        );
+        state.monitor(
+            txid2(),
+            script1(),
+            ScriptStatus::InMempool,
+            Event::BarFinality,
+        );
+        state.monitor(
+            txid2(),
+            script1(),
+            ScriptStatus::with_confirmations(1),
+            Event::BarFinality,
+        );
+
+        let scripts = state.monitoring_scripts();
+        let mut txids = scripts[&script1()].clone();
+        txids.sort();
+        let mut expected = vec![txid1(), txid2()];
+        expected.sort();
+
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(txids, expected);
+    }
+
     fn txid1() -> Txid {
         "1278ef8104c2f63c03d4d52bace29bed28bd5e664e67543735ddc95a39bfdc0f"
             .parse()
@@ -21,24 +21,12 @@ pub async fn next_maker_offers(
     let mut rx_a = rx_a.clone();
     let mut rx_b = rx_b.clone();
 
-    let non_empty_offer = |offers: MakerOffers| match &offers {
-        MakerOffers {
-            btcusd_long: Some(_),
-            ..
-        } if contract_symbol == &ContractSymbol::BtcUsd => Some(offers),
-        MakerOffers {
-            btcusd_short: Some(_),
-            ..
-        } if contract_symbol == &ContractSymbol::BtcUsd => Some(offers),
-        MakerOffers {
-            ethusd_long: Some(_),
-            ..
-        } if contract_symbol == &ContractSymbol::EthUsd => Some(offers),
-        MakerOffers {
-            ethusd_short: Some(_),
-            ..
-        } if contract_symbol == &ContractSymbol::EthUsd => Some(offers),
-        _ => None,
+    let non_empty_offer = |offers: MakerOffers| {
+        offers
+            .all()
+            .iter()
+            .any(|offer| &offer.contract_symbol == contract_symbol)
+            .then_some(offers)
     };
 
     let wait_until_a = next_with(&mut rx_a, non_empty_offer);
@@ -52,12 +40,7 @@ pub async fn next_maker_offers(
 pub async fn ensure_null_next_offers(rx: &mut watch::Receiver<MakerOffers>) -> Result<()> {
     let maker_offers = next(rx).await?;
 
-    ensure!(
-        maker_offers.btcusd_long.is_none()
-            && maker_offers.btcusd_short.is_none()
-            && maker_offers.ethusd_long.is_none()
-            && maker_offers.ethusd_short.is_none()
-    );
+    ensure!(maker_offers.is_empty());
 
     Ok(())
 }
@@ -13,10 +13,11 @@ use daemon::bdk::bitcoin::Amount;
 use daemon::bdk::bitcoin::Network;
 use daemon::bdk::bitcoin::SignedAmount;
 use daemon::bdk::bitcoin::Txid;
+use daemon::cfd_backup;
 use daemon::libp2p_utils::create_connect_multiaddr;
-use daemon::maia_core::secp256k1_zkp::XOnlyPublicKey;
 use daemon::online_status::ConnectionStatus;
 use daemon::oracle::Attestation;
+use daemon::position_metrics;
 use daemon::projection;
 use daemon::projection::Cfd;
 use daemon::projection::CfdState;
@@ -28,10 +29,13 @@ use daemon::Environment;
 use daemon::N_PAYOUTS;
 use maia::olivia::btc_example_0;
 use maia::OliviaData;
+use maker::cfd::OfferLevel;
 use maker::cfd::OfferParams;
 use model::libp2p::PeerId;
 use model::olivia::Announcement;
 use model::olivia::BitMexPriceEventId;
+use model::olivia::Oracles;
+use model::Catalogue;
 use model::CfdEvent;
 use model::CompleteFee;
 use model::ContractSymbol;
@@ -46,6 +50,7 @@ use model::Leverage;
 use model::LotSize;
 use model::OpeningFee;
 use model::OrderId;
+use model::PayoutCurve;
 use model::Position;
 use model::Price;
 use model::Role;
@@ -53,11 +58,11 @@ use model::TxFeeRate;
 use model::SETTLEMENT_INTERVAL;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use time::OffsetDateTime;
@@ -69,6 +74,7 @@ use xtra_bitmex_price_feed::LatestQuotes;
 use xtra_bitmex_price_feed::Quote;
 use xtra_libp2p::libp2p::Multiaddr;
 use xtra_libp2p::multiaddress_ext::MultiaddrExt;
+use xtra_libp2p_offer::maker::Audience;
 
 pub mod flow;
 pub mod maia;
@@ -252,17 +258,18 @@ pub struct OpenCfdArgs {
 
 pub fn initial_price_for(symbol: ContractSymbol) -> Price {
     Price::new(match symbol {
-        ContractSymbol::BtcUsd => dummy_btc_price(),
-        ContractSymbol::EthUsd => dummy_eth_price(),
+        symbol if symbol == ContractSymbol::BTC_USD => dummy_btc_price(),
+        symbol if symbol == ContractSymbol::ETH_USD => dummy_eth_price(),
+        symbol => panic!("No dummy price for {symbol}"),
     })
     .unwrap()
 }
 
 /// Different contract symbols can have different lot sizes
 fn lot_size_for(symbol: ContractSymbol) -> LotSize {
-    match symbol {
-        ContractSymbol::BtcUsd => LotSize::new(100),
-        ContractSymbol::EthUsd => LotSize::new(1),
+    match symbol.payout_curve() {
+        PayoutCurve::Inverse => LotSize::new(100),
+        PayoutCurve::Quanto => LotSize::new(1),
     }
 }
 
@@ -291,7 +298,7 @@ impl OpenCfdArgs {
 
 impl Default for OpenCfdArgs {
     fn default() -> Self {
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         Self {
             contract_symbol,
             initial_price: initial_price_for(contract_symbol),
@@ -331,14 +338,10 @@ pub async fn open_cfd(taker: &mut Taker, maker: &mut Maker, args: OpenCfdArgs) -
 
     mock_oracle_announcements(maker, taker, oracle_data.announcements()).await;
 
-    let offer_to_take = match (contract_symbol, position_maker) {
-        (ContractSymbol::BtcUsd, Position::Long) => received.btcusd_long,
-        (ContractSymbol::BtcUsd, Position::Short) => received.btcusd_short,
-        (ContractSymbol::EthUsd, Position::Long) => received.ethusd_long,
-        (ContractSymbol::EthUsd, Position::Short) => received.ethusd_short,
-    }
-    .context("Order for expected position not set")
-    .unwrap();
+    let offer_to_take = received
+        .best(contract_symbol, position_maker)
+        .context("Order for expected position not set")
+        .unwrap();
 
     let offer_id = offer_to_take.id;
 
@@ -541,11 +544,6 @@ impl FeeCalculator {
     }
 }
 
-fn oracle_pk() -> XOnlyPublicKey {
-    XOnlyPublicKey::from_str("ddd4636845a90185991826be5a494cde9f4a6947b1727217afedc6292fa4caf7")
-        .unwrap()
-}
-
 #[instrument]
 pub async fn start_both() -> (Maker, Taker) {
     let maker = Maker::start(&MakerConfig::default()).await;
@@ -560,38 +558,52 @@ pub async fn start_both() -> (Maker, Taker) {
 
 #[derive(Clone, Debug)]
 pub struct MakerConfig {
-    oracle_pk: XOnlyPublicKey,
+    oracles: Oracles,
     seed: RandomSeed,
     n_payouts: usize,
     libp2p_port: u16,
     blocked_peers: HashSet<xtra_libp2p::libp2p::PeerId>,
+    taker_groups: HashMap<xtra_libp2p::libp2p::PeerId, String>,
 }
 
 impl Default for MakerConfig {
     fn default() -> Self {
         Self {
-            oracle_pk: oracle_pk(),
+            oracles: Oracles::default(),
             seed: RandomSeed::default(),
             n_payouts: N_PAYOUTS,
             libp2p_port: portpicker::pick_unused_port().expect("to be able to find a free port"),
             blocked_peers: HashSet::new(),
+            taker_groups: HashMap::new(),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct TakerConfig {
-    oracle_pk: XOnlyPublicKey,
+    oracles: Oracles,
     seed: RandomSeed,
     n_payouts: usize,
+    accept_maker_requests: bool,
+}
+
+impl TakerConfig {
+    /// Carry out settlements and rollovers the maker asks for without approval.
+    pub fn accepting_maker_requests(self) -> Self {
+        Self {
+            accept_maker_requests: true,
+            ..self
+        }
+    }
 }
 
 impl Default for TakerConfig {
     fn default() -> Self {
         Self {
-            oracle_pk: oracle_pk(),
+            oracles: Oracles::default(),
             seed: RandomSeed::default(),
             n_payouts: N_PAYOUTS,
+            accept_maker_requests: false,
         }
     }
 }
@@ -690,7 +702,7 @@ impl Maker {
         let maker = maker::ActorSystem::new(
             db.clone(),
             wallet_addr,
-            config.oracle_pk,
+            config.oracles.clone(),
             |executor| {
                 let (oracle, mock) = OracleActor::new(executor);
                 oracle_mock = Some(mock);
@@ -707,8 +719,24 @@ impl Maker {
             config.n_payouts,
             projection_actor,
             identities.clone(),
-            endpoint_listen.clone(),
-            config.blocked_peers.clone(),
+            vec![endpoint_listen.clone()],
+            HashSet::from([endpoint_listen.clone()]),
+            maker::PeerAdmission {
+                configured_blocked_peers: config.blocked_peers.clone(),
+                ..Default::default()
+            },
+            config.taker_groups.clone(),
+            price_feed_addr.clone().into(),
+            None,
+            maker::risk::Limits::default(),
+            maker::order_policy::Policy::default(),
+            cfd_backup::Actor::new(
+                db.clone(),
+                Box::new(cfd_backup::InMemory::default()),
+                config.seed.derive_cfd_backup_key(),
+            ),
+            position_metrics::Actor::new(db.clone(), Catalogue::default().symbols()),
+            None,
         )
         .unwrap();
 
@@ -726,6 +754,7 @@ impl Maker {
             Network::Testnet,
             price_feed_addr.into(),
             Role::Maker,
+            Catalogue::default().symbols(),
             feed_senders,
         );
         tasks.add(projection_context.run(proj_actor));
@@ -755,6 +784,9 @@ impl Maker {
             leverage_choices,
             contract_symbol,
             lot_size,
+            levels_long,
+            levels_short,
+            audience,
         } = offer_params;
         self.system
             .set_offer_params(
@@ -769,6 +801,9 @@ impl Maker {
                 leverage_choices,
                 contract_symbol,
                 lot_size,
+                levels_long,
+                levels_short,
+                audience,
             )
             .await
             .unwrap();
@@ -886,7 +921,7 @@ impl Taker {
         let taker = daemon::TakerActorSystem::new(
             db.clone(),
             wallet_addr,
-            config.oracle_pk,
+            config.oracles.clone(),
             identities.clone(),
             |executor| {
                 let (oracle, mock) = OracleActor::new(executor);
@@ -903,10 +938,21 @@ impl Taker {
             price_feed_addr,
             config.n_payouts,
             Duration::from_secs(10),
+            None,
             projection_actor,
-            maker_identity,
-            maker_multiaddr.clone(),
+            vec![daemon::Maker {
+                identity: maker_identity,
+                multiaddrs: vec![maker_multiaddr.clone()],
+            }],
             Environment::new("test"),
+            cfd_backup::Actor::new(
+                db.clone(),
+                Box::new(cfd_backup::InMemory::default()),
+                config.seed.derive_cfd_backup_key(),
+            ),
+            position_metrics::Actor::new(db.clone(), Catalogue::default().symbols()),
+            None,
+            config.accept_maker_requests,
         )
         .unwrap();
 
@@ -924,6 +970,7 @@ impl Taker {
             Network::Testnet,
             taker.price_feed_actor.clone().into(),
             Role::Taker,
+            Catalogue::default().symbols(),
             feed_senders,
         );
         tasks.add(projection_context.run(proj_actor));
@@ -1014,7 +1061,7 @@ fn dummy_btc_quote() -> Quote {
         timestamp: OffsetDateTime::now_utc(),
         bid: dummy_btc_price(),
         ask: dummy_btc_price(),
-        symbol: xtra_bitmex_price_feed::ContractSymbol::BtcUsd,
+        symbol: xtra_bitmex_price_feed::ContractSymbol::BTC_USD,
     }
 }
 
@@ -1023,7 +1070,7 @@ fn dummy_eth_quote() -> Quote {
         timestamp: OffsetDateTime::now_utc(),
         bid: dummy_eth_price(),
         ask: dummy_eth_price(),
-        symbol: xtra_bitmex_price_feed::ContractSymbol::EthUsd,
+        symbol: xtra_bitmex_price_feed::ContractSymbol::ETH_USD,
     }
 }
 
@@ -1046,6 +1093,9 @@ impl OfferParamsBuilder {
             leverage_choices: vec![Leverage::TWO],
             contract_symbol: symbol,
             lot_size: lot_size_for(symbol),
+            levels_long: Vec::new(),
+            levels_short: Vec::new(),
+            audience: Audience::Everyone,
         })
     }
 
@@ -1062,6 +1112,25 @@ impl OfferParamsBuilder {
         self
     }
 
+    /// Add a price level to both sides of the order book, `offset` further away from the
+    /// initial price than the top level.
+    pub fn level(mut self, offset: Decimal, max_quantity: Contracts) -> Self {
+        let initial_price = initial_price_for(self.0.contract_symbol).into_decimal();
+        let level = |price: Decimal| OfferLevel {
+            price: Price::new(price).expect("positive price"),
+            min_quantity: self.0.min_quantity,
+            max_quantity,
+            leverage_choices: self.0.leverage_choices.clone(),
+        };
+
+        let long = level(initial_price - offset);
+        let short = level(initial_price + offset);
+        self.0.levels_long.push(long);
+        self.0.levels_short.push(short);
+
+        self
+    }
+
     pub fn build(self) -> OfferParams {
         self.0
     }
@@ -1075,7 +1144,7 @@ fn dummy_funding_fee() -> FundingFee {
         Leverage::ONE,
         Default::default(),
         0,
-        ContractSymbol::BtcUsd,
+        ContractSymbol::BTC_USD,
     )
     .unwrap()
 }
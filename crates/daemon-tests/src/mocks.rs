@@ -64,8 +64,9 @@ impl Mocks {
 
     pub async fn mock_oracle_announcement(&mut self, symbol: ContractSymbol) {
         let oracle_data = match symbol {
-            ContractSymbol::BtcUsd => btc_example_0(),
-            ContractSymbol::EthUsd => eth_example_0(),
+            symbol if symbol == ContractSymbol::BTC_USD => btc_example_0(),
+            symbol if symbol == ContractSymbol::ETH_USD => eth_example_0(),
+            symbol => panic!("No oracle data for {symbol}"),
         };
         self.mock_oracle_announcement_with(oracle_data.announcements())
             .await;
@@ -48,6 +48,10 @@ impl MonitorActor {
     async fn handle(&mut self, _: monitor::MonitorCetFinality) -> Result<()> {
         Ok(())
     }
+
+    async fn handle(&mut self, _: monitor::MonitorPunishFinality) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub struct MockMonitor {
@@ -50,12 +50,18 @@ impl WalletActor {
     async fn handle(&mut self, msg: wallet::Withdraw) -> Result<Txid> {
         self.mock.lock().await.withdraw(msg)
     }
+    async fn handle(&mut self, msg: wallet::DryRunWithdraw) -> Result<wallet::WithdrawPreview> {
+        self.mock.lock().await.dry_run_withdraw(msg)
+    }
     async fn handle(&mut self, msg: wallet::Sync) {
         self.mock.lock().await.sync(msg)
     }
     async fn handle(&mut self, msg: wallet::ImportSeed) -> Result<bdk::wallet::AddressInfo> {
         self.mock.lock().await.import_seed(msg)
     }
+    async fn handle(&mut self, msg: wallet::BuildCpfp) -> Result<wallet::Cpfp> {
+        self.mock.lock().await.build_cpfp(msg)
+    }
 }
 
 #[automock]
@@ -72,6 +78,13 @@ pub trait Wallet {
         unreachable!("mockall will reimplement this method")
     }
 
+    fn dry_run_withdraw(
+        &mut self,
+        _msg: wallet::DryRunWithdraw,
+    ) -> Result<wallet::WithdrawPreview> {
+        unreachable!("mockall will reimplement this method")
+    }
+
     fn sync(&mut self, _msg: wallet::Sync) {
         unreachable!("mockall will reimplement this method")
     }
@@ -79,6 +92,10 @@ pub trait Wallet {
     fn import_seed(&mut self, _msg: wallet::ImportSeed) -> Result<bdk::wallet::AddressInfo> {
         unreachable!("mockall will reimplement this method")
     }
+
+    fn build_cpfp(&mut self, _msg: wallet::BuildCpfp) -> Result<wallet::Cpfp> {
+        unreachable!("mockall will reimplement this method")
+    }
 }
 
 pub fn build_party_params(msg: wallet::BuildPartyParams) -> Result<PartyParams> {
@@ -1,3 +1,4 @@
+use daemon::maker_request;
 use daemon::projection::CfdState;
 use daemon_tests::confirm;
 use daemon_tests::dummy_btc_price;
@@ -12,8 +13,10 @@ use daemon_tests::open_cfd;
 use daemon_tests::start_both;
 use daemon_tests::wait_next_state;
 use daemon_tests::Maker;
+use daemon_tests::MakerConfig;
 use daemon_tests::OpenCfdArgs;
 use daemon_tests::Taker;
+use daemon_tests::TakerConfig;
 use model::ContractSymbol;
 use model::Contracts;
 use model::Leverage;
@@ -24,22 +27,22 @@ use rust_decimal_macros::dec;
 
 #[otel_test]
 async fn collaboratively_close_an_open_btc_usd_cfd_maker_going_short() {
-    collaboratively_close_an_open_cfd(Position::Short, ContractSymbol::BtcUsd).await;
+    collaboratively_close_an_open_cfd(Position::Short, ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn collaboratively_close_an_open_btc_usd_cfd_maker_going_long() {
-    collaboratively_close_an_open_cfd(Position::Long, ContractSymbol::BtcUsd).await;
+    collaboratively_close_an_open_cfd(Position::Long, ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn collaboratively_close_an_open_eth_usd_cfd_maker_going_short() {
-    collaboratively_close_an_open_cfd(Position::Short, ContractSymbol::EthUsd).await;
+    collaboratively_close_an_open_cfd(Position::Short, ContractSymbol::ETH_USD).await;
 }
 
 #[otel_test]
 async fn collaboratively_close_an_open_eth_usd_cfd_maker_going_long() {
-    collaboratively_close_an_open_cfd(Position::Long, ContractSymbol::EthUsd).await;
+    collaboratively_close_an_open_cfd(Position::Long, ContractSymbol::ETH_USD).await;
 }
 
 #[otel_test]
@@ -48,7 +51,11 @@ async fn maker_rejects_collab_settlement_after_commit_finality() {
     let cfd_args = OpenCfdArgs::default();
     let order_id = open_cfd(&mut taker, &mut maker, cfd_args.clone()).await;
     mock_quotes(&mut maker, &mut taker, cfd_args.contract_symbol).await;
-    taker.system.propose_settlement(order_id).await.unwrap();
+    taker
+        .system
+        .propose_settlement(order_id, None)
+        .await
+        .unwrap();
 
     wait_next_state!(
         order_id,
@@ -71,7 +78,11 @@ async fn maker_accepts_collab_settlement_after_commit_finality() {
     let order_id = open_cfd(&mut taker, &mut maker, cfd_args.clone()).await;
     mock_quotes(&mut maker, &mut taker, cfd_args.contract_symbol).await;
 
-    taker.system.propose_settlement(order_id).await.unwrap();
+    taker
+        .system
+        .propose_settlement(order_id, None)
+        .await
+        .unwrap();
 
     wait_next_state!(
         order_id,
@@ -87,14 +98,71 @@ async fn maker_accepts_collab_settlement_after_commit_finality() {
     wait_next_state!(order_id, maker, taker, CfdState::OpenCommitted);
 }
 
+#[otel_test]
+async fn maker_requests_settlement_and_taker_proposes_it_automatically() {
+    let mut maker = Maker::start(&MakerConfig::default()).await;
+    let mut taker = Taker::start(
+        &TakerConfig::default().accepting_maker_requests(),
+        maker.identity,
+        maker.connect_addr.clone(),
+    )
+    .await;
+    let cfd_args = OpenCfdArgs::default();
+    let order_id = open_cfd(&mut taker, &mut maker, cfd_args.clone()).await;
+    mock_quotes(&mut maker, &mut taker, cfd_args.contract_symbol).await;
+
+    maker.system.request_settlement(order_id).await.unwrap();
+
+    wait_next_state!(
+        order_id,
+        maker,
+        taker,
+        CfdState::IncomingSettlementProposal,
+        CfdState::OutgoingSettlementProposal
+    );
+}
+
+#[otel_test]
+async fn maker_requests_settlement_and_taker_proposes_it_after_approval() {
+    let (mut maker, mut taker) = start_both().await;
+    let cfd_args = OpenCfdArgs::default();
+    let order_id = open_cfd(&mut taker, &mut maker, cfd_args.clone()).await;
+    mock_quotes(&mut maker, &mut taker, cfd_args.contract_symbol).await;
+
+    maker.system.request_settlement(order_id).await.unwrap();
+
+    let taker_cfd = next_with(taker.cfd_feed(), |maybe_cfds| {
+        maybe_cfds
+            .and_then(one_cfd_with_state(CfdState::Open))
+            .filter(|cfd| cfd.maker_request.is_some())
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        taker_cfd.maker_request,
+        Some(maker_request::Request::Settlement)
+    );
+
+    taker.system.accept_maker_request(order_id).await.unwrap();
+
+    wait_next_state!(
+        order_id,
+        maker,
+        taker,
+        CfdState::IncomingSettlementProposal,
+        CfdState::OutgoingSettlementProposal
+    );
+}
+
 async fn collaboratively_close_an_open_cfd(
     position_maker: Position,
     contract_symbol: ContractSymbol,
 ) {
     let (mut maker, mut taker) = start_both().await;
     let oracle_data = match contract_symbol {
-        ContractSymbol::BtcUsd => btc_example_0(),
-        ContractSymbol::EthUsd => eth_example_0(),
+        symbol if symbol == ContractSymbol::BTC_USD => btc_example_0(),
+        symbol if symbol == ContractSymbol::ETH_USD => eth_example_0(),
+        symbol => panic!("Test fixtures only have oracle data for BTCUSD and ETHUSD, not {symbol}"),
     };
     let order_id = open_cfd(
         &mut taker,
@@ -111,7 +179,11 @@ async fn collaboratively_close_an_open_cfd(
     .await;
     mock_quotes(&mut maker, &mut taker, contract_symbol).await;
 
-    taker.system.propose_settlement(order_id).await.unwrap();
+    taker
+        .system
+        .propose_settlement(order_id, None)
+        .await
+        .unwrap();
 
     wait_next_state!(
         order_id,
@@ -181,11 +253,11 @@ fn assert_taker_liquidation_price(taker: &mut Taker) {
 fn expected_taker_liquidation_price(symbol: ContractSymbol, taker_position: Position) -> Decimal {
     match (symbol, taker_position) {
         // inverse payout curve
-        (ContractSymbol::BtcUsd, Position::Long) => dec!(32_767),
-        (ContractSymbol::BtcUsd, Position::Short) => dec!(99_620),
+        (ContractSymbol::BTC_USD, Position::Long) => dec!(32_767),
+        (ContractSymbol::BTC_USD, Position::Short) => dec!(99_620),
         // quanto linear payout curve
-        (ContractSymbol::EthUsd, Position::Long) => dec!(511),
-        (ContractSymbol::EthUsd, Position::Short) => dec!(2_250),
+        (ContractSymbol::ETH_USD, Position::Long) => dec!(511),
+        (ContractSymbol::ETH_USD, Position::Short) => dec!(2_250),
     }
 }
 
@@ -194,10 +266,10 @@ fn expected_taker_liquidation_price(symbol: ContractSymbol, taker_position: Posi
 fn expected_maker_liquidation_price(symbol: ContractSymbol, maker_position: Position) -> Decimal {
     match (symbol, maker_position) {
         // inverse payout curve
-        (ContractSymbol::BtcUsd, Position::Long) => dec!(16_383),
-        (ContractSymbol::BtcUsd, Position::Short) => dec!(99_751),
+        (ContractSymbol::BTC_USD, Position::Long) => dec!(16_383),
+        (ContractSymbol::BTC_USD, Position::Short) => dec!(99_751),
         // quanto linear payout curve
-        (ContractSymbol::EthUsd, Position::Long) => dec!(1),
-        (ContractSymbol::EthUsd, Position::Short) => dec!(3_000),
+        (ContractSymbol::ETH_USD, Position::Long) => dec!(1),
+        (ContractSymbol::ETH_USD, Position::Short) => dec!(3_000),
     }
 }
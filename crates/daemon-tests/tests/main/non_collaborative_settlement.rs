@@ -8,22 +8,22 @@ use otel_tests::otel_test;
 
 #[otel_test]
 async fn force_close_an_open_btc_usd_cfd_maker_going_short() {
-    force_close_open_cfd(Position::Short, ContractSymbol::BtcUsd).await;
+    force_close_open_cfd(Position::Short, ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn force_close_an_open_btc_usd_cfd_maker_going_long() {
-    force_close_open_cfd(Position::Long, ContractSymbol::BtcUsd).await;
+    force_close_open_cfd(Position::Long, ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn force_close_an_open_eth_usd_cfd_maker_going_short() {
-    force_close_open_cfd(Position::Short, ContractSymbol::EthUsd).await;
+    force_close_open_cfd(Position::Short, ContractSymbol::ETH_USD).await;
 }
 
 #[otel_test]
 async fn force_close_an_open_eth_usd_cfd_maker_going_long() {
-    force_close_open_cfd(Position::Long, ContractSymbol::EthUsd).await;
+    force_close_open_cfd(Position::Long, ContractSymbol::ETH_USD).await;
 }
 
 async fn force_close_open_cfd(position_maker: Position, contract_symbol: ContractSymbol) {
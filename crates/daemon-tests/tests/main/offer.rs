@@ -2,11 +2,13 @@ use daemon::projection::CfdOffer;
 use daemon::projection::MakerOffers;
 use daemon_tests::flow::ensure_null_next_offers;
 use daemon_tests::flow::next_maker_offers;
+use daemon_tests::initial_price_for;
 use daemon_tests::start_both;
 use daemon_tests::Maker;
 use daemon_tests::OfferParamsBuilder;
 use daemon_tests::Taker;
 use model::ContractSymbol;
+use model::Contracts;
 use model::Leverage;
 use model::Position;
 use otel_tests::otel_test;
@@ -15,12 +17,12 @@ use rust_decimal_macros::dec;
 
 #[otel_test]
 async fn taker_receives_btc_usd_offer_from_maker_on_publication() {
-    taker_receives_offer_from_maker_on_publication(ContractSymbol::BtcUsd).await;
+    taker_receives_offer_from_maker_on_publication(ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn taker_receives_eth_usd_offer_from_maker_on_publication() {
-    taker_receives_offer_from_maker_on_publication(ContractSymbol::EthUsd).await;
+    taker_receives_offer_from_maker_on_publication(ContractSymbol::ETH_USD).await;
 }
 
 #[otel_test]
@@ -28,8 +30,44 @@ async fn taker_can_receive_offers_with_different_symbols_from_maker() {
     let (mut maker, mut taker) = start_both().await;
     ensure_null_next_offers(taker.offers_feed()).await.unwrap();
 
-    test_offer(&mut maker, &mut taker, ContractSymbol::BtcUsd).await;
-    test_offer(&mut maker, &mut taker, ContractSymbol::EthUsd).await;
+    test_offer(&mut maker, &mut taker, ContractSymbol::BTC_USD).await;
+    test_offer(&mut maker, &mut taker, ContractSymbol::ETH_USD).await;
+}
+
+#[otel_test]
+async fn taker_receives_all_price_levels_from_maker() {
+    let (mut maker, mut taker) = start_both().await;
+    ensure_null_next_offers(taker.offers_feed()).await.unwrap();
+
+    let symbol = ContractSymbol::BTC_USD;
+    maker
+        .set_offer_params(
+            OfferParamsBuilder::new(symbol)
+                .level(dec!(100), Contracts::new(2000))
+                .level(dec!(200), Contracts::new(5000))
+                .build(),
+        )
+        .await;
+
+    let (published, received) =
+        next_maker_offers(maker.offers_feed(), taker.offers_feed(), &symbol)
+            .await
+            .unwrap();
+    assert_eq_offers(published, received.clone());
+
+    let short_levels = received
+        .levels(symbol, Position::Short)
+        .map(|offer| (offer.price.into_decimal(), offer.max_quantity))
+        .collect::<Vec<_>>();
+    let initial_price = initial_price_for(symbol).into_decimal();
+    assert_eq!(
+        short_levels,
+        vec![
+            (initial_price, Contracts::new(1000)),
+            (initial_price + dec!(100), Contracts::new(2000)),
+            (initial_price + dec!(200), Contracts::new(5000)),
+        ]
+    );
 }
 
 async fn publish_offer(maker: &mut Maker, contract_symbol: ContractSymbol) {
@@ -57,10 +95,7 @@ async fn test_offer(maker: &mut Maker, taker: &mut Taker, symbol: ContractSymbol
 
 /// Sanity-check values published on the feed
 fn verify_offer_values(offers: MakerOffers, symbol: ContractSymbol) {
-    let long_offer = match symbol {
-        ContractSymbol::BtcUsd => offers.btcusd_long.unwrap(),
-        ContractSymbol::EthUsd => offers.ethusd_long.unwrap(),
-    };
+    let long_offer = offers.best(symbol, Position::Long).unwrap();
     assert_eq!(long_offer.position_maker, Position::Long);
     let leverage_details = long_offer.leverage_details.first().unwrap();
     assert_eq!(leverage_details.leverage, Leverage::TWO);
@@ -69,10 +104,7 @@ fn verify_offer_values(offers: MakerOffers, symbol: ContractSymbol) {
         expected_taker_liquidation_price(symbol, long_offer.position_maker)
     );
 
-    let short_offer = match symbol {
-        ContractSymbol::BtcUsd => offers.btcusd_short.unwrap(),
-        ContractSymbol::EthUsd => offers.ethusd_short.unwrap(),
-    };
+    let short_offer = offers.best(symbol, Position::Short).unwrap();
     assert_eq!(short_offer.position_maker, Position::Short);
     let leverage_details = short_offer.leverage_details.first().unwrap();
     assert_eq!(leverage_details.leverage, Leverage::TWO);
@@ -89,10 +121,15 @@ async fn taker_receives_offer_from_maker_on_publication(contract_symbol: Contrac
 }
 
 fn assert_eq_offers(published: MakerOffers, received: MakerOffers) {
-    assert_eq_offer(published.btcusd_long, received.btcusd_long);
-    assert_eq_offer(published.btcusd_short, received.btcusd_short);
-    assert_eq_offer(published.ethusd_long, received.ethusd_long);
-    assert_eq_offer(published.ethusd_short, received.ethusd_short);
+    assert_eq!(
+        published.all().len(),
+        received.all().len(),
+        "Offer mismatch. Maker published {published:?}, taker received {received:?}"
+    );
+
+    for (published, received) in published.all().iter().zip(received.all()) {
+        assert_eq_offer(Some(published.clone()), Some(received.clone()));
+    }
 }
 
 /// Helper function to compare a maker's `CfdOffer` against the taker's corresponding `CfdOffer`.
@@ -134,6 +171,9 @@ fn assert_eq_offer(published: Option<CfdOffer>, received: Option<CfdOffer>) {
         received.leverage_details = Vec::new();
     }
 
+    // Only the taker tags offers with the peer id of the maker they come from
+    received.maker_peer_id = published.maker_peer_id;
+
     assert_eq!(published, received);
 }
 
@@ -142,10 +182,10 @@ fn assert_eq_offer(published: Option<CfdOffer>, received: Option<CfdOffer>) {
 fn expected_taker_liquidation_price(symbol: ContractSymbol, taker_position: Position) -> Decimal {
     match (symbol, taker_position) {
         // inverse payout curve
-        (ContractSymbol::BtcUsd, Position::Long) => dec!(33_333.333333333333333333333333),
-        (ContractSymbol::BtcUsd, Position::Short) => dec!(100_000),
+        (ContractSymbol::BTC_USD, Position::Long) => dec!(33_333.333333333333333333333333),
+        (ContractSymbol::BTC_USD, Position::Short) => dec!(100_000),
         // quanto linear payout curve
-        (ContractSymbol::EthUsd, Position::Long) => dec!(750),
-        (ContractSymbol::EthUsd, Position::Short) => dec!(2_250),
+        (ContractSymbol::ETH_USD, Position::Long) => dec!(750),
+        (ContractSymbol::ETH_USD, Position::Short) => dec!(2_250),
     }
 }
@@ -15,6 +15,7 @@ use model::ContractSymbol;
 use model::Contracts;
 use model::Leverage;
 use model::OrderId;
+use model::Position;
 use otel_tests::otel_test;
 
 #[otel_test]
@@ -23,7 +24,7 @@ async fn taker_places_order_and_maker_rejects() {
 
     ensure_null_next_offers(taker.offers_feed()).await.unwrap();
 
-    let symbol = ContractSymbol::BtcUsd;
+    let symbol = ContractSymbol::BTC_USD;
     maker
         .set_offer_params(OfferParamsBuilder::new(symbol).build())
         .await;
@@ -32,7 +33,10 @@ async fn taker_places_order_and_maker_rejects() {
         .await
         .unwrap();
 
-    let offer_id = received.btcusd_short.unwrap().id;
+    let offer_id = received
+        .best(ContractSymbol::BTC_USD, Position::Short)
+        .unwrap()
+        .id;
 
     taker.mocks.mock_oracle_announcement(symbol).await;
     maker.mocks.mock_oracle_announcement(symbol).await;
@@ -51,12 +55,12 @@ async fn taker_places_order_and_maker_rejects() {
 
 #[otel_test]
 async fn taker_places_btc_usd_order_and_maker_accepts_and_contract_setup() {
-    taker_places_order_and_maker_accepts_and_contract_setup(ContractSymbol::BtcUsd).await;
+    taker_places_order_and_maker_accepts_and_contract_setup(ContractSymbol::BTC_USD).await;
 }
 
 #[otel_test]
 async fn taker_places_eth_usd_order_and_maker_accepts_and_contract_setup() {
-    taker_places_order_and_maker_accepts_and_contract_setup(ContractSymbol::EthUsd).await;
+    taker_places_order_and_maker_accepts_and_contract_setup(ContractSymbol::ETH_USD).await;
 }
 
 async fn taker_places_order_and_maker_accepts_and_contract_setup(contract_symbol: ContractSymbol) {
@@ -73,10 +77,7 @@ async fn taker_places_order_and_maker_accepts_and_contract_setup(contract_symbol
             .await
             .unwrap();
 
-    let offer_id = match contract_symbol {
-        ContractSymbol::BtcUsd => received.btcusd_short.unwrap().id,
-        ContractSymbol::EthUsd => received.ethusd_short.unwrap().id,
-    };
+    let offer_id = received.best(contract_symbol, Position::Short).unwrap().id;
 
     taker.mocks.mock_oracle_announcement(contract_symbol).await;
     maker.mocks.mock_oracle_announcement(contract_symbol).await;
@@ -96,7 +97,7 @@ async fn taker_places_order_for_same_offer_twice_results_in_two_cfds() {
 
     ensure_null_next_offers(taker.offers_feed()).await.unwrap();
 
-    let symbol = ContractSymbol::BtcUsd;
+    let symbol = ContractSymbol::BTC_USD;
     maker
         .set_offer_params(OfferParamsBuilder::new(symbol).build())
         .await;
@@ -105,7 +106,10 @@ async fn taker_places_order_for_same_offer_twice_results_in_two_cfds() {
         .await
         .unwrap();
 
-    let offer_id = received.btcusd_short.unwrap().id;
+    let offer_id = received
+        .best(ContractSymbol::BTC_USD, Position::Short)
+        .unwrap()
+        .id;
 
     taker.mocks.mock_oracle_announcement(symbol).await;
     maker.mocks.mock_oracle_announcement(symbol).await;
@@ -7,24 +7,29 @@ use daemon_tests::maia::olivia::btc_example_0;
 use daemon_tests::maia::olivia::btc_example_1;
 use daemon_tests::maia::olivia::eth_example_0;
 use daemon_tests::maia::OliviaData;
+use daemon_tests::mock_oracle_announcements;
 use daemon_tests::open_cfd;
 use daemon_tests::start_both;
 use daemon_tests::wait_next_state;
 use daemon_tests::FeeCalculator;
 use daemon_tests::Maker;
+use daemon_tests::MakerConfig;
 use daemon_tests::OfferParamsBuilder;
 use daemon_tests::OpenCfdArgs;
 use daemon_tests::Taker;
+use daemon_tests::TakerConfig;
 use model::olivia::BitMexPriceEventId;
 use model::ContractSymbol;
 use model::OrderId;
 use model::Position;
 use otel_tests::otel_test;
+use std::collections::HashSet;
+use xtra_libp2p_rollover::policy::Policy;
 
 #[otel_test]
 async fn rollover_an_open_btc_usd_cfd_maker_going_short() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     rollover(
         &mut maker,
@@ -39,7 +44,7 @@ async fn rollover_an_open_btc_usd_cfd_maker_going_short() {
 #[otel_test]
 async fn rollover_an_open_eth_usd_cfd_maker_going_short() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::EthUsd, eth_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::ETH_USD, eth_example_0()).await;
 
     rollover(
         &mut maker,
@@ -54,7 +59,7 @@ async fn rollover_an_open_eth_usd_cfd_maker_going_short() {
 #[otel_test]
 async fn rollover_an_open_cfd_maker_going_long() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Long, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Long, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     rollover(
         &mut maker,
@@ -71,7 +76,7 @@ async fn double_rollover_an_open_cfd() {
     // double rollover ensures that both parties properly succeeded and can do another rollover
 
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     rollover(
         &mut maker,
@@ -92,6 +97,80 @@ async fn double_rollover_an_open_cfd() {
     .await;
 }
 
+#[otel_test]
+async fn maker_requests_rollover_right_after_previous_rollover() {
+    let mut maker = Maker::start(&MakerConfig::default()).await;
+    let mut taker = Taker::start(
+        &TakerConfig::default().accepting_maker_requests(),
+        maker.identity,
+        maker.connect_addr.clone(),
+    )
+    .await;
+    let open_cfd_args = OpenCfdArgs::default();
+    let fee_calculator = open_cfd_args.fee_calculator();
+    let order_id = open_cfd(&mut taker, &mut maker, open_cfd_args).await;
+    maker
+        .set_offer_params(OfferParamsBuilder::new(ContractSymbol::BTC_USD).build())
+        .await;
+
+    rollover(
+        &mut maker,
+        &mut taker,
+        order_id,
+        btc_example_0(),
+        fee_calculator.complete_fee_for_expired_settlement_event(),
+    )
+    .await;
+
+    mock_oracle_announcements(&mut maker, &mut taker, btc_example_0().announcements()).await;
+    let commit_txid_after_first_rollover = taker.latest_commit_txid();
+
+    maker.system.request_rollover(order_id).await.unwrap();
+
+    wait_next_state!(order_id, maker, taker, CfdState::RolloverSetup);
+    wait_next_state!(order_id, maker, taker, CfdState::Open);
+
+    assert_ne!(
+        commit_txid_after_first_rollover,
+        taker.latest_commit_txid(),
+        "The commit_txid should have changed after the requested rollover"
+    );
+    assert_eq!(taker.latest_commit_txid(), maker.latest_commit_txid());
+    daemon_tests::rollover::assert_rollover_fees(
+        &mut maker,
+        &mut taker,
+        fee_calculator.complete_fee_for_rollover_hours(48),
+    );
+}
+
+#[otel_test]
+async fn pending_maker_request_is_dropped_once_cfd_changes() {
+    let (mut maker, mut taker, order_id, fee_calculator) =
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
+
+    maker.system.request_rollover(order_id).await.unwrap();
+    next_with(taker.cfd_feed(), |maybe_cfds| {
+        maybe_cfds
+            .and_then(one_cfd_with_state(CfdState::Open))
+            .filter(|cfd| cfd.maker_request.is_some())
+    })
+    .await
+    .unwrap();
+
+    // The taker rolls over on its own instead of accepting the request
+    rollover(
+        &mut maker,
+        &mut taker,
+        order_id,
+        btc_example_0(),
+        fee_calculator.complete_fee_for_expired_settlement_event(),
+    )
+    .await;
+
+    assert_eq!(taker.first_cfd().maker_request, None);
+    assert!(taker.system.accept_maker_request(order_id).await.is_err());
+}
+
 #[otel_test]
 async fn maker_rejects_rollover_of_open_cfd() {
     let (mut maker, mut taker) = start_both().await;
@@ -100,10 +179,12 @@ async fn maker_rejects_rollover_of_open_cfd() {
     let taker_commit_txid_after_contract_setup = taker.latest_commit_txid();
     let maker_commit_txid_after_contract_setup = taker.latest_commit_txid();
 
-    let is_accepting_rollovers = false;
     maker
         .system
-        .update_rollover_configuration(is_accepting_rollovers)
+        .set_rollover_configuration(Policy {
+            paused: true,
+            ..Policy::default()
+        })
         .await
         .unwrap();
 
@@ -126,10 +207,35 @@ async fn maker_rejects_rollover_of_open_cfd() {
     );
 }
 
+#[otel_test]
+async fn maker_rejects_rollover_of_paused_contract_symbol() {
+    let (mut maker, mut taker) = start_both().await;
+    let order_id = open_cfd(&mut taker, &mut maker, OpenCfdArgs::default()).await;
+
+    let commit_txid_after_contract_setup = taker.latest_commit_txid();
+
+    maker
+        .system
+        .set_rollover_configuration(Policy {
+            paused_contract_symbols: HashSet::from([ContractSymbol::BTC_USD]),
+            ..Policy::default()
+        })
+        .await
+        .unwrap();
+
+    taker
+        .trigger_rollover_with_latest_dlc_params(order_id)
+        .await;
+
+    wait_next_state!(order_id, maker, taker, CfdState::Open);
+
+    assert_eq!(commit_txid_after_contract_setup, taker.latest_commit_txid());
+}
+
 #[otel_test]
 async fn given_rollover_completed_when_taker_fails_rollover_can_retry() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     // 1. Do two rollovers
     rollover(
@@ -197,7 +303,7 @@ async fn given_rollover_completed_when_taker_fails_rollover_can_retry() {
 #[otel_test]
 async fn given_contract_setup_completed_when_taker_fails_first_rollover_can_retry() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     let taker_commit_txid_after_contract_setup = taker.latest_commit_txid();
     let taker_settlement_event_id_after_contract_setup = taker.latest_settlement_event_id();
@@ -255,7 +361,7 @@ async fn given_contract_setup_completed_when_taker_fails_first_rollover_can_retr
 #[otel_test]
 async fn given_contract_setup_completed_when_taker_fails_two_rollovers_can_retry() {
     let (mut maker, mut taker, order_id, fee_calculator) =
-        prepare_rollover(Position::Short, ContractSymbol::BtcUsd, btc_example_0()).await;
+        prepare_rollover(Position::Short, ContractSymbol::BTC_USD, btc_example_0()).await;
 
     let taker_commit_txid_after_contract_setup = taker.latest_commit_txid();
     let taker_settlement_event_id_after_contract_setup = taker.latest_settlement_event_id();
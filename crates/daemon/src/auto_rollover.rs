@@ -1,4 +1,5 @@
 use crate::command;
+use crate::fee_estimate;
 use crate::oracle;
 use crate::Txid;
 use anyhow::Result;
@@ -8,8 +9,11 @@ use model::libp2p::PeerId;
 use model::olivia::BitMexPriceEventId;
 use model::CannotRollover;
 use model::OrderId;
+use rollover::protocol::RejectReason;
 use rollover::taker::ProposeRollover;
+use rollover::taker::Rejected;
 use sqlite_db;
+use std::collections::HashMap;
 use std::time::Duration;
 use time::OffsetDateTime;
 use xtra::Address;
@@ -17,22 +21,46 @@ use xtra_productivity::xtra_productivity;
 use xtras::SendAsyncNext;
 use xtras::SendInterval;
 
+/// How long we wait before proposing a rollover again after the first rejection.
+const INITIAL_BACKOFF: time::Duration = time::Duration::minutes(10);
+
+/// The longest we wait before proposing a rollover again after a rejection.
+const MAX_BACKOFF: time::Duration = time::Duration::hours(6);
+
 pub struct Actor {
     db: sqlite_db::Connection,
-    libp2p_rollover:
-        Address<rollover::taker::Actor<command::Executor, oracle::AnnouncementsChannel>>,
+    libp2p_rollover: Address<
+        rollover::taker::Actor<
+            command::Executor,
+            oracle::AnnouncementsChannel,
+            fee_estimate::EstimateChannel,
+        >,
+    >,
+    /// CFDs whose rollover the maker rejected, which we don't propose again before the deadline.
+    backoff: HashMap<OrderId, Backoff>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    until: OffsetDateTime,
+    rejections: u32,
 }
 
 impl Actor {
     pub fn new(
         db: sqlite_db::Connection,
         libp2p_rollover: Address<
-            rollover::taker::Actor<command::Executor, oracle::AnnouncementsChannel>,
+            rollover::taker::Actor<
+                command::Executor,
+                oracle::AnnouncementsChannel,
+                fee_estimate::EstimateChannel,
+            >,
         >,
     ) -> Self {
         Self {
             db,
             libp2p_rollover,
+            backoff: HashMap::new(),
         }
     }
 }
@@ -75,6 +103,34 @@ impl Actor {
             unreachable!("this should not happen on the taker side, we always know the peer id ,")
         }
     }
+
+    async fn handle(&mut self, Rejected { order_id, reason }: Rejected) {
+        let rejections = self
+            .backoff
+            .get(&order_id)
+            .map_or(1, |backoff| backoff.rejections + 1);
+        let delay = backoff_delay(reason.as_ref(), rejections);
+        let minutes = delay.whole_minutes();
+
+        match &reason {
+            Some(reason) => tracing::info!(
+                %order_id,
+                %reason,
+                "Maker rejected rollover, retrying in {minutes} minutes"
+            ),
+            None => {
+                tracing::info!(%order_id, "Maker rejected rollover, retrying in {minutes} minutes")
+            }
+        }
+
+        self.backoff.insert(
+            order_id,
+            Backoff {
+                until: OffsetDateTime::now_utc() + delay,
+                rejections,
+            },
+        );
+    }
 }
 
 impl Actor {
@@ -86,6 +142,7 @@ impl Actor {
             .address()
             .expect("actor to be able to give address to itself");
 
+        let now = OffsetDateTime::now_utc();
         let mut stream = self.db.load_all_open_cfds::<model::Cfd>(());
 
         while let Some(cfd) = stream.next().await {
@@ -99,7 +156,10 @@ impl Actor {
             let order_id = cfd.id();
             let maker_peer_id = cfd.counterparty_peer_id();
 
-            match cfd.can_auto_rollover_taker(OffsetDateTime::now_utc()) {
+            match cfd.can_auto_rollover_taker(now) {
+                Ok(_) if self.is_backing_off(order_id, now) => {
+                    tracing::trace!(%order_id, "Backing off from auto-rollover after rejection");
+                }
                 Ok((from_commit_txid, from_settlement_event_id)) => {
                     this.send_async_next(Rollover {
                         order_id,
@@ -113,6 +173,9 @@ impl Actor {
                     tracing::error!(%order_id, "Cannot auto-rollover CFD without a DLC");
                 }
                 Err(reason) => {
+                    // Once the CFD was rolled over or closed, past rejections no longer matter
+                    self.backoff.remove(&order_id);
+
                     tracing::trace!(%order_id, %reason, "CFD is not eligible for auto-rollover");
                 }
             }
@@ -120,6 +183,28 @@ impl Actor {
 
         Ok(())
     }
+
+    fn is_backing_off(&self, order_id: OrderId, now: OffsetDateTime) -> bool {
+        self.backoff
+            .get(&order_id)
+            .map_or(false, |backoff| backoff.until > now)
+    }
+}
+
+/// How long to wait before proposing a rollover again after the maker rejected it `rejections`
+/// times in a row, the last time for `reason`.
+///
+/// Reasons which are not going to change on their own back off for the maximum right away.
+fn backoff_delay(reason: Option<&RejectReason>, rejections: u32) -> time::Duration {
+    match reason {
+        Some(RejectReason::PositionTooLarge { .. } | RejectReason::TakerNotAllowed) => MAX_BACKOFF,
+        _ => {
+            let factor = 2_i32.saturating_pow(rejections.saturating_sub(1));
+            INITIAL_BACKOFF
+                .checked_mul(factor)
+                .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+        }
+    }
 }
 
 #[async_trait]
@@ -156,3 +241,30 @@ pub struct Rollover {
     pub from_commit_txid: Txid,
     pub from_settlement_event_id: BitMexPriceEventId,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::ContractSymbol;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_maximum() {
+        let reason = RejectReason::ContractSymbolPaused {
+            contract_symbol: ContractSymbol::ETH_USD,
+        };
+
+        assert_eq!(backoff_delay(Some(&reason), 1), time::Duration::minutes(10));
+        assert_eq!(backoff_delay(Some(&reason), 2), time::Duration::minutes(20));
+        assert_eq!(backoff_delay(Some(&reason), 3), time::Duration::minutes(40));
+        assert_eq!(backoff_delay(None, 10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(None, 100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn permanent_rejections_back_off_for_maximum() {
+        assert_eq!(
+            backoff_delay(Some(&RejectReason::TakerNotAllowed), 1),
+            MAX_BACKOFF
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Encrypted backups of the state of a daemon.
+//!
+//! A backup contains a consistent snapshot of the database, including the DLCs, revocation
+//! secrets and adaptor signatures of all CFDs, and the seed files of the daemon. It is encrypted
+//! with XChaCha20-Poly1305 using a key derived from a passphrase with Argon2id.
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::Key;
+use chacha20poly1305::KeyInit;
+use chacha20poly1305::XChaCha20Poly1305;
+use chacha20poly1305::XNonce;
+use model::Timestamp;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::Path;
+
+const MAGIC: &[u8; 16] = b"ITCHYSATS-BACKUP";
+const FORMAT_VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    created_at: Timestamp,
+    /// The version of the latest migration of the database snapshot.
+    schema_version: i64,
+    #[serde(with = "hex")]
+    database: Vec<u8>,
+    files: Vec<File>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct File {
+    name: String,
+    #[serde(with = "hex")]
+    content: Vec<u8>,
+}
+
+/// Create an encrypted backup of the database and the given files in `data_dir`.
+///
+/// Files that do not exist are skipped, e.g. the wallet seed file of a taker whose seed is
+/// managed by the app it is embedded in.
+pub async fn create(
+    db: &sqlite_db::Connection,
+    data_dir: &Path,
+    files: &[&str],
+    passphrase: &str,
+) -> Result<Vec<u8>> {
+    ensure!(!passphrase.is_empty(), "Passphrase must not be empty");
+
+    let snapshot_path = data_dir.join(format!("snapshot-{}.sqlite", Timestamp::now()));
+    db.snapshot(&snapshot_path).await?;
+
+    let snapshot = read_snapshot(&snapshot_path).await;
+    tokio::fs::remove_file(&snapshot_path)
+        .await
+        .context("Failed to remove database snapshot")?;
+    let (schema_version, database) = snapshot?;
+
+    let mut backup_files = Vec::new();
+    for name in files {
+        let path = data_dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+
+        let content = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        backup_files.push(File {
+            name: name.to_string(),
+            content,
+        });
+    }
+
+    let payload = Payload {
+        created_at: Timestamp::now(),
+        schema_version,
+        database,
+        files: backup_files,
+    };
+
+    encrypt(&serde_json::to_vec(&payload)?, passphrase)
+}
+
+/// Restore an encrypted backup into `data_dir`.
+///
+/// The database of the backup is restored to `database_file` and only the given `files` are
+/// restored from it. Existing files are moved aside rather than overwritten. Must not be called
+/// while the database is open.
+pub async fn restore(
+    backup: &[u8],
+    passphrase: &str,
+    data_dir: &Path,
+    database_file: &str,
+    files: &[&str],
+) -> Result<()> {
+    let payload = decrypt(backup, passphrase)?;
+    let payload = serde_json::from_slice::<Payload>(&payload).context("Malformed backup")?;
+
+    if let Some(file) = payload
+        .files
+        .iter()
+        .find(|file| !files.contains(&file.name.as_str()))
+    {
+        bail!("Backup contains unexpected file {}", file.name);
+    }
+
+    let created_at = payload.created_at;
+    let snapshot_path = data_dir.join(format!("restore-{created_at}.sqlite"));
+    tokio::fs::write(&snapshot_path, &payload.database).await?;
+
+    let schema_version = validate_snapshot(&snapshot_path, payload.schema_version).await;
+    if schema_version.is_err() {
+        tokio::fs::remove_file(&snapshot_path).await?;
+    }
+    let schema_version = schema_version?;
+
+    let suffix = format!("{}-backup", Timestamp::now());
+
+    move_aside(&data_dir.join(database_file), &suffix).await?;
+    tokio::fs::rename(&snapshot_path, data_dir.join(database_file)).await?;
+
+    for file in payload.files {
+        let path = data_dir.join(&file.name);
+        move_aside(&path, &suffix).await?;
+        tokio::fs::write(&path, file.content).await?;
+    }
+
+    let latest = sqlite_db::latest_migration_version();
+    tracing::info!(
+        "Restored backup created at {created_at} with schema version {schema_version}, \
+         latest is {latest}"
+    );
+
+    Ok(())
+}
+
+async fn read_snapshot(path: &Path) -> Result<(i64, Vec<u8>)> {
+    let schema_version = sqlite_db::validate_snapshot(path).await?;
+    let database = tokio::fs::read(path).await?;
+
+    Ok((schema_version, database))
+}
+
+async fn validate_snapshot(path: &Path, expected_schema_version: i64) -> Result<i64> {
+    let schema_version = sqlite_db::validate_snapshot(path).await?;
+    ensure!(
+        schema_version == expected_schema_version,
+        "Schema version of backup does not match its database"
+    );
+
+    Ok(schema_version)
+}
+
+async fn move_aside(path: &Path, suffix: &str) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let new_path = path.with_file_name(format!(
+        "{}-{suffix}",
+        path.file_name()
+            .context("Path without file name")?
+            .to_string_lossy()
+    ));
+    tracing::info!("Moving {} to {}", path.display(), new_path.display());
+
+    tokio::fs::rename(path, &new_path)
+        .await
+        .with_context(|| format!("Failed to move {}", path.display()))
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+    let salt = rng.gen::<[u8; SALT_LEN]>();
+    let nonce = rng.gen::<[u8; NONCE_LEN]>();
+
+    let cipher = cipher(passphrase, &salt)?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt backup"))?;
+
+    let mut backup = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    backup.extend_from_slice(MAGIC);
+    backup.push(FORMAT_VERSION);
+    backup.extend_from_slice(&salt);
+    backup.extend_from_slice(&nonce);
+    backup.extend_from_slice(&ciphertext);
+
+    Ok(backup)
+}
+
+fn decrypt(backup: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    ensure!(
+        backup.len() > HEADER_LEN && backup.starts_with(MAGIC),
+        "Not an ItchySats backup"
+    );
+
+    let (header, ciphertext) = backup.split_at(HEADER_LEN);
+    let (version, header) = header[MAGIC.len()..].split_first().expect("header length");
+    ensure!(
+        *version == FORMAT_VERSION,
+        "Unsupported backup format version {version}"
+    );
+    let (salt, nonce) = header.split_at(SALT_LEN);
+
+    cipher(passphrase, salt)?
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt backup, wrong passphrase?"))
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        ..argon2::Config::default()
+    };
+    let key = argon2::hash_raw(passphrase.as_bytes(), salt, &config)?;
+
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::OrderId;
+    use std::env;
+    use std::path::PathBuf;
+
+    async fn data_dir() -> PathBuf {
+        let data_dir = env::temp_dir().join(format!("backup-{}", OrderId::default()));
+        tokio::fs::create_dir_all(&data_dir).await.unwrap();
+
+        data_dir
+    }
+
+    #[test]
+    fn encrypted_payload_roundtrips_only_with_passphrase() {
+        let backup = encrypt(b"secrets", "correct horse").unwrap();
+
+        assert_eq!(decrypt(&backup, "correct horse").unwrap(), b"secrets");
+        assert!(decrypt(&backup, "battery staple").is_err());
+        assert!(decrypt(&backup[1..], "correct horse").is_err());
+    }
+
+    #[tokio::test]
+    async fn restores_database_and_seed_files() {
+        let source = data_dir().await;
+        let db = sqlite_db::memory().await.unwrap();
+        tokio::fs::write(source.join("seed"), b"seed")
+            .await
+            .unwrap();
+
+        let backup = create(&db, &source, &["seed", "missing"], "passphrase")
+            .await
+            .unwrap();
+
+        let target = data_dir().await;
+        tokio::fs::write(target.join("seed"), b"old seed")
+            .await
+            .unwrap();
+
+        restore(&backup, "passphrase", &target, "taker.sqlite", &["seed"])
+            .await
+            .unwrap();
+
+        let schema_version = sqlite_db::validate_snapshot(&target.join("taker.sqlite"))
+            .await
+            .unwrap();
+        assert_eq!(schema_version, sqlite_db::latest_migration_version());
+        assert_eq!(tokio::fs::read(target.join("seed")).await.unwrap(), b"seed");
+
+        tokio::fs::remove_dir_all(source).await.unwrap();
+        tokio::fs::remove_dir_all(target).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_unexpected_files() {
+        let source = data_dir().await;
+        let db = sqlite_db::memory().await.unwrap();
+        tokio::fs::write(source.join("seed"), b"seed")
+            .await
+            .unwrap();
+
+        let backup = create(&db, &source, &["seed"], "passphrase").await.unwrap();
+
+        let target = data_dir().await;
+        let result = restore(&backup, "passphrase", &target, "taker.sqlite", &[]).await;
+
+        assert!(result.is_err());
+        assert!(!target.join("taker.sqlite").exists());
+
+        tokio::fs::remove_dir_all(source).await.unwrap();
+        tokio::fs::remove_dir_all(target).await.unwrap();
+    }
+}
@@ -0,0 +1,746 @@
+//! Static backups of open CFDs.
+//!
+//! After every change of the DLC of a CFD we write a small backup of the CFD to a [`Sink`]. It
+//! contains everything needed to recreate the CFD in the database with its latest DLC, which is
+//! enough to sign the commit transaction, the CETs and the refund transaction. Together with the
+//! wallet seed this allows force-closing open CFDs after the database was lost.
+//!
+//! Backups are encrypted with a key derived from the wallet seed, see
+//! [`Seed::derive_cfd_backup_key`](crate::seed::Seed::derive_cfd_backup_key).
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use bdk::bitcoin::OutPoint;
+use bdk::bitcoin::Script;
+use bdk::bitcoin::Transaction;
+use bdk::bitcoin::Txid;
+use btsieve::ChainBackend;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::Key;
+use chacha20poly1305::KeyInit;
+use chacha20poly1305::XChaCha20Poly1305;
+use chacha20poly1305::XNonce;
+use model::libp2p::PeerId;
+use model::olivia::OracleConfig;
+use model::Cfd;
+use model::CfdEvent;
+use model::ContractSymbol;
+use model::Contracts;
+use model::Dlc;
+use model::EventKind;
+use model::FundingRate;
+use model::Identity;
+use model::Leverage;
+use model::OfferId;
+use model::OpeningFee;
+use model::OrderId;
+use model::Position;
+use model::Price;
+use model::Role;
+use model::Timestamp;
+use model::TxFeeRate;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use time::Duration;
+use xtra_productivity::xtra_productivity;
+
+const FORMAT_VERSION: u8 = 1;
+const NONCE_LEN: usize = 24;
+const FILE_EXTENSION: &str = "cfdbackup";
+
+/// The key CFD backups are encrypted with.
+#[derive(Clone, Copy)]
+pub struct BackupKey([u8; 32]);
+
+impl BackupKey {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(Key::from_slice(&self.0))
+    }
+}
+
+/// Where CFD backups are written to.
+#[async_trait]
+pub trait Sink: Send + Sync + 'static {
+    /// Store the backup of a CFD, replacing any previous backup of the same CFD.
+    async fn store(&self, order_id: OrderId, backup: Vec<u8>) -> Result<()>;
+
+    /// Remove the backup of a CFD.
+    async fn remove(&self, order_id: OrderId) -> Result<()>;
+
+    /// Load the backups of all CFDs.
+    async fn load_all(&self) -> Result<Vec<Vec<u8>>>;
+}
+
+/// Stores every CFD backup in a file in a local directory.
+///
+/// The directory should be on a different disk than the database to be of any use.
+pub struct LocalDirectory {
+    path: PathBuf,
+}
+
+impl LocalDirectory {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn file(&self, order_id: OrderId) -> PathBuf {
+        self.path.join(format!("{order_id}.{FILE_EXTENSION}"))
+    }
+}
+
+#[async_trait]
+impl Sink for LocalDirectory {
+    async fn store(&self, order_id: OrderId, backup: Vec<u8>) -> Result<()> {
+        tokio::fs::create_dir_all(&self.path).await?;
+
+        // Write to a temporary file first so we never end up with a partially written backup
+        let file = self.file(order_id);
+        let tmp_file = file.with_extension("tmp");
+        tokio::fs::write(&tmp_file, backup).await?;
+        tokio::fs::rename(&tmp_file, &file)
+            .await
+            .with_context(|| format!("Failed to write CFD backup to {}", file.display()))?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, order_id: OrderId) -> Result<()> {
+        let file = self.file(order_id);
+        if file.exists() {
+            tokio::fs::remove_file(file).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn load_all(&self) -> Result<Vec<Vec<u8>>> {
+        let mut backups = Vec::new();
+
+        let mut entries = tokio::fs::read_dir(&self.path)
+            .await
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(FILE_EXTENSION) {
+                continue;
+            }
+
+            backups.push(tokio::fs::read(path).await?);
+        }
+
+        Ok(backups)
+    }
+}
+
+/// Keeps CFD backups in memory, for tests.
+#[derive(Default, Clone)]
+pub struct InMemory {
+    backups: Arc<Mutex<HashMap<OrderId, Vec<u8>>>>,
+}
+
+#[async_trait]
+impl Sink for InMemory {
+    async fn store(&self, order_id: OrderId, backup: Vec<u8>) -> Result<()> {
+        self.backups
+            .lock()
+            .expect("lock not to be poisoned")
+            .insert(order_id, backup);
+
+        Ok(())
+    }
+
+    async fn remove(&self, order_id: OrderId) -> Result<()> {
+        self.backups
+            .lock()
+            .expect("lock not to be poisoned")
+            .remove(&order_id);
+
+        Ok(())
+    }
+
+    async fn load_all(&self) -> Result<Vec<Vec<u8>>> {
+        let backups = self.backups.lock().expect("lock not to be poisoned");
+
+        Ok(backups.values().cloned().collect())
+    }
+}
+
+/// The data of an open CFD needed to recreate it with its latest DLC.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CfdBackup {
+    pub timestamp: Timestamp,
+    pub id: OrderId,
+    pub offer_id: OfferId,
+    pub position: Position,
+    pub initial_price: Price,
+    pub taker_leverage: Leverage,
+    pub settlement_interval_secs: i64,
+    pub quantity: Contracts,
+    pub counterparty_network_identity: Identity,
+    pub counterparty_peer_id: Option<PeerId>,
+    pub role: Role,
+    pub opening_fee: OpeningFee,
+    pub initial_funding_rate: FundingRate,
+    pub initial_tx_fee_rate: TxFeeRate,
+    pub contract_symbol: ContractSymbol,
+    pub oracle: OracleConfig,
+    pub dlc: Dlc,
+}
+
+impl CfdBackup {
+    /// The backup of a CFD, if it has a DLC.
+    pub fn new(cfd: &Cfd) -> Option<Self> {
+        let dlc = cfd.dlc()?.clone();
+
+        Some(Self {
+            timestamp: Timestamp::now(),
+            id: cfd.id(),
+            offer_id: cfd.offer_id(),
+            position: cfd.position(),
+            initial_price: cfd.initial_price(),
+            taker_leverage: cfd.taker_leverage(),
+            settlement_interval_secs: cfd.settlement_time_interval_hours().whole_seconds(),
+            quantity: cfd.quantity(),
+            counterparty_network_identity: cfd.counterparty_network_identity(),
+            counterparty_peer_id: cfd.counterparty_peer_id(),
+            role: cfd.role(),
+            opening_fee: cfd.opening_fee(),
+            initial_funding_rate: cfd.initial_funding_rate(),
+            initial_tx_fee_rate: cfd.initial_tx_fee_rate(),
+            contract_symbol: cfd.contract_symbol(),
+            oracle: cfd.oracle().clone(),
+            dlc,
+        })
+    }
+
+    pub fn encrypt(&self, key: &BackupKey) -> Result<Vec<u8>> {
+        let plaintext = serde_json::to_vec(self)?;
+        let nonce = rand::thread_rng().gen::<[u8; NONCE_LEN]>();
+
+        let ciphertext = key
+            .cipher()
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt CFD backup"))?;
+
+        let mut backup = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        backup.push(FORMAT_VERSION);
+        backup.extend_from_slice(&nonce);
+        backup.extend_from_slice(&ciphertext);
+
+        Ok(backup)
+    }
+
+    pub fn decrypt(backup: &[u8], key: &BackupKey) -> Result<Self> {
+        ensure!(backup.len() > 1 + NONCE_LEN, "CFD backup too short");
+
+        let (version, backup) = backup.split_first().expect("backup length");
+        ensure!(
+            *version == FORMAT_VERSION,
+            "Unsupported CFD backup format version {version}"
+        );
+        let (nonce, ciphertext) = backup.split_at(NONCE_LEN);
+
+        let plaintext = key
+            .cipher()
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt CFD backup, was it made with another seed?"))?;
+
+        serde_json::from_slice(&plaintext).context("Malformed CFD backup")
+    }
+
+    fn into_cfd_and_event(self) -> (Cfd, CfdEvent) {
+        let cfd = Cfd::new(
+            self.id,
+            self.offer_id,
+            self.position,
+            self.initial_price,
+            self.taker_leverage,
+            Duration::seconds(self.settlement_interval_secs),
+            self.role,
+            self.quantity,
+            self.counterparty_network_identity,
+            self.counterparty_peer_id,
+            self.opening_fee,
+            self.initial_funding_rate,
+            self.initial_tx_fee_rate,
+            self.contract_symbol,
+            self.oracle,
+        );
+
+        let event = CfdEvent {
+            timestamp: self.timestamp,
+            id: self.id,
+            event: EventKind::ContractSetupCompleted {
+                dlc: Some(self.dlc),
+            },
+        };
+
+        (cfd, event)
+    }
+}
+
+/// The outcome of recovering CFDs from their backups.
+#[derive(Debug, Default)]
+pub struct Recovery {
+    /// The IDs of the recovered CFDs.
+    pub recovered: Vec<OrderId>,
+    /// The number of backups which could not be decrypted or are outdated.
+    pub skipped: usize,
+}
+
+/// Recreate the CFDs of all backups in `sink` that are not in the database.
+///
+/// Recovered CFDs have their latest DLC, but not their history. Their fees are only accurate if
+/// they were never rolled over, so they should be force-closed rather than settled
+/// collaboratively. Backups which cannot be decrypted or whose DLC is outdated according to
+/// `chain` are skipped and reported, see [`ensure_up_to_date`].
+pub async fn recover(
+    db: &sqlite_db::Connection,
+    sink: &dyn Sink,
+    key: &BackupKey,
+    chain: Arc<dyn ChainBackend>,
+) -> Result<Recovery> {
+    let open_cfd_ids = db.load_open_cfd_ids().await?;
+    let closed_cfd_ids = db.load_closed_cfd_ids().await?;
+
+    let mut recovery = Recovery::default();
+    for (index, backup) in sink.load_all().await?.into_iter().enumerate() {
+        let backup = match CfdBackup::decrypt(&backup, key) {
+            Ok(backup) => backup,
+            Err(e) => {
+                tracing::error!("Skipping CFD backup #{index}: {e:#}");
+                recovery.skipped += 1;
+                continue;
+            }
+        };
+        let order_id = backup.id;
+
+        if open_cfd_ids.contains(&order_id) || closed_cfd_ids.contains(&order_id) {
+            tracing::debug!(%order_id, "Skipping recovery of CFD already in the database");
+            continue;
+        }
+
+        let is_up_to_date = tokio::task::spawn_blocking({
+            let chain = chain.clone();
+            let dlc = backup.dlc.clone();
+            move || ensure_up_to_date(chain.as_ref(), &dlc)
+        })
+        .await?;
+        if let Err(e) = is_up_to_date {
+            tracing::error!(%order_id, "Skipping outdated CFD backup: {e:#}");
+            recovery.skipped += 1;
+            continue;
+        }
+
+        let (cfd, event) = backup.into_cfd_and_event();
+        db.insert_cfd(&cfd).await?;
+        db.append_event(event).await?;
+
+        tracing::info!(%order_id, "Recovered CFD from backup");
+        recovery.recovered.push(order_id);
+    }
+
+    Ok(recovery)
+}
+
+/// Make sure that the DLC of a backup is still the latest one on chain.
+///
+/// The backup is outdated if one of its revoked commit transactions was published, or if the lock
+/// or commit transaction was spent by a transaction the backup does not know of, e.g. the commit
+/// transaction of a later rollover. Force-closing with an outdated DLC would allow the
+/// counterparty to punish us. For a partially settled CFD the lock transaction is the latest
+/// partial settlement transaction, so only spends of its output are relevant.
+///
+/// Backends without an index over scripts only know about the transactions of the DLC, so only
+/// published revoked commit transactions are detected with them.
+fn ensure_up_to_date(chain: &dyn ChainBackend, dlc: &Dlc) -> Result<()> {
+    for revoked_commit in &dlc.revoked_commit {
+        if chain.transaction(&revoked_commit.txid)?.is_some() {
+            bail!(
+                "Revoked commit transaction {} was published",
+                revoked_commit.txid
+            );
+        }
+    }
+
+    let commit_txid = dlc.commit.0.txid();
+
+    let lock_script = dlc.lock.1.script_pubkey();
+    if let Some(spend) = find_spend(chain, &dlc.lock.0, &lock_script, &[commit_txid])? {
+        bail!("Lock transaction was spent by unknown transaction {spend}");
+    }
+
+    let commit_script = dlc.commit.2.script_pubkey();
+    if let Some(spend) = find_spend(chain, &dlc.commit.0, &commit_script, &[])? {
+        bail!("Commit transaction was already spent by {spend}");
+    }
+
+    Ok(())
+}
+
+/// Find a transaction spending the output of `tx` paying to `script`, other than `known_spends`.
+///
+/// Only spends of that exact output count. Other transactions in the history of `script`, e.g.
+/// the previous lock transaction of a partially settled CFD, are ignored.
+fn find_spend(
+    chain: &dyn ChainBackend,
+    tx: &Transaction,
+    script: &Script,
+    known_spends: &[Txid],
+) -> Result<Option<Txid>> {
+    let txid = tx.txid();
+    let vout = tx
+        .output
+        .iter()
+        .position(|out| &out.script_pubkey == script)
+        .with_context(|| format!("Transaction {txid} does not pay to {script}"))?;
+    let outpoint = OutPoint::new(txid, vout as u32);
+
+    let mut known_txids = vec![txid];
+    known_txids.extend_from_slice(known_spends);
+
+    for status in chain.script_history(script, &known_txids)? {
+        if known_txids.contains(&status.tx_hash) {
+            continue;
+        }
+
+        let candidate = chain
+            .transaction(&status.tx_hash)?
+            .with_context(|| format!("Transaction {} not found", status.tx_hash))?;
+        if candidate
+            .input
+            .iter()
+            .any(|input| input.previous_output == outpoint)
+        {
+            return Ok(Some(status.tx_hash));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Back up a CFD after its DLC changed.
+#[derive(Clone, Copy)]
+pub struct BackupCfd {
+    pub order_id: OrderId,
+}
+
+/// Remove the backup of a CFD once it is closed.
+#[derive(Clone, Copy)]
+pub struct RemoveBackup {
+    pub order_id: OrderId,
+}
+
+pub struct Actor {
+    db: sqlite_db::Connection,
+    sink: Box<dyn Sink>,
+    key: BackupKey,
+}
+
+impl Actor {
+    pub fn new(db: sqlite_db::Connection, sink: Box<dyn Sink>, key: BackupKey) -> Self {
+        Self { db, sink, key }
+    }
+
+    async fn backup(&self, order_id: OrderId) -> Result<()> {
+        let cfd = self.db.load_open_cfd::<Cfd>(order_id, ()).await?;
+        let backup = CfdBackup::new(&cfd).context("CFD without DLC")?;
+
+        self.sink.store(order_id, backup.encrypt(&self.key)?).await
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: BackupCfd) -> Result<()> {
+        let order_id = msg.order_id;
+
+        self.backup(order_id)
+            .await
+            .with_context(|| format!("Failed to back up CFD {order_id}"))?;
+        tracing::debug!(%order_id, "Backed up CFD");
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, msg: RemoveBackup) {
+        let order_id = msg.order_id;
+
+        if let Err(e) = self.sink.remove(order_id).await {
+            tracing::warn!(%order_id, "Failed to remove CFD backup: {e:#}");
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bdk::bitcoin::BlockHash;
+    use bdk::bitcoin::TxIn;
+    use bdk::bitcoin::TxOut;
+    use btsieve::BlockHeight;
+    use btsieve::Broadcast;
+    use btsieve::TxStatus;
+    use std::env;
+
+    /// A blockchain which only contains `transactions`.
+    #[derive(Default)]
+    struct Chain {
+        transactions: Vec<Transaction>,
+    }
+
+    impl ChainBackend for Chain {
+        fn tip_height(&self) -> Result<BlockHeight> {
+            unreachable!("recovery does not need the tip")
+        }
+
+        fn genesis_hash(&self) -> Result<BlockHash> {
+            unreachable!("recovery does not need the genesis hash")
+        }
+
+        fn script_history(&self, script: &Script, _: &[Txid]) -> Result<Vec<TxStatus>> {
+            let funding_txids = self
+                .transactions
+                .iter()
+                .filter(|tx| tx.output.iter().any(|out| &out.script_pubkey == script))
+                .map(|tx| tx.txid())
+                .collect::<Vec<_>>();
+
+            Ok(self
+                .transactions
+                .iter()
+                .filter(|tx| {
+                    funding_txids.contains(&tx.txid())
+                        || tx
+                            .input
+                            .iter()
+                            .any(|input| funding_txids.contains(&input.previous_output.txid))
+                })
+                .map(|tx| TxStatus {
+                    height: 1,
+                    tx_hash: tx.txid(),
+                })
+                .collect())
+        }
+
+        fn transaction(&self, txid: &Txid) -> Result<Option<Transaction>> {
+            Ok(self
+                .transactions
+                .iter()
+                .find(|tx| &tx.txid() == txid)
+                .cloned())
+        }
+
+        fn broadcast(&self, _: &Transaction) -> Result<Broadcast> {
+            unreachable!("recovery does not broadcast")
+        }
+
+        fn estimate_fee_rate(&self, _: u16) -> Result<Option<f32>> {
+            unreachable!("recovery does not estimate fees")
+        }
+    }
+
+    fn backup() -> CfdBackup {
+        let event = serde_json::from_str::<EventKind>(include_str!(
+            "../../sqlite-db/src/test_events/contract_setup_completed.json"
+        ))
+        .unwrap();
+        let dlc = match event {
+            EventKind::ContractSetupCompleted { dlc: Some(dlc) } => dlc,
+            _ => unreachable!("test event to contain a DLC"),
+        };
+
+        CfdBackup {
+            timestamp: Timestamp::new(1),
+            id: OrderId::default(),
+            offer_id: OfferId::default(),
+            position: Position::Long,
+            initial_price: Price::new(rust_decimal_macros::dec!(20_000)).unwrap(),
+            taker_leverage: Leverage::TWO,
+            settlement_interval_secs: 24 * 60 * 60,
+            quantity: Contracts::new(100),
+            counterparty_network_identity: Identity::new(x25519_dalek::PublicKey::from(
+                *b"hello world, oh what a beautiful",
+            )),
+            counterparty_peer_id: Some(PeerId::random()),
+            role: Role::Taker,
+            opening_fee: OpeningFee::default(),
+            initial_funding_rate: FundingRate::default(),
+            initial_tx_fee_rate: TxFeeRate::default(),
+            contract_symbol: ContractSymbol::BTC_USD,
+            oracle: OracleConfig::olivia(),
+            dlc,
+        }
+    }
+
+    #[test]
+    fn backup_roundtrips_only_with_same_key() {
+        let backup = backup();
+        let encrypted = backup.encrypt(&BackupKey::new([1; 32])).unwrap();
+
+        let decrypted = CfdBackup::decrypt(&encrypted, &BackupKey::new([1; 32])).unwrap();
+
+        assert_eq!(decrypted, backup);
+        assert!(CfdBackup::decrypt(&encrypted, &BackupKey::new([2; 32])).is_err());
+    }
+
+    #[tokio::test]
+    async fn local_directory_keeps_latest_backup_of_each_cfd() {
+        let path = env::temp_dir().join(format!("cfd-backups-{}", OrderId::default()));
+        let sink = LocalDirectory::new(path.clone());
+        let (first, second) = (OrderId::default(), OrderId::default());
+
+        sink.store(first, vec![1]).await.unwrap();
+        sink.store(first, vec![2]).await.unwrap();
+        sink.store(second, vec![3]).await.unwrap();
+        sink.remove(second).await.unwrap();
+
+        assert_eq!(sink.load_all().await.unwrap(), vec![vec![2]]);
+
+        tokio::fs::remove_dir_all(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn recovers_open_cfd_with_dlc_from_backup() {
+        let key = BackupKey::new([1; 32]);
+        let sink = InMemory::default();
+        let backup = backup();
+        sink.store(backup.id, backup.encrypt(&key).unwrap())
+            .await
+            .unwrap();
+        let chain = Arc::new(Chain {
+            transactions: vec![backup.dlc.lock.0.clone()],
+        });
+
+        let db = sqlite_db::memory().await.unwrap();
+        let recovery = recover(&db, &sink, &key, chain.clone()).await.unwrap();
+
+        assert_eq!(recovery.recovered, vec![backup.id]);
+        let cfd = db.load_open_cfd::<Cfd>(backup.id, ()).await.unwrap();
+        assert_eq!(cfd.dlc(), Some(&backup.dlc));
+
+        let recovery_again = recover(&db, &sink, &key, chain).await.unwrap();
+        assert!(recovery_again.recovered.is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_backups_which_cannot_be_decrypted() {
+        let key = BackupKey::new([1; 32]);
+        let sink = InMemory::default();
+        let backup = backup();
+        sink.store(backup.id, backup.encrypt(&key).unwrap())
+            .await
+            .unwrap();
+        sink.store(
+            OrderId::default(),
+            backup.encrypt(&BackupKey::new([2; 32])).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let db = sqlite_db::memory().await.unwrap();
+        let recovery = recover(&db, &sink, &key, Arc::new(Chain::default()))
+            .await
+            .unwrap();
+
+        assert_eq!(recovery.recovered, vec![backup.id]);
+        assert_eq!(recovery.skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn skips_backup_if_lock_was_spent_by_unknown_transaction() {
+        let key = BackupKey::new([1; 32]);
+        let sink = InMemory::default();
+        let backup = backup();
+        sink.store(backup.id, backup.encrypt(&key).unwrap())
+            .await
+            .unwrap();
+        let lock_tx = backup.dlc.lock.0.clone();
+        let newer_commit_tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(lock_tx.txid(), 0),
+                ..TxIn::default()
+            }],
+            output: vec![],
+        };
+        let chain = Arc::new(Chain {
+            transactions: vec![lock_tx, newer_commit_tx],
+        });
+
+        let db = sqlite_db::memory().await.unwrap();
+        let recovery = recover(&db, &sink, &key, chain).await.unwrap();
+
+        assert!(recovery.recovered.is_empty());
+        assert_eq!(recovery.skipped, 1);
+        assert!(db.load_open_cfd_ids().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recovers_partially_settled_cfd() {
+        let key = BackupKey::new([1; 32]);
+        let sink = InMemory::default();
+        let mut backup = backup();
+
+        let initial_lock_tx = backup.dlc.lock.0.clone();
+        let lock_script = backup.dlc.lock.1.script_pubkey();
+        let partial_settlement_tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(initial_lock_tx.txid(), 0),
+                ..TxIn::default()
+            }],
+            output: vec![
+                TxOut {
+                    value: 10_000,
+                    script_pubkey: Script::new(),
+                },
+                TxOut {
+                    value: 300_000,
+                    script_pubkey: lock_script,
+                },
+            ],
+        };
+        backup.dlc.lock.0 = partial_settlement_tx.clone();
+        backup.dlc.commit.0.input[0].previous_output =
+            OutPoint::new(partial_settlement_tx.txid(), 1);
+        backup.quantity = Contracts::new(70);
+
+        sink.store(backup.id, backup.encrypt(&key).unwrap())
+            .await
+            .unwrap();
+        let chain = Arc::new(Chain {
+            transactions: vec![initial_lock_tx, partial_settlement_tx],
+        });
+
+        let db = sqlite_db::memory().await.unwrap();
+        let recovery = recover(&db, &sink, &key, chain).await.unwrap();
+
+        assert_eq!(recovery.recovered, vec![backup.id]);
+        assert_eq!(recovery.skipped, 0);
+        let cfd = db.load_open_cfd::<Cfd>(backup.id, ()).await.unwrap();
+        assert_eq!(cfd.dlc(), Some(&backup.dlc));
+    }
+}
@@ -0,0 +1,590 @@
+//! Access to the blockchain through an Electrum server or a bitcoind node.
+//!
+//! The wallet syncs through bdk's [`AnyBlockchain`], everything else goes through the
+//! [`ChainBackend`] of the configured backend.
+
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bdk::bitcoin::blockdata::constants;
+use bdk::bitcoin::hashes::Hash;
+use bdk::bitcoin::BlockHash;
+use bdk::bitcoin::Network;
+use bdk::bitcoin::Script;
+use bdk::bitcoin::Transaction;
+use bdk::bitcoin::Txid;
+use bdk::bitcoincore_rpc;
+use bdk::bitcoincore_rpc::RpcApi;
+use bdk::blockchain::rpc::RpcBlockchain;
+use bdk::blockchain::rpc::RpcConfig;
+use bdk::blockchain::AnyBlockchain;
+use bdk::blockchain::ConfigurableBlockchain;
+use bdk::blockchain::ElectrumBlockchain;
+use bdk::electrum_client;
+use bdk::electrum_client::ElectrumApi;
+use btsieve::BlockHeight;
+use btsieve::Broadcast;
+use btsieve::ChainBackend;
+use btsieve::TxStatus;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Electrum client timeout in seconds
+///
+/// This timeout is used when establishing the connection and for all requests of the electrum
+/// client. We explicitly set the timeout because otherwise the underlying TCP connection timeout is
+/// used which is hard to be predicted.
+pub const ELECTRUM_CLIENT_TIMEOUT_SECS: u8 = 120;
+
+/// How to access the blockchain.
+#[derive(Clone)]
+pub enum Config {
+    Electrum {
+        url: String,
+    },
+    /// A bitcoind node, which has to run with `-txindex` to look up transactions of CFDs.
+    ///
+    /// [`Config::backend`] refuses to start if the index is missing or not synced.
+    Bitcoind {
+        url: String,
+        auth: BitcoindAuth,
+    },
+}
+
+#[derive(Clone)]
+pub enum BitcoindAuth {
+    CookieFile(PathBuf),
+    UserPass { user: String, password: String },
+}
+
+impl Config {
+    /// Connect to the backend, making sure it is on `network`.
+    pub fn backend(&self, network: Network) -> Result<Arc<dyn ChainBackend>> {
+        let backend: Arc<dyn ChainBackend> = match self {
+            Config::Electrum { url } => Arc::new(Electrum::new(url)?),
+            Config::Bitcoind { url, auth } => {
+                let bitcoind = Bitcoind::new(url, auth)?;
+                bitcoind.ensure_txindex()?;
+
+                Arc::new(bitcoind)
+            }
+        };
+
+        let genesis_hash = backend.genesis_hash()?;
+        ensure!(
+            genesis_hash == constants::genesis_block(network).block_hash(),
+            "Blockchain backend is not on {network}"
+        );
+
+        Ok(backend)
+    }
+
+    /// The blockchain to sync the wallet with.
+    ///
+    /// `wallet_name` is the name of the watch-only wallet the scripts of our wallet are imported
+    /// into if the backend is bitcoind.
+    pub fn wallet_blockchain(
+        &self,
+        network: Network,
+        wallet_name: String,
+    ) -> Result<AnyBlockchain> {
+        let blockchain = match self {
+            Config::Electrum { url } => {
+                let client = electrum_client::Client::new(url)
+                    .context("Failed to initialize Electrum RPC client")?;
+
+                ElectrumBlockchain::from(client).into()
+            }
+            Config::Bitcoind { url, auth } => RpcBlockchain::from_config(&RpcConfig {
+                url: url.clone(),
+                auth: auth.clone().into(),
+                network,
+                wallet_name,
+                sync_params: None,
+            })
+            .context("Failed to initialize bitcoind RPC client")?
+            .into(),
+        };
+
+        Ok(blockchain)
+    }
+}
+
+impl From<BitcoindAuth> for bdk::blockchain::rpc::Auth {
+    fn from(auth: BitcoindAuth) -> Self {
+        match auth {
+            BitcoindAuth::CookieFile(file) => Self::Cookie { file },
+            BitcoindAuth::UserPass { user, password } => Self::UserPass {
+                username: user,
+                password,
+            },
+        }
+    }
+}
+
+impl From<&BitcoindAuth> for bitcoincore_rpc::Auth {
+    fn from(auth: &BitcoindAuth) -> Self {
+        match auth {
+            BitcoindAuth::CookieFile(file) => Self::CookieFile(file.clone()),
+            BitcoindAuth::UserPass { user, password } => {
+                Self::UserPass(user.clone(), password.clone())
+            }
+        }
+    }
+}
+
+/// Bitcoin error codes: <https://github.com/bitcoin/bitcoin/blob/97d3500601c1d28642347d014a6de1e38f53ae4e/src/rpc/protocol.h#L23>
+#[derive(Clone, Copy)]
+pub enum RpcErrorCode {
+    /// Invalid address or key, also returned for unknown transactions. Error code -5.
+    RpcInvalidAddressOrKey,
+    /// General error during transaction or block submission Error code -25.
+    RpcVerifyError,
+    /// Transaction already in chain. Error code -27.
+    RpcVerifyAlreadyInChain,
+}
+
+impl From<RpcErrorCode> for i64 {
+    fn from(code: RpcErrorCode) -> Self {
+        match code {
+            RpcErrorCode::RpcInvalidAddressOrKey => -5,
+            RpcErrorCode::RpcVerifyError => -25,
+            RpcErrorCode::RpcVerifyAlreadyInChain => -27,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+pub struct Electrum {
+    client: electrum_client::Client,
+}
+
+impl Electrum {
+    pub fn new(url: &str) -> Result<Self> {
+        let client = electrum_client::Client::from_config(
+            url,
+            electrum_client::ConfigBuilder::new()
+                .timeout(Some(ELECTRUM_CLIENT_TIMEOUT_SECS))?
+                .build(),
+        )
+        .context("Failed to initialize Electrum RPC client")?;
+
+        Ok(Self { client })
+    }
+}
+
+impl ChainBackend for Electrum {
+    fn tip_height(&self) -> Result<BlockHeight> {
+        // We do not act on this subscription after this call, as we cannot rely on
+        // subscription push notifications because eventually the Electrum server will
+        // close the connection and subscriptions are not automatically renewed
+        // upon renewing the connection.
+        let height = self
+            .client
+            .block_headers_subscribe()
+            .context("Failed to subscribe to header notifications")?
+            .height;
+
+        Ok(height.into())
+    }
+
+    fn genesis_hash(&self) -> Result<BlockHash> {
+        let mut hash = self.client.server_features()?.genesis_hash;
+        hash.reverse(); // Sha256d hashes are displayed backwards
+
+        BlockHash::from_slice(&hash).context("Invalid genesis block hash returned by electrum RPC")
+    }
+
+    fn script_history(&self, script: &Script, _: &[Txid]) -> Result<Vec<TxStatus>> {
+        let history = self
+            .client
+            .script_get_history(script)?
+            .into_iter()
+            .map(|response| TxStatus {
+                height: response.height,
+                tx_hash: response.tx_hash,
+            })
+            .collect();
+
+        Ok(history)
+    }
+
+    fn transaction(&self, txid: &Txid) -> Result<Option<Transaction>> {
+        match self.client.transaction_get(txid) {
+            Ok(tx) => Ok(Some(tx)),
+            // Electrum servers respond with a protocol error for unknown transactions
+            Err(electrum_client::Error::Protocol(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> Result<Broadcast> {
+        let error = match self.client.transaction_broadcast(tx) {
+            Ok(_) => return Ok(Broadcast::Published),
+            Err(e) => e,
+        };
+
+        if let electrum_client::Error::Protocol(ref value) = error {
+            let rpc_error = parse_rpc_protocol_error(value)
+                .with_context(|| format!("Failed to parse electrum error response '{value:?}'"))?;
+
+            if rpc_error.code == i64::from(RpcErrorCode::RpcVerifyAlreadyInChain) {
+                return Ok(Broadcast::AlreadyInChain);
+            }
+
+            // We do this check because electrum sometimes returns an RpcVerifyError when it should
+            // be returning a RpcVerifyAlreadyInChain error,
+            if rpc_error.code == i64::from(RpcErrorCode::RpcVerifyError)
+                && rpc_error.message == "bad-txns-inputs-missingorspent"
+                && self.client.transaction_get(&tx.txid()).is_ok()
+            {
+                return Ok(Broadcast::AlreadyInChain);
+            }
+        }
+
+        Err(error.into())
+    }
+
+    fn estimate_fee_rate(&self, target_blocks: u16) -> Result<Option<f32>> {
+        let btc_per_kvb = self.client.estimate_fee(usize::from(target_blocks))?;
+
+        // Electrum servers respond with -1 if they cannot estimate the fee
+        if btc_per_kvb <= 0.0 {
+            return Ok(None);
+        }
+
+        Ok(Some((btc_per_kvb * 100_000.0) as f32))
+    }
+}
+
+fn parse_rpc_protocol_error(error_value: &Value) -> Result<RpcError> {
+    let json = error_value
+        .as_str()
+        .context("Not a string")?
+        .split_terminator("RPC error: ")
+        .nth(1)
+        .context("Unknown error code format")?;
+
+    let error = serde_json::from_str::<RpcError>(json).context("Error has unexpected format")?;
+
+    Ok(error)
+}
+
+pub struct Bitcoind {
+    client: bitcoincore_rpc::Client,
+}
+
+impl Bitcoind {
+    pub fn new(url: &str, auth: &BitcoindAuth) -> Result<Self> {
+        let client = bitcoincore_rpc::Client::new(url, auth.into())
+            .context("Failed to initialize bitcoind RPC client")?;
+
+        Ok(Self { client })
+    }
+
+    /// Make sure bitcoind indexes all transactions.
+    ///
+    /// Without the index bitcoind responds with [`RpcErrorCode::RpcInvalidAddressOrKey`] for
+    /// confirmed transactions that are not in its wallet, which we could not tell apart from
+    /// transactions that are not in the chain.
+    fn ensure_txindex(&self) -> Result<()> {
+        let indices = self
+            .client
+            .call::<HashMap<String, IndexInfo>>("getindexinfo", &[])
+            .context("Failed to get index info from bitcoind")?;
+
+        let txindex = indices
+            .get("txindex")
+            .context("bitcoind has to run with -txindex")?;
+        ensure!(
+            txindex.synced,
+            "The txindex of bitcoind is not synced yet, it is at block {}",
+            txindex.best_block_height
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct IndexInfo {
+    synced: bool,
+    best_block_height: u64,
+}
+
+impl ChainBackend for Bitcoind {
+    fn tip_height(&self) -> Result<BlockHeight> {
+        let height = self.client.get_block_count()?;
+
+        Ok(usize::try_from(height)?.into())
+    }
+
+    fn genesis_hash(&self) -> Result<BlockHash> {
+        Ok(self.client.get_block_hash(0)?)
+    }
+
+    /// bitcoind has no index over scripts, so we only look up the transactions we are waiting
+    /// for.
+    fn script_history(&self, _: &Script, txids: &[Txid]) -> Result<Vec<TxStatus>> {
+        let mut history = Vec::new();
+
+        for txid in txids {
+            let info = match self.client.get_raw_transaction_info(txid, None) {
+                Ok(info) => info,
+                Err(e) if is_rpc_error(&e, RpcErrorCode::RpcInvalidAddressOrKey) => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let height = match info.blockhash {
+                Some(blockhash) => {
+                    let header = self.client.get_block_header_info(&blockhash)?;
+
+                    // Transactions in blocks which are not part of the best chain anymore are
+                    // back in the mempool
+                    if header.confirmations > 0 {
+                        i32::try_from(header.height)?
+                    } else {
+                        0
+                    }
+                }
+                None => 0,
+            };
+
+            history.push(TxStatus {
+                height,
+                tx_hash: *txid,
+            });
+        }
+
+        Ok(history)
+    }
+
+    fn transaction(&self, txid: &Txid) -> Result<Option<Transaction>> {
+        match self.client.get_raw_transaction(txid, None) {
+            Ok(tx) => Ok(Some(tx)),
+            Err(e) if is_rpc_error(&e, RpcErrorCode::RpcInvalidAddressOrKey) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> Result<Broadcast> {
+        let error = match self.client.send_raw_transaction(tx) {
+            Ok(_) => return Ok(Broadcast::Published),
+            Err(e) => e,
+        };
+
+        if is_rpc_error(&error, RpcErrorCode::RpcVerifyAlreadyInChain) {
+            return Ok(Broadcast::AlreadyInChain);
+        }
+
+        // bitcoind does not report transactions whose outputs are already spent as being in the
+        // chain
+        if is_rpc_error(&error, RpcErrorCode::RpcVerifyError)
+            && self.transaction(&tx.txid())?.is_some()
+        {
+            return Ok(Broadcast::AlreadyInChain);
+        }
+
+        Err(error.into())
+    }
+
+    fn estimate_fee_rate(&self, target_blocks: u16) -> Result<Option<f32>> {
+        let estimate = self.client.estimate_smart_fee(target_blocks, None)?;
+
+        Ok(estimate
+            .fee_rate
+            .map(|btc_per_kvb| btc_per_kvb.as_sat() as f32 / 1000.0))
+    }
+}
+
+fn is_rpc_error(error: &bitcoincore_rpc::Error, code: RpcErrorCode) -> bool {
+    matches!(
+        error,
+        bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::Error::Rpc(rpc_error))
+            if i64::from(rpc_error.code) == i64::from(code)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::net::TcpStream;
+
+    #[test]
+    fn parse_already_in_chain_error_of_electrum() {
+        let value = Value::String(
+            "sendrawtransaction RPC error: {\"code\":-27,\"message\":\"Transaction already in block chain\"}"
+                .to_string(),
+        );
+
+        let error = parse_rpc_protocol_error(&value).unwrap();
+
+        assert_eq!(error.code, i64::from(RpcErrorCode::RpcVerifyAlreadyInChain));
+        assert_eq!(error.message, "Transaction already in block chain");
+    }
+
+    #[test]
+    fn bitcoind_backend_requires_txindex() {
+        let url = mock_bitcoind(|method| match method {
+            "getindexinfo" => Ok(serde_json::json!({})),
+            method => panic!("Unexpected call to {method}"),
+        });
+
+        let result = bitcoind_config(url).backend(Network::Regtest);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn bitcoind_backend_requires_synced_txindex() {
+        let url = mock_bitcoind(|method| match method {
+            "getindexinfo" => Ok(serde_json::json!({
+                "txindex": { "synced": false, "best_block_height": 100 }
+            })),
+            method => panic!("Unexpected call to {method}"),
+        });
+
+        let result = bitcoind_config(url).backend(Network::Regtest);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn bitcoind_backend_with_synced_txindex() {
+        let url = mock_bitcoind(|method| match method {
+            "getindexinfo" => Ok(serde_json::json!({
+                "txindex": { "synced": true, "best_block_height": 100 }
+            })),
+            "getblockhash" => Ok(Value::String(
+                constants::genesis_block(Network::Regtest)
+                    .block_hash()
+                    .to_string(),
+            )),
+            "getrawtransaction" => Err(serde_json::json!({
+                "code": -5,
+                "message": "No such mempool or blockchain transaction"
+            })),
+            method => panic!("Unexpected call to {method}"),
+        });
+
+        let backend = bitcoind_config(url).backend(Network::Regtest).unwrap();
+
+        assert!(backend.transaction(&Txid::default()).unwrap().is_none());
+        assert!(backend
+            .script_history(&Script::new(), &[Txid::default()])
+            .unwrap()
+            .is_empty());
+    }
+
+    fn bitcoind_config(url: String) -> Config {
+        Config::Bitcoind {
+            url,
+            auth: BitcoindAuth::UserPass {
+                user: "user".to_string(),
+                password: "password".to_string(),
+            },
+        }
+    }
+
+    /// Serve bitcoind's JSON-RPC interface, answering each call with what `respond` returns for
+    /// its method.
+    fn mock_bitcoind(respond: fn(&str) -> Result<Value, Value>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                std::thread::spawn(move || serve_json_rpc(stream, respond));
+            }
+        });
+
+        url
+    }
+
+    fn serve_json_rpc(stream: TcpStream, respond: fn(&str) -> Result<Value, Value>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+
+        loop {
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return; // The client closed the connection
+                }
+
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let request = serde_json::from_slice::<Value>(&body).unwrap();
+
+            let (result, error) = match respond(request["method"].as_str().unwrap()) {
+                Ok(result) => (result, Value::Null),
+                Err(error) => (Value::Null, error),
+            };
+            let response = serde_json::json!({
+                "result": result,
+                "error": error,
+                "id": request["id"],
+            })
+            .to_string();
+
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+        }
+    }
+
+    /// Ignored on CI because it requires a regtest bitcoind, e.g. started with
+    /// `bitcoind -regtest -txindex -server`
+    #[test]
+    #[ignore]
+    fn regtest_bitcoind_backend_is_on_regtest() {
+        let url =
+            std::env::var("TEST_BITCOIND_RPC").unwrap_or_else(|_| "http://127.0.0.1:18443".into());
+        let cookie = std::env::var("TEST_BITCOIND_COOKIE").unwrap_or_else(|_| {
+            let home = std::env::var("HOME").unwrap();
+            format!("{home}/.bitcoin/regtest/.cookie")
+        });
+
+        let config = Config::Bitcoind {
+            url,
+            auth: BitcoindAuth::CookieFile(cookie.into()),
+        };
+        let backend = config.backend(Network::Regtest).unwrap();
+
+        backend.tip_height().unwrap();
+        assert!(backend.transaction(&Txid::default()).unwrap().is_none());
+        assert!(backend
+            .script_history(&Script::new(), &[Txid::default()])
+            .unwrap()
+            .is_empty());
+    }
+}
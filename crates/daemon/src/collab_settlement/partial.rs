@@ -0,0 +1,5 @@
+pub mod maker;
+pub mod protocol;
+pub mod taker;
+
+pub const PROTOCOL: &str = "/itchysats/partial-collab-settlement/1.0.0";
@@ -0,0 +1,209 @@
+use crate::collab_settlement::partial::protocol::*;
+use crate::command;
+use crate::oracle;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use asynchronous_codec::Framed;
+use asynchronous_codec::JsonCodec;
+use futures::SinkExt;
+use futures::StreamExt;
+use libp2p_core::PeerId;
+use model::Contracts;
+use model::OrderId;
+use model::PartialSettlement;
+use model::Role;
+use std::collections::HashMap;
+use xtra_libp2p::NewInboundSubstream;
+use xtra_productivity::xtra_productivity;
+
+type ListenerConnection = (Connection, PartialSettlement, Contracts, PeerId);
+
+/// Permanent actor to handle incoming substreams for the
+/// `/itchysats/partial-collab-settlement/1.0.0` protocol.
+///
+/// There is only one instance of this actor for all connections, meaning we must always spawn a
+/// task whenever we interact with a substream to not block the execution of other connections.
+pub struct Actor {
+    pending_protocols: HashMap<OrderId, ListenerConnection>,
+    executor: command::Executor,
+    oracle: oracle::AnnouncementsChannel,
+    n_payouts: usize,
+}
+
+impl Actor {
+    pub fn new(
+        executor: command::Executor,
+        oracle: oracle::AnnouncementsChannel,
+        n_payouts: usize,
+    ) -> Self {
+        Self {
+            pending_protocols: HashMap::default(),
+            executor,
+            oracle,
+            n_payouts,
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: NewInboundSubstream, ctx: &mut xtra::Context<Self>) {
+        let NewInboundSubstream { peer_id, stream } = msg;
+        let address = ctx.address().expect("we are alive");
+
+        tokio_extras::spawn_fallible(
+            &address.clone(),
+            async move {
+                let mut framed = Framed::new(stream, JsonCodec::<Message, Message>::new());
+
+                let propose = framed
+                    .next()
+                    .await
+                    .context("End of stream while receiving Propose")?
+                    .context("Failed to decode Propose")?
+                    .into_propose()?;
+
+                address
+                    .send(ProposeReceived {
+                        propose,
+                        framed,
+                        peer_id,
+                    })
+                    .await?;
+
+                anyhow::Ok(())
+            },
+            move |e| async move {
+                tracing::warn!(%peer_id, "Failed to handle incoming partial settlement: {e:#}")
+            },
+        );
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: ProposeReceived) {
+        let ProposeReceived {
+            propose,
+            framed,
+            peer_id,
+        } = msg;
+        let order_id = propose.id;
+
+        let result = self
+            .executor
+            .execute(order_id, |cfd| {
+                cfd.verify_counterparty_peer_id(&peer_id.into())?;
+                cfd.start_partial_settlement_maker(
+                    propose.price,
+                    propose.quantity,
+                    self.n_payouts,
+                    &propose.unsigned_tx,
+                )
+            })
+            .await
+            .context("Failed to start partial settlement protocol");
+
+        let settlement = match result {
+            Ok(settlement) => settlement,
+            Err(e) => {
+                emit_failed(order_id, e, &self.executor).await;
+                return;
+            }
+        };
+
+        self.pending_protocols
+            .insert(order_id, (framed, settlement, propose.quantity, peer_id));
+    }
+
+    async fn handle(&mut self, msg: Accept, ctx: &mut xtra::Context<Self>) -> Result<()> {
+        let Accept { order_id } = msg;
+
+        let (mut framed, settlement, quantity, _peer) = self
+            .pending_protocols
+            .remove(&order_id)
+            .with_context(|| format!("No active protocol for order {order_id}"))?;
+
+        let this = ctx.address().expect("we are alive");
+        tokio_extras::spawn_fallible(
+            &this,
+            {
+                let executor = self.executor.clone();
+                let oracle = self.oracle.clone();
+                let n_payouts = self.n_payouts;
+                async move {
+                    framed
+                        .send(Message::Decision(Decision::Accept))
+                        .await
+                        .context("Failed to send Decision::Accept")?;
+
+                    let (settlement, dlc) = settle_partially(
+                        &mut framed,
+                        settlement,
+                        Role::Maker,
+                        &oracle,
+                        n_payouts,
+                        &executor,
+                    )
+                    .await?;
+
+                    emit_completed(order_id, settlement, quantity, dlc, &executor).await;
+                    anyhow::Ok(())
+                }
+            },
+            {
+                let executor = self.executor.clone();
+                move |e| async move {
+                    emit_failed(order_id, e, &executor).await;
+                }
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, msg: Reject, ctx: &mut xtra::Context<Self>) -> Result<()> {
+        let Reject { order_id } = msg;
+
+        let (mut framed, ..) = self
+            .pending_protocols
+            .remove(&order_id)
+            .with_context(|| format!("No active protocol for order {order_id}"))?;
+        emit_rejected(order_id, &self.executor).await;
+
+        let this = ctx.address().expect("we are alive");
+        tokio_extras::spawn_fallible(
+            &this,
+            async move { framed.send(Message::Decision(Decision::Reject)).await },
+            move |e| async move {
+                tracing::warn!(%order_id, "Failed to reject partial settlement: {e:#}")
+            },
+        );
+
+        Ok(())
+    }
+}
+
+struct ProposeReceived {
+    propose: Propose,
+    framed: Connection,
+    peer_id: PeerId,
+}
+
+#[derive(Clone, Copy)]
+pub struct Accept {
+    pub order_id: OrderId,
+}
+
+#[derive(Clone, Copy)]
+pub struct Reject {
+    pub order_id: OrderId,
+}
@@ -0,0 +1,360 @@
+use crate::bitcoin::secp256k1::ecdsa::Signature;
+use crate::bitcoin::Transaction;
+use crate::command;
+use crate::oracle;
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use asynchronous_codec::Framed;
+use asynchronous_codec::JsonCodec;
+use bdk_ext::keypair;
+use futures::SinkExt;
+use futures::StreamExt;
+use model::hex_transaction;
+use model::CollaborativeSettlement;
+use model::CompleteFee;
+use model::Contracts;
+use model::Dlc;
+use model::OrderId;
+use model::PartialSettlement;
+use model::Price;
+use model::RevokedCommit;
+use model::Role;
+use rollover::protocol::build_and_verify_cets_and_refund;
+use rollover::protocol::build_commit_descriptor;
+use rollover::protocol::build_own_cfd_transactions;
+use rollover::protocol::GetAnnouncements;
+use rollover::protocol::PunishParams;
+use rollover::protocol::RolloverMsg0;
+use rollover::protocol::RolloverMsg1;
+use rollover::protocol::RolloverMsg2;
+use serde::Deserialize;
+use serde::Serialize;
+use std::time::Duration;
+use tokio_extras::FutureExt;
+use xtra_libp2p::Substream;
+
+/// The duration that the taker waits until a decision (accept/reject) is expected from the maker
+///
+/// If the maker does not respond within `DECISION_TIMEOUT` seconds then the taker will fail the
+/// partial settlement.
+pub(crate) const DECISION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the protocol waits for the next message once the proposal was accepted.
+const PARTIAL_SETTLEMENT_MSG_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub(crate) type Connection = Framed<Substream, JsonCodec<Message, Message>>;
+
+/// Messages of the partial collaborative settlement protocol.
+///
+/// After the taker's `Propose` and the maker's `Decision`, both parties exchange the same
+/// messages in the same order: first the keys and signatures of the DLC that locks the remaining
+/// contracts, then the signatures of the partial settlement transaction and finally the
+/// revocation secrets of the commit transaction of the entire position.
+#[derive(Serialize, Deserialize)]
+pub enum Message {
+    Propose(Propose),
+    Decision(Decision),
+    Msg0(RolloverMsg0),
+    Msg1(Box<RolloverMsg1>),
+    Signature(SettlementSignature),
+    Revoke(RolloverMsg2),
+}
+
+impl Message {
+    pub fn into_propose(self) -> Result<Propose> {
+        match self {
+            Message::Propose(propose) => Ok(propose),
+            _ => bail!("Expected Propose"),
+        }
+    }
+
+    pub fn into_decision(self) -> Result<Decision> {
+        match self {
+            Message::Decision(decision) => Ok(decision),
+            _ => bail!("Expected Decision"),
+        }
+    }
+
+    fn into_msg0(self) -> Result<RolloverMsg0> {
+        match self {
+            Message::Msg0(msg0) => Ok(msg0),
+            _ => bail!("Expected Msg0"),
+        }
+    }
+
+    fn into_msg1(self) -> Result<RolloverMsg1> {
+        match self {
+            Message::Msg1(msg1) => Ok(*msg1),
+            _ => bail!("Expected Msg1"),
+        }
+    }
+
+    fn into_signature(self) -> Result<SettlementSignature> {
+        match self {
+            Message::Signature(signature) => Ok(signature),
+            _ => bail!("Expected Signature"),
+        }
+    }
+
+    fn into_revoke(self) -> Result<RolloverMsg2> {
+        match self {
+            Message::Revoke(msg2) => Ok(msg2),
+            _ => bail!("Expected Revoke"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Propose {
+    pub id: OrderId,
+    pub price: Price,
+    /// The number of contracts to settle.
+    pub quantity: Contracts,
+    /// The transaction that pays out the settled contracts and locks the remaining ones.
+    #[serde(with = "hex_transaction")]
+    pub unsigned_tx: Transaction,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Decision {
+    Accept,
+    Reject,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct SettlementSignature {
+    pub signature: Signature,
+}
+
+/// Set up the DLC of the remaining contracts and sign the partial settlement transaction.
+///
+/// The partial settlement transaction is only signed once both parties hold a valid commit
+/// transaction, CETs and refund transaction spending the new lock output, so neither party can
+/// publish it without the other being able to enforce the remaining position.
+///
+/// Our signature is only sent once the DLC of the remaining contracts is recorded, so that we can
+/// adopt it if the counterparty publishes the partial settlement transaction without completing
+/// the protocol.
+///
+/// Once both parties can publish the partial settlement transaction, they reveal the revocation
+/// secrets of the previous commit transaction, so that it can be punished like any other revoked
+/// commit transaction.
+pub(crate) async fn settle_partially(
+    framed: &mut Connection,
+    settlement: PartialSettlement,
+    role: Role,
+    oracle: &oracle::AnnouncementsChannel,
+    n_payouts: usize,
+    executor: &command::Executor,
+) -> Result<(CollaborativeSettlement, Dlc)> {
+    let PartialSettlement {
+        proposal,
+        transaction,
+        remaining_dlc: dlc,
+        remaining_params,
+        position,
+        contract_symbol,
+        oracle: oracle_config,
+        ..
+    } = settlement;
+    let oracle_pk = oracle_config.public_key;
+
+    let announcements = oracle
+        .get_announcements(oracle_config, dlc.event_ids())
+        .await
+        .context("Failed to get announcements")?;
+
+    let (rev_sk, rev_pk) = keypair::new(&mut rand::thread_rng());
+    let (publish_sk, publish_pk) = keypair::new(&mut rand::thread_rng());
+
+    framed
+        .send(Message::Msg0(RolloverMsg0 {
+            revocation_pk: rev_pk,
+            publish_pk,
+        }))
+        .await
+        .context("Failed to send Msg0")?;
+    let msg0 = next_message(framed, "Msg0").await?.into_msg0()?;
+
+    let punish_params = match role {
+        Role::Maker => PunishParams::new(rev_pk, msg0.revocation_pk, publish_pk, msg0.publish_pk),
+        Role::Taker => PunishParams::new(msg0.revocation_pk, rev_pk, msg0.publish_pk, publish_pk),
+    };
+
+    let own_cfd_txs = build_own_cfd_transactions(
+        &dlc,
+        remaining_params,
+        announcements,
+        oracle_pk,
+        position,
+        n_payouts,
+        remaining_params.complete_fee_before_rollover(),
+        punish_params,
+        role,
+        contract_symbol,
+    )
+    .await?;
+
+    framed
+        .send(Message::Msg1(Box::new(RolloverMsg1::from(
+            own_cfd_txs.clone(),
+        ))))
+        .await
+        .context("Failed to send Msg1")?;
+    let msg1 = next_message(framed, "Msg1").await?.into_msg1()?;
+
+    let commit_desc = build_commit_descriptor(
+        dlc.maker_identity_pk(role),
+        dlc.taker_identity_pk(role),
+        punish_params,
+    );
+    let (cets, refund_tx) = build_and_verify_cets_and_refund(
+        &dlc,
+        oracle_pk,
+        publish_pk,
+        role,
+        &own_cfd_txs,
+        &commit_desc,
+        &msg1,
+    )
+    .await?;
+
+    let signed_dlc = Dlc {
+        revocation: rev_sk,
+        revocation_pk_counterparty: msg0.revocation_pk,
+        publish: publish_sk,
+        publish_pk_counterparty: msg0.publish_pk,
+        commit: (own_cfd_txs.commit.0.clone(), msg1.commit, commit_desc),
+        cets,
+        refund: (refund_tx, msg1.refund),
+        refund_timelock: remaining_params.refund_timelock,
+        ..dlc.clone()
+    };
+
+    executor
+        .execute(proposal.order_id, |cfd| {
+            cfd.sign_partial_settlement(&transaction, proposal.quantity, signed_dlc.clone())
+        })
+        .await
+        .context("Failed to record signed partial settlement")?;
+
+    framed
+        .send(Message::Signature(SettlementSignature {
+            signature: transaction.own_signature(),
+        }))
+        .await
+        .context("Failed to send Signature")?;
+    let SettlementSignature { signature } =
+        next_message(framed, "Signature").await?.into_signature()?;
+
+    let settlement = transaction
+        .recv_counterparty_signature(signature)
+        .context("Failed to receive counterparty signature")?
+        .finalize()
+        .context("Failed to finalize transaction")?;
+
+    // Both parties can publish the partial settlement transaction by now, so we complete it even
+    // if the previous commit transaction cannot be revoked
+    let revoked_commit = match revoke_previous_commit(
+        framed,
+        &dlc,
+        remaining_params.complete_fee_before_rollover(),
+    )
+    .await
+    {
+        Ok(revoked_commit) => revoked_commit,
+        Err(e) => {
+            tracing::warn!(
+                order_id = %proposal.order_id,
+                "Failed to revoke commit transaction of the entire position: {e:#}"
+            );
+            signed_dlc.revoked_commit.clone()
+        }
+    };
+
+    let dlc = Dlc {
+        lock: (settlement.tx.clone(), signed_dlc.lock.1.clone()),
+        revoked_commit,
+        ..signed_dlc
+    };
+
+    Ok((settlement, dlc))
+}
+
+/// Exchange the revocation secrets of the commit transaction spending the previous lock output.
+async fn revoke_previous_commit(
+    framed: &mut Connection,
+    dlc: &Dlc,
+    complete_fee: CompleteFee,
+) -> Result<Vec<RevokedCommit>> {
+    framed
+        .send(Message::Revoke(RolloverMsg2 {
+            revocation_sk: dlc.revocation,
+        }))
+        .await
+        .context("Failed to send Revoke")?;
+    let msg2 = next_message(framed, "Revoke").await?.into_revoke()?;
+
+    dlc.base_dlc_params_from_latest(complete_fee)
+        .revoke_base_commit_tx(msg2.revocation_sk)
+        .context("Counterparty sent invalid revocation sk")
+}
+
+async fn next_message(framed: &mut Connection, name: &str) -> Result<Message> {
+    framed
+        .next()
+        .timeout(PARTIAL_SETTLEMENT_MSG_TIMEOUT, || {
+            tracing::debug_span!("next partial settlement message")
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Expected {name} within {} seconds",
+                PARTIAL_SETTLEMENT_MSG_TIMEOUT.as_secs()
+            )
+        })?
+        .with_context(|| format!("End of stream while receiving {name}"))?
+        .with_context(|| format!("Failed to decode {name}"))
+}
+
+pub(crate) async fn emit_completed(
+    order_id: OrderId,
+    settlement: CollaborativeSettlement,
+    quantity: Contracts,
+    dlc: Dlc,
+    executor: &command::Executor,
+) {
+    if let Err(e) = executor
+        .execute(order_id, |cfd| {
+            Ok(cfd.complete_partial_settlement(settlement, quantity, dlc))
+        })
+        .await
+    {
+        tracing::error!(%order_id, "Failed to execute `complete_partial_settlement` command: {e:#}");
+    }
+}
+
+pub(crate) async fn emit_rejected(order_id: OrderId, executor: &command::Executor) {
+    if let Err(e) = executor
+        .execute(order_id, |cfd| {
+            Ok(cfd.reject_partial_settlement(anyhow!("maker decision")))
+        })
+        .await
+    {
+        tracing::error!(%order_id, "Failed to execute `reject_partial_settlement` command: {e:#}")
+    }
+}
+
+/// Fail the partial settlement.
+///
+/// A partial settlement transaction we already signed is still adopted once it is confirmed.
+pub(crate) async fn emit_failed(order_id: OrderId, e: anyhow::Error, executor: &command::Executor) {
+    if let Err(e) = executor
+        .execute(order_id, |cfd| Ok(cfd.fail_partial_settlement(e)))
+        .await
+    {
+        tracing::error!(%order_id, "Failed to execute `fail_partial_settlement` command: {e:#}");
+    }
+}
@@ -0,0 +1,158 @@
+use crate::collab_settlement::partial::protocol::*;
+use crate::collab_settlement::partial::PROTOCOL;
+use crate::command;
+use crate::oracle;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use asynchronous_codec::Framed;
+use asynchronous_codec::JsonCodec;
+use futures::SinkExt;
+use futures::StreamExt;
+use model::libp2p::PeerId;
+use model::Contracts;
+use model::OrderId;
+use model::Price;
+use model::Role;
+use tokio_extras::FutureExt;
+use xtra::Address;
+use xtra_libp2p::Endpoint;
+use xtra_libp2p::OpenSubstream;
+use xtra_productivity::xtra_productivity;
+
+pub struct Actor {
+    endpoint: Address<Endpoint>,
+    executor: command::Executor,
+    oracle: oracle::AnnouncementsChannel,
+    n_payouts: usize,
+}
+
+impl Actor {
+    pub fn new(
+        endpoint: Address<Endpoint>,
+        executor: command::Executor,
+        oracle: oracle::AnnouncementsChannel,
+        n_payouts: usize,
+    ) -> Self {
+        Self {
+            endpoint,
+            executor,
+            oracle,
+            n_payouts,
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+#[derive(Clone, Copy)]
+pub struct SettlePartially {
+    pub order_id: OrderId,
+    pub price: Price,
+    pub quantity: Contracts,
+    pub maker_peer_id: PeerId,
+}
+
+#[xtra_productivity]
+impl Actor {
+    pub async fn handle(
+        &mut self,
+        msg: SettlePartially,
+        ctx: &mut xtra::Context<Self>,
+    ) -> Result<()> {
+        let SettlePartially {
+            order_id,
+            price,
+            quantity,
+            maker_peer_id,
+        } = msg;
+
+        let settlement = self
+            .executor
+            .execute(order_id, |cfd| {
+                cfd.start_partial_settlement_taker(price, quantity, self.n_payouts)
+            })
+            .await
+            .context("Could not start partially closing position")?;
+
+        tokio_extras::spawn_fallible(
+            &ctx.address().expect("self to be alive"),
+            {
+                let endpoint = self.endpoint.clone();
+                let executor = self.executor.clone();
+                let oracle = self.oracle.clone();
+                let n_payouts = self.n_payouts;
+                async move {
+                    let substream = endpoint
+                        .send(OpenSubstream::single_protocol(
+                            maker_peer_id.inner(),
+                            PROTOCOL,
+                        ))
+                        .await
+                        .context("Endpoint is disconnected")?
+                        .context("No connection to peer")?
+                        .await
+                        .context("Failed to open substream")?;
+                    let mut framed = Framed::new(substream, JsonCodec::<Message, Message>::new());
+
+                    framed
+                        .send(Message::Propose(Propose {
+                            id: order_id,
+                            price,
+                            quantity,
+                            unsigned_tx: settlement.transaction.unsigned_transaction().clone(),
+                        }))
+                        .await
+                        .context("Failed to send Propose")?;
+
+                    let decision = framed
+                        .next()
+                        .timeout(DECISION_TIMEOUT, || {
+                            tracing::debug_span!("receive decision")
+                        })
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Maker did not accept/reject within {} seconds.",
+                                DECISION_TIMEOUT.as_secs()
+                            )
+                        })?
+                        .context("End of stream while receiving Decision")?
+                        .context("Failed to decode Decision")?
+                        .into_decision()?;
+
+                    if let Decision::Reject = decision {
+                        emit_rejected(order_id, &executor).await;
+                        return Ok(());
+                    }
+
+                    let (settlement, dlc) = settle_partially(
+                        &mut framed,
+                        settlement,
+                        Role::Taker,
+                        &oracle,
+                        n_payouts,
+                        &executor,
+                    )
+                    .await?;
+
+                    emit_completed(order_id, settlement, quantity, dlc, &executor).await;
+                    anyhow::Ok(())
+                }
+            },
+            {
+                let executor = self.executor.clone();
+                move |e| async move {
+                    emit_failed(order_id, e, &executor).await;
+                }
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,503 @@
+//! Child-pays-for-parent (CPFP) fee bumping.
+//!
+//! Transactions that settle a CFD are built with the fee rate agreed upon during contract setup.
+//! If fees spike they can get stuck in the mempool, which puts the timelocks of the protocol at
+//! risk. This actor periodically checks the settlement transactions we published and spends our
+//! output with a wallet-funded child transaction so that parent and child together pay a target
+//! fee rate. If the parent is still not confirmed after some time we bump again at a higher fee
+//! rate, replacing the previous child.
+//!
+//! Commit transactions are out of scope: their only output is shared by both parties, so we cannot
+//! spend it alone, and they have no anchor output we could spend instead. Adding one would change
+//! the transactions both parties sign during contract setup and rollover. Instead we warn if a
+//! commit transaction we published is still unconfirmed after
+//! [`BumpPolicy::rebump_after`], so that it can be accelerated by other means.
+
+use crate::command;
+use crate::wallet;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use bdk::bitcoin::Script;
+use bdk::bitcoin::Transaction;
+use bdk::bitcoin::Txid;
+use futures::StreamExt;
+use model::CfdEvent;
+use model::EventKind;
+use model::OrderId;
+use model::Role;
+use model::Timestamp;
+use model::TxFeeRate;
+use sqlite_db;
+use std::num::NonZeroU32;
+use std::time::Duration;
+use xtra::prelude::MessageChannel;
+use xtra_productivity::xtra_productivity;
+use xtras::SendInterval;
+
+/// How often we check for settlement transactions that need a fee bump.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub struct Actor {
+    db: sqlite_db::Connection,
+    executor: command::Executor,
+    build_cpfp: MessageChannel<wallet::BuildCpfp, Result<wallet::Cpfp>>,
+    policy: BumpPolicy,
+}
+
+impl Actor {
+    pub fn new(
+        db: sqlite_db::Connection,
+        executor: command::Executor,
+        build_cpfp: MessageChannel<wallet::BuildCpfp, Result<wallet::Cpfp>>,
+        policy: BumpPolicy,
+    ) -> Self {
+        Self {
+            db,
+            executor,
+            build_cpfp,
+            policy,
+        }
+    }
+}
+
+/// Defines at which fee rates and how often we bump the fee of a transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct BumpPolicy {
+    /// The fee rate of the first bump.
+    pub target_fee_rate: TxFeeRate,
+    /// We never bump above this fee rate.
+    pub max_fee_rate: TxFeeRate,
+    /// If the parent is still not confirmed after this duration we bump again.
+    pub rebump_after: time::Duration,
+}
+
+impl Default for BumpPolicy {
+    fn default() -> Self {
+        Self {
+            target_fee_rate: TxFeeRate::new(NonZeroU32::new(10).expect("non-zero")),
+            max_fee_rate: TxFeeRate::new(NonZeroU32::new(200).expect("non-zero")),
+            rebump_after: time::Duration::HOUR,
+        }
+    }
+}
+
+impl BumpPolicy {
+    /// Whether a transaction published at `published_at` should have been bumped by now.
+    fn is_overdue(&self, published_at: Timestamp, now: Timestamp) -> bool {
+        now.seconds() - published_at.seconds() >= self.rebump_after.whole_seconds()
+    }
+
+    /// The fee rate of the next bump, if we should bump at this point in time.
+    ///
+    /// Every re-bump increases the fee rate of the last bump by 50%, capped at `max_fee_rate`.
+    fn next_fee_rate(
+        &self,
+        last_bump: Option<(Timestamp, TxFeeRate)>,
+        now: Timestamp,
+    ) -> Option<TxFeeRate> {
+        let (bumped_at, last_fee_rate) = match last_bump {
+            None => return Some(self.target_fee_rate),
+            Some(last_bump) => last_bump,
+        };
+
+        if !self.is_overdue(bumped_at, now) {
+            return None;
+        }
+
+        let last_fee_rate = last_fee_rate.to_u32();
+        let max_fee_rate = self.max_fee_rate.to_u32();
+
+        if last_fee_rate >= max_fee_rate {
+            return None;
+        }
+
+        let next_fee_rate = (last_fee_rate + last_fee_rate / 2)
+            .max(last_fee_rate + 1)
+            .min(max_fee_rate);
+
+        NonZeroU32::new(next_fee_rate).map(TxFeeRate::new)
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, _: BumpFees) {
+        if let Err(e) = self.bump_fees().await {
+            tracing::error!("Failed to bump fees: {e:#}");
+        }
+    }
+}
+
+impl Actor {
+    async fn bump_fees(&mut self) -> Result<()> {
+        let mut stream = self.db.load_all_open_cfds::<Cfd>(());
+
+        while let Some(cfd) = stream.next().await {
+            let cfd = match cfd {
+                Ok(cfd) => cfd,
+                Err(e) => {
+                    tracing::warn!("Failed to load CFD from database: {e:#}");
+                    continue;
+                }
+            };
+
+            if let Some((commit_txid, published_at)) = cfd.unconfirmed_commit {
+                if self.policy.is_overdue(published_at, Timestamp::now()) {
+                    tracing::warn!(
+                        order_id = %cfd.id,
+                        %commit_txid,
+                        "Commit transaction is still unconfirmed and cannot be fee-bumped"
+                    );
+                }
+            }
+
+            let parent = match cfd.pending {
+                Some(parent) => parent,
+                None => continue,
+            };
+
+            let fee_rate = match self.policy.next_fee_rate(cfd.last_bump, Timestamp::now()) {
+                Some(fee_rate) => fee_rate,
+                None => continue,
+            };
+
+            let order_id = cfd.id;
+            let parent_txid = parent.txid();
+            if let Err(e) = self.bump_fee(order_id, parent, fee_rate).await {
+                tracing::warn!(%order_id, %parent_txid, "Failed to bump fee: {e:#}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn bump_fee(
+        &self,
+        order_id: OrderId,
+        parent: Transaction,
+        fee_rate: TxFeeRate,
+    ) -> Result<()> {
+        let parent_txid = parent.txid();
+
+        let cpfp = self
+            .build_cpfp
+            .send(wallet::BuildCpfp { parent, fee_rate })
+            .await
+            .context("Wallet actor disconnected")??;
+
+        match cpfp {
+            wallet::Cpfp::Child(child_tx) => {
+                self.executor
+                    .execute(order_id, |cfd| {
+                        cfd.handle_fee_bumped(parent_txid, child_tx, fee_rate)
+                    })
+                    .await?;
+            }
+            wallet::Cpfp::Confirmed | wallet::Cpfp::NotPublished | wallet::Cpfp::NotNeeded => {
+                tracing::trace!(%order_id, %parent_txid, outcome = ?cpfp, "No fee bump needed");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
+        let this = ctx.address().expect("we are alive");
+        tokio_extras::spawn(
+            &this.clone(),
+            this.send_interval(CHECK_INTERVAL, || BumpFees, xtras::IncludeSpan::Always),
+        );
+    }
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+/// Message sent to ourselves at an interval to check if any of the
+/// published transactions needs a fee bump.
+#[derive(Clone, Copy)]
+pub struct BumpFees;
+
+/// Read-model of the CFD for the CPFP actor.
+#[derive(Debug, Clone)]
+struct Cfd {
+    id: OrderId,
+    role: Role,
+
+    /// Our script pubkey according to the latest DLC.
+    own_script_pubkey: Option<Script>,
+
+    /// Published transaction paying to us which is not confirmed yet.
+    pending: Option<Transaction>,
+    /// When and at which fee rate we last bumped the fee of the pending transaction.
+    last_bump: Option<(Timestamp, TxFeeRate)>,
+    /// Commit transaction we published which is not confirmed yet, and when we published it.
+    unconfirmed_commit: Option<(Txid, Timestamp)>,
+
+    version: u32,
+}
+
+impl Cfd {
+    fn apply(mut self, event: CfdEvent) -> Self {
+        self.version += 1;
+
+        use EventKind::*;
+        match event.event {
+            ContractSetupCompleted { dlc: Some(dlc) }
+            | RolloverCompleted { dlc: Some(dlc), .. } => Self {
+                own_script_pubkey: Some(dlc.script_pubkey_for(self.role)),
+                ..self
+            },
+            // The partial settlement transaction pays out the settled contracts and locks the
+            // remaining ones, until it is confirmed it is our pending transaction
+            PartialSettlementCompleted {
+                spend_tx,
+                dlc: Some(dlc),
+                ..
+            } => Self {
+                own_script_pubkey: Some(dlc.script_pubkey_for(self.role)),
+                ..self
+            }
+            .with_pending(spend_tx),
+            // a confirmed partial settlement transaction is the lock of the remaining contracts
+            LockConfirmed => Self {
+                pending: None,
+                last_bump: None,
+                ..self
+            },
+            CollaborativeSettlementCompleted { spend_tx: tx, .. }
+            | CetTimelockExpiredPostOracleAttestation { cet: tx }
+            | OracleAttestedPostCetTimelock { cet: tx, .. }
+            | RefundTimelockExpired { refund_tx: tx }
+            | RevokedCommitPublished { punish_tx: tx } => self.with_pending(tx),
+            FeeBumped {
+                parent_txid,
+                fee_rate,
+                ..
+            } => {
+                let is_pending = self
+                    .pending
+                    .as_ref()
+                    .map(|pending| pending.txid() == parent_txid)
+                    .unwrap_or(false);
+
+                if !is_pending {
+                    return self;
+                }
+
+                Self {
+                    last_bump: Some((event.timestamp, fee_rate)),
+                    ..self
+                }
+            }
+            ManualCommit { tx }
+            | OracleAttestedPriorCetTimelock {
+                commit_tx: Some(tx),
+                ..
+            } => Self {
+                unconfirmed_commit: Some((tx.txid(), event.timestamp)),
+                ..self
+            },
+            CommitConfirmed => Self {
+                unconfirmed_commit: None,
+                ..self
+            },
+            // final states, nothing to bump anymore
+            CetConfirmed | RefundConfirmed | CollaborativeSettlementConfirmed | PunishConfirmed => {
+                Self {
+                    pending: None,
+                    last_bump: None,
+                    unconfirmed_commit: None,
+                    ..self
+                }
+            }
+            ContractSetupCompleted { dlc: None }
+            | RolloverCompleted { dlc: None, .. }
+            | ContractSetupStarted
+            | ContractSetupFailed
+            | OfferRejected
+            | RolloverStarted
+            | RolloverAccepted
+            | RolloverRejected
+            | RolloverFailed
+            | CollaborativeSettlementStarted { .. }
+            | CollaborativeSettlementProposalAccepted
+            | CollaborativeSettlementRejected
+            | CollaborativeSettlementFailed
+            | PartialSettlementStarted { .. }
+            | PartialSettlementSigned { .. }
+            | PartialSettlementCompleted { dlc: None, .. }
+            | PartialSettlementRejected
+            | PartialSettlementFailed
+            | LockConfirmedAfterFinality
+            | RevokeConfirmed
+            | CetTimelockExpiredPriorOracleAttestation
+            | OracleAttestedPriorCetTimelock {
+                commit_tx: None, ..
+            } => self,
+        }
+    }
+
+    /// Track `tx` as the pending transaction if it pays to us.
+    ///
+    /// Transactions without an output for us (e.g. a CET after we got liquidated) cannot be
+    /// bumped by us.
+    fn with_pending(self, tx: Transaction) -> Self {
+        let pays_to_us = match &self.own_script_pubkey {
+            Some(script) => tx
+                .output
+                .iter()
+                .any(|output| output.script_pubkey == *script),
+            None => false,
+        };
+
+        if !pays_to_us {
+            return Self {
+                pending: None,
+                last_bump: None,
+                ..self
+            };
+        }
+
+        Self {
+            pending: Some(tx),
+            last_bump: None,
+            ..self
+        }
+    }
+}
+
+impl sqlite_db::CfdAggregate for Cfd {
+    type CtorArgs = ();
+
+    fn new(_: Self::CtorArgs, cfd: sqlite_db::Cfd) -> Self {
+        Self {
+            id: cfd.id,
+            role: cfd.role,
+            own_script_pubkey: None,
+            pending: None,
+            last_bump: None,
+            unconfirmed_commit: None,
+            version: 0,
+        }
+    }
+
+    fn apply(self, event: CfdEvent) -> Self {
+        self.apply(event)
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_rate(sat_per_vbyte: u32) -> TxFeeRate {
+        TxFeeRate::new(NonZeroU32::new(sat_per_vbyte).unwrap())
+    }
+
+    fn policy() -> BumpPolicy {
+        BumpPolicy {
+            target_fee_rate: fee_rate(10),
+            max_fee_rate: fee_rate(40),
+            rebump_after: time::Duration::HOUR,
+        }
+    }
+
+    #[test]
+    fn given_no_bump_yet_then_bump_at_target_fee_rate() {
+        let next = policy().next_fee_rate(None, Timestamp::new(0));
+
+        assert_eq!(next, Some(fee_rate(10)));
+    }
+
+    #[test]
+    fn given_recent_bump_then_no_rebump() {
+        let last_bump = (Timestamp::new(0), fee_rate(10));
+
+        let next = policy().next_fee_rate(Some(last_bump), Timestamp::new(30 * 60));
+
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn given_old_bump_then_rebump_at_higher_fee_rate() {
+        let last_bump = (Timestamp::new(0), fee_rate(10));
+
+        let next = policy().next_fee_rate(Some(last_bump), Timestamp::new(60 * 60));
+
+        assert_eq!(next, Some(fee_rate(15)));
+    }
+
+    #[test]
+    fn given_rebump_would_exceed_max_fee_rate_then_rebump_at_max_fee_rate() {
+        let last_bump = (Timestamp::new(0), fee_rate(30));
+
+        let next = policy().next_fee_rate(Some(last_bump), Timestamp::new(60 * 60));
+
+        assert_eq!(next, Some(fee_rate(40)));
+    }
+
+    #[test]
+    fn given_last_bump_at_max_fee_rate_then_no_rebump() {
+        let last_bump = (Timestamp::new(0), fee_rate(40));
+
+        let next = policy().next_fee_rate(Some(last_bump), Timestamp::new(2 * 60 * 60));
+
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn given_minimal_fee_rate_then_rebump_increases_fee_rate() {
+        let last_bump = (Timestamp::new(0), fee_rate(1));
+
+        let next = policy().next_fee_rate(Some(last_bump), Timestamp::new(60 * 60));
+
+        assert_eq!(next, Some(fee_rate(2)));
+    }
+
+    #[test]
+    fn given_published_commit_then_unconfirmed_until_commit_confirmed() {
+        let commit_tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![],
+        };
+        let cfd = Cfd {
+            id: OrderId::default(),
+            role: Role::Taker,
+            own_script_pubkey: None,
+            pending: None,
+            last_bump: None,
+            unconfirmed_commit: None,
+            version: 0,
+        };
+        let event = |event| CfdEvent {
+            timestamp: Timestamp::new(0),
+            id: cfd.id,
+            event,
+        };
+
+        let cfd = cfd.clone().apply(event(EventKind::ManualCommit {
+            tx: commit_tx.clone(),
+        }));
+        assert_eq!(
+            cfd.unconfirmed_commit,
+            Some((commit_tx.txid(), Timestamp::new(0)))
+        );
+        assert!(!policy().is_overdue(Timestamp::new(0), Timestamp::new(30 * 60)));
+        assert!(policy().is_overdue(Timestamp::new(0), Timestamp::new(60 * 60)));
+
+        let cfd = cfd.apply(event(EventKind::CommitConfirmed));
+        assert_eq!(cfd.unconfirmed_commit, None);
+    }
+}
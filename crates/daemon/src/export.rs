@@ -0,0 +1,375 @@
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use bdk::bitcoin::SignedAmount;
+use bdk::bitcoin::Txid;
+use model::calculate_margin;
+use model::calculate_profit;
+use model::ClosedCfd;
+use model::ContractSymbol;
+use model::Contracts;
+use model::Leverage;
+use model::OrderId;
+use model::PayoutCurve;
+use model::Position;
+use model::Price;
+use model::Role;
+use model::Settlement;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Format in which closed CFDs are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn render(&self, positions: &[ClosedPosition]) -> Result<String> {
+        let rendered = match self {
+            Format::Csv => to_csv(positions)?,
+            Format::Json => serde_json::to_string_pretty(positions)?,
+        };
+
+        Ok(rendered)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Csv => "csv".fmt(f),
+            Format::Json => "json".fmt(f),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = match s.to_lowercase().as_str() {
+            "csv" => Format::Csv,
+            "json" => Format::Json,
+            other => bail!("Unknown export format {other}, expected csv or json"),
+        };
+
+        Ok(format)
+    }
+}
+
+/// A closed CFD as reported for tax and accounting purposes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ClosedPosition {
+    pub order_id: OrderId,
+    #[serde(with = "time::serde::rfc3339")]
+    pub opened_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub closed_at: OffsetDateTime,
+    pub contract_symbol: ContractSymbol,
+    pub position: Position,
+    /// The quantity the CFD was opened with, including contracts that were settled partially.
+    pub quantity: Contracts,
+    pub entry_price: Price,
+    /// The price the CFD was settled at, unknown if it was refunded or punished.
+    pub exit_price: Option<Price>,
+    /// The fees we paid over the lifetime of the CFD, negative if we received them.
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_sat")]
+    pub fees_sat: SignedAmount,
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_sat")]
+    pub pnl_sat: SignedAmount,
+    /// The realised PnL valued at the exit price.
+    ///
+    /// The PnL of partially settled contracts is valued at the price they were settled at. Only
+    /// known for inverse contracts such as BTCUSD with an exit price. It is `None` for quanto
+    /// contracts such as ETHUSD, because their exit price is not a bitcoin price.
+    pub pnl_usd: Option<Decimal>,
+    pub closing_txid: Txid,
+}
+
+impl TryFrom<ClosedCfd> for ClosedPosition {
+    type Error = anyhow::Error;
+
+    fn try_from(cfd: ClosedCfd) -> Result<Self, Self::Error> {
+        let our_leverage = match cfd.role {
+            Role::Maker => Leverage::ONE,
+            Role::Taker => cfd.taker_leverage,
+        };
+        let margin = |n_contracts| {
+            calculate_margin(
+                cfd.contract_symbol,
+                cfd.initial_price,
+                n_contracts,
+                our_leverage,
+            )
+        };
+
+        let (exit_price, payout, closing_txid) = match cfd.settlement {
+            Settlement::Collaborative {
+                txid,
+                payout,
+                price,
+                ..
+            }
+            | Settlement::Cet {
+                txid,
+                payout,
+                price,
+                ..
+            } => (Some(price), payout, txid),
+            Settlement::Refund { txid, payout, .. } | Settlement::Punish { txid, payout, .. } => {
+                (None, payout, txid)
+            }
+        };
+
+        let (mut pnl_sat, _) = calculate_profit(payout.inner(), margin(cfd.n_contracts));
+        let mut pnl_usd = pnl_in_usd(cfd.contract_symbol, pnl_sat, exit_price);
+        let mut quantity = cfd.n_contracts;
+        let mut fees = cfd.fees.inner();
+
+        for partial_payout in cfd.partial_payouts.iter() {
+            let (partial_pnl_sat, _) = calculate_profit(
+                partial_payout.payout.inner(),
+                margin(partial_payout.n_contracts),
+            );
+            let partial_pnl_usd = pnl_in_usd(
+                cfd.contract_symbol,
+                partial_pnl_sat,
+                Some(partial_payout.price),
+            );
+
+            pnl_sat += partial_pnl_sat;
+            pnl_usd = pnl_usd
+                .zip(partial_pnl_usd)
+                .map(|(pnl_usd, partial_pnl_usd)| pnl_usd + partial_pnl_usd);
+            quantity = quantity + partial_payout.n_contracts;
+            fees += partial_payout.fees.inner();
+        }
+
+        Ok(Self {
+            order_id: cfd.id,
+            opened_at: OffsetDateTime::from_unix_timestamp(cfd.creation_timestamp.seconds())?,
+            closed_at: OffsetDateTime::from_unix_timestamp(cfd.closing_timestamp.seconds())?,
+            contract_symbol: cfd.contract_symbol,
+            position: cfd.position,
+            quantity,
+            entry_price: cfd.initial_price,
+            exit_price,
+            fees_sat: fees,
+            pnl_sat,
+            pnl_usd: pnl_usd.map(|pnl_usd| pnl_usd.round_dp(2)),
+            closing_txid,
+        })
+    }
+}
+
+/// Value a PnL in USD at `price`, if it is a bitcoin price.
+fn pnl_in_usd(
+    contract_symbol: ContractSymbol,
+    pnl: SignedAmount,
+    price: Option<Price>,
+) -> Option<Decimal> {
+    match (contract_symbol.payout_curve(), price) {
+        (PayoutCurve::Inverse, Some(price)) => {
+            let pnl_btc = Decimal::from(pnl.as_sat()) / Decimal::from(100_000_000);
+            Some(pnl_btc * price.into_decimal())
+        }
+        _ => None,
+    }
+}
+
+/// Load all closed CFDs from the database as positions to export.
+pub async fn closed_positions(db: &sqlite_db::Connection) -> Result<Vec<ClosedPosition>> {
+    db.load_closed_cfds()
+        .await?
+        .into_iter()
+        .map(ClosedPosition::try_from)
+        .collect()
+}
+
+/// Write all closed CFDs to `output` in the given format, or to stdout if no `output` is given.
+pub async fn write_closed_positions(
+    db: &sqlite_db::Connection,
+    format: Format,
+    output: Option<&Path>,
+) -> Result<()> {
+    let positions = closed_positions(db).await?;
+    let export = format.render(&positions)?;
+
+    match output {
+        Some(path) => {
+            tokio::fs::write(path, export)
+                .await
+                .with_context(|| format!("Failed to write export to {}", path.display()))?;
+            tracing::info!(
+                "Exported {} closed CFDs to {}",
+                positions.len(),
+                path.display()
+            );
+        }
+        None => std::io::stdout()
+            .write_all(export.as_bytes())
+            .context("Failed to write export to stdout")?,
+    }
+
+    Ok(())
+}
+
+const CSV_HEADER: &str = "order_id,opened_at,closed_at,contract_symbol,position,quantity,\
+entry_price,exit_price,fees_sat,pnl_sat,pnl_usd,closing_txid";
+
+fn to_csv(positions: &[ClosedPosition]) -> Result<String> {
+    let mut csv = format!("{CSV_HEADER}\n");
+
+    for position in positions {
+        let opened_at = position.opened_at.format(&Rfc3339)?;
+        let closed_at = position.closed_at.format(&Rfc3339)?;
+        let exit_price = position
+            .exit_price
+            .map(|price| price.to_string())
+            .unwrap_or_default();
+        let pnl_usd = position
+            .pnl_usd
+            .map(|pnl| pnl.to_string())
+            .unwrap_or_default();
+
+        csv.push_str(&format!(
+            "{},{opened_at},{closed_at},{},{:?},{},{},{exit_price},{},{},{pnl_usd},{}\n",
+            position.order_id,
+            position.contract_symbol,
+            position.position,
+            position.quantity,
+            position.entry_price,
+            position.fees_sat.as_sat(),
+            position.pnl_sat.as_sat(),
+            position.closing_txid,
+        ));
+    }
+
+    Ok(csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bdk::bitcoin::Amount;
+    use model::Fees;
+    use model::Identity;
+    use model::Lock;
+    use model::PartialPayout;
+    use model::Payout;
+    use model::Timestamp;
+    use model::Vout;
+    use rust_decimal_macros::dec;
+
+    fn closed_cfd(settlement: Settlement) -> ClosedCfd {
+        ClosedCfd {
+            id: OrderId::default(),
+            offer_id: OrderId::default(),
+            position: Position::Long,
+            initial_price: Price::new(dec!(20_000)).unwrap(),
+            taker_leverage: Leverage::TWO,
+            n_contracts: Contracts::new(100),
+            counterparty_network_identity: Identity::new(x25519_dalek::PublicKey::from(
+                *b"hello world, oh what a beautiful",
+            )),
+            counterparty_peer_id: model::libp2p::PeerId::random(),
+            role: Role::Taker,
+            fees: Fees::new(SignedAmount::from_sat(1_000)),
+            expiry_timestamp: OffsetDateTime::UNIX_EPOCH,
+            lock: Lock {
+                txid: Txid::default(),
+                dlc_vout: Vout::new(0),
+            },
+            settlement,
+            partial_payouts: Vec::new(),
+            creation_timestamp: Timestamp::new(0),
+            closing_timestamp: Timestamp::new(86_400),
+            contract_symbol: ContractSymbol::BTC_USD,
+        }
+    }
+
+    #[test]
+    fn collaborative_settlement_has_pnl_in_usd() {
+        // margin of 100 contracts at 20k with leverage two is 0.0025 BTC
+        let position = ClosedPosition::try_from(closed_cfd(Settlement::Collaborative {
+            txid: Txid::default(),
+            vout: Vout::new(0),
+            payout: Payout::new(Amount::from_sat(350_000)),
+            price: Price::new(dec!(25_000)).unwrap(),
+        }))
+        .unwrap();
+
+        assert_eq!(position.pnl_sat, SignedAmount::from_sat(100_000));
+        assert_eq!(position.pnl_usd, Some(dec!(25)));
+        assert_eq!(position.exit_price, Some(Price::new(dec!(25_000)).unwrap()));
+    }
+
+    #[test]
+    fn partial_settlements_are_part_of_pnl_and_fees() {
+        // 30 of the 100 contracts were settled at 25k first, the margin of the remaining 70
+        // contracts is 0.00175 BTC
+        let mut cfd = closed_cfd(Settlement::Collaborative {
+            txid: Txid::default(),
+            vout: Vout::new(0),
+            payout: Payout::new(Amount::from_sat(245_000)),
+            price: Price::new(dec!(20_000)).unwrap(),
+        });
+        cfd.n_contracts = Contracts::new(70);
+        cfd.partial_payouts = vec![PartialPayout {
+            txid: Txid::default(),
+            vout: Vout::new(1),
+            payout: Payout::new(Amount::from_sat(105_000)),
+            price: Price::new(dec!(25_000)).unwrap(),
+            n_contracts: Contracts::new(30),
+            fees: Fees::new(SignedAmount::from_sat(300)),
+        }];
+
+        let position = ClosedPosition::try_from(cfd).unwrap();
+
+        assert_eq!(position.quantity, Contracts::new(100));
+        assert_eq!(position.pnl_sat, SignedAmount::from_sat(100_000));
+        assert_eq!(position.fees_sat, SignedAmount::from_sat(1_300));
+        // 0.0003 BTC at 25k and 0.0007 BTC at 20k
+        assert_eq!(position.pnl_usd, Some(dec!(21.50)));
+        assert_eq!(position.exit_price, Some(Price::new(dec!(20_000)).unwrap()));
+    }
+
+    #[test]
+    fn refund_has_no_exit_price_and_no_pnl_in_usd() {
+        let position = ClosedPosition::try_from(closed_cfd(Settlement::Refund {
+            commit_txid: Txid::default(),
+            txid: Txid::default(),
+            vout: Vout::new(0),
+            payout: Payout::new(Amount::from_sat(250_000)),
+        }))
+        .unwrap();
+
+        assert_eq!(position.pnl_sat, SignedAmount::ZERO);
+        assert_eq!(position.exit_price, None);
+        assert_eq!(position.pnl_usd, None);
+
+        let csv = Format::Csv.render(&[position]).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+
+        assert_eq!(csv.lines().next().unwrap(), CSV_HEADER);
+        assert!(row.starts_with(&format!(
+            "{},1970-01-01T00:00:00Z,1970-01-02T00:00:00Z,BTCUSD,Long,100,20000,,1000,0,,",
+            position.order_id
+        )));
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("CSV".parse::<Format>().unwrap(), Format::Csv);
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Estimates of the fee rate of the transactions of CFDs.
+//!
+//! The maker derives the fee rate of its offers and rollovers from the estimate of its chain
+//! backend. The taker refuses offers and rollovers with fee rates far off its own estimate,
+//! because the fee rate decides whether commit transactions and CETs confirm in time.
+
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use btsieve::ChainBackend;
+use model::TxFeeRate;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use xtra::prelude::MessageChannel;
+use xtra_productivity::xtra_productivity;
+use xtras::SendInterval;
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// The number of blocks a transaction should confirm within.
+    target_blocks: u16,
+    /// The lowest fee rate we use, regardless of the estimate.
+    floor: TxFeeRate,
+    /// The highest fee rate we use, regardless of the estimate.
+    ceiling: TxFeeRate,
+}
+
+impl Config {
+    pub fn new(target_blocks: u16, floor: TxFeeRate, ceiling: TxFeeRate) -> Result<Self> {
+        ensure!(
+            target_blocks > 0,
+            "Confirmation target must be at least one block"
+        );
+        ensure!(
+            floor.to_u32() <= ceiling.to_u32(),
+            "Fee rate floor {floor} is above ceiling {ceiling}"
+        );
+
+        Ok(Self {
+            target_blocks,
+            floor,
+            ceiling,
+        })
+    }
+
+    /// The lowest fee rate we use, or accept from the counterparty.
+    pub fn floor(&self) -> TxFeeRate {
+        self.floor
+    }
+
+    /// The highest fee rate we use, or accept from the counterparty.
+    pub fn ceiling(&self) -> TxFeeRate {
+        self.ceiling
+    }
+
+    /// Round an estimate up to whole satoshi per vbyte and keep it between floor and ceiling.
+    fn clamp(&self, sat_per_vbyte: f32) -> TxFeeRate {
+        let fee_rate =
+            (sat_per_vbyte.ceil() as u32).clamp(self.floor.to_u32(), self.ceiling.to_u32());
+
+        TxFeeRate::new(NonZeroU32::new(fee_rate).expect("floor to be non-zero"))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            target_blocks: 6,
+            floor: TxFeeRate::default(),
+            ceiling: TxFeeRate::new(NonZeroU32::new(100).expect("non-zero")),
+        }
+    }
+}
+
+/// Get the latest fee rate estimate, if there is one yet.
+#[derive(Clone, Copy)]
+pub struct GetTxFeeRate;
+
+/// Our fee rate estimate for the rollover protocol, which checks the maker's fee rate against it.
+#[derive(Clone)]
+pub struct EstimateChannel(Option<MessageChannel<GetTxFeeRate, Option<TxFeeRate>>>);
+
+impl EstimateChannel {
+    pub fn new(channel: Option<MessageChannel<GetTxFeeRate, Option<TxFeeRate>>>) -> Self {
+        Self(channel)
+    }
+}
+
+#[async_trait]
+impl rollover::protocol::EstimateTxFeeRate for EstimateChannel {
+    async fn estimate_tx_fee_rate(&self) -> Option<TxFeeRate> {
+        let channel = self.0.as_ref()?;
+
+        match channel.send(GetTxFeeRate).await {
+            Ok(estimate) => estimate,
+            Err(_) => {
+                tracing::warn!("Fee estimate actor disconnected");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct UpdateEstimate;
+
+pub struct Actor {
+    chain: Arc<dyn ChainBackend>,
+    config: Config,
+    latest: Option<TxFeeRate>,
+}
+
+impl Actor {
+    pub fn new(chain: Arc<dyn ChainBackend>, config: Config) -> Self {
+        Self {
+            chain,
+            config,
+            latest: None,
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, _: GetTxFeeRate) -> Option<TxFeeRate> {
+        self.latest
+    }
+
+    async fn handle(&mut self, _: UpdateEstimate) {
+        let target_blocks = self.config.target_blocks;
+
+        // Estimating the fee rate blocks on a request to the chain backend
+        let estimate = tokio::task::spawn_blocking({
+            let chain = self.chain.clone();
+            move || chain.estimate_fee_rate(target_blocks)
+        })
+        .await
+        .context("Fee rate estimation panicked")
+        .and_then(|estimate| estimate);
+
+        let estimate = match estimate {
+            Ok(Some(estimate)) => estimate,
+            Ok(None) => {
+                tracing::debug!(%target_blocks, "Chain backend has no fee rate estimate");
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to estimate fee rate: {e:#}");
+                return;
+            }
+        };
+
+        let fee_rate = self.config.clamp(estimate);
+        if self.latest != Some(fee_rate) {
+            tracing::info!(%fee_rate, %estimate, %target_blocks, "Updated transaction fee rate");
+        }
+
+        self.latest = Some(fee_rate);
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
+        let this = ctx.address().expect("we just started");
+
+        tokio_extras::spawn(
+            &this.clone(),
+            this.send_interval(
+                UPDATE_INTERVAL,
+                || UpdateEstimate,
+                xtras::IncludeSpan::Always,
+            ),
+        );
+    }
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_rate(sat_per_vbyte: u32) -> TxFeeRate {
+        TxFeeRate::new(NonZeroU32::new(sat_per_vbyte).unwrap())
+    }
+
+    #[test]
+    fn estimate_is_rounded_up_and_clamped() {
+        let config = Config::new(2, fee_rate(2), fee_rate(50)).unwrap();
+
+        assert_eq!(config.clamp(0.5), fee_rate(2));
+        assert_eq!(config.clamp(10.1), fee_rate(11));
+        assert_eq!(config.clamp(120.0), fee_rate(50));
+    }
+
+    #[test]
+    fn floor_above_ceiling_is_invalid() {
+        assert!(Config::new(2, fee_rate(10), fee_rate(5)).is_err());
+    }
+}
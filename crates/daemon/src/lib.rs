@@ -5,6 +5,7 @@ use crate::bitcoin::Network;
 use crate::bitcoin::Txid;
 use crate::listen_protocols::TAKER_LISTEN_PROTOCOLS;
 use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context as _;
 use anyhow::Result;
 pub use bdk;
@@ -13,13 +14,13 @@ use bdk::bitcoin::Amount;
 use bdk::FeeRate;
 use identify::PeerInfo;
 use libp2p_core::Multiaddr;
-use libp2p_tcp::TokioTcpConfig;
 pub use maia;
 pub use maia_core;
-use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use model::libp2p::PeerId;
 use model::olivia;
+use model::olivia::Oracles;
 use model::Contracts;
+use model::FeeLedgerEntry;
 use model::Identity;
 use model::Leverage;
 use model::OfferId;
@@ -31,7 +32,10 @@ use parse_display::Display;
 use ping_pong::ping;
 use ping_pong::pong;
 use seed::Identities;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
@@ -51,16 +55,24 @@ use xtras::supervisor::Supervisor;
 pub mod archive_closed_cfds;
 pub mod archive_failed_cfds;
 pub mod auto_rollover;
+pub mod backup;
+pub mod cfd_backup;
+pub mod chain;
 pub mod collab_settlement;
 pub mod command;
+pub mod cpfp;
+pub mod export;
+pub mod fee_estimate;
 pub mod identify;
 pub mod libp2p_utils;
 pub mod listen_protocols;
+pub mod maker_request;
 pub mod monitor;
 pub mod online_status;
 pub mod oracle;
 pub mod order;
 pub mod position_metrics;
+pub mod price_feed;
 pub mod process_manager;
 pub mod projection;
 pub mod seed;
@@ -78,15 +90,39 @@ pub const PING_INTERVAL: Duration = Duration::from_secs(30);
 
 pub const N_PAYOUTS: usize = 200;
 
+/// A maker the taker connects to.
+#[derive(Debug, Clone)]
+pub struct Maker {
+    pub identity: Identity,
+    /// The addresses of the maker to try in order, each including its peer id.
+    pub multiaddrs: Vec<Multiaddr>,
+}
+
+impl Maker {
+    pub fn peer_id(&self) -> Result<PeerId> {
+        let peer_id = self
+            .multiaddrs
+            .first()
+            .context("No maker address")?
+            .clone()
+            .extract_peer_id()
+            .context("Unable to extract peer id from maker address")?;
+
+        Ok(PeerId::from(peer_id))
+    }
+}
+
 pub struct TakerActorSystem<O, W, P> {
     pub cfd_actor: Address<taker_cfd::Actor>,
     pub wallet_actor: Address<W>,
     _oracle_actor: Address<O>,
     pub auto_rollover_actor: Address<auto_rollover::Actor>,
+    maker_request_actor: Address<maker_request::taker::Actor>,
     pub price_feed_actor: Address<P>,
     executor: command::Executor,
     _close_cfds_actor: Address<archive_closed_cfds::Actor>,
     _archive_failed_cfds_actor: Address<archive_failed_cfds::Actor>,
+    _cpfp_actor: Address<cpfp::Actor>,
     _pong_actor: Address<pong::Actor>,
     _online_status_actor: Address<online_status::Actor>,
     _identify_dialer_actor: Address<identify::dialer::Actor>,
@@ -111,11 +147,9 @@ where
         + Handler<wallet::Withdraw, Return = Result<Txid>>
         + Handler<wallet::ImportSeed, Return = Result<bdk::wallet::AddressInfo>>
         + Handler<wallet::Sync, Return = ()>
+        + Handler<wallet::BuildCpfp, Return = Result<wallet::Cpfp>>
         + Actor<Stop = ()>,
-    P: Handler<
-            xtra_bitmex_price_feed::GetLatestQuotes,
-            Return = xtra_bitmex_price_feed::LatestQuotes,
-        > + Actor<Stop = xtra_bitmex_price_feed::Error>,
+    P: xtra_bitmex_price_feed::PriceFeed,
 {
     #[instrument(
         name = "Create TakerActorSystem",
@@ -131,17 +165,21 @@ where
     pub fn new<M>(
         db: sqlite_db::Connection,
         wallet_actor_addr: Address<W>,
-        oracle_pk: XOnlyPublicKey,
+        oracles: Oracles,
         identity: Identities,
         oracle_constructor: impl FnOnce(command::Executor) -> O,
         monitor_constructor: impl FnOnce(command::Executor) -> Result<M>,
         price_feed_actor: Address<P>,
         n_payouts: usize,
         connect_timeout: Duration,
+        tor_socks_proxy: Option<SocketAddr>,
         projection_actor: Address<projection::Actor>,
-        maker_identity: Identity,
-        maker_multiaddr: Multiaddr,
+        makers: Vec<Maker>,
         environment: Environment,
+        cfd_backup: cfd_backup::Actor,
+        position_metrics: position_metrics::Actor,
+        fee_estimate: Option<fee_estimate::Actor>,
+        accept_maker_requests: bool,
     ) -> Result<Self>
     where
         M: Handler<monitor::MonitorAfterContractSetup, Return = ()>
@@ -149,23 +187,40 @@ where
             + Handler<monitor::Sync, Return = ()>
             + Handler<monitor::MonitorCollaborativeSettlement, Return = ()>
             + Handler<monitor::MonitorCetFinality, Return = Result<()>>
+            + Handler<monitor::MonitorPunishFinality, Return = Result<()>>
             + Handler<monitor::TryBroadcastTransaction, Return = Result<()>>
             + Actor<Stop = ()>,
     {
+        ensure!(!makers.is_empty(), "Need at least one maker to connect to");
+
+        let maker_peer_ids = makers
+            .iter()
+            .map(|maker| Ok((maker.peer_id()?, maker.identity)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        ensure!(
+            maker_peer_ids.len() == makers.len(),
+            "Maker peer ids must be unique"
+        );
+
         let (maker_online_status_feed_sender, maker_online_status_feed_receiver) =
             watch::channel(ConnectionStatus::Offline);
 
         let (monitor_addr, monitor_ctx) = Context::new(None);
         let (oracle_addr, oracle_ctx) = Context::new(None);
         let (process_manager_addr, process_manager_ctx) = Context::new(None);
+        let (maker_request_addr, maker_request_ctx) = Context::new(None);
 
         let executor = command::Executor::new(db.clone(), process_manager_addr.clone());
 
         let mut tasks = Tasks::default();
 
-        let position_metrics_actor = position_metrics::Actor::new(db.clone())
-            .create(None)
-            .spawn(&mut tasks);
+        let position_metrics_actor = position_metrics.create(None).spawn(&mut tasks);
+        let cfd_backup_addr = cfd_backup.create(None).spawn(&mut tasks);
+        let fee_estimate_config = fee_estimate
+            .as_ref()
+            .map(fee_estimate::Actor::config)
+            .unwrap_or_default();
+        let fee_estimate_addr = fee_estimate.map(|actor| actor.create(None).spawn(&mut tasks));
 
         tasks.add(process_manager_ctx.run(process_manager::Actor::new(
             db.clone(),
@@ -176,8 +231,13 @@ where
             monitor_addr.clone().into(),
             monitor_addr.clone().into(),
             monitor_addr.clone().into(),
+            monitor_addr.clone().into(),
+            monitor_addr.clone().into(),
             monitor_addr.into(),
             oracle_addr.clone().into(),
+            cfd_backup_addr.clone().into(),
+            cfd_backup_addr.into(),
+            Some(maker_request_addr.clone().into()),
         )));
 
         let (endpoint_addr, endpoint_context) = Context::new(None);
@@ -192,7 +252,6 @@ where
             move || {
                 order::taker::Actor::new(
                     n_payouts,
-                    oracle_pk,
                     oracle.clone().into(),
                     (db.clone(), process_manager.clone()),
                     (wallet.clone().into(), wallet.clone().into()),
@@ -214,49 +273,73 @@ where
             }
         });
         tasks.add(collab_settlement_supervisor.run_log_summary());
+        let (partial_collab_settlement_supervisor, partial_collab_settlement_addr) =
+            Supervisor::new({
+                let endpoint_addr = endpoint_addr.clone();
+                let executor = executor.clone();
+                let oracle_addr = oracle_addr.clone();
+                move || {
+                    collab_settlement::partial::taker::Actor::new(
+                        endpoint_addr.clone(),
+                        executor.clone(),
+                        oracle::AnnouncementsChannel::new(oracle_addr.clone().into()),
+                        n_payouts,
+                    )
+                }
+            });
+        tasks.add(partial_collab_settlement_supervisor.run_log_summary());
 
         let cfd_actor_addr = taker_cfd::Actor::new(
             db.clone(),
-            projection_actor,
-            collab_settlement_addr,
+            projection_actor.clone(),
+            (collab_settlement_addr, partial_collab_settlement_addr),
             order,
-            maker_identity,
-            PeerId::from(
-                maker_multiaddr
-                    .clone()
-                    .extract_peer_id()
-                    .context("Unable to extract peer id from maker address")?,
-            ),
+            maker_peer_ids.clone(),
+            oracles,
+            fee_estimate_addr.clone().map(Into::into),
         )
         .create(None)
         .spawn(&mut tasks);
 
+        let (auto_rollover_addr, auto_rollover_ctx) = Context::new(None);
+
         let (rollover_supervisor, rollover_addr) = Supervisor::new({
             let endpoint_addr = endpoint_addr.clone();
             let executor = executor.clone();
             let oracle_addr = oracle_addr.clone();
+            let auto_rollover_addr = auto_rollover_addr.clone();
+            let fee_estimate_addr = fee_estimate_addr.clone();
             move || {
                 rollover::taker::Actor::new(
                     endpoint_addr.clone(),
                     executor.clone(),
-                    oracle_pk,
                     oracle::AnnouncementsChannel::new(oracle_addr.clone().into()),
                     n_payouts,
+                    auto_rollover_addr.clone().into(),
+                    fee_estimate::EstimateChannel::new(fee_estimate_addr.clone().map(Into::into)),
+                    (fee_estimate_config.floor(), fee_estimate_config.ceiling()),
                 )
             }
         });
         tasks.add(rollover_supervisor.run_log_summary());
 
-        let auto_rollover_addr = auto_rollover::Actor::new(db.clone(), rollover_addr)
-            .create(None)
-            .spawn(&mut tasks);
+        tasks.add(auto_rollover_ctx.run(auto_rollover::Actor::new(db.clone(), rollover_addr)));
+
+        tasks.add(maker_request_ctx.run(maker_request::taker::Actor::new(
+            executor.clone(),
+            cfd_actor_addr.clone(),
+            price_feed_actor.clone().into(),
+            auto_rollover_addr.clone(),
+            projection_actor,
+            accept_maker_requests,
+        )));
 
         let online_status_actor = online_status::Actor::new(
             endpoint_addr.clone(),
-            maker_multiaddr
-                .clone()
-                .extract_peer_id()
-                .expect("to be able to extract peer id"),
+            maker_peer_ids
+                .keys()
+                .map(|peer_id| peer_id.inner())
+                .collect(),
             maker_online_status_feed_sender,
         )
         .create(None)
@@ -265,14 +348,24 @@ where
         tasks.add(monitor_ctx.run(monitor_constructor(executor.clone())?));
         tasks.add(oracle_ctx.run(oracle_constructor(executor.clone())));
 
-        let dialer_constructor = {
-            let endpoint_addr = endpoint_addr.clone();
-            move || dialer::Actor::new(endpoint_addr.clone(), maker_multiaddr.clone())
-        };
-        let (dialer_supervisor, dialer_actor) = Supervisor::<_, dialer::Error>::with_policy(
-            dialer_constructor,
-            always_restart_after(RESTART_INTERVAL),
-        );
+        // One dialer per maker keeps the connection to each maker alive
+        let mut dialer_supervisors = Vec::new();
+        let mut dialer_actors = Vec::new();
+        for maker in makers {
+            let dialer_constructor = {
+                let endpoint_addr = endpoint_addr.clone();
+                move || {
+                    dialer::Actor::with_addresses(endpoint_addr.clone(), maker.multiaddrs.clone())
+                }
+            };
+            let (dialer_supervisor, dialer_actor) = Supervisor::<_, dialer::Error>::with_policy(
+                dialer_constructor,
+                always_restart_after(RESTART_INTERVAL),
+            );
+
+            dialer_supervisors.push(dialer_supervisor);
+            dialer_actors.push(dialer_actor);
+        }
 
         let (offer_supervisor, offer_addr) = Supervisor::new({
             let cfd_actor_addr = cfd_actor_addr.clone();
@@ -302,14 +395,28 @@ where
             Supervisor::new(move || ping::Actor::new(endpoint_addr.clone(), PING_INTERVAL));
         tasks.add(supervisor.run_log_summary());
 
+        let mut connection_dropped_subscribers: Vec<
+            MessageChannel<endpoint::ConnectionDropped, ()>,
+        > = vec![
+            ping_actor.clone().into(),
+            online_status_actor.clone().into(),
+            identify_dialer_actor.clone().into(),
+        ];
+        connection_dropped_subscribers.extend(
+            dialer_actors
+                .into_iter()
+                .map(|dialer_actor| dialer_actor.into()),
+        );
+
         let endpoint = Endpoint::new(
-            Box::new(TokioTcpConfig::new),
+            Box::new(move || libp2p_utils::transport(tor_socks_proxy)),
             identity.libp2p,
             ENDPOINT_CONNECTION_TIMEOUT,
             TAKER_LISTEN_PROTOCOLS.inbound_substream_handlers(
                 pong_address.clone(),
                 identify_listener_actor,
                 offer_addr,
+                maker_request_addr.clone(),
             ),
             endpoint::Subscribers::new(
                 vec![
@@ -317,12 +424,7 @@ where
                     ping_actor.clone().into(),
                     identify_dialer_actor.clone().into(),
                 ],
-                vec![
-                    dialer_actor.into(),
-                    ping_actor.into(),
-                    online_status_actor.clone().into(),
-                    identify_dialer_actor.clone().into(),
-                ],
+                connection_dropped_subscribers,
                 vec![],
                 vec![],
             ),
@@ -331,7 +433,9 @@ where
 
         tasks.add(endpoint_context.run(endpoint));
 
-        tasks.add(dialer_supervisor.run_log_summary());
+        for dialer_supervisor in dialer_supervisors {
+            tasks.add(dialer_supervisor.run_log_summary());
+        }
         tasks.add(offer_supervisor.run_log_summary());
         tasks.add(identify_listener_supervisor.run_log_summary());
 
@@ -341,6 +445,14 @@ where
         let archive_failed_cfds_actor = archive_failed_cfds::Actor::new(db.clone())
             .create(None)
             .spawn(&mut tasks);
+        let cpfp_actor = cpfp::Actor::new(
+            db.clone(),
+            executor.clone(),
+            wallet_actor_addr.clone().into(),
+            cpfp::BumpPolicy::default(),
+        )
+        .create(None)
+        .spawn(&mut tasks);
 
         tracing::debug!("Taker actor system ready");
 
@@ -349,10 +461,12 @@ where
             wallet_actor: wallet_actor_addr,
             _oracle_actor: oracle_addr,
             auto_rollover_actor: auto_rollover_addr,
+            maker_request_actor: maker_request_addr,
             price_feed_actor,
             executor,
             _close_cfds_actor: close_cfds_actor,
             _archive_failed_cfds_actor: archive_failed_cfds_actor,
+            _cpfp_actor: cpfp_actor,
             _tasks: tasks,
             maker_online_status_feed_receiver,
             identify_info_feed_receiver,
@@ -392,41 +506,34 @@ where
     }
 
     #[instrument(skip(self), err)]
-    pub async fn propose_settlement(&self, order_id: OrderId) -> Result<()> {
-        let contract_symbol = self
-            .executor
-            .query(order_id, |cfd| Ok(cfd.contract_symbol()))
-            .await?;
+    pub async fn propose_settlement(
+        &self,
+        order_id: OrderId,
+        quantity: Option<Contracts>,
+    ) -> Result<()> {
+        propose_settlement_at_latest_quote(
+            &self.executor,
+            &self.price_feed_actor.clone().into(),
+            &self.cfd_actor,
+            order_id,
+            quantity,
+        )
+        .await
+    }
 
-        let latest_quote = *self
-            .price_feed_actor
-            .send(xtra_bitmex_price_feed::GetLatestQuotes)
-            .await
-            .context("Price feed not available")?
-            .get(&into_price_feed_symbol(contract_symbol))
-            .context("No quote available")?;
-
-        let quote_timestamp = latest_quote
-            .timestamp
-            .format(&time::format_description::well_known::Rfc3339)
-            .context("Failed to format timestamp")?;
-
-        let threshold = QUOTE_INTERVAL_MINUTES.minutes() * 2;
-
-        if latest_quote.is_older_than(threshold) {
-            bail!(
-                "Latest quote is older than {} minutes. Refusing to settle with old price.",
-                threshold.whole_minutes()
-            )
-        }
+    /// Carry out the settlement or rollover the maker asked for on a CFD.
+    #[instrument(skip(self), err)]
+    pub async fn accept_maker_request(&self, order_id: OrderId) -> Result<()> {
+        self.maker_request_actor
+            .send(maker_request::taker::Accept { order_id })
+            .await?
+    }
 
-        self.cfd_actor
-            .send(taker_cfd::ProposeSettlement {
-                order_id,
-                bid: Price::new(latest_quote.bid())?,
-                ask: Price::new(latest_quote.ask())?,
-                quote_timestamp,
-            })
+    /// Decline the settlement or rollover the maker asked for on a CFD.
+    #[instrument(skip(self), err)]
+    pub async fn reject_maker_request(&self, order_id: OrderId) -> Result<()> {
+        self.maker_request_actor
+            .send(maker_request::taker::Reject { order_id })
             .await?
     }
 
@@ -446,6 +553,21 @@ where
             .await?
     }
 
+    /// The history of the fees of a CFD, if there is an open or closed CFD with this ID.
+    pub async fn fee_ledger(&self, order_id: OrderId) -> Result<Option<Vec<FeeLedgerEntry>>> {
+        self.db.load_fee_ledger(order_id).await
+    }
+
+    /// All closed CFDs, ordered by the time they were closed.
+    pub async fn closed_positions(&self) -> Result<Vec<export::ClosedPosition>> {
+        export::closed_positions(&self.db).await
+    }
+
+    /// An encrypted backup of the database and the seed files in `data_dir`.
+    pub async fn backup(&self, data_dir: &Path, passphrase: &str) -> Result<Vec<u8>> {
+        backup::create(&self.db, data_dir, &seed::TAKER_SEED_FILES, passphrase).await
+    }
+
     #[instrument(skip(self), err)]
     pub async fn sync_wallet(&self) -> Result<()> {
         self.wallet_actor.send(wallet::Sync).await?;
@@ -483,6 +605,53 @@ where
     }
 }
 
+/// Propose to settle the CFD at our latest quote, refusing to settle with an outdated price.
+async fn propose_settlement_at_latest_quote(
+    executor: &command::Executor,
+    price_feed: &MessageChannel<
+        xtra_bitmex_price_feed::GetLatestQuotes,
+        xtra_bitmex_price_feed::LatestQuotes,
+    >,
+    cfd_actor: &Address<taker_cfd::Actor>,
+    order_id: OrderId,
+    quantity: Option<Contracts>,
+) -> Result<()> {
+    let contract_symbol = executor
+        .query(order_id, |cfd| Ok(cfd.contract_symbol()))
+        .await?;
+
+    let latest_quote = *price_feed
+        .send(xtra_bitmex_price_feed::GetLatestQuotes)
+        .await
+        .context("Price feed not available")?
+        .get(&into_price_feed_symbol(contract_symbol))
+        .context("No quote available")?;
+
+    let quote_timestamp = latest_quote
+        .timestamp
+        .format(&time::format_description::well_known::Rfc3339)
+        .context("Failed to format timestamp")?;
+
+    let threshold = QUOTE_INTERVAL_MINUTES.minutes() * 2;
+
+    if latest_quote.is_older_than(threshold) {
+        bail!(
+            "Latest quote is older than {} minutes. Refusing to settle with old price.",
+            threshold.whole_minutes()
+        )
+    }
+
+    cfd_actor
+        .send(taker_cfd::ProposeSettlement {
+            order_id,
+            bid: Price::new(latest_quote.bid())?,
+            ask: Price::new(latest_quote.ask())?,
+            quote_timestamp,
+            quantity,
+        })
+        .await?
+}
+
 /// A struct defining our environment
 ///
 /// We can run on all kinds of environment, hence this is just a wrapper around string.
@@ -517,11 +686,11 @@ pub fn version() -> String {
     VERSION.to_string()
 }
 
-fn into_price_feed_symbol(symbol: model::ContractSymbol) -> xtra_bitmex_price_feed::ContractSymbol {
-    match symbol {
-        model::ContractSymbol::BtcUsd => xtra_bitmex_price_feed::ContractSymbol::BtcUsd,
-        model::ContractSymbol::EthUsd => xtra_bitmex_price_feed::ContractSymbol::EthUsd,
-    }
+pub fn into_price_feed_symbol(
+    symbol: model::ContractSymbol,
+) -> xtra_bitmex_price_feed::ContractSymbol {
+    xtra_bitmex_price_feed::ContractSymbol::new(symbol.feed_instrument())
+        .expect("feed instruments of contract symbols to be valid instruments")
 }
 
 #[cfg(test)]
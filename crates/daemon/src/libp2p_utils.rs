@@ -1,24 +1,104 @@
 use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
+use libp2p_core::transport::OptionalTransport;
+use libp2p_core::transport::OrTransport;
+use libp2p_core::Transport;
+use libp2p_dns::ResolverConfig;
+use libp2p_dns::ResolverOpts;
+use libp2p_dns::TokioDnsConfig;
+use libp2p_tcp::TokioTcpConfig;
+use libp2p_tor::TorDialTransport;
 use std::net::IpAddr;
 use std::net::SocketAddr;
+use xtra_libp2p::multiaddress_ext::MultiaddrExt;
 
 use libp2p_core::Multiaddr;
 use libp2p_core::PeerId;
 
+/// The transport used for libp2p connections.
+///
+/// With a Tor SOCKS proxy every connection is dialed through Tor and we cannot listen. Otherwise
+/// plain TCP is used, resolving `/dns`, `/dns4` and `/dns6` addresses with the system resolver.
+pub fn transport(
+    tor_socks_proxy: Option<SocketAddr>,
+) -> OrTransport<
+    OptionalTransport<TorDialTransport>,
+    OptionalTransport<TokioDnsConfig<TokioTcpConfig>>,
+> {
+    match tor_socks_proxy {
+        Some(socks_proxy) => OptionalTransport::some(TorDialTransport::new(socks_proxy))
+            .or_transport(OptionalTransport::none()),
+        None => OptionalTransport::none().or_transport(OptionalTransport::some(dns_tcp())),
+    }
+}
+
+fn dns_tcp() -> TokioDnsConfig<TokioTcpConfig> {
+    TokioDnsConfig::system(TokioTcpConfig::new())
+        .or_else(|e| {
+            tracing::warn!("Failed to load system DNS config, falling back to default: {e:#}");
+            TokioDnsConfig::custom(
+                TokioTcpConfig::new(),
+                ResolverConfig::default(),
+                ResolverOpts::default(),
+            )
+        })
+        .expect("default DNS config to be valid")
+}
+
 /// Creates MultiAddr from SocketAddr and PeerId
 pub fn create_connect_tcp_multiaddr(
     socket_addr: &SocketAddr,
     peer_id: PeerId,
 ) -> Result<Multiaddr> {
-    let ip = socket_addr.ip();
-    let port = socket_addr.port();
-    ensure!(socket_addr.is_ipv4(), "only ipv4 is supported");
+    let listen_multiaddr = create_listen_tcp_multiaddr(&socket_addr.ip(), socket_addr.port())?;
 
-    format!("/ip4/{ip}/tcp/{port}/p2p/{peer_id}")
-        .parse::<Multiaddr>()
-        .with_context(|| "failed to construct multiaddr")
+    create_connect_multiaddr(&listen_multiaddr, &peer_id)
+}
+
+/// Creates MultiAddr to dial the other party at `address`
+///
+/// The address is either a multiaddr or given as `<host>:<port>`, where the host can be an IP
+/// address, a hostname or an onion address. Hostnames are resolved when dialing, onion addresses
+/// can only be dialed through Tor.
+pub fn create_connect_multiaddr_from_address(address: &str, peer_id: PeerId) -> Result<Multiaddr> {
+    if address.starts_with('/') {
+        let multiaddr = address
+            .parse::<Multiaddr>()
+            .with_context(|| format!("Invalid multiaddr {address}"))?;
+
+        return match multiaddr.clone().extract_peer_id() {
+            Some(address_peer_id) => {
+                ensure!(
+                    address_peer_id == peer_id,
+                    "Peer id in {address} does not match {peer_id}"
+                );
+                Ok(multiaddr)
+            }
+            None => create_connect_multiaddr(&multiaddr, &peer_id),
+        };
+    }
+
+    let multiaddr = match address.parse::<SocketAddr>() {
+        Ok(socket_addr) => create_listen_tcp_multiaddr(&socket_addr.ip(), socket_addr.port())?,
+        Err(_) => {
+            let (host, port) = address
+                .rsplit_once(':')
+                .with_context(|| format!("No port given in {address}"))?;
+            let port = port
+                .parse::<u16>()
+                .with_context(|| format!("Invalid port in {address}"))?;
+
+            match host.strip_suffix(".onion") {
+                Some(service_id) => format!("/onion3/{service_id}:{port}"),
+                None => format!("/dns/{host}/tcp/{port}"),
+            }
+            .parse::<Multiaddr>()
+            .with_context(|| format!("Invalid address {address}"))?
+        }
+    };
+
+    create_connect_multiaddr(&multiaddr, &peer_id)
 }
 
 /// Construct a Multiaddr that can dial in to other party given their MultiAddr
@@ -34,9 +114,12 @@ pub fn create_connect_multiaddr(
 
 /// Creates MultiAddr from SocketAddr
 pub fn create_listen_tcp_multiaddr(ip: &IpAddr, port: u16) -> Result<Multiaddr> {
-    ensure!(ip.is_ipv4(), "only ipv4 is supported");
+    let multiaddr = match ip {
+        IpAddr::V4(ip) => format!("/ip4/{ip}/tcp/{port}"),
+        IpAddr::V6(ip) => format!("/ip6/{ip}/tcp/{port}"),
+    };
 
-    format!("/ip4/{ip}/tcp/{port}")
+    multiaddr
         .parse::<Multiaddr>()
         .with_context(|| "failed to construct multiaddr")
 }
@@ -48,3 +131,54 @@ pub fn can_use_libp2p(cfd: &model::Cfd) -> bool {
     // can't dial in to them using libp2p.
     cfd.counterparty_peer_id().is_some()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER_ID: &str = "12D3KooWP3BN6bq9jPy8cP7Grj1QyUBfr7U6BeQFgMwfTTu12wuY";
+
+    #[test]
+    fn connect_multiaddr_from_address() {
+        for (address, expected) in [
+            ("127.0.0.1:10000", "/ip4/127.0.0.1/tcp/10000"),
+            ("[::1]:10000", "/ip6/::1/tcp/10000"),
+            (
+                "mainnet.itchysats.network:10001",
+                "/dns/mainnet.itchysats.network/tcp/10001",
+            ),
+            (
+                "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion:10000",
+                "/onion3/2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid:10000",
+            ),
+            (
+                "/dns6/mainnet.itchysats.network/tcp/10001",
+                "/dns6/mainnet.itchysats.network/tcp/10001",
+            ),
+        ] {
+            let multiaddr =
+                create_connect_multiaddr_from_address(address, PEER_ID.parse().unwrap()).unwrap();
+
+            assert_eq!(multiaddr.to_string(), format!("{expected}/p2p/{PEER_ID}"));
+        }
+    }
+
+    #[test]
+    fn reject_multiaddr_with_other_peer_id() {
+        let other_peer_id = "12D3KooWEsK2X8Tp24XtyWh7DM65VfwXtNH2cmfs2JsWmkmwKbV1";
+
+        let result = create_connect_multiaddr_from_address(
+            &format!("/ip4/127.0.0.1/tcp/10000/p2p/{other_peer_id}"),
+            PEER_ID.parse().unwrap(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ipv6_listen_multiaddr() {
+        let multiaddr = create_listen_tcp_multiaddr(&"::".parse().unwrap(), 10000).unwrap();
+
+        assert_eq!(multiaddr.to_string(), "/ip6/::/tcp/10000");
+    }
+}
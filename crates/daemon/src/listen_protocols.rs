@@ -1,6 +1,7 @@
 use crate::collab_settlement;
 use crate::command;
 use crate::identify;
+use crate::maker_request;
 use crate::oracle;
 use crate::order;
 use ping_pong::pong;
@@ -17,11 +18,16 @@ pub const MAKER_LISTEN_PROTOCOLS: MakerListenProtocols = MakerListenProtocols::n
     (
         collab_settlement::PROTOCOL,
         collab_settlement::deprecated::PROTOCOL,
+        collab_settlement::partial::PROTOCOL,
     ),
 );
 
-pub const TAKER_LISTEN_PROTOCOLS: TakerListenProtocols =
-    TakerListenProtocols::new(ping_pong::PROTOCOL, identify::PROTOCOL, offer::PROTOCOL);
+pub const TAKER_LISTEN_PROTOCOLS: TakerListenProtocols = TakerListenProtocols::new(
+    ping_pong::PROTOCOL,
+    identify::PROTOCOL,
+    offer::PROTOCOL,
+    maker_request::PROTOCOL,
+);
 
 pub const REQUIRED_MAKER_LISTEN_PROTOCOLS: RequiredMakerListenProtocols =
     RequiredMakerListenProtocols::new(
@@ -62,6 +68,7 @@ pub struct MakerListenProtocols {
     rollover_deprecated: &'static str,
     collaborative_settlement: &'static str,
     collaborative_settlement_deprecated: &'static str,
+    partial_collaborative_settlement: &'static str,
 }
 
 type RolloverAddress<R> =
@@ -72,17 +79,18 @@ type RolloverDeprecatedAddress<RD> = Address<
 >;
 
 impl MakerListenProtocols {
-    pub const NR_OF_SUPPORTED_PROTOCOLS: usize = 8;
+    pub const NR_OF_SUPPORTED_PROTOCOLS: usize = 9;
 
     pub const fn new(
         ping: &'static str,
         identify: &'static str,
         (order, order_deprecated): (&'static str, &'static str),
         (rollover, rollover_deprecated): (&'static str, &'static str),
-        (collaborative_settlement, collaborative_settlement_deprecated): (
-            &'static str,
-            &'static str,
-        ),
+        (
+            collaborative_settlement,
+            collaborative_settlement_deprecated,
+            partial_collaborative_settlement,
+        ): (&'static str, &'static str, &'static str),
     ) -> Self {
         Self {
             ping,
@@ -93,6 +101,7 @@ impl MakerListenProtocols {
             rollover_deprecated,
             collaborative_settlement,
             collaborative_settlement_deprecated,
+            partial_collaborative_settlement,
         }
     }
 
@@ -112,14 +121,29 @@ impl MakerListenProtocols {
             RolloverAddress<R>,
             RolloverDeprecatedAddress<RD>,
         ),
-        (collaborative_settlement_handler, collaborative_settlement_deprecated_handler): (
+        (
+            collaborative_settlement_handler,
+            collaborative_settlement_deprecated_handler,
+            partial_collaborative_settlement_handler,
+        ): (
             Address<collab_settlement::maker::Actor>,
             Address<collab_settlement::deprecated::maker::Actor>,
+            Address<collab_settlement::partial::maker::Actor>,
         ),
     ) -> [(&'static str, MessageChannel<NewInboundSubstream, ()>); Self::NR_OF_SUPPORTED_PROTOCOLS]
     where
-        R: rollover::protocol::GetRates + Send + Sync + Clone + 'static,
-        RD: rollover::deprecated::protocol::GetRates + Send + Sync + Clone + 'static,
+        R: rollover::protocol::GetRates
+            + rollover::protocol::ApproveRollover
+            + Send
+            + Sync
+            + Clone
+            + 'static,
+        RD: rollover::deprecated::protocol::GetRates
+            + rollover::deprecated::protocol::ApproveRollover
+            + Send
+            + Sync
+            + Clone
+            + 'static,
     {
         // We deconstruct to ensure that all protocols are being used
         let MakerListenProtocols {
@@ -131,6 +155,7 @@ impl MakerListenProtocols {
             rollover_deprecated,
             collaborative_settlement,
             collaborative_settlement_deprecated,
+            partial_collaborative_settlement,
         } = self;
 
         [
@@ -148,6 +173,10 @@ impl MakerListenProtocols {
                 collaborative_settlement_deprecated,
                 collaborative_settlement_deprecated_handler.into(),
             ),
+            (
+                partial_collaborative_settlement,
+                partial_collaborative_settlement_handler.into(),
+            ),
         ]
     }
 }
@@ -164,6 +193,7 @@ impl From<MakerListenProtocols> for HashSet<String> {
             rollover_deprecated,
             collaborative_settlement,
             collaborative_settlement_deprecated,
+            partial_collaborative_settlement,
         } = maker;
 
         HashSet::from([
@@ -175,6 +205,7 @@ impl From<MakerListenProtocols> for HashSet<String> {
             rollover_deprecated.to_string(),
             collaborative_settlement.to_string(),
             collaborative_settlement_deprecated.to_string(),
+            partial_collaborative_settlement.to_string(),
         ])
     }
 }
@@ -235,16 +266,23 @@ pub struct TakerListenProtocols {
     ping: &'static str,
     identify: &'static str,
     offer: &'static str,
+    maker_request: &'static str,
 }
 
 impl TakerListenProtocols {
-    const NR_OF_SUPPORTED_PROTOCOLS: usize = 3;
+    const NR_OF_SUPPORTED_PROTOCOLS: usize = 4;
 
-    pub const fn new(ping: &'static str, identify: &'static str, offer: &'static str) -> Self {
+    pub const fn new(
+        ping: &'static str,
+        identify: &'static str,
+        offer: &'static str,
+        maker_request: &'static str,
+    ) -> Self {
         Self {
             ping,
             identify,
             offer,
+            maker_request,
         }
     }
 
@@ -257,6 +295,7 @@ impl TakerListenProtocols {
         ping_handler: Address<pong::Actor>,
         identify_handler: Address<identify::listener::Actor>,
         offer_handler: Address<offer::taker::Actor>,
+        maker_request_handler: Address<maker_request::taker::Actor>,
     ) -> [(&'static str, MessageChannel<NewInboundSubstream, ()>); Self::NR_OF_SUPPORTED_PROTOCOLS]
     {
         // We deconstruct to ensure that all protocols are being used
@@ -264,12 +303,14 @@ impl TakerListenProtocols {
             ping,
             identify,
             offer,
+            maker_request,
         } = self;
 
         [
             (ping, ping_handler.into()),
             (identify, identify_handler.into()),
             (offer, offer_handler.into()),
+            (maker_request, maker_request_handler.into()),
         ]
     }
 }
@@ -281,9 +322,15 @@ impl From<TakerListenProtocols> for HashSet<String> {
             ping,
             identify,
             offer,
+            maker_request,
         } = protocols;
 
-        HashSet::from_iter([ping.to_string(), identify.to_string(), offer.to_string()])
+        HashSet::from_iter([
+            ping.to_string(),
+            identify.to_string(),
+            offer.to_string(),
+            maker_request.to_string(),
+        ])
     }
 }
 
@@ -0,0 +1,24 @@
+use parse_display::Display;
+use serde::Deserialize;
+use serde::Serialize;
+
+mod protocol;
+pub mod taker;
+
+pub use protocol::dialer;
+pub use protocol::Decision;
+
+/// Protocol through which the maker asks a taker to settle or roll over a CFD.
+///
+/// All protocols after contract setup are initiated by the taker, so the taker answers a request
+/// by starting the usual taker-initiated protocol.
+pub const PROTOCOL: &str = "/itchysats/maker-request/1.0.0";
+
+/// What the maker asks the taker to do with a CFD.
+#[derive(Debug, Clone, Copy, Display, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[display(style = "lowercase")]
+pub enum Request {
+    Settlement,
+    Rollover,
+}
@@ -0,0 +1,86 @@
+use crate::maker_request::Request;
+use crate::maker_request::PROTOCOL;
+use anyhow::Context;
+use anyhow::Result;
+use asynchronous_codec::Framed;
+use asynchronous_codec::JsonCodec;
+use futures::SinkExt;
+use futures::StreamExt;
+use libp2p_core::PeerId;
+use model::OrderId;
+use serde::Deserialize;
+use serde::Serialize;
+use std::time::Duration;
+use tokio_extras::FutureExt;
+use xtra::Address;
+use xtra_libp2p::Endpoint;
+use xtra_libp2p::OpenSubstream;
+use xtra_libp2p::Substream;
+
+/// How long the maker waits for the taker to decide on a request.
+///
+/// If the taker accepts automatically it starts the requested protocol before responding.
+const DECISION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Propose {
+    pub order_id: OrderId,
+    pub request: Request,
+}
+
+/// The taker's answer to a maker request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    /// The taker started the requested protocol.
+    Accepted,
+    /// The request awaits the approval of the taker.
+    ///
+    /// Pending requests are only kept in memory, a restart of the taker drops them without
+    /// notice. The maker has to ask again if the CFD is still open by then.
+    Pending,
+    Rejected {
+        reason: String,
+    },
+}
+
+pub(crate) type ListenerConnection = Framed<Substream, JsonCodec<Decision, Propose>>;
+
+/// Ask the taker `counterparty` to settle or roll over the CFD `order_id`.
+#[tracing::instrument(skip(endpoint))]
+pub async fn dialer(
+    endpoint: Address<Endpoint>,
+    order_id: OrderId,
+    counterparty: PeerId,
+    request: Request,
+) -> Result<Decision> {
+    let substream = endpoint
+        .send(OpenSubstream::single_protocol(counterparty, PROTOCOL))
+        .await
+        .context("Endpoint is disconnected")?
+        .context("No connection to peer")?
+        .await
+        .context("Failed to open substream")?;
+    let mut framed = Framed::new(substream, JsonCodec::<Propose, Decision>::new());
+
+    framed
+        .send(Propose { order_id, request })
+        .await
+        .context("Failed to send request")?;
+
+    let decision = framed
+        .next()
+        .timeout(DECISION_TIMEOUT, || {
+            tracing::debug_span!("Receive decision on maker request")
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Taker did not decide on request within {} seconds",
+                DECISION_TIMEOUT.as_secs()
+            )
+        })?
+        .context("End of stream while receiving decision")?
+        .context("Failed to decode decision")?;
+
+    Ok(decision)
+}
@@ -0,0 +1,264 @@
+use crate::auto_rollover;
+use crate::command;
+use crate::maker_request::protocol::Decision;
+use crate::maker_request::protocol::ListenerConnection;
+use crate::maker_request::protocol::Propose;
+use crate::maker_request::Request;
+use crate::projection;
+use crate::propose_settlement_at_latest_quote;
+use crate::taker_cfd;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use asynchronous_codec::Framed;
+use asynchronous_codec::JsonCodec;
+use futures::SinkExt;
+use futures::StreamExt;
+use libp2p_core::PeerId;
+use model::OrderId;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+use xtra::prelude::MessageChannel;
+use xtra::Address;
+use xtra_bitmex_price_feed::GetLatestQuotes;
+use xtra_bitmex_price_feed::LatestQuotes;
+use xtra_libp2p::NewInboundSubstream;
+use xtra_productivity::xtra_productivity;
+
+/// Permanent actor to handle incoming substreams for the `/itchysats/maker-request/1.0.0`
+/// protocol.
+///
+/// Requests are either carried out right away or kept until the user accepts or rejects them,
+/// depending on `auto_accept`.
+pub struct Actor {
+    executor: command::Executor,
+    cfd_actor: Address<taker_cfd::Actor>,
+    price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+    auto_rollover: Address<auto_rollover::Actor>,
+    projection: Address<projection::Actor>,
+    auto_accept: bool,
+    /// Requests awaiting approval by the user, dropped on restart.
+    pending: HashMap<OrderId, Request>,
+}
+
+impl Actor {
+    pub fn new(
+        executor: command::Executor,
+        cfd_actor: Address<taker_cfd::Actor>,
+        price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+        auto_rollover: Address<auto_rollover::Actor>,
+        projection: Address<projection::Actor>,
+        auto_accept: bool,
+    ) -> Self {
+        Self {
+            executor,
+            cfd_actor,
+            price_feed,
+            auto_rollover,
+            projection,
+            auto_accept,
+            pending: HashMap::default(),
+        }
+    }
+
+    /// Start the taker-initiated protocol the maker asked for.
+    async fn carry_out(&self, order_id: OrderId, request: Request) -> Result<()> {
+        match request {
+            Request::Settlement => {
+                propose_settlement_at_latest_quote(
+                    &self.executor,
+                    &self.price_feed,
+                    &self.cfd_actor,
+                    order_id,
+                    None,
+                )
+                .await
+            }
+            Request::Rollover => {
+                let (maker_peer_id, (from_commit_txid, from_settlement_event_id)) = self
+                    .executor
+                    .query(order_id, |cfd| {
+                        Ok((
+                            cfd.counterparty_peer_id(),
+                            cfd.can_rollover_taker(OffsetDateTime::now_utc())?,
+                        ))
+                    })
+                    .await?;
+
+                self.auto_rollover
+                    .send(auto_rollover::Rollover {
+                        order_id,
+                        maker_peer_id,
+                        from_commit_txid,
+                        from_settlement_event_id,
+                    })
+                    .await
+                    .context("Auto-rollover actor is disconnected")
+            }
+        }
+    }
+
+    async fn update_projection(&self) {
+        if let Err(e) = self
+            .projection
+            .send(projection::Update(self.pending.clone()))
+            .await
+        {
+            tracing::warn!("Failed to update projection with maker requests: {e:#}");
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: NewInboundSubstream, ctx: &mut xtra::Context<Self>) {
+        let NewInboundSubstream { peer_id, stream } = msg;
+        let address = ctx.address().expect("we are alive");
+
+        tokio_extras::spawn_fallible(
+            &address.clone(),
+            async move {
+                let mut framed: ListenerConnection = Framed::new(stream, JsonCodec::new());
+
+                let Propose { order_id, request } = framed
+                    .next()
+                    .await
+                    .context("End of stream while receiving request")?
+                    .context("Failed to decode request")?;
+
+                let decision = address
+                    .send(RequestReceived {
+                        order_id,
+                        request,
+                        peer_id,
+                    })
+                    .await?;
+
+                framed
+                    .send(decision)
+                    .await
+                    .context("Failed to send decision")?;
+
+                anyhow::Ok(())
+            },
+            move |e| async move {
+                tracing::warn!(%peer_id, "Failed to handle incoming maker request: {e:#}")
+            },
+        );
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: RequestReceived) -> Decision {
+        let RequestReceived {
+            order_id,
+            request,
+            peer_id,
+        } = msg;
+
+        if let Err(e) = self
+            .executor
+            .query(order_id, |cfd| {
+                cfd.verify_counterparty_peer_id(&peer_id.into())
+            })
+            .await
+        {
+            tracing::warn!(%order_id, %peer_id, "Rejecting maker request: {e:#}");
+
+            return Decision::Rejected {
+                reason: "Unknown CFD".to_string(),
+            };
+        }
+
+        if !self.auto_accept {
+            tracing::info!(%order_id, %request, "Maker requested {request}, awaiting approval");
+
+            self.pending.insert(order_id, request);
+            self.update_projection().await;
+
+            return Decision::Pending;
+        }
+
+        tracing::info!(%order_id, %request, "Maker requested {request}, accepting automatically");
+
+        match self.carry_out(order_id, request).await {
+            Ok(()) => Decision::Accepted,
+            Err(e) => {
+                tracing::warn!(%order_id, %request, "Failed to carry out maker request: {e:#}");
+
+                Decision::Rejected {
+                    reason: format!("{e:#}"),
+                }
+            }
+        }
+    }
+
+    async fn handle(&mut self, msg: Accept) -> Result<()> {
+        let Accept { order_id } = msg;
+
+        // The request is settled either way, if it cannot be carried out the user has to act on
+        // the CFD directly
+        let request = self
+            .pending
+            .remove(&order_id)
+            .with_context(|| format!("No pending maker request for CFD {order_id}"))?;
+        self.update_projection().await;
+
+        self.carry_out(order_id, request).await
+    }
+
+    async fn handle(&mut self, msg: Reject) -> Result<()> {
+        let Reject { order_id } = msg;
+
+        self.pending
+            .remove(&order_id)
+            .with_context(|| format!("No pending maker request for CFD {order_id}"))?;
+        self.update_projection().await;
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, msg: CfdChanged) {
+        let CfdChanged(order_id) = msg;
+
+        if let Some(request) = self.pending.remove(&order_id) {
+            tracing::info!(%order_id, %request, "CFD changed, dropping pending maker request");
+
+            self.update_projection().await;
+        }
+    }
+}
+
+/// A maker request was received, to be carried out or kept for approval.
+struct RequestReceived {
+    order_id: OrderId,
+    request: Request,
+    peer_id: PeerId,
+}
+
+/// The user approved the pending maker request on a CFD.
+#[derive(Clone, Copy)]
+pub struct Accept {
+    pub order_id: OrderId,
+}
+
+/// The user declined the pending maker request on a CFD.
+#[derive(Clone, Copy)]
+pub struct Reject {
+    pub order_id: OrderId,
+}
+
+/// A new event was recorded on the CFD.
+///
+/// Any change to the CFD supersedes a pending request on it: the request was carried out by other
+/// means or the CFD moved on, e.g. it was settled or committed.
+#[derive(Clone, Copy)]
+pub struct CfdChanged(pub OrderId);
@@ -1,7 +1,6 @@
 use crate::bitcoin::consensus::encode::serialize_hex;
 use crate::bitcoin::Transaction;
 use crate::command;
-use crate::wallet::RpcErrorCode;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -9,10 +8,9 @@ use bdk::bitcoin::PublicKey;
 use bdk::bitcoin::Script;
 use bdk::bitcoin::Txid;
 use bdk::descriptor::Descriptor;
-use bdk::electrum_client;
-use bdk::electrum_client::ElectrumApi;
-use bdk::electrum_client::GetHistoryRes;
 use bdk::miniscript::DescriptorTrait;
+use btsieve::Broadcast;
+use btsieve::ChainBackend;
 use btsieve::ScriptStatus;
 use btsieve::State;
 use btsieve::TxStatus;
@@ -22,7 +20,6 @@ use model::Dlc;
 use model::EventKind;
 use model::OrderId;
 use model::CET_TIMELOCK;
-use serde_json::Value;
 use sqlite_db;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -39,15 +36,9 @@ const CLOSE_FINALITY_CONFIRMATIONS: u32 = 3;
 const COMMIT_FINALITY_CONFIRMATIONS: u32 = 1;
 const CET_FINALITY_CONFIRMATIONS: u32 = 3;
 const REFUND_FINALITY_CONFIRMATIONS: u32 = 3;
+const PUNISH_FINALITY_CONFIRMATIONS: u32 = 3;
 const BATCH_SIZE: usize = 25;
 
-/// Electrum client timeout in seconds
-///
-/// This timeout is used when establishing the connection and for all requests of the electrum
-/// client. We explicitly set the timeout because otherwise the underlying TCP connection timeout is
-/// used which is hard to be predicted.
-const ELECTRUM_CLIENT_TIMEOUT_SECS: u8 = 120;
-
 /// Timeout for each response from script_get_history
 ///
 /// Requests are batched and all batches handled in parallel. We expect the responses to arrive
@@ -70,11 +61,22 @@ pub struct MonitorCollaborativeSettlement {
     pub tx: (Txid, Script),
 }
 
+/// Watch for the confirmation of a partial settlement transaction we signed.
+pub struct MonitorPartialSettlement {
+    pub order_id: OrderId,
+    pub tx: (Txid, Script),
+}
+
 pub struct MonitorCetFinality {
     pub order_id: OrderId,
     pub cet: Transaction,
 }
 
+pub struct MonitorPunishFinality {
+    pub order_id: OrderId,
+    pub punish_tx: Transaction,
+}
+
 pub struct TryBroadcastTransaction {
     pub tx: Transaction,
     pub kind: TransactionKind,
@@ -87,6 +89,9 @@ pub enum TransactionKind {
     Refund,
     CollaborativeClose,
     Cet,
+    Punish,
+    Cpfp,
+    PartialSettlement,
 }
 
 impl TransactionKind {
@@ -97,29 +102,13 @@ impl TransactionKind {
             TransactionKind::Refund => "refund",
             TransactionKind::CollaborativeClose => "collaborative-close",
             TransactionKind::Cet => "contract-execution",
+            TransactionKind::Punish => "punish",
+            TransactionKind::Cpfp => "cpfp",
+            TransactionKind::PartialSettlement => "partial-settlement",
         }
     }
 }
 
-fn parse_rpc_protocol_error(error_value: &Value) -> Result<RpcError> {
-    let json = error_value
-        .as_str()
-        .context("Not a string")?
-        .split_terminator("RPC error: ")
-        .nth(1)
-        .context("Unknown error code format")?;
-
-    let error = serde_json::from_str::<RpcError>(json).context("Error has unexpected format")?;
-
-    Ok(error)
-}
-
-#[derive(serde::Deserialize)]
-struct RpcError {
-    code: i64,
-    message: String,
-}
-
 #[derive(Clone, Copy)]
 pub struct Sync;
 
@@ -127,7 +116,7 @@ pub struct Sync;
 //  -> Might as well just send out all events independent of sending to the cfd actor.
 pub struct Actor {
     executor: command::Executor,
-    client: Arc<bdk::electrum_client::Client>,
+    chain: Arc<dyn ChainBackend>,
     state: State<Event>,
     db: sqlite_db::Connection,
 }
@@ -143,6 +132,9 @@ struct Cfd {
     collaborative_settlement: Option<(Txid, Script)>,
     monitor_collaborative_settlement_finality: bool,
 
+    partial_settlement: Option<(Txid, Script)>,
+    monitor_partial_settlement_finality: bool,
+
     commit: Option<Commit>,
     monitor_commit_finality: bool,
     monitor_cet_timelock: bool,
@@ -156,10 +148,14 @@ struct Cfd {
 
     monitor_revoked_commit_transactions: Vec<RevokedCommit>,
 
+    punish: Option<(Txid, Script)>,
+    monitor_punish_finality: bool,
+
     // Rebroadcast transactions upon startup
     broadcast_lock: Option<Transaction>,
     broadcast_cet: Option<Transaction>,
     broadcast_commit: Option<Transaction>,
+    broadcast_punish: Option<Transaction>,
 
     version: u32,
 }
@@ -174,6 +170,8 @@ impl sqlite_db::CfdAggregate for Cfd {
             monitor_lock_finality: false,
             collaborative_settlement: None,
             monitor_collaborative_settlement_finality: false,
+            partial_settlement: None,
+            monitor_partial_settlement_finality: false,
             commit: None,
             monitor_commit_finality: false,
             monitor_cet_timelock: false,
@@ -183,9 +181,12 @@ impl sqlite_db::CfdAggregate for Cfd {
             refund: None,
             monitor_refund_finality: false,
             monitor_revoked_commit_transactions: Vec::new(),
+            punish: None,
+            monitor_punish_finality: false,
             broadcast_lock: None,
             broadcast_cet: None,
             broadcast_commit: None,
+            broadcast_punish: None,
             version: 0,
         }
     }
@@ -254,6 +255,41 @@ impl Cfd {
                     ..self
                 }
             }
+            // The counterparty can publish the partial settlement transaction from now on, even if
+            // the protocol fails
+            PartialSettlementSigned { dlc, .. } => Self {
+                partial_settlement: Some((dlc.lock.0.txid(), dlc.lock.1.script_pubkey())),
+                monitor_partial_settlement_finality: true,
+                ..self
+            },
+            // The partial settlement transaction locks the remaining contracts. Until it is
+            // confirmed the revoked commit transactions can still be published, including the
+            // commit transaction of the entire position.
+            PartialSettlementCompleted { dlc: Some(dlc), .. } => {
+                let TransactionsAfterContractSetup {
+                    lock,
+                    commit,
+                    refund,
+                } = TransactionsAfterContractSetup::new(&dlc);
+                let TransactionsAfterRollover {
+                    revoked_commits, ..
+                } = TransactionsAfterRollover::new(&dlc);
+
+                Self {
+                    lock: Some(lock),
+                    monitor_lock_finality: true,
+                    commit: Some(commit),
+                    monitor_commit_finality: true,
+                    monitor_cet_timelock: true,
+                    monitor_refund_timelock: true,
+                    refund: Some(refund),
+                    monitor_refund_finality: true,
+                    monitor_revoked_commit_transactions: revoked_commits,
+                    monitor_partial_settlement_finality: false,
+                    broadcast_lock: Some(dlc.lock.0),
+                    ..self
+                }
+            }
             CollaborativeSettlementCompleted {
                 spend_tx, script, ..
             } => {
@@ -280,16 +316,36 @@ impl Cfd {
                 ..self
             },
             // final states, don't monitor or re-broadcast anything
-            CetConfirmed | RefundConfirmed | CollaborativeSettlementConfirmed => Self {
-                monitor_lock_finality: false,
+            CetConfirmed | RefundConfirmed | CollaborativeSettlementConfirmed | PunishConfirmed => {
+                Self {
+                    monitor_lock_finality: false,
+                    monitor_commit_finality: false,
+                    monitor_cet_timelock: false,
+                    monitor_refund_timelock: false,
+                    monitor_refund_finality: false,
+                    monitor_revoked_commit_transactions: Vec::new(),
+                    monitor_collaborative_settlement_finality: false,
+                    monitor_partial_settlement_finality: false,
+                    monitor_cet_finality: false,
+                    monitor_punish_finality: false,
+                    broadcast_lock: None,
+                    broadcast_cet: None,
+                    broadcast_commit: None,
+                    broadcast_punish: None,
+                    ..self
+                }
+            }
+            // the counterparty published a revoked commit transaction, the only transaction that
+            // matters from now on is our punish transaction
+            RevokedCommitPublished { punish_tx } => Self {
+                punish: first_output_txid_and_script(&punish_tx),
+                monitor_punish_finality: true,
+                broadcast_punish: Some(punish_tx),
                 monitor_commit_finality: false,
                 monitor_cet_timelock: false,
                 monitor_refund_timelock: false,
                 monitor_refund_finality: false,
                 monitor_revoked_commit_transactions: Vec::new(),
-                monitor_collaborative_settlement_finality: false,
-                monitor_cet_finality: false,
-                broadcast_lock: None,
                 broadcast_cet: None,
                 broadcast_commit: None,
                 ..self
@@ -320,14 +376,16 @@ impl Cfd {
             | CollaborativeSettlementRejected
             | CollaborativeSettlementFailed
             | CollaborativeSettlementProposalAccepted
+            | PartialSettlementCompleted { dlc: None, .. }
+            | PartialSettlementStarted { .. }
+            | PartialSettlementRejected
+            | PartialSettlementFailed
             | ContractSetupStarted
             | ContractSetupFailed
             | OfferRejected
-            | RolloverRejected => self,
-            RevokeConfirmed => {
-                // TODO: Implement revoked logic
-                self
-            }
+            | RolloverRejected
+            | RevokeConfirmed
+            | FeeBumped { .. } => self,
         }
     }
 }
@@ -342,30 +400,31 @@ fn cet_txid_and_script(cet: Transaction) -> Option<(Txid, Script)> {
     }
 }
 
+fn first_output_txid_and_script(tx: &Transaction) -> Option<(Txid, Script)> {
+    match tx.output.first() {
+        Some(output) => Some((tx.txid(), output.script_pubkey.clone())),
+        None => {
+            let txid = tx.txid();
+            tracing::error!(
+                %txid,
+                "Failed to monitor transaction using script pubkey because it has no outputs"
+            );
+            None
+        }
+    }
+}
+
 impl Actor {
     pub fn new(
         db: sqlite_db::Connection,
-        electrum_rpc_url: String,
+        chain: Arc<dyn ChainBackend>,
         executor: command::Executor,
     ) -> Result<Self> {
-        let client = bdk::electrum_client::Client::from_config(
-            &electrum_rpc_url,
-            electrum_client::ConfigBuilder::new()
-                .timeout(Some(ELECTRUM_CLIENT_TIMEOUT_SECS))?
-                .build(),
-        )
-        .context("Failed to initialize Electrum RPC client")?;
-
         // Initially fetch the latest block for storing the height.
-        // We do not act on this subscription after this call.
-        let latest_block = client
-            .block_headers_subscribe()
-            .context("Failed to subscribe to header notifications")?
-            .height
-            .into();
+        let latest_block = chain.tip_height()?;
 
         Ok(Self {
-            client: Arc::new(client),
+            chain,
             executor,
             state: State::new(latest_block),
             db,
@@ -401,6 +460,19 @@ impl Actor {
         );
     }
 
+    fn monitor_partial_settlement_finality(
+        &mut self,
+        order_id: OrderId,
+        partial_settlement_params: (Txid, Script),
+    ) {
+        self.state.monitor(
+            partial_settlement_params.0,
+            partial_settlement_params.1,
+            ScriptStatus::with_confirmations(LOCK_FINALITY_CONFIRMATIONS),
+            Event::PartialSettlementFinality(order_id),
+        );
+    }
+
     fn monitor_cet_finality(&mut self, order_id: OrderId, close_params: (Txid, Script)) {
         self.state.monitor(
             close_params.0,
@@ -410,6 +482,15 @@ impl Actor {
         );
     }
 
+    fn monitor_punish_finality(&mut self, order_id: OrderId, punish_params: (Txid, Script)) {
+        self.state.monitor(
+            punish_params.0,
+            punish_params.1,
+            ScriptStatus::with_confirmations(PUNISH_FINALITY_CONFIRMATIONS),
+            Event::PunishFinality(order_id),
+        );
+    }
+
     fn monitor_commit_cet_timelock(
         &mut self,
         order_id: OrderId,
@@ -468,7 +549,7 @@ impl Actor {
                 txid,
                 script_pubkey,
                 ScriptStatus::InMempool,
-                Event::RevokedTransactionFound(order_id),
+                Event::RevokedTransactionFound(order_id, txid),
             )
         }
     }
@@ -478,45 +559,19 @@ impl Actor {
         let start_time = Instant::now();
 
         // Fetch the latest block for storing the height.
-        // We do not act on this subscription after this call, as we cannot rely on
-        // subscription push notifications because eventually the Electrum server will
-        // close the connection and subscriptions are not automatically renewed
-        // upon renewing the connection.
-        let latest_block_height = self
-            .client
-            .block_headers_subscribe()
-            .context("Failed to subscribe to header notifications")?
-            .height
-            .into();
+        let latest_block_height = self.chain.tip_height()?;
 
         let num_transactions = self.state.num_monitoring();
 
         tracing::debug!("Sync Started: Updating status of {num_transactions} transactions");
 
-        let scripts = self
-            .state
-            .monitoring_scripts()
-            .cloned()
-            .collect::<Vec<Script>>();
+        let scripts = self.state.monitoring_scripts().into_iter().collect();
 
-        let histories = batch_script_get_history(self.client.clone(), scripts).await;
+        let histories = batch_script_get_history(self.chain.clone(), scripts).await;
 
         tracing::trace!("Sync Update: Fetching histories finished, updating state");
 
-        let mut ready_events = self.state.update(
-            latest_block_height,
-            histories
-                .into_iter()
-                .map(|list| {
-                    list.into_iter()
-                        .map(|response| TxStatus {
-                            height: response.height,
-                            tx_hash: response.tx_hash,
-                        })
-                        .collect()
-                })
-                .collect(),
-        );
+        let mut ready_events = self.state.update(latest_block_height, histories);
 
         tracing::trace!("Sync Update: Processing events: {ready_events:?}");
 
@@ -536,6 +591,10 @@ impl Actor {
                     })
                     .await
                 }
+                Event::PartialSettlementFinality(id) => {
+                    self.invoke_cfd_command(id, |cfd| Ok(cfd.handle_partial_settlement_confirmed()))
+                        .await
+                }
                 Event::CetTimelockExpired(id) => {
                     self.invoke_cfd_command(id, |cfd| cfd.handle_cet_timelock_expired().map(Some))
                         .await
@@ -548,8 +607,34 @@ impl Actor {
                     self.invoke_cfd_command(id, |cfd| Ok(Some(cfd.handle_refund_confirmed())))
                         .await
                 }
-                Event::RevokedTransactionFound(id) => {
-                    self.invoke_cfd_command(id, |cfd| Ok(Some(cfd.handle_revoke_confirmed())))
+                Event::RevokedTransactionFound(id, txid) => {
+                    let revoked_commit_tx = match self.chain.transaction(&txid) {
+                        Ok(Some(tx)) => tx,
+                        Ok(None) => {
+                            tracing::warn!(
+                                order_id = %id,
+                                %txid,
+                                "Revoked commit transaction not found"
+                            );
+                            continue;
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                order_id = %id,
+                                %txid,
+                                "Failed to fetch revoked commit transaction: {e:#}"
+                            );
+                            continue;
+                        }
+                    };
+
+                    self.invoke_cfd_command(id, |cfd| {
+                        cfd.handle_revoked_commit_published(&revoked_commit_tx)
+                    })
+                    .await
+                }
+                Event::PunishFinality(id) => {
+                    self.invoke_cfd_command(id, |cfd| Ok(Some(cfd.handle_punish_confirmed())))
                         .await
                 }
                 Event::RefundTimelockExpired(id) => {
@@ -585,11 +670,13 @@ enum Event {
     LockFinality(OrderId),
     CommitFinality(OrderId),
     CloseFinality(OrderId),
+    PartialSettlementFinality(OrderId),
     CetTimelockExpired(OrderId),
     CetFinality(OrderId),
     RefundTimelockExpired(OrderId),
     RefundFinality(OrderId),
-    RevokedTransactionFound(OrderId),
+    RevokedTransactionFound(OrderId, Txid),
+    PunishFinality(OrderId),
 }
 
 #[async_trait]
@@ -621,6 +708,8 @@ impl xtra::Actor for Actor {
                             monitor_lock_finality,
                             collaborative_settlement,
                             monitor_collaborative_settlement_finality,
+                            partial_settlement,
+                            monitor_partial_settlement_finality,
                             commit,
                             monitor_commit_finality,
                             monitor_cet_timelock,
@@ -630,9 +719,12 @@ impl xtra::Actor for Actor {
                             refund,
                             monitor_refund_finality,
                             monitor_revoked_commit_transactions,
+                            punish,
+                            monitor_punish_finality,
                             broadcast_lock,
                             broadcast_cet,
                             broadcast_commit,
+                            broadcast_punish,
                             ..
                         } = match cfd {
                             Ok(cfd) => cfd,
@@ -669,6 +761,20 @@ impl xtra::Actor for Actor {
                             }
                         }
 
+                        if let Some(tx) = broadcast_punish {
+                            let span = tracing::debug_span!("Broadcast punish TX", order_id = %id);
+                            if let Err(e) = this
+                                .send(TryBroadcastTransaction {
+                                    tx,
+                                    kind: TransactionKind::Punish,
+                                })
+                                .instrument(span)
+                                .await?
+                            {
+                                tracing::warn!("{e:#}")
+                            }
+                        }
+
                         if let Some(tx) = broadcast_lock {
                             let span = tracing::debug_span!("Broadcast lock TX", order_id = %id);
                             if let Err(e) = this
@@ -689,6 +795,8 @@ impl xtra::Actor for Actor {
                             monitor_lock_finality,
                             collaborative_settlement,
                             monitor_collaborative_settlement_finality,
+                            partial_settlement,
+                            monitor_partial_settlement_finality,
                             commit,
                             monitor_commit_finality,
                             monitor_cet_timelock,
@@ -698,6 +806,8 @@ impl xtra::Actor for Actor {
                             refund,
                             monitor_refund_finality,
                             monitor_revoked_commit_transactions,
+                            punish,
+                            monitor_punish_finality,
                         })
                         .await?;
                     }
@@ -762,39 +872,26 @@ impl Actor {
         );
     }
 
+    fn handle_partial_settlement(&mut self, partial_settlement: MonitorPartialSettlement) {
+        self.monitor_partial_settlement_finality(
+            partial_settlement.order_id,
+            partial_settlement.tx,
+        );
+    }
+
     async fn handle_try_broadcast_transaction(&self, msg: TryBroadcastTransaction) -> Result<()> {
         let TryBroadcastTransaction { tx, kind } = msg;
 
-        let result = self.client.transaction_broadcast(&tx);
-
-        if let Err(electrum_client::Error::Protocol(ref value)) = result {
-            let rpc_error = parse_rpc_protocol_error(value)
-                .with_context(|| format!("Failed to parse electrum error response '{value:?}'"))?;
-
-            if rpc_error.code == i64::from(RpcErrorCode::RpcVerifyAlreadyInChain) {
-                let txid = tx.txid();
-                tracing::trace!(
-                    %txid, kind = %kind.name(), "Attempted to broadcast transaction that was already on-chain",
-                );
+        let result = self.chain.broadcast(&tx);
+        let txid = tx.txid();
 
-                return Ok(());
-            }
+        if let Ok(Broadcast::AlreadyInChain) = result {
+            tracing::trace!(
+                %txid, kind = %kind.name(), "Attempted to broadcast transaction that was already on-chain",
+            );
 
-            // We do this check because electrum sometimes returns an RpcVerifyError when it should
-            // be returning a RpcVerifyAlreadyInChain error,
-            if rpc_error.code == i64::from(RpcErrorCode::RpcVerifyError)
-                && rpc_error.message == "bad-txns-inputs-missingorspent"
-            {
-                if let Ok(tx) = self.client.transaction_get(&tx.txid()) {
-                    let txid = tx.txid();
-                    tracing::trace!(
-                        %txid, kind = %kind.name(), "Attempted to broadcast transaction that was already on-chain",
-                    );
-                    return Ok(());
-                }
-            }
+            return Ok(());
         }
-        let txid = tx.txid();
 
         result.with_context(|| {
             let tx_hex = serialize_hex(&tx);
@@ -818,6 +915,8 @@ impl Actor {
             monitor_lock_finality,
             collaborative_settlement,
             monitor_collaborative_settlement_finality,
+            partial_settlement,
+            monitor_partial_settlement_finality,
             commit,
             monitor_commit_finality,
             monitor_cet_timelock,
@@ -827,6 +926,8 @@ impl Actor {
             refund,
             monitor_refund_finality,
             monitor_revoked_commit_transactions,
+            punish,
+            monitor_punish_finality,
         } = msg;
 
         if let (Some(lock), true) = (lock, monitor_lock_finality) {
@@ -860,9 +961,17 @@ impl Actor {
             self.monitor_close_finality(id, params);
         }
 
+        if let (Some(params), true) = (partial_settlement, monitor_partial_settlement_finality) {
+            self.monitor_partial_settlement_finality(id, params);
+        }
+
         if let (Some(params), true) = (cet, monitor_cet_finality) {
             self.monitor_cet_finality(id, params);
         }
+
+        if let (Some(params), true) = (punish, monitor_punish_finality) {
+            self.monitor_punish_finality(id, params);
+        }
     }
 
     async fn handle_monitor_cet_finality(&mut self, msg: MonitorCetFinality) -> Result<()> {
@@ -879,6 +988,21 @@ impl Actor {
 
         Ok(())
     }
+
+    async fn handle_monitor_punish_finality(&mut self, msg: MonitorPunishFinality) -> Result<()> {
+        let txid = msg.punish_tx.txid();
+        let script = msg
+            .punish_tx
+            .output
+            .first()
+            .context("Failed to monitor punish TX using script pubkey because it has no outputs")?
+            .script_pubkey
+            .clone();
+
+        self.monitor_punish_finality(msg.order_id, (txid, script));
+
+        Ok(())
+    }
 }
 
 impl MonitorAfterContractSetup {
@@ -1015,6 +1139,9 @@ struct ReinitMonitoring {
     collaborative_settlement: Option<(Txid, Script)>,
     monitor_collaborative_settlement_finality: bool,
 
+    partial_settlement: Option<(Txid, Script)>,
+    monitor_partial_settlement_finality: bool,
+
     commit: Option<Commit>,
     monitor_commit_finality: bool,
     monitor_cet_timelock: bool,
@@ -1027,6 +1154,9 @@ struct ReinitMonitoring {
     monitor_refund_finality: bool,
 
     monitor_revoked_commit_transactions: Vec<RevokedCommit>,
+
+    punish: Option<(Txid, Script)>,
+    monitor_punish_finality: bool,
 }
 
 #[xtra_productivity]
@@ -1051,9 +1181,9 @@ static TRANSACTION_BROADCAST_COUNTER: conquer_once::Lazy<prometheus::IntCounterV
     });
 
 async fn batch_script_get_history(
-    client: Arc<electrum_client::Client>,
-    scripts: Vec<Script>,
-) -> Vec<Vec<GetHistoryRes>> {
+    chain: Arc<dyn ChainBackend>,
+    scripts: Vec<(Script, Vec<Txid>)>,
+) -> Vec<Vec<TxStatus>> {
     let (tx_script_updates, mut rx_script_updates) = tokio::sync::mpsc::channel(BATCH_SIZE * 4);
 
     let scripts_len = scripts.len();
@@ -1062,12 +1192,12 @@ async fn batch_script_get_history(
     // It's important to move here so the sender gets dropped and the receiver finishes correctly
     batches.for_each(move |batch| {
         let tx_script_updates = tx_script_updates.clone();
-        let client = client.clone();
+        let chain = chain.clone();
 
         tokio::task::spawn_blocking({
             move || {
-                for script in batch {
-                    match client.script_get_history(&script) {
+                for (script, txids) in batch {
+                    match chain.script_history(&script, &txids) {
                         Ok(script_history_response) => {
                             // We use blocking_send to stay within a sync context here
                             // One should not use async code in a spawn_blocking block
@@ -1134,11 +1264,10 @@ static SYNC_DURATION_HISTOGRAM: conquer_once::Lazy<prometheus::Histogram> =
 
 #[cfg(test)]
 mod test {
+    use crate::chain::Electrum;
     use crate::monitor::batch_script_get_history;
-    use crate::monitor::ELECTRUM_CLIENT_TIMEOUT_SECS;
     use bdk::bitcoin;
     use bdk::bitcoin::Script;
-    use bdk::electrum_client;
     use std::str::FromStr;
     use std::sync::Arc;
     use std::time::SystemTime;
@@ -1189,17 +1318,14 @@ mod test {
         tracing::info!("Test runner started...");
         let start_time = SystemTime::now();
 
-        let client = bdk::electrum_client::Client::from_config(
-            get_test_server().as_str(),
-            electrum_client::ConfigBuilder::new()
-                .timeout(Some(ELECTRUM_CLIENT_TIMEOUT_SECS))
-                .unwrap()
-                .build(),
-        )
-        .unwrap();
+        let electrum = Electrum::new(get_test_server().as_str()).unwrap();
 
         let scripts_len = scripts.len();
-        let rx_script_history = batch_script_get_history(Arc::new(client), scripts).await;
+        let scripts = scripts
+            .into_iter()
+            .map(|script| (script, Vec::new()))
+            .collect();
+        let rx_script_history = batch_script_get_history(Arc::new(electrum), scripts).await;
 
         assert_eq!(scripts_len, rx_script_history.len());
 
@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use libp2p_core::PeerId;
+use std::collections::HashSet;
 use std::time::Duration;
 use tokio::sync::watch;
 use xtra::prelude::*;
@@ -14,26 +15,44 @@ pub enum ConnectionStatus {
     Offline,
 }
 
-/// Actor that transmits updates of ConnectionStatus of a specified PeerId based on
+/// Actor that transmits updates of ConnectionStatus of a set of PeerIds based on
 /// information transmitted by the Endpoint via a watch channel.
+///
+/// We are considered `Online` as long as we are connected to at least one of the watched peers.
 pub struct Actor {
     endpoint: Address<Endpoint>,
-    watched_peer: PeerId,
+    watched_peers: HashSet<PeerId>,
+    connected_peers: HashSet<PeerId>,
     sender: watch::Sender<ConnectionStatus>,
 }
 
 impl Actor {
     pub fn new(
         endpoint: Address<Endpoint>,
-        watched_peer: PeerId,
+        watched_peers: HashSet<PeerId>,
         sender: watch::Sender<ConnectionStatus>,
     ) -> Self {
         Self {
             endpoint,
-            watched_peer,
+            watched_peers,
+            connected_peers: HashSet::new(),
             sender,
         }
     }
+
+    fn status(&self) -> ConnectionStatus {
+        if self.connected_peers.is_empty() {
+            ConnectionStatus::Offline
+        } else {
+            ConnectionStatus::Online
+        }
+    }
+
+    fn send_status(&self) {
+        self.sender
+            .send(self.status())
+            .expect("Receiver to outlive this actor");
+    }
 }
 
 #[async_trait]
@@ -44,22 +63,17 @@ impl xtra::Actor for Actor {
     async fn started(&mut self, ctx: &mut Context<Self>) {
         tracing::debug!(
             "Online status watch actor started. Monitoring for peer id changes: {:?}",
-            self.watched_peer
+            self.watched_peers
         );
 
         match self.endpoint.send(GetConnectionStats).await {
             Ok(connection_stats) => {
-                let status = if connection_stats
+                self.connected_peers = connection_stats
                     .connected_peers
-                    .contains(&self.watched_peer)
-                {
-                    ConnectionStatus::Online
-                } else {
-                    ConnectionStatus::Offline
-                };
-                self.sender
-                    .send(status)
-                    .expect("Receiver to outlive this actor");
+                    .intersection(&self.watched_peers)
+                    .copied()
+                    .collect();
+                self.send_status();
             }
             Err(e) => {
                 tracing::error!(
@@ -87,10 +101,9 @@ impl Actor {
             "Adding newly established connection to online_status: {:?}",
             msg.peer_id
         );
-        if msg.peer_id == self.watched_peer {
-            self.sender
-                .send(ConnectionStatus::Online)
-                .expect("Receiver to outlive this actor");
+        if self.watched_peers.contains(&msg.peer_id) {
+            self.connected_peers.insert(msg.peer_id);
+            self.send_status();
         }
     }
 
@@ -100,10 +113,8 @@ impl Actor {
             msg.peer_id
         );
 
-        if msg.peer_id == self.watched_peer {
-            self.sender
-                .send(ConnectionStatus::Offline)
-                .expect("Receiver to outlive this actor");
+        if self.connected_peers.remove(&msg.peer_id) {
+            self.send_status();
         }
     }
 }
@@ -8,13 +8,16 @@ use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use model::olivia;
 use model::olivia::next_announcement_after;
 use model::olivia::BitMexPriceEventId;
+use model::olivia::IndexPrice;
+use model::olivia::OracleConfig;
+use model::olivia::Oracles;
 use model::CfdEvent;
 use model::ContractSymbol;
 use model::EventKind;
+use model::OrderId;
 use sqlite_db;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use strum::IntoEnumIterator;
 use time::Duration;
 use time::OffsetDateTime;
 use tracing::Instrument;
@@ -37,11 +40,18 @@ const SYNC_ANNOUNCEMENTS_INTERVAL: core::time::Duration = std::time::Duration::f
 const SYNC_ATTESTATIONS_INTERVAL: core::time::Duration = std::time::Duration::from_secs(30);
 
 pub struct Actor {
-    announcements: HashMap<BitMexPriceEventId, (OffsetDateTime, Vec<XOnlyPublicKey>)>,
-    pending_attestations: HashSet<BitMexPriceEventId>,
+    /// Announcements by the public key of the oracle that published them.
+    announcements:
+        HashMap<(XOnlyPublicKey, BitMexPriceEventId), (OffsetDateTime, Vec<XOnlyPublicKey>)>,
+    pending_attestations: HashSet<(BitMexPriceEventId, OracleConfig)>,
     executor: command::Executor,
     db: sqlite_db::Connection,
     client: reqwest::Client,
+    /// The oracles to fetch announcements from, per index they attest to.
+    ///
+    /// These are the configured oracles and the oracles of open CFDs, which may differ if the
+    /// configuration changed after a CFD was opened.
+    oracles: HashSet<(IndexPrice, OracleConfig)>,
 }
 
 /// We want to fetch at least this much announcements into the future
@@ -69,8 +79,10 @@ pub struct SyncAnnouncements;
 #[derive(Clone, Copy)]
 pub struct SyncAttestations;
 
+/// Monitor the attestations of the given events, using the oracle of the CFD `order_id`.
 #[derive(Clone)]
 pub struct MonitorAttestations {
+    pub order_id: OrderId,
     pub event_ids: Vec<BitMexPriceEventId>,
 }
 
@@ -78,9 +90,15 @@ pub struct MonitorAttestations {
 /// local state.
 ///
 /// Each `Announcement` corresponds to a [`BitMexPriceEventId`]
-/// included in the message.
+/// included in the message and was published by `oracle`.
 #[derive(Clone)]
-pub struct GetAnnouncements(pub Vec<BitMexPriceEventId>);
+pub struct GetAnnouncements {
+    pub oracle: OracleConfig,
+    pub event_ids: Vec<BitMexPriceEventId>,
+}
+
+/// A module-private message to monitor the attestations of the CFDs that are open at startup.
+struct MonitorOpenCfds(Vec<(OracleConfig, Vec<BitMexPriceEventId>)>);
 
 #[derive(Debug, Clone)]
 pub struct Attestation(olivia::Attestation);
@@ -88,6 +106,7 @@ pub struct Attestation(olivia::Attestation);
 /// A module-private message to allow parallelization of fetching announcements.
 #[derive(Debug)]
 struct NewAnnouncementFetched {
+    oracle_pk: XOnlyPublicKey,
     id: BitMexPriceEventId,
     expected_outcome_time: OffsetDateTime,
     nonce_pks: Vec<XOnlyPublicKey>,
@@ -97,12 +116,14 @@ struct NewAnnouncementFetched {
 #[derive(Debug)]
 struct NewAttestationFetched {
     id: BitMexPriceEventId,
+    oracle: OracleConfig,
     attestation: Attestation,
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 struct Cfd {
     event_ids: Option<Vec<BitMexPriceEventId>>,
+    oracle: OracleConfig,
     version: u32,
 }
 
@@ -134,8 +155,12 @@ impl Cfd {
 impl sqlite_db::CfdAggregate for Cfd {
     type CtorArgs = ();
 
-    fn new(_: Self::CtorArgs, _: sqlite_db::Cfd) -> Self {
-        Self::default()
+    fn new(_: Self::CtorArgs, cfd: sqlite_db::Cfd) -> Self {
+        Self {
+            event_ids: None,
+            oracle: cfd.oracle,
+            version: 0,
+        }
     }
 
     fn apply(self, event: CfdEvent) -> Self {
@@ -148,7 +173,12 @@ impl sqlite_db::CfdAggregate for Cfd {
 }
 
 impl Actor {
-    pub fn new(db: sqlite_db::Connection, executor: command::Executor) -> Self {
+    pub fn new(
+        db: sqlite_db::Connection,
+        executor: command::Executor,
+        oracles: Oracles,
+        contract_symbols: &[ContractSymbol],
+    ) -> Self {
         Self {
             announcements: HashMap::new(),
             pending_attestations: HashSet::new(),
@@ -158,30 +188,42 @@ impl Actor {
                 .timeout(REQWEST_TIMEOUT)
                 .build()
                 .expect("to build from static arguments"),
+            oracles: contract_symbols
+                .iter()
+                .map(|&contract_symbol| {
+                    (
+                        IndexPrice::from(contract_symbol),
+                        oracles.get(contract_symbol),
+                    )
+                })
+                .collect(),
         }
     }
 
     fn ensure_having_announcements(
         &mut self,
-        contract_symbol: ContractSymbol,
+        index: IndexPrice,
+        oracle: &OracleConfig,
         ctx: &mut xtra::Context<Self>,
     ) {
         for hour in 1..ANNOUNCEMENT_LOOKAHEAD.whole_hours() {
-            let event_id = next_announcement_after(
-                OffsetDateTime::now_utc() + Duration::hours(hour),
-                contract_symbol,
-            );
+            let event_id =
+                next_announcement_after(OffsetDateTime::now_utc() + Duration::hours(hour), index);
 
-            if self.announcements.get(&event_id).is_some() {
+            if self
+                .announcements
+                .get(&(oracle.public_key, event_id))
+                .is_some()
+            {
                 continue;
             }
             let this = ctx.address().expect("self to be alive");
             let client = self.client.clone();
+            let oracle_pk = oracle.public_key;
+            let url = oracle.event_url(event_id);
 
             let this_clone = this.clone();
             let task = async move {
-                let url = event_id.to_olivia_url();
-
                 tracing::debug!(event_id = %event_id, "Fetching announcement");
 
                 let response = client
@@ -201,6 +243,7 @@ impl Actor {
                     .context("Failed to deserialize as Announcement")?;
 
                 this.send(NewAnnouncementFetched {
+                    oracle_pk,
                     id: event_id,
                     nonce_pks: announcement.nonce_pks,
                     expected_outcome_time: announcement.expected_outcome_time,
@@ -221,7 +264,7 @@ impl Actor {
     }
 
     fn update_pending_attestations(&mut self, ctx: &mut xtra::Context<Self>) {
-        for event_id in self.pending_attestations.iter().copied() {
+        for (event_id, oracle) in self.pending_attestations.iter().cloned() {
             if !event_id.has_likely_occurred() {
                 tracing::trace!("Skipping {event_id} because it likely hasn't occurred yet");
 
@@ -230,12 +273,11 @@ impl Actor {
 
             let this = ctx.address().expect("self to be alive");
             let client = self.client.clone();
+            let url = oracle.event_url(event_id);
 
             tokio_extras::spawn_fallible(
                 &this.clone(),
                 async move {
-                    let url = event_id.to_olivia_url();
-
                     tracing::debug!(%event_id, "Fetching attestation");
 
                     let response = client
@@ -256,6 +298,7 @@ impl Actor {
 
                     this.send(NewAttestationFetched {
                         id: event_id,
+                        oracle,
                         attestation: Attestation(attestation),
                     })
                     .await??;
@@ -269,8 +312,12 @@ impl Actor {
         }
     }
 
-    fn add_pending_attestation(&mut self, event_id: BitMexPriceEventId) {
-        if !self.pending_attestations.insert(event_id) {
+    fn add_pending_attestation(&mut self, event_id: BitMexPriceEventId, oracle: OracleConfig) {
+        // Rollovers of the CFD need announcements of the same oracle
+        self.oracles
+            .insert((event_id.index_price(), oracle.clone()));
+
+        if !self.pending_attestations.insert((event_id, oracle)) {
             tracing::trace!("Attestation for {event_id} already being monitored");
         }
     }
@@ -278,41 +325,76 @@ impl Actor {
 
 #[xtra_productivity]
 impl Actor {
-    fn handle_monitor_attestations(&mut self, msg: MonitorAttestations) {
-        for id in msg.event_ids.into_iter() {
-            self.add_pending_attestation(id);
+    async fn handle_monitor_attestations(&mut self, msg: MonitorAttestations) {
+        let MonitorAttestations {
+            order_id,
+            event_ids,
+        } = msg;
+
+        let oracle = match self.db.load_open_cfd::<Cfd>(order_id, ()).await {
+            Ok(Cfd { oracle, .. }) => oracle,
+            Err(e) => {
+                tracing::warn!(%order_id, "Failed to load oracle of CFD: {e:#}");
+                return;
+            }
+        };
+
+        for id in event_ids {
+            self.add_pending_attestation(id, oracle.clone());
+        }
+    }
+
+    fn handle_monitor_open_cfds(&mut self, msg: MonitorOpenCfds) {
+        for (oracle, event_ids) in msg.0 {
+            for id in event_ids {
+                self.add_pending_attestation(id, oracle.clone());
+            }
         }
     }
 
     fn handle_get_announcements(
         &mut self,
-        GetAnnouncements(ids): GetAnnouncements,
+        msg: GetAnnouncements,
+        ctx: &mut xtra::Context<Self>,
     ) -> Result<Vec<olivia::Announcement>, NoAnnouncement> {
-        let announcements = ids
+        let GetAnnouncements { oracle, event_ids } = msg;
+
+        let announcements = event_ids
             .iter()
             .map(|id| {
                 self.announcements
-                    .get_key_value(id)
-                    .map(|(id, (time, nonce_pks))| olivia::Announcement {
+                    .get_key_value(&(oracle.public_key, *id))
+                    .map(|((_, id), (time, nonce_pks))| olivia::Announcement {
                         id: *id,
                         expected_outcome_time: *time,
                         nonce_pks: nonce_pks.clone(),
                     })
                     .ok_or(NoAnnouncement(*id))
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<_, _>>();
 
-        Ok(announcements)
+        if announcements.is_err() {
+            // Start fetching from an oracle we did not know about, so that a retry can succeed
+            for index in event_ids.iter().map(|id| id.index_price()) {
+                if self.oracles.insert((index, oracle.clone())) {
+                    self.ensure_having_announcements(index, &oracle, ctx);
+                }
+            }
+        }
+
+        announcements
     }
 
     fn handle_new_announcement_fetched(&mut self, msg: NewAnnouncementFetched) {
-        self.announcements
-            .insert(msg.id, (msg.expected_outcome_time, msg.nonce_pks));
+        self.announcements.insert(
+            (msg.oracle_pk, msg.id),
+            (msg.expected_outcome_time, msg.nonce_pks),
+        );
     }
 
     fn handle_sync_announcements(&mut self, _: SyncAnnouncements, ctx: &mut xtra::Context<Self>) {
-        for contract_symbol in ContractSymbol::iter() {
-            self.ensure_having_announcements(contract_symbol, ctx);
+        for (index, oracle) in self.oracles.clone() {
+            self.ensure_having_announcements(index, &oracle, ctx);
         }
     }
 
@@ -321,21 +403,32 @@ impl Actor {
     }
 
     async fn handle_new_attestation_fetched(&mut self, msg: NewAttestationFetched) -> Result<()> {
-        let NewAttestationFetched { id, attestation } = msg;
+        let NewAttestationFetched {
+            id,
+            oracle,
+            attestation,
+        } = msg;
 
         tracing::info!("Fetched new attestation for {id}");
 
-        for id in self.db.load_open_cfd_ids().await? {
+        for order_id in self.db.load_open_cfd_ids().await? {
             if let Err(err) = self
                 .executor
-                .execute(id, |cfd| cfd.decrypt_cet(&attestation.0))
+                .execute(order_id, |cfd| {
+                    // Another oracle's attestation of the same event cannot decrypt our CETs
+                    if cfd.oracle() != &oracle {
+                        return Ok(None);
+                    }
+
+                    cfd.decrypt_cet(&attestation.0)
+                })
                 .await
             {
-                tracing::error!(order_id = %id, "Failed to decrypt CET using attestation: {err:#}")
+                tracing::error!(%order_id, "Failed to decrypt CET using attestation: {err:#}")
             }
         }
 
-        self.pending_attestations.remove(&id);
+        self.pending_attestations.remove(&(id, oracle));
 
         Ok(())
     }
@@ -367,7 +460,9 @@ impl xtra::Actor for Actor {
                     .load_all_open_cfds::<Cfd>(())
                     .filter_map(|res| async move {
                         match res {
-                            Ok(Cfd { event_ids, .. }) => event_ids,
+                            Ok(Cfd {
+                                event_ids, oracle, ..
+                            }) => event_ids.map(|event_ids| (oracle, event_ids)),
                             Err(e) => {
                                 tracing::warn!("Failed to load CFD from database: {e:#}");
                                 None
@@ -378,12 +473,8 @@ impl xtra::Actor for Actor {
                     .instrument(span.clone())
                     .await;
 
-                let _: Result<(), xtra::Error> = this
-                    .send(MonitorAttestations {
-                        event_ids: event_ids.concat(),
-                    })
-                    .instrument(span)
-                    .await;
+                let _: Result<(), xtra::Error> =
+                    this.send(MonitorOpenCfds(event_ids)).instrument(span).await;
 
                 this.send_interval(
                     SYNC_ATTESTATIONS_INTERVAL,
@@ -441,11 +532,12 @@ impl AnnouncementsChannel {
 impl rollover::deprecated::protocol::GetAnnouncements for AnnouncementsChannel {
     async fn get_announcements(
         &self,
-        events: Vec<BitMexPriceEventId>,
+        oracle: OracleConfig,
+        event_ids: Vec<BitMexPriceEventId>,
     ) -> Result<Vec<olivia::Announcement>> {
         let announcements = self
             .0
-            .send(GetAnnouncements(events))
+            .send(GetAnnouncements { oracle, event_ids })
             .await
             .context("Oracle actor disconnected")?
             .context("Failed to get announcements")?;
@@ -458,11 +550,12 @@ impl rollover::deprecated::protocol::GetAnnouncements for AnnouncementsChannel {
 impl rollover::protocol::GetAnnouncements for AnnouncementsChannel {
     async fn get_announcements(
         &self,
-        events: Vec<BitMexPriceEventId>,
+        oracle: OracleConfig,
+        event_ids: Vec<BitMexPriceEventId>,
     ) -> Result<Vec<olivia::Announcement>> {
         let announcements = self
             .0
-            .send(GetAnnouncements(events))
+            .send(GetAnnouncements { oracle, event_ids })
             .await
             .context("Oracle actor disconnected")?
             .context("Failed to get announcements")?;
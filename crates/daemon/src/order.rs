@@ -1,4 +1,21 @@
+use model::Contracts;
+use model::Leverage;
+use model::OrderId;
+
 mod current;
 pub mod deprecated;
 
 pub use current::*;
+
+/// Notification about an order a taker placed on one of our offers.
+///
+/// Sent by the maker's order protocol actors once the CFD was created and the order awaits a
+/// decision.
+#[derive(Clone)]
+pub struct NewOrder {
+    pub order_id: OrderId,
+    pub offer: model::Offer,
+    pub quantity: Contracts,
+    pub leverage: Leverage,
+    pub peer_id: model::libp2p::PeerId,
+}
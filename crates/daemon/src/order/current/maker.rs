@@ -6,6 +6,7 @@ use crate::order::current::protocol;
 use crate::order::current::protocol::MakerMessage;
 use crate::order::current::protocol::SetupMsg;
 use crate::order::current::protocol::TakerMessage;
+use crate::order::NewOrder;
 use crate::process_manager;
 use crate::projection;
 use crate::wallet;
@@ -16,11 +17,11 @@ use async_trait::async_trait;
 use asynchronous_codec::Framed;
 use asynchronous_codec::JsonCodec;
 use bdk::bitcoin::psbt::PartiallySignedTransaction;
-use bdk::bitcoin::XOnlyPublicKey;
 use futures::channel::oneshot;
 use futures::future;
 use futures::SinkExt;
 use futures::StreamExt;
+use libp2p_core::PeerId;
 use maia_core::PartyParams;
 use model::olivia;
 use model::Cfd;
@@ -43,7 +44,6 @@ const ORDER_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct Actor {
     executor: command::Executor,
-    oracle_pk: XOnlyPublicKey,
     get_announcement:
         MessageChannel<oracle::GetAnnouncements, Result<Vec<olivia::Announcement>, NoAnnouncement>>,
     build_party_params: MessageChannel<wallet::BuildPartyParams, Result<PartyParams>>,
@@ -53,12 +53,12 @@ pub struct Actor {
     decision_senders: HashMap<OrderId, oneshot::Sender<protocol::Decision>>,
     db: sqlite_db::Connection,
     latest_offers: MessageChannel<offer::maker::GetLatestOffers, Vec<model::Offer>>,
+    new_orders: MessageChannel<NewOrder, ()>,
 }
 
 impl Actor {
     pub fn new(
         n_payouts: usize,
-        oracle_pk: XOnlyPublicKey,
         get_announcement: MessageChannel<
             oracle::GetAnnouncements,
             Result<Vec<olivia::Announcement>, NoAnnouncement>,
@@ -70,10 +70,10 @@ impl Actor {
         ),
         projection: xtra::Address<projection::Actor>,
         latest_offers: MessageChannel<offer::maker::GetLatestOffers, Vec<model::Offer>>,
+        new_orders: MessageChannel<NewOrder, ()>,
     ) -> Self {
         Self {
             executor: command::Executor::new(db.clone(), process_manager),
-            oracle_pk,
             get_announcement,
             build_party_params,
             sign,
@@ -82,6 +82,7 @@ impl Actor {
             decision_senders: HashMap::default(),
             db,
             latest_offers,
+            new_orders,
         }
     }
 
@@ -102,10 +103,12 @@ impl Actor {
     }
 
     #[instrument(skip(self))]
-    async fn pick_offer(&self, offer_id: OfferId) -> Result<model::Offer> {
+    async fn pick_offer(&self, peer_id: PeerId, offer_id: OfferId) -> Result<model::Offer> {
+        // Only the offers sent to this taker can be taken, private offers of other takers are not
+        // visible to it
         let latest_offers = self
             .latest_offers
-            .send(offer::maker::GetLatestOffers)
+            .send(offer::maker::GetLatestOffers::for_peer(peer_id))
             .await
             .context("Failed to retrieve latest offer from offers actor")?;
 
@@ -150,7 +153,7 @@ impl Actor {
         tracing::info!(%peer_id, %quantity, %order_id, %offer_id, "Taker wants to place an order");
 
         // Reject the order if the offer cannot be found in the latest offers
-        let offer = match self.pick_offer(offer_id).await {
+        let offer = match self.pick_offer(peer_id, offer_id).await {
             Ok(offer) => offer,
             Err(e) => {
                 tracing::warn!(
@@ -160,7 +163,9 @@ impl Actor {
 
                 let future = async move {
                     framed
-                        .send(MakerMessage::Decision(protocol::Decision::Reject))
+                        .send(MakerMessage::Decision(protocol::Decision::Reject {
+                            reason: None,
+                        }))
                         .await?;
 
                     anyhow::Ok(())
@@ -211,12 +216,27 @@ impl Actor {
         let (sender, receiver) = oneshot::channel();
         self.decision_senders.insert(order_id, sender);
 
+        if let Err(e) = self
+            .new_orders
+            .send_async_safe(NewOrder {
+                order_id,
+                offer: offer.clone(),
+                quantity,
+                leverage,
+                peer_id: peer_id.into(),
+            })
+            .await
+        {
+            tracing::warn!(%order_id, "Failed to notify about new order: {e:#}");
+        }
+
         let task = {
             let build_party_params = self.build_party_params.clone();
             let sign = self.sign.clone();
             let get_announcement = self.get_announcement.clone();
             let executor = self.executor.clone();
-            let oracle_pk = self.oracle_pk;
+            let oracle = offer.oracle.clone();
+            let oracle_pk = oracle.public_key;
             let n_payouts = self.n_payouts;
             async move {
                 match receiver.await? {
@@ -227,16 +247,20 @@ impl Actor {
 
                         tracing::info!(%peer_id, %quantity, %order_id, "Order accepted");
                     }
-                    protocol::Decision::Reject => {
+                    protocol::Decision::Reject { reason } => {
                         framed
-                            .send(MakerMessage::Decision(protocol::Decision::Reject))
+                            .send(MakerMessage::Decision(protocol::Decision::Reject {
+                                reason: reason.clone(),
+                            }))
                             .await?;
 
-                        tracing::info!(%peer_id, %quantity, %order_id, "Order rejected");
+                        let reason = reason.unwrap_or_else(|| "Unknown".to_owned());
+
+                        tracing::info!(%peer_id, %quantity, %order_id, %reason, "Order rejected");
 
                         executor
                             .execute(order_id, |cfd| {
-                                cfd.reject_contract_setup(anyhow::anyhow!("Unknown"))
+                                cfd.reject_contract_setup(anyhow::anyhow!(reason))
                             })
                             .await?;
 
@@ -251,7 +275,10 @@ impl Actor {
                 let (sink, stream) = framed.split();
 
                 let announcement = get_announcement
-                    .send(oracle::GetAnnouncements(vec![oracle_event_id]))
+                    .send(oracle::GetAnnouncements {
+                        oracle,
+                        event_ids: vec![oracle_event_id],
+                    })
                     .await??;
 
                 let dlc = contract_setup::new(
@@ -329,16 +356,18 @@ impl Actor {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Decision {
     Accept(OrderId),
     Reject(OrderId),
+    /// Reject the order and tell the taker why.
+    RejectWithReason(OrderId, String),
 }
 
 impl Decision {
     fn id(&self) -> OrderId {
         match self {
-            Decision::Accept(id) | Decision::Reject(id) => *id,
+            Decision::Accept(id) | Decision::Reject(id) | Decision::RejectWithReason(id, _) => *id,
         }
     }
 }
@@ -347,7 +376,10 @@ impl From<Decision> for protocol::Decision {
     fn from(decision: Decision) -> Self {
         match decision {
             Decision::Accept(_) => protocol::Decision::Accept,
-            Decision::Reject(_) => protocol::Decision::Reject,
+            Decision::Reject(_) => protocol::Decision::Reject { reason: None },
+            Decision::RejectWithReason(_, reason) => protocol::Decision::Reject {
+                reason: Some(reason),
+            },
         }
     }
 }
@@ -356,7 +388,7 @@ impl fmt::Display for Decision {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             Decision::Accept(_) => "Accept",
-            Decision::Reject(_) => "Reject",
+            Decision::Reject(_) | Decision::RejectWithReason(..) => "Reject",
         };
 
         s.fmt(f)
@@ -44,12 +44,65 @@ pub(crate) enum MakerMessage {
     ContractSetupMsg(Box<SetupMsg>),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "WireDecision", into = "WireDecision")]
 pub(crate) enum Decision {
+    Accept,
+    /// Reject the order, optionally telling the taker why.
+    Reject {
+        reason: Option<String>,
+    },
+}
+
+/// How a [`Decision`] is encoded.
+///
+/// Decisions without a reason keep the encoding of makers that predate reasons, `"Accept"` and
+/// `"Reject"`, so that these makers and takers still understand each other.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WireDecision {
+    Plain(PlainDecision),
+    WithReason(DecisionWithReason),
+}
+
+#[derive(Serialize, Deserialize)]
+enum PlainDecision {
     Accept,
     Reject,
 }
 
+#[derive(Serialize, Deserialize)]
+enum DecisionWithReason {
+    Reject {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+impl From<Decision> for WireDecision {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Accept => WireDecision::Plain(PlainDecision::Accept),
+            Decision::Reject { reason: None } => WireDecision::Plain(PlainDecision::Reject),
+            Decision::Reject { reason } => {
+                WireDecision::WithReason(DecisionWithReason::Reject { reason })
+            }
+        }
+    }
+}
+
+impl From<WireDecision> for Decision {
+    fn from(decision: WireDecision) -> Self {
+        match decision {
+            WireDecision::Plain(PlainDecision::Accept) => Decision::Accept,
+            WireDecision::Plain(PlainDecision::Reject) => Decision::Reject { reason: None },
+            WireDecision::WithReason(DecisionWithReason::Reject { reason }) => {
+                Decision::Reject { reason }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "payload")]
 pub enum SetupMsg {
@@ -234,3 +287,38 @@ impl TryFrom<TakerMessage> for SetupMsg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decision_without_reason_keeps_plain_encoding() {
+        let accept = serde_json::to_string(&Decision::Accept).unwrap();
+        let reject = serde_json::to_string(&Decision::Reject { reason: None }).unwrap();
+
+        assert_eq!(accept, r#""Accept""#);
+        assert_eq!(reject, r#""Reject""#);
+    }
+
+    #[test]
+    fn decode_plain_decisions() {
+        let accept = serde_json::from_str::<Decision>(r#""Accept""#).unwrap();
+        let reject = serde_json::from_str::<Decision>(r#""Reject""#).unwrap();
+
+        assert_eq!(accept, Decision::Accept);
+        assert_eq!(reject, Decision::Reject { reason: None });
+    }
+
+    #[test]
+    fn roundtrip_rejection_with_reason() {
+        let decision = Decision::Reject {
+            reason: Some("Exposure limit exceeded".to_owned()),
+        };
+
+        let encoded = serde_json::to_string(&decision).unwrap();
+        let decoded = serde_json::from_str::<Decision>(&encoded).unwrap();
+
+        assert_eq!(decoded, decision);
+    }
+}
@@ -18,7 +18,6 @@ use async_trait::async_trait;
 use asynchronous_codec::Framed;
 use asynchronous_codec::JsonCodec;
 use bdk::bitcoin::psbt::PartiallySignedTransaction;
-use bdk::bitcoin::XOnlyPublicKey;
 use futures::future;
 use futures::SinkExt;
 use futures::StreamExt;
@@ -40,12 +39,11 @@ use xtra_libp2p::OpenSubstream;
 use xtra_productivity::xtra_productivity;
 
 /// Timeout for awaiting a response to an order request from the maker
-const PLACE_ORDER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+pub const PLACE_ORDER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct Actor {
     endpoint: xtra::Address<Endpoint>,
     executor: command::Executor,
-    oracle_pk: XOnlyPublicKey,
     get_announcement:
         MessageChannel<oracle::GetAnnouncements, Result<Vec<olivia::Announcement>, NoAnnouncement>>,
     build_party_params: MessageChannel<wallet::BuildPartyParams, Result<PartyParams>>,
@@ -58,7 +56,6 @@ pub struct Actor {
 impl Actor {
     pub fn new(
         n_payouts: usize,
-        oracle_pk: XOnlyPublicKey,
         get_announcement: MessageChannel<
             oracle::GetAnnouncements,
             Result<Vec<olivia::Announcement>, NoAnnouncement>,
@@ -74,7 +71,6 @@ impl Actor {
         Self {
             endpoint,
             executor: command::Executor::new(db.clone(), process_manager),
-            oracle_pk,
             get_announcement,
             build_party_params,
             sign,
@@ -97,7 +93,6 @@ impl Actor {
             let endpoint = self.endpoint.clone();
             let executor = self.executor.clone();
             let db = self.db.clone();
-            let oracle_pk = self.oracle_pk;
             let n_payouts = self.n_payouts;
             let projection = self.projection.clone();
             async move {
@@ -113,6 +108,8 @@ impl Actor {
                 } = msg;
 
                 let oracle_event_id = offer.oracle_event_id;
+                let oracle = offer.oracle.clone();
+                let oracle_pk = oracle.public_key;
                 let cfd = Cfd::from_order(
                     order_id,
                     &offer,
@@ -169,12 +166,19 @@ impl Actor {
                     MakerMessage::Decision(Decision::Accept) => {
                         tracing::info!(order_id = %msg.order_id, %maker_peer_id, "Order accepted");
                     }
-                    MakerMessage::Decision(Decision::Reject) => {
-                        tracing::info!(order_id = %msg.order_id, %maker_peer_id, "Order rejected");
+                    MakerMessage::Decision(Decision::Reject { reason }) => {
+                        let reason = reason.unwrap_or_else(|| "Unknown".to_owned());
+
+                        tracing::info!(
+                            order_id = %msg.order_id,
+                            %maker_peer_id,
+                            %reason,
+                            "Order rejected"
+                        );
 
                         executor
                             .execute(order_id, |cfd| {
-                                cfd.reject_contract_setup(anyhow::anyhow!("Unknown"))
+                                cfd.reject_contract_setup(anyhow::anyhow!(reason))
                             })
                             .await?;
 
@@ -190,7 +194,10 @@ impl Actor {
                 let (sink, stream) = framed.split();
 
                 let announcement = get_announcement
-                    .send(oracle::GetAnnouncements(vec![oracle_event_id]))
+                    .send(oracle::GetAnnouncements {
+                        oracle,
+                        event_ids: vec![oracle_event_id],
+                    })
                     .await??;
 
                 let dlc = contract_setup::new(
@@ -28,14 +28,13 @@ use maia_core::PartyParams;
 use maia_core::PunishParams;
 use model::olivia;
 use model::olivia::BitMexPriceEventId;
-use model::payout_curve::ETHUSD_MULTIPLIER;
 use model::shared_protocol::verify_adaptor_signature;
 use model::shared_protocol::verify_cets;
 use model::shared_protocol::verify_signature;
 use model::Cet;
-use model::ContractSymbol;
 use model::Dlc;
 use model::OraclePayouts;
+use model::PayoutCurve;
 use model::Payouts;
 use model::Position;
 use model::Role;
@@ -311,8 +310,8 @@ async fn create_cfd_transactions(
 
     let settlement_event_id = announcements.last().context("Empty announcements")?.id;
 
-    let payouts = match setup_params.contract_symbol {
-        ContractSymbol::BtcUsd => Payouts::new_inverse_double_initial(
+    let payouts = match setup_params.contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => Payouts::new_inverse_double_initial(
             (position, role),
             setup_params.price,
             setup_params.quantity,
@@ -320,13 +319,13 @@ async fn create_cfd_transactions(
             n_payouts,
             setup_params.fee_account.settle(),
         )?,
-        ContractSymbol::EthUsd => Payouts::new_quanto(
+        PayoutCurve::Quanto => Payouts::new_quanto(
             (position, role),
             setup_params.price.to_u64(),
             setup_params.quantity.to_u64(),
             (setup_params.long_leverage, setup_params.short_leverage),
             n_payouts,
-            ETHUSD_MULTIPLIER,
+            setup_params.contract_symbol.multiplier(),
             setup_params.fee_account.settle(),
         )?,
     };
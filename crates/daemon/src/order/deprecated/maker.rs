@@ -6,6 +6,7 @@ use crate::order::deprecated::protocol;
 use crate::order::deprecated::protocol::MakerMessage;
 use crate::order::deprecated::protocol::SetupMsg;
 use crate::order::deprecated::protocol::TakerMessage;
+use crate::order::NewOrder;
 use crate::process_manager;
 use crate::projection;
 use crate::wallet;
@@ -16,7 +17,6 @@ use async_trait::async_trait;
 use asynchronous_codec::Framed;
 use asynchronous_codec::JsonCodec;
 use bdk::bitcoin::psbt::PartiallySignedTransaction;
-use bdk::bitcoin::XOnlyPublicKey;
 use futures::channel::oneshot;
 use futures::future;
 use futures::SinkExt;
@@ -43,22 +43,21 @@ const ORDER_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct Actor {
     executor: command::Executor,
-    oracle_pk: XOnlyPublicKey,
     get_announcement:
         MessageChannel<oracle::GetAnnouncements, Result<Vec<olivia::Announcement>, NoAnnouncement>>,
     build_party_params: MessageChannel<wallet::BuildPartyParams, Result<PartyParams>>,
     sign: MessageChannel<wallet::Sign, Result<PartiallySignedTransaction>>,
     projection: xtra::Address<projection::Actor>,
     n_payouts: usize,
-    decision_senders: HashMap<OrderId, oneshot::Sender<protocol::Decision>>,
+    decision_senders: HashMap<OrderId, oneshot::Sender<Decision>>,
     db: sqlite_db::Connection,
     latest_offers: MessageChannel<offer::maker::GetLatestOffers, Vec<model::Offer>>,
+    new_orders: MessageChannel<NewOrder, ()>,
 }
 
 impl Actor {
     pub fn new(
         n_payouts: usize,
-        oracle_pk: XOnlyPublicKey,
         get_announcement: MessageChannel<
             oracle::GetAnnouncements,
             Result<Vec<olivia::Announcement>, NoAnnouncement>,
@@ -70,10 +69,10 @@ impl Actor {
         ),
         projection: xtra::Address<projection::Actor>,
         latest_offers: MessageChannel<offer::maker::GetLatestOffers, Vec<model::Offer>>,
+        new_orders: MessageChannel<NewOrder, ()>,
     ) -> Self {
         Self {
             executor: command::Executor::new(db.clone(), process_manager),
-            oracle_pk,
             get_announcement,
             build_party_params,
             sign,
@@ -82,6 +81,7 @@ impl Actor {
             decision_senders: HashMap::default(),
             db,
             latest_offers,
+            new_orders,
         }
     }
 
@@ -105,7 +105,7 @@ impl Actor {
     async fn pick_offer(&self, offer_id: OfferId) -> Result<model::Offer> {
         let latest_offers = self
             .latest_offers
-            .send(offer::maker::GetLatestOffers)
+            .send(offer::maker::GetLatestOffers::for_everyone())
             .await
             .context("Failed to retrieve latest offer from offers actor")?;
 
@@ -208,32 +208,54 @@ impl Actor {
         let (sender, receiver) = oneshot::channel();
         self.decision_senders.insert(order_id, sender);
 
+        if let Err(e) = self
+            .new_orders
+            .send_async_safe(NewOrder {
+                order_id,
+                offer: offer.clone(),
+                quantity,
+                leverage,
+                peer_id: peer_id.into(),
+            })
+            .await
+        {
+            tracing::warn!(%order_id, "Failed to notify about new order: {e:#}");
+        }
+
         let task = {
             let build_party_params = self.build_party_params.clone();
             let sign = self.sign.clone();
             let get_announcement = self.get_announcement.clone();
             let executor = self.executor.clone();
-            let oracle_pk = self.oracle_pk;
+            let oracle = offer.oracle.clone();
+            let oracle_pk = oracle.public_key;
             let n_payouts = self.n_payouts;
             async move {
                 match receiver.await? {
-                    protocol::Decision::Accept => {
+                    Decision::Accept(_) => {
                         framed
                             .send(MakerMessage::Decision(protocol::Decision::Accept))
                             .await?;
 
                         tracing::info!(%peer_id, %quantity, %order_id, "Order accepted");
                     }
-                    protocol::Decision::Reject => {
+                    decision @ (Decision::Reject(_) | Decision::RejectWithReason(..)) => {
+                        // This version of the protocol cannot tell the taker why we rejected
+                        // the order, but we still record the reason on our side
                         framed
                             .send(MakerMessage::Decision(protocol::Decision::Reject))
                             .await?;
 
-                        tracing::info!(%peer_id, %quantity, %order_id, "Order rejected");
+                        let reason = match decision {
+                            Decision::RejectWithReason(_, reason) => reason,
+                            _ => "Unknown".to_owned(),
+                        };
+
+                        tracing::info!(%peer_id, %quantity, %order_id, %reason, "Order rejected");
 
                         executor
                             .execute(order_id, |cfd| {
-                                cfd.reject_contract_setup(anyhow::anyhow!("Unknown"))
+                                cfd.reject_contract_setup(anyhow::anyhow!(reason))
                             })
                             .await?;
 
@@ -248,7 +270,10 @@ impl Actor {
                 let (sink, stream) = framed.split();
 
                 let announcement = get_announcement
-                    .send(oracle::GetAnnouncements(vec![oracle_event_id]))
+                    .send(oracle::GetAnnouncements {
+                        oracle,
+                        event_ids: vec![oracle_event_id],
+                    })
                     .await??;
 
                 let dlc = contract_setup::new(
@@ -319,32 +344,27 @@ impl Actor {
             .context("Can't make decision on nonexistent order {id}")?;
 
         sender
-            .send(msg.into())
+            .send(msg)
             .map_err(|_| anyhow!("Can't deliver decision on taking order {id}"))?;
 
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Decision {
     Accept(OrderId),
     Reject(OrderId),
+    /// Reject the order, recording why.
+    ///
+    /// The taker is only told that the order was rejected.
+    RejectWithReason(OrderId, String),
 }
 
 impl Decision {
     fn id(&self) -> OrderId {
         match self {
-            Decision::Accept(id) | Decision::Reject(id) => *id,
-        }
-    }
-}
-
-impl From<Decision> for protocol::Decision {
-    fn from(decision: Decision) -> Self {
-        match decision {
-            Decision::Accept(_) => protocol::Decision::Accept,
-            Decision::Reject(_) => protocol::Decision::Reject,
+            Decision::Accept(id) | Decision::Reject(id) | Decision::RejectWithReason(id, _) => *id,
         }
     }
 }
@@ -353,7 +373,7 @@ impl fmt::Display for Decision {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             Decision::Accept(_) => "Accept",
-            Decision::Reject(_) => "Reject",
+            Decision::Reject(_) | Decision::RejectWithReason(..) => "Reject",
         };
 
         s.fmt(f)
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use bdk::bitcoin::Amount;
 use futures::StreamExt;
 use model::calculate_margin;
+use model::libp2p::PeerId;
 use model::CfdEvent;
 use model::ClosedCfd;
 use model::ContractSymbol;
@@ -18,12 +19,14 @@ use model::Role;
 use model::Settlement;
 use sqlite_db;
 use std::collections::HashMap;
-use strum::IntoEnumIterator;
+use std::collections::HashSet;
 use xtra_productivity::xtra_productivity;
 use xtras::SendAsyncNext;
 
 pub struct Actor {
     db: sqlite_db::Connection,
+    /// The contract symbols of the catalogue, whose metrics are reported even without CFDs.
+    contract_symbols: Vec<ContractSymbol>,
     state: State,
 }
 
@@ -34,9 +37,10 @@ struct State {
 }
 
 impl Actor {
-    pub fn new(db: sqlite_db::Connection) -> Self {
+    pub fn new(db: sqlite_db::Connection, contract_symbols: Vec<ContractSymbol>) -> Self {
         Self {
             db,
+            contract_symbols,
             state: State::default(),
         }
     }
@@ -73,7 +77,13 @@ impl Actor {
 
         self.state.cfds = cfds;
 
-        for symbol in ContractSymbol::iter() {
+        let symbols = self
+            .contract_symbols
+            .iter()
+            .copied()
+            .chain(self.state.cfds.values().map(|cfd| cfd.contract_symbol))
+            .collect::<HashSet<_>>();
+        for symbol in symbols {
             metrics::update_position_metrics(&self.state.cfds, symbol);
         }
     }
@@ -84,10 +94,18 @@ impl Actor {
             return;
         };
 
-        for symbol in ContractSymbol::iter() {
-            metrics::update_position_metrics(&self.state.cfds, symbol)
+        if let Some(cfd) = self.state.cfds.get(&msg.0) {
+            metrics::update_position_metrics(&self.state.cfds, cfd.contract_symbol)
         }
     }
+
+    async fn handle(&mut self, msg: GetOpenPositions) -> OpenPositions {
+        self.state.open_positions(msg.0)
+    }
+
+    async fn handle(&mut self, msg: GetExposure) -> Exposure {
+        self.state.exposure(msg.excluding)
+    }
 }
 
 impl State {
@@ -97,6 +115,51 @@ impl State {
 
         Ok(())
     }
+
+    fn open_positions(&self, symbol: ContractSymbol) -> OpenPositions {
+        self.cfds
+            .values()
+            .filter(|cfd| cfd.contract_symbol == symbol)
+            .filter(|cfd| matches!(cfd.state, AggregatedState::New | AggregatedState::Open))
+            .fold(OpenPositions::default(), |positions, cfd| {
+                match cfd.position {
+                    Position::Long => OpenPositions {
+                        long: positions.long + cfd.quantity,
+                        ..positions
+                    },
+                    Position::Short => OpenPositions {
+                        short: positions.short + cfd.quantity,
+                        ..positions
+                    },
+                }
+            })
+    }
+
+    fn exposure(&self, excluding: Option<OrderId>) -> Exposure {
+        let mut exposure = Exposure::default();
+
+        for cfd in self
+            .cfds
+            .values()
+            .filter(|cfd| Some(cfd.id) != excluding)
+            .filter(|cfd| matches!(cfd.state, AggregatedState::New | AggregatedState::Open))
+        {
+            let positions = exposure.positions.entry(cfd.contract_symbol).or_default();
+            match cfd.position {
+                Position::Long => positions.long = positions.long + cfd.quantity,
+                Position::Short => positions.short = positions.short + cfd.quantity,
+            }
+
+            if let Some(peer_id) = cfd.counterparty_peer_id {
+                let contracts = exposure.per_taker.entry(peer_id).or_insert(Contracts::ZERO);
+                *contracts = *contracts + cfd.quantity;
+            }
+
+            exposure.margin += cfd.margin;
+        }
+
+        exposure
+    }
 }
 
 #[derive(Debug)]
@@ -106,6 +169,46 @@ struct Initialize;
 #[derive(Clone, Copy)]
 pub struct CfdChanged(pub OrderId);
 
+/// Get the quantity of our open positions in the given contract.
+#[derive(Clone, Copy)]
+pub struct GetOpenPositions(pub ContractSymbol);
+
+/// The quantity of our open (or about to be opened) positions per side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenPositions {
+    pub long: Contracts,
+    pub short: Contracts,
+}
+
+impl Default for OpenPositions {
+    fn default() -> Self {
+        Self {
+            long: Contracts::ZERO,
+            short: Contracts::ZERO,
+        }
+    }
+}
+
+/// Get our exposure across all open (or about to be opened) positions.
+#[derive(Clone, Copy)]
+pub struct GetExposure {
+    /// A CFD to leave out, e.g. because it is the one being decided on.
+    pub excluding: Option<OrderId>,
+}
+
+/// Our exposure across all open (or about to be opened) positions.
+#[derive(Debug, Clone, Default)]
+pub struct Exposure {
+    pub positions: HashMap<ContractSymbol, OpenPositions>,
+    /// The number of contracts held with each taker, across all contract symbols.
+    ///
+    /// Takers are identified by their peer ID because takers on the current order protocol all
+    /// share the same placeholder `Identity`.
+    pub per_taker: HashMap<PeerId, Contracts>,
+    /// The sum of the margin we locked up.
+    pub margin: Amount,
+}
+
 /// Read-model of the CFD for the position metrics actor.
 #[derive(Clone, Copy)]
 pub struct Cfd {
@@ -117,6 +220,7 @@ pub struct Cfd {
 
     state: AggregatedState,
     counterparty_network_identity: Identity,
+    counterparty_peer_id: Option<PeerId>,
 
     contract_symbol: ContractSymbol,
     version: u32,
@@ -163,6 +267,7 @@ impl sqlite_db::CfdAggregate for Cfd {
             margin_counterparty,
             state: AggregatedState::New,
             counterparty_network_identity: cfd.counterparty_network_identity,
+            counterparty_peer_id: cfd.counterparty_peer_id,
             contract_symbol: cfd.contract_symbol,
             version: 0,
         }
@@ -217,6 +322,30 @@ impl Cfd {
                 state: AggregatedState::Closed,
                 ..self
             },
+            PartialSettlementStarted { .. }
+            | PartialSettlementSigned { .. }
+            | PartialSettlementCompleted { dlc: None, .. }
+            | PartialSettlementRejected
+            | PartialSettlementFailed => Self {
+                // should still be open
+                ..self
+            },
+            PartialSettlementCompleted {
+                quantity: settled, ..
+            } => {
+                let remaining = self.quantity - settled;
+
+                Self {
+                    quantity: remaining,
+                    margin: self.margin_of(remaining, self.margin),
+                    margin_counterparty: self.margin_of(remaining, self.margin_counterparty),
+                    ..self
+                }
+            }
+            FeeBumped { .. } => Self {
+                // fee bumps don't change the state of a position
+                ..self
+            },
             ManualCommit { .. } | CommitConfirmed => Self {
                 // we don't know yet if the position will be closed immediately (e.g. through
                 // punishing) or a bit later after the oracle has attested to the price
@@ -230,7 +359,7 @@ impl Cfd {
                 state: AggregatedState::Refunded,
                 ..self
             },
-            RevokeConfirmed => Self {
+            RevokeConfirmed | RevokedCommitPublished { .. } | PunishConfirmed => Self {
                 // the other party was punished, we are done here!
                 state: AggregatedState::Closed,
                 ..self
@@ -265,6 +394,16 @@ impl Cfd {
             },
         }
     }
+
+    /// The part of `margin` that belongs to `remaining` of our contracts.
+    ///
+    /// The margin is proportional to the quantity for a given price and leverage.
+    fn margin_of(&self, remaining: Contracts, margin: Amount) -> Amount {
+        let sat =
+            margin.as_sat() as u128 * remaining.to_u64() as u128 / self.quantity.to_u64() as u128;
+
+        Amount::from_sat(sat as u64)
+    }
 }
 
 impl sqlite_db::ClosedCfdAggregate for Cfd {
@@ -275,6 +414,7 @@ impl sqlite_db::ClosedCfdAggregate for Cfd {
             n_contracts: quantity,
             settlement,
             counterparty_network_identity,
+            counterparty_peer_id,
             role,
             taker_leverage,
             initial_price,
@@ -283,7 +423,9 @@ impl sqlite_db::ClosedCfdAggregate for Cfd {
         } = closed_cfd;
 
         let state = match settlement {
-            Settlement::Collaborative { .. } | Settlement::Cet { .. } => AggregatedState::Closed,
+            Settlement::Collaborative { .. }
+            | Settlement::Cet { .. }
+            | Settlement::Punish { .. } => AggregatedState::Closed,
             Settlement::Refund { .. } => AggregatedState::Refunded,
         };
 
@@ -308,6 +450,7 @@ impl sqlite_db::ClosedCfdAggregate for Cfd {
             margin_counterparty,
             state,
             counterparty_network_identity,
+            counterparty_peer_id: Some(counterparty_peer_id),
             contract_symbol,
             version: 0,
         }
@@ -323,6 +466,7 @@ impl sqlite_db::FailedCfdAggregate for Cfd {
             n_contracts: quantity,
             kind,
             counterparty_network_identity,
+            counterparty_peer_id,
             role,
             taker_leverage,
             initial_price,
@@ -355,6 +499,7 @@ impl sqlite_db::FailedCfdAggregate for Cfd {
             margin_counterparty,
             state,
             counterparty_network_identity,
+            counterparty_peer_id: Some(counterparty_peer_id),
             contract_symbol: cfd.contract_symbol,
             version: 0,
         }
@@ -372,6 +517,7 @@ mod metrics {
     use model::Position;
     use rust_decimal::prelude::ToPrimitive;
     use std::collections::HashMap;
+    use std::collections::HashSet;
 
     const POSITION_LABEL: &str = "position";
     const POSITION_LONG_LABEL: &str = "long";
@@ -0,0 +1,68 @@
+use crate::into_price_feed_symbol;
+use tokio_extras::Tasks;
+use xtra::prelude::MessageChannel;
+use xtra::Address;
+use xtra_bitmex_price_feed::median;
+use xtra_bitmex_price_feed::replay;
+use xtra_bitmex_price_feed::Error;
+use xtra_bitmex_price_feed::GetLatestQuotes;
+use xtra_bitmex_price_feed::LatestQuotes;
+use xtra_bitmex_price_feed::Network;
+use xtra_bitmex_price_feed::Source;
+use xtras::supervisor::always_restart;
+use xtras::supervisor::Supervisor;
+
+/// The price feed that is handed to the actor system.
+pub type Actor = median::Actor;
+
+/// Spawn a supervised actor for every source and aggregate them into a single price feed.
+///
+/// With a single source the aggregate simply forwards its quotes.
+pub fn spawn(
+    sources: &[Source],
+    contract_symbols: &[model::ContractSymbol],
+    network: Network,
+    tasks: &mut Tasks,
+) -> Address<Actor> {
+    let instruments = contract_symbols
+        .iter()
+        .copied()
+        .map(into_price_feed_symbol)
+        .collect::<Vec<_>>();
+
+    let channels = sources
+        .iter()
+        .map(|source| -> MessageChannel<GetLatestQuotes, LatestQuotes> {
+            match source.clone() {
+                Source::Bitmex => {
+                    let instruments = instruments.clone();
+                    let (supervisor, address) = Supervisor::with_policy(
+                        move || xtra_bitmex_price_feed::Actor::new(network, instruments.clone()),
+                        always_restart::<Error>(),
+                    );
+                    tasks.add(supervisor.run_log_summary());
+
+                    address.into()
+                }
+                Source::File(path) => {
+                    let instruments = instruments.clone();
+                    let (supervisor, address) = Supervisor::with_policy(
+                        move || replay::Actor::new(path.clone(), instruments.clone()),
+                        always_restart::<Error>(),
+                    );
+                    tasks.add(supervisor.run_log_summary());
+
+                    address.into()
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (supervisor, address) = Supervisor::with_policy(
+        move || median::Actor::new(channels.clone()),
+        always_restart::<Error>(),
+    );
+    tasks.add(supervisor.run_log_summary());
+
+    address
+}
@@ -1,12 +1,17 @@
+use crate::cfd_backup;
+use crate::maker_request;
 use crate::monitor::MonitorAfterContractSetup;
 use crate::monitor::MonitorAfterRollover;
 use crate::monitor::MonitorCetFinality;
 use crate::monitor::MonitorCollaborativeSettlement;
+use crate::monitor::MonitorPartialSettlement;
+use crate::monitor::MonitorPunishFinality;
 use crate::monitor::TransactionKind;
 use crate::monitor::TryBroadcastTransaction;
 use crate::oracle;
 use crate::position_metrics;
 use crate::projection;
+use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
 use model::CfdEvent;
@@ -28,7 +33,13 @@ pub struct Actor {
     monitor_after_rollover: MessageChannel<MonitorAfterRollover, ()>,
     monitor_cet_finality: MessageChannel<MonitorCetFinality, Result<()>>,
     monitor_collaborative_settlement: MessageChannel<MonitorCollaborativeSettlement, ()>,
+    monitor_partial_settlement: MessageChannel<MonitorPartialSettlement, ()>,
+    monitor_punish_finality: MessageChannel<MonitorPunishFinality, Result<()>>,
     monitor_attestation: MessageChannel<oracle::MonitorAttestations, ()>,
+    backup_cfd: MessageChannel<cfd_backup::BackupCfd, Result<()>>,
+    remove_cfd_backup: MessageChannel<cfd_backup::RemoveBackup, ()>,
+    /// Only the taker keeps requests of the maker which a change to the CFD supersedes.
+    cfd_changed_maker_requests: Option<MessageChannel<maker_request::taker::CfdChanged, ()>>,
 }
 
 pub struct Event(CfdEvent);
@@ -51,7 +62,12 @@ impl Actor {
         monitor_after_rollover: MessageChannel<MonitorAfterRollover, ()>,
         monitor_cet_finality: MessageChannel<MonitorCetFinality, Result<()>>,
         monitor_collaborative_settlement: MessageChannel<MonitorCollaborativeSettlement, ()>,
+        monitor_partial_settlement: MessageChannel<MonitorPartialSettlement, ()>,
+        monitor_punish_finality: MessageChannel<MonitorPunishFinality, Result<()>>,
         monitor_attestation: MessageChannel<oracle::MonitorAttestations, ()>,
+        backup_cfd: MessageChannel<cfd_backup::BackupCfd, Result<()>>,
+        remove_cfd_backup: MessageChannel<cfd_backup::RemoveBackup, ()>,
+        cfd_changed_maker_requests: Option<MessageChannel<maker_request::taker::CfdChanged, ()>>,
     ) -> Self {
         Self {
             db,
@@ -63,7 +79,12 @@ impl Actor {
             monitor_after_rollover,
             monitor_cet_finality,
             monitor_collaborative_settlement,
+            monitor_partial_settlement,
+            monitor_punish_finality,
             monitor_attestation,
+            backup_cfd,
+            remove_cfd_backup,
+            cfd_changed_maker_requests,
         }
     }
 }
@@ -77,6 +98,7 @@ impl Actor {
         self.db.append_event(event.clone()).await?;
 
         // 2. Post process event
+        let mut dlc_changed = false;
         use EventKind::*;
         match event.event {
             ContractSetupCompleted { dlc: Some(dlc), .. } => {
@@ -97,9 +119,52 @@ impl Actor {
 
                 self.monitor_attestation
                     .send_async_safe(oracle::MonitorAttestations {
+                        order_id: event.id,
+                        event_ids: dlc.event_ids(),
+                    })
+                    .await?;
+
+                dlc_changed = true;
+            }
+            PartialSettlementSigned { dlc, .. } => {
+                self.monitor_partial_settlement
+                    .send_async_safe(MonitorPartialSettlement {
+                        order_id: event.id,
+                        tx: (dlc.lock.0.txid(), dlc.lock.1.script_pubkey()),
+                    })
+                    .await?;
+            }
+            PartialSettlementCompleted {
+                spend_tx,
+                dlc: Some(dlc),
+                ..
+            } => {
+                // Both parties hold the signed transaction, either of them can publish it. If we
+                // only adopted the partial settlement once it was confirmed, it is already on chain.
+                let span = tracing::debug_span!(
+                    "Broadcast partial settlement TX",
+                    order_id = %event.id
+                );
+                self.try_broadcast_transaction
+                    .send_async_safe(TryBroadcastTransaction {
+                        tx: spend_tx,
+                        kind: TransactionKind::PartialSettlement,
+                    })
+                    .instrument(span)
+                    .await?;
+
+                self.monitor_after_contract_setup
+                    .send_async_safe(MonitorAfterContractSetup::new(event.id, &dlc))
+                    .await?;
+
+                self.monitor_attestation
+                    .send_async_safe(oracle::MonitorAttestations {
+                        order_id: event.id,
                         event_ids: dlc.event_ids(),
                     })
                     .await?;
+
+                dlc_changed = true;
             }
             CollaborativeSettlementCompleted {
                 spend_tx, script, ..
@@ -185,9 +250,17 @@ impl Actor {
 
                 self.monitor_attestation
                     .send_async_safe(oracle::MonitorAttestations {
+                        order_id: event.id,
                         event_ids: dlc.event_ids(),
                     })
                     .await?;
+
+                dlc_changed = true;
+            }
+            CollaborativeSettlementConfirmed | CetConfirmed | RefundConfirmed | PunishConfirmed => {
+                self.remove_cfd_backup
+                    .send_async_safe(cfd_backup::RemoveBackup { order_id: event.id })
+                    .await?;
             }
             RefundTimelockExpired { refund_tx: tx } => {
                 let span = tracing::debug_span!("Broadcast refund TX", order_id = %event.id);
@@ -199,9 +272,35 @@ impl Actor {
                     .instrument(span)
                     .await?;
             }
+            RevokedCommitPublished { punish_tx } => {
+                let _ = self
+                    .monitor_punish_finality
+                    .send_async_safe(MonitorPunishFinality {
+                        order_id: event.id,
+                        punish_tx: punish_tx.clone(),
+                    })
+                    .await?;
+                let span = tracing::debug_span!("Broadcast punish TX", order_id = %event.id);
+                self.try_broadcast_transaction
+                    .send_async_safe(TryBroadcastTransaction {
+                        tx: punish_tx,
+                        kind: TransactionKind::Punish,
+                    })
+                    .instrument(span)
+                    .await?;
+            }
+            FeeBumped { child_tx, .. } => {
+                let span = tracing::debug_span!("Broadcast CPFP TX", order_id = %event.id);
+                self.try_broadcast_transaction
+                    .send_async_safe(TryBroadcastTransaction {
+                        tx: child_tx,
+                        kind: TransactionKind::Cpfp,
+                    })
+                    .instrument(span)
+                    .await?;
+            }
             ContractSetupCompleted { dlc: None, .. }
             | RolloverCompleted { dlc: None, .. }
-            | RefundConfirmed
             | CollaborativeSettlementStarted { .. }
             | ContractSetupStarted
             | ContractSetupFailed
@@ -214,11 +313,13 @@ impl Actor {
             | LockConfirmed
             | LockConfirmedAfterFinality
             | CommitConfirmed
-            | CetConfirmed
             | RevokeConfirmed
-            | CollaborativeSettlementConfirmed
             | CollaborativeSettlementRejected
             | CollaborativeSettlementFailed
+            | PartialSettlementCompleted { dlc: None, .. }
+            | PartialSettlementStarted { .. }
+            | PartialSettlementRejected
+            | PartialSettlementFailed
             | CetTimelockExpiredPriorOracleAttestation => {}
         }
 
@@ -232,6 +333,21 @@ impl Actor {
             .send_async_safe(position_metrics::CfdChanged(event.id))
             .await?;
 
+        // 5. Drop the pending maker request on the CFD
+        if let Some(cfd_changed_maker_requests) = &self.cfd_changed_maker_requests {
+            cfd_changed_maker_requests
+                .send_async_safe(maker_request::taker::CfdChanged(event.id))
+                .await?;
+        }
+
+        // 6. Back up the new DLC, failing the command if we cannot
+        if dlc_changed {
+            self.backup_cfd
+                .send(cfd_backup::BackupCfd { order_id: event.id })
+                .await
+                .context("CFD backup actor is disconnected")??;
+        }
+
         Ok(())
     }
 }
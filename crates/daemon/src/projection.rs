@@ -1,3 +1,5 @@
+use crate::into_price_feed_symbol;
+use crate::maker_request;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -21,6 +23,7 @@ use model::calculate_short_liquidation_price;
 use model::libp2p::PeerId;
 use model::long_and_short_leverage;
 use model::market_closing_price;
+use model::olivia::OracleConfig;
 use model::CfdEvent;
 use model::ClosedCfd;
 use model::ContractSymbol;
@@ -120,12 +123,13 @@ impl Actor {
         network: Network,
         price_feed: MessageChannel<GetLatestQuotes, xtra_bitmex_price_feed::LatestQuotes>,
         role: Role,
+        contract_symbols: Vec<ContractSymbol>,
         feed_senders: Arc<FeedSenders>,
     ) -> Self {
         Self {
             db,
             tx: Tx(feed_senders),
-            state: State::new(network),
+            state: State::new(network, contract_symbols),
             price_feed,
             role,
         }
@@ -149,6 +153,7 @@ pub struct Cfd {
     /// The taker leverage
     #[serde(rename = "leverage")]
     pub leverage_taker: Leverage,
+    #[serde(with = "contract_symbol_name")]
     pub contract_symbol: ContractSymbol,
     pub position: Position,
     #[serde(with = "round_to_two_dp")]
@@ -194,6 +199,9 @@ pub struct Cfd {
     #[serde(with = "round_to_two_dp::opt")]
     pub pending_settlement_proposal_price: Option<Price>,
 
+    /// Settlement or rollover the maker asked for, awaiting our approval.
+    pub maker_request: Option<maker_request::Request>,
+
     #[serde(skip)]
     #[derivative(PartialEq = "ignore")]
     aggregated: Aggregated,
@@ -222,6 +230,10 @@ pub struct Aggregated {
     cet: Option<Transaction>,
     /// If this is present, it should have been published.
     refund_tx: Option<Transaction>,
+    /// If this is present, it should have been published.
+    punish_tx: Option<Transaction>,
+    /// Child transactions bumping the fee of a published transaction.
+    fee_bumps: Vec<Txid>,
 
     /// If this is present the cet has not been published
     timelocked_cet: Option<Transaction>,
@@ -249,6 +261,8 @@ impl Aggregated {
             collab_settlement_tx: None,
             cet: None,
             refund_tx: None,
+            punish_tx: None,
+            fee_bumps: Vec::new(),
             timelocked_cet: None,
             commit_published: false,
             refund_published: false,
@@ -264,6 +278,11 @@ impl Aggregated {
             return Some(extract_payout_amount(tx, script));
         }
 
+        if let Some(tx) = &self.punish_tx {
+            let script = self.latest_dlc.as_ref()?.script_pubkey_for(role);
+            return Some(extract_payout_amount(tx, &script));
+        }
+
         if let Some(tx) = &self.refund_tx {
             let script = self.latest_dlc.as_ref()?.script_pubkey_for(role);
             return Some(extract_payout_amount(tx, &script));
@@ -413,6 +432,7 @@ impl Cfd {
             expiry_timestamp: None,
             counterparty: counterparty_peer_id.unwrap_or_else(PeerId::placeholder),
             pending_settlement_proposal_price: None,
+            maker_request: None,
             aggregated: Aggregated::new(fee_account),
             network,
         }
@@ -499,10 +519,45 @@ impl Cfd {
 
                 self.aggregated.state = CfdState::PendingClose;
             }
-            CollaborativeSettlementRejected => {
+            CollaborativeSettlementRejected
+            | PartialSettlementRejected
+            | PartialSettlementFailed => {
                 self.aggregated.settlement_state = None;
                 self.pending_settlement_proposal_price = None;
             }
+            PartialSettlementStarted { proposal } => {
+                self.aggregated.settlement_state = Some(ProtocolNegotiationState::Started);
+                if let Role::Maker = self.role {
+                    self.pending_settlement_proposal_price = Some(proposal.price);
+                };
+            }
+            PartialSettlementSigned { .. } => {}
+            PartialSettlementCompleted {
+                quantity: settled,
+                dlc,
+                ..
+            } => {
+                self.aggregated.settlement_state = None;
+                self.pending_settlement_proposal_price = None;
+
+                self.aggregated.fee_account = self
+                    .aggregated
+                    .fee_account
+                    .reduce(self.quantity - settled, self.quantity);
+                self.accumulated_fees = self.aggregated.fee_account.balance();
+                self.quantity = self.quantity - settled;
+
+                if let Some(dlc) = &dlc {
+                    (self.margin, self.margin_counterparty) = match self.role {
+                        Role::Maker => (dlc.maker_lock_amount, dlc.taker_lock_amount),
+                        Role::Taker => (dlc.taker_lock_amount, dlc.maker_lock_amount),
+                    };
+                }
+                self.aggregated.latest_dlc = dlc;
+
+                // The remaining contracts are locked by the partial settlement transaction
+                self.aggregated.state = CfdState::PendingOpen;
+            }
             CollaborativeSettlementFailed => {
                 self.aggregated.settlement_state = None;
                 self.pending_settlement_proposal_price = None;
@@ -570,9 +625,20 @@ impl Cfd {
                 self.aggregated.state = CfdState::PendingCommit;
             }
             RevokeConfirmed => {
-                // TODO: Implement revoked logic
+                // Legacy event, we don't know if the counterparty was punished
                 self.aggregated.state = CfdState::OpenCommitted;
             }
+            RevokedCommitPublished { punish_tx } => {
+                self.aggregated.punish_tx = Some(punish_tx);
+
+                self.aggregated.state = CfdState::PendingClose;
+            }
+            PunishConfirmed => {
+                self.aggregated.state = CfdState::Closed;
+            }
+            FeeBumped { child_tx, .. } => {
+                self.aggregated.fee_bumps.push(child_tx.txid());
+            }
         };
 
         self.state = self.aggregated.derive_cfd_state(self.role);
@@ -593,6 +659,19 @@ impl Cfd {
         if let Some(cet_url) = self.cet_url(self.network) {
             self.details.tx_url_list.insert(cet_url);
         }
+        if let Some(revoked_commit_tx_url) = self.revoked_commit_tx_url(self.network) {
+            self.details.tx_url_list.insert(revoked_commit_tx_url);
+        }
+        if let Some(punish_tx_url) = self.punish_tx_url(self.network) {
+            self.details.tx_url_list.insert(punish_tx_url);
+        }
+        for fee_bump_txid in self.aggregated.fee_bumps.iter() {
+            self.details.tx_url_list.insert(TxUrl::new(
+                *fee_bump_txid,
+                self.network,
+                TxLabel::FeeBump,
+            ));
+        }
 
         self.aggregated.version += 1;
 
@@ -690,6 +769,21 @@ impl Cfd {
         }
     }
 
+    /// Surface the pending maker request on an open CFD, along with the actions to decide on it.
+    fn with_maker_request(mut self, request: Option<maker_request::Request>) -> Self {
+        if self.state != CfdState::Open {
+            return self;
+        }
+
+        if let Some(request) = request {
+            self.maker_request = Some(request);
+            self.actions
+                .extend([CfdAction::AcceptMakerRequest, CfdAction::RejectMakerRequest]);
+        }
+
+        self
+    }
+
     fn derive_actions(&self) -> HashSet<CfdAction> {
         match (self.state, self.role) {
             (CfdState::PendingSetup, Role::Maker) => {
@@ -699,7 +793,10 @@ impl Cfd {
             (CfdState::ContractSetup, _) => HashSet::new(),
             (CfdState::Rejected, _) => HashSet::new(),
             (CfdState::PendingOpen, _) => HashSet::new(),
-            (CfdState::Open, _) => HashSet::from([CfdAction::Commit, CfdAction::Settle]),
+            (CfdState::Open, Role::Maker) => {
+                HashSet::from([CfdAction::Commit, CfdAction::Settle, CfdAction::RollOver])
+            }
+            (CfdState::Open, Role::Taker) => HashSet::from([CfdAction::Commit, CfdAction::Settle]),
             (CfdState::PendingCommit, _) => HashSet::new(),
             (CfdState::PendingCet, _) => HashSet::new(),
             (CfdState::PendingClose, _) => HashSet::new(),
@@ -781,16 +878,49 @@ impl Cfd {
 
         Some(url)
     }
+
+    /// Returns the URL to the revoked commit transaction spent by our punish transaction.
+    fn revoked_commit_tx_url(&self, network: Network) -> Option<TxUrl> {
+        let tx = self.aggregated.punish_tx.as_ref()?;
+        let revoked_commit_txid = tx.input.first()?.previous_output.txid;
+
+        let url = TxUrl::new(revoked_commit_txid, network, TxLabel::Commit);
+
+        Some(url)
+    }
+
+    fn punish_tx_url(&self, network: Network) -> Option<TxUrl> {
+        let tx = self.aggregated.punish_tx.as_ref()?;
+        let dlc = self.aggregated.latest_dlc.as_ref()?;
+
+        let url = TxUrl::from_transaction(
+            tx,
+            &dlc.script_pubkey_for(self.role),
+            network,
+            TxLabel::Punish,
+        );
+
+        Some(url)
+    }
 }
 
 /// Internal struct to keep all the senders around in one place
 struct Tx(Arc<FeedSenders>);
 
 impl Tx {
-    fn send_cfds_update(&self, cfds: &HashMap<OrderId, Cfd>, quotes: &LatestQuotes) {
+    fn send_cfds_update(
+        &self,
+        cfds: &HashMap<OrderId, Cfd>,
+        quotes: &LatestQuotes,
+        maker_requests: &HashMap<OrderId, maker_request::Request>,
+    ) {
         let cfds_with_quote = cfds
             .iter()
-            .map(|(_, cfd)| cfd.clone().with_current_quote(Some(quotes)))
+            .map(|(order_id, cfd)| {
+                cfd.clone()
+                    .with_current_quote(Some(quotes))
+                    .with_maker_request(maker_requests.get(order_id).copied())
+            })
             .sorted_by(|a, b| {
                 Ord::cmp(
                     &b.aggregated.creation_timestamp,
@@ -816,10 +946,14 @@ impl Tx {
 /// Internal struct to keep state in one place
 struct State {
     network: Network,
+    /// The contract symbols of the catalogue, in the order they are shown in.
+    contract_symbols: Vec<ContractSymbol>,
     latest_quotes: LatestQuotes,
     offers: MakerOffers,
     /// All hydrated CFDs.
     cfds: Option<HashMap<OrderId, Cfd>>,
+    /// Requests of the maker awaiting our approval.
+    maker_requests: HashMap<OrderId, maker_request::Request>,
 }
 
 impl sqlite_db::CfdAggregate for Cfd {
@@ -932,6 +1066,21 @@ impl sqlite_db::ClosedCfdAggregate for Cfd {
                     );
                     (None, payout, CfdState::Refunded)
                 }
+                Settlement::Punish {
+                    commit_txid,
+                    txid,
+                    vout,
+                    payout,
+                } => {
+                    tx_url_list.insert(
+                        TxUrl::new(commit_txid, network, TxLabel::Commit).with_output_index(0),
+                    );
+
+                    tx_url_list.insert(
+                        TxUrl::new(txid, network, TxLabel::Punish).with_output_index(vout.into()),
+                    );
+                    (None, payout, CfdState::Closed)
+                }
             };
 
             (CfdDetails { tx_url_list }, price, payout, state)
@@ -971,6 +1120,7 @@ impl sqlite_db::ClosedCfdAggregate for Cfd {
             expiry_timestamp: Some(expiry_timestamp),
             counterparty: counterparty_peer_id,
             pending_settlement_proposal_price: None,
+            maker_request: None,
             aggregated,
             network,
         }
@@ -1056,6 +1206,7 @@ impl sqlite_db::FailedCfdAggregate for Cfd {
             expiry_timestamp: None,
             counterparty: counterparty_peer_id,
             pending_settlement_proposal_price: None,
+            maker_request: None,
             aggregated,
             network,
         }
@@ -1063,12 +1214,14 @@ impl sqlite_db::FailedCfdAggregate for Cfd {
 }
 
 impl State {
-    fn new(network: Network) -> Self {
+    fn new(network: Network, contract_symbols: Vec<ContractSymbol>) -> Self {
         Self {
             network,
+            contract_symbols,
             latest_quotes: LatestQuotes::default(),
             cfds: None,
             offers: MakerOffers::default(),
+            maker_requests: HashMap::default(),
         }
     }
 
@@ -1089,31 +1242,28 @@ impl State {
         self.latest_quotes = quotes;
     }
 
+    /// Replace the price levels of each side of the order book for which there are new offers.
     fn update_offers(&mut self, new_offers: Vec<CfdOffer>) {
-        for new_offer in new_offers.into_iter() {
-            match &new_offer {
-                CfdOffer {
-                    contract_symbol: ContractSymbol::BtcUsd,
-                    position_maker: Position::Long,
-                    ..
-                } => self.offers.btcusd_long = Some(new_offer),
-                CfdOffer {
-                    contract_symbol: ContractSymbol::BtcUsd,
-                    position_maker: Position::Short,
-                    ..
-                } => self.offers.btcusd_short = Some(new_offer),
-                CfdOffer {
-                    contract_symbol: ContractSymbol::EthUsd,
-                    position_maker: Position::Long,
-                    ..
-                } => self.offers.ethusd_long = Some(new_offer),
-                CfdOffer {
-                    contract_symbol: ContractSymbol::EthUsd,
-                    position_maker: Position::Short,
-                    ..
-                } => self.offers.ethusd_short = Some(new_offer),
-            }
-        }
+        let sides = new_offers
+            .iter()
+            .map(|offer| (offer.contract_symbol, offer.position_maker))
+            .collect::<HashSet<_>>();
+
+        let offers = self
+            .offers
+            .all()
+            .iter()
+            .filter(|offer| !sides.contains(&(offer.contract_symbol, offer.position_maker)))
+            .cloned()
+            .chain(new_offers)
+            .collect();
+
+        self.offers = MakerOffers::new(offers, &self.contract_symbols);
+    }
+
+    /// Replace the order book with the offers across all makers.
+    fn replace_offers(&mut self, new_offers: Vec<CfdOffer>) {
+        self.offers = MakerOffers::new(new_offers, &self.contract_symbols);
     }
 }
 
@@ -1144,6 +1294,7 @@ impl Actor {
                 .as_ref()
                 .expect("we initialized the state above; qed"),
             &self.state.latest_quotes,
+            &self.state.maker_requests,
         );
     }
 
@@ -1159,6 +1310,7 @@ impl Actor {
                 .as_ref()
                 .expect("update_cfd fails if the CFDs have not been initialized yet"),
             &self.state.latest_quotes,
+            &self.state.maker_requests,
         );
     }
 
@@ -1182,6 +1334,31 @@ impl Actor {
         }
     }
 
+    fn handle(&mut self, msg: Update<HashMap<PeerId, Vec<model::Offer>>>) {
+        let new_offers = msg
+            .0
+            .into_iter()
+            .flat_map(|(maker_peer_id, offers)| {
+                offers.into_iter().map(move |offer| (maker_peer_id, offer))
+            })
+            .filter_map(
+                |(maker_peer_id, offer)| match CfdOffer::new(offer, self.role) {
+                    Ok(offer) => Some(offer.with_maker_peer_id(maker_peer_id)),
+                    Err(e) => {
+                        tracing::warn!("Failed to build CfdOffer from model::Offer: {e:#}");
+                        None
+                    }
+                },
+            )
+            .collect_vec();
+
+        self.state.replace_offers(new_offers);
+
+        if let Err(e) = self.tx.send_offer_update(self.state.offers.clone()) {
+            tracing::error!("Failed to propagate offer update: {e:#}");
+        }
+    }
+
     fn handle(&mut self, msg: Update<LatestQuotes>) {
         self.state.update_quotes(msg.0.clone());
         self.tx.send_quotes_update(msg.0.clone());
@@ -1193,7 +1370,30 @@ impl Actor {
             .context("Cannot update CFDs with new quote until they are initialized.")
         {
             Ok(hydrated_cfds) => {
-                self.tx.send_cfds_update(hydrated_cfds, &msg.0);
+                self.tx
+                    .send_cfds_update(hydrated_cfds, &msg.0, &self.state.maker_requests);
+            }
+            Err(e) => {
+                tracing::debug!("{e:#}");
+            }
+        };
+    }
+
+    fn handle(&mut self, msg: Update<HashMap<OrderId, maker_request::Request>>) {
+        self.state.maker_requests = msg.0;
+
+        match self
+            .state
+            .cfds
+            .as_ref()
+            .context("Cannot show maker requests until the CFDs are initialized.")
+        {
+            Ok(hydrated_cfds) => {
+                self.tx.send_cfds_update(
+                    hydrated_cfds,
+                    &self.state.latest_quotes,
+                    &self.state.maker_requests,
+                );
             }
             Err(e) => {
                 tracing::debug!("{e:#}");
@@ -1211,6 +1411,7 @@ impl xtra::Actor for Actor {
 
         tokio_extras::spawn(&this.clone(), {
             let price_feed = self.price_feed.clone();
+            let contract_symbols = self.state.contract_symbols.clone();
 
             async move {
                 loop {
@@ -1224,7 +1425,7 @@ impl xtra::Actor for Actor {
                         match latest {
                             Ok(quotes) => {
                                 let _ = this
-                                    .send(Update(into_projection_quotes(quotes)))
+                                    .send(Update(into_projection_quotes(quotes, &contract_symbols)))
                                     .instrument(span)
                                     .await;
                             }
@@ -1266,34 +1467,90 @@ impl From<xtra_bitmex_price_feed::Quote> for Quote {
 
 pub type LatestQuotes = HashMap<ContractSymbol, Quote>;
 
-/// Converts between ContractSymbol types
-fn as_contract_symbol(symbol: &xtra_bitmex_price_feed::ContractSymbol) -> ContractSymbol {
-    match symbol {
-        xtra_bitmex_price_feed::ContractSymbol::BtcUsd => ContractSymbol::BtcUsd,
-        xtra_bitmex_price_feed::ContractSymbol::EthUsd => ContractSymbol::EthUsd,
-    }
-}
-
 /// Converts quotes from xtra_bitmex_price_feed into projection types
-fn into_projection_quotes(latest_quotes: xtra_bitmex_price_feed::LatestQuotes) -> LatestQuotes {
-    latest_quotes
+///
+/// Quotes for instruments that none of the `contract_symbols` is priced by are dropped.
+fn into_projection_quotes(
+    latest_quotes: xtra_bitmex_price_feed::LatestQuotes,
+    contract_symbols: &[ContractSymbol],
+) -> LatestQuotes {
+    contract_symbols
         .iter()
-        .map(|(symbol, quote)| (as_contract_symbol(symbol), (*quote).into()))
+        .filter_map(|&symbol| {
+            let quote = latest_quotes.get(&into_price_feed_symbol(symbol))?;
+
+            Some((symbol, (*quote).into()))
+        })
         .collect()
 }
 
+/// The order book, made up of any number of price levels per contract symbol and position.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
-pub struct MakerOffers {
-    pub btcusd_long: Option<CfdOffer>,
-    pub btcusd_short: Option<CfdOffer>,
-    pub ethusd_long: Option<CfdOffer>,
-    pub ethusd_short: Option<CfdOffer>,
+#[serde(transparent)]
+pub struct MakerOffers(Vec<CfdOffer>);
+
+impl MakerOffers {
+    /// Order `offers` by contract symbol and position, the best price for the taker first.
+    ///
+    /// Contract symbols come in the order of `contract_symbols`. Offers on any other contract
+    /// symbol are dropped, we cannot take them.
+    fn new(offers: Vec<CfdOffer>, contract_symbols: &[ContractSymbol]) -> Self {
+        let sides = contract_symbols
+            .iter()
+            .copied()
+            .cartesian_product([Position::Long, Position::Short]);
+
+        let offers = sides
+            .flat_map(|(contract_symbol, position_maker)| {
+                offers
+                    .iter()
+                    .filter(move |offer| {
+                        offer.contract_symbol == contract_symbol
+                            && offer.position_maker == position_maker
+                    })
+                    .cloned()
+                    .sorted_by(|a, b| position_maker.cmp_offer_prices(a.price, b.price))
+            })
+            .collect();
+
+        Self(offers)
+    }
+
+    /// All offers, the best price for the taker first on each side of the order book.
+    pub fn all(&self) -> &[CfdOffer] {
+        &self.0
+    }
+
+    /// The price levels of one side of the order book, the best price for the taker first.
+    pub fn levels(
+        &self,
+        contract_symbol: ContractSymbol,
+        position_maker: Position,
+    ) -> impl Iterator<Item = &CfdOffer> {
+        self.0.iter().filter(move |offer| {
+            offer.contract_symbol == contract_symbol && offer.position_maker == position_maker
+        })
+    }
+
+    /// The offer with the best price for the taker on one side of the order book.
+    pub fn best(
+        &self,
+        contract_symbol: ContractSymbol,
+        position_maker: Position,
+    ) -> Option<&CfdOffer> {
+        self.levels(contract_symbol, position_maker).next()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct CfdOffer {
     pub id: OfferId,
 
+    #[serde(with = "contract_symbol_name")]
     pub contract_symbol: ContractSymbol,
 
     #[serde(rename = "position")]
@@ -1336,6 +1593,14 @@ pub struct CfdOffer {
 
     pub creation_timestamp: Timestamp,
     pub settlement_time_interval_in_secs: u64,
+
+    /// The peer id of the maker who published the offer
+    ///
+    /// Only known on the taker side, where offers of several makers are merged into one feed.
+    pub maker_peer_id: Option<PeerId>,
+
+    /// The oracle attesting to the price of the contract
+    pub oracle: OracleConfig,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -1440,8 +1705,17 @@ impl CfdOffer {
             funding_rate_annualized_percent: AnnualisedFundingPercent::from(offer.funding_rate)
                 .to_string(),
             funding_rate_hourly_percent: HourlyFundingPercent::from(offer.funding_rate).to_string(),
+            maker_peer_id: None,
+            oracle: offer.oracle,
         })
     }
+
+    fn with_maker_peer_id(self, maker_peer_id: PeerId) -> Self {
+        Self {
+            maker_peer_id: Some(maker_peer_id),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -1479,6 +1753,9 @@ pub enum CfdAction {
     Settle,
     AcceptSettlement,
     RejectSettlement,
+    RollOver,
+    AcceptMakerRequest,
+    RejectMakerRequest,
 }
 
 mod round_to_two_dp {
@@ -1565,6 +1842,24 @@ mod round_to_two_dp {
     }
 }
 
+/// Contract symbols as the UI knows them: the built-in ones by wire name, any other by name.
+mod contract_symbol_name {
+    use super::*;
+    use model::Catalogue;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(
+        symbol: &ContractSymbol,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if Catalogue::default().symbols().contains(symbol) {
+            Serialize::serialize(symbol, serializer)
+        } else {
+            serializer.collect_str(symbol)
+        }
+    }
+}
+
 /// Construct a mempool.space URL for a given txid
 pub fn to_mempool_url(txid: Txid, network: Network) -> String {
     match network {
@@ -1621,6 +1916,8 @@ pub enum TxLabel {
     Cet,
     Refund,
     Collaborative,
+    Punish,
+    FeeBump,
 }
 
 struct AnnualisedFundingPercent(Decimal);
@@ -1670,6 +1967,7 @@ impl fmt::Display for HourlyFundingPercent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use model::Catalogue;
     use model::OfferId;
     use model::OpeningFee;
     use model::TxFeeRate;
@@ -1716,7 +2014,8 @@ mod tests {
             OpeningFee::new(Amount::from_sat(2000)),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         )
     }
 
@@ -1757,7 +2056,8 @@ mod tests {
             OpeningFee::new(Amount::ZERO),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         );
 
         let contract_setup_completed =
@@ -1927,4 +2227,147 @@ mod tests {
         // from a closed CFD
         assert_eq!(projection_open, projection_closed);
     }
+
+    #[test]
+    fn given_offers_from_several_makers_then_order_book_is_sorted_best_price_first() {
+        let maker_a = PeerId::random();
+        let maker_b = PeerId::random();
+        let offers = HashMap::from([
+            (
+                maker_a,
+                vec![
+                    dummy_offer(Position::Short, dec!(20_000)),
+                    dummy_offer(Position::Short, dec!(20_200)),
+                    dummy_offer(Position::Long, dec!(19_000)),
+                ],
+            ),
+            (
+                maker_b,
+                vec![
+                    dummy_offer(Position::Short, dec!(20_100)),
+                    dummy_offer(Position::Long, dec!(19_100)),
+                ],
+            ),
+        ]);
+        let offers = offers
+            .into_iter()
+            .flat_map(|(maker, offers)| {
+                offers.into_iter().map(move |offer| {
+                    CfdOffer::new(offer, Role::Taker)
+                        .unwrap()
+                        .with_maker_peer_id(maker)
+                })
+            })
+            .collect();
+
+        let mut state = State::new(Network::Testnet, Catalogue::default().symbols());
+        state.replace_offers(offers);
+
+        let btcusd_short = state
+            .offers
+            .best(ContractSymbol::BTC_USD, Position::Short)
+            .unwrap();
+        assert_eq!(btcusd_short.price, Price::new(dec!(20_000)).unwrap());
+        assert_eq!(btcusd_short.maker_peer_id, Some(maker_a));
+
+        let btcusd_long = state
+            .offers
+            .best(ContractSymbol::BTC_USD, Position::Long)
+            .unwrap();
+        assert_eq!(btcusd_long.price, Price::new(dec!(19_100)).unwrap());
+        assert_eq!(btcusd_long.maker_peer_id, Some(maker_b));
+
+        let short_levels = state
+            .offers
+            .levels(ContractSymbol::BTC_USD, Position::Short)
+            .map(|offer| offer.price.into_decimal())
+            .collect_vec();
+        assert_eq!(short_levels, vec![dec!(20_000), dec!(20_100), dec!(20_200)]);
+
+        assert!(state
+            .offers
+            .best(ContractSymbol::ETH_USD, Position::Long)
+            .is_none());
+    }
+
+    #[test]
+    fn given_new_offers_for_one_side_then_other_side_is_kept() {
+        let offer = |position_maker, price| {
+            CfdOffer::new(dummy_offer(position_maker, price), Role::Maker).unwrap()
+        };
+
+        let mut state = State::new(Network::Testnet, Catalogue::default().symbols());
+        state.update_offers(vec![
+            offer(Position::Long, dec!(19_000)),
+            offer(Position::Long, dec!(18_900)),
+            offer(Position::Short, dec!(20_000)),
+        ]);
+        state.update_offers(vec![offer(Position::Long, dec!(19_500))]);
+
+        let prices = state
+            .offers
+            .all()
+            .iter()
+            .map(|offer| (offer.position_maker, offer.price.into_decimal()))
+            .collect_vec();
+        assert_eq!(
+            prices,
+            vec![
+                (Position::Long, dec!(19_500)),
+                (Position::Short, dec!(20_000))
+            ]
+        );
+    }
+
+    #[test]
+    fn given_offer_on_symbol_outside_catalogue_then_it_is_dropped() {
+        let catalogue = Catalogue::new(vec![model::ContractConfig {
+            symbol: "SOLUSD".to_owned(),
+            payout_curve: model::PayoutCurve::Quanto,
+            multiplier: Some(dec!(0.0000001)),
+            oracle_index: "BSOL".to_owned(),
+            feed_instrument: "SOLUSD".to_owned(),
+        }])
+        .unwrap();
+        let solusd = catalogue.parse("SOLUSD").unwrap();
+        let offer = CfdOffer::new(
+            model::Offer {
+                contract_symbol: solusd,
+                ..dummy_offer(Position::Long, dec!(30))
+            },
+            Role::Taker,
+        )
+        .unwrap();
+
+        let mut state = State::new(Network::Testnet, Catalogue::default().symbols());
+        state.replace_offers(vec![offer.clone()]);
+        assert!(state.offers.all().is_empty());
+
+        let mut state = State::new(Network::Testnet, catalogue.symbols());
+        state.replace_offers(vec![offer]);
+        assert!(state.offers.best(solusd, Position::Long).is_some());
+    }
+
+    fn dummy_offer(position_maker: Position, price: Decimal) -> model::Offer {
+        model::Offer {
+            id: OfferId::default(),
+            contract_symbol: ContractSymbol::BTC_USD,
+            position_maker,
+            price: Price::new(price).unwrap(),
+            min_quantity: Contracts::new(100),
+            max_quantity: Contracts::new(1000),
+            leverage_choices: vec![Leverage::TWO],
+            creation_timestamp_maker: Timestamp::now(),
+            settlement_interval: SETTLEMENT_INTERVAL,
+            oracle_event_id: model::olivia::BitMexPriceEventId::with_20_digits(
+                OffsetDateTime::now_utc(),
+                ContractSymbol::BTC_USD,
+            ),
+            oracle: model::olivia::OracleConfig::olivia(),
+            tx_fee_rate: TxFeeRate::default(),
+            funding_rate: FundingRate::default(),
+            opening_fee: OpeningFee::default(),
+            lot_size: LotSize::new(100),
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::cfd_backup::BackupKey;
 use anyhow::anyhow;
 use anyhow::bail;
 use anyhow::Result;
@@ -20,6 +21,11 @@ pub const TAKER_IDENTITY_SEED_FILE: &str = "taker_id_seed";
 pub const MAKER_WALLET_SEED_FILE: &str = "maker_seed";
 pub const MAKER_IDENTITY_SEED_FILE: &str = "maker_id_seed";
 
+/// The seed files of the taker to include in backups.
+pub const TAKER_SEED_FILES: [&str; 2] = [TAKER_WALLET_SEED_FILE, TAKER_IDENTITY_SEED_FILE];
+/// The seed files of the maker to include in backups.
+pub const MAKER_SEED_FILES: [&str; 2] = [MAKER_WALLET_SEED_FILE, MAKER_IDENTITY_SEED_FILE];
+
 pub const RANDOM_SEED_SIZE: usize = 256;
 pub const APP_SEED_SIZE: usize = 32;
 
@@ -67,6 +73,16 @@ pub trait Seed {
         (x25519_dalek::PublicKey::from(&identity_sk), identity_sk)
     }
 
+    fn derive_cfd_backup_key(&self) -> BackupKey {
+        let mut key = [0u8; 32];
+
+        Hkdf::<Sha256>::new(None, &self.seed())
+            .expand(b"CFD_BACKUP_KEY", &mut key)
+            .expect("okm array is of correct length");
+
+        BackupKey::new(key)
+    }
+
     fn derive_ed25519_keypair(&self) -> ed25519::Keypair {
         let mut secret = [0u8; 32];
 
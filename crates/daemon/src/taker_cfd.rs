@@ -1,13 +1,17 @@
 use crate::collab_settlement;
+use crate::collab_settlement::partial::taker::SettlePartially;
 use crate::collab_settlement::taker::Settle;
+use crate::fee_estimate;
 use crate::order;
 use crate::projection;
 use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
 use model::libp2p::PeerId;
 use model::market_closing_price;
+use model::olivia::Oracles;
 use model::Cfd;
 use model::Contracts;
 use model::Identity;
@@ -16,9 +20,11 @@ use model::OfferId;
 use model::OrderId;
 use model::Price;
 use model::Role;
+use model::TxFeeRate;
 use sqlite_db;
 use std::collections::HashMap;
 use time::OffsetDateTime;
+use xtra::prelude::MessageChannel;
 use xtra_productivity::xtra_productivity;
 use xtras::SendAsyncSafe;
 
@@ -35,35 +41,61 @@ pub struct ProposeSettlement {
     pub bid: Price,
     pub ask: Price,
     pub quote_timestamp: String,
+    /// The number of contracts to settle, settles the entire position if `None`.
+    pub quantity: Option<Contracts>,
 }
 
 pub struct Actor {
     db: sqlite_db::Connection,
     projection_actor: xtra::Address<projection::Actor>,
     collab_settlement_actor: xtra::Address<collab_settlement::taker::Actor>,
+    partial_collab_settlement_actor: xtra::Address<collab_settlement::partial::taker::Actor>,
     order_actor: xtra::Address<order::taker::Actor>,
     offers: Offers,
-    maker_identity: Identity,
-    maker_peer_id: PeerId,
+    /// The makers we are connected to, by peer id.
+    makers: HashMap<PeerId, Identity>,
+    /// The oracles we trust, offers attested by other oracles cannot be taken.
+    oracles: Oracles,
+    /// Source of our own fee rate estimate, offers with fee rates far off it are ignored.
+    tx_fee_rate_estimate: Option<MessageChannel<fee_estimate::GetTxFeeRate, Option<TxFeeRate>>>,
 }
 
 impl Actor {
     pub fn new(
         db: sqlite_db::Connection,
         projection_actor: xtra::Address<projection::Actor>,
-        collab_settlement_actor: xtra::Address<collab_settlement::taker::Actor>,
+        (collab_settlement_actor, partial_collab_settlement_actor): (
+            xtra::Address<collab_settlement::taker::Actor>,
+            xtra::Address<collab_settlement::partial::taker::Actor>,
+        ),
         order_actor: xtra::Address<order::taker::Actor>,
-        maker_identity: Identity,
-        maker_peer_id: PeerId,
+        makers: HashMap<PeerId, Identity>,
+        oracles: Oracles,
+        tx_fee_rate_estimate: Option<MessageChannel<fee_estimate::GetTxFeeRate, Option<TxFeeRate>>>,
     ) -> Self {
         Self {
             db,
             projection_actor,
             collab_settlement_actor,
+            partial_collab_settlement_actor,
             order_actor,
             offers: Offers::default(),
-            maker_identity,
-            maker_peer_id,
+            makers,
+            oracles,
+            tx_fee_rate_estimate,
+        }
+    }
+
+    /// Our latest fee rate estimate, if estimation is enabled and an estimate is available.
+    async fn estimated_tx_fee_rate(&self) -> Option<TxFeeRate> {
+        let channel = self.tx_fee_rate_estimate.as_ref()?;
+
+        match channel.send(fee_estimate::GetTxFeeRate).await {
+            Ok(estimate) => estimate,
+            Err(_) => {
+                tracing::warn!("Fee estimate actor disconnected");
+                None
+            }
         }
     }
 }
@@ -71,9 +103,43 @@ impl Actor {
 #[xtra_productivity]
 impl Actor {
     async fn handle_latest_offers(&mut self, msg: offer::taker::LatestOffers) {
-        self.offers.insert(msg.0.clone());
+        let maker_peer_id = PeerId::from(msg.maker_peer_id);
+
+        if !self.makers.contains_key(&maker_peer_id) {
+            tracing::warn!(%maker_peer_id, "Ignoring offers from unknown maker");
+            return;
+        }
 
-        if let Err(e) = self.projection_actor.send(projection::Update(msg.0)).await {
+        let offers = match self.estimated_tx_fee_rate().await {
+            Some(estimate) => msg
+                .offers
+                .into_iter()
+                .filter(|offer| {
+                    let within_band = offer.tx_fee_rate.is_within_band(estimate);
+                    if !within_band {
+                        tracing::warn!(
+                            %maker_peer_id,
+                            offer_id = %offer.id,
+                            offered = %offer.tx_fee_rate,
+                            %estimate,
+                            "Ignoring offer with fee rate far off our estimate"
+                        );
+                    }
+
+                    within_band
+                })
+                .collect(),
+            None => msg.offers,
+        };
+
+        self.offers.insert(maker_peer_id, offers);
+
+        let latest_offers = self.offers.latest();
+        if let Err(e) = self
+            .projection_actor
+            .send(projection::Update(latest_offers))
+            .await
+        {
             tracing::warn!("Failed to send current offers to projection actor: {e:#}");
         };
     }
@@ -84,24 +150,43 @@ impl Actor {
             bid,
             ask,
             quote_timestamp,
+            quantity,
         } = msg;
 
         let cfd = self.db.load_open_cfd::<Cfd>(order_id, ()).await?;
 
         let proposal_closing_price = market_closing_price(bid, ask, Role::Taker, cfd.position());
 
-        tracing::debug!(%order_id, %proposal_closing_price, %bid, %ask, %quote_timestamp, "Proposing settlement of contract");
+        let maker_peer_id = cfd
+            .counterparty_peer_id()
+            .context("No counterparty peer id found")?;
 
         // Wait for the response to check for invariants (ie. whether it is possible to settle)
-        self.collab_settlement_actor
-            .send(Settle {
-                order_id,
-                price: proposal_closing_price,
-                maker_peer_id: cfd
-                    .counterparty_peer_id()
-                    .context("No counterparty peer id found")?,
-            })
-            .await??;
+        match quantity {
+            None => {
+                tracing::debug!(%order_id, %proposal_closing_price, %bid, %ask, %quote_timestamp, "Proposing settlement of contract");
+
+                self.collab_settlement_actor
+                    .send(Settle {
+                        order_id,
+                        price: proposal_closing_price,
+                        maker_peer_id,
+                    })
+                    .await??;
+            }
+            Some(quantity) => {
+                tracing::debug!(%order_id, %quantity, %proposal_closing_price, %bid, %ask, %quote_timestamp, "Proposing partial settlement of contract");
+
+                self.partial_collab_settlement_actor
+                    .send(SettlePartially {
+                        order_id,
+                        price: proposal_closing_price,
+                        quantity,
+                        maker_peer_id,
+                    })
+                    .await??;
+            }
+        }
 
         Ok(())
     }
@@ -113,7 +198,7 @@ impl Actor {
             leverage,
         } = msg;
 
-        let offer = self
+        let (maker_peer_id, offer) = self
             .offers
             .get(&offer_id)
             .context("Offer to take could not be found in current maker offers, you might have an outdated offer")?;
@@ -122,13 +207,24 @@ impl Actor {
             bail!("The maker's offer appears to be outdated, refusing to place order");
         }
 
+        ensure!(
+            offer.oracle == self.oracles.get(offer.contract_symbol),
+            "The maker's offer is attested by oracle {} which we do not trust, refusing to place order",
+            offer.oracle.public_key
+        );
+
+        let maker_identity = *self
+            .makers
+            .get(&maker_peer_id)
+            .with_context(|| format!("Unknown maker {maker_peer_id}"))?;
+
         let order_id = OrderId::default();
         let place_order = order::taker::PlaceOrder::new(
             order_id,
             offer,
             (quantity, leverage),
-            self.maker_peer_id.inner(),
-            self.maker_identity,
+            maker_peer_id.inner(),
+            maker_identity,
         );
 
         self.order_actor
@@ -140,25 +236,47 @@ impl Actor {
     }
 }
 
+/// The offers of all makers we are connected to.
 #[derive(Default)]
-struct Offers(HashMap<OfferId, model::Offer>);
+struct Offers {
+    /// All offers which can still be taken, by offer id.
+    ///
+    /// Offers are kept around after a maker publishes new ones so that an order for an offer
+    /// which was just replaced can still be placed.
+    all: HashMap<OfferId, (PeerId, model::Offer)>,
+    /// The most recent offers published by each maker.
+    latest: HashMap<PeerId, Vec<model::Offer>>,
+}
 
 impl Offers {
-    fn insert(&mut self, offers: Vec<model::Offer>) {
-        for offer in offers.into_iter() {
-            self.0.insert(offer.id, offer);
+    fn insert(&mut self, maker_peer_id: PeerId, offers: Vec<model::Offer>) {
+        for offer in offers.iter() {
+            self.all.insert(offer.id, (maker_peer_id, offer.clone()));
         }
+
+        self.latest.insert(maker_peer_id, offers);
     }
 
-    fn get(&mut self, id: &OfferId) -> Option<model::Offer> {
+    fn get(&mut self, id: &OfferId) -> Option<(PeerId, model::Offer)> {
         self.remove_old_offers();
 
-        self.0.get(id).cloned()
+        self.all.get(id).cloned()
+    }
+
+    /// The most recent offers of all makers, tagged with the maker's peer id.
+    fn latest(&mut self) -> HashMap<PeerId, Vec<model::Offer>> {
+        self.remove_old_offers();
+
+        self.latest.clone()
     }
 
     fn remove_old_offers(&mut self) {
-        self.0
-            .retain(|_, offer| offer.is_safe_to_take(OffsetDateTime::now_utc()));
+        let now = OffsetDateTime::now_utc();
+
+        self.all.retain(|_, (_, offer)| offer.is_safe_to_take(now));
+        for offers in self.latest.values_mut() {
+            offers.retain(|offer| offer.is_safe_to_take(now));
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::bitcoin::secp256k1::Secp256k1;
+use crate::chain;
 use crate::seed::RandomSeed;
 use crate::seed::Seed;
 use crate::seed::RANDOM_SEED_SIZE;
@@ -9,22 +10,22 @@ use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
-use bdk::bitcoin::blockdata::constants;
-use bdk::bitcoin::hashes::Hash;
 use bdk::bitcoin::util::bip32::ExtendedPrivKey;
+use bdk::bitcoin::util::psbt;
 use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
 use bdk::bitcoin::Address;
 use bdk::bitcoin::Amount;
-use bdk::bitcoin::BlockHash;
 use bdk::bitcoin::Network;
 use bdk::bitcoin::OutPoint;
 use bdk::bitcoin::PublicKey;
+use bdk::bitcoin::Script;
+use bdk::bitcoin::Transaction;
 use bdk::bitcoin::Txid;
+use bdk::blockchain::AnyBlockchain;
 use bdk::blockchain::Blockchain;
-use bdk::blockchain::ElectrumBlockchain;
+use bdk::blockchain::GetTx;
 use bdk::database::BatchDatabase;
-use bdk::electrum_client;
-use bdk::electrum_client::ElectrumApi;
+use bdk::miniscript::DescriptorTrait;
 use bdk::sled;
 use bdk::sled::Tree;
 use bdk::wallet::tx_builder::TxOrdering;
@@ -35,6 +36,7 @@ use bdk::FeeRate;
 use bdk::KeychainKind;
 use bdk::SignOptions;
 use bdk::SyncOptions;
+use bdk::TransactionDetails;
 use bdk::Wallet;
 use maia_core::PartyParams;
 use maia_core::TxBuilderExt;
@@ -114,24 +116,23 @@ pub struct Actor<B, DB> {
     managed_wallet: bool,
 }
 
-impl Actor<ElectrumBlockchain, Tree> {
+impl Actor<AnyBlockchain, Tree> {
     pub fn spawn(
-        electrum_rpc_url: &str,
+        chain: &chain::Config,
         ext_priv_key: ExtendedPrivKey,
         db_path: PathBuf,
         managed_wallet: bool,
     ) -> Result<(xtra::Address<Self>, watch::Receiver<Option<WalletInfo>>)> {
-        let client = electrum_client::Client::new(electrum_rpc_url)
-            .context("Failed to initialize Electrum RPC client")?;
-
-        ensure!(
-            seed_and_rpc_on_same_network(&client, ext_priv_key.network)?,
-            "Wallet seed and Electrum RPC client on different networks."
-        );
+        // Fails if the backend is on a different network than the wallet seed
+        chain
+            .backend(ext_priv_key.network)
+            .context("Wallet seed and blockchain backend on different networks")?;
 
         // Create a database (using default sled type) to store wallet data
         let db = sled::open(db_path)?;
         let wallet = Actor::build_wallet(ext_priv_key, db.clone())?;
+        let blockchain_client =
+            chain.wallet_blockchain(ext_priv_key.network, wallet_name(ext_priv_key)?)?;
 
         // UTXOs chosen after coin selection will only be locked for a
         // few wallet sync intervals. UTXOs which were actually
@@ -147,7 +148,7 @@ impl Actor<ElectrumBlockchain, Tree> {
             wallet,
             sender,
             used_utxos: LockedUtxos::new(time_to_lock),
-            blockchain_client: ElectrumBlockchain::from(client),
+            blockchain_client,
             db: Some(db),
             managed_wallet,
         };
@@ -160,14 +161,7 @@ impl Actor<ElectrumBlockchain, Tree> {
     }
 
     fn build_wallet(ext_priv_key: ExtendedPrivKey, db: Db) -> Result<Wallet<Tree>> {
-        let wallet_name = wallet_name_from_descriptor(
-            bdk::template::Bip84(ext_priv_key, KeychainKind::External),
-            Some(bdk::template::Bip84(ext_priv_key, KeychainKind::Internal)),
-            ext_priv_key.network,
-            &Secp256k1::new(),
-        )?;
-
-        let db = db.open_tree(wallet_name)?;
+        let db = db.open_tree(wallet_name(ext_priv_key)?)?;
 
         let wallet = Wallet::new(
             bdk::template::Bip84(ext_priv_key, KeychainKind::External),
@@ -221,7 +215,7 @@ where
     }
 }
 
-impl<DB> Actor<ElectrumBlockchain, DB>
+impl<DB> Actor<AnyBlockchain, DB>
 where
     DB: BatchDatabase,
 {
@@ -280,7 +274,7 @@ where
 }
 
 #[xtra_productivity]
-impl<DB> Actor<ElectrumBlockchain, DB>
+impl<DB> Actor<AnyBlockchain, DB>
 where
     DB: BatchDatabase,
 {
@@ -298,6 +292,145 @@ where
     pub fn handle_withdraw(&mut self, msg: Withdraw) -> Result<Txid> {
         self.sync_internal()?;
 
+        let amount = msg.amount;
+        let address = msg.address.clone();
+        let (mut psbt, _) = self.build_withdraw_tx(msg, AddressIndex::New)?;
+
+        self.wallet.sign(&mut psbt, SignOptions::default())?;
+
+        let tx = psbt.extract_tx();
+        let txid = tx.txid();
+        self.blockchain_client.broadcast(&tx)?;
+
+        match amount {
+            Some(amount) => tracing::info!(%txid, %amount, %address, "Withdrew from wallet"),
+            None => tracing::info!(%txid, %address, "Drained wallet"),
+        }
+
+        Ok(txid)
+    }
+
+    pub fn handle_dry_run_withdraw(&mut self, msg: DryRunWithdraw) -> Result<WithdrawPreview> {
+        self.sync_internal()?;
+
+        // Nothing is broadcast, so the change address can be used by the next transaction
+        let (psbt, details) = self.build_withdraw_tx(msg.0, AddressIndex::LastUnused)?;
+        let fee = details.fee.context("Unknown fee of withdraw transaction")?;
+
+        Ok(WithdrawPreview {
+            psbt,
+            fee: Amount::from_sat(fee),
+        })
+    }
+
+    pub fn handle_build_cpfp(&mut self, msg: BuildCpfp) -> Result<Cpfp> {
+        self.sync_internal()?;
+
+        let BuildCpfp { parent, fee_rate } = msg;
+        let parent_txid = parent.txid();
+
+        match self.wallet.get_tx(&parent_txid, false)? {
+            None => return Ok(Cpfp::NotPublished),
+            Some(TransactionDetails {
+                confirmation_time: Some(_),
+                ..
+            }) => return Ok(Cpfp::Confirmed),
+            Some(_) => {}
+        }
+
+        let fee_rate = FeeRate::from(fee_rate);
+
+        let parent_fee = self.transaction_fee(&parent)?;
+        let parent_vsize = (parent.weight() + 3) / 4;
+        let parent_target_fee = fee_rate.fee_vb(parent_vsize);
+
+        if parent_fee >= parent_target_fee {
+            return Ok(Cpfp::NotNeeded);
+        }
+
+        let (vout, txout) = parent
+            .output
+            .iter()
+            .enumerate()
+            .find(|(_, txout)| {
+                self.wallet
+                    .is_mine(&txout.script_pubkey)
+                    .unwrap_or_default()
+            })
+            .with_context(|| format!("Transaction {parent_txid} does not pay to our wallet"))?;
+
+        // We add our output of the parent as a foreign UTXO because the wallet does not consider
+        // it spendable anymore if a previous child transaction spending from it is in the mempool
+        let outpoint = OutPoint::new(parent_txid, vout as u32);
+        let psbt_input = psbt::Input {
+            witness_utxo: Some(txout.clone()),
+            non_witness_utxo: Some(parent.clone()),
+            ..Default::default()
+        };
+        let satisfaction_weight = self
+            .wallet
+            .get_descriptor_for_keychain(KeychainKind::External)
+            .max_satisfaction_weight()?;
+        let drain_script = self.wallet.get_address(AddressIndex::New)?.script_pubkey();
+
+        // The first child only tells us how much fee the child pays at `fee_rate` on its own
+        let (_, child_details) = self.build_child_tx(
+            outpoint,
+            psbt_input.clone(),
+            satisfaction_weight,
+            drain_script.clone(),
+            ChildFee::Rate(fee_rate),
+        )?;
+        let child_fee = child_details
+            .fee
+            .context("Unknown fee of child transaction")?;
+
+        // The child has to make up for the fee that the parent is missing to reach `fee_rate`
+        let package_fee = child_fee + (parent_target_fee - parent_fee);
+
+        let (mut psbt, _) = self.build_child_tx(
+            outpoint,
+            psbt_input,
+            satisfaction_weight,
+            drain_script,
+            ChildFee::Absolute(package_fee),
+        )?;
+
+        let finalized = self.wallet.sign(&mut psbt, SignOptions::default())?;
+        ensure!(finalized, "Failed to sign child transaction");
+
+        let used_inputs = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|input| input.previous_output);
+        self.used_utxos.extend(used_inputs);
+
+        let child = psbt.extract_tx();
+
+        tracing::info!(
+            %parent_txid,
+            child_txid = %child.txid(),
+            fee_sat = %package_fee,
+            "Built child transaction to bump fee",
+        );
+
+        Ok(Cpfp::Child(child))
+    }
+}
+
+impl<B, DB> Actor<B, DB>
+where
+    DB: BatchDatabase,
+{
+    /// Build the unsigned transaction for a withdrawal.
+    ///
+    /// Change is sent to the internal address at `change_index`.
+    fn build_withdraw_tx(
+        &mut self,
+        msg: Withdraw,
+        change_index: AddressIndex,
+    ) -> Result<(PartiallySignedTransaction, TransactionDetails)> {
         if msg.address.network != self.wallet.network() {
             bail!(
                 "Address has invalid network. It was {} but the wallet is connected to {}",
@@ -309,44 +442,96 @@ where
         let fee_rate = msg.fee.unwrap_or_else(FeeRate::default_min_relay_fee);
         let address = msg.address;
 
-        let mut psbt = {
-            let mut tx_builder = self.wallet.build_tx();
-
-            tx_builder
-                .fee_rate(fee_rate)
-                // Turn on RBF signaling
-                .enable_rbf();
+        let mut tx_builder = self.wallet.build_tx();
 
-            match msg.amount {
-                Some(amount) => {
-                    tracing::info!(%amount, %address, "Withdrawing from wallet");
+        tx_builder
+            .fee_rate(fee_rate)
+            // Turn on RBF signaling
+            .enable_rbf();
+
+        match msg.amount {
+            Some(amount) => {
+                let change_script = self
+                    .wallet
+                    .get_internal_address(change_index)?
+                    .script_pubkey();
+
+                tx_builder
+                    .add_recipient(address.script_pubkey(), amount.as_sat())
+                    .drain_to(change_script);
+            }
+            None => {
+                tx_builder.drain_wallet().drain_to(address.script_pubkey());
+            }
+        }
 
-                    tx_builder.add_recipient(address.script_pubkey(), amount.as_sat());
-                }
-                None => {
-                    tracing::info!(%address, "Draining wallet");
+        let result = tx_builder.finish()?;
 
-                    tx_builder.drain_wallet().drain_to(address.script_pubkey());
-                }
-            }
+        Ok(result)
+    }
+}
 
-            let (psbt, _) = tx_builder.finish()?;
+impl<DB> Actor<AnyBlockchain, DB>
+where
+    DB: BatchDatabase,
+{
+    /// Calculate the fee paid by `tx` by looking up the outputs it spends.
+    fn transaction_fee(&self, tx: &Transaction) -> Result<u64> {
+        let mut input_value = 0;
+        for input in tx.input.iter() {
+            let OutPoint { txid, vout } = input.previous_output;
+            let prev_tx = self
+                .blockchain_client
+                .get_tx(&txid)?
+                .with_context(|| format!("Unknown transaction {txid}"))?;
+            let prev_out = prev_tx
+                .output
+                .get(vout as usize)
+                .with_context(|| format!("No output at vout {vout} in {txid}"))?;
+
+            input_value += prev_out.value;
+        }
 
-            psbt
-        };
+        let output_value = tx.output.iter().map(|output| output.value).sum::<u64>();
 
-        self.wallet.sign(&mut psbt, SignOptions::default())?;
+        input_value
+            .checked_sub(output_value)
+            .context("Transaction spends more than its inputs")
+    }
 
-        let tx = psbt.extract_tx();
-        let txid = tx.txid();
-        self.blockchain_client.broadcast(&tx)?;
+    fn build_child_tx(
+        &mut self,
+        outpoint: OutPoint,
+        psbt_input: psbt::Input,
+        satisfaction_weight: usize,
+        drain_script: Script,
+        fee: ChildFee,
+    ) -> Result<(PartiallySignedTransaction, TransactionDetails)> {
+        let mut tx_builder = self.wallet.build_tx();
+
+        tx_builder
+            .add_foreign_utxo(outpoint, psbt_input, satisfaction_weight)?
+            .unspendable(self.used_utxos.list())
+            .drain_to(drain_script)
+            // Allow replacing the child with one paying a higher fee
+            .enable_rbf();
+
+        match fee {
+            ChildFee::Rate(fee_rate) => tx_builder.fee_rate(fee_rate),
+            ChildFee::Absolute(fee) => tx_builder.fee_absolute(fee),
+        };
 
-        tracing::info!(%txid, "Withdraw successful");
+        let result = tx_builder.finish()?;
 
-        Ok(txid)
+        Ok(result)
     }
 }
 
+enum ChildFee {
+    Rate(FeeRate),
+    Absolute(u64),
+}
+
 #[xtra_productivity]
 impl<B, DB> Actor<B, DB>
 where
@@ -391,7 +576,7 @@ where
 }
 
 #[async_trait]
-impl<DB: 'static> xtra::Actor for Actor<ElectrumBlockchain, DB>
+impl<DB: 'static> xtra::Actor for Actor<AnyBlockchain, DB>
 where
     DB: BatchDatabase + Send,
 {
@@ -436,22 +621,35 @@ pub struct Withdraw {
     pub address: Address,
 }
 
-/// Bitcoin error codes: <https://github.com/bitcoin/bitcoin/blob/97d3500601c1d28642347d014a6de1e38f53ae4e/src/rpc/protocol.h#L23>
-#[derive(Clone, Copy)]
-pub enum RpcErrorCode {
-    /// General error during transaction or block submission Error code -25.
-    RpcVerifyError,
-    /// Transaction already in chain. Error code -27.
-    RpcVerifyAlreadyInChain,
+/// Build the transaction for a [`Withdraw`] without signing or broadcasting it.
+pub struct DryRunWithdraw(pub Withdraw);
+
+#[derive(Debug)]
+pub struct WithdrawPreview {
+    /// The unsigned withdraw transaction.
+    pub psbt: PartiallySignedTransaction,
+    pub fee: Amount,
 }
 
-impl From<RpcErrorCode> for i64 {
-    fn from(code: RpcErrorCode) -> Self {
-        match code {
-            RpcErrorCode::RpcVerifyError => -25,
-            RpcErrorCode::RpcVerifyAlreadyInChain => -27,
-        }
-    }
+/// Build a child transaction spending our output of `parent`.
+///
+/// The child pays enough fees for the parent and the child to reach `fee_rate` together
+/// (child-pays-for-parent).
+pub struct BuildCpfp {
+    pub parent: Transaction,
+    pub fee_rate: TxFeeRate,
+}
+
+#[derive(Debug)]
+pub enum Cpfp {
+    /// The parent transaction is already confirmed.
+    Confirmed,
+    /// The parent transaction is not known to the blockchain yet.
+    NotPublished,
+    /// The parent transaction already pays at least the requested fee rate.
+    NotNeeded,
+    /// A signed child transaction which still has to be broadcast.
+    Child(Transaction),
 }
 
 /// Module private trait to faciliate testing.
@@ -541,16 +739,17 @@ impl LockedUtxos {
     }
 }
 
-/// Compare the hash of the genesis block of the electrum RPC endpoint to the expected network's
-/// genesis block hash. If they differ, the electrum RPC is not for the network that we expect.
-fn seed_and_rpc_on_same_network(rpc: &electrum_client::Client, network: Network) -> Result<bool> {
-    let network_hash = constants::genesis_block(network).block_hash();
-    let mut hash = rpc.server_features()?.genesis_hash;
-    hash.reverse(); // Sha256d hashes are displayed backwards
-    let rpc_hash = BlockHash::from_slice(&hash)
-        .context("Invalid genesis block hash returned by electrum RPC")?;
-
-    Ok(network_hash == rpc_hash)
+/// The name of the wallet derived from `ext_priv_key`, which is also used as the name of the
+/// watch-only wallet in bitcoind.
+fn wallet_name(ext_priv_key: ExtendedPrivKey) -> Result<String> {
+    let wallet_name = wallet_name_from_descriptor(
+        bdk::template::Bip84(ext_priv_key, KeychainKind::External),
+        Some(bdk::template::Bip84(ext_priv_key, KeychainKind::Internal)),
+        ext_priv_key.network,
+        &Secp256k1::new(),
+    )?;
+
+    Ok(wallet_name)
 }
 
 #[cfg(test)]
@@ -772,6 +971,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dry_run_withdraw_reuses_change_address() {
+        let mut actor = Actor::new_offline::<MemoryDatabase>(
+            Amount::ONE_BTC,
+            1,
+            Duration::from_secs(120),
+            MemoryDatabase::new(),
+        )
+        .unwrap();
+        let address = actor
+            .wallet
+            .get_address(AddressIndex::Peek(100))
+            .unwrap()
+            .address;
+        let withdraw = || Withdraw {
+            amount: Some(Amount::from_btc(0.2).unwrap()),
+            fee: None,
+            address: address.clone(),
+        };
+        let change_script = |psbt: &PartiallySignedTransaction| {
+            psbt.unsigned_tx
+                .output
+                .iter()
+                .find(|output| output.script_pubkey != address.script_pubkey())
+                .expect("withdraw to have a change output")
+                .script_pubkey
+                .clone()
+        };
+
+        let (first, _) = actor
+            .build_withdraw_tx(withdraw(), AddressIndex::LastUnused)
+            .unwrap();
+        let (second, _) = actor
+            .build_withdraw_tx(withdraw(), AddressIndex::LastUnused)
+            .unwrap();
+
+        assert_eq!(change_script(&first), change_script(&second));
+    }
+
     #[tokio::test]
     async fn utxo_is_locked_after_building_party_params() {
         let mut tasks = Tasks::default();
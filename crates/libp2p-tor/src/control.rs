@@ -0,0 +1,332 @@
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use libp2p_core::Multiaddr;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+
+/// Key type to ask Tor for when creating a new onion service.
+const NEW_KEY: &str = "NEW:ED25519-V3";
+
+/// How to authenticate with the Tor control port.
+#[derive(Clone, Debug)]
+pub enum Authentication {
+    /// Tor runs without `CookieAuthentication` and `HashedControlPassword`.
+    Null,
+    /// The password matching `HashedControlPassword` in the Tor config.
+    Password(String),
+    /// The cookie file written by Tor if `CookieAuthentication` is enabled.
+    Cookie(PathBuf),
+}
+
+/// An onion service hosted by a Tor daemon.
+///
+/// Tor forwards connections to `<service-id>.onion:<virtual-port>` to a local target. The service
+/// is bound to the control connection and thus removed by Tor once this is dropped or Tor closes
+/// the connection, see [`closed`](Self::closed).
+pub struct OnionService {
+    service_id: String,
+    virtual_port: u16,
+    private_key: String,
+    control: ControlConnection,
+}
+
+impl OnionService {
+    /// Ask the Tor daemon behind `control_port` to forward `virtual_port` of an onion service to
+    /// `target`.
+    ///
+    /// Pass the [`private_key`](Self::private_key) of a previous run to keep the onion address,
+    /// otherwise Tor generates a new one.
+    pub async fn add(
+        control_port: SocketAddr,
+        authentication: &Authentication,
+        private_key: Option<&str>,
+        virtual_port: u16,
+        target: SocketAddr,
+    ) -> Result<Self> {
+        let mut control = ControlConnection::connect(control_port).await?;
+        control.authenticate(authentication).await?;
+
+        let key = private_key.unwrap_or(NEW_KEY);
+        let reply = control
+            .command(&format!("ADD_ONION {key} Port={virtual_port},{target}"))
+            .await
+            .context("Failed to add onion service")?;
+
+        let service_id = reply_value(&reply, "ServiceID")
+            .context("Tor did not return the onion service id")?
+            .to_owned();
+        let private_key = match private_key {
+            Some(private_key) => private_key.to_owned(),
+            None => reply_value(&reply, "PrivateKey")
+                .context("Tor did not return the onion service key")?
+                .to_owned(),
+        };
+
+        let service = Self {
+            service_id,
+            virtual_port,
+            private_key,
+            control,
+        };
+        tracing::info!(address = %service.multiaddr(), "Added onion service");
+
+        Ok(service)
+    }
+
+    /// The address to dial the onion service on.
+    pub fn multiaddr(&self) -> Multiaddr {
+        format!("/onion3/{}:{}", self.service_id, self.virtual_port)
+            .parse()
+            .expect("Tor to return a valid v3 service id")
+    }
+
+    /// The key of the onion service in Tor's `<type>:<blob>` format.
+    pub fn private_key(&self) -> &str {
+        &self.private_key
+    }
+
+    /// Wait until Tor closes the control connection, e.g. because it restarted.
+    ///
+    /// Tor removes the onion service along with the connection, so it has to be added again.
+    pub async fn closed(&mut self) {
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            match self.control.stream.read_line(&mut line).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {} // We did not subscribe to any events, so there is nothing to handle
+            }
+        }
+    }
+}
+
+struct ControlConnection {
+    stream: BufReader<TcpStream>,
+}
+
+impl ControlConnection {
+    async fn connect(control_port: SocketAddr) -> Result<Self> {
+        let stream = TcpStream::connect(control_port)
+            .await
+            .with_context(|| format!("Failed to connect to Tor control port at {control_port}"))?;
+
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    async fn authenticate(&mut self, authentication: &Authentication) -> Result<()> {
+        let command = match authentication {
+            Authentication::Null => "AUTHENTICATE".to_owned(),
+            Authentication::Password(password) => {
+                let password = password.replace('\\', "\\\\").replace('"', "\\\"");
+                format!("AUTHENTICATE \"{password}\"")
+            }
+            Authentication::Cookie(path) => {
+                let cookie = tokio::fs::read(path)
+                    .await
+                    .with_context(|| format!("Failed to read Tor cookie file {path:?}"))?;
+                format!("AUTHENTICATE {}", hex::encode(cookie))
+            }
+        };
+
+        self.command(&command)
+            .await
+            .context("Failed to authenticate with Tor control port")?;
+
+        Ok(())
+    }
+
+    /// Send a command and return the lines of a successful reply without the status code.
+    async fn command(&mut self, command: &str) -> Result<Vec<String>> {
+        self.stream
+            .get_mut()
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await?;
+
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                bail!("Tor closed the control connection");
+            }
+            let line = line.trim_end();
+
+            let (status, separator, text) = match (line.get(..3), line.get(3..4), line.get(4..)) {
+                (Some(status), Some(separator), Some(text)) => (status, separator, text),
+                _ => bail!("Malformed reply from Tor: {line}"),
+            };
+            if status != "250" {
+                bail!("Tor replied with an error: {line}");
+            }
+
+            lines.push(text.to_owned());
+
+            if separator == " " {
+                return Ok(lines);
+            }
+        }
+    }
+}
+
+/// Find the value of `key` in reply lines of the form `<key>=<value>`.
+fn reply_value<'a>(reply: &'a [String], key: &str) -> Option<&'a str> {
+    reply.iter().find_map(|line| {
+        let (k, value) = line.split_once('=')?;
+        (k == key).then_some(value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const ONION: &str = "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid";
+
+    #[tokio::test]
+    async fn add_onion_service_through_control_port() {
+        let control_port = mock_control_port(vec![
+            ("AUTHENTICATE \"secret\"", "250 OK\r\n".to_owned()),
+            (
+                "ADD_ONION NEW:ED25519-V3 Port=10000,127.0.0.1:10001",
+                format!("250-ServiceID={ONION}\r\n250-PrivateKey=ED25519-V3:key\r\n250 OK\r\n"),
+            ),
+        ])
+        .await;
+
+        let service = OnionService::add(
+            control_port,
+            &Authentication::Password("secret".to_owned()),
+            None,
+            10000,
+            "127.0.0.1:10001".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            service.multiaddr(),
+            format!("/onion3/{ONION}:10000")
+                .parse::<Multiaddr>()
+                .unwrap()
+        );
+        assert_eq!(service.private_key(), "ED25519-V3:key");
+    }
+
+    #[tokio::test]
+    async fn reuse_private_key_of_previous_run() {
+        let control_port = mock_control_port(vec![
+            ("AUTHENTICATE", "250 OK\r\n".to_owned()),
+            (
+                "ADD_ONION ED25519-V3:key Port=10000,127.0.0.1:10001",
+                format!("250-ServiceID={ONION}\r\n250 OK\r\n"),
+            ),
+        ])
+        .await;
+
+        let service = OnionService::add(
+            control_port,
+            &Authentication::Null,
+            Some("ED25519-V3:key"),
+            10000,
+            "127.0.0.1:10001".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(service.private_key(), "ED25519-V3:key");
+    }
+
+    #[tokio::test]
+    async fn fail_if_authentication_is_rejected() {
+        let control_port = mock_control_port(vec![(
+            "AUTHENTICATE",
+            "515 Authentication failed: Wrong length on authentication cookie.\r\n".to_owned(),
+        )])
+        .await;
+
+        let result = OnionService::add(
+            control_port,
+            &Authentication::Null,
+            None,
+            10000,
+            "127.0.0.1:10001".parse().unwrap(),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn notice_when_tor_closes_the_control_connection() {
+        let control_port = mock_control_port_with(
+            vec![
+                ("AUTHENTICATE", "250 OK\r\n".to_owned()),
+                (
+                    "ADD_ONION NEW:ED25519-V3 Port=10000,127.0.0.1:10001",
+                    format!("250-ServiceID={ONION}\r\n250-PrivateKey=ED25519-V3:key\r\n250 OK\r\n"),
+                ),
+            ],
+            false,
+        )
+        .await;
+
+        let mut service = OnionService::add(
+            control_port,
+            &Authentication::Null,
+            None,
+            10000,
+            "127.0.0.1:10001".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        service.closed().await;
+    }
+
+    /// A Tor control port that expects the given commands in order and answers with the given
+    /// replies.
+    async fn mock_control_port(conversation: Vec<(&'static str, String)>) -> SocketAddr {
+        mock_control_port_with(conversation, true).await
+    }
+
+    /// Like [`mock_control_port`], but closes the connection after the conversation unless
+    /// `keep_open` is set.
+    async fn mock_control_port_with(
+        conversation: Vec<(&'static str, String)>,
+        keep_open: bool,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        #[allow(clippy::disallowed_methods)]
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+
+            for (expected, reply) in conversation {
+                let mut command = String::new();
+                stream.read_line(&mut command).await.unwrap();
+                assert_eq!(command.trim_end(), expected);
+
+                stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+
+            if keep_open {
+                // Keep the connection open like Tor does
+                let mut rest = String::new();
+                let _ = stream.read_line(&mut rest).await;
+            }
+        });
+
+        addr
+    }
+}
@@ -0,0 +1,12 @@
+//! Tor support for libp2p.
+//!
+//! Dialing goes through the SOCKS5 proxy of a local Tor daemon, see [`TorDialTransport`].
+//! Hosting happens through the Tor control port, see [`OnionService`]: Tor forwards connections
+//! to the onion service to a local TCP listener, so no public port is needed.
+
+pub use control::Authentication;
+pub use control::OnionService;
+pub use socks::TorDialTransport;
+
+mod control;
+mod socks;
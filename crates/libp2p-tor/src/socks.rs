@@ -0,0 +1,215 @@
+use data_encoding::BASE32;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt;
+use libp2p_core::multiaddr::Protocol;
+use libp2p_core::transport::ListenerEvent;
+use libp2p_core::transport::TransportError;
+use libp2p_core::Multiaddr;
+use libp2p_core::Transport;
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_util::compat::Compat;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+/// A [`Transport`] that dials through the SOCKS5 proxy of a Tor daemon.
+///
+/// Besides `/onion3` addresses, `/ip4`, `/ip6` and `/dns` addresses are dialed through Tor as well
+/// so that the remote never learns our IP address. Host names are resolved by Tor, not locally.
+///
+/// Listening is not supported, use an [`OnionService`](crate::OnionService) instead.
+#[derive(Clone, Copy, Debug)]
+pub struct TorDialTransport {
+    socks_proxy: SocketAddr,
+}
+
+impl TorDialTransport {
+    pub fn new(socks_proxy: SocketAddr) -> Self {
+        Self { socks_proxy }
+    }
+}
+
+impl Transport for TorDialTransport {
+    type Output = Compat<TcpStream>;
+    type Error = io::Error;
+    type Listener =
+        BoxStream<'static, Result<ListenerEvent<Self::ListenerUpgrade, Self::Error>, Self::Error>>;
+    type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Listener, TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let (host, port) = match socks_target(&addr) {
+            Some(target) => target,
+            None => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+        let socks_proxy = self.socks_proxy;
+
+        Ok(async move {
+            tracing::debug!(%addr, "Dialing through Tor");
+
+            let stream = Socks5Stream::connect(socks_proxy, (host.as_str(), port))
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+            Ok(stream.into_inner().compat())
+        }
+        .boxed())
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        self.dial(addr)
+    }
+
+    fn address_translation(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
+
+/// Extract the host and port to hand to the SOCKS5 proxy.
+///
+/// A trailing `/p2p/<peer-id>` is ignored.
+fn socks_target(addr: &Multiaddr) -> Option<(String, u16)> {
+    let mut protocols = addr.iter();
+
+    let target = match protocols.next()? {
+        Protocol::Onion3(onion) => (onion_host(onion.hash()), onion.port()),
+        host => {
+            let host = match host {
+                Protocol::Ip4(ip) => ip.to_string(),
+                Protocol::Ip6(ip) => ip.to_string(),
+                Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => {
+                    host.to_string()
+                }
+                _ => return None,
+            };
+
+            match protocols.next()? {
+                Protocol::Tcp(port) => (host, port),
+                _ => return None,
+            }
+        }
+    };
+
+    match protocols.next() {
+        None | Some(Protocol::P2p(_)) => Some(target),
+        Some(_) => None,
+    }
+}
+
+/// The host name of an onion service, i.e. `<base32 of the address>.onion`.
+fn onion_host(hash: &[u8; 35]) -> String {
+    format!("{}.onion", BASE32.encode(hash).to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::AsyncReadExt;
+    use futures::AsyncWriteExt;
+    use tokio::io::AsyncReadExt as _;
+    use tokio::io::AsyncWriteExt as _;
+    use tokio::net::TcpListener;
+
+    const ONION: &str = "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid";
+
+    #[test]
+    fn onion_address_is_handed_to_proxy_as_host_name() {
+        let addr = format!("/onion3/{ONION}:10000").parse().unwrap();
+
+        let target = socks_target(&addr).unwrap();
+
+        assert_eq!(target, (format!("{ONION}.onion"), 10000));
+    }
+
+    #[test]
+    fn host_names_are_not_resolved_locally() {
+        let addr = "/dns/mainnet.itchysats.network/tcp/10001".parse().unwrap();
+
+        let target = socks_target(&addr).unwrap();
+
+        assert_eq!(target, ("mainnet.itchysats.network".to_owned(), 10001));
+    }
+
+    #[test]
+    fn reject_udp_address() {
+        let addr = "/ip4/127.0.0.1/udp/10000".parse().unwrap();
+
+        assert!(socks_target(&addr).is_none());
+    }
+
+    #[tokio::test]
+    async fn dial_onion_address_through_socks_proxy() {
+        let (socks_proxy, requested_target) = mock_socks_proxy().await;
+        let addr = format!("/onion3/{ONION}:10000").parse().unwrap();
+
+        let mut stream = TorDialTransport::new(socks_proxy)
+            .dial(addr)
+            .unwrap()
+            .await
+            .unwrap();
+        stream.write_all(b"hello").await.unwrap();
+        let mut echo = [0u8; 5];
+        stream.read_exact(&mut echo).await.unwrap();
+
+        assert_eq!(&echo, b"hello");
+        assert_eq!(
+            requested_target.await.unwrap(),
+            (format!("{ONION}.onion"), 10000)
+        );
+    }
+
+    /// A SOCKS5 proxy that accepts a single connection and echoes everything back.
+    ///
+    /// Resolves the returned future to the host and port the client asked for.
+    async fn mock_socks_proxy() -> (SocketAddr, tokio::task::JoinHandle<(String, u16)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        #[allow(clippy::disallowed_methods)]
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            // Greeting: version, number of methods, methods
+            let mut greeting = [0u8; 2];
+            stream.read_exact(&mut greeting).await.unwrap();
+            let mut methods = vec![0u8; greeting[1] as usize];
+            stream.read_exact(&mut methods).await.unwrap();
+            assert!(methods.contains(&0x00), "client offers no authentication");
+            stream.write_all(&[0x05, 0x00]).await.unwrap();
+
+            // Request: version, CONNECT, reserved, domain name, length, name, port
+            let mut request = [0u8; 5];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..4], &[0x05, 0x01, 0x00, 0x03]);
+            let mut host = vec![0u8; request[4] as usize];
+            stream.read_exact(&mut host).await.unwrap();
+            let port = stream.read_u16().await.unwrap();
+
+            // Reply: succeeded, bound to 0.0.0.0:0
+            stream
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+
+            (String::from_utf8(host).unwrap(), port)
+        });
+
+        (addr, handle)
+    }
+}
@@ -1,5 +1,13 @@
+use crate::admission::PeerAdmission;
 use crate::cfd;
 use crate::metrics::time_to_first_position;
+use crate::order_policy;
+use crate::quoting;
+use crate::risk;
+use crate::rollover_policy::save_rollover_policy;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context as _;
 use anyhow::Result;
 use bdk::bitcoin;
 use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
@@ -7,10 +15,17 @@ use bdk::bitcoin::Amount;
 use bdk::bitcoin::Txid;
 use daemon::archive_closed_cfds;
 use daemon::archive_failed_cfds;
+use daemon::backup;
+use daemon::cfd_backup;
 use daemon::collab_settlement;
 use daemon::command;
+use daemon::cpfp;
+use daemon::export;
+use daemon::fee_estimate;
 use daemon::identify;
+use daemon::libp2p_utils;
 use daemon::listen_protocols::MAKER_LISTEN_PROTOCOLS;
+use daemon::maker_request;
 use daemon::monitor;
 use daemon::oracle;
 use daemon::oracle::NoAnnouncement;
@@ -18,15 +33,16 @@ use daemon::order;
 use daemon::position_metrics;
 use daemon::process_manager;
 use daemon::projection;
+use daemon::seed;
 use daemon::seed::Identities;
 use daemon::wallet;
 use daemon::Environment;
-use libp2p_tcp::TokioTcpConfig;
-use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use maia_core::PartyParams;
 use model::olivia::Announcement;
+use model::olivia::Oracles;
 use model::ContractSymbol;
 use model::Contracts;
+use model::FeeLedgerEntry;
 use model::FundingRate;
 use model::Leverage;
 use model::LotSize;
@@ -35,21 +51,30 @@ use model::OrderId;
 use model::Price;
 use model::Role;
 use model::TxFeeRate;
+use offer::maker::Audience;
 use ping_pong::ping;
 use ping_pong::pong;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Mutex;
 use tokio_extras::Tasks;
+use xtra::prelude::MessageChannel;
 use xtra::Actor;
 use xtra::Address;
 use xtra::Context;
 use xtra::Handler;
+use xtra_bitmex_price_feed::GetLatestQuotes;
+use xtra_bitmex_price_feed::LatestQuotes;
 use xtra_libp2p::endpoint;
 use xtra_libp2p::libp2p::Multiaddr;
 use xtra_libp2p::libp2p::PeerId;
 use xtra_libp2p::listener;
+use xtra_libp2p::BlockPeer;
 use xtra_libp2p::Endpoint;
+use xtra_libp2p::UnblockPeer;
 use xtras::supervisor::always_restart_after;
 use xtras::supervisor::Supervisor;
 
@@ -62,6 +87,7 @@ pub const RESTART_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct ActorSystem<O: 'static, W: 'static> {
     pub cfd_actor: Address<cfd::Actor>,
+    quoting_actor: Option<Address<quoting::Actor>>,
     wallet_actor: Address<W>,
 
     pub rollover_actor: Address<
@@ -77,9 +103,15 @@ pub struct ActorSystem<O: 'static, W: 'static> {
     _oracle_actor: Address<O>,
     _archive_closed_cfds_actor: Address<archive_closed_cfds::Actor>,
     _archive_failed_cfds_actor: Address<archive_failed_cfds::Actor>,
+    _cpfp_actor: Address<cpfp::Actor>,
     executor: command::Executor,
     _tasks: Tasks,
     _pong_actor: Address<pong::Actor>,
+    endpoint: Address<Endpoint>,
+    configured_blocked_peers: HashSet<PeerId>,
+    taker_groups: HashSet<String>,
+    rollover_policy: Mutex<rollover::policy::Policy>,
+    db: sqlite_db::Connection,
 }
 
 impl<O, W> ActorSystem<O, W>
@@ -90,22 +122,33 @@ where
     W: Handler<wallet::BuildPartyParams, Return = Result<PartyParams>>
         + Handler<wallet::Sign, Return = Result<PartiallySignedTransaction>>
         + Handler<wallet::Withdraw, Return = Result<Txid>>
+        + Handler<wallet::DryRunWithdraw, Return = Result<wallet::WithdrawPreview>>
         + Handler<wallet::Sync, Return = ()>
+        + Handler<wallet::BuildCpfp, Return = Result<wallet::Cpfp>>
         + Actor<Stop = ()>,
 {
     #[allow(clippy::too_many_arguments)]
     pub fn new<M>(
         db: sqlite_db::Connection,
         wallet_addr: Address<W>,
-        oracle_pk: XOnlyPublicKey,
+        oracles: Oracles,
         oracle_constructor: impl FnOnce(command::Executor) -> O,
         monitor_constructor: impl FnOnce(command::Executor) -> Result<M>,
         settlement_interval: time::Duration,
         n_payouts: usize,
         projection_actor: Address<projection::Actor>,
         identity: Identities,
-        listen_multiaddr: Multiaddr,
-        blocked_peers: HashSet<PeerId>,
+        listen_multiaddrs: Vec<Multiaddr>,
+        announced_multiaddrs: HashSet<Multiaddr>,
+        admission: PeerAdmission,
+        taker_groups: HashMap<PeerId, String>,
+        price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+        quoting: Option<quoting::Config>,
+        limits: risk::Limits,
+        order_policy: order_policy::Policy,
+        cfd_backup: cfd_backup::Actor,
+        position_metrics: position_metrics::Actor,
+        fee_estimate: Option<fee_estimate::Actor>,
     ) -> Result<Self>
     where
         M: Handler<monitor::MonitorAfterContractSetup, Return = ()>
@@ -114,32 +157,39 @@ where
             + Handler<monitor::MonitorCollaborativeSettlement, Return = ()>
             + Handler<monitor::TryBroadcastTransaction, Return = Result<()>>
             + Handler<monitor::MonitorCetFinality, Return = Result<()>>
+            + Handler<monitor::MonitorPunishFinality, Return = Result<()>>
             + Actor<Stop = ()>,
     {
         let (monitor_addr, monitor_ctx) = Context::new(None);
         let (oracle_addr, oracle_ctx) = Context::new(None);
         let (process_manager_addr, process_manager_ctx) = Context::new(None);
         let (time_to_first_position_addr, time_to_first_position_ctx) = Context::new(None);
+        let (cfd_actor_addr, cfd_actor_ctx) = Context::new(None);
 
         let executor = command::Executor::new(db.clone(), process_manager_addr.clone());
 
         let mut tasks = Tasks::default();
 
-        let position_metrics_actor = position_metrics::Actor::new(db.clone())
-            .create(None)
-            .spawn(&mut tasks);
+        let position_metrics_actor = position_metrics.create(None).spawn(&mut tasks);
+        let cfd_backup_addr = cfd_backup.create(None).spawn(&mut tasks);
+        let fee_estimate_addr = fee_estimate.map(|actor| actor.create(None).spawn(&mut tasks));
 
         tasks.add(process_manager_ctx.run(process_manager::Actor::new(
             db.clone(),
             Role::Maker,
             projection_actor.clone().into(),
-            position_metrics_actor.into(),
+            position_metrics_actor.clone().into(),
+            monitor_addr.clone().into(),
+            monitor_addr.clone().into(),
             monitor_addr.clone().into(),
             monitor_addr.clone().into(),
             monitor_addr.clone().into(),
             monitor_addr.clone().into(),
             monitor_addr.into(),
             oracle_addr.clone().into(),
+            cfd_backup_addr.clone().into(),
+            cfd_backup_addr.into(),
+            None,
         )));
 
         let (endpoint_addr, endpoint_context) = Context::new(None);
@@ -152,7 +202,8 @@ where
 
         let (supervisor, maker_offer_address) = Supervisor::new({
             let endpoint_addr = endpoint_addr.clone();
-            move || offer::maker::Actor::new(endpoint_addr.clone())
+            let taker_groups = taker_groups.clone();
+            move || offer::maker::Actor::new(endpoint_addr.clone(), taker_groups.clone())
         });
         tasks.add(supervisor.run_log_summary());
 
@@ -163,15 +214,16 @@ where
             let wallet = wallet_addr.clone();
             let projection = projection_actor.clone();
             let maker_offer_address = maker_offer_address.clone();
+            let cfd_actor_addr = cfd_actor_addr.clone();
             move || {
                 order::maker::Actor::new(
                     n_payouts,
-                    oracle_pk,
                     oracle.clone().into(),
                     (db.clone(), process_manager.clone()),
                     (wallet.clone().into(), wallet.clone().into()),
                     projection.clone(),
                     maker_offer_address.clone().into(),
+                    cfd_actor_addr.clone().into(),
                 )
             }
         });
@@ -184,15 +236,16 @@ where
             let wallet = wallet_addr.clone();
             let projection = projection_actor.clone();
             let maker_offer_address = maker_offer_address.clone();
+            let cfd_actor_addr = cfd_actor_addr.clone();
             move || {
                 order::deprecated::maker::Actor::new(
                     n_payouts,
-                    oracle_pk,
                     oracle.clone().into(),
                     (db.clone(), process_manager.clone()),
                     (wallet.clone().into(), wallet.clone().into()),
                     projection.clone(),
                     maker_offer_address.clone().into(),
+                    cfd_actor_addr.clone().into(),
                 )
             }
         });
@@ -213,22 +266,53 @@ where
             });
         tasks.add(collab_settlement_deprecated_supervisor.run_log_summary());
 
-        let cfd_actor_addr = cfd::Actor::new(
+        let (partial_collab_settlement_supervisor, partial_collab_settlement_addr) =
+            Supervisor::new({
+                let executor = executor.clone();
+                let oracle_addr = oracle_addr.clone();
+                move || {
+                    collab_settlement::partial::maker::Actor::new(
+                        executor.clone(),
+                        oracle::AnnouncementsChannel::new(oracle_addr.clone().into()),
+                        n_payouts,
+                    )
+                }
+            });
+        tasks.add(partial_collab_settlement_supervisor.run_log_summary());
+
+        tasks.add(cfd_actor_ctx.run(cfd::Actor::new(
             settlement_interval,
+            oracles,
             projection_actor,
             time_to_first_position_addr,
             (
                 collab_settlement_addr.clone(),
                 collab_settlement_deprecated_addr.clone(),
+                partial_collab_settlement_addr.clone(),
             ),
             (
                 maker_offer_address.clone(),
                 maker_offer_address_deprecated.clone(),
             ),
             (order.clone(), order_deprecated.clone()),
-        )
-        .create(None)
-        .spawn(&mut tasks);
+            limits,
+            position_metrics_actor.clone().into(),
+            order_policy,
+            price_feed.clone(),
+            fee_estimate_addr.map(Into::into),
+            taker_groups.clone(),
+        )));
+
+        let quoting_actor = quoting.map(|config| {
+            quoting::Actor::new(
+                config,
+                price_feed,
+                position_metrics_actor.into(),
+                cfd_actor_addr.clone(),
+            )
+            .create(None)
+            .spawn(&mut tasks)
+        });
 
         let (rollover_deprecated_supervisor, rollover_deprecated_addr) = Supervisor::new({
             let executor = executor.clone();
@@ -237,9 +321,8 @@ where
             move || {
                 rollover::deprecated::maker::Actor::new(
                     executor.clone(),
-                    oracle_pk,
                     oracle::AnnouncementsChannel::new(oracle_addr.clone().into()),
-                    cfd::RatesChannel::new(cfd_actor_addr.clone().into()),
+                    cfd::RatesChannel::new(cfd_actor_addr.clone()),
                     n_payouts,
                 )
             }
@@ -253,9 +336,8 @@ where
             move || {
                 rollover::maker::Actor::new(
                     executor.clone(),
-                    oracle_pk,
                     oracle::AnnouncementsChannel::new(oracle_addr.clone().into()),
-                    cfd::RatesChannel::new(cfd_actor_addr.clone().into()),
+                    cfd::RatesChannel::new(cfd_actor_addr.clone()),
                     n_payouts,
                 )
             }
@@ -267,14 +349,20 @@ where
             move || ping::Actor::new(endpoint_addr.clone(), PING_INTERVAL)
         });
 
-        let (listener_supervisor, listener_actor) = Supervisor::<_, listener::Error>::with_policy(
-            {
-                let listen_multiaddr = listen_multiaddr.clone();
-                let endpoint_addr = endpoint_addr.clone();
-                move || listener::Actor::new(endpoint_addr.clone(), listen_multiaddr.clone())
-            },
-            always_restart_after(RESTART_INTERVAL),
-        );
+        let (listener_supervisors, listener_actors): (Vec<_>, Vec<_>) = listen_multiaddrs
+            .into_iter()
+            .map(|listen_multiaddr| {
+                Supervisor::<_, listener::Error>::with_policy(
+                    {
+                        let endpoint_addr = endpoint_addr.clone();
+                        move || {
+                            listener::Actor::new(endpoint_addr.clone(), listen_multiaddr.clone())
+                        }
+                    },
+                    always_restart_after(RESTART_INTERVAL),
+                )
+            })
+            .unzip();
 
         // TODO: Shouldn't this actor also be supervised?
         let pong_address = pong::Actor.create(None).spawn(&mut tasks);
@@ -286,17 +374,25 @@ where
                     daemon::version(),
                     Environment::unknown(),
                     identity.public(),
-                    HashSet::from([listen_multiaddr.clone()]),
+                    announced_multiaddrs.clone(),
                     MAKER_LISTEN_PROTOCOLS.into(),
                 )
             }
         });
 
+        let endpoint_actor = endpoint_addr.clone();
         let (identify_dialer_supervisor, identify_dialer_actor) =
             Supervisor::new(move || identify::dialer::Actor::new(endpoint_addr.clone()));
 
+        let PeerAdmission {
+            configured_blocked_peers,
+            blocked_peers,
+            allowed_peers,
+            rate_limits,
+        } = admission;
+
         let endpoint = Endpoint::new(
-            Box::new(TokioTcpConfig::new),
+            Box::new(|| libp2p_utils::transport(None)),
             identity.libp2p,
             ENDPOINT_CONNECTION_TIMEOUT,
             MAKER_LISTEN_PROTOCOLS.inbound_substream_handlers(
@@ -304,7 +400,11 @@ where
                 identify_listener_actor,
                 (order, order_deprecated),
                 (rollover_addr.clone(), rollover_deprecated_addr.clone()),
-                (collab_settlement_addr, collab_settlement_deprecated_addr),
+                (
+                    collab_settlement_addr,
+                    collab_settlement_deprecated_addr,
+                    partial_collab_settlement_addr,
+                ),
             ),
             endpoint::Subscribers::new(
                 vec![
@@ -320,14 +420,27 @@ where
                     identify_dialer_actor.into(),
                 ],
                 vec![],
-                vec![listener_actor.into()],
+                listener_actors
+                    .into_iter()
+                    .map(|listener_actor| listener_actor.into())
+                    .collect(),
             ),
-            Arc::new(blocked_peers),
+            Arc::new(&configured_blocked_peers | &blocked_peers),
         );
+        let endpoint = match allowed_peers {
+            Some(allowed_peers) => endpoint.with_allowlist(allowed_peers),
+            None => endpoint,
+        };
+        let endpoint = match rate_limits {
+            Some(rate_limits) => endpoint.with_rate_limits(rate_limits),
+            None => endpoint,
+        };
 
         tasks.add(endpoint_context.run(endpoint));
 
-        tasks.add(listener_supervisor.run_log_summary());
+        for listener_supervisor in listener_supervisors {
+            tasks.add(listener_supervisor.run_log_summary());
+        }
         tasks.add(ping_supervisor.run_log_summary());
         tasks.add(identify_listener_supervisor.run_log_summary());
         tasks.add(identify_dialer_supervisor.run_log_summary());
@@ -344,27 +457,46 @@ where
             .create(None)
             .spawn(&mut tasks);
 
-        tasks.add(time_to_first_position_ctx.run(time_to_first_position::Actor::new(db)));
+        let cpfp_actor = cpfp::Actor::new(
+            db.clone(),
+            executor.clone(),
+            wallet_addr.clone().into(),
+            cpfp::BumpPolicy::default(),
+        )
+        .create(None)
+        .spawn(&mut tasks);
+
+        tasks.add(time_to_first_position_ctx.run(time_to_first_position::Actor::new(db.clone())));
 
         tracing::debug!("Maker actor system ready");
 
         Ok(Self {
             cfd_actor: cfd_actor_addr,
+            quoting_actor,
             wallet_actor: wallet_addr,
             rollover_actor: rollover_addr,
             rollover_actor_deprecated: rollover_deprecated_addr,
             _archive_closed_cfds_actor: archive_closed_cfds_actor,
             _archive_failed_cfds_actor: archive_failed_cfds_actor,
+            _cpfp_actor: cpfp_actor,
             executor,
             _oracle_actor: oracle_addr,
             _tasks: tasks,
             _pong_actor: pong_address,
+            endpoint: endpoint_actor,
+            configured_blocked_peers,
+            taker_groups: taker_groups.into_values().collect(),
+            rollover_policy: Mutex::new(rollover::policy::Policy::default()),
+            db,
         })
     }
 
     /// Adjust the parameters which create offers for the connected takers.
     ///
-    /// Once one offer is taken, another one with the same parameters is created.
+    /// Once one offer is taken, another one with the same parameters is created. If automatic
+    /// quoting is enabled, the given prices are replaced with prices derived from the price feed.
+    ///
+    /// Offers for a group or a single taker replace the general offers for these takers only.
     #[allow(clippy::too_many_arguments)]
     pub async fn set_offer_params(
         &self,
@@ -379,26 +511,114 @@ where
         leverage_choices: Vec<Leverage>,
         contract_symbol: ContractSymbol,
         lot_size: LotSize,
+        levels_long: Vec<cfd::OfferLevel>,
+        levels_short: Vec<cfd::OfferLevel>,
+        audience: Audience,
+    ) -> Result<()> {
+        self.ensure_known_audience(&audience)?;
+
+        let params = cfd::OfferParams {
+            price_long,
+            price_short,
+            min_quantity,
+            max_quantity,
+            tx_fee_rate,
+            funding_rate_long,
+            funding_rate_short,
+            opening_fee,
+            leverage_choices,
+            contract_symbol,
+            lot_size,
+            levels_long,
+            levels_short,
+            audience,
+        };
+
+        match &self.quoting_actor {
+            Some(quoting_actor) => quoting_actor.send(quoting::UpdateParams(params)).await??,
+            None => self.cfd_actor.send(params).await??,
+        }
+
+        Ok(())
+    }
+
+    /// Withdraw the offers on `contract_symbol` for a group or a single taker.
+    ///
+    /// The takers fall back to the general offers.
+    pub async fn remove_offers(
+        &self,
+        contract_symbol: ContractSymbol,
+        audience: Audience,
     ) -> Result<()> {
+        self.ensure_known_audience(&audience)?;
+
+        if let Some(quoting_actor) = &self.quoting_actor {
+            quoting_actor
+                .send(quoting::RemoveParams {
+                    contract_symbol,
+                    audience: audience.clone(),
+                })
+                .await?;
+        }
+
         self.cfd_actor
-            .send(cfd::OfferParams {
-                price_long,
-                price_short,
-                min_quantity,
-                max_quantity,
-                tx_fee_rate,
-                funding_rate_long,
-                funding_rate_short,
-                opening_fee,
-                leverage_choices,
+            .send(cfd::RemoveOffers {
                 contract_symbol,
-                lot_size,
+                audience,
             })
             .await??;
 
         Ok(())
     }
 
+    fn ensure_known_audience(&self, audience: &Audience) -> Result<()> {
+        if let Audience::Group(group) = audience {
+            ensure!(
+                self.taker_groups.contains(group),
+                "Unknown taker group {group}"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The peers which are refused to connect.
+    pub async fn blocked_peers(&self) -> Result<HashSet<PeerId>> {
+        let mut blocked_peers = self.configured_blocked_peers.clone();
+        blocked_peers.extend(
+            self.db
+                .load_blocked_peers()
+                .await?
+                .into_iter()
+                .map(|peer_id| peer_id.inner()),
+        );
+
+        Ok(blocked_peers)
+    }
+
+    /// Refuse connections from `peer_id`, dropping the current connection if any.
+    pub async fn block_peer(&self, peer_id: PeerId) -> Result<()> {
+        self.db.insert_blocked_peer(peer_id.into()).await?;
+        self.endpoint.send(BlockPeer(peer_id)).await?;
+
+        Ok(())
+    }
+
+    /// Accept connections from `peer_id` again.
+    pub async fn unblock_peer(&self, peer_id: PeerId) -> Result<()> {
+        ensure!(
+            !self.configured_blocked_peers.contains(&peer_id),
+            "Peer {peer_id} is blocked in blocked_peers.toml"
+        );
+
+        if !self.db.delete_blocked_peer(peer_id.into()).await? {
+            tracing::debug!(%peer_id, "Peer to unblock was not blocked");
+        }
+        self.endpoint.send(UnblockPeer(peer_id)).await?;
+
+        Ok(())
+    }
+
     pub async fn accept_order(&self, order_id: OrderId) -> Result<()> {
         self.cfd_actor.send(cfd::AcceptOrder { order_id }).await??;
         Ok(())
@@ -423,6 +643,56 @@ where
         Ok(())
     }
 
+    /// Ask the taker to propose collaborative settlement of the CFD.
+    ///
+    /// The taker's proposal still has to be accepted like any other.
+    pub async fn request_settlement(&self, order_id: OrderId) -> Result<()> {
+        self.request_from_taker(order_id, maker_request::Request::Settlement)
+            .await
+    }
+
+    /// Ask the taker to roll over the CFD now instead of waiting for the rollover schedule.
+    pub async fn request_rollover(&self, order_id: OrderId) -> Result<()> {
+        self.request_from_taker(order_id, maker_request::Request::Rollover)
+            .await
+    }
+
+    async fn request_from_taker(
+        &self,
+        order_id: OrderId,
+        request: maker_request::Request,
+    ) -> Result<()> {
+        let taker_peer_id = self
+            .executor
+            .query(order_id, |cfd| {
+                cfd.counterparty_peer_id()
+                    .context("No counterparty peer id found")
+            })
+            .await?;
+
+        let decision = maker_request::dialer(
+            self.endpoint.clone(),
+            order_id,
+            taker_peer_id.inner(),
+            request,
+        )
+        .await?;
+
+        match decision {
+            maker_request::Decision::Accepted => {
+                tracing::info!(%order_id, "Taker accepted {request} request")
+            }
+            maker_request::Decision::Pending => {
+                tracing::info!(%order_id, "Taker has to approve {request} request")
+            }
+            maker_request::Decision::Rejected { reason } => {
+                bail!("Taker rejected {request} request: {reason}")
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn commit(&self, order_id: OrderId) -> Result<()> {
         self.executor
             .execute(order_id, |cfd| cfd.manual_commit_to_blockchain())
@@ -446,22 +716,79 @@ where
             .await?
     }
 
+    /// Build the transaction [`ActorSystem::withdraw`] would broadcast, without signing it.
+    pub async fn dry_run_withdraw(
+        &self,
+        amount: Option<Amount>,
+        address: bitcoin::Address,
+        fee: f32,
+    ) -> Result<wallet::WithdrawPreview> {
+        self.wallet_actor
+            .send(wallet::DryRunWithdraw(wallet::Withdraw {
+                amount,
+                address,
+                fee: Some(bdk::FeeRate::from_sat_per_vb(fee)),
+            }))
+            .await?
+    }
+
     pub async fn sync_wallet(&self) -> Result<()> {
         self.wallet_actor.send(wallet::Sync).await?;
         Ok(())
     }
 
-    pub async fn update_rollover_configuration(&self, is_accepting_rollovers: bool) -> Result<()> {
+    /// The history of the fees of a CFD, if there is an open or closed CFD with this ID.
+    pub async fn fee_ledger(&self, order_id: OrderId) -> Result<Option<Vec<FeeLedgerEntry>>> {
+        self.db.load_fee_ledger(order_id).await
+    }
+
+    /// All closed CFDs, ordered by the time they were closed.
+    pub async fn closed_positions(&self) -> Result<Vec<export::ClosedPosition>> {
+        export::closed_positions(&self.db).await
+    }
+
+    /// An encrypted backup of the database and the seed files in `data_dir`.
+    pub async fn backup(&self, data_dir: &Path, passphrase: &str) -> Result<Vec<u8>> {
+        backup::create(&self.db, data_dir, &seed::MAKER_SEED_FILES, passphrase).await
+    }
+
+    /// Replace the rollover policy without persisting it, e.g. with the policy loaded at startup.
+    pub async fn set_rollover_configuration(&self, policy: rollover::policy::Policy) -> Result<()> {
+        let mut current = self.rollover_policy.lock().await;
+        self.send_rollover_policy(policy.clone()).await?;
+        *current = policy;
+
+        Ok(())
+    }
+
+    /// Change the current rollover policy and persist it in `data_dir`.
+    pub async fn update_rollover_configuration(
+        &self,
+        data_dir: &Path,
+        update: impl FnOnce(&mut rollover::policy::Policy),
+    ) -> Result<()> {
+        let mut current = self.rollover_policy.lock().await;
+
+        let mut policy = current.clone();
+        update(&mut policy);
+
+        save_rollover_policy(data_dir, policy.clone()).await?;
+        self.send_rollover_policy(policy.clone()).await?;
+        *current = policy;
+
+        Ok(())
+    }
+
+    async fn send_rollover_policy(&self, policy: rollover::policy::Policy) -> Result<()> {
         self.rollover_actor_deprecated
             .send(rollover::deprecated::maker::UpdateConfiguration::new(
-                is_accepting_rollovers,
+                policy.clone(),
             ))
             .await?;
         self.rollover_actor
-            .send(rollover::maker::UpdateConfiguration::new(
-                is_accepting_rollovers,
-            ))
+            .send(rollover::maker::UpdateConfiguration::new(policy))
             .await?;
+
         Ok(())
     }
 }
@@ -0,0 +1,43 @@
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+use xtra_libp2p::libp2p::PeerId;
+use xtra_libp2p::RateLimits;
+
+const ALLOWED_PEERS_FILENAME: &str = "allowed_peers.toml";
+
+/// Which takers may connect to the maker.
+#[derive(Clone, Debug, Default)]
+pub struct PeerAdmission {
+    /// Peers blocked in `blocked_peers.toml`, these cannot be unblocked at runtime.
+    pub configured_blocked_peers: HashSet<PeerId>,
+    /// Peers blocked at runtime, persisted in the database.
+    pub blocked_peers: HashSet<PeerId>,
+    /// If set, only these peers can connect.
+    pub allowed_peers: Option<HashSet<PeerId>>,
+    pub rate_limits: Option<RateLimits>,
+}
+
+/// Convenience type to load the allowed peer list from toml
+#[derive(Deserialize)]
+struct AllowedPeers {
+    allowed: HashSet<PeerId>,
+}
+
+/// Load the only peers which may connect in allowlist-only mode.
+pub async fn load_allowed_peers(directory: &Path) -> Result<HashSet<PeerId>> {
+    let path = directory.join(ALLOWED_PEERS_FILENAME);
+
+    let raw = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Allowlist-only mode requires a config file at {path:?}"))?;
+    let allowed = toml::from_str::<AllowedPeers>(&raw)
+        .with_context(|| format!("Failed to parse allowed peers at {path:?}"))?
+        .allowed;
+
+    tracing::info!("Only accepting connections from {} peers", allowed.len());
+
+    Ok(allowed)
+}
@@ -1,10 +1,17 @@
 use crate::metrics::time_to_first_position;
+use crate::order_policy;
+use crate::risk;
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
+use daemon::fee_estimate;
+use daemon::into_price_feed_symbol;
 use daemon::order;
+use daemon::position_metrics;
 use daemon::projection;
+use model::olivia::OracleConfig;
+use model::olivia::Oracles;
 use model::ContractSymbol;
 use model::Contracts;
 use model::FundingRate;
@@ -18,20 +25,35 @@ use model::Price;
 use model::Timestamp;
 use model::TxFeeRate;
 use nonempty::NonEmpty;
+use offer::maker::Audience;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
 use time::Duration;
 use time::OffsetDateTime;
 use xtra::prelude::MessageChannel;
+use xtra_bitmex_price_feed::GetLatestQuotes;
+use xtra_bitmex_price_feed::LatestQuotes;
+use xtra_libp2p::libp2p::PeerId;
 use xtra_productivity::xtra_productivity;
 use xtras::SendAsyncSafe;
+use xtras::SendInterval;
 
 const ROLLOVER_PARAMS_TTL: Duration = Duration::minutes(5);
 
+/// How often we check whether published offers need a new fee rate.
+const REPRICE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 #[derive(Clone)]
 pub struct NewOffers {
     pub params: OfferParams,
 }
 
+/// Withdraw the offers on a contract symbol published to a group or a single taker.
+pub struct RemoveOffers {
+    pub contract_symbol: ContractSymbol,
+    pub audience: Audience,
+}
+
 #[derive(Clone, Copy)]
 pub struct AcceptOrder {
     pub order_id: OrderId,
@@ -62,8 +84,23 @@ pub struct TakerDisconnected {
     pub id: Identity,
 }
 
+/// Get the rates to roll over a CFD on `contract_symbol` with the taker `peer_id`.
+#[derive(Clone, Copy)]
+pub struct GetRolloverParams {
+    contract_symbol: ContractSymbol,
+    peer_id: model::libp2p::PeerId,
+}
+
+/// Publish offers again whose fee rate differs from the latest estimate.
+#[derive(Clone, Copy)]
+struct RepriceOffers;
+
 #[derive(Clone, Copy)]
-pub struct GetRolloverParams(ContractSymbol);
+pub struct ApproveRollover {
+    order_id: OrderId,
+    contract_symbol: ContractSymbol,
+    peer_id: model::libp2p::PeerId,
+}
 
 #[derive(Clone, Debug)]
 pub struct OfferParams {
@@ -78,10 +115,26 @@ pub struct OfferParams {
     pub leverage_choices: Vec<Leverage>,
     pub contract_symbol: ContractSymbol,
     pub lot_size: LotSize,
+    /// Further price levels for the maker's long position, next to the one at `price_long`.
+    pub levels_long: Vec<OfferLevel>,
+    /// Further price levels for the maker's short position, next to the one at `price_short`.
+    pub levels_short: Vec<OfferLevel>,
+    /// The takers the offers are published to.
+    pub audience: Audience,
+}
+
+/// A price level of the order book, published as an offer of its own.
+#[derive(Clone, Debug)]
+pub struct OfferLevel {
+    pub price: Price,
+    pub min_quantity: Contracts,
+    pub max_quantity: Contracts,
+    pub leverage_choices: Vec<Leverage>,
 }
 
 impl OfferParams {
-    fn into_offers(self, settlement_interval: Duration) -> Vec<model::Offer> {
+    /// Create one offer per price level, the best price for the taker first on each side.
+    fn into_offers(self, settlement_interval: Duration, oracle: OracleConfig) -> Vec<model::Offer> {
         let Self {
             price_long,
             price_short,
@@ -94,44 +147,50 @@ impl OfferParams {
             leverage_choices,
             contract_symbol,
             lot_size,
+            levels_long,
+            levels_short,
+            audience: _,
         } = self;
 
+        let sides = [
+            (Position::Long, price_long, levels_long, funding_rate_long),
+            (
+                Position::Short,
+                price_short,
+                levels_short,
+                funding_rate_short,
+            ),
+        ];
+
         let mut offers = Vec::new();
 
-        if let Some(price_long) = price_long {
-            let long = model::Offer::new(
-                Position::Long,
-                price_long,
+        for (position, price, levels, funding_rate) in sides {
+            let top_level = price.map(|price| OfferLevel {
+                price,
                 min_quantity,
                 max_quantity,
-                settlement_interval,
-                tx_fee_rate,
-                funding_rate_long,
-                opening_fee,
-                leverage_choices.clone(),
-                contract_symbol,
-                lot_size,
-            );
-
-            offers.push(long);
-        }
+                leverage_choices: leverage_choices.clone(),
+            });
 
-        if let Some(price_short) = price_short {
-            let short = model::Offer::new(
-                Position::Short,
-                price_short,
-                min_quantity,
-                max_quantity,
-                settlement_interval,
-                tx_fee_rate,
-                funding_rate_short,
-                opening_fee,
-                leverage_choices,
-                contract_symbol,
-                lot_size,
-            );
+            let mut levels = top_level.into_iter().chain(levels).collect::<Vec<_>>();
+            levels.sort_by(|a, b| position.cmp_offer_prices(a.price, b.price));
 
-            offers.push(short);
+            offers.extend(levels.into_iter().map(|level| {
+                model::Offer::new(
+                    position,
+                    level.price,
+                    level.min_quantity,
+                    level.max_quantity,
+                    settlement_interval,
+                    tx_fee_rate,
+                    funding_rate,
+                    opening_fee,
+                    level.leverage_choices,
+                    contract_symbol,
+                    lot_size,
+                    oracle.clone(),
+                )
+            }));
         }
 
         offers
@@ -145,10 +204,29 @@ struct RolloverProposal {
     pub timestamp: Timestamp,
 }
 
-#[derive(Default, Clone)]
+/// The rates of the latest offers of an audience, used to roll over the CFDs of its takers.
+#[derive(Clone, Copy)]
 struct RolloverParams {
-    funding_rates: HashMap<ContractSymbol, (FundingRates, OffsetDateTime)>,
+    funding_rates: FundingRates,
     tx_fee_rate: TxFeeRate,
+    expiry: OffsetDateTime,
+}
+
+/// An order awaiting our decision.
+#[derive(Clone, Copy)]
+struct PendingOrder {
+    order: risk::Order,
+    /// When the taker stops waiting for our decision.
+    expiry: OffsetDateTime,
+}
+
+impl PendingOrder {
+    fn new(order: risk::Order) -> Self {
+        Self {
+            order,
+            expiry: OffsetDateTime::now_utc() + order::taker::PLACE_ORDER_RESPONSE_TIMEOUT,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -159,26 +237,43 @@ pub struct FundingRates {
 
 pub struct Actor {
     settlement_interval: Duration,
+    oracles: Oracles,
     projection: xtra::Address<projection::Actor>,
-    rollover_params: RolloverParams,
+    rollover_params: HashMap<(ContractSymbol, Audience), RolloverParams>,
+    /// The group of each taker in one, to find the rollover parameters of its audience.
+    taker_groups: HashMap<PeerId, String>,
     time_to_first_position: xtra::Address<time_to_first_position::Actor>,
     collab_settlement: xtra::Address<daemon::collab_settlement::maker::Actor>,
     collab_settlement_deprecated:
         xtra::Address<daemon::collab_settlement::deprecated::maker::Actor>,
+    partial_collab_settlement: xtra::Address<daemon::collab_settlement::partial::maker::Actor>,
     offer: xtra::Address<offer::maker::Actor>,
     offer_deprecated: xtra::Address<offer::deprecated::maker::Actor>,
     order: xtra::Address<order::maker::Actor>,
     order_deprecated: xtra::Address<order::deprecated::maker::Actor>,
+    limits: risk::Limits,
+    exposure: MessageChannel<position_metrics::GetExposure, position_metrics::Exposure>,
+    /// Orders awaiting our decision.
+    pending_orders: HashMap<OrderId, PendingOrder>,
+    order_policy: order_policy::Policy,
+    price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+    /// Source of the live fee rate estimate, overriding the fee rate of offer parameters.
+    tx_fee_rate_estimate: Option<MessageChannel<fee_estimate::GetTxFeeRate, Option<TxFeeRate>>>,
+    /// The parameters of the offers we currently publish, to publish them again with a new fee
+    /// rate.
+    published_offer_params: HashMap<(ContractSymbol, Audience), OfferParams>,
 }
 
 impl Actor {
     pub fn new(
         settlement_interval: Duration,
+        oracles: Oracles,
         projection: xtra::Address<projection::Actor>,
         time_to_first_position: xtra::Address<time_to_first_position::Actor>,
-        (collab_settlement, collab_settlement_deprecated): (
+        (collab_settlement, collab_settlement_deprecated, partial_collab_settlement): (
             xtra::Address<daemon::collab_settlement::maker::Actor>,
             xtra::Address<daemon::collab_settlement::deprecated::maker::Actor>,
+            xtra::Address<daemon::collab_settlement::partial::maker::Actor>,
         ),
         (offer, offer_deprecated): (
             xtra::Address<offer::maker::Actor>,
@@ -188,55 +283,110 @@ impl Actor {
             xtra::Address<order::maker::Actor>,
             xtra::Address<order::deprecated::maker::Actor>,
         ),
+        limits: risk::Limits,
+        exposure: MessageChannel<position_metrics::GetExposure, position_metrics::Exposure>,
+        order_policy: order_policy::Policy,
+        price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+        tx_fee_rate_estimate: Option<MessageChannel<fee_estimate::GetTxFeeRate, Option<TxFeeRate>>>,
+        taker_groups: HashMap<PeerId, String>,
     ) -> Self {
         Self {
             settlement_interval,
+            oracles,
             projection,
-            rollover_params: RolloverParams::default(),
+            rollover_params: HashMap::new(),
+            taker_groups,
             time_to_first_position,
             collab_settlement,
             collab_settlement_deprecated,
+            partial_collab_settlement,
             offer,
             offer_deprecated,
             order,
             order_deprecated,
+            limits,
+            exposure,
+            pending_orders: HashMap::new(),
+            order_policy,
+            price_feed,
+            tx_fee_rate_estimate,
+            published_offer_params: HashMap::new(),
+        }
+    }
+
+    /// The latest fee rate estimate, if estimation is enabled and an estimate is available.
+    async fn estimated_tx_fee_rate(&self) -> Option<TxFeeRate> {
+        let channel = self.tx_fee_rate_estimate.as_ref()?;
+
+        match channel.send(fee_estimate::GetTxFeeRate).await {
+            Ok(estimate) => estimate,
+            Err(_) => {
+                tracing::warn!("Fee estimate actor disconnected");
+                None
+            }
         }
     }
 
     fn udpate_rollover_params(
         &mut self,
         contract_symbol: ContractSymbol,
+        audience: Audience,
         long: FundingRate,
         short: FundingRate,
         tx_fee_rate: TxFeeRate,
     ) {
-        let funding_rates = FundingRates { long, short };
-        let expiry = OffsetDateTime::now_utc() + ROLLOVER_PARAMS_TTL;
+        let params = RolloverParams {
+            funding_rates: FundingRates { long, short },
+            tx_fee_rate,
+            expiry: OffsetDateTime::now_utc() + ROLLOVER_PARAMS_TTL,
+        };
 
         self.rollover_params
-            .funding_rates
-            .insert(contract_symbol, (funding_rates, expiry));
-        self.rollover_params.tx_fee_rate = tx_fee_rate;
+            .insert((contract_symbol, audience), params);
     }
-}
 
-impl Actor {
-    async fn handle_taker_connected(&mut self, taker_id: Identity) -> Result<()> {
-        self.time_to_first_position
-            .send_async_safe(time_to_first_position::Connected::new(taker_id))
-            .await?;
-        Ok(())
+    /// Get our exposure, leaving out the CFD with `excluding`.
+    async fn current_exposure(
+        &self,
+        excluding: Option<OrderId>,
+    ) -> Result<position_metrics::Exposure> {
+        self.exposure
+            .send(position_metrics::GetExposure { excluding })
+            .await
+            .context("Position metrics actor disconnected")
     }
 
-    async fn handle_taker_disconnected(&mut self, _taker_id: Identity) -> Result<()> {
-        Ok(())
+    /// Forget orders whose takers have stopped waiting for our decision.
+    ///
+    /// Orders are only removed once we decide on them, so we would otherwise keep the orders of
+    /// takers that timed out or disconnected forever.
+    fn remove_expired_pending_orders(&mut self) {
+        let now = OffsetDateTime::now_utc();
+
+        self.pending_orders.retain(|order_id, pending| {
+            let expired = pending.expiry < now;
+            if expired {
+                tracing::debug!(%order_id, "Forgetting order the taker stopped waiting for");
+            }
+
+            !expired
+        });
     }
-}
 
-#[xtra_productivity]
-impl Actor {
-    async fn handle_accept_order(&mut self, msg: AcceptOrder) -> Result<()> {
-        let AcceptOrder { order_id } = msg;
+    async fn accept_order(&mut self, order_id: OrderId) -> Result<()> {
+        self.remove_expired_pending_orders();
+
+        // Our exposure might have changed since the order was placed
+        if let Some(pending) = self.pending_orders.get(&order_id).copied() {
+            let exposure = self.current_exposure(Some(order_id)).await?;
+
+            if let Err(breach) = self.limits.check_order(&exposure, &pending.order) {
+                self.reject_order(order_id, Some(breach.to_string()))
+                    .await?;
+                bail!("Rejected order {order_id} instead: {breach}");
+            }
+        }
+        self.pending_orders.remove(&order_id);
 
         let res = self
             .order
@@ -263,20 +413,28 @@ impl Actor {
         Ok(())
     }
 
-    async fn handle_reject_order(&mut self, msg: RejectOrder) -> Result<()> {
-        let RejectOrder { order_id } = msg;
+    /// Reject the order, telling the taker `reason` if their protocol version supports it.
+    async fn reject_order(&mut self, order_id: OrderId, reason: Option<String>) -> Result<()> {
+        self.pending_orders.remove(&order_id);
 
-        let res = self
-            .order
-            .send(order::maker::Decision::Reject(order_id))
-            .await
-            .map_err(anyhow::Error::new);
+        let (decision, deprecated_decision) = match reason {
+            Some(reason) => (
+                order::maker::Decision::RejectWithReason(order_id, reason.clone()),
+                order::deprecated::maker::Decision::RejectWithReason(order_id, reason),
+            ),
+            None => (
+                order::maker::Decision::Reject(order_id),
+                order::deprecated::maker::Decision::Reject(order_id),
+            ),
+        };
+
+        let res = self.order.send(decision).await.map_err(anyhow::Error::new);
 
         // We try with the deprecated order protocol if the latest version fails
         if let Err(e0) | Ok(Err(e0)) = res {
             if let Err(e1) | Ok(Err(e1)) = self
                 .order_deprecated
-                .send(order::deprecated::maker::Decision::Reject(order_id))
+                .send(deprecated_decision)
                 .await
                 .map_err(anyhow::Error::new)
             {
@@ -290,6 +448,122 @@ impl Actor {
 
         Ok(())
     }
+}
+
+impl Actor {
+    /// Collect what the order policy needs to know about `order`.
+    async fn order_facts(&self, order: &order::NewOrder) -> order_policy::Facts {
+        let spread = match self.price_feed.send(GetLatestQuotes).await {
+            Ok(quotes) => quotes
+                .get(&into_price_feed_symbol(order.offer.contract_symbol))
+                .map(|quote| {
+                    let mid = (quote.bid + quote.ask) / Decimal::TWO;
+                    (order.offer.price.into_decimal() - mid).abs() / mid
+                }),
+            Err(e) => {
+                tracing::warn!("Price feed not available: {e:#}");
+                None
+            }
+        };
+
+        let offer_age = Timestamp::now().seconds() - order.offer.creation_timestamp_maker.seconds();
+
+        order_policy::Facts {
+            quantity: order.quantity,
+            leverage: order.leverage,
+            peer_id: order.peer_id,
+            spread,
+            offer_age: std::time::Duration::from_secs(offer_age.max(0) as u64),
+        }
+    }
+}
+
+impl Actor {
+    async fn handle_taker_connected(&mut self, taker_id: Identity) -> Result<()> {
+        self.time_to_first_position
+            .send_async_safe(time_to_first_position::Connected::new(taker_id))
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_taker_disconnected(&mut self, _taker_id: Identity) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle_accept_order(&mut self, msg: AcceptOrder) -> Result<()> {
+        let AcceptOrder { order_id } = msg;
+
+        self.accept_order(order_id).await
+    }
+
+    async fn handle_reject_order(&mut self, msg: RejectOrder) -> Result<()> {
+        let RejectOrder { order_id } = msg;
+
+        self.reject_order(order_id, None).await
+    }
+
+    async fn handle_new_order(&mut self, msg: order::NewOrder) {
+        self.remove_expired_pending_orders();
+
+        let order = risk::Order::from(&msg);
+        let order_id = order.order_id;
+
+        let exposure = match self.current_exposure(Some(order_id)).await {
+            Ok(exposure) => exposure,
+            Err(e) => {
+                tracing::warn!(%order_id, "Unable to check order against limits: {e:#}");
+                self.pending_orders
+                    .insert(order_id, PendingOrder::new(order));
+                return;
+            }
+        };
+
+        if let Err(breach) = self.limits.check_order(&exposure, &order) {
+            tracing::info!(%order_id, peer_id = %order.peer_id, "Rejecting order: {breach}");
+
+            if let Err(e) = self.reject_order(order_id, Some(breach.to_string())).await {
+                tracing::warn!(%order_id, "{e:#}");
+            }
+
+            return;
+        }
+
+        self.pending_orders
+            .insert(order_id, PendingOrder::new(order));
+
+        let facts = self.order_facts(&msg).await;
+        let rule = match self.order_policy.decide(&facts) {
+            Some(rule) => rule.clone(),
+            None => {
+                tracing::info!(
+                    %order_id,
+                    ?facts,
+                    "No order policy rule matched, awaiting manual decision"
+                );
+                return;
+            }
+        };
+
+        tracing::info!(
+            %order_id,
+            rule = %rule.name,
+            action = %rule.action,
+            ?facts,
+            "Deciding on order by order policy"
+        );
+
+        let result = match rule.action {
+            order_policy::Action::Accept => self.accept_order(order_id).await,
+            order_policy::Action::Reject => self.reject_order(order_id, None).await,
+        };
+
+        if let Err(e) = result {
+            tracing::warn!(%order_id, rule = %rule.name, "Failed to {} order: {e:#}", rule.action);
+        }
+    }
 
     async fn handle_accept_settlement(&mut self, msg: AcceptSettlement) -> Result<()> {
         let AcceptSettlement { order_id } = msg;
@@ -302,6 +576,15 @@ impl Actor {
 
         // We try with the deprecated collaborative settlement protocol if the latest version fails
         if let Err(e0) | Ok(Err(e0)) = res {
+            // The proposal might be to settle only part of the position
+            if let Ok(Ok(())) = self
+                .partial_collab_settlement
+                .send(daemon::collab_settlement::partial::maker::Accept { order_id })
+                .await
+            {
+                return Ok(());
+            }
+
             if let Err(e1) | Ok(Err(e1)) = self
                 .collab_settlement_deprecated
                 .send(daemon::collab_settlement::deprecated::maker::Accept { order_id })
@@ -330,6 +613,15 @@ impl Actor {
 
         // We try with the deprecated collaborative settlement protocol if the latest version fails
         if let Err(e0) | Ok(Err(e0)) = res {
+            // The proposal might be to settle only part of the position
+            if let Ok(Ok(())) = self
+                .partial_collab_settlement
+                .send(daemon::collab_settlement::partial::maker::Reject { order_id })
+                .await
+            {
+                return Ok(());
+            }
+
             if let Err(e1) | Ok(Err(e1)) = self
                 .collab_settlement_deprecated
                 .send(daemon::collab_settlement::deprecated::maker::Reject { order_id })
@@ -347,38 +639,87 @@ impl Actor {
         Ok(())
     }
 
-    async fn handle(
-        &mut self,
-        GetRolloverParams(contract_symbol): GetRolloverParams,
-    ) -> Result<(FundingRates, TxFeeRate)> {
-        let (funding_rates, expiry) = *self
-            .rollover_params
-            .funding_rates
-            .get(&contract_symbol)
-            .with_context(|| format!("Missing {contract_symbol} funding rates"))?;
-
-        if expiry < OffsetDateTime::now_utc() {
+    async fn handle(&mut self, msg: GetRolloverParams) -> Result<(FundingRates, TxFeeRate)> {
+        let GetRolloverParams {
+            contract_symbol,
+            peer_id,
+        } = msg;
+
+        let params = rollover_params_for(
+            &self.rollover_params,
+            &self.taker_groups,
+            contract_symbol,
+            peer_id.inner(),
+        )
+        .with_context(|| format!("Missing {contract_symbol} funding rates"))?;
+
+        if params.expiry < OffsetDateTime::now_utc() {
             bail!("Outdated funding rates");
         }
 
-        let tx_fee_rate = self.rollover_params.tx_fee_rate;
+        let tx_fee_rate = self
+            .estimated_tx_fee_rate()
+            .await
+            .unwrap_or(params.tx_fee_rate);
 
-        Ok((funding_rates, tx_fee_rate))
+        Ok((params.funding_rates, tx_fee_rate))
+    }
+
+    async fn handle(&mut self, msg: ApproveRollover) -> Result<()> {
+        let ApproveRollover {
+            order_id,
+            contract_symbol,
+            peer_id,
+        } = msg;
+
+        let exposure = self.current_exposure(None).await?;
+        self.limits
+            .check_rollover(&exposure, peer_id, contract_symbol)
+            .with_context(|| format!("Rollover of {order_id} not possible"))?;
+
+        Ok(())
     }
 }
 
-#[xtra_productivity]
 impl Actor {
-    async fn handle_offer_params(&mut self, offer_params: OfferParams) -> Result<()> {
-        // 1. Update internal state for rollovers
+    /// Publish offers created from `offer_params` to their audience.
+    async fn publish_offers(&mut self, mut offer_params: OfferParams) -> Result<()> {
+        if let Some(tx_fee_rate) = self.estimated_tx_fee_rate().await {
+            offer_params.tx_fee_rate = tx_fee_rate;
+        }
+        self.published_offer_params.insert(
+            (offer_params.contract_symbol, offer_params.audience.clone()),
+            offer_params.clone(),
+        );
+
+        // 1. Update internal state for rollovers of the takers in the audience
         self.udpate_rollover_params(
             offer_params.contract_symbol,
+            offer_params.audience.clone(),
             offer_params.funding_rate_long,
             offer_params.funding_rate_short,
             offer_params.tx_fee_rate,
         );
 
-        let offers = offer_params.into_offers(self.settlement_interval);
+        // Private offers are only sent to their audience, the UI follows the general offers
+        if offer_params.audience != Audience::Everyone {
+            let audience = offer_params.audience.clone();
+            let oracle = self.oracles.get(offer_params.contract_symbol);
+            let offers = offer_params.into_offers(self.settlement_interval, oracle);
+
+            if let Err(e) = self
+                .offer
+                .send_async_safe(offer::maker::NewOffers::for_audience(offers, audience))
+                .await
+            {
+                tracing::warn!("{e:#}");
+            }
+
+            return Ok(());
+        }
+
+        let oracle = self.oracles.get(offer_params.contract_symbol);
+        let offers = offer_params.into_offers(self.settlement_interval, oracle);
 
         // 2. Notify UI via feed
         self.projection
@@ -399,7 +740,7 @@ impl Actor {
             // Takers on the deprecated version only care (and know how to handle) BTCUSD offers
             let btcusd_offers = offers
                 .into_iter()
-                .filter(|offer| offer.contract_symbol == ContractSymbol::BtcUsd)
+                .filter(|offer| offer.contract_symbol == ContractSymbol::BTC_USD)
                 .collect::<Vec<_>>();
 
             if let Some(btcusd_offers) = NonEmpty::from_vec(btcusd_offers) {
@@ -415,6 +756,64 @@ impl Actor {
 
         Ok(())
     }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, offer_params: OfferParams) -> Result<()> {
+        self.publish_offers(offer_params).await
+    }
+
+    async fn handle(&mut self, msg: RemoveOffers) -> Result<()> {
+        let RemoveOffers {
+            contract_symbol,
+            audience,
+        } = msg;
+
+        if audience == Audience::Everyone {
+            bail!("Only offers published to a group or a single taker can be removed");
+        }
+
+        self.published_offer_params
+            .remove(&(contract_symbol, audience.clone()));
+        self.rollover_params
+            .remove(&(contract_symbol, audience.clone()));
+
+        self.offer
+            .send_async_safe(offer::maker::RemoveOffers {
+                contract_symbol,
+                audience,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle(&mut self, _: RepriceOffers) -> Result<()> {
+        let tx_fee_rate = match self.estimated_tx_fee_rate().await {
+            Some(tx_fee_rate) => tx_fee_rate,
+            None => return Ok(()),
+        };
+
+        let outdated = self
+            .published_offer_params
+            .values()
+            .filter(|offer_params| offer_params.tx_fee_rate != tx_fee_rate)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for offer_params in outdated {
+            tracing::info!(
+                contract_symbol = %offer_params.contract_symbol,
+                audience = %offer_params.audience,
+                %tx_fee_rate,
+                "Publishing offers with new fee rate"
+            );
+            self.publish_offers(offer_params).await?;
+        }
+
+        Ok(())
+    }
 
     async fn handle(&mut self, msg: TakerConnected) -> Result<()> {
         self.handle_taker_connected(msg.id).await
@@ -425,15 +824,58 @@ impl Actor {
     }
 }
 
-/// Source of offer rates used for rolling over CFDs.
+/// The rollover parameters of the most specific audience `peer_id` belongs to.
+///
+/// Takers roll over at the rates of the offers they see: their own offers first, then the ones of
+/// their group and the ones for everyone last.
+fn rollover_params_for(
+    rollover_params: &HashMap<(ContractSymbol, Audience), RolloverParams>,
+    taker_groups: &HashMap<PeerId, String>,
+    contract_symbol: ContractSymbol,
+    peer_id: PeerId,
+) -> Option<RolloverParams> {
+    let mut audiences = vec![Audience::Peer(peer_id)];
+    if let Some(group) = taker_groups.get(&peer_id) {
+        audiences.push(Audience::Group(group.clone()));
+    }
+    audiences.push(Audience::Everyone);
+
+    audiences
+        .into_iter()
+        .find_map(|audience| rollover_params.get(&(contract_symbol, audience)))
+        .copied()
+}
+
+/// Source of offer rates used for rolling over CFDs, and of the decision whether to roll over
+/// at all.
 #[derive(Clone)]
-pub struct RatesChannel(MessageChannel<GetRolloverParams, Result<(FundingRates, TxFeeRate)>>);
+pub struct RatesChannel {
+    rates: MessageChannel<GetRolloverParams, Result<(FundingRates, TxFeeRate)>>,
+    approval: MessageChannel<ApproveRollover, Result<()>>,
+}
 
 impl RatesChannel {
-    pub fn new(
-        channel: MessageChannel<GetRolloverParams, Result<(FundingRates, TxFeeRate)>>,
-    ) -> Self {
-        Self(channel)
+    pub fn new(cfd: xtra::Address<Actor>) -> Self {
+        Self {
+            rates: cfd.clone().into(),
+            approval: cfd.into(),
+        }
+    }
+
+    async fn approve(
+        &self,
+        order_id: OrderId,
+        contract_symbol: ContractSymbol,
+        peer_id: model::libp2p::PeerId,
+    ) -> Result<()> {
+        self.approval
+            .send(ApproveRollover {
+                order_id,
+                contract_symbol,
+                peer_id,
+            })
+            .await
+            .context("CFD actor disconnected")?
     }
 }
 
@@ -442,10 +884,14 @@ impl rollover::deprecated::protocol::GetRates for RatesChannel {
     async fn get_rates(
         &self,
         contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
     ) -> Result<rollover::deprecated::protocol::Rates> {
         let (FundingRates { long, short }, tx_fee_rate) = self
-            .0
-            .send(GetRolloverParams(contract_symbol))
+            .rates
+            .send(GetRolloverParams {
+                contract_symbol,
+                peer_id: counterparty,
+            })
             .await
             .context("CFD actor disconnected")??;
 
@@ -462,10 +908,14 @@ impl rollover::protocol::GetRates for RatesChannel {
     async fn get_rates(
         &self,
         contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
     ) -> Result<rollover::protocol::Rates> {
         let (FundingRates { long, short }, tx_fee_rate) = self
-            .0
-            .send(GetRolloverParams(contract_symbol))
+            .rates
+            .send(GetRolloverParams {
+                contract_symbol,
+                peer_id: counterparty,
+            })
             .await
             .context("CFD actor disconnected")??;
 
@@ -473,9 +923,158 @@ impl rollover::protocol::GetRates for RatesChannel {
     }
 }
 
+#[async_trait]
+impl rollover::deprecated::protocol::ApproveRollover for RatesChannel {
+    async fn approve_rollover(
+        &self,
+        order_id: OrderId,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<()> {
+        self.approve(order_id, contract_symbol, counterparty).await
+    }
+}
+
+#[async_trait]
+impl rollover::protocol::ApproveRollover for RatesChannel {
+    async fn approve_rollover(
+        &self,
+        order_id: OrderId,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<()> {
+        self.approve(order_id, contract_symbol, counterparty).await
+    }
+}
+
 #[async_trait]
 impl xtra::Actor for Actor {
     type Stop = ();
 
+    async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
+        if self.tx_fee_rate_estimate.is_none() {
+            return;
+        }
+
+        let this = ctx.address().expect("we just started");
+
+        tokio_extras::spawn(
+            &this.clone(),
+            this.send_interval(
+                REPRICE_INTERVAL,
+                || RepriceOffers,
+                xtras::IncludeSpan::Always,
+            ),
+        );
+    }
+
     async fn stopped(self) -> Self::Stop {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bdk::bitcoin::Amount;
+    use rust_decimal_macros::dec;
+
+    fn price(price: Decimal) -> Price {
+        Price::new(price).unwrap()
+    }
+
+    fn level(price: Price, max_quantity: u64) -> OfferLevel {
+        OfferLevel {
+            price,
+            min_quantity: Contracts::new(100),
+            max_quantity: Contracts::new(max_quantity),
+            leverage_choices: vec![Leverage::TWO],
+        }
+    }
+
+    #[test]
+    fn offers_are_created_per_level_best_price_first() {
+        let params = OfferParams {
+            price_long: Some(price(dec!(19_900))),
+            price_short: Some(price(dec!(20_100))),
+            min_quantity: Contracts::new(100),
+            max_quantity: Contracts::new(1000),
+            tx_fee_rate: TxFeeRate::default(),
+            funding_rate_long: FundingRate::default(),
+            funding_rate_short: FundingRate::default(),
+            opening_fee: OpeningFee::new(Amount::from_sat(2)),
+            leverage_choices: vec![Leverage::TWO],
+            contract_symbol: ContractSymbol::BTC_USD,
+            lot_size: LotSize::new(100),
+            levels_long: vec![
+                level(price(dec!(19_500)), 5000),
+                level(price(dec!(19_800)), 2000),
+            ],
+            levels_short: vec![level(price(dec!(20_300)), 3000)],
+            audience: Audience::Everyone,
+        };
+
+        let offers = params.into_offers(model::SETTLEMENT_INTERVAL, OracleConfig::olivia());
+
+        let ladder = offers
+            .iter()
+            .map(|offer| (offer.position_maker, offer.price, offer.max_quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ladder,
+            vec![
+                (Position::Long, price(dec!(19_900)), Contracts::new(1000)),
+                (Position::Long, price(dec!(19_800)), Contracts::new(2000)),
+                (Position::Long, price(dec!(19_500)), Contracts::new(5000)),
+                (Position::Short, price(dec!(20_100)), Contracts::new(1000)),
+                (Position::Short, price(dec!(20_300)), Contracts::new(3000)),
+            ]
+        );
+    }
+
+    fn rollover_params(funding_rate: Decimal) -> RolloverParams {
+        let funding_rate = FundingRate::new(funding_rate).unwrap();
+
+        RolloverParams {
+            funding_rates: FundingRates {
+                long: funding_rate,
+                short: funding_rate,
+            },
+            tx_fee_rate: TxFeeRate::default(),
+            expiry: OffsetDateTime::now_utc() + ROLLOVER_PARAMS_TTL,
+        }
+    }
+
+    #[test]
+    fn takers_roll_over_at_the_rates_of_their_most_specific_audience() {
+        let vip = PeerId::random();
+        let grouped = PeerId::random();
+        let other = PeerId::random();
+        let taker_groups = HashMap::from([(grouped, "friends".to_owned())]);
+
+        let symbol = ContractSymbol::BTC_USD;
+        let params = HashMap::from([
+            ((symbol, Audience::Everyone), rollover_params(dec!(0.001))),
+            (
+                (symbol, Audience::Group("friends".to_owned())),
+                rollover_params(dec!(0.0005)),
+            ),
+            ((symbol, Audience::Peer(vip)), rollover_params(dec!(0.0001))),
+        ]);
+
+        let funding_rate = |peer_id| {
+            rollover_params_for(&params, &taker_groups, symbol, peer_id)
+                .unwrap()
+                .funding_rates
+                .long
+        };
+
+        assert_eq!(funding_rate(vip), FundingRate::new(dec!(0.0001)).unwrap());
+        assert_eq!(
+            funding_rate(grouped),
+            FundingRate::new(dec!(0.0005)).unwrap()
+        );
+        assert_eq!(funding_rate(other), FundingRate::new(dec!(0.001)).unwrap());
+        assert!(
+            rollover_params_for(&params, &taker_groups, ContractSymbol::ETH_USD, vip).is_none()
+        );
+    }
+}
@@ -1,21 +1,52 @@
+use anyhow::Result;
 use bdk::bitcoin::util::bip32::ExtendedPrivKey;
+use bdk::bitcoin::Amount;
 use clap::Parser;
 use daemon::bdk;
+use daemon::fee_estimate;
+use libp2p_tor::Authentication;
+use model::olivia::OracleConfig;
+use model::olivia::Oracles;
+use model::Catalogue;
+use model::Contracts;
+use model::TxFeeRate;
+use rust_decimal::Decimal;
+use shared_bin::cli::parse_contracts_limit;
+use shared_bin::cli::parse_oracle;
+use shared_bin::cli::resolve_contract_symbols;
 use shared_bin::cli::Network;
 use shared_bin::logger::LevelFilter;
 use shared_bin::logger::LOCAL_COLLECTOR_ENDPOINT;
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
+use xtra_bitmex_price_feed::Source;
+use xtra_libp2p::libp2p::Multiaddr;
+use xtra_libp2p::RateLimits;
 
 pub use actor_system::ActorSystem;
+pub use admission::load_allowed_peers;
+pub use admission::PeerAdmission;
 pub use blocked_peers::load_blocked_peers;
+pub use onion_service::add_onion_service;
+pub use onion_service::keep_onion_service;
+pub use order_policy::load_order_policy;
+pub use rollover_policy::load_rollover_policy;
+pub use taker_groups::load_taker_groups;
 
 mod actor_system;
+mod admission;
 mod blocked_peers;
 pub mod cfd;
 mod metrics;
+mod onion_service;
+pub mod order_policy;
+pub mod quoting;
+pub mod risk;
+mod rollover_policy;
 pub mod routes;
+mod taker_groups;
 
 #[derive(Clone, Debug)]
 pub struct Password(String);
@@ -46,14 +77,64 @@ pub struct Opts {
     #[clap(long, default_value = "10000")]
     pub p2p_port: u16,
 
+    /// The multiaddr to listen on for libp2p connections, e.g. `/ip6/::/tcp/10000`.
+    ///
+    /// Only TCP on an IPv4 or IPv6 address is supported.
+    ///
+    /// Can be given multiple times. If not specified, it defaults to all IPv4 interfaces on
+    /// `p2p-port`.
+    #[clap(long)]
+    pub p2p_listen: Vec<Multiaddr>,
+
+    /// The multiaddr to advertise to takers, e.g. `/dns/maker.example.com/tcp/10000`.
+    ///
+    /// Can be given multiple times. If not specified, the listen addresses are advertised.
+    #[clap(long)]
+    pub p2p_announce: Vec<Multiaddr>,
+
+    /// The control port of a Tor daemon, e.g. `127.0.0.1:9051`.
+    ///
+    /// If specified, libp2p connections are accepted through an onion service only and the p2p
+    /// port is not exposed publicly. The onion service forwards to `p2p-port` on localhost, hence
+    /// it cannot be combined with `p2p-listen`.
+    #[clap(long, conflicts_with = "p2p_listen")]
+    pub tor_control_port: Option<SocketAddr>,
+
+    /// Password for the Tor control port, if Tor is configured with `HashedControlPassword`.
+    #[clap(long, conflicts_with = "tor_control_cookie")]
+    pub tor_control_password: Option<String>,
+
+    /// Cookie file for the Tor control port, if Tor is configured with `CookieAuthentication`.
+    #[clap(long)]
+    pub tor_control_cookie: Option<PathBuf>,
+
     /// The IP address to listen on for the HTTP API.
     #[clap(long, default_value = "127.0.0.1:8001")]
     pub http_address: SocketAddr,
 
+    /// Where to get prices from, either `bitmex` or `file:<path>` to replay quotes from a file.
+    ///
+    /// Can be given multiple times, in which case the median over all sources is used.
+    #[clap(long, default_value = "bitmex")]
+    pub price_feed: Vec<Source>,
+
+    /// Use another oracle than Olivia for a contract symbol.
+    ///
+    /// Given as `<SYMBOL>=<PUBLIC_KEY>,<BASE_URL>[,<EVENT_PREFIX>]`, can be given once per symbol.
+    #[clap(long, value_parser(parse_oracle))]
+    pub oracle: Vec<(String, OracleConfig)>,
+
     /// Where to permanently store data, defaults to the current working directory.
     #[clap(long)]
     pub data_dir: Option<PathBuf>,
 
+    /// Where to write the backups of open CFDs to, defaults to `cfd_backups` in the data dir.
+    ///
+    /// Together with the seed these backups are sufficient to recover open CFDs. The directory
+    /// should be on a different disk than the data dir.
+    #[clap(long)]
+    pub cfd_backup_dir: Option<PathBuf>,
+
     /// If enabled logs will be in json format
     #[clap(short, long)]
     pub json: bool,
@@ -118,4 +199,129 @@ pub struct Opts {
     /// If enabled, the log will be printed to {service_name}.log in the data dir
     #[clap(long)]
     pub log_to_file: bool,
+
+    /// Price offers automatically from the price feed with the given spread, relative to the
+    /// mid price (e.g. 0.002 for 0.2%).
+    ///
+    /// The remaining offer parameters are taken from the last `PUT /<symbol>/offer`.
+    #[clap(long)]
+    pub quote_spread: Option<Decimal>,
+
+    /// Shift automatically quoted prices by this fraction of the mid price per contract of net
+    /// open exposure.
+    #[clap(long, default_value = "0")]
+    pub quote_skew: Decimal,
+
+    /// How often automatically quoted offers are re-priced, in seconds.
+    #[clap(long, default_value = "30")]
+    pub quote_interval_secs: u64,
+
+    /// Reject orders that would bring the number of contracts held with a single taker above
+    /// this limit.
+    #[clap(long)]
+    pub max_contracts_per_taker: Option<u64>,
+
+    /// Reject orders that would bring our net long or short exposure in a contract symbol above
+    /// this limit.
+    ///
+    /// Given as `<SYMBOL>=<CONTRACTS>`, can be given once per symbol.
+    #[clap(long, value_parser(parse_contracts_limit))]
+    pub max_net_exposure: Vec<(String, Contracts)>,
+
+    /// Reject orders that would bring the total margin we locked up above this limit, in
+    /// satoshis.
+    #[clap(long)]
+    pub max_total_margin: Option<u64>,
+
+    /// Derive the transaction fee rate of offers and rollovers from the fee estimate of the
+    /// chain backend, targeting confirmation within this many blocks.
+    ///
+    /// If not provided, the fee rate is taken from the last `PUT /<symbol>/offer`.
+    #[clap(long)]
+    pub tx_fee_rate_target_blocks: Option<u16>,
+
+    /// The lowest estimated transaction fee rate we use, in sat/vbyte.
+    #[clap(long, default_value = "1")]
+    pub min_tx_fee_rate: TxFeeRate,
+
+    /// The highest estimated transaction fee rate we use, in sat/vbyte.
+    #[clap(long, default_value = "100")]
+    pub max_tx_fee_rate: TxFeeRate,
+
+    /// Only accept libp2p connections from the peers in `allowed_peers.toml` in the data dir.
+    #[clap(long)]
+    pub allowlist_only: bool,
+
+    /// Drop libp2p connections of a peer which connects more often than this per minute.
+    #[clap(long)]
+    pub max_connections_per_minute: Option<u32>,
+
+    /// Close substreams of a peer which opens more than this many per minute on a connection.
+    #[clap(long)]
+    pub max_substreams_per_minute: Option<u32>,
+}
+
+impl Opts {
+    /// The configuration of the quoting engine, if automatic quoting is enabled.
+    pub fn quoting(&self) -> Option<quoting::Config> {
+        Some(quoting::Config {
+            spread: self.quote_spread?,
+            skew: self.quote_skew,
+            interval: Duration::from_secs(self.quote_interval_secs),
+        })
+    }
+
+    /// The configuration of the fee rate estimation, if enabled.
+    pub fn fee_estimate(&self) -> Result<Option<fee_estimate::Config>> {
+        let target_blocks = match self.tx_fee_rate_target_blocks {
+            Some(target_blocks) => target_blocks,
+            None => return Ok(None),
+        };
+
+        let config =
+            fee_estimate::Config::new(target_blocks, self.min_tx_fee_rate, self.max_tx_fee_rate)?;
+
+        Ok(Some(config))
+    }
+
+    /// The rate limits on libp2p connections, if any.
+    pub fn rate_limits(&self) -> Option<RateLimits> {
+        if self.max_connections_per_minute.is_none() && self.max_substreams_per_minute.is_none() {
+            return None;
+        }
+
+        Some(RateLimits {
+            connections: self.max_connections_per_minute.unwrap_or(u32::MAX),
+            substreams: self.max_substreams_per_minute.unwrap_or(u32::MAX),
+            period: Duration::from_secs(60),
+        })
+    }
+
+    /// How to authenticate with the Tor control port.
+    pub fn tor_authentication(&self) -> Authentication {
+        match (&self.tor_control_password, &self.tor_control_cookie) {
+            (Some(password), _) => Authentication::Password(password.clone()),
+            (None, Some(cookie)) => Authentication::Cookie(cookie.clone()),
+            (None, None) => Authentication::Null,
+        }
+    }
+
+    /// The oracles to use per contract symbol.
+    pub fn oracles(&self, catalogue: &Catalogue) -> Result<Oracles> {
+        Ok(Oracles::new(resolve_contract_symbols(
+            catalogue,
+            &self.oracle,
+        )?))
+    }
+
+    /// The risk limits enforced on orders and rollovers.
+    pub fn limits(&self, catalogue: &Catalogue) -> Result<risk::Limits> {
+        Ok(risk::Limits {
+            max_contracts_per_taker: self.max_contracts_per_taker.map(Contracts::new),
+            max_net_exposure: resolve_contract_symbols(catalogue, &self.max_net_exposure)?
+                .into_iter()
+                .collect(),
+            max_total_margin: self.max_total_margin.map(Amount::from_sat),
+        })
+    }
 }
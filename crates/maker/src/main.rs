@@ -1,10 +1,15 @@
 use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
 use daemon::bdk::FeeRate;
+use daemon::cfd_backup;
+use daemon::fee_estimate;
 use daemon::monitor;
 use daemon::oracle;
+use daemon::position_metrics;
+use daemon::price_feed;
 use daemon::projection;
 use daemon::seed;
 use daemon::seed::RandomSeed;
@@ -12,21 +17,31 @@ use daemon::seed::Seed;
 use daemon::wallet;
 use daemon::wallet::MAKER_WALLET_ID;
 use daemon::N_PAYOUTS;
+use maker::add_onion_service;
+use maker::keep_onion_service;
+use maker::load_allowed_peers;
 use maker::load_blocked_peers;
+use maker::load_order_policy;
+use maker::load_rollover_policy;
+use maker::load_taker_groups;
 use maker::routes;
 use maker::ActorSystem;
 use maker::Opts;
-use model::olivia;
+use maker::PeerAdmission;
 use model::Role;
 use model::SETTLEMENT_INTERVAL;
 use rocket_cookie_auth::users::Users;
 use shared_bin::catchers::default_catchers;
-use shared_bin::cli::Withdraw;
+use shared_bin::cli::backup_passphrase;
+use shared_bin::cli::Command;
+use shared_bin::contracts::load_contracts;
 use shared_bin::fairings;
 use shared_bin::logger;
+use std::collections::HashSet;
 use std::net::SocketAddr;
 use tokio_extras::Tasks;
-use xtras::supervisor::always_restart;
+use xtra_libp2p::libp2p::multiaddr::Protocol;
+use xtra_libp2p::multiaddress_ext::MultiaddrExt;
 use xtras::supervisor::Supervisor;
 
 #[rocket::main]
@@ -64,10 +79,38 @@ async fn main() -> Result<()> {
         "CFDs created with this release will settle after {settlement_interval_hours} hours"
     );
 
+    let catalogue = load_contracts(&data_dir)
+        .await
+        .context("Failed to load contract catalogue")?;
+    let contract_symbols = catalogue.symbols();
+    tracing::info!("Offering contract symbols: {contract_symbols:?}");
+
+    if let Some(Command::RestoreBackup {
+        input,
+        passphrase_file,
+    }) = opts.network.command()
+    {
+        let passphrase = backup_passphrase(passphrase_file.as_deref()).await?;
+        let backup = tokio::fs::read(input)
+            .await
+            .with_context(|| format!("Failed to read backup {}", input.display()))?;
+        daemon::backup::restore(
+            &backup,
+            &passphrase,
+            &data_dir,
+            "maker.sqlite",
+            &seed::MAKER_SEED_FILES,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
     let wallet_seed_file = &data_dir.join(seed::MAKER_WALLET_SEED_FILE);
     let wallet_seed = RandomSeed::initialize(wallet_seed_file).await?;
 
     let bitcoin_network = opts.network.bitcoin_network();
+    let chain = opts.network.chain()?;
 
     let ext_priv_key = match opts.wallet_xprv {
         Some(wallet_xprv) => {
@@ -85,18 +128,14 @@ async fn main() -> Result<()> {
     let mut wallet_dir = data_dir.clone();
 
     wallet_dir.push(MAKER_WALLET_ID);
-    let (wallet, wallet_feed_receiver) = wallet::Actor::spawn(
-        opts.network.electrum(),
-        ext_priv_key,
-        wallet_dir,
-        wallet_seed.is_managed(),
-    )?;
+    let (wallet, wallet_feed_receiver) =
+        wallet::Actor::spawn(&chain, ext_priv_key, wallet_dir, wallet_seed.is_managed())?;
 
-    if let Some(Withdraw::Withdraw {
+    if let Some(Command::Withdraw {
         amount,
         address,
         fee,
-    }) = opts.network.withdraw()
+    }) = opts.network.command()
     {
         wallet
             .send(wallet::Withdraw {
@@ -131,63 +170,232 @@ async fn main() -> Result<()> {
         .merge(("secret_key", RandomSeed::default().seed()));
 
     let p2p_port = opts.p2p_port;
-    let p2p_socket = format!("0.0.0.0:{p2p_port}").parse::<SocketAddr>().unwrap();
+    let p2p_socket = match opts.tor_control_port {
+        // Only reachable through the onion service
+        Some(_) => format!("127.0.0.1:{p2p_port}"),
+        None => format!("0.0.0.0:{p2p_port}"),
+    }
+    .parse::<SocketAddr>()
+    .unwrap();
 
     let db =
         sqlite_db::connect(data_dir.join("maker.sqlite"), opts.ignore_migration_errors).await?;
 
-    let blocked_peers = load_blocked_peers(&data_dir)
+    if let Some(Command::ExportClosedCfds { format, output }) = opts.network.command() {
+        daemon::export::write_closed_positions(&db, *format, output.as_deref()).await?;
+
+        return Ok(());
+    }
+
+    if let Some(Command::Backup {
+        output,
+        passphrase_file,
+    }) = opts.network.command()
+    {
+        let passphrase = backup_passphrase(passphrase_file.as_deref()).await?;
+        let backup =
+            daemon::backup::create(&db, &data_dir, &seed::MAKER_SEED_FILES, &passphrase).await?;
+        tokio::fs::write(output, backup)
+            .await
+            .with_context(|| format!("Failed to write backup to {}", output.display()))?;
+
+        return Ok(());
+    }
+
+    let cfd_backup_dir = opts
+        .cfd_backup_dir
+        .clone()
+        .unwrap_or_else(|| {
+            let cfd_backup_dir = data_dir.join("cfd_backups");
+            tracing::warn!(
+                "No --cfd-backup-dir configured, writing CFD backups to {} in the data directory. Put them on a different disk to survive losing the database",
+                cfd_backup_dir.display()
+            );
+
+            cfd_backup_dir
+        });
+    let cfd_backup_sink = cfd_backup::LocalDirectory::new(cfd_backup_dir);
+    let cfd_backup_key = wallet_seed.derive_cfd_backup_key();
+
+    if let Some(Command::RecoverCfds) = opts.network.command() {
+        let recovery = cfd_backup::recover(
+            &db,
+            &cfd_backup_sink,
+            &cfd_backup_key,
+            chain.backend(bitcoin_network)?,
+        )
+        .await?;
+        tracing::info!(
+            "Recovered {} CFDs: {:?}",
+            recovery.recovered.len(),
+            recovery.recovered
+        );
+        if recovery.skipped > 0 {
+            tracing::error!(
+                "Skipped {} CFD backups which could not be decrypted or are outdated",
+                recovery.skipped
+            );
+        }
+
+        return Ok(());
+    }
+
+    let configured_blocked_peers = load_blocked_peers(&data_dir)
         .await
         .context("Failed to load blocked peers")?;
+    let blocked_peers = db
+        .load_blocked_peers()
+        .await
+        .context("Failed to load blocked peers from database")?
+        .into_iter()
+        .map(|peer_id| peer_id.inner())
+        .collect();
+    let allowed_peers = match opts.allowlist_only {
+        true => Some(load_allowed_peers(&data_dir).await?),
+        false => None,
+    };
+    let admission = PeerAdmission {
+        configured_blocked_peers,
+        blocked_peers,
+        allowed_peers,
+        rate_limits: opts.rate_limits(),
+    };
+
+    let order_policy = load_order_policy(&data_dir)
+        .await
+        .context("Failed to load order policy")?;
+
+    let taker_groups = load_taker_groups(&data_dir)
+        .await
+        .context("Failed to load taker groups")?;
+
+    let rollover_policy = load_rollover_policy(&data_dir)
+        .await
+        .context("Failed to load rollover policy")?;
 
     // Create actors
-    let endpoint_listen =
-        daemon::libp2p_utils::create_listen_tcp_multiaddr(&p2p_socket.ip(), p2p_socket.port())
-            .expect("to parse properly");
-
-    let (supervisor, price_feed) = Supervisor::with_policy(
-        {
-            let network = opts.network.bitmex_network();
-            move || xtra_bitmex_price_feed::Actor::new(network)
-        },
-        always_restart::<xtra_bitmex_price_feed::Error>(),
+    let endpoint_listen = match opts.p2p_listen.as_slice() {
+        [] => vec![daemon::libp2p_utils::create_listen_tcp_multiaddr(
+            &p2p_socket.ip(),
+            p2p_socket.port(),
+        )
+        .expect("to parse properly")],
+        p2p_listen => p2p_listen.to_vec(),
+    };
+    for listen_multiaddr in &endpoint_listen {
+        let is_ip = matches!(
+            listen_multiaddr.iter().next(),
+            Some(Protocol::Ip4(_) | Protocol::Ip6(_))
+        );
+        ensure!(
+            is_ip && listen_multiaddr.is_tcp(),
+            "Cannot listen on {listen_multiaddr}, only TCP on an IP address is supported"
+        );
+    }
+
+    let onion_address = match opts.tor_control_port {
+        Some(control_port) => {
+            let authentication = opts.tor_authentication();
+            let onion_service =
+                add_onion_service(&data_dir, control_port, &authentication, p2p_socket)
+                    .await
+                    .context("Failed to add onion service")?;
+            let onion_address = onion_service.multiaddr();
+
+            // Kept alive until shutdown, Tor removes the onion service once it is dropped
+            tasks.add(keep_onion_service(
+                onion_service,
+                control_port,
+                authentication,
+                p2p_socket,
+            ));
+
+            Some(onion_address)
+        }
+        None => None,
+    };
+    let endpoint_announce = match (onion_address, opts.p2p_announce.as_slice()) {
+        (Some(onion_address), p2p_announce) => p2p_announce
+            .iter()
+            .cloned()
+            .chain([onion_address])
+            .collect::<HashSet<_>>(),
+        (None, []) => endpoint_listen.iter().cloned().collect(),
+        (None, p2p_announce) => p2p_announce.iter().cloned().collect(),
+    };
+    tracing::info!(
+        "Announcing libp2p addresses: {}",
+        endpoint_announce
+            .iter()
+            .map(|multiaddr| multiaddr.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let price_feed = price_feed::spawn(
+        &opts.price_feed,
+        &contract_symbols,
+        opts.network.bitmex_network(),
+        &mut tasks,
     );
-    tasks.add(supervisor.run_log_summary());
 
     let (feed_senders, feed_receivers) = projection::feeds();
     let feed_senders = std::sync::Arc::new(feed_senders);
 
     let (supervisor, projection_actor) = Supervisor::new({
         let db = db.clone();
+        let price_feed = price_feed.clone();
+        let contract_symbols = contract_symbols.clone();
         move || {
             projection::Actor::new(
                 db.clone(),
                 bitcoin_network,
                 price_feed.clone().into(),
                 Role::Maker,
+                contract_symbols.clone(),
                 feed_senders.clone(),
             )
         }
     });
     tasks.add(supervisor.run_log_summary());
 
+    let fee_estimate = match opts.fee_estimate()? {
+        Some(config) => Some(fee_estimate::Actor::new(
+            chain.backend(bitcoin_network)?,
+            config,
+        )),
+        None => None,
+    };
+
+    let oracles = opts.oracles(&catalogue)?;
     let maker = ActorSystem::new(
         db.clone(),
         wallet.clone(),
-        *olivia::PUBLIC_KEY,
-        |executor| oracle::Actor::new(db.clone(), executor),
-        |executor| {
-            let electrum = opts.network.electrum().to_string();
-            monitor::Actor::new(db.clone(), electrum, executor)
-        },
+        oracles.clone(),
+        |executor| oracle::Actor::new(db.clone(), executor, oracles, &contract_symbols),
+        |executor| monitor::Actor::new(db.clone(), chain.backend(bitcoin_network)?, executor),
         SETTLEMENT_INTERVAL,
         N_PAYOUTS,
         projection_actor.clone(),
         identities,
         endpoint_listen,
-        blocked_peers,
+        endpoint_announce,
+        admission,
+        taker_groups,
+        price_feed.clone().into(),
+        opts.quoting(),
+        opts.limits(&catalogue)?,
+        order_policy,
+        cfd_backup::Actor::new(db.clone(), Box::new(cfd_backup_sink), cfd_backup_key),
+        position_metrics::Actor::new(db.clone(), contract_symbols.clone()),
+        fee_estimate,
     )?;
 
+    maker
+        .set_rollover_configuration(rollover_policy)
+        .await
+        .context("Failed to apply rollover policy")?;
+
     if let Some(password) = opts.password {
         db.clone()
             .update_password(rocket_cookie_auth::user::create_password(
@@ -205,15 +413,27 @@ async fn main() -> Result<()> {
         .manage(maker)
         .manage(users)
         .manage(bitcoin_network)
+        .manage(data_dir)
+        .manage(contract_symbols)
+        .manage(catalogue)
         .mount(
             "/api",
             rocket::routes![
                 routes::maker_feed,
                 routes::put_offer_params,
                 routes::put_offer_params_for_symbol,
+                routes::delete_offer_for_symbol,
+                routes::get_blocked_peers,
+                routes::put_blocked_peer,
+                routes::delete_blocked_peer,
                 routes::post_cfd_action,
+                routes::get_cfd_fees,
+                routes::get_closed_cfds_export,
+                routes::post_backup,
                 routes::get_cfds,
                 routes::put_sync_wallet,
+                routes::post_withdraw_request,
+                routes::update_rollover_configuration,
                 shared_bin::routes::get_health_check,
                 shared_bin::routes::get_metrics,
                 shared_bin::routes::get_version,
@@ -0,0 +1,99 @@
+use anyhow::Context;
+use anyhow::Result;
+use libp2p_tor::Authentication;
+use libp2p_tor::OnionService;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+const KEY_FILENAME: &str = "onion_service_key";
+
+/// How long to wait before trying to add the onion service again if Tor is not reachable.
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Host the libp2p endpoint listening on `target` as an onion service.
+///
+/// The key of the onion service is stored in `directory` so that the onion address stays the same
+/// across restarts. The onion service is removed once the returned value is dropped.
+pub async fn add_onion_service(
+    directory: &Path,
+    control_port: SocketAddr,
+    authentication: &Authentication,
+    target: SocketAddr,
+) -> Result<OnionService> {
+    let key_file = directory.join(KEY_FILENAME);
+
+    let private_key = if key_file.try_exists()? {
+        let private_key = tokio::fs::read_to_string(&key_file).await?;
+        Some(private_key.trim().to_owned())
+    } else {
+        None
+    };
+
+    let onion_service = OnionService::add(
+        control_port,
+        authentication,
+        private_key.as_deref(),
+        target.port(),
+        target,
+    )
+    .await?;
+
+    if private_key.is_none() {
+        write_private_key(&key_file, onion_service.private_key())
+            .await
+            .with_context(|| format!("Failed to store onion service key at {key_file:?}"))?;
+    }
+
+    Ok(onion_service)
+}
+
+/// Keep `onion_service` up, adding it again with the same key whenever Tor drops it, e.g. because
+/// Tor restarted.
+pub async fn keep_onion_service(
+    mut onion_service: OnionService,
+    control_port: SocketAddr,
+    authentication: Authentication,
+    target: SocketAddr,
+) {
+    loop {
+        onion_service.closed().await;
+        tracing::warn!("Tor closed the control connection, adding onion service again");
+
+        onion_service = loop {
+            match OnionService::add(
+                control_port,
+                &authentication,
+                Some(onion_service.private_key()),
+                target.port(),
+                target,
+            )
+            .await
+            {
+                Ok(onion_service) => break onion_service,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to add onion service, retrying in {} seconds: {e:#}",
+                        RETRY_INTERVAL.as_secs()
+                    );
+                    tokio_extras::time::sleep(RETRY_INTERVAL).await;
+                }
+            }
+        };
+    }
+}
+
+/// Write the onion service key readable by the owner only.
+async fn write_private_key(key_file: &Path, private_key: &str) -> Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(key_file).await?;
+    file.write_all(private_key.as_bytes()).await?;
+    file.flush().await?;
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use anyhow::Context;
+use anyhow::Result;
+use model::libp2p::PeerId;
+use model::Contracts;
+use model::Leverage;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+const FILENAME: &str = "order_policy.toml";
+
+/// Rules to decide automatically about incoming orders.
+///
+/// The rules are evaluated in order and the first matching rule decides. Orders that match no
+/// rule are left for manual review.
+///
+/// ```toml
+/// [[rule]]
+/// name = "small orders close to the market"
+/// action = "accept"
+/// max_quantity = 1000
+/// max_leverage = 2
+/// max_spread = 0.005
+/// max_offer_age_secs = 60
+///
+/// [[rule]]
+/// name = "known troublemaker"
+/// action = "reject"
+/// takers = ["12D3KooWE1krEN7utXUFhLmLXLWPSuah5WQ7ChzUFetkMS7TRXsm"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+/// A rule that matches an order if all of its conditions hold.
+///
+/// Conditions that are not set always hold.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    pub action: Action,
+    pub min_quantity: Option<u64>,
+    pub max_quantity: Option<u64>,
+    pub max_leverage: Option<u8>,
+    /// The peer IDs of the takers this rule applies to.
+    pub takers: Option<HashSet<PeerId>>,
+    /// The maximum distance of the offer price from the mid price of the price feed, relative to
+    /// the mid price.
+    ///
+    /// Never holds if the price feed has no quote for the contract symbol.
+    pub max_spread: Option<Decimal>,
+    /// The maximum time since the offer the order was placed on was created.
+    pub max_offer_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Accept,
+    Reject,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Accept => "accept".fmt(f),
+            Action::Reject => "reject".fmt(f),
+        }
+    }
+}
+
+/// What we know about an order when deciding about it.
+#[derive(Debug, Clone, Copy)]
+pub struct Facts {
+    pub quantity: Contracts,
+    pub leverage: Leverage,
+    pub peer_id: PeerId,
+    /// The distance of the offer price from the mid price of the price feed, relative to the mid
+    /// price.
+    pub spread: Option<Decimal>,
+    pub offer_age: Duration,
+}
+
+impl Policy {
+    /// The first rule matching an order with the given facts, if any.
+    pub fn decide(&self, facts: &Facts) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.matches(facts))
+    }
+}
+
+impl Rule {
+    fn matches(&self, facts: &Facts) -> bool {
+        let min_quantity = self
+            .min_quantity
+            .map_or(true, |min| facts.quantity >= Contracts::new(min));
+        let max_quantity = self
+            .max_quantity
+            .map_or(true, |max| facts.quantity <= Contracts::new(max));
+        let max_leverage = self
+            .max_leverage
+            .map_or(true, |max| facts.leverage.get() <= max);
+        let takers = self
+            .takers
+            .as_ref()
+            .map_or(true, |takers| takers.contains(&facts.peer_id));
+        let max_spread = self.max_spread.map_or(true, |max| {
+            facts.spread.map_or(false, |spread| spread <= max)
+        });
+        let max_offer_age = self
+            .max_offer_age_secs
+            .map_or(true, |max| facts.offer_age <= Duration::from_secs(max));
+
+        min_quantity && max_quantity && max_leverage && takers && max_spread && max_offer_age
+    }
+}
+
+pub async fn load_order_policy(directory: &Path) -> Result<Policy> {
+    let path = directory.join(FILENAME);
+
+    if !path.try_exists()? {
+        tracing::info!(
+            "No order policy, all orders need a manual decision. Expected config file at: {path:?}"
+        );
+
+        return Ok(Policy::default());
+    }
+
+    let raw = tokio::fs::read_to_string(&path).await?;
+    let policy = toml::from_str::<Policy>(&raw)
+        .with_context(|| format!("Failed to parse order policy at {path:?}"))?;
+
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn facts() -> Facts {
+        Facts {
+            quantity: Contracts::new(100),
+            leverage: Leverage::TWO,
+            peer_id: PeerId::random(),
+            spread: Some(dec!(0.001)),
+            offer_age: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            [[rule]]
+            name = "large"
+            action = "reject"
+            min_quantity = 1000
+
+            [[rule]]
+            name = "small"
+            action = "accept"
+            max_quantity = 1000
+            max_leverage = 2
+
+            [[rule]]
+            name = "everything"
+            action = "reject"
+            "#,
+        )
+        .unwrap();
+
+        let rule = policy.decide(&facts()).unwrap();
+
+        assert_eq!(rule.name, "small");
+        assert_eq!(rule.action, Action::Accept);
+    }
+
+    #[test]
+    fn no_matching_rule_leaves_decision_to_operator() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            [[rule]]
+            name = "close to the market"
+            action = "accept"
+            max_spread = 0.0005
+            "#,
+        )
+        .unwrap();
+
+        assert!(policy.decide(&facts()).is_none());
+        assert!(policy
+            .decide(&Facts {
+                spread: None,
+                ..facts()
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn rule_for_specific_takers() {
+        let taker = PeerId::random();
+        let policy = toml::from_str::<Policy>(&format!(
+            r#"
+            [[rule]]
+            name = "blocked"
+            action = "reject"
+            takers = ["{taker}"]
+            "#
+        ))
+        .unwrap();
+
+        assert!(policy
+            .decide(&Facts {
+                peer_id: taker,
+                ..facts()
+            })
+            .is_some());
+        assert!(policy.decide(&facts()).is_none());
+    }
+
+    #[test]
+    fn stale_offers_do_not_match() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            [[rule]]
+            name = "fresh"
+            action = "accept"
+            max_offer_age_secs = 5
+            "#,
+        )
+        .unwrap();
+
+        assert!(policy.decide(&facts()).is_none());
+    }
+}
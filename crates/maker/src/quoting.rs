@@ -0,0 +1,341 @@
+use crate::cfd;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use daemon::into_price_feed_symbol;
+use daemon::position_metrics;
+use model::ContractSymbol;
+use model::Price;
+use offer::maker::Audience;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::time::Duration;
+use time::ext::NumericalDuration;
+use xtra::prelude::MessageChannel;
+use xtra_bitmex_price_feed::GetLatestQuotes;
+use xtra_bitmex_price_feed::LatestQuotes;
+use xtra_bitmex_price_feed::Quote;
+use xtra_bitmex_price_feed::QUOTE_INTERVAL_MINUTES;
+use xtra_productivity::xtra_productivity;
+use xtras::SendInterval;
+
+/// Parameters of the quoting engine.
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// The difference between our short and long price, relative to the mid price.
+    ///
+    /// E.g. `0.002` quotes 0.1% below and 0.1% above the mid price.
+    pub spread: Decimal,
+    /// How much both prices are shifted per contract of net open exposure, relative to the mid
+    /// price.
+    ///
+    /// If we are net long, both prices are lowered to make going long less and going short more
+    /// attractive for us, and vice versa.
+    pub skew: Decimal,
+    /// How often offers are re-priced.
+    pub interval: Duration,
+}
+
+/// Publishes offers priced from the latest quotes of the price feed.
+///
+/// The offer parameters other than the prices are taken from the last [`UpdateParams`] of each
+/// contract symbol and audience.
+pub struct Actor {
+    config: Config,
+    params: HashMap<(ContractSymbol, Audience), cfd::OfferParams>,
+    price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+    positions: MessageChannel<position_metrics::GetOpenPositions, position_metrics::OpenPositions>,
+    cfd: xtra::Address<cfd::Actor>,
+}
+
+impl Actor {
+    pub fn new(
+        config: Config,
+        price_feed: MessageChannel<GetLatestQuotes, LatestQuotes>,
+        positions: MessageChannel<
+            position_metrics::GetOpenPositions,
+            position_metrics::OpenPositions,
+        >,
+        cfd: xtra::Address<cfd::Actor>,
+    ) -> Self {
+        Self {
+            config,
+            params: HashMap::new(),
+            price_feed,
+            positions,
+            cfd,
+        }
+    }
+
+    async fn requote(&mut self, key: (ContractSymbol, Audience)) -> Result<()> {
+        let params = match self.params.get(&key) {
+            Some(params) => params.clone(),
+            None => return Ok(()),
+        };
+        let (contract_symbol, audience) = key;
+
+        let quotes = self
+            .price_feed
+            .send(GetLatestQuotes)
+            .await
+            .context("Price feed not available")?;
+        let quote = quotes
+            .get(&into_price_feed_symbol(contract_symbol))
+            .with_context(|| format!("No quote available for {contract_symbol}"))?;
+
+        let threshold = QUOTE_INTERVAL_MINUTES.minutes() * 2;
+        if quote.is_older_than(threshold) {
+            tracing::warn!(
+                %contract_symbol,
+                %audience,
+                "Latest quote is older than {} minutes, not re-pricing offers with old price",
+                threshold.whole_minutes()
+            );
+            return Ok(());
+        }
+
+        let positions = self
+            .positions
+            .send(position_metrics::GetOpenPositions(contract_symbol))
+            .await
+            .context("Position metrics not available")?;
+        let net_exposure = positions.long.into_decimal() - positions.short.into_decimal();
+
+        let (price_long, price_short) = prices(quote, net_exposure, &self.config)?;
+
+        tracing::debug!(%contract_symbol, %audience, %price_long, %price_short, %net_exposure, "Re-pricing offers");
+
+        self.cfd
+            .send(requoted(params, price_long, price_short))
+            .await??;
+
+        Ok(())
+    }
+}
+
+/// Update the parameters the offers for a contract symbol and audience are created with.
+///
+/// The prices are derived from the price feed, a side without a price stays disabled. Further price
+/// levels keep their distance to the given `price_long` and `price_short`, if any.
+pub struct UpdateParams(pub cfd::OfferParams);
+
+/// Stop publishing offers for a contract symbol to an audience.
+pub struct RemoveParams {
+    pub contract_symbol: ContractSymbol,
+    pub audience: Audience,
+}
+
+#[derive(Clone, Copy)]
+struct Requote;
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: UpdateParams) -> Result<()> {
+        let key = (msg.0.contract_symbol, msg.0.audience.clone());
+        self.params.insert(key.clone(), msg.0);
+
+        self.requote(key).await
+    }
+
+    async fn handle(&mut self, msg: RemoveParams) {
+        self.params.remove(&(msg.contract_symbol, msg.audience));
+    }
+
+    async fn handle(&mut self, _: Requote) {
+        let keys = self.params.keys().cloned().collect::<Vec<_>>();
+
+        for (contract_symbol, audience) in keys {
+            if let Err(e) = self.requote((contract_symbol, audience.clone())).await {
+                tracing::warn!(%contract_symbol, %audience, "Failed to re-price offers: {e:#}");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = ();
+
+    async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
+        let this = ctx.address().expect("we just started");
+
+        tokio_extras::spawn(
+            &this.clone(),
+            this.send_interval(self.config.interval, || Requote, xtras::IncludeSpan::Always),
+        );
+    }
+
+    async fn stopped(self) -> Self::Stop {}
+}
+
+/// Derive the price at which we go long and the price at which we go short from `quote`.
+fn prices(quote: &Quote, net_exposure: Decimal, config: &Config) -> Result<(Price, Price)> {
+    let mid = (quote.bid + quote.ask) / dec!(2);
+    let half_spread = config.spread / dec!(2);
+    let skew = config.skew * net_exposure;
+
+    let price_long = Price::new(mid * (Decimal::ONE - half_spread - skew))
+        .context("Skew too large for long price")?;
+    let price_short = Price::new(mid * (Decimal::ONE + half_spread - skew))
+        .context("Skew too large for short price")?;
+
+    Ok((price_long, price_short))
+}
+
+/// Re-price `params` at `price_long` and `price_short`.
+///
+/// A side without a price stays disabled.
+fn requoted(params: cfd::OfferParams, price_long: Price, price_short: Price) -> cfd::OfferParams {
+    let levels_long = shift_levels(params.levels_long.clone(), params.price_long, price_long);
+    let levels_short = shift_levels(params.levels_short.clone(), params.price_short, price_short);
+
+    cfd::OfferParams {
+        price_long: params.price_long.map(|_| price_long),
+        price_short: params.price_short.map(|_| price_short),
+        levels_long,
+        levels_short,
+        ..params
+    }
+}
+
+/// Move the price `levels` along with the top of the book, from `from` to `to`.
+///
+/// Levels which would end up with a non-positive price are dropped.
+fn shift_levels(
+    levels: Vec<cfd::OfferLevel>,
+    from: Option<Price>,
+    to: Price,
+) -> Vec<cfd::OfferLevel> {
+    let from = match from {
+        Some(from) => from,
+        None => return levels,
+    };
+    let shift = to.into_decimal() - from.into_decimal();
+
+    levels
+        .into_iter()
+        .filter_map(|level| {
+            let price = Price::new(level.price.into_decimal() + shift).ok()?;
+
+            Some(cfd::OfferLevel { price, ..level })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bdk::bitcoin::Amount;
+    use model::Contracts;
+    use model::FundingRate;
+    use model::Leverage;
+    use model::LotSize;
+    use model::OpeningFee;
+    use model::TxFeeRate;
+    use time::OffsetDateTime;
+
+    fn quote(bid: Decimal, ask: Decimal) -> Quote {
+        Quote {
+            timestamp: OffsetDateTime::now_utc(),
+            bid,
+            ask,
+            symbol: xtra_bitmex_price_feed::ContractSymbol::BTC_USD,
+        }
+    }
+
+    fn config(spread: Decimal, skew: Decimal) -> Config {
+        Config {
+            spread,
+            skew,
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn quotes_around_mid_price_without_exposure() {
+        let (long, short) = prices(
+            &quote(dec!(19_990), dec!(20_010)),
+            Decimal::ZERO,
+            &config(dec!(0.002), dec!(0.0001)),
+        )
+        .unwrap();
+
+        assert_eq!(long, Price::new(dec!(19_980)).unwrap());
+        assert_eq!(short, Price::new(dec!(20_020)).unwrap());
+    }
+
+    #[test]
+    fn net_long_exposure_lowers_prices() {
+        let (long, short) = prices(
+            &quote(dec!(20_000), dec!(20_000)),
+            dec!(10),
+            &config(dec!(0.002), dec!(0.0001)),
+        )
+        .unwrap();
+
+        assert_eq!(long, Price::new(dec!(19_960)).unwrap());
+        assert_eq!(short, Price::new(dec!(20_000)).unwrap());
+    }
+
+    #[test]
+    fn net_short_exposure_raises_prices() {
+        let (long, short) = prices(
+            &quote(dec!(20_000), dec!(20_000)),
+            dec!(-10),
+            &config(dec!(0.002), dec!(0.0001)),
+        )
+        .unwrap();
+
+        assert_eq!(long, Price::new(dec!(20_000)).unwrap());
+        assert_eq!(short, Price::new(dec!(20_040)).unwrap());
+    }
+
+    #[test]
+    fn levels_move_along_with_top_of_book() {
+        let level = |price: Decimal| cfd::OfferLevel {
+            price: Price::new(price).unwrap(),
+            min_quantity: Contracts::new(100),
+            max_quantity: Contracts::new(1000),
+            leverage_choices: vec![Leverage::TWO],
+        };
+
+        let levels = shift_levels(
+            vec![level(dec!(19_800)), level(dec!(80))],
+            Some(Price::new(dec!(20_000)).unwrap()),
+            Price::new(dec!(19_900)).unwrap(),
+        );
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].price, Price::new(dec!(19_700)).unwrap());
+    }
+
+    #[test]
+    fn requoting_keeps_disabled_side_disabled() {
+        let params = cfd::OfferParams {
+            price_long: Some(Price::new(dec!(19_900)).unwrap()),
+            price_short: None,
+            min_quantity: Contracts::new(100),
+            max_quantity: Contracts::new(1000),
+            tx_fee_rate: TxFeeRate::default(),
+            funding_rate_long: FundingRate::default(),
+            funding_rate_short: FundingRate::default(),
+            opening_fee: OpeningFee::new(Amount::from_sat(2)),
+            leverage_choices: vec![Leverage::TWO],
+            contract_symbol: ContractSymbol::BTC_USD,
+            lot_size: LotSize::new(100),
+            levels_long: vec![],
+            levels_short: vec![],
+            audience: Audience::Everyone,
+        };
+
+        let params = requoted(
+            params,
+            Price::new(dec!(19_980)).unwrap(),
+            Price::new(dec!(20_020)).unwrap(),
+        );
+
+        assert_eq!(params.price_long, Some(Price::new(dec!(19_980)).unwrap()));
+        assert_eq!(params.price_short, None);
+    }
+}
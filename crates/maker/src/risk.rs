@@ -0,0 +1,306 @@
+use bdk::bitcoin::Amount;
+use daemon::order::NewOrder;
+use daemon::position_metrics::Exposure;
+use model::calculate_margin;
+use model::libp2p::PeerId;
+use model::ContractSymbol;
+use model::Contracts;
+use model::Leverage;
+use model::OrderId;
+use model::Position;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+/// Limits on the risk we are willing to take on.
+///
+/// Orders that would breach a limit are rejected. Rollovers are rejected while a limit is
+/// breached, e.g. because it was lowered after the positions were opened, so that the exposure
+/// decreases as positions expire.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// The maximum number of contracts we hold with a single taker, across all contract symbols.
+    pub max_contracts_per_taker: Option<Contracts>,
+    /// The maximum net long or short exposure per contract symbol.
+    pub max_net_exposure: HashMap<ContractSymbol, Contracts>,
+    /// The maximum margin we lock up across all positions.
+    pub max_total_margin: Option<Amount>,
+}
+
+/// An order a taker placed, as far as our limits are concerned.
+#[derive(Debug, Clone, Copy)]
+pub struct Order {
+    pub order_id: OrderId,
+    pub peer_id: PeerId,
+    pub contract_symbol: ContractSymbol,
+    /// Our position, i.e. the opposite of the taker's.
+    pub position: Position,
+    pub quantity: Contracts,
+    /// The margin we have to lock up for this order.
+    pub margin: Amount,
+}
+
+impl From<&NewOrder> for Order {
+    fn from(order: &NewOrder) -> Self {
+        let contract_symbol = order.offer.contract_symbol;
+
+        Self {
+            order_id: order.order_id,
+            peer_id: order.peer_id,
+            contract_symbol,
+            position: order.offer.position_maker,
+            quantity: order.quantity,
+            margin: calculate_margin(
+                contract_symbol,
+                order.offer.price,
+                order.quantity,
+                Leverage::ONE,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum Breach {
+    #[error("Taker would hold {total} contracts with us, exceeding the limit of {limit}")]
+    ContractsPerTaker { total: Contracts, limit: Contracts },
+    #[error("Net {contract_symbol} exposure of {net} contracts would exceed the limit of {limit}")]
+    NetExposure {
+        contract_symbol: ContractSymbol,
+        net: Decimal,
+        limit: Contracts,
+    },
+    #[error("Total margin of {total} would exceed the limit of {limit}")]
+    TotalMargin { total: Amount, limit: Amount },
+}
+
+impl Limits {
+    /// Check whether taking `order` would breach a limit.
+    ///
+    /// `exposure` must not include the order itself.
+    pub fn check_order(&self, exposure: &Exposure, order: &Order) -> Result<(), Breach> {
+        if let Some(limit) = self.max_contracts_per_taker {
+            let total = exposure
+                .per_taker
+                .get(&order.peer_id)
+                .copied()
+                .unwrap_or(Contracts::ZERO)
+                + order.quantity;
+
+            if total > limit {
+                return Err(Breach::ContractsPerTaker { total, limit });
+            }
+        }
+
+        if let Some(limit) = self.max_net_exposure.get(&order.contract_symbol).copied() {
+            let before = net_exposure(exposure, order.contract_symbol);
+            let net = match order.position {
+                Position::Long => before + order.quantity.into_decimal(),
+                Position::Short => before - order.quantity.into_decimal(),
+            };
+
+            // Orders that reduce our exposure are always welcome
+            if net.abs() > limit.into_decimal() && net.abs() > before.abs() {
+                return Err(Breach::NetExposure {
+                    contract_symbol: order.contract_symbol,
+                    net,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_total_margin {
+            let total = exposure.margin + order.margin;
+
+            if total > limit {
+                return Err(Breach::TotalMargin { total, limit });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check whether our current exposure breaches a limit relevant to rolling over a CFD on
+    /// `contract_symbol` with `peer_id`.
+    pub fn check_rollover(
+        &self,
+        exposure: &Exposure,
+        peer_id: PeerId,
+        contract_symbol: ContractSymbol,
+    ) -> Result<(), Breach> {
+        if let Some(limit) = self.max_contracts_per_taker {
+            let total = exposure
+                .per_taker
+                .get(&peer_id)
+                .copied()
+                .unwrap_or(Contracts::ZERO);
+
+            if total > limit {
+                return Err(Breach::ContractsPerTaker { total, limit });
+            }
+        }
+
+        if let Some(limit) = self.max_net_exposure.get(&contract_symbol).copied() {
+            let net = net_exposure(exposure, contract_symbol);
+
+            if net.abs() > limit.into_decimal() {
+                return Err(Breach::NetExposure {
+                    contract_symbol,
+                    net,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_total_margin {
+            if exposure.margin > limit {
+                return Err(Breach::TotalMargin {
+                    total: exposure.margin,
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Our net exposure in `contract_symbol`, positive if we are net long.
+fn net_exposure(exposure: &Exposure, contract_symbol: ContractSymbol) -> Decimal {
+    exposure
+        .positions
+        .get(&contract_symbol)
+        .map(|positions| positions.long.into_decimal() - positions.short.into_decimal())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use daemon::position_metrics::OpenPositions;
+    use rust_decimal_macros::dec;
+
+    fn order(peer_id: PeerId, position: Position, quantity: u64) -> Order {
+        Order {
+            order_id: OrderId::default(),
+            peer_id,
+            contract_symbol: ContractSymbol::BTC_USD,
+            position,
+            quantity: Contracts::new(quantity),
+            margin: Amount::from_sat(quantity * 1_000),
+        }
+    }
+
+    fn exposure(long: u64, short: u64) -> Exposure {
+        Exposure {
+            positions: HashMap::from([(
+                ContractSymbol::BTC_USD,
+                OpenPositions {
+                    long: Contracts::new(long),
+                    short: Contracts::new(short),
+                },
+            )]),
+            ..Exposure::default()
+        }
+    }
+
+    fn peer_id() -> PeerId {
+        xtra_libp2p::libp2p::PeerId::random().into()
+    }
+
+    #[test]
+    fn rejects_order_exceeding_contracts_per_taker() {
+        let taker = peer_id();
+        let limits = Limits {
+            max_contracts_per_taker: Some(Contracts::new(100)),
+            ..Limits::default()
+        };
+        let exposure = Exposure {
+            per_taker: HashMap::from([(taker, Contracts::new(80))]),
+            ..Exposure::default()
+        };
+
+        assert!(limits
+            .check_order(&exposure, &order(taker, Position::Long, 20))
+            .is_ok());
+        assert_eq!(
+            limits.check_order(&exposure, &order(taker, Position::Long, 30)),
+            Err(Breach::ContractsPerTaker {
+                total: Contracts::new(110),
+                limit: Contracts::new(100)
+            })
+        );
+        assert!(limits
+            .check_order(&exposure, &order(peer_id(), Position::Long, 30))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_order_exceeding_net_exposure() {
+        let limits = Limits {
+            max_net_exposure: HashMap::from([(ContractSymbol::BTC_USD, Contracts::new(100))]),
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            limits.check_order(&exposure(90, 0), &order(peer_id(), Position::Long, 20)),
+            Err(Breach::NetExposure {
+                contract_symbol: ContractSymbol::BTC_USD,
+                net: dec!(110),
+                limit: Contracts::new(100)
+            })
+        );
+        assert!(limits
+            .check_order(&exposure(90, 0), &order(peer_id(), Position::Short, 150))
+            .is_ok());
+    }
+
+    #[test]
+    fn accepts_order_reducing_breached_net_exposure() {
+        let limits = Limits {
+            max_net_exposure: HashMap::from([(ContractSymbol::BTC_USD, Contracts::new(100))]),
+            ..Limits::default()
+        };
+
+        assert!(limits
+            .check_order(&exposure(0, 200), &order(peer_id(), Position::Long, 50))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_order_exceeding_total_margin() {
+        let limits = Limits {
+            max_total_margin: Some(Amount::from_sat(100_000)),
+            ..Limits::default()
+        };
+        let exposure = Exposure {
+            margin: Amount::from_sat(90_000),
+            ..Exposure::default()
+        };
+
+        assert_eq!(
+            limits.check_order(&exposure, &order(peer_id(), Position::Long, 20)),
+            Err(Breach::TotalMargin {
+                total: Amount::from_sat(110_000),
+                limit: Amount::from_sat(100_000)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_rollover_while_limit_is_breached() {
+        let limits = Limits {
+            max_net_exposure: HashMap::from([(ContractSymbol::BTC_USD, Contracts::new(100))]),
+            ..Limits::default()
+        };
+
+        assert!(limits
+            .check_rollover(&exposure(100, 0), peer_id(), ContractSymbol::BTC_USD)
+            .is_ok());
+        assert!(limits
+            .check_rollover(&exposure(0, 101), peer_id(), ContractSymbol::BTC_USD)
+            .is_err());
+        assert!(limits
+            .check_rollover(&exposure(0, 101), peer_id(), ContractSymbol::ETH_USD)
+            .is_ok());
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::Context;
+use anyhow::Result;
+use model::ContractSymbol;
+use model::Contracts;
+use model::FundingRate;
+use rollover::policy::Policy;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use xtra_libp2p::libp2p::PeerId;
+
+const FILENAME: &str = "rollover_policy.toml";
+
+/// Convenience type to store the rollover policy as toml.
+///
+/// The file is written by the maker whenever the rollover configuration is updated through the
+/// API, so that it survives a restart.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RolloverPolicy {
+    #[serde(default)]
+    paused: bool,
+    #[serde(default)]
+    paused_contract_symbols: HashSet<ContractSymbol>,
+    #[serde(default)]
+    blocked_takers: HashSet<PeerId>,
+    max_quantity: Option<Contracts>,
+    min_funding_rate: Option<FundingRate>,
+}
+
+impl From<RolloverPolicy> for Policy {
+    fn from(policy: RolloverPolicy) -> Self {
+        Self {
+            paused: policy.paused,
+            paused_contract_symbols: policy.paused_contract_symbols,
+            blocked_takers: policy.blocked_takers,
+            max_quantity: policy.max_quantity,
+            min_funding_rate: policy.min_funding_rate,
+        }
+    }
+}
+
+impl From<Policy> for RolloverPolicy {
+    fn from(policy: Policy) -> Self {
+        Self {
+            paused: policy.paused,
+            paused_contract_symbols: policy.paused_contract_symbols,
+            blocked_takers: policy.blocked_takers,
+            max_quantity: policy.max_quantity,
+            min_funding_rate: policy.min_funding_rate,
+        }
+    }
+}
+
+pub async fn load_rollover_policy(directory: &Path) -> Result<Policy> {
+    let path = directory.join(FILENAME);
+
+    if !path.try_exists()? {
+        tracing::info!("Accepting all rollovers. Expected config file at: {path:?}");
+
+        return Ok(Policy::default());
+    }
+
+    let raw = tokio::fs::read_to_string(path).await?;
+    Ok(toml::from_str::<RolloverPolicy>(&raw)?.into())
+}
+
+pub async fn save_rollover_policy(directory: &Path, policy: Policy) -> Result<()> {
+    let path = directory.join(FILENAME);
+    let raw = toml::to_string(&RolloverPolicy::from(policy))?;
+
+    // Write to a temporary file first so we never end up with a partially written policy
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, raw).await?;
+    tokio::fs::rename(&tmp_path, &path)
+        .await
+        .with_context(|| format!("Failed to write rollover policy to {}", path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::OrderId;
+    use rust_decimal_macros::dec;
+    use std::env;
+    use std::path::PathBuf;
+
+    async fn data_dir() -> PathBuf {
+        let data_dir = env::temp_dir().join(format!("rollover-policy-{}", OrderId::default()));
+        tokio::fs::create_dir_all(&data_dir).await.unwrap();
+
+        data_dir
+    }
+
+    #[tokio::test]
+    async fn saved_policy_is_loaded_again() {
+        let data_dir = data_dir().await;
+        let policy = Policy {
+            paused: true,
+            paused_contract_symbols: HashSet::from([ContractSymbol::ETH_USD]),
+            blocked_takers: HashSet::from([PeerId::random()]),
+            max_quantity: Some(Contracts::new(1000)),
+            min_funding_rate: Some(FundingRate::new(dec!(0.0001)).unwrap()),
+        };
+
+        save_rollover_policy(&data_dir, policy.clone())
+            .await
+            .unwrap();
+        let loaded = load_rollover_policy(&data_dir).await.unwrap();
+
+        assert_eq!(loaded, policy);
+    }
+
+    #[tokio::test]
+    async fn missing_file_accepts_all_rollovers() {
+        let data_dir = data_dir().await;
+
+        let loaded = load_rollover_policy(&data_dir).await.unwrap();
+
+        assert_eq!(loaded, Policy::default());
+    }
+}
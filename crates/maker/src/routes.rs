@@ -1,16 +1,24 @@
 #![allow(clippy::let_unit_value)] // see: https://github.com/SergioBenitez/Rocket/issues/2211
 use crate::actor_system::ActorSystem;
+use crate::cfd;
 use anyhow::Result;
 use bdk::sled;
-use daemon::bdk::blockchain::ElectrumBlockchain;
+use daemon::bdk::bitcoin::Amount;
+use daemon::bdk::bitcoin::Network;
+use daemon::bdk::blockchain::AnyBlockchain;
+use daemon::export;
 use daemon::oracle;
+use daemon::projection;
 use daemon::projection::Cfd;
 use daemon::projection::CfdAction;
 use daemon::projection::FeedReceivers;
 use daemon::wallet;
 use http_api_problem::HttpApiProblem;
 use http_api_problem::StatusCode;
+use model::libp2p::PeerId;
+use model::Catalogue;
 use model::Contracts;
+use model::FeeLedgerEntry;
 use model::FundingRate;
 use model::Leverage;
 use model::LotSize;
@@ -19,36 +27,43 @@ use model::OrderId;
 use model::Price;
 use model::TxFeeRate;
 use model::WalletInfo;
+use offer::maker::Audience;
 use rocket::http::ContentType;
 use rocket::http::Status;
-use rocket::request::FromParam;
 use rocket::response::stream::Event;
 use rocket::response::stream::EventStream;
 use rocket::response::Responder;
 use rocket::serde::json::Json;
+use rocket::Either;
 use rocket::State;
 use rocket_cookie_auth::user::User;
 use rust_embed::RustEmbed;
 use rust_embed_rocket::EmbeddedFileExt;
 use serde::Deserialize;
+use serde::Serialize;
+use shared_bin::best_offer_sse_events;
+use shared_bin::quote_sse_events;
 use shared_bin::ToSseEvent;
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::PathBuf;
 use tokio::select;
 use tokio::sync::watch;
 use tracing::instrument;
 use uuid::Uuid;
 
-pub type Maker = ActorSystem<oracle::Actor, wallet::Actor<ElectrumBlockchain, sled::Tree>>;
+pub type Maker = ActorSystem<oracle::Actor, wallet::Actor<AnyBlockchain, sled::Tree>>;
 
 #[allow(clippy::too_many_arguments)]
 #[rocket::get("/feed")]
 pub async fn maker_feed(
     rx: &State<FeedReceivers>,
     rx_wallet: &State<watch::Receiver<Option<WalletInfo>>>,
+    contract_symbols: &State<Vec<model::ContractSymbol>>,
     _user: User,
 ) -> EventStream![] {
     let rx = rx.inner();
+    let contract_symbols = contract_symbols.inner().clone();
     let mut rx_cfds = rx.cfds.clone();
     let mut rx_wallet = rx_wallet.inner().clone();
     let mut rx_offers = rx.offers.clone();
@@ -59,14 +74,15 @@ pub async fn maker_feed(
         yield wallet_info.to_sse_event();
 
         let offers = rx_offers.borrow().clone();
-        yield Event::json(&offers.btcusd_long).event("btcusd_long_offer");
-        yield Event::json(&offers.btcusd_short).event("btcusd_short_offer");
-        yield Event::json(&offers.ethusd_long).event("ethusd_long_offer");
-        yield Event::json(&offers.ethusd_short).event("ethusd_short_offer");
+        for event in best_offer_sse_events(&offers, &contract_symbols) {
+            yield event;
+        }
+        yield offers.to_sse_event();
 
         let quote = rx_quote.borrow().clone();
-        yield Event::json(&quote.get(&model::ContractSymbol::BtcUsd)).event("btcusd_quote");
-        yield Event::json(&quote.get(&model::ContractSymbol::EthUsd)).event("ethusd_quote");
+        for event in quote_sse_events(&quote, &contract_symbols) {
+            yield event;
+        }
 
         let cfds = rx_cfds.borrow().clone();
         if let Some(cfds) = cfds {
@@ -81,10 +97,10 @@ pub async fn maker_feed(
                 },
                 Ok(()) = rx_offers.changed() => {
                     let offers = rx_offers.borrow().clone();
-                    yield Event::json(&offers.btcusd_long).event("btcusd_long_offer");
-                    yield Event::json(&offers.btcusd_short).event("btcusd_short_offer");
-                    yield Event::json(&offers.ethusd_long).event("ethusd_long_offer");
-                    yield Event::json(&offers.ethusd_short).event("ethusd_short_offer");
+                    for event in best_offer_sse_events(&offers, &contract_symbols) {
+                        yield event;
+                    }
+                    yield offers.to_sse_event();
                 }
                 Ok(()) = rx_cfds.changed() => {
                     let cfds = rx_cfds.borrow().clone();
@@ -94,8 +110,9 @@ pub async fn maker_feed(
                 }
                 Ok(()) = rx_quote.changed() => {
                     let quote = rx_quote.borrow().clone();
-                    yield Event::json(&quote.get(&model::ContractSymbol::BtcUsd)).event("btcusd_quote");
-                    yield Event::json(&quote.get(&model::ContractSymbol::EthUsd)).event("ethusd_quote");
+                    for event in quote_sse_events(&quote, &contract_symbols) {
+                        yield event;
+                    }
                 }
             }
         }
@@ -121,6 +138,67 @@ pub struct CfdNewOfferParamsRequest {
     pub leverage_choices: Vec<Leverage>,
     #[serde(default = "default_lot_size")]
     pub lot_size: LotSize,
+    /// Further price levels for the maker's long position
+    #[serde(default)]
+    pub levels_long: Vec<CfdOfferLevelRequest>,
+    /// Further price levels for the maker's short position
+    #[serde(default)]
+    pub levels_short: Vec<CfdOfferLevelRequest>,
+    /// Only offer to this taker, instead of to everyone
+    #[serde(default)]
+    pub peer_id: Option<PeerId>,
+    /// Only offer to the takers in this group from `taker_groups.toml`, instead of to everyone
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// A price level of the order book in addition to the one at `price_long` or `price_short`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CfdOfferLevelRequest {
+    pub price: Price,
+    pub min_quantity: Contracts,
+    pub max_quantity: Contracts,
+    #[serde(default = "empty_leverage")]
+    pub leverage_choices: Vec<Leverage>,
+}
+
+impl From<CfdOfferLevelRequest> for cfd::OfferLevel {
+    fn from(level: CfdOfferLevelRequest) -> Self {
+        Self {
+            price: level.price,
+            min_quantity: level.min_quantity,
+            max_quantity: level.max_quantity,
+            leverage_choices: level.leverage_choices,
+        }
+    }
+}
+
+impl CfdNewOfferParamsRequest {
+    fn levels(&self) -> (Vec<cfd::OfferLevel>, Vec<cfd::OfferLevel>) {
+        let into_levels =
+            |levels: &[CfdOfferLevelRequest]| levels.iter().cloned().map(Into::into).collect();
+
+        (
+            into_levels(&self.levels_long),
+            into_levels(&self.levels_short),
+        )
+    }
+
+    fn audience(&self) -> Result<Audience, HttpApiProblem> {
+        audience(self.peer_id, self.group.clone())
+    }
+}
+
+/// The takers to publish offers to, everyone unless a single taker or group is given.
+fn audience(peer_id: Option<PeerId>, group: Option<String>) -> Result<Audience, HttpApiProblem> {
+    match (peer_id, group) {
+        (None, None) => Ok(Audience::Everyone),
+        (Some(peer_id), None) => Ok(Audience::Peer(peer_id.inner())),
+        (None, Some(group)) => Ok(Audience::Group(group)),
+        (Some(_), Some(_)) => Err(HttpApiProblem::new(StatusCode::BAD_REQUEST)
+            .title("Invalid audience")
+            .detail("Offers can either be for a taker or for a group, not both")),
+    }
 }
 
 fn empty_leverage() -> Vec<Leverage> {
@@ -140,6 +218,8 @@ pub async fn put_offer_params(
     _user: User,
 ) -> Result<(), HttpApiProblem> {
     tracing::warn!("Deprecated /offer was called. Please use /<contract_symbol>/offer from now.");
+    let (levels_long, levels_short) = offer_params.levels();
+    let audience = offer_params.audience()?;
     maker
         .set_offer_params(
             offer_params.price_long,
@@ -151,8 +231,11 @@ pub async fn put_offer_params(
             offer_params.daily_funding_rate_short,
             offer_params.opening_fee,
             offer_params.leverage_choices.clone(),
-            ContractSymbol::BtcUsd.into(),
+            model::ContractSymbol::BTC_USD,
             offer_params.lot_size,
+            levels_long,
+            levels_short,
+            audience,
         )
         .await
         .map_err(|e| {
@@ -164,47 +247,30 @@ pub async fn put_offer_params(
     Ok(())
 }
 
-#[derive(Debug, Copy, Clone, strum_macros::Display)]
-pub enum ContractSymbol {
-    BtcUsd,
-    EthUsd,
-}
-
-impl From<ContractSymbol> for model::ContractSymbol {
-    fn from(symbol: ContractSymbol) -> Self {
-        match symbol {
-            ContractSymbol::BtcUsd => model::ContractSymbol::BtcUsd,
-            ContractSymbol::EthUsd => model::ContractSymbol::EthUsd,
-        }
-    }
-}
-
-impl<'r> FromParam<'r> for ContractSymbol {
-    type Error = anyhow::Error;
-
-    fn from_param(param: &'r str) -> Result<Self, Self::Error> {
-        match param.to_lowercase().as_str() {
-            "btcusd" => Ok(ContractSymbol::BtcUsd),
-            "ethusd" => Ok(ContractSymbol::EthUsd),
-            _ => anyhow::bail!("Unknown contract symbol provided: {param}"),
-        }
-    }
+/// Look up a contract symbol given in a path segment, e.g. `btcusd`, in the catalogue.
+fn contract_symbol(
+    catalogue: &Catalogue,
+    symbol: &str,
+) -> Result<model::ContractSymbol, HttpApiProblem> {
+    catalogue.parse(symbol).map_err(|e| {
+        HttpApiProblem::new(StatusCode::BAD_REQUEST)
+            .title("Unknown ContractSymbol provided")
+            .detail(format!("{e:#}"))
+    })
 }
 
 #[rocket::put("/<symbol>/offer", data = "<offer_params>")]
-#[instrument(name = "PUT /offer", skip(maker, _user), err)]
+#[instrument(name = "PUT /offer", skip(maker, catalogue, _user), err)]
 pub async fn put_offer_params_for_symbol(
-    symbol: Result<ContractSymbol>,
+    symbol: &str,
     offer_params: Json<CfdNewOfferParamsRequest>,
     maker: &State<Maker>,
+    catalogue: &State<Catalogue>,
     _user: User,
 ) -> Result<(), HttpApiProblem> {
-    // if we use `ContractSymbol` as arg directly the error gets lost. So we need to do this:
-    let symbol = symbol.map_err(|e| {
-        HttpApiProblem::new(StatusCode::BAD_REQUEST)
-            .title("Unknown ContractSymbol provided")
-            .detail(format!("{e:#}"))
-    })?;
+    let symbol = contract_symbol(catalogue, symbol)?;
+    let (levels_long, levels_short) = offer_params.levels();
+    let audience = offer_params.audience()?;
     maker
         .set_offer_params(
             offer_params.price_long,
@@ -216,8 +282,11 @@ pub async fn put_offer_params_for_symbol(
             offer_params.daily_funding_rate_short,
             offer_params.opening_fee,
             offer_params.leverage_choices.clone(),
-            symbol.into(),
+            symbol,
             offer_params.lot_size,
+            levels_long,
+            levels_short,
+            audience,
         )
         .await
         .map_err(|e| {
@@ -229,6 +298,101 @@ pub async fn put_offer_params_for_symbol(
     Ok(())
 }
 
+#[rocket::delete("/<symbol>/offer?<peer_id>&<group>")]
+#[instrument(name = "DELETE /offer", skip(maker, catalogue, _user), err)]
+pub async fn delete_offer_for_symbol(
+    symbol: &str,
+    peer_id: Option<String>,
+    group: Option<String>,
+    maker: &State<Maker>,
+    catalogue: &State<Catalogue>,
+    _user: User,
+) -> Result<(), HttpApiProblem> {
+    let symbol = contract_symbol(catalogue, symbol)?;
+    let peer_id = peer_id.as_deref().map(parse_peer_id).transpose()?;
+    let audience = match audience(peer_id, group)? {
+        Audience::Everyone => {
+            return Err(HttpApiProblem::new(StatusCode::BAD_REQUEST)
+                .title("Invalid audience")
+                .detail("Only offers for a taker or a group can be removed"))
+        }
+        audience => audience,
+    };
+
+    maker.remove_offers(symbol, audience).await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Removing offer failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    Ok(())
+}
+
+#[rocket::get("/blocked-peers")]
+#[instrument(name = "GET /blocked-peers", skip(maker, _user), err)]
+pub async fn get_blocked_peers(
+    maker: &State<Maker>,
+    _user: User,
+) -> Result<Json<Vec<PeerId>>, HttpApiProblem> {
+    let blocked_peers = maker.blocked_peers().await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Loading blocked peers failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    let mut blocked_peers = blocked_peers
+        .into_iter()
+        .map(PeerId::from)
+        .collect::<Vec<_>>();
+    blocked_peers.sort_by_key(|peer_id| peer_id.to_string());
+
+    Ok(Json(blocked_peers))
+}
+
+#[rocket::put("/blocked-peers/<peer_id>")]
+#[instrument(name = "PUT /blocked-peers/<peer_id>", skip(maker, _user), err)]
+pub async fn put_blocked_peer(
+    peer_id: &str,
+    maker: &State<Maker>,
+    _user: User,
+) -> Result<(), HttpApiProblem> {
+    let peer_id = parse_peer_id(peer_id)?;
+
+    maker.block_peer(peer_id.inner()).await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Blocking peer failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    Ok(())
+}
+
+#[rocket::delete("/blocked-peers/<peer_id>")]
+#[instrument(name = "DELETE /blocked-peers/<peer_id>", skip(maker, _user), err)]
+pub async fn delete_blocked_peer(
+    peer_id: &str,
+    maker: &State<Maker>,
+    _user: User,
+) -> Result<(), HttpApiProblem> {
+    let peer_id = parse_peer_id(peer_id)?;
+
+    maker.unblock_peer(peer_id.inner()).await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Unblocking peer failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    Ok(())
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId, HttpApiProblem> {
+    peer_id.parse::<PeerId>().map_err(|e| {
+        HttpApiProblem::new(StatusCode::BAD_REQUEST)
+            .title("Invalid peer id")
+            .detail(format!("{e:#}"))
+    })
+}
+
 #[rocket::post("/cfd/<order_id>/<action>")]
 #[instrument(name = "POST /cfd/<order_id>/<action>", skip(maker, _user), err)]
 pub async fn post_cfd_action(
@@ -248,9 +412,11 @@ pub async fn post_cfd_action(
         CfdAction::AcceptSettlement => maker.accept_settlement(order_id).await,
         CfdAction::RejectSettlement => maker.reject_settlement(order_id).await,
         CfdAction::Commit => maker.commit(order_id).await,
-        CfdAction::Settle => {
+        CfdAction::Settle => maker.request_settlement(order_id).await,
+        CfdAction::RollOver => maker.request_rollover(order_id).await,
+        CfdAction::AcceptMakerRequest | CfdAction::RejectMakerRequest => {
             return Err(HttpApiProblem::new(StatusCode::BAD_REQUEST)
-                .detail("Collaborative settlement can only be triggered by taker"));
+                .detail(format!("maker cannot invoke action {action}")));
         }
     };
 
@@ -263,6 +429,91 @@ pub async fn post_cfd_action(
     Ok(())
 }
 
+#[rocket::get("/cfd/<order_id>/fees")]
+#[instrument(name = "GET /cfd/<order_id>/fees", skip(maker, _user), err)]
+pub async fn get_cfd_fees(
+    order_id: Uuid,
+    maker: &State<Maker>,
+    _user: User,
+) -> Result<Json<Vec<FeeLedgerEntry>>, HttpApiProblem> {
+    let order_id = OrderId::from(order_id);
+
+    let ledger = maker.fee_ledger(order_id).await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Loading fees failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    match ledger {
+        Some(ledger) => Ok(Json(ledger)),
+        None => Err(HttpApiProblem::new(StatusCode::NOT_FOUND)
+            .title("CFD not found")
+            .detail(format!("No open or closed CFD with order id {order_id}"))),
+    }
+}
+
+/// Export all closed CFDs as CSV (default) or JSON.
+#[rocket::get("/cfds/closed/export?<format>")]
+#[instrument(name = "GET /cfds/closed/export", skip(maker, _user), err)]
+pub async fn get_closed_cfds_export(
+    format: Option<&str>,
+    maker: &State<Maker>,
+    _user: User,
+) -> Result<(ContentType, String), HttpApiProblem> {
+    let format = format
+        .map(str::parse::<export::Format>)
+        .transpose()
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::BAD_REQUEST)
+                .title("Invalid export format")
+                .detail(format!("{e:#}"))
+        })?
+        .unwrap_or(export::Format::Csv);
+
+    let export = maker
+        .closed_positions()
+        .await
+        .and_then(|positions| format.render(&positions))
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .title("Exporting closed CFDs failed")
+                .detail(format!("{e:#}"))
+        })?;
+
+    let content_type = match format {
+        export::Format::Csv => ContentType::CSV,
+        export::Format::Json => ContentType::JSON,
+    };
+
+    Ok((content_type, export))
+}
+
+#[derive(Clone, Deserialize)]
+pub struct BackupRequest {
+    passphrase: String,
+}
+
+/// Download an encrypted backup of the database and the seed files.
+#[rocket::post("/backup", data = "<backup_request>")]
+#[instrument(name = "POST /backup", skip_all, err)]
+pub async fn post_backup(
+    backup_request: Json<BackupRequest>,
+    maker: &State<Maker>,
+    data_dir: &State<PathBuf>,
+    _user: User,
+) -> Result<(ContentType, Vec<u8>), HttpApiProblem> {
+    let backup = maker
+        .backup(data_dir, &backup_request.passphrase)
+        .await
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .title("Creating backup failed")
+                .detail(format!("{e:#}"))
+        })?;
+
+    Ok((ContentType::Binary, backup))
+}
+
 #[derive(RustEmbed)]
 #[folder = "../../maker-frontend/dist/maker"]
 struct Asset;
@@ -293,6 +544,67 @@ pub async fn put_sync_wallet(maker: &State<Maker>, _user: User) -> Result<(), Ht
     Ok(())
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct WithdrawRequest {
+    address: bdk::bitcoin::Address,
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_btc")]
+    amount: Amount,
+    fee: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawDryRun {
+    /// The unsigned transaction, base64-encoded.
+    psbt: String,
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_sat")]
+    fee: Amount,
+}
+
+/// Withdraw from the maker's wallet. An amount of zero drains the wallet.
+///
+/// With `dry_run=true` the transaction is only built, not signed or broadcast.
+#[rocket::post("/withdraw?<dry_run>", data = "<withdraw_request>")]
+#[instrument(name = "POST /withdraw", skip(maker, _user), err)]
+pub async fn post_withdraw_request(
+    withdraw_request: Json<WithdrawRequest>,
+    dry_run: Option<bool>,
+    maker: &State<Maker>,
+    network: &State<Network>,
+    _user: User,
+) -> Result<Either<String, Json<WithdrawDryRun>>, HttpApiProblem> {
+    let amount = (withdraw_request.amount != Amount::ZERO).then(|| withdraw_request.amount);
+    let address = withdraw_request.address.clone();
+    let fee = withdraw_request.fee;
+
+    let to_problem = |e: anyhow::Error| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Could not proceed with withdraw request")
+            .detail(format!("{e:#}"))
+    };
+
+    if dry_run.unwrap_or(false) {
+        let preview = maker
+            .dry_run_withdraw(amount, address, fee)
+            .await
+            .map_err(to_problem)?;
+
+        return Ok(Either::Right(Json(WithdrawDryRun {
+            psbt: preview.psbt.to_string(),
+            fee: preview.fee,
+        })));
+    }
+
+    let txid = maker
+        .withdraw(amount, address, fee)
+        .await
+        .map_err(to_problem)?;
+
+    Ok(Either::Left(projection::to_mempool_url(
+        txid,
+        *network.inner(),
+    )))
+}
+
 #[rocket::get("/cfds")]
 #[instrument(name = "GET /cfds", skip_all, err)]
 pub async fn get_cfds<'r>(
@@ -311,20 +623,94 @@ pub async fn get_cfds<'r>(
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+/// Changes to the rollovers to accept.
+///
+/// All fields are optional and fields which are not set keep their current value, so that e.g.
+/// the rollover switch can still be toggled on its own. `max_quantity` and `min_funding_rate` are
+/// removed by setting them to `null`.
+#[derive(Debug, Clone, Deserialize)]
 pub struct RolloverConfig {
-    is_accepting_rollovers: bool,
+    #[serde(default)]
+    is_accepting_rollovers: Option<bool>,
+    /// Looked up in the catalogue by name, e.g. `BTCUSD`.
+    #[serde(default)]
+    paused_contract_symbols: Option<HashSet<String>>,
+    #[serde(default)]
+    blocked_takers: Option<HashSet<PeerId>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    max_quantity: Option<Option<Contracts>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    min_funding_rate: Option<Option<FundingRate>>,
+}
+
+impl RolloverConfig {
+    fn paused_contract_symbols(
+        &self,
+        catalogue: &Catalogue,
+    ) -> Result<Option<HashSet<model::ContractSymbol>>> {
+        self.paused_contract_symbols
+            .as_ref()
+            .map(|symbols| {
+                symbols
+                    .iter()
+                    .map(|symbol| catalogue.parse(symbol))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn apply(
+        self,
+        paused_contract_symbols: Option<HashSet<model::ContractSymbol>>,
+        policy: &mut rollover::policy::Policy,
+    ) {
+        if let Some(is_accepting_rollovers) = self.is_accepting_rollovers {
+            policy.paused = !is_accepting_rollovers;
+        }
+        if let Some(paused_contract_symbols) = paused_contract_symbols {
+            policy.paused_contract_symbols = paused_contract_symbols;
+        }
+        if let Some(blocked_takers) = self.blocked_takers {
+            policy.blocked_takers = blocked_takers;
+        }
+        if let Some(max_quantity) = self.max_quantity {
+            policy.max_quantity = max_quantity;
+        }
+        if let Some(min_funding_rate) = self.min_funding_rate {
+            policy.min_funding_rate = min_funding_rate;
+        }
+    }
+}
+
+/// Distinguish a field set to `null` from a missing field.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 #[rocket::post("/rollover/config", data = "<config>")]
-#[instrument(name = "POST /rollover/config", skip(maker), err)]
+#[instrument(name = "POST /rollover/config", skip(maker, data_dir, catalogue), err)]
 pub async fn update_rollover_configuration(
     config: Json<RolloverConfig>,
     maker: &State<Maker>,
+    data_dir: &State<PathBuf>,
+    catalogue: &State<Catalogue>,
     _user: User,
 ) -> Result<(), HttpApiProblem> {
+    let config = config.into_inner();
+    let paused_contract_symbols = config.paused_contract_symbols(catalogue).map_err(|e| {
+        HttpApiProblem::new(StatusCode::BAD_REQUEST)
+            .title("Unknown ContractSymbol provided")
+            .detail(format!("{e:#}"))
+    })?;
+
     maker
-        .update_rollover_configuration(config.is_accepting_rollovers)
+        .update_rollover_configuration(data_dir, |policy| {
+            config.apply(paused_contract_symbols, policy)
+        })
         .await
         .map_err(|e| {
             HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
@@ -0,0 +1,89 @@
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use xtra_libp2p::libp2p::PeerId;
+
+const FILENAME: &str = "taker_groups.toml";
+
+/// Convenience type to load the taker groups from toml
+#[derive(Deserialize)]
+struct TakerGroups {
+    groups: HashMap<String, HashSet<PeerId>>,
+}
+
+impl TakerGroups {
+    /// The group of each taker, a taker can only be in one group.
+    fn by_peer(self) -> Result<HashMap<PeerId, String>> {
+        let mut by_peer = HashMap::new();
+
+        for (group, peers) in self.groups {
+            for peer_id in peers {
+                if let Some(other) = by_peer.insert(peer_id, group.clone()) {
+                    bail!("Taker {peer_id} is in group {group} and {other}");
+                }
+            }
+        }
+
+        Ok(by_peer)
+    }
+}
+
+/// Load the groups takers can be sent private offers as.
+pub async fn load_taker_groups(directory: &Path) -> Result<HashMap<PeerId, String>> {
+    let path = directory.join(FILENAME);
+
+    if !path.try_exists()? {
+        tracing::info!("No taker groups. Expected config file at: {path:?}");
+
+        return Ok(HashMap::default());
+    }
+
+    let raw = tokio::fs::read_to_string(&path).await?;
+    let groups = toml::from_str::<TakerGroups>(&raw)
+        .with_context(|| format!("Failed to parse taker groups at {path:?}"))?;
+
+    groups.by_peer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER_ID: &str = "12D3KooWP3BN6bq9jPy8cP7Grj1QyUBfr7U6BeQFgMwfTTu12wuY";
+    const OTHER_PEER_ID: &str = "12D3KooWEsK2X8Tp24XtyWh7DM65VfwXtNH2cmfs2JsWmkmwKbV1";
+
+    #[test]
+    fn takers_are_mapped_to_their_group() {
+        let groups = toml::from_str::<TakerGroups>(&format!(
+            r#"
+            [groups]
+            vip = ["{PEER_ID}"]
+            market-makers = ["{OTHER_PEER_ID}"]
+            "#
+        ))
+        .unwrap();
+
+        let by_peer = groups.by_peer().unwrap();
+
+        assert_eq!(by_peer[&PEER_ID.parse().unwrap()], "vip");
+        assert_eq!(by_peer[&OTHER_PEER_ID.parse().unwrap()], "market-makers");
+    }
+
+    #[test]
+    fn taker_cannot_be_in_two_groups() {
+        let groups = toml::from_str::<TakerGroups>(&format!(
+            r#"
+            [groups]
+            vip = ["{PEER_ID}"]
+            market-makers = ["{PEER_ID}"]
+            "#
+        ))
+        .unwrap();
+
+        assert!(groups.by_peer().is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! An oracle that announces and attests to BitMEX price events in the same format as
+//! [Olivia](https://h00.ooo).
+//!
+//! Instead of observing an actual index price, the price to attest to is set by the user. This
+//! allows running makers and takers end-to-end without depending on the public Olivia instance
+//! and its hourly schedule.
+
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use bdk::bitcoin::hashes::sha256;
+use bdk::bitcoin::hashes::Hash;
+use bdk::bitcoin::hashes::HashEngine;
+use maia_core::secp256k1_zkp::schnorr::Signature;
+use maia_core::secp256k1_zkp::KeyPair;
+use maia_core::secp256k1_zkp::Message;
+use maia_core::secp256k1_zkp::PublicKey;
+use maia_core::secp256k1_zkp::SecretKey;
+use maia_core::secp256k1_zkp::XOnlyPublicKey;
+use maia_core::secp256k1_zkp::SECP256K1;
+use model::olivia::BitMexPriceEventId;
+use model::olivia::EVENT_TIME_FORMAT;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+pub struct Oracle {
+    /// The oracle's secret key, negated if necessary such that its public key has an even Y
+    /// coordinate.
+    secret_key: SecretKey,
+    public_key: XOnlyPublicKey,
+}
+
+impl Oracle {
+    pub fn new(secret_key: SecretKey) -> Self {
+        let (secret_key, public_key) = with_even_y(secret_key);
+
+        Self {
+            secret_key,
+            public_key,
+        }
+    }
+
+    pub fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    /// The announcement for the given event, without an attestation.
+    pub fn announce(&self, event_id: BitMexPriceEventId) -> Result<Response> {
+        Ok(Response {
+            announcement: self.announcement(event_id)?,
+            attestation: None,
+        })
+    }
+
+    /// The announcement for the given event, attesting to `price`.
+    pub fn attest(&self, event_id: BitMexPriceEventId, price: u64) -> Result<Response> {
+        let n_digits = event_id.digits();
+        ensure!(
+            n_digits >= 64 || price < 1 << n_digits,
+            "Price {price} does not fit into {n_digits} digits"
+        );
+
+        let scalars = (0..n_digits)
+            .map(|index| {
+                // Digits are attested to from most to least significant
+                let digit = (price >> (n_digits - 1 - index)) & 1;
+                self.attest_digit(event_id, index, digit as u8)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Response {
+            announcement: self.announcement(event_id)?,
+            attestation: Some(Attestation {
+                outcome: price.to_string(),
+                schemes: AttestationSchemes {
+                    olivia_v1: OliviaV1Attestation { scalars },
+                },
+                time: format_time(OffsetDateTime::now_utc())?,
+            }),
+        })
+    }
+
+    fn announcement(&self, event_id: BitMexPriceEventId) -> Result<Announcement> {
+        let nonces = (0..event_id.digits())
+            .map(|index| with_even_y(self.nonce(event_id, index)).1)
+            .collect();
+
+        let data = serde_json::to_string(&AnnouncementData {
+            id: event_id,
+            expected_outcome_time: format_time(event_id.timestamp())?,
+            descriptor: Descriptor {
+                kind: "digit-decomposition",
+                is_signed: false,
+                n_digits: event_id.digits(),
+                unit: None,
+            },
+            schemes: AnnouncementSchemes {
+                olivia_v1: OliviaV1Announcement { nonces },
+                ecdsa_v1: Empty {},
+            },
+        })?;
+
+        let keypair = KeyPair::from_seckey_slice(SECP256K1, self.secret_key.as_ref())
+            .context("Oracle secret key is valid")?;
+        let digest = Message::from_slice(&sha256::Hash::hash(data.as_bytes())[..])?;
+        let signature = SECP256K1.sign_schnorr_no_aux_rand(&digest, &keypair);
+
+        Ok(Announcement {
+            oracle_event: OracleEvent {
+                encoding: "json",
+                data,
+            },
+            signature,
+        })
+    }
+
+    /// Reveal `s = k + e * x` for the nonce `k` of the digit at `index`, with
+    /// `e = H_BIP340(R || X || sha256(digit))`.
+    fn attest_digit(
+        &self,
+        event_id: BitMexPriceEventId,
+        index: usize,
+        digit: u8,
+    ) -> Result<SecretKey> {
+        let (nonce, nonce_pk) = with_even_y(self.nonce(event_id, index));
+
+        let mut scalar = self.secret_key;
+        scalar.mul_assign(&challenge(&nonce_pk, &self.public_key, digit))?;
+        scalar.add_assign(nonce.as_ref())?;
+
+        Ok(scalar)
+    }
+
+    /// Derive the nonce for the digit at `index` of the given event.
+    ///
+    /// Nonces are derived deterministically from the oracle's secret key so that restarting the
+    /// oracle does not change announcements that have already been published.
+    fn nonce(&self, event_id: BitMexPriceEventId, index: usize) -> SecretKey {
+        let mut engine = sha256::Hash::engine();
+        engine.input(self.secret_key.as_ref());
+        engine.input(event_id.to_string().as_bytes());
+        engine.input(&(index as u64).to_be_bytes());
+
+        SecretKey::from_slice(&sha256::Hash::from_engine(engine)[..])
+            .expect("hash to be a valid secret key with overwhelming probability")
+    }
+}
+
+/// The BIP340 challenge committing to the nonce, the oracle's key and the attested digit.
+fn challenge(nonce_pk: &XOnlyPublicKey, oracle_pk: &XOnlyPublicKey, digit: u8) -> [u8; 32] {
+    let tag = sha256::Hash::hash(b"BIP0340/challenge");
+    let msg = sha256::Hash::hash(&[digit]);
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(&tag[..]);
+    engine.input(&tag[..]);
+    engine.input(&nonce_pk.serialize());
+    engine.input(&oracle_pk.serialize());
+    engine.input(&msg[..]);
+
+    sha256::Hash::from_engine(engine).into_inner()
+}
+
+/// Negate the secret key if its public key has an odd Y coordinate, as required for x-only keys.
+fn with_even_y(mut secret_key: SecretKey) -> (SecretKey, XOnlyPublicKey) {
+    let public_key = PublicKey::from_secret_key(SECP256K1, &secret_key).serialize();
+    if public_key[0] == 0x03 {
+        secret_key.negate_assign();
+    }
+
+    let x_only =
+        XOnlyPublicKey::from_slice(&public_key[1..]).expect("valid public key to be valid x-only");
+
+    (secret_key, x_only)
+}
+
+fn format_time(time: OffsetDateTime) -> Result<String> {
+    Ok(time.format(&EVENT_TIME_FORMAT)?)
+}
+
+/// An event as served by Olivia, which is what `model::olivia` parses.
+#[derive(Debug, Serialize)]
+pub struct Response {
+    announcement: Announcement,
+    attestation: Option<Attestation>,
+}
+
+#[derive(Debug, Serialize)]
+struct Announcement {
+    oracle_event: OracleEvent,
+    signature: Signature,
+}
+
+#[derive(Debug, Serialize)]
+struct OracleEvent {
+    encoding: &'static str,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct AnnouncementData {
+    id: BitMexPriceEventId,
+    expected_outcome_time: String,
+    descriptor: Descriptor,
+    schemes: AnnouncementSchemes,
+}
+
+#[derive(Debug, Serialize)]
+struct Descriptor {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    is_signed: bool,
+    n_digits: usize,
+    unit: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnnouncementSchemes {
+    #[serde(rename = "olivia-v1")]
+    olivia_v1: OliviaV1Announcement,
+    #[serde(rename = "ecdsa-v1")]
+    ecdsa_v1: Empty,
+}
+
+#[derive(Debug, Serialize)]
+struct OliviaV1Announcement {
+    nonces: Vec<XOnlyPublicKey>,
+}
+
+#[derive(Debug, Serialize)]
+struct Empty {}
+
+#[derive(Debug, Serialize)]
+struct Attestation {
+    outcome: String,
+    schemes: AttestationSchemes,
+    time: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AttestationSchemes {
+    #[serde(rename = "olivia-v1")]
+    olivia_v1: OliviaV1Attestation,
+}
+
+#[derive(Debug, Serialize)]
+struct OliviaV1Attestation {
+    scalars: Vec<SecretKey>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::olivia;
+    use model::olivia::IndexPrice;
+    use std::str::FromStr;
+    use time::macros::datetime;
+
+    fn oracle() -> Oracle {
+        let secret_key =
+            SecretKey::from_str("4fdb35c2b3cf1b2a6b7bb0c9cd5a8fa5e4b5a20ee4e8ac0bb4ee0e5e51c7e7b0")
+                .unwrap();
+
+        Oracle::new(secret_key)
+    }
+
+    fn event_id() -> BitMexPriceEventId {
+        BitMexPriceEventId::with_20_digits(
+            datetime!(2021-10-04 22:00:00).assume_utc(),
+            IndexPrice::BXBT,
+        )
+    }
+
+    #[test]
+    fn announcement_can_be_parsed_as_olivia_announcement() {
+        let oracle = oracle();
+
+        let json = serde_json::to_string(&oracle.announce(event_id()).unwrap()).unwrap();
+        let announcement = serde_json::from_str::<olivia::Announcement>(&json).unwrap();
+
+        assert_eq!(announcement.id, event_id());
+        assert_eq!(announcement.expected_outcome_time, event_id().timestamp());
+        assert_eq!(announcement.nonce_pks.len(), 20);
+    }
+
+    #[test]
+    fn announcement_is_stable() {
+        let first = serde_json::to_value(oracle().announce(event_id()).unwrap()).unwrap();
+        let second = serde_json::to_value(oracle().announce(event_id()).unwrap()).unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn attestation_can_be_parsed_as_olivia_attestation() {
+        let oracle = oracle();
+
+        let json = serde_json::to_string(&oracle.attest(event_id(), 48935).unwrap()).unwrap();
+        let attestation = serde_json::from_str::<olivia::Attestation>(&json).unwrap();
+
+        assert_eq!(attestation.id, event_id());
+        assert_eq!(attestation.price, 48935);
+        assert_eq!(attestation.scalars.len(), 20);
+    }
+
+    #[test]
+    fn attestation_matches_announced_nonces() {
+        let oracle = oracle();
+        let price = 0b1011_0101_0011_1100_0110;
+
+        let json = serde_json::to_string(&oracle.attest(event_id(), price).unwrap()).unwrap();
+        let announcement = serde_json::from_str::<olivia::Announcement>(&json).unwrap();
+        let attestation = serde_json::from_str::<olivia::Attestation>(&json).unwrap();
+
+        let oracle_pk = even_y(&oracle.public_key());
+
+        for (index, (nonce_pk, scalar)) in announcement
+            .nonce_pks
+            .iter()
+            .zip(attestation.scalars.iter())
+            .enumerate()
+        {
+            let digit = ((price >> (19 - index)) & 1) as u8;
+
+            let mut challenge_pk = oracle_pk;
+            challenge_pk
+                .mul_assign(SECP256K1, &challenge(nonce_pk, &oracle.public_key(), digit))
+                .unwrap();
+            let expected = even_y(nonce_pk).combine(&challenge_pk).unwrap();
+
+            assert_eq!(PublicKey::from_secret_key(SECP256K1, scalar), expected);
+        }
+    }
+
+    fn even_y(x_only: &XOnlyPublicKey) -> PublicKey {
+        PublicKey::from_slice(&[&[0x02], &x_only.serialize()[..]].concat()).unwrap()
+    }
+
+    #[test]
+    fn rejects_price_exceeding_digits() {
+        let result = oracle().attest(event_id(), 1 << 20);
+
+        assert!(result.is_err());
+    }
+}
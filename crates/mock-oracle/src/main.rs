@@ -0,0 +1,174 @@
+use anyhow::Result;
+use clap::Parser;
+use maia_core::secp256k1_zkp::SecretKey;
+use mock_oracle::Oracle;
+use mock_oracle::Response;
+use model::olivia::BitMexPriceEventId;
+use model::olivia::IndexPrice;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+
+#[derive(Parser)]
+struct Opts {
+    /// The IP address and port to listen on.
+    #[clap(long, default_value = "127.0.0.1:8100")]
+    http_address: SocketAddr,
+
+    /// The secret key to attest with, hex-encoded.
+    ///
+    /// If not given, a random key is generated. Pass a fixed key to keep announcements stable
+    /// across restarts.
+    #[clap(long)]
+    secret_key: Option<SecretKey>,
+
+    /// The path under which price events are served.
+    #[clap(long, default_value = "/x/BitMEX")]
+    event_prefix: String,
+}
+
+/// The prices to attest to.
+#[derive(Default)]
+struct Prices {
+    /// The price used for events that occur without an explicitly set price.
+    current: HashMap<IndexPrice, u64>,
+    /// Prices of events that have been attested to, so that attestations don't change.
+    attested: HashMap<BitMexPriceEventId, u64>,
+}
+
+impl Prices {
+    fn attested_price(&mut self, event_id: BitMexPriceEventId) -> Option<u64> {
+        if let Some(price) = self.attested.get(&event_id) {
+            return Some(*price);
+        }
+
+        if event_id.timestamp() > OffsetDateTime::now_utc() {
+            return None;
+        }
+
+        let price = *self.current.get(&event_id.index_price())?;
+        self.attested.insert(event_id, price);
+
+        Some(price)
+    }
+}
+
+#[derive(Deserialize)]
+struct SetPrice {
+    price: u64,
+}
+
+#[rocket::get("/<index>/<event>?<n>")]
+fn get_event(
+    index: &str,
+    event: &str,
+    n: usize,
+    oracle: &State<Oracle>,
+    prices: &State<Mutex<Prices>>,
+) -> Result<Json<Response>, Status> {
+    let event_id = BitMexPriceEventId::from_str(&format!("{index}/{event}?n={n}"))
+        .map_err(|_| Status::NotFound)?;
+
+    let price = prices
+        .lock()
+        .expect("lock not to be poisoned")
+        .attested_price(event_id);
+
+    let response = match price {
+        Some(price) => oracle.attest(event_id, price),
+        None => oracle.announce(event_id),
+    }
+    .map_err(|e| {
+        tracing::warn!(%event_id, "Failed to serve event: {e:#}");
+        Status::BadRequest
+    })?;
+
+    Ok(Json(response))
+}
+
+/// Set the price to attest to for all events of `index` that have occurred but were not
+/// attested to yet.
+#[rocket::put("/prices/<index>", data = "<body>")]
+fn put_price(
+    index: &str,
+    body: Json<SetPrice>,
+    prices: &State<Mutex<Prices>>,
+) -> Result<Status, Status> {
+    let index = IndexPrice::from_str(index).map_err(|_| Status::NotFound)?;
+
+    prices
+        .lock()
+        .expect("lock not to be poisoned")
+        .current
+        .insert(index, body.price);
+
+    tracing::info!(%index, price = body.price, "Set price");
+
+    Ok(Status::NoContent)
+}
+
+/// Attest to `price` for a specific event, even if it lies in the future.
+#[rocket::put("/attestations/<index>/<event>?<n>", data = "<body>")]
+fn put_attestation(
+    index: &str,
+    event: &str,
+    n: usize,
+    body: Json<SetPrice>,
+    prices: &State<Mutex<Prices>>,
+) -> Result<Status, Status> {
+    let event_id = BitMexPriceEventId::from_str(&format!("{index}/{event}?n={n}"))
+        .map_err(|_| Status::NotFound)?;
+
+    let mut prices = prices.lock().expect("lock not to be poisoned");
+    if prices.attested.contains_key(&event_id) {
+        return Err(Status::Conflict);
+    }
+    prices.attested.insert(event_id, body.price);
+
+    tracing::info!(%event_id, price = body.price, "Set attestation");
+
+    Ok(Status::NoContent)
+}
+
+#[rocket::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter("info,rocket=warn")
+        .init();
+
+    let opts = Opts::parse();
+
+    let secret_key = opts
+        .secret_key
+        .unwrap_or_else(|| bdk_ext::keypair::new(&mut rand::thread_rng()).0);
+    let oracle = Oracle::new(secret_key);
+
+    let public_key = oracle.public_key();
+    let base_url = format!("http://{}", opts.http_address);
+    // Events are served for any index, so the oracle works for every contract symbol
+    tracing::info!(
+        "Use this oracle with: --oracle <contract symbol>={public_key},{base_url},{}",
+        opts.event_prefix
+    );
+
+    let figment = rocket::Config::figment()
+        .merge(("address", opts.http_address.ip()))
+        .merge(("port", opts.http_address.port()))
+        .merge(("cli_colors", false));
+
+    rocket::custom(figment)
+        .manage(oracle)
+        .manage(Mutex::new(Prices::default()))
+        .mount(opts.event_prefix.as_str(), rocket::routes![get_event])
+        .mount("/", rocket::routes![put_price, put_attestation])
+        .launch()
+        .await?;
+
+    Ok(())
+}
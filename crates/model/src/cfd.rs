@@ -3,11 +3,11 @@ use crate::hex_transaction;
 use crate::libp2p::PeerId;
 use crate::olivia;
 use crate::olivia::BitMexPriceEventId;
+use crate::olivia::OracleConfig;
 use crate::payout_curve::inverse;
 use crate::payout_curve::quanto;
 use crate::payout_curve::InverseMaxPrice;
 use crate::payout_curve::Payouts;
-use crate::payout_curve::ETHUSD_MULTIPLIER;
 use crate::rollover::BaseDlcParams;
 use crate::rollover::RolloverParams;
 use crate::CompleteFee;
@@ -20,6 +20,7 @@ use crate::Identity;
 use crate::Leverage;
 use crate::LotSize;
 use crate::OpeningFee;
+use crate::PayoutCurve;
 use crate::Percent;
 use crate::Position;
 use crate::Price;
@@ -32,10 +33,14 @@ use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use bdk::bitcoin;
+use bdk::bitcoin::consensus;
+use bdk::bitcoin::hashes::sha256;
+use bdk::bitcoin::hashes::Hash;
 use bdk::bitcoin::secp256k1::SecretKey;
 use bdk::bitcoin::util::key::PublicKey;
 use bdk::bitcoin::Address;
 use bdk::bitcoin::Amount;
+use bdk::bitcoin::OutPoint;
 use bdk::bitcoin::Script;
 use bdk::bitcoin::SignedAmount;
 use bdk::bitcoin::Transaction;
@@ -44,13 +49,16 @@ use bdk::bitcoin::TxOut;
 use bdk::bitcoin::Txid;
 use bdk::descriptor::Descriptor;
 use bdk::miniscript::DescriptorTrait;
+use bdk_ext::SecretKeyExt;
 use itertools::Itertools;
+use maia::lock_descriptor;
 use maia::spending_tx_sighash;
 use maia_core::secp256k1_zkp;
 use maia_core::secp256k1_zkp::ecdsa::Signature;
 use maia_core::secp256k1_zkp::EcdsaAdaptorSignature;
 use maia_core::secp256k1_zkp::SECP256K1;
 use maia_core::TransactionExt;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::de::Error as _;
@@ -66,6 +74,13 @@ use uuid::Uuid;
 
 pub const CET_TIMELOCK: u32 = 12;
 
+/// Upper bound of the virtual size of a partial settlement transaction: one input spending the
+/// 2-of-2 lock output, the new lock output and up to two payout outputs.
+const PARTIAL_SETTLEMENT_TX_VBYTES: u64 = 236;
+
+/// Outputs below this amount are not relayed by the network.
+const DUST_LIMIT: u64 = 546;
+
 // TODO: Clean this up to be a separate type
 pub type OfferId = OrderId;
 
@@ -169,6 +184,11 @@ pub struct Offer {
     /// The maker includes this into the Order based on the Oracle announcement to be used.
     pub oracle_event_id: BitMexPriceEventId,
 
+    /// The oracle that attests to `oracle_event_id`
+    ///
+    /// Allows the taker to check that the maker uses the oracle the taker trusts.
+    pub oracle: OracleConfig,
+
     pub tx_fee_rate: TxFeeRate,
     pub funding_rate: FundingRate,
     pub opening_fee: OpeningFee,
@@ -189,6 +209,7 @@ impl Offer {
         leverage_choices: Vec<Leverage>,
         contract_symbol: ContractSymbol,
         lot_size: LotSize,
+        oracle: OracleConfig,
     ) -> Self {
         let oracle_event_id = olivia::next_announcement_after(
             time::OffsetDateTime::now_utc() + settlement_interval,
@@ -206,6 +227,7 @@ impl Offer {
             creation_timestamp_maker: Timestamp::now(),
             settlement_interval,
             oracle_event_id,
+            oracle,
             tx_fee_rate,
             funding_rate,
             opening_fee,
@@ -260,6 +282,21 @@ pub struct SettlementProposal {
     pub price: Price,
 }
 
+/// Proposed partial collaborative settlement
+///
+/// The `taker` and `maker` amounts are the payouts for the settled `quantity`; the remaining
+/// contracts stay locked in a new DLC.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PartialSettlementProposal {
+    pub order_id: OrderId,
+    pub quantity: Contracts,
+    #[serde(with = "::bdk::bitcoin::util::amount::serde::as_btc")]
+    pub taker: Amount,
+    #[serde(with = "::bdk::bitcoin::util::amount::serde::as_btc")]
+    pub maker: Amount,
+    pub price: Price,
+}
+
 /// Reasons why we cannot rollover a CFD.
 #[derive(thiserror::Error, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CannotRollover {
@@ -277,6 +314,8 @@ pub enum CannotRollover {
     Closed,
     #[error("Cannot rollover CFD without events")]
     NoEvents,
+    #[error("Rolling over would not extend the CFD, it was rolled over within the hour")]
+    NotExtended,
 }
 
 /// Reasons why we cannot collab close a CFD
@@ -356,6 +395,40 @@ pub enum EventKind {
     // commit transaction for some
     CollaborativeSettlementFailed,
 
+    PartialSettlementStarted {
+        proposal: PartialSettlementProposal,
+    },
+    /// We are about to send our signature of the partial settlement transaction.
+    ///
+    /// From then on the counterparty can publish the transaction. The DLC of the remaining
+    /// contracts is recorded so that we can adopt it if they do so without completing the
+    /// protocol.
+    PartialSettlementSigned {
+        #[serde(with = "hex_transaction")]
+        spend_tx: Transaction,
+        script: Script,
+        price: Price,
+        /// The number of contracts that are settled.
+        quantity: Contracts,
+        dlc: Dlc,
+    },
+    /// Part of the position was settled collaboratively.
+    ///
+    /// The spend transaction pays out the settled contracts and locks the remaining ones in a new
+    /// lock output, which is the lock of the new `dlc`. It has to be broadcast as a result of this
+    /// event.
+    PartialSettlementCompleted {
+        #[serde(with = "hex_transaction")]
+        spend_tx: Transaction,
+        script: Script,
+        price: Price,
+        /// The number of contracts that were settled.
+        quantity: Contracts,
+        dlc: Option<Dlc>,
+    },
+    PartialSettlementRejected,
+    PartialSettlementFailed,
+
     LockConfirmed,
     /// The lock transaction is confirmed after CFD was closed
     ///
@@ -367,7 +440,13 @@ pub enum EventKind {
     CommitConfirmed,
     CetConfirmed,
     RefundConfirmed,
+    /// A revoked commit transaction was seen on chain
+    ///
+    /// This event is no longer emitted, it is only kept to be able to load events that were
+    /// recorded before we started punishing the counterparty. Revoked commit transactions result
+    /// in `RevokedCommitPublished` now.
     RevokeConfirmed,
+    PunishConfirmed,
     CollaborativeSettlementConfirmed,
 
     CetTimelockExpiredPriorOracleAttestation,
@@ -381,6 +460,15 @@ pub enum EventKind {
         refund_tx: Transaction,
     },
 
+    /// The counterparty published a revoked commit transaction.
+    ///
+    /// The punish transaction spends the entire output of the revoked commit transaction to our
+    /// own address and has to be broadcast as a result of this event.
+    RevokedCommitPublished {
+        #[serde(with = "hex_transaction")]
+        punish_tx: Transaction,
+    },
+
     OracleAttestedPriorCetTimelock {
         #[serde(with = "hex_transaction")]
         timelocked_cet: Transaction,
@@ -401,6 +489,18 @@ pub enum EventKind {
         #[serde(with = "hex_transaction")]
         tx: Transaction,
     },
+
+    /// We bumped the fee of a published transaction by spending our output with a child
+    /// transaction (CPFP).
+    ///
+    /// The child transaction has to be broadcast as a result of this event. A later bump for the
+    /// same parent replaces the previous child transaction.
+    FeeBumped {
+        parent_txid: Txid,
+        #[serde(with = "hex_transaction")]
+        child_tx: Transaction,
+        fee_rate: TxFeeRate,
+    },
 }
 
 impl fmt::Display for EventKind {
@@ -421,21 +521,29 @@ impl fmt::Display for EventKind {
             CollaborativeSettlementCompleted { .. } => "CollaborativeSettlementCompleted",
             CollaborativeSettlementRejected => "CollaborativeSettlementRejected",
             CollaborativeSettlementFailed => "CollaborativeSettlementFailed",
+            PartialSettlementStarted { .. } => "PartialSettlementStarted",
+            PartialSettlementSigned { .. } => "PartialSettlementSigned",
+            PartialSettlementCompleted { .. } => "PartialSettlementCompleted",
+            PartialSettlementRejected => "PartialSettlementRejected",
+            PartialSettlementFailed => "PartialSettlementFailed",
             LockConfirmed => "LockConfirmed",
             LockConfirmedAfterFinality => "LockConfirmedAfterFinality",
             CommitConfirmed => "CommitConfirmed",
             CetConfirmed => "CetConfirmed",
             RefundConfirmed => "RefundConfirmed",
             RevokeConfirmed => "RevokeConfirmed",
+            PunishConfirmed => "PunishConfirmed",
             CollaborativeSettlementConfirmed => "CollaborativeSettlementConfirmed",
             CetTimelockExpiredPriorOracleAttestation => "CetTimelockExpiredPriorOracleAttestation",
             CetTimelockExpiredPostOracleAttestation { .. } => {
                 "CetTimelockExpiredPostOracleAttestation"
             }
             RefundTimelockExpired { .. } => "RefundTimelockExpired",
+            RevokedCommitPublished { .. } => "RevokedCommitPublished",
             OracleAttestedPriorCetTimelock { .. } => "OracleAttestedPriorCetTimelock",
             OracleAttestedPostCetTimelock { .. } => "OracleAttestedPostCetTimelock",
             ManualCommit { .. } => "ManualCommit",
+            FeeBumped { .. } => "FeeBumped",
         };
 
         s.fmt(f)
@@ -449,6 +557,7 @@ impl EventKind {
     pub const COLLABORATIVE_SETTLEMENT_CONFIRMED: &'static str = "CollaborativeSettlementConfirmed";
     pub const CET_CONFIRMED: &'static str = "CetConfirmed";
     pub const REFUND_CONFIRMED: &'static str = "RefundConfirmed";
+    pub const PUNISH_CONFIRMED: &'static str = "PunishConfirmed";
     pub const CONTRACT_SETUP_FAILED: &'static str = "ContractSetupFailed";
     pub const OFFER_REJECTED: &'static str = "OfferRejected";
 
@@ -501,16 +610,20 @@ pub struct Cfd {
     long_leverage: Leverage,
     short_leverage: Leverage,
     settlement_interval: Duration,
-    quantity: Contracts,
     counterparty_network_identity: Identity,
     counterparty_peer_id: Option<PeerId>,
     role: Role,
     opening_fee: OpeningFee,
     initial_tx_fee_rate: TxFeeRate,
     contract_symbol: ContractSymbol,
+    /// The oracle attesting to the price events of this CFD, agreed upon in the offer.
+    oracle: OracleConfig,
     // dynamic (based on events)
     fee_account: FeeAccount,
 
+    /// The number of contracts that are still open, reduced by partial settlements.
+    quantity: Contracts,
+
     dlc: Option<Dlc>,
 
     /// Holds the decrypted CET transaction if we have previously emitted it as part of an event.
@@ -529,12 +642,16 @@ pub struct Cfd {
     collaborative_settlement_spend_tx: Option<Transaction>,
     refund_tx: Option<Transaction>,
 
+    /// Holds the punish transaction if the counterparty published a revoked commit transaction.
+    punish_tx: Option<Transaction>,
+
     lock_finality: bool,
 
     commit_finality: bool,
     refund_finality: bool,
     cet_finality: bool,
     collaborative_settlement_finality: bool,
+    punish_finality: bool,
     cet_timelock_expired: bool,
 
     refund_timelock_expired: bool,
@@ -542,6 +659,18 @@ pub struct Cfd {
     during_contract_setup: bool,
     during_rollover: bool,
     settlement_proposal: Option<SettlementProposal>,
+    partial_settlement_proposal: Option<PartialSettlementProposal>,
+    /// A partial settlement transaction we signed, which the counterparty can publish.
+    signed_partial_settlement: Option<SignedPartialSettlement>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SignedPartialSettlement {
+    spend_tx: Transaction,
+    script: Script,
+    price: Price,
+    quantity: Contracts,
+    dlc: Dlc,
 }
 
 impl Cfd {
@@ -563,6 +692,7 @@ impl Cfd {
         initial_funding_rate: FundingRate,
         initial_tx_fee_rate: TxFeeRate,
         contract_symbol: ContractSymbol,
+        oracle: OracleConfig,
     ) -> Self {
         let (long_leverage, short_leverage) =
             long_and_short_leverage(taker_leverage, role, position);
@@ -595,21 +725,26 @@ impl Cfd {
             opening_fee,
             initial_tx_fee_rate,
             contract_symbol,
+            oracle,
             dlc: None,
             cet: None,
             commit_tx: None,
             collaborative_settlement_spend_tx: None,
             refund_tx: None,
+            punish_tx: None,
             lock_finality: false,
             commit_finality: false,
             refund_finality: false,
             cet_finality: false,
             collaborative_settlement_finality: false,
+            punish_finality: false,
             cet_timelock_expired: false,
             refund_timelock_expired: false,
             during_contract_setup: false,
             during_rollover: false,
             settlement_proposal: None,
+            partial_settlement_proposal: None,
+            signed_partial_settlement: None,
             fee_account: FeeAccount::new(position, role)
                 .add_opening_fee(opening_fee)
                 .add_funding_fee(initial_funding_fee),
@@ -646,6 +781,7 @@ impl Cfd {
             offer.funding_rate,
             offer.tx_fee_rate,
             offer.contract_symbol,
+            offer.oracle.clone(),
         )
     }
 
@@ -685,6 +821,8 @@ impl Cfd {
 
     fn is_in_collaborative_settlement(&self) -> bool {
         self.settlement_proposal.is_some()
+            || self.partial_settlement_proposal.is_some()
+            || self.signed_partial_settlement.is_some()
     }
 
     fn is_in_force_close(&self) -> bool {
@@ -694,15 +832,35 @@ impl Cfd {
     pub fn can_auto_rollover_taker(
         &self,
         now: OffsetDateTime,
+    ) -> Result<(Txid, BitMexPriceEventId), CannotRollover> {
+        let (commit_txid, settlement_event_id) = self.can_rollover_taker(now)?;
+
+        let time_until_expiry = settlement_event_id.timestamp() - now;
+        if time_until_expiry > SETTLEMENT_INTERVAL - Duration::HOUR {
+            return Err(CannotRollover::TooRecent);
+        }
+
+        Ok((commit_txid, settlement_event_id))
+    }
+
+    /// Whether the taker can roll over the CFD right away, regardless of the rollover schedule.
+    ///
+    /// This is the case when the maker asked us to roll over. Only a rollover which would not
+    /// move the settlement event, because the CFD was rolled over within the same hour, is refused.
+    pub fn can_rollover_taker(
+        &self,
+        now: OffsetDateTime,
     ) -> Result<(Txid, BitMexPriceEventId), CannotRollover> {
         self.can_rollover()?;
 
         let dlc = self.dlc.as_ref().ok_or(CannotRollover::NoDlc)?;
 
-        let expiry_timestamp = dlc.settlement_event_id.timestamp();
-        let time_until_expiry = expiry_timestamp - now;
-        if time_until_expiry > SETTLEMENT_INTERVAL - Duration::HOUR {
-            return Err(CannotRollover::TooRecent);
+        // The maker settles a rollover at the first event after the settlement interval from now
+        let to_event_id =
+            olivia::next_announcement_after(now + self.settlement_interval, self.contract_symbol);
+        let from_settlement_time = dlc.settlement_event_id.timestamp();
+        if from_settlement_time > now && to_event_id.timestamp() <= from_settlement_time {
+            return Err(CannotRollover::NotExtended);
         }
 
         Ok((dlc.commit.0.txid(), dlc.settlement_event_id))
@@ -764,7 +922,10 @@ impl Cfd {
 
     /// Any transaction spending from lock has reached finality on the blockchain
     fn is_final(&self) -> bool {
-        self.collaborative_settlement_finality || self.cet_finality || self.refund_finality
+        self.collaborative_settlement_finality
+            || self.cet_finality
+            || self.refund_finality
+            || self.punish_finality
     }
 
     fn is_collaboratively_closed(&self) -> bool {
@@ -775,6 +936,10 @@ impl Cfd {
         self.refund_tx.is_some()
     }
 
+    fn is_punished(&self) -> bool {
+        self.punish_tx.is_some()
+    }
+
     /// Aggregate that defines if a CFD is considered closed
     ///
     /// A CFD is considered closed when the closing price can't change anymore, which means that we
@@ -784,11 +949,13 @@ impl Cfd {
     /// - the cfd was attested (i.e.a CET is set)
     /// - the cfd was collaboratively close (i.e. the collab close transaction is set)
     /// - the cfd was refunded (i.e. the refund transaction is set)
+    /// - the counterparty was punished (i.e. the punish transaction is set)
     fn is_closed(&self) -> bool {
         self.is_final()
             || self.is_attested()
             || self.is_collaboratively_closed()
             || self.is_refunded()
+            || self.is_punished()
     }
 
     pub fn start_contract_setup(&self) -> Result<(CfdEvent, SetupParams, Position)> {
@@ -1111,8 +1278,40 @@ impl Cfd {
         n_payouts: usize,
         inverse_max_price_config: InverseMaxPrice,
     ) -> Result<(SettlementTransaction, SettlementProposal)> {
-        let payouts = match self.contract_symbol {
-            ContractSymbol::BtcUsd => Payouts::new_inverse(
+        let (maker_payout, taker_payout) =
+            self.settlement_payout(current_price, n_payouts, inverse_max_price_config)?;
+
+        let dlc = self
+            .dlc
+            .as_ref()
+            .context("Collaborative close without DLC")?;
+
+        let collab_settlement_tx = dlc.collab_settlement_transaction(
+            maker_payout,
+            taker_payout,
+            current_price,
+            self.role,
+        )?;
+
+        let proposal = SettlementProposal {
+            order_id: self.id,
+            taker: taker_payout,
+            maker: maker_payout,
+            price: current_price,
+        };
+
+        Ok((collab_settlement_tx, proposal))
+    }
+
+    /// The maker and taker payouts of settling the entire position at `current_price`.
+    fn settlement_payout(
+        &self,
+        current_price: Price,
+        n_payouts: usize,
+        inverse_max_price_config: InverseMaxPrice,
+    ) -> Result<(Amount, Amount)> {
+        let payouts = match self.contract_symbol.payout_curve() {
+            PayoutCurve::Inverse => Payouts::new_inverse(
                 (self.position, self.role),
                 self.initial_price,
                 self.quantity,
@@ -1121,13 +1320,13 @@ impl Cfd {
                 self.fee_account.settle(),
                 inverse_max_price_config,
             )?,
-            ContractSymbol::EthUsd => Payouts::new_quanto(
+            PayoutCurve::Quanto => Payouts::new_quanto(
                 (self.position, self.role),
                 self.initial_price.to_u64(),
                 self.quantity.to_u64(),
                 (self.long_leverage, self.short_leverage),
                 n_payouts,
-                ETHUSD_MULTIPLIER,
+                self.contract_symbol.multiplier(),
                 self.fee_account.settle(),
             )?,
         }
@@ -1138,26 +1337,7 @@ impl Cfd {
             .find(|&x| x.digits().range().contains(&current_price.to_u64()))
             .context("find current price on the payout curve")?;
 
-        let dlc = self
-            .dlc
-            .as_ref()
-            .context("Collaborative close without DLC")?;
-
-        let collab_settlement_tx = dlc.collab_settlement_transaction(
-            *payout.maker_amount(),
-            *payout.taker_amount(),
-            current_price,
-            self.role,
-        )?;
-
-        let proposal = SettlementProposal {
-            order_id: self.id,
-            taker: *payout.taker_amount(),
-            maker: *payout.maker_amount(),
-            price: current_price,
-        };
-
-        Ok((collab_settlement_tx, proposal))
+        Ok((*payout.maker_amount(), *payout.taker_amount()))
     }
 
     pub fn accept_collaborative_settlement_proposal(
@@ -1178,6 +1358,191 @@ impl Cfd {
         ))
     }
 
+    /// Start settling `quantity` of the contracts of this CFD collaboratively.
+    ///
+    /// The remaining contracts stay open: they are locked in a new output of the settlement
+    /// transaction for which a new DLC has to be set up.
+    pub fn start_partial_settlement_taker(
+        self,
+        current_price: Price,
+        quantity: Contracts,
+        n_payouts: usize,
+    ) -> Result<(CfdEvent, PartialSettlement)> {
+        ensure!(self.role == Role::Taker);
+
+        let partial_settlement =
+            self.make_partial_settlement(current_price, quantity, n_payouts)?;
+
+        Ok((
+            self.event(EventKind::PartialSettlementStarted {
+                proposal: partial_settlement.proposal,
+            }),
+            partial_settlement,
+        ))
+    }
+
+    /// Process the taker's partial settlement proposal.
+    ///
+    /// Like the collaborative settlement of the entire position, this sets the maximum payout
+    /// price to Olivia's maximum attestation price and assumes that the counterparty has used the
+    /// same configuration.
+    pub fn start_partial_settlement_maker(
+        self,
+        current_price: Price,
+        quantity: Contracts,
+        n_payouts: usize,
+        proposed_settlement_transaction: &Transaction,
+    ) -> Result<(CfdEvent, PartialSettlement)> {
+        ensure!(self.role == Role::Maker);
+
+        let partial_settlement =
+            self.make_partial_settlement(current_price, quantity, n_payouts)?;
+
+        let local_settlement_transaction = partial_settlement.transaction.unsigned_transaction();
+        ensure!(
+            *local_settlement_transaction == *proposed_settlement_transaction,
+            "Proposed partial settlement does not equal locally created one. Local: {local_settlement_transaction:?}, proposed: {proposed_settlement_transaction:?}"
+        );
+
+        Ok((
+            self.event(EventKind::PartialSettlementStarted {
+                proposal: partial_settlement.proposal,
+            }),
+            partial_settlement,
+        ))
+    }
+
+    fn make_partial_settlement(
+        &self,
+        current_price: Price,
+        quantity: Contracts,
+        n_payouts: usize,
+    ) -> Result<PartialSettlement> {
+        ensure!(!self.is_in_collaborative_settlement());
+        ensure!(
+            !self.during_rollover,
+            "Cannot partially settle during rollover"
+        );
+        self.can_settle_collaboratively()
+            .context("Cannot collaboratively settle")?;
+        ensure!(
+            self.lock_finality,
+            "Cannot partially settle before the lock transaction is final"
+        );
+        ensure!(
+            quantity > Contracts::ZERO && quantity < self.quantity,
+            "Can only partially settle less than {} contracts, got {quantity}",
+            self.quantity
+        );
+
+        let dlc = self
+            .dlc
+            .as_ref()
+            .context("Partial settlement without DLC")?;
+        let remaining = self.quantity - quantity;
+
+        let maker_position = match self.role {
+            Role::Maker => self.position,
+            Role::Taker => self.position.counter_position(),
+        };
+        let (maker_leverage, taker_leverage) = match maker_position {
+            Position::Long => (self.long_leverage, self.short_leverage),
+            Position::Short => (self.short_leverage, self.long_leverage),
+        };
+        let maker_lock_amount = calculate_margin(
+            self.contract_symbol,
+            self.initial_price,
+            remaining,
+            maker_leverage,
+        );
+        let taker_lock_amount = calculate_margin(
+            self.contract_symbol,
+            self.initial_price,
+            remaining,
+            taker_leverage,
+        );
+
+        // The settled contracts get their share of what settling the entire position would pay
+        // out, the rest of the lock output is locked again for the remaining contracts.
+        let (maker_payout, taker_payout) =
+            self.settlement_payout(current_price, n_payouts, InverseMaxPrice::OliviaMax)?;
+        let settled_amount = (dlc.maker_lock_amount + dlc.taker_lock_amount)
+            .checked_sub(maker_lock_amount + taker_lock_amount)
+            .context("Remaining contracts require more than the locked amount")?;
+        let maker_payout = Decimal::from(maker_payout.as_sat()) * quantity.into_decimal()
+            / self.quantity.into_decimal();
+        let maker_payout = Amount::from_sat(
+            maker_payout
+                .round_dp_with_strategy(0, rust_decimal::RoundingStrategy::ToZero)
+                .to_u64()
+                .context("Failed to represent as u64")?,
+        )
+        .min(settled_amount);
+        let taker_payout = settled_amount - maker_payout;
+
+        // The remaining contracts are locked with new identity keys, so that the new lock output
+        // does not share its script with the one being spent
+        let (identity, identity_counterparty) = dlc.partial_settlement_identities()?;
+        let (maker_identity_pk, taker_identity_pk) = match self.role {
+            Role::Maker => (identity_pk(&identity), identity_counterparty),
+            Role::Taker => (identity_counterparty, identity_pk(&identity)),
+        };
+        let relock_desc = lock_descriptor(maker_identity_pk, taker_identity_pk);
+
+        let transaction = dlc.partial_settlement_transaction(
+            (maker_payout, taker_payout),
+            (maker_lock_amount, taker_lock_amount),
+            &relock_desc,
+            current_price,
+            self.initial_tx_fee_rate,
+            self.role,
+        )?;
+
+        let remaining_dlc = Dlc {
+            identity,
+            identity_counterparty,
+            lock: (transaction.unsigned_transaction().clone(), relock_desc),
+            maker_lock_amount,
+            taker_lock_amount,
+            ..dlc.clone()
+        };
+
+        let remaining_params = RolloverParams::new(
+            self.initial_price,
+            remaining,
+            self.long_leverage,
+            self.short_leverage,
+            self.refund_timelock_in_blocks(),
+            self.initial_tx_fee_rate,
+            self.fee_account.reduce(remaining, self.quantity),
+            FundingFee::calculate(
+                self.initial_price,
+                remaining,
+                self.long_leverage,
+                self.short_leverage,
+                self.initial_funding_rate,
+                0,
+                self.contract_symbol,
+            )?,
+        );
+
+        Ok(PartialSettlement {
+            proposal: PartialSettlementProposal {
+                order_id: self.id,
+                quantity,
+                taker: taker_payout,
+                maker: maker_payout,
+                price: current_price,
+            },
+            transaction,
+            remaining_dlc,
+            remaining_params,
+            position: self.position,
+            contract_symbol: self.contract_symbol,
+            oracle: self.oracle.clone(),
+        })
+    }
+
     pub fn complete_contract_setup(self, dlc: Dlc) -> Result<CfdEvent> {
         if self.version > 1 {
             bail!(
@@ -1249,6 +1614,85 @@ impl Cfd {
         self.event_with_error(EventKind::CollaborativeSettlementFailed, error)
     }
 
+    /// Record the settlement of `quantity` contracts together with the DLC of the remaining
+    /// contracts.
+    pub fn complete_partial_settlement(
+        self,
+        settlement: CollaborativeSettlement,
+        quantity: Contracts,
+        dlc: Dlc,
+    ) -> CfdEvent {
+        if let Err(e) = self.can_settle_collaboratively() {
+            return self.fail_partial_settlement(anyhow!(e));
+        }
+
+        if dlc.lock.0.txid() != settlement.tx.txid() {
+            return self.fail_partial_settlement(anyhow!(
+                "DLC of the remaining contracts is not locked by the settlement transaction"
+            ));
+        }
+
+        self.event(EventKind::PartialSettlementCompleted {
+            spend_tx: settlement.tx,
+            script: settlement.script_pubkey,
+            price: settlement.price,
+            quantity,
+            dlc: Some(dlc),
+        })
+    }
+
+    /// Record the partial settlement transaction and the DLC of the remaining contracts before
+    /// handing our signature to the counterparty.
+    pub fn sign_partial_settlement(
+        self,
+        transaction: &SettlementTransaction,
+        quantity: Contracts,
+        dlc: Dlc,
+    ) -> Result<CfdEvent> {
+        ensure!(
+            self.partial_settlement_proposal.is_some(),
+            "Cannot sign partial settlement without proposal"
+        );
+
+        Ok(self.event(EventKind::PartialSettlementSigned {
+            spend_tx: transaction.unsigned_transaction().clone(),
+            script: transaction.own_script_pubkey().clone(),
+            price: transaction.price(),
+            quantity,
+            dlc,
+        }))
+    }
+
+    /// Adopt the DLC of the remaining contracts once the partial settlement transaction we
+    /// signed is confirmed, even though the protocol did not complete.
+    ///
+    /// Returns `None` if there is no such partial settlement.
+    pub fn handle_partial_settlement_confirmed(self) -> Option<CfdEvent> {
+        let SignedPartialSettlement {
+            spend_tx,
+            script,
+            price,
+            quantity,
+            dlc,
+        } = self.signed_partial_settlement.clone()?;
+
+        Some(self.event(EventKind::PartialSettlementCompleted {
+            spend_tx,
+            script,
+            price,
+            quantity,
+            dlc: Some(dlc),
+        }))
+    }
+
+    pub fn reject_partial_settlement(self, reason: anyhow::Error) -> CfdEvent {
+        self.event_with_error(EventKind::PartialSettlementRejected, reason)
+    }
+
+    pub fn fail_partial_settlement(self, error: anyhow::Error) -> CfdEvent {
+        self.event_with_error(EventKind::PartialSettlementFailed, error)
+    }
+
     /// Given an attestation, find and decrypt the relevant CET.
     ///
     /// In case the Cfd was already closed we return `Ok(None)`, because then the attestation is not
@@ -1354,8 +1798,59 @@ impl Cfd {
         self.event(EventKind::RefundConfirmed)
     }
 
-    pub fn handle_revoke_confirmed(self) -> CfdEvent {
-        self.event(EventKind::RevokeConfirmed)
+    /// Build the punish transaction for a revoked commit transaction published by the
+    /// counterparty.
+    ///
+    /// In case we already reached finality with a transaction spending from lock, or the punish
+    /// transaction was already emitted, we return `Ok(None)`.
+    pub fn handle_revoked_commit_published(
+        self,
+        revoked_commit_tx: &Transaction,
+    ) -> Result<Option<CfdEvent>> {
+        if self.is_final() || self.is_punished() {
+            return Ok(None);
+        }
+
+        let dlc = self.dlc.as_ref().context("CFD does not have a DLC")?;
+        let punish_tx = dlc
+            .signed_punish_tx(self.role, revoked_commit_tx)
+            .context("Failed to build punish transaction")?;
+
+        Ok(Some(
+            self.event(EventKind::RevokedCommitPublished { punish_tx }),
+        ))
+    }
+
+    pub fn handle_punish_confirmed(self) -> CfdEvent {
+        self.event(EventKind::PunishConfirmed)
+    }
+
+    /// Record a child transaction paying for `parent_txid` at `fee_rate`.
+    ///
+    /// Returns `None` if the CFD is already final, in which case there is nothing left to bump.
+    pub fn handle_fee_bumped(
+        self,
+        parent_txid: Txid,
+        child_tx: Transaction,
+        fee_rate: TxFeeRate,
+    ) -> Result<Option<CfdEvent>> {
+        if self.is_final() {
+            return Ok(None);
+        }
+
+        ensure!(
+            child_tx
+                .input
+                .iter()
+                .any(|input| input.previous_output.txid == parent_txid),
+            "Child transaction does not spend from {parent_txid}"
+        );
+
+        Ok(Some(self.event(EventKind::FeeBumped {
+            parent_txid,
+            child_tx,
+            fee_rate,
+        })))
     }
 
     pub fn manual_commit_to_blockchain(&self) -> Result<CfdEvent> {
@@ -1388,12 +1883,15 @@ impl Cfd {
             EventKind::ContractSetupFailed
             | EventKind::RolloverFailed
             | EventKind::CollaborativeSettlementFailed
+            | EventKind::PartialSettlementFailed
             | EventKind::OfferRejected
             | EventKind::RolloverRejected
             | EventKind::CollaborativeSettlementRejected
+            | EventKind::PartialSettlementRejected
             | EventKind::CetConfirmed
             | EventKind::RefundConfirmed
-            | EventKind::RevokeConfirmed => {
+            | EventKind::RevokeConfirmed
+            | EventKind::RevokedCommitPublished { .. } => {
                 tracing::warn!(order_id = %self.id, peer_id=?self.counterparty_peer_id, message);
             }
             _ => {
@@ -1479,10 +1977,18 @@ impl Cfd {
         self.contract_symbol
     }
 
+    pub fn oracle(&self) -> &OracleConfig {
+        &self.oracle
+    }
+
     pub fn opening_fee(&self) -> OpeningFee {
         self.opening_fee
     }
 
+    pub fn dlc(&self) -> Option<&Dlc> {
+        self.dlc.as_ref()
+    }
+
     /// Check whether PeerId matches the one the CFD got created with
     pub fn verify_counterparty_peer_id(&self, peer_id: &PeerId) -> Result<()> {
         match self.counterparty_peer_id() {
@@ -1599,6 +2105,42 @@ impl Cfd {
             CollaborativeSettlementRejected | CollaborativeSettlementFailed => {
                 self.settlement_proposal = None;
             }
+            PartialSettlementStarted { proposal } => {
+                self.partial_settlement_proposal = Some(proposal)
+            }
+            PartialSettlementSigned {
+                spend_tx,
+                script,
+                price,
+                quantity,
+                dlc,
+            } => {
+                self.signed_partial_settlement = Some(SignedPartialSettlement {
+                    spend_tx,
+                    script,
+                    price,
+                    quantity,
+                    dlc,
+                })
+            }
+            PartialSettlementCompleted { quantity, dlc, .. } => {
+                let remaining = self.quantity - quantity;
+
+                self.fee_account = self.fee_account.reduce(remaining, self.quantity);
+                self.quantity = remaining;
+                self.dlc = dlc;
+                self.partial_settlement_proposal = None;
+                self.signed_partial_settlement = None;
+
+                // The remaining contracts are locked by the settlement transaction which is not
+                // confirmed yet
+                self.lock_finality = false;
+            }
+            // A partial settlement transaction we signed can still be published by the
+            // counterparty, so we keep it until it is confirmed
+            PartialSettlementRejected | PartialSettlementFailed => {
+                self.partial_settlement_proposal = None;
+            }
             CetConfirmed => self.cet_finality = true,
             RefundConfirmed => self.refund_finality = true,
             CollaborativeSettlementConfirmed => self.collaborative_settlement_finality = true,
@@ -1615,10 +2157,15 @@ impl Cfd {
                 // commands
             }
             ManualCommit { tx } => self.commit_tx = Some(tx),
+            RevokedCommitPublished { punish_tx } => self.punish_tx = Some(punish_tx),
+            PunishConfirmed => self.punish_finality = true,
+            FeeBumped { .. } => {
+                // fee bumps do not change the state of the CFD, they only help to get the
+                // parent transaction confirmed
+            }
             RevokeConfirmed => {
-                tracing::error!(order_id = %self.id, "Revoked logic not implemented");
-                // TODO: we should punish the other party instead. For now, we pretend we are in
-                // commit finalized and will receive our money based on an old CET.
+                // Legacy event recorded before punishing was implemented, back then we pretended
+                // to be in commit finality and to receive our money based on an old CET.
                 self.commit_finality = true;
             }
         }
@@ -1768,13 +2315,13 @@ pub fn calculate_margin(
     quantity: Contracts,
     leverage: Leverage,
 ) -> Amount {
-    match contract_symbol {
-        ContractSymbol::BtcUsd => inverse::calculate_margin(price, quantity, leverage),
-        ContractSymbol::EthUsd => quanto::calculate_initial_margin(
+    match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => inverse::calculate_margin(price, quantity, leverage),
+        PayoutCurve::Quanto => quanto::calculate_initial_margin(
             price.to_u64(),
             quantity.to_u64(),
             leverage,
-            ETHUSD_MULTIPLIER,
+            contract_symbol.multiplier(),
         ),
     }
 }
@@ -1795,8 +2342,8 @@ pub fn calculate_payout_at_price(
     short_leverage: Leverage,
     fee_account: FeeAccount,
 ) -> Result<Amount> {
-    match contract_symbol {
-        ContractSymbol::BtcUsd => inverse::calculate_payout_at_price(
+    match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => inverse::calculate_payout_at_price(
             initial_price,
             closing_price,
             quantity,
@@ -1804,8 +2351,8 @@ pub fn calculate_payout_at_price(
             short_leverage,
             fee_account,
         ),
-        ContractSymbol::EthUsd => {
-            let multiplier = ETHUSD_MULTIPLIER;
+        PayoutCurve::Quanto => {
+            let multiplier = contract_symbol.multiplier();
 
             let position = fee_account.position;
             let leverage = match position {
@@ -1861,11 +2408,11 @@ pub fn calculate_long_liquidation_price(
     leverage: Leverage,
     contract_symbol: ContractSymbol,
 ) -> Decimal {
-    match contract_symbol {
-        ContractSymbol::BtcUsd => {
+    match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => {
             inverse::calculate_long_liquidation_price(leverage, initial_price).into_decimal()
         }
-        ContractSymbol::EthUsd => {
+        PayoutCurve::Quanto => {
             let initial_price = initial_price.to_u64();
 
             let liquidation_price =
@@ -1882,11 +2429,11 @@ pub fn calculate_short_liquidation_price(
     leverage: Leverage,
     contract_symbol: ContractSymbol,
 ) -> Decimal {
-    match contract_symbol {
-        ContractSymbol::BtcUsd => {
+    match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => {
             inverse::calculate_short_liquidation_price(leverage, initial_price).into_decimal()
         }
-        ContractSymbol::EthUsd => {
+        PayoutCurve::Quanto => {
             let initial_price = initial_price.to_u64();
 
             let liquidation_price =
@@ -2027,6 +2574,10 @@ impl SettlementTransaction {
         self.price
     }
 
+    pub fn own_script_pubkey(&self) -> &Script {
+        &self.own_script_pk
+    }
+
     /// Validate and store counterparty signature
     pub fn recv_counterparty_signature(self, counterparty_signature: Signature) -> Result<Self> {
         let sighash = spending_tx_sighash(
@@ -2068,12 +2619,81 @@ impl SettlementTransaction {
     }
 }
 
+/// Everything needed to settle part of a CFD and to set up the DLC of the remaining contracts.
+#[derive(Clone, Debug)]
+pub struct PartialSettlement {
+    pub proposal: PartialSettlementProposal,
+    pub transaction: SettlementTransaction,
+    /// The DLC of the remaining contracts, locked by the output of `transaction`.
+    ///
+    /// The commit, CETs and refund transaction are still the ones of the entire position and
+    /// have to be replaced before the partial settlement is signed.
+    pub remaining_dlc: Dlc,
+    pub remaining_params: RolloverParams,
+    pub position: Position,
+    pub contract_symbol: ContractSymbol,
+    pub oracle: OracleConfig,
+}
+
 impl Dlc {
     pub fn collab_settlement_transaction(
         &self,
-        payout_maker: Amount,
-        payout_taker: Amount,
+        payout_maker: Amount,
+        payout_taker: Amount,
+        current_price: Price,
+        role: Role,
+    ) -> Result<SettlementTransaction> {
+        let (lock_tx, lock_desc) = &self.lock;
+        let (lock_outpoint, lock_amount) = {
+            let outpoint = lock_tx
+                .outpoint(&lock_desc.script_pubkey())
+                .expect("lock script to be in lock tx");
+            let amount = Amount::from_sat(lock_tx.output[outpoint.vout as usize].value);
+
+            (outpoint, amount)
+        };
+
+        let (tx, sighash) = maia::close_transaction(
+            lock_desc,
+            lock_outpoint,
+            lock_amount,
+            (&self.maker_address, payout_maker),
+            (&self.taker_address, payout_taker),
+            1,
+        )
+        .context("Unable to build collaborative close transaction")?;
+
+        let own_signature = SECP256K1.sign_ecdsa(&sighash, &self.identity);
+
+        let own_pk = bitcoin::PublicKey::new(secp256k1_zkp::PublicKey::from_secret_key(
+            SECP256K1,
+            &self.identity,
+        ));
+
+        Ok(SettlementTransaction {
+            lock_desc: lock_desc.clone(),
+            lock_amount,
+            price: current_price,
+            unsigned_transaction: tx,
+            own_pk,
+            own_script_pk: self.script_pubkey_for(role),
+            own_signature,
+            counterparty_pk: self.identity_counterparty,
+            counterparty_signature: None,
+        })
+    }
+
+    /// Build a transaction that pays out the settled part of the position and locks the
+    /// remaining margins in a new output with `relock_desc`.
+    ///
+    /// Payouts below the dust limit are added to the transaction fee.
+    pub fn partial_settlement_transaction(
+        &self,
+        (payout_maker, payout_taker): (Amount, Amount),
+        (lock_amount_maker, lock_amount_taker): (Amount, Amount),
+        relock_desc: &Descriptor<PublicKey>,
         current_price: Price,
+        fee_rate: TxFeeRate,
         role: Role,
     ) -> Result<SettlementTransaction> {
         let (lock_tx, lock_desc) = &self.lock;
@@ -2086,16 +2706,38 @@ impl Dlc {
             (outpoint, amount)
         };
 
-        let (tx, sighash) = maia::close_transaction(
-            lock_desc,
-            lock_outpoint,
-            lock_amount,
-            (&self.maker_address, payout_maker),
-            (&self.taker_address, payout_taker),
-            1,
-        )
-        .context("Unable to build collaborative close transaction")?;
+        // Both parties pay half of the fee
+        let fee = Amount::from_sat(PARTIAL_SETTLEMENT_TX_VBYTES * fee_rate.to_u32() as u64 / 2);
+
+        let relock = TxOut {
+            value: (lock_amount_maker + lock_amount_taker).as_sat(),
+            script_pubkey: relock_desc.script_pubkey(),
+        };
+        let payouts = [
+            (payout_maker, &self.maker_address),
+            (payout_taker, &self.taker_address),
+        ]
+        .into_iter()
+        .filter_map(|(amount, address)| {
+            let amount = amount.checked_sub(fee)?;
+            (amount.as_sat() >= DUST_LIMIT).then(|| TxOut {
+                value: amount.as_sat(),
+                script_pubkey: address.script_pubkey(),
+            })
+        });
+
+        let tx = Transaction {
+            version: 2,
+            input: vec![TxIn {
+                previous_output: lock_outpoint,
+                ..Default::default()
+            }],
+            lock_time: 0,
+            output: std::iter::once(relock).chain(payouts).collect(),
+        };
 
+        let sighash =
+            spending_tx_sighash(&tx, lock_desc, lock_amount).context("could not obtain sighash")?;
         let own_signature = SECP256K1.sign_ecdsa(&sighash, &self.identity);
 
         let own_pk = bitcoin::PublicKey::new(secp256k1_zkp::PublicKey::from_secret_key(
@@ -2269,6 +2911,45 @@ impl Dlc {
         Ok(signed_cet)
     }
 
+    /// Build and sign the punish transaction for a revoked commit transaction that was published
+    /// by the counterparty.
+    ///
+    /// The punish transaction spends the entire output of the revoked commit transaction to our
+    /// own address. The counterparty's publication secret key is extracted from their signature
+    /// on the revoked commit transaction, which together with their revocation secret key allows
+    /// us to spend the output on our own.
+    pub fn signed_punish_tx(
+        &self,
+        own_role: Role,
+        revoked_commit_tx: &Transaction,
+    ) -> Result<Transaction> {
+        let revoked_commit_txid = revoked_commit_tx.txid();
+        let revoked_commit = self
+            .revoked_commit
+            .iter()
+            .find(|revoked_commit| revoked_commit.txid == revoked_commit_txid)
+            .with_context(|| format!("Unknown revoked commit TXID {revoked_commit_txid}"))?;
+
+        let (commit_descriptor, identity) = revoked_commit.commit_descriptor(self, own_role)?;
+
+        let own_address = match own_role {
+            Role::Maker => &self.maker_address,
+            Role::Taker => &self.taker_address,
+        };
+
+        let punish_tx = maia::punish_transaction(
+            &commit_descriptor,
+            own_address,
+            revoked_commit.encsig_ours,
+            identity,
+            revoked_commit.revocation_sk_theirs,
+            revoked_commit.publication_pk_theirs,
+            revoked_commit_tx,
+        )?;
+
+        Ok(punish_tx)
+    }
+
     /// All the oracle event IDs associated with the DLC.
     ///
     /// This includes:
@@ -2324,10 +3005,37 @@ impl Dlc {
     }
 
     pub fn identity_pk(&self) -> PublicKey {
-        PublicKey::new(secp256k1_zkp::PublicKey::from_secret_key(
-            SECP256K1,
-            &self.identity,
-        ))
+        identity_pk(&self.identity)
+    }
+
+    /// The identity keys that lock the remaining contracts once a partial settlement spends the
+    /// current lock output.
+    fn partial_settlement_identities(&self) -> Result<(SecretKey, PublicKey)> {
+        let (lock_tx, lock_desc) = &self.lock;
+        let lock_outpoint = lock_tx.outpoint(&lock_desc.script_pubkey())?;
+
+        let tweak = identity_tweak(lock_outpoint)?;
+
+        tweak_identities(self.identity, self.identity_counterparty, tweak)
+    }
+
+    /// The identity keys of the lock output that was spent by the partial settlement that
+    /// created the current lock output.
+    ///
+    /// Only meaningful if the current lock transaction is a partial settlement transaction.
+    fn identities_before_partial_settlement(&self) -> Result<(SecretKey, PublicKey)> {
+        let spent_lock = self
+            .lock
+            .0
+            .input
+            .first()
+            .context("Lock transaction without inputs")?
+            .previous_output;
+
+        let mut tweak = identity_tweak(spent_lock)?;
+        tweak.negate_assign();
+
+        tweak_identities(self.identity, self.identity_counterparty, tweak)
     }
 
     pub fn maker_identity_pk(&self, own_role: Role) -> PublicKey {
@@ -2345,6 +3053,43 @@ impl Dlc {
     }
 }
 
+fn identity_pk(identity: &SecretKey) -> PublicKey {
+    PublicKey::new(secp256k1_zkp::PublicKey::from_secret_key(
+        SECP256K1, identity,
+    ))
+}
+
+/// The tweak added to both identity keys when a partial settlement spends `spent_lock`.
+///
+/// Both parties derive it from the spent lock output, so each of them can compute the
+/// counterparty's new identity public key without another round of communication.
+fn identity_tweak(spent_lock: OutPoint) -> Result<SecretKey> {
+    let mut preimage = b"itchysats/partial-settlement/identity".to_vec();
+    preimage.extend(consensus::serialize(&spent_lock));
+
+    let tweak = sha256::Hash::hash(&preimage);
+
+    SecretKey::from_slice(&tweak.into_inner()).context("Invalid identity tweak")
+}
+
+fn tweak_identities(
+    mut identity: SecretKey,
+    mut identity_counterparty: PublicKey,
+    tweak: SecretKey,
+) -> Result<(SecretKey, PublicKey)> {
+    let tweak = tweak.secret_bytes();
+
+    identity
+        .add_assign(&tweak)
+        .context("Failed to tweak own identity")?;
+    identity_counterparty
+        .inner
+        .add_exp_assign(SECP256K1, &tweak)
+        .context("Failed to tweak counterparty identity")?;
+
+    Ok((identity, identity_counterparty))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SignCetError {
     #[error("Attestation {id} is irrelevant for DLC with lock TX {txid}")]
@@ -2374,6 +3119,13 @@ pub struct RevokedCommit {
     pub revocation_sk_ours: Option<SecretKey>,
     pub revocation_sk_theirs: SecretKey,
     pub publication_pk_theirs: PublicKey,
+
+    /// Our own publication key for this commit tx
+    ///
+    /// Together with the revocation keys this is needed to reconstruct the descriptor of the
+    /// revoked commit transaction's output, which is required to punish.
+    /// Revoked commit transactions recorded before this was stored cannot be punished.
+    pub publication_pk_ours: Option<PublicKey>,
     // To monitor revoked commit transaction
     pub txid: Txid,
     pub script_pubkey: Script,
@@ -2393,6 +3145,58 @@ pub struct RevokedCommit {
     pub complete_fee: Option<CompleteFee>,
 }
 
+impl RevokedCommit {
+    /// Reconstruct the descriptor of the revoked commit transaction's output, together with our
+    /// identity key that it was built with.
+    ///
+    /// Commit transactions revoked by a partial settlement spend the previous lock output, which
+    /// is locked by the identity keys from before the partial settlement.
+    fn commit_descriptor(
+        &self,
+        dlc: &Dlc,
+        own_role: Role,
+    ) -> Result<(Descriptor<PublicKey>, SecretKey)> {
+        let revocation_sk_ours = self
+            .revocation_sk_ours
+            .context("Missing own revocation sk")?;
+        let publication_pk_ours = self
+            .publication_pk_ours
+            .context("Missing own publication pk")?;
+
+        let identities = std::iter::once((dlc.identity, dlc.identity_counterparty))
+            .chain(dlc.identities_before_partial_settlement().ok());
+
+        for (identity, identity_counterparty) in identities {
+            let ours = (
+                identity_pk(&identity),
+                PublicKey::new(revocation_sk_ours.to_public_key()),
+                publication_pk_ours,
+            );
+            let theirs = (
+                identity_counterparty,
+                PublicKey::new(self.revocation_sk_theirs.to_public_key()),
+                self.publication_pk_theirs,
+            );
+
+            let (maker, taker) = match own_role {
+                Role::Maker => (ours, theirs),
+                Role::Taker => (theirs, ours),
+            };
+
+            let descriptor = maia::commit_descriptor(maker, taker);
+
+            if descriptor.script_pubkey() == self.script_pubkey {
+                return Ok((descriptor, identity));
+            }
+        }
+
+        bail!(
+            "Reconstructed wrong descriptor for revoked commit TX {}",
+            self.txid
+        )
+    }
+}
+
 /// Used when transactions (e.g. collaborative close) are recorded as a part of
 /// CfdState in the cases when we can't solely rely on state transition
 /// timestamp as it could have occurred for different reasons (like a new
@@ -2442,6 +3246,7 @@ impl CollaborativeSettlement {
 
 #[cfg(test)]
 mod tests {
+    use crate::Catalogue;
     use crate::Percent;
 
     use super::*;
@@ -2451,7 +3256,6 @@ mod tests {
     use bdk::bitcoin::SignedAmount;
     use bdk_ext::keypair;
     use bdk_ext::SecretKeyExt;
-    use maia::lock_descriptor;
     use proptest::prelude::*;
     use rand::thread_rng;
     use rust_decimal_macros::dec;
@@ -2466,7 +3270,7 @@ mod tests {
         let quantity = Contracts::new(40000);
         let leverage = Leverage::new(1).unwrap();
 
-        let long_margin = calculate_margin(ContractSymbol::BtcUsd, price, quantity, leverage);
+        let long_margin = calculate_margin(ContractSymbol::BTC_USD, price, quantity, leverage);
 
         assert_eq!(long_margin, Amount::ONE_BTC);
     }
@@ -2477,7 +3281,7 @@ mod tests {
         let quantity = Contracts::new(40000);
         let leverage = Leverage::new(10).unwrap();
 
-        let long_margin = calculate_margin(ContractSymbol::BtcUsd, price, quantity, leverage);
+        let long_margin = calculate_margin(ContractSymbol::BTC_USD, price, quantity, leverage);
 
         assert_eq!(long_margin, Amount::from_btc(0.1).unwrap());
     }
@@ -2489,7 +3293,8 @@ mod tests {
         let price = Price::new(dec!(40000)).unwrap();
         let quantity = Contracts::new(40000);
 
-        let short_margin = calculate_margin(ContractSymbol::BtcUsd, price, quantity, Leverage::ONE);
+        let short_margin =
+            calculate_margin(ContractSymbol::BTC_USD, price, quantity, Leverage::ONE);
 
         assert_eq!(short_margin, Amount::ONE_BTC);
     }
@@ -2499,7 +3304,8 @@ mod tests {
         let price = Price::new(dec!(40000)).unwrap();
         let quantity = Contracts::new(20000);
 
-        let short_margin = calculate_margin(ContractSymbol::BtcUsd, price, quantity, Leverage::ONE);
+        let short_margin =
+            calculate_margin(ContractSymbol::BTC_USD, price, quantity, Leverage::ONE);
 
         assert_eq!(short_margin, Amount::from_btc(0.5).unwrap());
     }
@@ -2509,7 +3315,8 @@ mod tests {
         let price = Price::new(dec!(40000)).unwrap();
         let quantity = Contracts::new(80000);
 
-        let short_margin = calculate_margin(ContractSymbol::BtcUsd, price, quantity, Leverage::ONE);
+        let short_margin =
+            calculate_margin(ContractSymbol::BTC_USD, price, quantity, Leverage::ONE);
 
         assert_eq!(short_margin, Amount::from_btc(2.0).unwrap());
     }
@@ -2537,7 +3344,7 @@ mod tests {
         let empty_fee_short = FeeAccount::new(Position::Short, Role::Maker);
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(10_000)).unwrap(),
             Price::new(dec!(10_000)).unwrap(),
             Contracts::new(10_000),
@@ -2550,7 +3357,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(10_000)).unwrap(),
             Price::new(dec!(10_000)).unwrap(),
             Contracts::new(10_000),
@@ -2566,7 +3373,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(10_000)).unwrap(),
             Price::new(dec!(10_000)).unwrap(),
             Contracts::new(10_000),
@@ -2582,7 +3389,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(10_000)).unwrap(),
             Price::new(dec!(20_000)).unwrap(),
             Contracts::new(10_000),
@@ -2595,7 +3402,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(9_000)).unwrap(),
             Price::new(dec!(6_000)).unwrap(),
             Contracts::new(9_000),
@@ -2608,7 +3415,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(10_000)).unwrap(),
             Price::new(dec!(5_000)).unwrap(),
             Contracts::new(10_000),
@@ -2621,7 +3428,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(50_400)).unwrap(),
             Price::new(dec!(60_000)).unwrap(),
             Contracts::new(10_000),
@@ -2634,7 +3441,7 @@ mod tests {
         );
 
         assert_profit_loss_values(
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
             Price::new(dec!(50_400)).unwrap(),
             Price::new(dec!(60_000)).unwrap(),
             Contracts::new(10_000),
@@ -2694,7 +3501,7 @@ mod tests {
         let closing_price = Price::new(dec!(16_000)).unwrap();
         let quantity = Contracts::new(10_000);
         let leverage = Leverage::ONE; // same leverage for both parties
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
 
         let opening_fee = OpeningFee::new(Amount::from_sat(500));
         let funding_fee = FundingFee::new(
@@ -2753,7 +3560,7 @@ mod tests {
         let quantity = Contracts::new(10_000);
         let long_leverage = Leverage::TWO;
         let short_leverage = Leverage::ONE;
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
 
         let opening_fee = OpeningFee::new(Amount::from_sat(500));
         let funding_fee = FundingFee::new(
@@ -2997,6 +3804,39 @@ mod tests {
         assert_eq!(cannot_roll_over, CannotRollover::TooRecent)
     }
 
+    #[test]
+    fn given_cfd_was_rolled_over_in_previous_hour_then_rollover_on_request() {
+        let cfd = Cfd::dummy_taker_long();
+        let contract_symbol = cfd.contract_symbol;
+        let settlement_event_id = BitMexPriceEventId::with_20_digits(
+            datetime!(2021-11-19 10:00:00).assume_utc(),
+            contract_symbol,
+        );
+        let cfd = cfd.dummy_open(settlement_event_id);
+
+        let (_, from_settlement_event_id) = cfd
+            .can_rollover_taker(datetime!(2021-11-18 10:30:00).assume_utc())
+            .unwrap();
+
+        assert_eq!(from_settlement_event_id, settlement_event_id)
+    }
+
+    #[test]
+    fn given_cfd_was_rolled_over_in_same_hour_then_no_rollover_on_request() {
+        let cfd = Cfd::dummy_taker_long();
+        let contract_symbol = cfd.contract_symbol;
+        let cfd = cfd.dummy_open(BitMexPriceEventId::with_20_digits(
+            datetime!(2021-11-19 10:00:00).assume_utc(),
+            contract_symbol,
+        ));
+
+        let cannot_roll_over = cfd
+            .can_rollover_taker(datetime!(2021-11-18 09:30:00).assume_utc())
+            .unwrap_err();
+
+        assert_eq!(cannot_roll_over, CannotRollover::NotExtended)
+    }
+
     #[test]
     fn given_cfd_not_locked_then_no_rollover() {
         let cfd = Cfd::dummy_not_open_yet();
@@ -3010,7 +3850,22 @@ mod tests {
     fn given_cfd_has_attestation_then_no_rollover() {
         let cfd = Cfd::dummy_with_attestation(BitMexPriceEventId::with_20_digits(
             datetime!(2021-11-19 10:00:00).assume_utc(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+        ));
+
+        let cannot_roll_over = cfd.can_rollover().unwrap_err();
+
+        assert!(matches!(cannot_roll_over, CannotRollover::Closed))
+    }
+
+    #[test]
+    fn given_revoked_commit_published_then_no_rollover() {
+        let cfd = Cfd::dummy_taker_long().dummy_open(dummy_event_id());
+        let cfd = cfd.clone().apply(CfdEvent::new(
+            cfd.id,
+            EventKind::RevokedCommitPublished {
+                punish_tx: dummy_transaction(),
+            },
         ));
 
         let cannot_roll_over = cfd.can_rollover().unwrap_err();
@@ -3018,11 +3873,81 @@ mod tests {
         assert!(matches!(cannot_roll_over, CannotRollover::Closed))
     }
 
+    #[test]
+    fn given_punish_confirmed_then_cfd_is_final() {
+        let cfd = Cfd::dummy_taker_long().dummy_open(dummy_event_id());
+        let cfd = cfd.clone().apply(CfdEvent::new(
+            cfd.id,
+            EventKind::RevokedCommitPublished {
+                punish_tx: dummy_transaction(),
+            },
+        ));
+        let punish_confirmed = cfd.clone().handle_punish_confirmed();
+        let cfd = cfd.apply(punish_confirmed);
+
+        assert!(cfd.is_final());
+        assert!(cfd.is_closed());
+    }
+
+    #[test]
+    fn given_child_spends_parent_then_fee_bump_recorded() {
+        let cfd = Cfd::dummy_taker_long().dummy_open(dummy_event_id());
+        let parent = dummy_transaction();
+        let child = dummy_child_transaction(&parent);
+
+        let event = cfd
+            .handle_fee_bumped(parent.txid(), child.clone(), TxFeeRate::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            event.event,
+            EventKind::FeeBumped {
+                parent_txid: parent.txid(),
+                child_tx: child,
+                fee_rate: TxFeeRate::default()
+            }
+        );
+    }
+
+    #[test]
+    fn given_child_does_not_spend_parent_then_cannot_record_fee_bump() {
+        let cfd = Cfd::dummy_taker_long().dummy_open(dummy_event_id());
+        let parent = dummy_transaction();
+
+        let result =
+            cfd.handle_fee_bumped(parent.txid(), dummy_transaction(), TxFeeRate::default());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_cfd_final_then_no_fee_bump() {
+        let cfd = Cfd::dummy_final(dummy_event_id());
+        let parent = dummy_transaction();
+        let child = dummy_child_transaction(&parent);
+
+        let event = cfd
+            .handle_fee_bumped(parent.txid(), child, TxFeeRate::default())
+            .unwrap();
+
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn given_unknown_revoked_commit_then_cannot_punish() {
+        let cfd = Cfd::dummy_taker_long().dummy_open(dummy_event_id());
+
+        let result = cfd.handle_revoked_commit_published(&dummy_transaction());
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn given_cfd_final_then_no_rollover() {
         let cfd = Cfd::dummy_final(BitMexPriceEventId::with_20_digits(
             datetime!(2021-11-19 10:00:00).assume_utc(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
         ));
 
         let cannot_roll_over = cfd.can_rollover().unwrap_err();
@@ -3040,7 +3965,7 @@ mod tests {
             Leverage::ONE,
             funding_rate,
             SETTLEMENT_INTERVAL.whole_hours(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
         )
         .unwrap();
 
@@ -3333,6 +4258,203 @@ mod tests {
         assert!(result_maker.is_err(), "When having commit tx available we should not be able to trigger collaborative settlement");
     }
 
+    #[test]
+    fn given_open_cfd_when_partial_settlement_then_both_parties_agree_and_quantity_is_reduced() {
+        let quantity = Contracts::new(1000);
+        let settled = Contracts::new(300);
+        let price = Price::new(dec!(60000)).unwrap();
+        let order_id = OrderId::default();
+
+        let taker_keys = new_keypair();
+        let maker_keys = new_keypair();
+
+        let taker_long = Cfd::dummy_taker_long()
+            .with_id(order_id)
+            .with_quantity(quantity)
+            .dummy_open(dummy_event_id())
+            .with_lock(taker_keys, maker_keys);
+        let maker_short = Cfd::dummy_maker_short()
+            .with_id(order_id)
+            .with_quantity(quantity)
+            .dummy_open(dummy_event_id())
+            .with_lock(taker_keys, maker_keys);
+
+        let (taker_event, taker_settlement) = taker_long
+            .clone()
+            .start_partial_settlement_taker(price, settled, N_PAYOUTS)
+            .unwrap();
+        let (maker_event, maker_settlement) = maker_short
+            .clone()
+            .start_partial_settlement_maker(
+                price,
+                settled,
+                N_PAYOUTS,
+                taker_settlement.transaction.unsigned_transaction(),
+            )
+            .unwrap();
+
+        assert_eq!(taker_event.event, maker_event.event);
+        assert_eq!(
+            taker_settlement.remaining_dlc.lock.0.output[0].value,
+            (maker_settlement.remaining_dlc.maker_lock_amount
+                + maker_settlement.remaining_dlc.taker_lock_amount)
+                .as_sat()
+        );
+
+        let taker_settlement_tx = taker_settlement
+            .transaction
+            .clone()
+            .recv_counterparty_signature(maker_settlement.transaction.own_signature())
+            .unwrap()
+            .finalize()
+            .unwrap();
+        let taker_long = taker_long.apply(taker_event);
+        let event = taker_long.clone().complete_partial_settlement(
+            taker_settlement_tx,
+            settled,
+            taker_settlement.remaining_dlc.clone(),
+        );
+        assert!(matches!(
+            event.event,
+            EventKind::PartialSettlementCompleted { .. }
+        ));
+        let taker_long = taker_long.apply(event);
+
+        assert_eq!(taker_long.quantity, Contracts::new(700));
+        assert!(!taker_long.is_in_collaborative_settlement());
+        assert!(!taker_long.lock_finality);
+    }
+
+    #[test]
+    fn given_partial_settlement_then_remaining_contracts_are_locked_with_new_script() {
+        let quantity = Contracts::new(1000);
+        let settled = Contracts::new(300);
+        let price = Price::new(dec!(60000)).unwrap();
+        let order_id = OrderId::default();
+
+        let taker_keys = new_keypair();
+        let maker_keys = new_keypair();
+
+        let taker_long = Cfd::dummy_taker_long()
+            .with_id(order_id)
+            .with_quantity(quantity)
+            .dummy_open(dummy_event_id())
+            .with_lock(taker_keys, maker_keys);
+        let maker_short = Cfd::dummy_maker_short()
+            .with_id(order_id)
+            .with_quantity(quantity)
+            .dummy_open(dummy_event_id())
+            .with_lock(taker_keys, maker_keys);
+        let spent_lock_script = taker_long.dlc.as_ref().unwrap().lock.1.script_pubkey();
+
+        let (_, taker_settlement) = taker_long
+            .start_partial_settlement_taker(price, settled, N_PAYOUTS)
+            .unwrap();
+        let (_, maker_settlement) = maker_short
+            .start_partial_settlement_maker(
+                price,
+                settled,
+                N_PAYOUTS,
+                taker_settlement.transaction.unsigned_transaction(),
+            )
+            .unwrap();
+
+        let taker_dlc = taker_settlement.remaining_dlc;
+        let maker_dlc = maker_settlement.remaining_dlc;
+        let relock_script = taker_dlc.lock.1.script_pubkey();
+
+        assert_ne!(relock_script, spent_lock_script);
+        assert_eq!(relock_script, maker_dlc.lock.1.script_pubkey());
+        assert_eq!(taker_dlc.lock.0.output[0].script_pubkey, relock_script);
+        assert_eq!(taker_dlc.identity_pk(), maker_dlc.identity_counterparty);
+        assert_eq!(maker_dlc.identity_pk(), taker_dlc.identity_counterparty);
+        assert_eq!(
+            taker_dlc.identities_before_partial_settlement().unwrap(),
+            (taker_keys.0, maker_keys.1)
+        );
+    }
+
+    #[test]
+    fn given_signed_partial_settlement_when_counterparty_drops_connection_then_adopt_it_once_confirmed(
+    ) {
+        let quantity = Contracts::new(1000);
+        let settled = Contracts::new(300);
+        let price = Price::new(dec!(60000)).unwrap();
+
+        let taker_long = Cfd::dummy_taker_long()
+            .with_quantity(quantity)
+            .dummy_open(dummy_event_id())
+            .with_lock(new_keypair(), new_keypair());
+
+        let (event, settlement) = taker_long
+            .clone()
+            .start_partial_settlement_taker(price, settled, N_PAYOUTS)
+            .unwrap();
+        let taker_long = taker_long.apply(event);
+        assert!(
+            taker_long
+                .clone()
+                .handle_partial_settlement_confirmed()
+                .is_none(),
+            "Nothing to adopt before we signed"
+        );
+
+        let event = taker_long
+            .clone()
+            .sign_partial_settlement(
+                &settlement.transaction,
+                settled,
+                settlement.remaining_dlc.clone(),
+            )
+            .unwrap();
+        let taker_long = taker_long.apply(event);
+
+        // The maker received our signature and drops the connection instead of sending theirs
+        let event = taker_long
+            .clone()
+            .fail_partial_settlement(anyhow!("End of stream while receiving Signature"));
+        let taker_long = taker_long.apply(event);
+
+        assert_eq!(taker_long.quantity, quantity);
+        assert_eq!(
+            taker_long.can_rollover(),
+            Err(CannotRollover::InCollaborativeSettlement)
+        );
+
+        let event = taker_long
+            .clone()
+            .handle_partial_settlement_confirmed()
+            .expect("signed partial settlement to be kept");
+        assert!(matches!(
+            event.event,
+            EventKind::PartialSettlementCompleted { .. }
+        ));
+        let taker_long = taker_long.apply(event);
+
+        assert_eq!(taker_long.quantity, Contracts::new(700));
+        assert_eq!(taker_long.dlc, Some(settlement.remaining_dlc));
+        assert!(taker_long.handle_partial_settlement_confirmed().is_none());
+    }
+
+    #[test]
+    fn given_open_cfd_when_partially_settling_entire_quantity_then_error() {
+        let taker_keys = new_keypair();
+        let maker_keys = new_keypair();
+
+        let taker_long = Cfd::dummy_taker_long()
+            .with_quantity(Contracts::new(100))
+            .dummy_open(dummy_event_id())
+            .with_lock(taker_keys, maker_keys);
+
+        let result = taker_long.start_partial_settlement_taker(
+            Price::new(dec!(60000)).unwrap(),
+            Contracts::new(100),
+            N_PAYOUTS,
+        );
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn given_no_rollover_then_no_rollover_fee() {
         let quantity = Contracts::new(10);
@@ -3498,9 +4620,9 @@ mod tests {
     fn given_current_settlement_in_12_hours_and_candidate_in_19_then_7_hour_extension() {
         for now in common_time_boundaries() {
             let from_event_id =
-                BitMexPriceEventId::with_20_digits(now + 12.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now + 12.hours(), ContractSymbol::BTC_USD);
             let to_event_id =
-                BitMexPriceEventId::with_20_digits(now + 19.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now + 19.hours(), ContractSymbol::BTC_USD);
 
             let taker = Cfd::dummy_taker_long().dummy_open(from_event_id);
             let maker = Cfd::dummy_maker_short().dummy_open(from_event_id);
@@ -3533,12 +4655,12 @@ mod tests {
 
             let to_event_id = BitMexPriceEventId::with_20_digits(
                 now + settlement_interval.hours(),
-                ContractSymbol::BtcUsd,
+                ContractSymbol::BTC_USD,
             );
 
             for hour in 0..settlement_interval {
                 let from_event_id =
-                    BitMexPriceEventId::with_20_digits(now + hour.hours(), ContractSymbol::BtcUsd);
+                    BitMexPriceEventId::with_20_digits(now + hour.hours(), ContractSymbol::BTC_USD);
 
                 let taker = Cfd::dummy_taker_long().dummy_open(from_event_id);
                 let maker = Cfd::dummy_maker_short().dummy_open(from_event_id);
@@ -3569,13 +4691,13 @@ mod tests {
     ) {
         for now in common_time_boundaries() {
             let event_id_1_hour_ago =
-                BitMexPriceEventId::with_20_digits(now - 1.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now - 1.hours(), ContractSymbol::BTC_USD);
 
             let taker = Cfd::dummy_taker_long().dummy_open(event_id_1_hour_ago);
             let maker = Cfd::dummy_maker_short().dummy_open(event_id_1_hour_ago);
 
             let event_id_in_24_hours =
-                BitMexPriceEventId::with_20_digits(now - 24.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now - 24.hours(), ContractSymbol::BTC_USD);
 
             assert_eq!(
                 taker
@@ -3610,9 +4732,9 @@ mod tests {
     ) {
         for now in common_time_boundaries() {
             let from_event_id =
-                BitMexPriceEventId::with_20_digits(now + 2.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now + 2.hours(), ContractSymbol::BTC_USD);
             let earlier_event_id =
-                BitMexPriceEventId::with_20_digits(now + 1.hours(), ContractSymbol::BtcUsd);
+                BitMexPriceEventId::with_20_digits(now + 1.hours(), ContractSymbol::BTC_USD);
 
             let taker = Cfd::dummy_taker_long().dummy_open(from_event_id);
             let maker = Cfd::dummy_maker_short().dummy_open(from_event_id);
@@ -3706,7 +4828,7 @@ mod tests {
                 leverage,
                 funding_rate,
                 SETTLEMENT_INTERVAL.whole_hours(),
-                ContractSymbol::BtcUsd,
+                ContractSymbol::BTC_USD,
             )
                 .unwrap();
             let funding_fee_for_one_hour = FundingFee::calculate(
@@ -3716,7 +4838,7 @@ mod tests {
                 leverage,
                 funding_rate,
                 1,
-                ContractSymbol::BtcUsd,
+                ContractSymbol::BTC_USD,
             )
                 .unwrap();
             let fee_account = FeeAccount::new(Position::Long, Role::Taker);
@@ -3751,7 +4873,7 @@ mod tests {
     fn given_order_creation_timestamp_outdated_then_order_outdated() {
         let creation_timestamp = Timestamp::now();
         let order =
-            Offer::dummy_short(ContractSymbol::BtcUsd).with_creation_timestamp(creation_timestamp);
+            Offer::dummy_short(ContractSymbol::BTC_USD).with_creation_timestamp(creation_timestamp);
 
         let now =
             OffsetDateTime::now_utc() + Duration::seconds(Offer::OUTDATED_AFTER_MINS * 60 + 1);
@@ -3763,7 +4885,7 @@ mod tests {
     fn given_order_creation_timestamp_not_outdated_then_order_not_outdated() {
         let creation_timestamp = Timestamp::now();
         let order =
-            Offer::dummy_short(ContractSymbol::BtcUsd).with_creation_timestamp(creation_timestamp);
+            Offer::dummy_short(ContractSymbol::BTC_USD).with_creation_timestamp(creation_timestamp);
 
         let now =
             OffsetDateTime::now_utc() + Duration::seconds(Offer::OUTDATED_AFTER_MINS * 60 - 1);
@@ -3778,7 +4900,7 @@ mod tests {
         // --|---------|<--------|--------------------------------->|--
         //             now
 
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         let order = Offer::dummy_short(contract_symbol).with_oracle_event_id(
             BitMexPriceEventId::with_20_digits(
                 datetime!(2021-11-19 10:00:00).assume_utc(),
@@ -3798,7 +4920,7 @@ mod tests {
         // --|---------|<--------|--------------------------------->|--
         //                       now
 
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         let order = Offer::dummy_short(contract_symbol).with_oracle_event_id(
             BitMexPriceEventId::with_20_digits(
                 datetime!(2021-11-19 10:00:00).assume_utc(),
@@ -3818,7 +4940,7 @@ mod tests {
         // --|---------|<--------|--------------------------------->|--
         //   now
 
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         let order = Offer::dummy_short(contract_symbol).with_oracle_event_id(
             BitMexPriceEventId::with_20_digits(
                 datetime!(2021-11-19 10:00:00).assume_utc(),
@@ -3838,7 +4960,7 @@ mod tests {
         // --|---------|<--------|--------------------------------->|--
         //   now
 
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         let order = Offer::dummy_short(contract_symbol).with_oracle_event_id(
             BitMexPriceEventId::with_20_digits(
                 datetime!(2021-11-19 10:00:00).assume_utc(),
@@ -3861,7 +4983,7 @@ mod tests {
         // --|---------|<--------|--------------------------------->|--
         //                       now
 
-        let contract_symbol = ContractSymbol::BtcUsd;
+        let contract_symbol = ContractSymbol::BTC_USD;
         let order = Offer::dummy_short(contract_symbol).with_oracle_event_id(
             BitMexPriceEventId::with_20_digits(
                 datetime!(2021-11-19 10:00:00).assume_utc(),
@@ -4209,7 +5331,7 @@ mod tests {
         }
 
         fn dummy_with_attestation(event_id: BitMexPriceEventId) -> Self {
-            let contract_symbol = event_id.contract_symbol();
+            let contract_symbol = dummy_contract_symbol(event_id);
             let cfd = Cfd::from_order(
                 OrderId::default(),
                 &Offer::dummy_short(contract_symbol),
@@ -4226,7 +5348,7 @@ mod tests {
         }
 
         fn dummy_final(event_id: BitMexPriceEventId) -> Self {
-            let contract_symbol = event_id.contract_symbol();
+            let contract_symbol = dummy_contract_symbol(event_id);
             let cfd = Cfd::from_order(
                 OrderId::default(),
                 &Offer::dummy_short(contract_symbol),
@@ -4277,11 +5399,12 @@ mod tests {
                 vec![Leverage::TWO],
                 contract_symbol,
                 LotSize::new(100),
+                OracleConfig::olivia(),
             )
         }
 
         fn dummy_btc_usd_short() -> Self {
-            Self::dummy_short(ContractSymbol::BtcUsd)
+            Self::dummy_short(ContractSymbol::BTC_USD)
         }
 
         fn with_price(mut self, price: Price) -> Self {
@@ -4397,7 +5520,7 @@ mod tests {
             dummy_cet_with_zero_price_range.insert(
                 BitMexPriceEventId::with_20_digits(
                     OffsetDateTime::now_utc(),
-                    ContractSymbol::BtcUsd,
+                    ContractSymbol::BTC_USD,
                 ),
                 vec![Cet {
                     maker_amount: Amount::from_sat(0),
@@ -4442,6 +5565,18 @@ mod tests {
         dummy_partially_signed_transaction().extract_tx()
     }
 
+    fn dummy_child_transaction(parent: &Transaction) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: bitcoin::OutPoint::new(parent.txid(), 0),
+                ..Default::default()
+            }],
+            output: vec![],
+        }
+    }
+
     pub fn dummy_partially_signed_transaction() -> PartiallySignedTransaction {
         // very simple dummy psbt that does not contain anything
         // pulled in from github.com-1ecc6299db9ec823/bitcoin-0.27.1/src/util/psbt/mod.rs:238
@@ -4472,8 +5607,17 @@ mod tests {
         Some(PeerId::random())
     }
 
+    /// The built-in contract symbol whose price `event_id` refers to.
+    fn dummy_contract_symbol(event_id: BitMexPriceEventId) -> ContractSymbol {
+        Catalogue::default()
+            .symbols()
+            .into_iter()
+            .find(|symbol| olivia::IndexPrice::from(*symbol) == event_id.index_price())
+            .expect("event to refer to a built-in contract symbol")
+    }
+
     pub fn dummy_event_id() -> BitMexPriceEventId {
-        BitMexPriceEventId::with_20_digits(OffsetDateTime::now_utc(), ContractSymbol::BtcUsd)
+        BitMexPriceEventId::with_20_digits(OffsetDateTime::now_utc(), ContractSymbol::BTC_USD)
     }
 
     fn extract_payout_amount(tx: Transaction, script: Script) -> Amount {
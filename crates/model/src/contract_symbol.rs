@@ -0,0 +1,503 @@
+//! The catalogue of contract symbols.
+//!
+//! BTCUSD and ETHUSD are built in. Further contract symbols are defined in a [`Catalogue`] which is
+//! built at startup and handed to the components that need to look contract symbols up by name.
+//!
+//! A contract symbol carries its whole definition, so that CFDs, offers and events can be read
+//! back without a catalogue. Contract symbols other than the built-in ones are serialized with
+//! their definition for the same reason.
+
+use crate::payout_curve::ETHUSD_MULTIPLIER;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// The maximum length of the names in the definition of a contract symbol.
+const MAX_NAME_LEN: usize = 16;
+
+/// Separates the fields of the wire name of contract symbols that are not built in.
+const WIRE_NAME_SEPARATOR: &str = ":";
+
+/// How the payout of a contract is derived from the price of its underlying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutCurve {
+    /// Margin and payout in BTC for a contract quoted in USD per BTC, e.g. BTCUSD.
+    Inverse,
+    /// Margin and payout in BTC for a contract on another underlying, converted at a fixed
+    /// multiplier, e.g. ETHUSD.
+    Quanto,
+}
+
+impl fmt::Display for PayoutCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayoutCurve::Inverse => f.write_str("inverse"),
+            PayoutCurve::Quanto => f.write_str("quanto"),
+        }
+    }
+}
+
+impl FromStr for PayoutCurve {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inverse" => Ok(PayoutCurve::Inverse),
+            "quanto" => Ok(PayoutCurve::Quanto),
+            _ => bail!("Unknown payout curve {s}"),
+        }
+    }
+}
+
+/// An upper case, alphanumeric name stored inline, so that contract symbols can be `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Name {
+    bytes: [u8; MAX_NAME_LEN],
+    len: u8,
+}
+
+impl Name {
+    /// Panics if `name` is not a valid name, to be used for constants only.
+    const fn from_static(name: &'static str) -> Self {
+        let name = name.as_bytes();
+        assert!(!name.is_empty() && name.len() <= MAX_NAME_LEN);
+
+        let mut bytes = [0; MAX_NAME_LEN];
+        let mut i = 0;
+        while i < name.len() {
+            assert!(name[i].is_ascii_uppercase() || name[i].is_ascii_digit());
+            bytes[i] = name[i];
+            i += 1;
+        }
+
+        Self {
+            bytes,
+            len: name.len() as u8,
+        }
+    }
+
+    /// Validate `name` and convert it to upper case.
+    pub(crate) fn new(name: &str) -> Result<Self> {
+        ensure!(
+            !name.is_empty()
+                && name.len() <= MAX_NAME_LEN
+                && name.chars().all(|c| c.is_ascii_alphanumeric()),
+            "Invalid name {name:?}, expected 1 to {MAX_NAME_LEN} alphanumeric characters"
+        );
+
+        let mut bytes = [0; MAX_NAME_LEN];
+        bytes[..name.len()].copy_from_slice(name.to_ascii_uppercase().as_bytes());
+
+        Ok(Self {
+            bytes,
+            len: name.len() as u8,
+        })
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).expect("names to be ASCII")
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The definition of a contract symbol as given in the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractConfig {
+    pub symbol: String,
+    pub payout_curve: PayoutCurve,
+    /// Required for quanto contracts.
+    #[serde(default)]
+    pub multiplier: Option<Decimal>,
+    pub oracle_index: String,
+    pub feed_instrument: String,
+}
+
+/// The contract symbols known to the process: the built-in ones and those defined in the config.
+///
+/// The default catalogue only knows the built-in contract symbols.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalogue {
+    /// Contract symbols defined in addition to the built-in ones.
+    contracts: Vec<ContractSymbol>,
+}
+
+impl Catalogue {
+    /// Validate the definitions of contract symbols offered in addition to the built-in ones.
+    ///
+    /// Defining a contract symbol again with the same definition is a no-op.
+    pub fn new(configs: Vec<ContractConfig>) -> Result<Self> {
+        let mut contracts = Vec::<ContractSymbol>::new();
+
+        for config in configs {
+            let symbol = ContractSymbol::try_from(config)?;
+
+            let built_in = ContractSymbol::BUILT_IN;
+            if !is_new_definition(built_in.iter().chain(contracts.iter()), &symbol)? {
+                continue;
+            }
+
+            contracts.push(symbol);
+        }
+
+        Ok(Self { contracts })
+    }
+
+    /// All contract symbols of the catalogue, the built-in ones first.
+    pub fn symbols(&self) -> Vec<ContractSymbol> {
+        ContractSymbol::BUILT_IN
+            .into_iter()
+            .chain(self.contracts.iter().copied())
+            .collect()
+    }
+
+    /// Look up a contract symbol by name or wire name, ignoring case.
+    pub fn parse(&self, name: &str) -> Result<ContractSymbol> {
+        self.symbols()
+            .into_iter()
+            .find(|symbol| symbol.is_named(name))
+            .with_context(|| format!("Unknown contract symbol {name}"))
+    }
+}
+
+impl TryFrom<ContractConfig> for ContractSymbol {
+    type Error = anyhow::Error;
+
+    fn try_from(config: ContractConfig) -> Result<Self> {
+        let context = || format!("Invalid definition of contract symbol {}", config.symbol);
+
+        let symbol = Name::new(&config.symbol).with_context(context)?;
+        let oracle_index = Name::new(&config.oracle_index).with_context(context)?;
+        let feed_instrument = Name::new(&config.feed_instrument).with_context(context)?;
+
+        let multiplier = match (config.payout_curve, config.multiplier) {
+            (PayoutCurve::Inverse, _) => Decimal::ONE,
+            (PayoutCurve::Quanto, Some(multiplier)) if multiplier > Decimal::ZERO => multiplier,
+            (PayoutCurve::Quanto, _) => {
+                bail!("Quanto contract symbol {symbol} needs a positive multiplier")
+            }
+        };
+
+        Ok(Self {
+            symbol,
+            payout_curve: config.payout_curve,
+            multiplier: multiplier.normalize(),
+            oracle_index,
+            feed_instrument,
+        })
+    }
+}
+
+/// Whether `symbol` is not among the `known` definitions yet.
+///
+/// Fails if it clashes with one of them.
+fn is_new_definition<'a>(
+    known: impl IntoIterator<Item = &'a ContractSymbol>,
+    symbol: &ContractSymbol,
+) -> Result<bool> {
+    for known in known {
+        if known == symbol {
+            return Ok(false);
+        }
+
+        ensure!(
+            known.symbol != symbol.symbol,
+            "Contract symbol {symbol} is already defined differently"
+        );
+        ensure!(
+            known.oracle_index != symbol.oracle_index,
+            "Oracle index {} is already used by {known}",
+            symbol.oracle_index
+        );
+        ensure!(
+            known.feed_instrument != symbol.feed_instrument,
+            "Feed instrument {} is already used by {known}",
+            symbol.feed_instrument
+        );
+    }
+
+    Ok(true)
+}
+
+/// A contract symbol together with its definition.
+///
+/// Contract symbols are only equal if their definitions are.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContractSymbol {
+    symbol: Name,
+    payout_curve: PayoutCurve,
+    /// BTC per contract and unit of price of quanto contracts.
+    multiplier: Decimal,
+    /// The index whose price the oracle attests to, e.g. `BXBT`.
+    oracle_index: Name,
+    /// The instrument whose quotes the price feed subscribes to, e.g. `XBTUSD`.
+    feed_instrument: Name,
+}
+
+impl ContractSymbol {
+    pub const BTC_USD: ContractSymbol = ContractSymbol {
+        symbol: Name::from_static("BTCUSD"),
+        payout_curve: PayoutCurve::Inverse,
+        multiplier: Decimal::ONE,
+        oracle_index: Name::from_static("BXBT"),
+        feed_instrument: Name::from_static("XBTUSD"),
+    };
+
+    pub const ETH_USD: ContractSymbol = ContractSymbol {
+        symbol: Name::from_static("ETHUSD"),
+        payout_curve: PayoutCurve::Quanto,
+        multiplier: ETHUSD_MULTIPLIER,
+        oracle_index: Name::from_static("BETH"),
+        feed_instrument: Name::from_static("ETHUSD"),
+    };
+
+    const BUILT_IN: [ContractSymbol; 2] = [ContractSymbol::BTC_USD, ContractSymbol::ETH_USD];
+
+    pub fn payout_curve(&self) -> PayoutCurve {
+        self.payout_curve
+    }
+
+    pub fn multiplier(&self) -> Decimal {
+        self.multiplier
+    }
+
+    pub fn oracle_index(&self) -> &str {
+        self.oracle_index.as_str()
+    }
+
+    pub(crate) const fn oracle_index_name(&self) -> Name {
+        self.oracle_index
+    }
+
+    pub fn feed_instrument(&self) -> &str {
+        self.feed_instrument.as_str()
+    }
+
+    /// The name of the contract symbol when serialized.
+    ///
+    /// Peers and databases know the built-in contract symbols by the names of the enum variants
+    /// they used to be. Any other contract symbol is serialized together with its definition,
+    /// e.g. `SOLUSD:quanto:0.0000001:BSOL:SOLUSD`.
+    pub fn wire_name(&self) -> Cow<'static, str> {
+        if *self == ContractSymbol::BTC_USD {
+            return Cow::Borrowed("BtcUsd");
+        }
+        if *self == ContractSymbol::ETH_USD {
+            return Cow::Borrowed("EthUsd");
+        }
+
+        let fields = [
+            self.symbol.to_string(),
+            self.payout_curve.to_string(),
+            self.multiplier.to_string(),
+            self.oracle_index.to_string(),
+            self.feed_instrument.to_string(),
+        ];
+
+        Cow::Owned(fields.join(WIRE_NAME_SEPARATOR))
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        self.symbol.as_str().eq_ignore_ascii_case(name)
+            || self.wire_name().eq_ignore_ascii_case(name)
+    }
+}
+
+impl fmt::Debug for ContractSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.wire_name())
+    }
+}
+
+impl fmt::Display for ContractSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol.as_str())
+    }
+}
+
+impl FromStr for ContractSymbol {
+    type Err = anyhow::Error;
+
+    /// Parse a built-in contract symbol by name or wire name, ignoring case, or any other
+    /// contract symbol by its wire name.
+    ///
+    /// Contract symbols from the config can only be looked up by name with [`Catalogue::parse`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(symbol) = ContractSymbol::BUILT_IN
+            .into_iter()
+            .find(|symbol| symbol.is_named(s))
+        {
+            return Ok(symbol);
+        }
+
+        let fields = s.split(WIRE_NAME_SEPARATOR).collect::<Vec<_>>();
+        let (symbol, payout_curve, multiplier, oracle_index, feed_instrument) = match fields[..] {
+            [symbol, payout_curve, multiplier, oracle_index, feed_instrument] => (
+                symbol,
+                payout_curve,
+                multiplier,
+                oracle_index,
+                feed_instrument,
+            ),
+            _ => bail!("Unknown contract symbol {s}, look it up in the contract catalogue"),
+        };
+
+        let symbol = ContractSymbol::try_from(ContractConfig {
+            symbol: symbol.to_owned(),
+            payout_curve: payout_curve.parse()?,
+            multiplier: Some(
+                multiplier
+                    .parse()
+                    .with_context(|| format!("Invalid multiplier in contract symbol {s}"))?,
+            ),
+            oracle_index: oracle_index.to_owned(),
+            feed_instrument: feed_instrument.to_owned(),
+        })?;
+        is_new_definition(&ContractSymbol::BUILT_IN, &symbol)?;
+
+        Ok(symbol)
+    }
+}
+
+impl Serialize for ContractSymbol {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.wire_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractSymbol {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+
+        name.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn solusd() -> ContractConfig {
+        ContractConfig {
+            symbol: "SOLUSD".to_owned(),
+            payout_curve: PayoutCurve::Quanto,
+            multiplier: Some(dec!(0.0000001)),
+            oracle_index: "BSOL".to_owned(),
+            feed_instrument: "SOLUSD".to_owned(),
+        }
+    }
+
+    #[test]
+    fn built_in_symbols_keep_their_wire_names() {
+        assert_eq!(
+            serde_json::to_string(&ContractSymbol::BTC_USD).unwrap(),
+            "\"BtcUsd\""
+        );
+        assert_eq!(
+            serde_json::from_str::<ContractSymbol>("\"EthUsd\"").unwrap(),
+            ContractSymbol::ETH_USD
+        );
+        assert_eq!(ContractSymbol::BTC_USD.to_string(), "BTCUSD");
+        assert_eq!(
+            "btcusd".parse::<ContractSymbol>().unwrap(),
+            ContractSymbol::BTC_USD
+        );
+    }
+
+    #[test]
+    fn catalogue_symbol_can_be_looked_up() {
+        let catalogue = Catalogue::new(vec![solusd()]).unwrap();
+        let solusd = catalogue.symbols()[2];
+
+        assert_eq!(solusd.to_string(), "SOLUSD");
+        assert_eq!(catalogue.parse("solusd").unwrap(), solusd);
+        assert_eq!(catalogue.parse("EthUsd").unwrap(), ContractSymbol::ETH_USD);
+        assert_eq!(solusd.payout_curve(), PayoutCurve::Quanto);
+        assert_eq!(solusd.oracle_index(), "BSOL");
+    }
+
+    #[test]
+    fn catalogue_symbol_roundtrips_without_catalogue() {
+        let solusd = Catalogue::new(vec![solusd()]).unwrap().symbols()[2];
+
+        let json = serde_json::to_string(&solusd).unwrap();
+        assert_eq!(json, "\"SOLUSD:quanto:0.0000001:BSOL:SOLUSD\"");
+        assert_eq!(
+            serde_json::from_str::<ContractSymbol>(&json).unwrap(),
+            solusd
+        );
+    }
+
+    #[test]
+    fn defining_same_symbol_twice_is_a_no_op() {
+        let catalogue = Catalogue::new(vec![solusd(), solusd()]).unwrap();
+
+        assert_eq!(catalogue, Catalogue::new(vec![solusd()]).unwrap());
+    }
+
+    #[test]
+    fn conflicting_definitions_are_rejected() {
+        let redefined_btcusd = ContractConfig {
+            symbol: "BTCUSD".to_owned(),
+            ..solusd()
+        };
+        let reused_index = ContractConfig {
+            symbol: "XRPUSD".to_owned(),
+            oracle_index: "BSOL".to_owned(),
+            feed_instrument: "XRPUSD".to_owned(),
+            ..solusd()
+        };
+        let quanto_without_multiplier = ContractConfig {
+            symbol: "ADAUSD".to_owned(),
+            multiplier: None,
+            oracle_index: "BADA".to_owned(),
+            feed_instrument: "ADAUSD".to_owned(),
+            ..solusd()
+        };
+
+        assert!(Catalogue::new(vec![redefined_btcusd]).is_err());
+        assert!(Catalogue::new(vec![solusd(), reused_index]).is_err());
+        assert!(Catalogue::new(vec![quanto_without_multiplier]).is_err());
+    }
+
+    #[test]
+    fn unknown_symbol_cannot_be_parsed() {
+        assert!("DOGEUSD".parse::<ContractSymbol>().is_err());
+        assert!(serde_json::from_str::<ContractSymbol>("\"DOGEUSD\"").is_err());
+        assert!(Catalogue::default().parse("SOLUSD").is_err());
+    }
+
+    #[test]
+    fn wire_name_clashing_with_built_in_symbol_cannot_be_parsed() {
+        assert!("BTCUSD:quanto:0.0000001:BSOL:SOLUSD"
+            .parse::<ContractSymbol>()
+            .is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::CfdEvent;
+use crate::Contracts;
+use crate::EventKind;
+use crate::FeeAccount;
+use crate::FundingFee;
+use crate::FundingRate;
+use crate::OpeningFee;
+use crate::Position;
+use crate::Price;
+use crate::Role;
+use crate::Timestamp;
+use bdk::bitcoin::SignedAmount;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The history of the fees of a CFD, with one entry per change of its fee account.
+///
+/// Built by applying the events of a CFD in order, the balance of the last entry is the balance
+/// of the fee account of the CFD.
+#[derive(Debug, Clone)]
+pub struct FeeLedger {
+    fee_account: FeeAccount,
+    initial_price: Price,
+    quantity: Contracts,
+    opening_fee: OpeningFee,
+    initial_funding_fee: FundingFee,
+    entries: Vec<FeeLedgerEntry>,
+}
+
+/// A single change of the fee account of a CFD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeLedgerEntry {
+    pub timestamp: Timestamp,
+    pub kind: FeeKind,
+    /// The funding rate the fee was charged at, if it is a funding fee.
+    pub funding_rate: Option<FundingRate>,
+    /// The price the fee was calculated with.
+    pub price: Price,
+    /// The fee we paid, negative if we received it.
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_btc")]
+    pub fee: SignedAmount,
+    /// The balance of the fee account after this entry, positive if we owe it to the
+    /// counterparty.
+    #[serde(with = "bdk::bitcoin::util::amount::serde::as_btc")]
+    pub balance: SignedAmount,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeKind {
+    /// The fee for opening the CFD.
+    Opening,
+    /// The funding fee charged for the first settlement interval when the CFD was opened, or
+    /// when it was rolled over.
+    Funding,
+    /// The part of the balance that was settled when the CFD was partially closed.
+    PartialSettlement,
+}
+
+impl FeeLedger {
+    pub fn new(
+        position: Position,
+        role: Role,
+        initial_price: Price,
+        quantity: Contracts,
+        opening_fee: OpeningFee,
+        initial_funding_fee: FundingFee,
+    ) -> Self {
+        Self {
+            fee_account: FeeAccount::new(position, role),
+            initial_price,
+            quantity,
+            opening_fee,
+            initial_funding_fee,
+            entries: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn apply(mut self, event: &CfdEvent) -> Self {
+        let timestamp = event.timestamp;
+
+        match &event.event {
+            EventKind::ContractSetupCompleted { .. } => {
+                let fee_account = self.fee_account.add_opening_fee(self.opening_fee);
+                self.record(timestamp, FeeKind::Opening, None, fee_account);
+
+                let funding_fee = self.initial_funding_fee;
+                let fee_account = self.fee_account.add_funding_fee(funding_fee);
+                self.record(
+                    timestamp,
+                    FeeKind::Funding,
+                    Some(funding_fee.rate),
+                    fee_account,
+                );
+            }
+            EventKind::RolloverCompleted {
+                funding_fee,
+                complete_fee,
+                ..
+            } => {
+                let fee_account = match complete_fee {
+                    None => self.fee_account.add_funding_fee(*funding_fee),
+                    Some(complete_fee) => self.fee_account.from_complete_fee(*complete_fee),
+                };
+                self.record(
+                    timestamp,
+                    FeeKind::Funding,
+                    Some(funding_fee.rate),
+                    fee_account,
+                );
+            }
+            EventKind::PartialSettlementCompleted { quantity, .. } => {
+                let remaining = self.quantity - *quantity;
+
+                let fee_account = self.fee_account.reduce(remaining, self.quantity);
+                self.record(timestamp, FeeKind::PartialSettlement, None, fee_account);
+
+                self.quantity = remaining;
+            }
+            _ => {}
+        }
+
+        self
+    }
+
+    pub fn entries(&self) -> &[FeeLedgerEntry] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<FeeLedgerEntry> {
+        self.entries
+    }
+
+    fn record(
+        &mut self,
+        timestamp: Timestamp,
+        kind: FeeKind,
+        funding_rate: Option<FundingRate>,
+        fee_account: FeeAccount,
+    ) {
+        self.entries.push(FeeLedgerEntry {
+            timestamp,
+            kind,
+            funding_rate,
+            price: self.initial_price,
+            fee: fee_account.balance() - self.fee_account.balance(),
+            balance: fee_account.balance(),
+        });
+
+        self.fee_account = fee_account;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompleteFee;
+    use crate::OrderId;
+    use bdk::bitcoin::Amount;
+    use rust_decimal_macros::dec;
+
+    fn event(event: EventKind, timestamp: i64) -> CfdEvent {
+        CfdEvent {
+            timestamp: Timestamp::new(timestamp),
+            id: OrderId::default(),
+            event,
+        }
+    }
+
+    fn funding_fee(sats: u64, rate: rust_decimal::Decimal) -> FundingFee {
+        FundingFee {
+            fee: Amount::from_sat(sats),
+            rate: FundingRate::new(rate).unwrap(),
+        }
+    }
+
+    fn ledger() -> FeeLedger {
+        FeeLedger::new(
+            Position::Long,
+            Role::Taker,
+            Price::new(dec!(20_000)).unwrap(),
+            Contracts::new(100),
+            OpeningFee::new(Amount::from_sat(100)),
+            funding_fee(50, dec!(0.001)),
+        )
+    }
+
+    #[test]
+    fn records_opening_and_rollover_fees_with_running_balance() {
+        let ledger = ledger()
+            .apply(&event(EventKind::ContractSetupCompleted { dlc: None }, 1))
+            .apply(&event(EventKind::RolloverStarted, 2))
+            .apply(&event(
+                EventKind::RolloverCompleted {
+                    dlc: None,
+                    funding_fee: funding_fee(30, dec!(-0.001)),
+                    complete_fee: None,
+                },
+                3,
+            ));
+
+        let fees = ledger
+            .entries()
+            .iter()
+            .map(|entry| (entry.kind, entry.fee.as_sat(), entry.balance.as_sat()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fees,
+            vec![
+                (FeeKind::Opening, 100, 100),
+                (FeeKind::Funding, 50, 150),
+                (FeeKind::Funding, -30, 120),
+            ]
+        );
+        assert_eq!(
+            ledger.entries()[2].funding_rate,
+            Some(FundingRate::new(dec!(-0.001)).unwrap())
+        );
+        assert_eq!(ledger.entries()[2].timestamp, Timestamp::new(3));
+    }
+
+    #[test]
+    fn complete_fee_replaces_balance() {
+        let ledger = ledger()
+            .apply(&event(EventKind::ContractSetupCompleted { dlc: None }, 1))
+            .apply(&event(
+                EventKind::RolloverCompleted {
+                    dlc: None,
+                    funding_fee: funding_fee(50, dec!(0.001)),
+                    complete_fee: Some(CompleteFee::LongPaysShort(Amount::from_sat(200))),
+                },
+                2,
+            ));
+
+        let last = ledger.entries().last().unwrap();
+
+        assert_eq!(last.fee.as_sat(), 50);
+        assert_eq!(last.balance.as_sat(), 200);
+    }
+}
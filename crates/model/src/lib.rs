@@ -25,12 +25,12 @@ use std::ops::Sub;
 use std::str;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
-use strum_macros::Display;
-use strum_macros::EnumIter;
 use time::OffsetDateTime;
 
 mod cfd;
 mod contract_setup;
+mod contract_symbol;
+mod fee_ledger;
 pub mod hex_transaction;
 pub mod libp2p;
 pub mod olivia;
@@ -41,6 +41,13 @@ pub mod transaction_ext;
 
 pub use cfd::*;
 pub use contract_setup::SetupParams;
+pub use contract_symbol::Catalogue;
+pub use contract_symbol::ContractConfig;
+pub use contract_symbol::ContractSymbol;
+pub use contract_symbol::PayoutCurve;
+pub use fee_ledger::FeeKind;
+pub use fee_ledger::FeeLedger;
+pub use fee_ledger::FeeLedgerEntry;
 pub use payout_curve::OraclePayouts;
 pub use payout_curve::Payouts;
 pub use rollover::BaseDlcParams;
@@ -370,13 +377,6 @@ impl From<Decimal> for Percent {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter, Display)]
-#[strum(serialize_all = "UPPERCASE")]
-pub enum ContractSymbol {
-    BtcUsd,
-    EthUsd,
-}
-
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Position {
     Long,
@@ -391,6 +391,20 @@ impl Position {
             Position::Short => Position::Long,
         }
     }
+
+    /// Orders the prices of offers in which the maker takes this position, the better price for
+    /// the taker first.
+    ///
+    /// The taker sells to a long maker and wants the highest price, and buys from a short maker
+    /// and wants the lowest price.
+    pub fn cmp_offer_prices(&self, a: Price, b: Price) -> Ordering {
+        let (a, b) = (a.into_decimal(), b.into_decimal());
+
+        match self {
+            Position::Long => b.cmp(&a),
+            Position::Short => a.cmp(&b),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -828,8 +842,30 @@ impl FeeAccount {
             },
         }
     }
+
+    /// Scale the balance down to the contracts that remain open after a partial settlement.
+    ///
+    /// The fees of the settled contracts are paid out as part of the partial settlement.
+    #[must_use]
+    pub fn reduce(self, remaining: Contracts, total: Contracts) -> Self {
+        let balance =
+            Decimal::from(self.balance.as_sat()) * remaining.into_decimal() / total.into_decimal();
+        let balance = balance
+            .round_dp_with_strategy(0, rust_decimal::RoundingStrategy::ToZero)
+            .to_i64()
+            .expect("not to overflow");
+
+        Self {
+            balance: SignedAmount::from_sat(balance),
+            ..self
+        }
+    }
 }
 
+/// How far a fee rate proposed by the counterparty may be off our own estimate, as a factor in
+/// either direction.
+const MAX_TX_FEE_RATE_DEVIATION_FACTOR: u32 = 3;
+
 /// Transaction fee in satoshis per vbyte
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TxFeeRate(NonZeroU32);
@@ -846,6 +882,16 @@ impl TxFeeRate {
     pub fn inner(&self) -> NonZeroU32 {
         self.0
     }
+
+    /// Whether this fee rate, proposed by the counterparty, is within a sane band around our own
+    /// `estimate`.
+    pub fn is_within_band(self, estimate: TxFeeRate) -> bool {
+        let proposed = self.to_u32();
+        let estimate = estimate.to_u32();
+
+        proposed.saturating_mul(MAX_TX_FEE_RATE_DEVIATION_FACTOR) >= estimate
+            && proposed <= estimate.saturating_mul(MAX_TX_FEE_RATE_DEVIATION_FACTOR)
+    }
 }
 
 impl From<TxFeeRate> for bdk::FeeRate {
@@ -908,7 +954,7 @@ impl From<Vout> for u32 {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Fees(SignedAmount);
 
 impl Fees {
@@ -1020,10 +1066,33 @@ pub enum Settlement {
         vout: Vout,
         payout: Payout,
     },
+    /// The counterparty published a revoked commit transaction and we punished them.
+    Punish {
+        /// The TXID of the revoked commit transaction.
+        commit_txid: Txid,
+        txid: Txid,
+        vout: Vout,
+        payout: Payout,
+    },
+}
+
+/// A partial settlement of a closed CFD.
+///
+/// The contracts settled this way are not part of the `n_contracts`, `fees` and `settlement` of
+/// the closed CFD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialPayout {
+    pub txid: Txid,
+    pub vout: Vout,
+    pub payout: Payout,
+    pub price: Price,
+    pub n_contracts: Contracts,
+    /// The fees that were paid out together with the settled contracts.
+    pub fees: Fees,
 }
 
 /// Data loaded from the database about a closed CFD.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct ClosedCfd {
     pub id: OrderId,
     pub offer_id: OfferId,
@@ -1038,7 +1107,11 @@ pub struct ClosedCfd {
     pub expiry_timestamp: OffsetDateTime,
     pub lock: Lock,
     pub settlement: Settlement,
+    /// The partial settlements preceding the final `settlement`, in the order they happened.
+    pub partial_payouts: Vec<PartialPayout>,
     pub creation_timestamp: Timestamp,
+    /// The timestamp of the last event of the CFD before it was closed.
+    pub closing_timestamp: Timestamp,
     pub contract_symbol: ContractSymbol,
 }
 
@@ -1071,6 +1144,28 @@ mod tests {
         assert_eq!(double.0, dec!(18));
     }
 
+    #[test]
+    fn proposed_tx_fee_rate_within_band_of_estimate() {
+        let fee_rate = |sat_per_vbyte| TxFeeRate::new(NonZeroU32::new(sat_per_vbyte).unwrap());
+
+        assert!(fee_rate(10).is_within_band(fee_rate(10)));
+        assert!(fee_rate(4).is_within_band(fee_rate(10)));
+        assert!(fee_rate(30).is_within_band(fee_rate(10)));
+
+        assert!(!fee_rate(3).is_within_band(fee_rate(10)));
+        assert!(!fee_rate(31).is_within_band(fee_rate(10)));
+    }
+
+    #[test]
+    fn better_offer_price_for_taker_is_ordered_first() {
+        let low = Price::new(dec!(19_000)).unwrap();
+        let high = Price::new(dec!(20_000)).unwrap();
+
+        assert_eq!(Position::Long.cmp_offer_prices(high, low), Ordering::Less);
+        assert_eq!(Position::Short.cmp_offer_prices(low, high), Ordering::Less);
+        assert_eq!(Position::Short.cmp_offer_prices(low, low), Ordering::Equal);
+    }
+
     #[test]
     fn leverage_does_not_alter_type() {
         let quantity = Contracts::new(61234);
@@ -1210,6 +1305,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn given_fee_account_when_reduce_then_balance_is_scaled_towards_zero() {
+        let funding_fee = FundingFee::new(
+            Amount::from_sat(1001),
+            FundingRate::new(dec!(-0.001)).unwrap(),
+        );
+
+        let long_taker = FeeAccount::new(Position::Long, Role::Taker)
+            .add_funding_fee(funding_fee)
+            .reduce(Contracts::new(300), Contracts::new(1000))
+            .settle();
+
+        assert_eq!(
+            long_taker,
+            CompleteFee::ShortPaysLong(Amount::from_sat(300))
+        );
+    }
+
     #[test]
     fn long_taker_short_maker_roundtrip() {
         let opening_fee = OpeningFee::new(Amount::from_sat(100));
@@ -1552,6 +1665,6 @@ mod tests {
     }
 
     fn dummy_contract_symbol() -> ContractSymbol {
-        ContractSymbol::BtcUsd
+        ContractSymbol::BTC_USD
     }
 }
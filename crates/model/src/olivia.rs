@@ -6,13 +6,15 @@ use conquer_once::Lazy;
 use derivative::Derivative;
 use maia_core::secp256k1_zkp::SecretKey;
 use serde::Deserialize;
+use serde::Serialize;
+use serde_with::serde_as;
 use serde_with::DeserializeFromStr;
+use serde_with::DisplayFromStr;
 use serde_with::SerializeDisplay;
+use std::collections::HashMap;
 use std::fmt;
 use std::str;
 use std::str::FromStr;
-use strum_macros::Display;
-use strum_macros::EnumString;
 use time::ext::NumericalDuration;
 use time::format_description::FormatItem;
 use time::macros::format_description;
@@ -22,6 +24,7 @@ use time::PrimitiveDateTime;
 use time::Time;
 use url::Url;
 
+use crate::contract_symbol::Name;
 use crate::ContractSymbol;
 
 pub const EVENT_TIME_FORMAT: &[FormatItem] =
@@ -32,6 +35,72 @@ pub static PUBLIC_KEY: Lazy<XOnlyPublicKey> = Lazy::new(|| {
         .expect("static key to be valid")
 });
 
+const BASE_URL: &str = "https://h00.ooo";
+
+const EVENT_PREFIX: &str = "/x/BitMEX";
+
+/// The oracle attesting to the price of a contract.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OracleConfig {
+    /// The key the oracle signs its attestations with.
+    pub public_key: XOnlyPublicKey,
+    /// Where announcements and attestations are published.
+    #[serde_as(as = "DisplayFromStr")]
+    pub base_url: Url,
+    /// The path under `base_url` that price events are published at, e.g. `/x/BitMEX`.
+    pub event_prefix: String,
+}
+
+impl OracleConfig {
+    /// The Olivia instance at <https://h00.ooo>.
+    pub fn olivia() -> Self {
+        Self {
+            public_key: *PUBLIC_KEY,
+            base_url: BASE_URL.parse().expect("valid URL from constant"),
+            event_prefix: EVENT_PREFIX.to_string(),
+        }
+    }
+
+    /// The URL of the given event on this oracle.
+    pub fn event_url(&self, event_id: BitMexPriceEventId) -> Url {
+        // Not using `Url::join` because it drops the last segment of a base URL without a
+        // trailing slash
+        let url = format!(
+            "{}/{}/{}",
+            self.base_url.as_str().trim_end_matches('/'),
+            self.event_prefix.trim_matches('/'),
+            event_id.path()
+        );
+
+        url.parse()
+            .expect("Event id can be appended to a valid URL")
+    }
+}
+
+/// The oracles used per contract symbol.
+///
+/// Symbols without an explicitly configured oracle use [`OracleConfig::olivia`].
+#[derive(Debug, Clone, Default)]
+pub struct Oracles(HashMap<ContractSymbol, OracleConfig>);
+
+impl Oracles {
+    pub fn new(oracles: impl IntoIterator<Item = (ContractSymbol, OracleConfig)>) -> Self {
+        Self(oracles.into_iter().collect())
+    }
+
+    pub fn get(&self, contract_symbol: ContractSymbol) -> OracleConfig {
+        self.0
+            .get(&contract_symbol)
+            .cloned()
+            .unwrap_or_else(OracleConfig::olivia)
+    }
+
+    pub fn public_key(&self, contract_symbol: ContractSymbol) -> XOnlyPublicKey {
+        self.get(contract_symbol).public_key
+    }
+}
+
 #[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 #[serde(try_from = "olivia_api::Response")]
 pub struct Announcement {
@@ -62,20 +131,40 @@ pub struct BitMexPriceEventId {
     index: IndexPrice,
 }
 
-#[derive(Derivative, Debug, Clone, Copy, Display, EnumString)]
-#[derivative(PartialEq, Eq, Hash)]
-#[strum(serialize_all = "UPPERCASE")]
-pub enum IndexPrice {
-    Bxbt,
-    Beth,
+/// The index whose price the oracle attests to for a contract symbol, e.g. `BXBT` for BTCUSD.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexPrice(Name);
+
+impl IndexPrice {
+    pub const BXBT: IndexPrice = IndexPrice(ContractSymbol::BTC_USD.oracle_index_name());
+    pub const BETH: IndexPrice = IndexPrice(ContractSymbol::ETH_USD.oracle_index_name());
+}
+
+impl fmt::Debug for IndexPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+impl fmt::Display for IndexPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+impl FromStr for IndexPrice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index = Name::new(s).with_context(|| format!("Invalid index {s}"))?;
+
+        Ok(Self(index))
+    }
 }
 
 impl From<ContractSymbol> for IndexPrice {
     fn from(contract_symbol: ContractSymbol) -> Self {
-        match contract_symbol {
-            ContractSymbol::BtcUsd => Self::Bxbt,
-            ContractSymbol::EthUsd => Self::Beth,
-        }
+        Self(contract_symbol.oracle_index_name())
     }
 }
 
@@ -107,12 +196,16 @@ impl BitMexPriceEventId {
         now > self.timestamp + Duration::minutes(1)
     }
 
-    pub fn to_olivia_url(self) -> Url {
-        "https://h00.ooo"
-            .parse::<Url>()
-            .expect("valid URL from constant")
-            .join(&self.to_string())
-            .expect("Event id can be joined")
+    /// The part of the event id that follows the oracle's event prefix.
+    fn path(&self) -> String {
+        format!(
+            "{}/{}.price?n={}",
+            self.index,
+            self.timestamp
+                .format(&EVENT_TIME_FORMAT)
+                .expect("should always format and we can't return an error here"),
+            self.digits
+        )
     }
 
     pub fn timestamp(&self) -> OffsetDateTime {
@@ -126,26 +219,11 @@ impl BitMexPriceEventId {
     pub fn index_price(&self) -> IndexPrice {
         self.index
     }
-
-    pub fn contract_symbol(&self) -> ContractSymbol {
-        match self.index {
-            IndexPrice::Bxbt => ContractSymbol::BtcUsd,
-            IndexPrice::Beth => ContractSymbol::EthUsd,
-        }
-    }
 }
 
 impl fmt::Display for BitMexPriceEventId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "/x/BitMEX/{}/{}.price?n={}",
-            self.index,
-            self.timestamp
-                .format(&EVENT_TIME_FORMAT)
-                .expect("should always format and we can't return an error here"),
-            self.digits
-        )
+        write!(f, "{EVENT_PREFIX}/{}", self.path())
     }
 }
 
@@ -153,14 +231,11 @@ impl FromStr for BitMexPriceEventId {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let rest = s.trim_start_matches("/x/BitMEX/");
-
-        let [index, rest]: [&str; 2] = rest
-            .split('/')
-            .collect::<Vec<_>>()
-            .try_into()
-            .ok()
-            .context("Failed to parse index")?;
+        // Oracles may publish events under a different prefix, only the last two segments
+        // identify the event
+        let mut segments = s.rsplitn(3, '/');
+        let rest = segments.next().context("Failed to parse timestamp")?;
+        let index = segments.next().context("Failed to parse index")?;
 
         let index = IndexPrice::from_str(index)?;
 
@@ -361,7 +436,7 @@ mod olivia_api {
             let expected = olivia::Announcement {
                 id: BitMexPriceEventId::with_20_digits(
                     datetime!(2021-10-04 22:00:00).assume_utc(),
-                    IndexPrice::Bxbt,
+                    IndexPrice::BXBT,
                 ),
                 expected_outcome_time: datetime!(2021-10-04 22:00:00).assume_utc(),
                 nonce_pks: vec![
@@ -439,7 +514,7 @@ mod olivia_api {
             let expected = olivia::Attestation {
                 id: BitMexPriceEventId::with_20_digits(
                     datetime!(2021-10-04 22:00:00).assume_utc(),
-                    IndexPrice::Bxbt,
+                    IndexPrice::BXBT,
                 ),
                 price: 48935,
                 scalars: vec![
@@ -519,11 +594,10 @@ mod tests {
 
     #[test]
     fn to_olivia_url() {
-        let url = BitMexPriceEventId::with_20_digits(
+        let url = OracleConfig::olivia().event_url(BitMexPriceEventId::with_20_digits(
             datetime!(2021-09-23 10:00:00).assume_utc(),
-            IndexPrice::Bxbt,
-        )
-        .to_olivia_url();
+            IndexPrice::BXBT,
+        ));
 
         assert_eq!(
             url,
@@ -533,6 +607,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn to_custom_oracle_url() {
+        let oracle = OracleConfig {
+            base_url: "https://oracle.example.com/staging/".parse().unwrap(),
+            event_prefix: "/x/Staging/".to_string(),
+            ..OracleConfig::olivia()
+        };
+
+        let url = oracle.event_url(BitMexPriceEventId::with_20_digits(
+            datetime!(2021-09-23 10:00:00).assume_utc(),
+            IndexPrice::BETH,
+        ));
+
+        assert_eq!(
+            url,
+            "https://oracle.example.com/staging/x/Staging/BETH/2021-09-23T10:00:00.price?n=20"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn unconfigured_symbol_uses_olivia() {
+        let oracle = OracleConfig {
+            base_url: "https://oracle.example.com".parse().unwrap(),
+            ..OracleConfig::olivia()
+        };
+        let oracles = Oracles::new([(ContractSymbol::ETH_USD, oracle.clone())]);
+
+        assert_eq!(oracles.get(ContractSymbol::ETH_USD), oracle);
+        assert_eq!(oracles.get(ContractSymbol::BTC_USD), OracleConfig::olivia());
+    }
+
+    #[test]
+    fn oracle_config_roundtrip() {
+        let oracle = OracleConfig::olivia();
+
+        let json = serde_json::to_string(&oracle).unwrap();
+        let deserialized = serde_json::from_str::<OracleConfig>(&json).unwrap();
+
+        assert_eq!(deserialized, oracle);
+    }
+
     #[test]
     fn parse_event_id() {
         let parsed = "/x/BitMEX/BXBT/2021-09-23T10:00:00.price?n=20"
@@ -540,7 +657,20 @@ mod tests {
             .unwrap();
         let expected = BitMexPriceEventId::with_20_digits(
             datetime!(2021-09-23 10:00:00).assume_utc(),
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
+        );
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn parse_event_id_with_custom_prefix() {
+        let parsed = "/x/Staging/BETH/2021-09-23T10:00:00.price?n=20"
+            .parse::<BitMexPriceEventId>()
+            .unwrap();
+        let expected = BitMexPriceEventId::with_20_digits(
+            datetime!(2021-09-23 10:00:00).assume_utc(),
+            IndexPrice::BETH,
         );
 
         assert_eq!(parsed, expected);
@@ -548,7 +678,7 @@ mod tests {
 
     #[test]
     fn new_event_has_no_nanos() {
-        let now = BitMexPriceEventId::with_20_digits(OffsetDateTime::now_utc(), IndexPrice::Bxbt);
+        let now = BitMexPriceEventId::with_20_digits(OffsetDateTime::now_utc(), IndexPrice::BXBT);
 
         assert_eq!(now.timestamp.nanosecond(), 0);
     }
@@ -557,7 +687,7 @@ mod tests {
     fn has_occured_if_in_the_past() {
         let past_event = BitMexPriceEventId::with_20_digits(
             datetime!(2021-09-23 10:00:00).assume_utc(),
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
         );
 
         assert!(past_event.has_likely_occurred());
@@ -567,7 +697,7 @@ mod tests {
     fn next_event_id_after_timestamp() {
         let event_id = next_announcement_after(
             datetime!(2021-09-23 10:40:00).assume_utc(),
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
         );
 
         assert_eq!(
@@ -580,7 +710,7 @@ mod tests {
     fn next_event_id_is_midnight_next_day() {
         let event_id = next_announcement_after(
             datetime!(2021-09-23 23:40:00).assume_utc(),
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
         );
 
         assert_eq!(
@@ -594,7 +724,7 @@ mod tests {
         let actual = hourly_events(
             datetime!(2022-07-05 23:40:00).assume_utc(),
             datetime!(2022-07-06 23:40:00).assume_utc(),
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
         )
         .unwrap()
         .iter()
@@ -638,7 +768,7 @@ mod tests {
             datetime!(2022-07-05 00:00:00).assume_utc(),
             datetime!(2022-07-05 00:30:00).assume_utc(),
             Duration::MINUTE,
-            IndexPrice::Bxbt,
+            IndexPrice::BXBT,
         )
         .unwrap()
         .iter()
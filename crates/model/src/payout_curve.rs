@@ -303,7 +303,7 @@ mod tests {
                     let timestamp = datetime!(2022-07-29 13:00:00).assume_utc().add(i.hours());
 
                     Announcement {
-                        id: BitMexPriceEventId::new(timestamp, 1, ContractSymbol::BtcUsd),
+                        id: BitMexPriceEventId::new(timestamp, 1, ContractSymbol::BTC_USD),
                         expected_outcome_time: timestamp,
                         nonce_pks: vec![
                             "d02d163cf9623f567c4e3faf851a9266ac1ede13da4ca4141f3a7717fba9a739"
@@ -374,7 +374,7 @@ mod tests {
                     let timestamp = datetime!(2022-07-29 13:00:00).assume_utc().add(i.hours());
 
                     Announcement {
-                        id: BitMexPriceEventId::new(timestamp, 1, ContractSymbol::EthUsd),
+                        id: BitMexPriceEventId::new(timestamp, 1, ContractSymbol::ETH_USD),
                         expected_outcome_time: timestamp,
                         nonce_pks: vec![
                             "d02d163cf9623f567c4e3faf851a9266ac1ede13da4ca4141f3a7717fba9a739"
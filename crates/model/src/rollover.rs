@@ -89,6 +89,7 @@ pub struct BaseCommitParams {
     pub commit_encsig_ours: EcdsaAdaptorSignature,
     pub revocation_pk_theirs: PublicKey,
     pub publish_pk_theirs: PublicKey,
+    pub publish_pk_ours: Option<PublicKey>,
 
     // To monitor.
     pub commit_txid: Txid,
@@ -126,6 +127,7 @@ impl Dlc {
                 commit_encsig_ours: self.commit.1,
                 revocation_pk_theirs: self.revocation_pk_counterparty,
                 publish_pk_theirs: self.publish_pk_counterparty,
+                publish_pk_ours: Some(PublicKey::new(self.publish.to_public_key())),
                 commit_txid: self.commit.0.txid(),
                 commit_script_pubkey: self.commit.2.script_pubkey(),
                 settlement_event_id: self.settlement_event_id,
@@ -150,6 +152,7 @@ impl Dlc {
             revocation_sk_ours,
             revocation_sk_theirs,
             publication_pk_theirs: publish_pk_theirs,
+            publication_pk_ours: publish_pk_ours,
             txid: commit_txid,
             script_pubkey: commit_script_pubkey,
             settlement_event_id,
@@ -178,6 +181,7 @@ impl Dlc {
                 revocation_sk_ours,
                 revocation_pk_theirs: PublicKey::new(revocation_sk_theirs.to_public_key()),
                 publish_pk_theirs,
+                publish_pk_ours,
                 commit_txid,
                 commit_script_pubkey,
                 settlement_event_id,
@@ -210,6 +214,7 @@ impl BaseDlcParams {
             revocation_sk_ours: Some(self.base_commit_params.revocation_sk_ours),
             revocation_sk_theirs,
             publication_pk_theirs: self.base_commit_params.publish_pk_theirs,
+            publication_pk_ours: self.base_commit_params.publish_pk_ours,
             txid: self.base_commit_params.commit_txid,
             script_pubkey: self.base_commit_params.commit_script_pubkey,
             settlement_event_id: Some(self.base_commit_params.settlement_event_id),
@@ -1,10 +1,21 @@
 use crate::MAINNET_ELECTRUM;
 use crate::TESTNET_ELECTRUM;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
 use daemon::bdk::bitcoin;
 use daemon::bdk::bitcoin::Address;
 use daemon::bdk::bitcoin::Amount;
+use daemon::chain;
+use daemon::export::Format;
+use model::olivia::OracleConfig;
+use model::Catalogue;
+use model::ContractSymbol;
+use model::Contracts;
+use std::path::Path;
 use std::path::PathBuf;
 
 #[derive(Parser, Clone)]
@@ -15,8 +26,11 @@ pub enum Network {
         #[clap(long, default_value = MAINNET_ELECTRUM)]
         electrum: String,
 
+        #[clap(flatten)]
+        bitcoind: Bitcoind,
+
         #[clap(subcommand)]
-        withdraw: Option<Withdraw>,
+        command: Option<Command>,
     },
     /// Run on testnet
     Testnet {
@@ -24,8 +38,11 @@ pub enum Network {
         #[clap(long, default_value = TESTNET_ELECTRUM)]
         electrum: String,
 
+        #[clap(flatten)]
+        bitcoind: Bitcoind,
+
         #[clap(subcommand)]
-        withdraw: Option<Withdraw>,
+        command: Option<Command>,
     },
     /// Run on signet
     Signet {
@@ -33,8 +50,11 @@ pub enum Network {
         #[clap(long)]
         electrum: String,
 
+        #[clap(flatten)]
+        bitcoind: Bitcoind,
+
         #[clap(subcommand)]
-        withdraw: Option<Withdraw>,
+        command: Option<Command>,
     },
     /// Run on regtest
     Regtest {
@@ -42,22 +62,47 @@ pub enum Network {
         #[clap(long)]
         electrum: String,
 
+        #[clap(flatten)]
+        bitcoind: Bitcoind,
+
         #[clap(subcommand)]
-        withdraw: Option<Withdraw>,
+        command: Option<Command>,
     },
 }
 
+/// Use a bitcoind node instead of the Electrum backend.
+#[derive(Args, Clone, Default)]
+pub struct Bitcoind {
+    /// URL of the JSON-RPC interface of a bitcoind node to use instead of the Electrum backend,
+    /// e.g. "http://127.0.0.1:8332". The node has to run with `-txindex`.
+    #[clap(long)]
+    bitcoind_rpc: Option<String>,
+
+    /// Cookie file to authenticate with bitcoind, e.g. "~/.bitcoin/.cookie".
+    #[clap(long, conflicts_with_all = ["bitcoind_user", "bitcoind_password"])]
+    bitcoind_cookie: Option<PathBuf>,
+
+    /// User to authenticate with bitcoind, requires `--bitcoind-password`.
+    #[clap(long, requires = "bitcoind_password")]
+    bitcoind_user: Option<String>,
+
+    /// Password to authenticate with bitcoind, requires `--bitcoind-user`.
+    #[clap(long, requires = "bitcoind_user")]
+    bitcoind_password: Option<String>,
+}
+
 impl Default for Network {
     fn default() -> Self {
         Network::Mainnet {
             electrum: MAINNET_ELECTRUM.to_string(),
-            withdraw: None,
+            bitcoind: Bitcoind::default(),
+            command: None,
         }
     }
 }
 
 #[derive(Subcommand, Clone)]
-pub enum Withdraw {
+pub enum Command {
     Withdraw {
         /// Optionally specify the amount of Bitcoin to be withdrawn. If not specified the wallet
         /// will be drained. Amount is to be specified with denomination, e.g. "0.1 BTC"
@@ -71,6 +116,70 @@ pub enum Withdraw {
         #[clap(long)]
         address: Address,
     },
+    /// Export all closed CFDs for tax and accounting purposes.
+    ///
+    /// The `pnl_usd` column is empty for quanto contracts such as ETHUSD, whose exit price is not
+    /// a bitcoin price, and for CFDs without an exit price.
+    ExportClosedCfds {
+        /// The format to export in, either "csv" or "json".
+        #[clap(long, default_value = "csv")]
+        format: Format,
+        /// The file to write the export to. If not specified it is written to stdout.
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+    /// Write an encrypted backup of the database and the seed files.
+    Backup {
+        /// The file to write the backup to.
+        #[clap(long)]
+        output: PathBuf,
+        /// A file containing the passphrase to encrypt the backup with. If not specified the
+        /// passphrase is read from the `ITCHYSATS_BACKUP_PASSPHRASE` environment variable.
+        #[clap(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Restore an encrypted backup. Existing database and seed files are kept next to the
+    /// restored ones with a `-backup` suffix.
+    RestoreBackup {
+        /// The backup file to restore.
+        #[clap(long)]
+        input: PathBuf,
+        /// A file containing the passphrase the backup was encrypted with. If not specified the
+        /// passphrase is read from the `ITCHYSATS_BACKUP_PASSPHRASE` environment variable.
+        #[clap(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Recover open CFDs from their backups so they can be force-closed.
+    ///
+    /// Requires the seed the backups were created with. CFDs that are already in the database are
+    /// skipped.
+    RecoverCfds,
+}
+
+/// The environment variable the backup passphrase is read from if no passphrase file is given.
+const BACKUP_PASSPHRASE_ENV: &str = "ITCHYSATS_BACKUP_PASSPHRASE";
+
+/// Read the passphrase of the `backup` and `restore-backup` subcommands.
+///
+/// The passphrase is not accepted as an argument to keep it out of the process list and the shell
+/// history.
+pub async fn backup_passphrase(passphrase_file: Option<&Path>) -> Result<String> {
+    let passphrase = match passphrase_file {
+        Some(file) => tokio::fs::read_to_string(file)
+            .await
+            .with_context(|| format!("Failed to read passphrase from {}", file.display()))?
+            .trim_end_matches(['\r', '\n'])
+            .to_string(),
+        None => std::env::var(BACKUP_PASSPHRASE_ENV).with_context(|| {
+            format!("Either specify --passphrase-file or set {BACKUP_PASSPHRASE_ENV}")
+        })?,
+    };
+
+    if passphrase.is_empty() {
+        bail!("Backup passphrase must not be empty");
+    }
+
+    Ok(passphrase)
 }
 
 impl Network {
@@ -83,6 +192,45 @@ impl Network {
         }
     }
 
+    fn bitcoind(&self) -> &Bitcoind {
+        match self {
+            Network::Mainnet { bitcoind, .. } => bitcoind,
+            Network::Testnet { bitcoind, .. } => bitcoind,
+            Network::Signet { bitcoind, .. } => bitcoind,
+            Network::Regtest { bitcoind, .. } => bitcoind,
+        }
+    }
+
+    /// How to access the blockchain, through bitcoind if `--bitcoind-rpc` is given and Electrum
+    /// otherwise.
+    pub fn chain(&self) -> Result<chain::Config> {
+        let Bitcoind {
+            bitcoind_rpc,
+            bitcoind_cookie,
+            bitcoind_user,
+            bitcoind_password,
+        } = self.bitcoind().clone();
+
+        let url = match bitcoind_rpc {
+            Some(url) => url,
+            None => {
+                return Ok(chain::Config::Electrum {
+                    url: self.electrum().to_string(),
+                })
+            }
+        };
+
+        let auth = match (bitcoind_cookie, bitcoind_user, bitcoind_password) {
+            (Some(cookie), None, None) => chain::BitcoindAuth::CookieFile(cookie),
+            (None, Some(user), Some(password)) => chain::BitcoindAuth::UserPass { user, password },
+            _ => bail!(
+                "Either --bitcoind-cookie or --bitcoind-user and --bitcoind-password are required"
+            ),
+        };
+
+        Ok(chain::Config::Bitcoind { url, auth })
+    }
+
     pub fn bitcoin_network(&self) -> bitcoin::Network {
         match self {
             Network::Mainnet { .. } => bitcoin::Network::Bitcoin,
@@ -110,12 +258,12 @@ impl Network {
         }
     }
 
-    pub fn withdraw(&self) -> &Option<Withdraw> {
+    pub fn command(&self) -> &Option<Command> {
         match self {
-            Network::Mainnet { withdraw, .. } => withdraw,
-            Network::Testnet { withdraw, .. } => withdraw,
-            Network::Signet { withdraw, .. } => withdraw,
-            Network::Regtest { withdraw, .. } => withdraw,
+            Network::Mainnet { command, .. } => command,
+            Network::Testnet { command, .. } => command,
+            Network::Signet { command, .. } => command,
+            Network::Regtest { command, .. } => command,
         }
     }
 
@@ -140,3 +288,154 @@ impl std::fmt::Debug for Network {
         }
     }
 }
+
+/// Parse an oracle for a contract symbol.
+///
+/// Expects `<SYMBOL>=<PUBLIC_KEY>,<BASE_URL>[,<EVENT_PREFIX>]`, e.g.
+/// `BTCUSD=ddd4...caf7,https://h00.ooo,/x/BitMEX`. The event prefix defaults to the one used by
+/// Olivia.
+///
+/// The contract symbol is looked up once the catalogue is loaded, see
+/// [`resolve_contract_symbols`].
+pub fn parse_oracle(s: &str) -> Result<(String, OracleConfig)> {
+    let (symbol, oracle) = s
+        .split_once('=')
+        .context("Expected <SYMBOL>=<PUBLIC_KEY>,<BASE_URL>[,<EVENT_PREFIX>]")?;
+
+    let symbol = parse_contract_symbol(symbol)?;
+
+    let mut parts = oracle.split(',');
+    let public_key = parts
+        .next()
+        .context("Missing oracle public key")?
+        .parse()
+        .context("Failed to parse oracle public key")?;
+    let base_url = parts
+        .next()
+        .context("Missing oracle base URL")?
+        .parse()
+        .context("Failed to parse oracle base URL")?;
+    let event_prefix = parts
+        .next()
+        .map(str::to_string)
+        .unwrap_or_else(|| OracleConfig::olivia().event_prefix);
+
+    if parts.next().is_some() {
+        bail!("Too many oracle parameters");
+    }
+
+    Ok((
+        symbol,
+        OracleConfig {
+            public_key,
+            base_url,
+            event_prefix,
+        },
+    ))
+}
+
+/// Parse a limit on the number of contracts for a contract symbol.
+///
+/// Expects `<SYMBOL>=<CONTRACTS>`, e.g. `BTCUSD=10000`.
+///
+/// The contract symbol is looked up once the catalogue is loaded, see
+/// [`resolve_contract_symbols`].
+pub fn parse_contracts_limit(s: &str) -> Result<(String, Contracts)> {
+    let (symbol, contracts) = s.split_once('=').context("Expected <SYMBOL>=<CONTRACTS>")?;
+
+    let symbol = parse_contract_symbol(symbol)?;
+    let contracts = contracts
+        .parse::<u64>()
+        .context("Failed to parse number of contracts")?;
+
+    Ok((symbol, Contracts::new(contracts)))
+}
+
+fn parse_contract_symbol(s: &str) -> Result<String> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!("Invalid contract symbol {s}")
+    }
+
+    Ok(s.to_uppercase())
+}
+
+/// Look up the contract symbols of values given per contract symbol on the command line.
+///
+/// Contract symbols from the command line can only be looked up once the catalogue is loaded,
+/// see [`crate::contracts::load_contracts`].
+pub fn resolve_contract_symbols<T>(
+    catalogue: &Catalogue,
+    values: &[(String, T)],
+) -> Result<Vec<(ContractSymbol, T)>>
+where
+    T: Clone,
+{
+    values
+        .iter()
+        .map(|(symbol, value)| {
+            let symbol = catalogue.parse(symbol)?;
+
+            Ok((symbol, value.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_oracle_with_default_prefix() {
+        let (symbol, oracle) = parse_oracle(
+            "ethusd=ddd4636845a90185991826be5a494cde9f4a6947b1727217afedc6292fa4caf7,https://oracle.example.com",
+        )
+        .unwrap();
+
+        assert_eq!(symbol, "ETHUSD");
+        assert_eq!(oracle.public_key, OracleConfig::olivia().public_key);
+        assert_eq!(oracle.base_url.as_str(), "https://oracle.example.com/");
+        assert_eq!(oracle.event_prefix, "/x/BitMEX");
+    }
+
+    #[test]
+    fn parse_oracle_with_prefix() {
+        let (symbol, oracle) = parse_oracle(
+            "BTCUSD=ddd4636845a90185991826be5a494cde9f4a6947b1727217afedc6292fa4caf7,https://oracle.example.com,/x/Staging",
+        )
+        .unwrap();
+
+        assert_eq!(symbol, "BTCUSD");
+        assert_eq!(oracle.event_prefix, "/x/Staging");
+    }
+
+    #[test]
+    fn reject_invalid_symbol() {
+        assert!(parse_oracle(
+            "DOGE/USD=ddd4636845a90185991826be5a494cde9f4a6947b1727217afedc6292fa4caf7,https://oracle.example.com"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reject_unknown_symbol_once_resolved() {
+        let oracle = parse_oracle(
+            "DOGEUSD=ddd4636845a90185991826be5a494cde9f4a6947b1727217afedc6292fa4caf7,https://oracle.example.com"
+        )
+        .unwrap();
+
+        assert!(resolve_contract_symbols(&Catalogue::default(), &[oracle]).is_err());
+    }
+
+    #[test]
+    fn parse_contracts_limit_for_symbol() {
+        let limit = parse_contracts_limit("btcusd=10000").unwrap();
+
+        let [(symbol, contracts)]: [(ContractSymbol, Contracts); 1] =
+            resolve_contract_symbols(&Catalogue::default(), &[limit])
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(symbol, ContractSymbol::BTC_USD);
+        assert_eq!(contracts, Contracts::new(10000));
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Context;
+use anyhow::Result;
+use model::ContractConfig;
+use serde::Deserialize;
+use std::path::Path;
+
+const FILENAME: &str = "contracts.toml";
+
+/// Contract symbols offered in addition to the built-in BTCUSD and ETHUSD.
+///
+/// ```toml
+/// [[contract]]
+/// symbol = "SOLUSD"
+/// payout_curve = "quanto"
+/// multiplier = "0.0000001"
+/// oracle_index = "BSOL"
+/// feed_instrument = "SOLUSD"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Catalogue {
+    #[serde(default, rename = "contract")]
+    contracts: Vec<ContractConfig>,
+}
+
+/// Build the catalogue of contract symbols from the definitions in `directory`.
+///
+/// The catalogue is needed to look up contract symbols by name, e.g. from the command line, and
+/// lists the contract symbols to hand to the components that need them.
+pub async fn load_contracts(directory: &Path) -> Result<model::Catalogue> {
+    let path = directory.join(FILENAME);
+
+    let contracts = if path.try_exists()? {
+        let raw = tokio::fs::read_to_string(&path).await?;
+        parse_catalogue(&raw)
+            .with_context(|| format!("Failed to load contract catalogue at {path:?}"))?
+            .contracts
+    } else {
+        tracing::info!("Only built-in contract symbols. Expected config file at: {path:?}");
+
+        Vec::new()
+    };
+
+    model::Catalogue::new(contracts)
+        .with_context(|| format!("Invalid contract catalogue at {path:?}"))
+}
+
+fn parse_catalogue(raw: &str) -> Result<Catalogue> {
+    let catalogue = toml::from_str(raw)?;
+
+    Ok(catalogue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::PayoutCurve;
+
+    #[test]
+    fn parse_catalogue_entries() {
+        let catalogue = parse_catalogue(
+            r#"
+            [[contract]]
+            symbol = "SOLUSD"
+            payout_curve = "quanto"
+            multiplier = "0.0000001"
+            oracle_index = "BSOL"
+            feed_instrument = "SOLUSD"
+
+            [[contract]]
+            symbol = "BTCEUR"
+            payout_curve = "inverse"
+            oracle_index = "BXBTEUR"
+            feed_instrument = "XBTEUR"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(catalogue.contracts.len(), 2);
+        assert_eq!(catalogue.contracts[0].payout_curve, PayoutCurve::Quanto);
+        assert!(catalogue.contracts[0].multiplier.is_some());
+        assert_eq!(catalogue.contracts[1].payout_curve, PayoutCurve::Inverse);
+        assert!(catalogue.contracts[1].multiplier.is_none());
+    }
+
+    #[test]
+    fn reject_unknown_payout_curve() {
+        let result = parse_catalogue(
+            r#"
+            [[contract]]
+            symbol = "SOLUSD"
+            payout_curve = "linear"
+            oracle_index = "BSOL"
+            feed_instrument = "SOLUSD"
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod catchers;
 pub mod cli;
+pub mod contracts;
 pub mod fairings;
 pub mod logger;
 pub mod routes;
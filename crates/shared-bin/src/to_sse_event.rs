@@ -7,6 +7,10 @@ use daemon::listen_protocols::does_maker_satisfy_taker_needs;
 use daemon::listen_protocols::REQUIRED_MAKER_LISTEN_PROTOCOLS;
 use daemon::online_status;
 use daemon::projection::Cfd;
+use daemon::projection::LatestQuotes;
+use daemon::projection::MakerOffers;
+use model::ContractSymbol;
+use model::Position;
 use model::Timestamp;
 use rocket::response::stream::Event;
 use serde::Serialize;
@@ -22,6 +26,48 @@ impl ToSseEvent for Vec<Cfd> {
     }
 }
 
+impl ToSseEvent for MakerOffers {
+    fn to_sse_event(&self) -> Event {
+        Event::json(&self).event("offers")
+    }
+}
+
+/// The best offer of each side of the order book, one event per contract symbol and position.
+///
+/// These events predate the order book with several price levels per side, which is published
+/// as a whole via [`MakerOffers::to_sse_event`].
+pub fn best_offer_sse_events(
+    offers: &MakerOffers,
+    contract_symbols: &[ContractSymbol],
+) -> Vec<Event> {
+    contract_symbols
+        .iter()
+        .copied()
+        .flat_map(|contract_symbol| {
+            [(Position::Long, "long"), (Position::Short, "short")]
+                .into_iter()
+                .map(move |(position_maker, side)| {
+                    let symbol = contract_symbol.to_string().to_lowercase();
+
+                    Event::json(&offers.best(contract_symbol, position_maker))
+                        .event(format!("{symbol}_{side}_offer"))
+                })
+        })
+        .collect()
+}
+
+/// The latest quote of each contract symbol, one event per contract symbol.
+pub fn quote_sse_events(quotes: &LatestQuotes, contract_symbols: &[ContractSymbol]) -> Vec<Event> {
+    contract_symbols
+        .iter()
+        .map(|contract_symbol| {
+            let symbol = contract_symbol.to_string().to_lowercase();
+
+            Event::json(&quotes.get(contract_symbol)).event(format!("{symbol}_quote"))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct WalletInfo {
     #[serde(with = "daemon::bdk::bitcoin::util::amount::serde::as_btc")]
@@ -0,0 +1,5 @@
+ALTER TABLE
+    revoked_commit_transactions
+ADD
+    -- We allow NULL values to ensure backwards compatibility (we cannot chose a default value for this easily)
+    COLUMN publication_pk_ours text NULL;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS closed_fee_ledger (
+    id integer PRIMARY KEY autoincrement,
+    cfd_id integer NOT NULL,
+    timestamp integer NOT NULL,
+    kind text NOT NULL,
+    funding_rate text,
+    price text NOT NULL,
+    fee integer NOT NULL,
+    balance integer NOT NULL,
+    FOREIGN KEY (cfd_id) REFERENCES closed_cfds (id)
+);
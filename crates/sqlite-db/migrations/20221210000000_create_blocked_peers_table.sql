@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS blocked_peers (
+    peer_id text PRIMARY KEY NOT NULL,
+    blocked_at integer NOT NULL
+);
@@ -0,0 +1,3 @@
+-- CFDs opened before the oracle was stored are attested by Olivia
+ALTER TABLE cfds
+    ADD COLUMN oracle TEXT;
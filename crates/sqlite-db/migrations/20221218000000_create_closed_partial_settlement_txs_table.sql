@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS closed_partial_settlement_txs (
+    id integer PRIMARY KEY autoincrement,
+    cfd_id integer NOT NULL,
+    txid text NOT NULL,
+    vout integer NOT NULL,
+    payout integer NOT NULL,
+    price text NOT NULL,
+    n_contracts integer NOT NULL,
+    fees integer NOT NULL,
+    FOREIGN KEY (cfd_id) REFERENCES closed_cfds (id)
+);
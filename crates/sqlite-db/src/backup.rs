@@ -0,0 +1,125 @@
+use crate::Connection;
+use crate::MIGRATOR;
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::ConnectOptions;
+use std::path::Path;
+
+impl Connection {
+    /// Write a consistent snapshot of the database to `path`.
+    ///
+    /// The snapshot is taken in a single read transaction, so it is safe to call while the
+    /// database is in use. Fails if there already is a file at `path`.
+    pub async fn snapshot(&self, path: &Path) -> Result<()> {
+        let path = path
+            .to_str()
+            .context("Snapshot path is not valid unicode")?;
+
+        let mut conn = self.inner.acquire().await?;
+
+        sqlx::query("VACUUM INTO $1")
+            .bind(path)
+            .execute(&mut conn)
+            .await
+            .with_context(|| format!("Failed to write snapshot to {path}"))?;
+
+        Ok(())
+    }
+}
+
+/// The version of the latest migration known to this version of the database.
+pub fn latest_migration_version() -> i64 {
+    MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .expect("at least one migration")
+}
+
+/// Validate that the database snapshot at `path` can be restored.
+///
+/// A snapshot can be restored if all of its migrations succeeded and are known to this version of
+/// the database. Snapshots with older migrations are migrated when they are opened. Returns the
+/// version of the latest migration of the snapshot.
+pub async fn validate_snapshot(path: &Path) -> Result<i64> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .context("Failed to open database snapshot")?;
+
+    let migrations: Vec<(i64, bool)> =
+        sqlx::query_as("SELECT version, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&mut conn)
+            .await
+            .context("Database snapshot has no migrations")?;
+
+    let mut latest = None;
+    for (version, success) in migrations {
+        ensure!(success, "Migration {version} of database snapshot failed");
+
+        if !MIGRATOR
+            .iter()
+            .any(|migration| migration.version == version)
+        {
+            bail!(
+                "Database snapshot has migration {version} which is unknown to this version, \
+                 upgrade before restoring it"
+            );
+        }
+
+        latest = Some(version);
+    }
+
+    latest.context("Database snapshot has no migrations")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory;
+    use crate::tests::dummy_cfd;
+    use model::OrderId;
+
+    #[tokio::test]
+    async fn snapshot_of_migrated_database_is_valid() {
+        let db = memory().await.unwrap();
+        db.insert_cfd(&dummy_cfd()).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("snapshot-{}.sqlite", OrderId::default()));
+        db.snapshot(&path).await.unwrap();
+
+        let version = validate_snapshot(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(version, latest_migration_version());
+    }
+
+    #[tokio::test]
+    async fn snapshot_with_unknown_migration_is_invalid() {
+        let db = memory().await.unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO _sqlx_migrations
+            (version, description, success, checksum, execution_time)
+            VALUES ($1, 'from the future', 1, x'00', 0)
+            "#,
+        )
+        .bind(latest_migration_version() + 1)
+        .execute(&db.inner)
+        .await
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("snapshot-{}.sqlite", OrderId::default()));
+        db.snapshot(&path).await.unwrap();
+
+        let result = validate_snapshot(&path).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
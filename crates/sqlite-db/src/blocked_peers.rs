@@ -0,0 +1,98 @@
+use crate::models;
+use crate::Connection;
+use anyhow::Result;
+use model::libp2p::PeerId;
+use time::OffsetDateTime;
+
+impl Connection {
+    /// Block `peer_id` from connecting.
+    ///
+    /// Blocking a peer which is already blocked has no effect.
+    pub async fn insert_blocked_peer(&self, peer_id: PeerId) -> Result<()> {
+        let mut conn = self.inner.acquire().await?;
+
+        let peer_id = models::PeerId::from(peer_id);
+        let blocked_at = OffsetDateTime::now_utc().unix_timestamp();
+
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO blocked_peers
+            (
+                peer_id,
+                blocked_at
+            )
+            VALUES ($1, $2)
+            "#,
+            peer_id,
+            blocked_at,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Allow `peer_id` to connect again.
+    ///
+    /// Returns whether the peer was blocked.
+    pub async fn delete_blocked_peer(&self, peer_id: PeerId) -> Result<bool> {
+        let mut conn = self.inner.acquire().await?;
+
+        let peer_id = models::PeerId::from(peer_id);
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM blocked_peers
+            WHERE peer_id = $1
+            "#,
+            peer_id,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn load_blocked_peers(&self) -> Result<Vec<PeerId>> {
+        let mut conn = self.inner.acquire().await?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                peer_id as "peer_id: models::PeerId"
+            FROM
+                blocked_peers
+            ORDER BY
+                blocked_at
+            "#
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.peer_id.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory;
+    use model::libp2p::PeerId;
+
+    #[tokio::test]
+    async fn blocked_peers_can_be_unblocked() {
+        let db = memory().await.unwrap();
+
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+        db.insert_blocked_peer(peer_id).await.unwrap();
+        db.insert_blocked_peer(peer_id).await.unwrap();
+        db.insert_blocked_peer(other_peer_id).await.unwrap();
+
+        let was_blocked = db.delete_blocked_peer(peer_id).await.unwrap();
+        let was_blocked_twice = db.delete_blocked_peer(peer_id).await.unwrap();
+
+        assert!(was_blocked);
+        assert!(!was_blocked_twice);
+        assert_eq!(db.load_blocked_peers().await.unwrap(), vec![other_peer_id]);
+    }
+}
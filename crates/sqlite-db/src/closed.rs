@@ -12,6 +12,8 @@ use crate::delete_from_events_table;
 use crate::derive_known_peer_id;
 use crate::event_log::EventLog;
 use crate::event_log::EventLogEntry;
+use crate::fee_ledger;
+use crate::fee_ledger::insert_closed_fee_ledger;
 use crate::load_cfd_events;
 use crate::load_cfd_row;
 use crate::models;
@@ -42,6 +44,7 @@ use model::Leverage;
 use model::Lock;
 use model::OfferId;
 use model::OrderId;
+use model::PartialPayout;
 use model::Position;
 use model::Price;
 use model::Role;
@@ -76,6 +79,7 @@ impl Connection {
                 let cfd = load_cfd_row(&mut db_tx, id).await?;
                 let events = load_cfd_events(&mut db_tx, id, 0).await?;
                 let event_log = EventLog::new(&events);
+                let fee_ledger = fee_ledger::build(&cfd, &events);
 
                 let closed_cfd = ClosedCfdInputAggregate::new(cfd);
                 let closed_cfd = events
@@ -83,9 +87,11 @@ impl Connection {
                     .try_fold(closed_cfd, ClosedCfdInputAggregate::apply)?
                     .build()?;
 
-                insert_closed_cfd(&mut db_tx, closed_cfd).await?;
+                insert_closed_cfd(&mut db_tx, &closed_cfd).await?;
                 insert_event_log(&mut db_tx, id, event_log).await?;
+                insert_closed_fee_ledger(&mut db_tx, id, fee_ledger.entries()).await?;
 
+                insert_partial_payouts(&mut db_tx, id, &closed_cfd.partial_payouts).await?;
                 insert_settlement(&mut db_tx, id, closed_cfd.settlement).await?;
 
                 delete_from_events_table(&mut db_tx, id).await?;
@@ -112,81 +118,28 @@ impl Connection {
     {
         let mut conn = self.inner.acquire().await?;
 
-        let inner_id = models::OrderId::from(id);
-        let cfd = sqlx::query!(
-            r#"
-            SELECT
-                order_id as "order_id: models::OrderId",
-                offer_id as "offer_id: models::OfferId",
-                position as "position: models::Position",
-                initial_price as "initial_price: models::Price",
-                taker_leverage as "taker_leverage: models::Leverage",
-                n_contracts as "n_contracts: models::Contracts",
-                counterparty_network_identity as "counterparty_network_identity: models::Identity",
-                counterparty_peer_id as "counterparty_peer_id: models::PeerId",
-                role as "role: models::Role",
-                fees as "fees: models::Fees",
-                expiry_timestamp,
-                lock_txid as "lock_txid: models::Txid",
-                lock_dlc_vout as "lock_dlc_vout: models::Vout",
-                contract_symbol as "contract_symbol: models::ContractSymbol"
-            FROM
-                closed_cfds
-            WHERE
-                closed_cfds.order_id = $1
-            "#,
-            inner_id
-        )
-        .fetch_one(&mut conn)
-        .await?;
+        let cfd = load_closed_cfd_row(&mut conn, id).await?;
 
-        let expiry_timestamp = OffsetDateTime::from_unix_timestamp(cfd.expiry_timestamp)?;
-
-        let collaborative_settlement = load_collaborative_settlement(&mut conn, id).await?;
-        let cet_settlement = load_cet_settlement(&mut conn, id).await?;
-        let refund_settlement = load_refund_settlement(&mut conn, id).await?;
-
-        let settlement = match (collaborative_settlement, cet_settlement, refund_settlement) {
-            (Some(collaborative_settlement), None, None) => collaborative_settlement,
-            (None, Some(cet), None) => cet,
-            (None, None, Some(refund)) => refund,
-            _ => {
-                bail!(
-                    "Closed CFD has insane combination of transactions:
-                       {collaborative_settlement:?},
-                       {cet_settlement:?},
-                       {refund_settlement:?}"
-                )
-            }
-        };
+        Ok(C::new_closed(args, cfd))
+    }
 
-        let creation_timestamp = load_creation_timestamp(&mut conn, id).await?;
+    /// Load all closed CFDs from the database, ordered by the time they were closed.
+    pub async fn load_closed_cfds(&self) -> Result<Vec<ClosedCfd>> {
+        let ids = self.load_closed_cfd_ids().await?;
 
-        let cfd = ClosedCfd {
-            id,
-            offer_id: cfd.offer_id.into(),
-            position: cfd.position.into(),
-            initial_price: cfd.initial_price.into(),
-            taker_leverage: cfd.taker_leverage.into(),
-            n_contracts: cfd.n_contracts.try_into()?,
-            counterparty_network_identity: cfd.counterparty_network_identity.into(),
-            counterparty_peer_id: cfd.counterparty_peer_id.into(),
-            role: cfd.role.into(),
-            fees: cfd.fees.into(),
-            expiry_timestamp,
-            lock: Lock {
-                txid: cfd.lock_txid.into(),
-                dlc_vout: cfd.lock_dlc_vout.into(),
-            },
-            settlement,
-            creation_timestamp,
-            contract_symbol: cfd.contract_symbol.into(),
-        };
+        let mut conn = self.inner.acquire().await?;
 
-        Ok(C::new_closed(args, cfd))
+        let mut cfds = Vec::with_capacity(ids.len());
+        for id in ids {
+            cfds.push(load_closed_cfd_row(&mut conn, id).await?);
+        }
+
+        cfds.sort_by_key(|cfd| cfd.closing_timestamp);
+
+        Ok(cfds)
     }
 
-    pub(crate) async fn load_closed_cfd_ids(&self) -> Result<Vec<OrderId>> {
+    pub async fn load_closed_cfd_ids(&self) -> Result<Vec<OrderId>> {
         let mut conn = self.inner.acquire().await?;
 
         let ids = sqlx::query!(
@@ -227,10 +180,13 @@ struct ClosedCfdInputAggregate {
     initial_funding_fee: FundingFee,
     latest_dlc: Option<Dlc>,
     collaborative_settlement: Option<(bdk::bitcoin::Transaction, Script, Price)>,
+    partial_payouts: Vec<PartialPayout>,
     cet: Option<(bdk::bitcoin::Transaction, Price)>,
     cet_confirmed: bool,
     collaborative_settlement_confirmed: bool,
     refund_confirmed: bool,
+    punish: Option<bdk::bitcoin::Transaction>,
+    punish_confirmed: bool,
     contract_symbol: ContractSymbol,
 }
 
@@ -285,10 +241,13 @@ impl ClosedCfdInputAggregate {
             initial_funding_fee,
             latest_dlc: None,
             collaborative_settlement: None,
+            partial_payouts: Vec::new(),
             cet: None,
             cet_confirmed: false,
             collaborative_settlement_confirmed: false,
             refund_confirmed: false,
+            punish: None,
+            punish_confirmed: false,
             contract_symbol,
         }
     }
@@ -329,6 +288,41 @@ impl ClosedCfdInputAggregate {
             }
             CollaborativeSettlementRejected => {}
             CollaborativeSettlementFailed => {}
+            PartialSettlementStarted { .. } => {}
+            PartialSettlementSigned { .. } => {}
+            PartialSettlementCompleted {
+                spend_tx,
+                script,
+                price,
+                quantity,
+                dlc,
+            } => {
+                let remaining = self.n_contracts - quantity;
+                let fee_account = self.fee_account.reduce(remaining, self.n_contracts);
+
+                let OutPoint { txid, vout } = spend_tx
+                    .outpoint(&script)
+                    .context("Missing spend script in partial settlement TX")?;
+                let payout = &spend_tx
+                    .output
+                    .get(vout as usize)
+                    .with_context(|| format!("No output at vout {vout}"))?;
+
+                self.partial_payouts.push(PartialPayout {
+                    txid,
+                    vout: model::Vout::new(vout),
+                    payout: model::Payout::new(Amount::from_sat(payout.value)),
+                    price,
+                    n_contracts: quantity,
+                    fees: Fees::new(self.fee_account.balance() - fee_account.balance()),
+                });
+
+                self.fee_account = fee_account;
+                self.n_contracts = remaining;
+                self.latest_dlc = dlc;
+            }
+            PartialSettlementRejected => {}
+            PartialSettlementFailed => {}
             LockConfirmed => {}
             LockConfirmedAfterFinality => {}
             CommitConfirmed => {}
@@ -339,6 +333,12 @@ impl ClosedCfdInputAggregate {
                 self.refund_confirmed = true;
             }
             RevokeConfirmed => {}
+            RevokedCommitPublished { punish_tx } => {
+                self.punish = Some(punish_tx);
+            }
+            PunishConfirmed => {
+                self.punish_confirmed = true;
+            }
             CollaborativeSettlementConfirmed => {
                 self.collaborative_settlement_confirmed = true;
             }
@@ -364,6 +364,7 @@ impl ClosedCfdInputAggregate {
                 self.cet = Some((cet, price));
             }
             ManualCommit { .. } => {}
+            FeeBumped { .. } => {}
         }
 
         Ok(self)
@@ -473,6 +474,40 @@ impl ClosedCfdInputAggregate {
         })
     }
 
+    fn punish(&self) -> Result<Settlement> {
+        let punish_tx = self.punish.as_ref().context("Punish TX not set")?;
+
+        // The punish transaction spends the revoked commit transaction
+        // published by the counterparty, not the latest one
+        let commit_txid = punish_tx
+            .input
+            .first()
+            .context("Punish TX without inputs")?
+            .previous_output
+            .txid;
+
+        let own_script_pubkey = self.latest_dlc()?.script_pubkey_for(self.role);
+
+        let OutPoint { txid, vout } = punish_tx
+            .outpoint(&own_script_pubkey)
+            .context("Missing spend script in punish TX")?;
+
+        let payout = &punish_tx
+            .output
+            .get(vout as usize)
+            .with_context(|| format!("No output at vout {vout}"))?;
+        let payout = model::Payout::new(Amount::from_sat(payout.value));
+
+        let vout = model::Vout::new(vout);
+
+        Ok(Settlement::Punish {
+            commit_txid,
+            txid,
+            vout,
+            payout,
+        })
+    }
+
     fn build(self) -> Result<ClosedCfdInput> {
         let Self {
             id,
@@ -486,6 +521,7 @@ impl ClosedCfdInputAggregate {
             role,
             fee_account,
             contract_symbol,
+            ref partial_payouts,
             ..
         } = self;
 
@@ -496,15 +532,18 @@ impl ClosedCfdInputAggregate {
             self.collaborative_settlement_confirmed,
             self.cet_confirmed,
             self.refund_confirmed,
+            self.punish_confirmed,
         ) {
-            (true, false, false) => self.collaborative_settlement()?,
-            (false, true, false) => self.cet()?,
-            (false, false, true) => self.refund()?,
-            (collaborative_settlement, cet, refund) => bail!(
+            (true, false, false, false) => self.collaborative_settlement()?,
+            (false, true, false, false) => self.cet()?,
+            (false, false, true, false) => self.refund()?,
+            (false, false, false, true) => self.punish()?,
+            (collaborative_settlement, cet, refund, punish) => bail!(
                 "Insane transaction combination:
                     Collaborative settlement: {collaborative_settlement:?},
                     CET: {cet:?},
-                    Refund: {refund:?},"
+                    Refund: {refund:?},
+                    Punish: {punish:?},"
             ),
         };
 
@@ -522,6 +561,7 @@ impl ClosedCfdInputAggregate {
             expiry_timestamp: dlc.settlement_event_id.timestamp(),
             lock,
             settlement,
+            partial_payouts: partial_payouts.clone(),
             contract_symbol,
         })
     }
@@ -529,7 +569,7 @@ impl ClosedCfdInputAggregate {
 
 /// All the data related to a closed CFD that we want to store in the
 /// database.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct ClosedCfdInput {
     id: OrderId,
     offer_id: OfferId,
@@ -544,10 +584,11 @@ struct ClosedCfdInput {
     expiry_timestamp: OffsetDateTime,
     lock: Lock,
     settlement: Settlement,
+    partial_payouts: Vec<PartialPayout>,
     contract_symbol: ContractSymbol,
 }
 
-async fn insert_closed_cfd(conn: &mut SqliteConnection, cfd: ClosedCfdInput) -> Result<()> {
+async fn insert_closed_cfd(conn: &mut SqliteConnection, cfd: &ClosedCfdInput) -> Result<()> {
     let expiry_timestamp = cfd.expiry_timestamp.unix_timestamp();
 
     let counterparty_peer_id = match cfd.counterparty_peer_id {
@@ -670,11 +711,79 @@ async fn insert_settlement(
             )
             .await?
         }
+        Settlement::Punish {
+            commit_txid,
+            txid,
+            vout,
+            payout,
+        } => {
+            insert_punish_settlement(
+                &mut *conn,
+                id,
+                commit_txid.into(),
+                txid.into(),
+                vout.into(),
+                payout.into(),
+            )
+            .await?
+        }
     };
 
     Ok(())
 }
 
+async fn insert_partial_payouts(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+    partial_payouts: &[PartialPayout],
+) -> Result<()> {
+    let id = models::OrderId::from(id);
+
+    for partial_payout in partial_payouts {
+        let txid = models::Txid::from(partial_payout.txid);
+        let vout = models::Vout::from(partial_payout.vout);
+        let payout = models::Payout::from(partial_payout.payout);
+        let price = models::Price::from(partial_payout.price);
+        let n_contracts = models::Contracts::from(partial_payout.n_contracts);
+        let fees = models::Fees::from(partial_payout.fees);
+
+        let query_result = sqlx::query!(
+            r#"
+            INSERT INTO closed_partial_settlement_txs
+            (
+                cfd_id,
+                txid,
+                vout,
+                payout,
+                price,
+                n_contracts,
+                fees
+            )
+            VALUES
+            (
+                (SELECT id FROM closed_cfds WHERE closed_cfds.order_id = $1),
+                $2, $3, $4, $5, $6, $7
+            )
+            "#,
+            id,
+            txid,
+            vout,
+            payout,
+            price,
+            n_contracts,
+            fees,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        if query_result.rows_affected() != 1 {
+            bail!("failed to insert into closed_partial_settlement_txs");
+        }
+    }
+
+    Ok(())
+}
+
 async fn insert_collaborative_settlement(
     conn: &mut SqliteConnection,
     id: OrderId,
@@ -804,6 +913,48 @@ async fn insert_refund_settlement(
     Ok(())
 }
 
+async fn insert_punish_settlement(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+    commit_txid: Txid,
+    txid: Txid,
+    vout: Vout,
+    payout: Payout,
+) -> Result<()> {
+    insert_commit_tx(&mut *conn, id, commit_txid).await?;
+
+    let id = models::OrderId::from(id);
+
+    let query_result = sqlx::query!(
+        r#"
+        INSERT INTO closed_punish_txs
+        (
+            cfd_id,
+            txid,
+            vout,
+            payout
+        )
+        VALUES
+        (
+            (SELECT id FROM closed_cfds WHERE closed_cfds.order_id = $1),
+            $2, $3, $4
+        )
+        "#,
+        id,
+        txid,
+        vout,
+        payout,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if query_result.rows_affected() != 1 {
+        bail!("failed to insert into closed_punish_txs");
+    }
+
+    Ok(())
+}
+
 async fn insert_commit_tx(conn: &mut SqliteConnection, id: OrderId, txid: Txid) -> Result<()> {
     let id = models::OrderId::from(id);
 
@@ -862,6 +1013,49 @@ async fn load_collaborative_settlement(
     Ok(row.map(|settlement| settlement.into()))
 }
 
+async fn load_partial_payouts(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+) -> Result<Vec<PartialPayout>> {
+    let id = models::OrderId::from(id);
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            closed_partial_settlement_txs.txid as "txid: models::Txid",
+            closed_partial_settlement_txs.vout as "vout: models::Vout",
+            closed_partial_settlement_txs.payout as "payout: models::Payout",
+            closed_partial_settlement_txs.price as "price: models::Price",
+            closed_partial_settlement_txs.n_contracts as "n_contracts: models::Contracts",
+            closed_partial_settlement_txs.fees as "fees: models::Fees"
+        FROM
+            closed_partial_settlement_txs
+        JOIN
+            closed_cfds on closed_cfds.id = closed_partial_settlement_txs.cfd_id
+        WHERE
+            closed_cfds.order_id = $1
+        ORDER BY
+            closed_partial_settlement_txs.id
+        "#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(PartialPayout {
+                txid: row.txid.into(),
+                vout: row.vout.into(),
+                payout: row.payout.into(),
+                price: row.price.into(),
+                n_contracts: row.n_contracts.try_into()?,
+                fees: row.fees.into(),
+            })
+        })
+        .collect()
+}
+
 async fn load_cet_settlement(
     conn: &mut SqliteConnection,
     id: OrderId,
@@ -925,6 +1119,37 @@ async fn load_refund_settlement(
     Ok(row.map(|settlement| settlement.into()))
 }
 
+async fn load_punish_settlement(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+) -> Result<Option<Settlement>> {
+    let id = models::OrderId::from(id);
+
+    let row = sqlx::query_as!(
+        models::Settlement::Punish,
+        r#"
+        SELECT
+            closed_commit_txs.txid as "commit_txid!: models::Txid",
+            closed_punish_txs.txid as "txid: models::Txid",
+            closed_punish_txs.vout as "vout: models::Vout",
+            closed_punish_txs.payout as "payout: models::Payout"
+        FROM
+            closed_punish_txs
+        JOIN
+            closed_commit_txs on closed_commit_txs.cfd_id = closed_punish_txs.cfd_id
+        JOIN
+            closed_cfds on closed_cfds.id = closed_punish_txs.cfd_id
+        WHERE
+            closed_cfds.order_id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(row.map(|settlement| settlement.into()))
+}
+
 async fn insert_event_log(
     conn: &mut SqliteConnection,
     id: OrderId,
@@ -961,6 +1186,94 @@ async fn insert_event_log(
     Ok(())
 }
 
+async fn load_closed_cfd_row(conn: &mut SqliteConnection, id: OrderId) -> Result<ClosedCfd> {
+    let inner_id = models::OrderId::from(id);
+    let cfd = sqlx::query!(
+        r#"
+        SELECT
+            order_id as "order_id: models::OrderId",
+            offer_id as "offer_id: models::OfferId",
+            position as "position: models::Position",
+            initial_price as "initial_price: models::Price",
+            taker_leverage as "taker_leverage: models::Leverage",
+            n_contracts as "n_contracts: models::Contracts",
+            counterparty_network_identity as "counterparty_network_identity: models::Identity",
+            counterparty_peer_id as "counterparty_peer_id: models::PeerId",
+            role as "role: models::Role",
+            fees as "fees: models::Fees",
+            expiry_timestamp,
+            lock_txid as "lock_txid: models::Txid",
+            lock_dlc_vout as "lock_dlc_vout: models::Vout",
+            contract_symbol as "contract_symbol: models::ContractSymbol"
+        FROM
+            closed_cfds
+        WHERE
+            closed_cfds.order_id = $1
+        "#,
+        inner_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let expiry_timestamp = OffsetDateTime::from_unix_timestamp(cfd.expiry_timestamp)?;
+
+    let collaborative_settlement = load_collaborative_settlement(conn, id).await?;
+    let cet_settlement = load_cet_settlement(conn, id).await?;
+    let refund_settlement = load_refund_settlement(conn, id).await?;
+    let punish_settlement = load_punish_settlement(conn, id).await?;
+
+    let settlement = match (
+        collaborative_settlement,
+        cet_settlement,
+        refund_settlement,
+        punish_settlement,
+    ) {
+        (Some(collaborative_settlement), None, None, None) => collaborative_settlement,
+        (None, Some(cet), None, None) => cet,
+        (None, None, Some(refund), None) => refund,
+        (None, None, None, Some(punish)) => punish,
+        _ => {
+            bail!(
+                "Closed CFD has insane combination of transactions:
+                   {collaborative_settlement:?},
+                   {cet_settlement:?},
+                   {refund_settlement:?},
+                   {punish_settlement:?}"
+            )
+        }
+    };
+
+    let partial_payouts = load_partial_payouts(conn, id).await?;
+
+    let creation_timestamp = load_creation_timestamp(conn, id).await?;
+    let closing_timestamp = load_closing_timestamp(conn, id).await?;
+
+    let cfd = ClosedCfd {
+        id,
+        offer_id: cfd.offer_id.into(),
+        position: cfd.position.into(),
+        initial_price: cfd.initial_price.into(),
+        taker_leverage: cfd.taker_leverage.into(),
+        n_contracts: cfd.n_contracts.try_into()?,
+        counterparty_network_identity: cfd.counterparty_network_identity.into(),
+        counterparty_peer_id: cfd.counterparty_peer_id.into(),
+        role: cfd.role.into(),
+        fees: cfd.fees.into(),
+        expiry_timestamp,
+        lock: Lock {
+            txid: cfd.lock_txid.into(),
+            dlc_vout: cfd.lock_dlc_vout.into(),
+        },
+        settlement,
+        partial_payouts,
+        creation_timestamp,
+        closing_timestamp,
+        contract_symbol: cfd.contract_symbol.into(),
+    };
+
+    Ok(cfd)
+}
+
 /// Obtain the time at which the closed CFD was created, according to
 /// the `event_log` table.
 ///
@@ -990,12 +1303,42 @@ async fn load_creation_timestamp(conn: &mut SqliteConnection, id: OrderId) -> Re
     Ok(Timestamp::new(row.created_at))
 }
 
+/// Obtain the time at which the closed CFD was closed, according to
+/// the `event_log` table.
+///
+/// We use the timestamp of the last event for a particular CFD `id`
+/// in the `event_log` table.
+async fn load_closing_timestamp(conn: &mut SqliteConnection, id: OrderId) -> Result<Timestamp> {
+    let id = models::OrderId::from(id);
+
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            event_log.created_at as "created_at!: i64"
+        FROM
+            event_log
+        JOIN
+            closed_cfds on closed_cfds.id = event_log.cfd_id
+        WHERE
+            closed_cfds.order_id = $1
+        ORDER BY event_log.created_at DESC
+        LIMIT 1
+        "#,
+        id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Timestamp::new(row.created_at))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::memory;
     use bdk::bitcoin::SignedAmount;
     use model::libp2p::PeerId;
+    use model::olivia::OracleConfig;
     use model::Cfd;
     use model::ContractSymbol;
     use model::Contracts;
@@ -1225,6 +1568,69 @@ mod tests {
         assert_eq!(inserted, loaded);
     }
 
+    #[tokio::test]
+    async fn insert_punish_tx_roundtrip() {
+        let db = memory().await.unwrap();
+        let mut conn = db.inner.acquire().await.unwrap();
+
+        let id = OrderId::default();
+
+        insert_dummy_closed_cfd(&mut *conn, id).await.unwrap();
+
+        let inserted = Settlement::Punish {
+            commit_txid: bdk::bitcoin::Txid::default(),
+            txid: bdk::bitcoin::Txid::default(),
+            vout: Vout::new(0),
+            payout: Payout::new(Amount::ONE_BTC),
+        };
+
+        insert_settlement(&mut *conn, id, inserted).await.unwrap();
+
+        let loaded = load_punish_settlement(&mut *conn, id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(inserted, loaded);
+    }
+
+    #[tokio::test]
+    async fn insert_partial_payouts_roundtrip() {
+        let db = memory().await.unwrap();
+        let mut conn = db.inner.acquire().await.unwrap();
+
+        let id = OrderId::default();
+
+        insert_dummy_closed_cfd(&mut *conn, id).await.unwrap();
+
+        let inserted = vec![
+            PartialPayout {
+                txid: bdk::bitcoin::Txid::default(),
+                vout: Vout::new(1),
+                payout: Payout::new(Amount::from_sat(100_000)),
+                price: Price::new(dec!(40_000)).expect("To be valid price"),
+                n_contracts: Contracts::new(30),
+                fees: Fees::new(SignedAmount::from_sat(-300)),
+            },
+            PartialPayout {
+                txid: bdk::bitcoin::Txid::default(),
+                vout: Vout::new(0),
+                payout: Payout::new(Amount::from_sat(50_000)),
+                price: Price::new(dec!(41_000)).expect("To be valid price"),
+                n_contracts: Contracts::new(20),
+                fees: Fees::new(SignedAmount::from_sat(200)),
+            },
+        ];
+
+        insert_partial_payouts(&mut conn, id, &inserted)
+            .await
+            .unwrap();
+
+        let loaded = load_partial_payouts(&mut conn, id).await.unwrap();
+
+        assert_eq!(inserted, loaded);
+    }
+
     #[tokio::test]
     async fn given_confirmed_settlement_when_move_cfds_to_closed_table_then_creation_timestamp_is_that_of_first_event(
     ) {
@@ -1257,6 +1663,36 @@ mod tests {
         assert_eq!(creation_timestamp, Some(first_event_timestamp));
     }
 
+    #[tokio::test]
+    async fn given_confirmed_settlement_when_move_cfds_to_closed_table_then_closing_timestamp_is_that_of_last_event(
+    ) {
+        let db = memory().await.unwrap();
+
+        let (cfd, contract_setup_completed, collaborative_settlement_completed) =
+            cfd_collaboratively_settled();
+        let order_id = cfd.id();
+
+        db.insert_cfd(&cfd).await.unwrap();
+
+        let last_event_timestamp = Timestamp::new(Timestamp::now().seconds() + 60);
+        let mut collab_settlement_confirmed = collab_settlement_confirmed(&cfd);
+        collab_settlement_confirmed.timestamp = last_event_timestamp;
+
+        db.append_event(contract_setup_completed).await.unwrap();
+        db.append_event(collaborative_settlement_completed)
+            .await
+            .unwrap();
+        db.append_event(collab_settlement_confirmed).await.unwrap();
+
+        db.move_to_closed_cfds().await.unwrap();
+
+        let closed_cfds = db.load_closed_cfds().await.unwrap();
+
+        assert_eq!(closed_cfds.len(), 1);
+        assert_eq!(closed_cfds[0].id, order_id);
+        assert_eq!(closed_cfds[0].closing_timestamp, last_event_timestamp);
+    }
+
     async fn insert_dummy_closed_cfd(conn: &mut SqliteConnection, id: OrderId) -> Result<()> {
         let cfd = ClosedCfdInput {
             id,
@@ -1280,10 +1716,11 @@ mod tests {
                 payout: Payout::new(Amount::ONE_BTC),
                 price: Price::new(Decimal::ONE_HUNDRED).expect("To be valid price"),
             },
-            contract_symbol: ContractSymbol::BtcUsd,
+            partial_payouts: Vec::new(),
+            contract_symbol: ContractSymbol::BTC_USD,
         };
 
-        insert_closed_cfd(&mut *conn, cfd).await?;
+        insert_closed_cfd(&mut *conn, &cfd).await?;
 
         Ok(())
     }
@@ -1309,7 +1746,8 @@ mod tests {
             OpeningFee::new(Amount::ZERO),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         );
 
         let contract_setup_completed =
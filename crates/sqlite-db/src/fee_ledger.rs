@@ -0,0 +1,214 @@
+use crate::load_cfd_events;
+use crate::load_cfd_row;
+use crate::models;
+use crate::Cfd;
+use crate::Connection;
+use crate::Error;
+use anyhow::bail;
+use anyhow::Result;
+use model::long_and_short_leverage;
+use model::CfdEvent;
+use model::FeeLedger;
+use model::FeeLedgerEntry;
+use model::FundingFee;
+use model::OrderId;
+use model::SETTLEMENT_INTERVAL;
+use sqlx::SqliteConnection;
+
+impl Connection {
+    /// Load the fee ledger of an open or closed CFD.
+    ///
+    /// Returns `None` if there is neither an open nor a closed CFD with this `id`. The ledger of
+    /// CFDs that were closed before the ledger was recorded is empty.
+    pub async fn load_fee_ledger(&self, id: OrderId) -> Result<Option<Vec<FeeLedgerEntry>>> {
+        let mut conn = self.inner.acquire().await?;
+
+        match load_cfd_row(&mut conn, id).await {
+            Ok(cfd) => {
+                let events = load_cfd_events(&mut conn, id, 0).await?;
+
+                Ok(Some(build(&cfd, &events).into_entries()))
+            }
+            Err(Error::OpenCfdNotFound) => load_closed_fee_ledger(&mut conn, id).await,
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Build the fee ledger of a CFD from its events.
+pub(crate) fn build(cfd: &Cfd, events: &[CfdEvent]) -> FeeLedger {
+    let (long_leverage, short_leverage) =
+        long_and_short_leverage(cfd.taker_leverage, cfd.role, cfd.position);
+
+    let initial_funding_fee = FundingFee::calculate(
+        cfd.initial_price,
+        cfd.quantity,
+        long_leverage,
+        short_leverage,
+        cfd.initial_funding_rate,
+        SETTLEMENT_INTERVAL.whole_hours(),
+        cfd.contract_symbol,
+    )
+    .expect("values from db to be sane");
+
+    let ledger = FeeLedger::new(
+        cfd.position,
+        cfd.role,
+        cfd.initial_price,
+        cfd.quantity,
+        cfd.opening_fee,
+        initial_funding_fee,
+    );
+
+    events.iter().fold(ledger, FeeLedger::apply)
+}
+
+pub(crate) async fn insert_closed_fee_ledger(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+    entries: &[FeeLedgerEntry],
+) -> Result<()> {
+    let id = models::OrderId::from(id);
+
+    for entry in entries {
+        let timestamp = models::Timestamp::from(entry.timestamp);
+        let kind = models::FeeKind::from(entry.kind);
+        let funding_rate = entry.funding_rate.map(models::FundingRate::from);
+        let price = models::Price::from(entry.price);
+        let fee = models::Fees::from(entry.fee);
+        let balance = models::Fees::from(entry.balance);
+
+        let query_result = sqlx::query!(
+            r#"
+            INSERT INTO closed_fee_ledger
+            (
+                cfd_id,
+                timestamp,
+                kind,
+                funding_rate,
+                price,
+                fee,
+                balance
+            )
+            VALUES
+            (
+                (SELECT id FROM closed_cfds WHERE closed_cfds.order_id = $1),
+                $2, $3, $4, $5, $6, $7
+            )
+            "#,
+            id,
+            timestamp,
+            kind,
+            funding_rate,
+            price,
+            fee,
+            balance,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        if query_result.rows_affected() != 1 {
+            bail!("failed to insert into closed_fee_ledger");
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_closed_fee_ledger(
+    conn: &mut SqliteConnection,
+    id: OrderId,
+) -> Result<Option<Vec<FeeLedgerEntry>>> {
+    let id = models::OrderId::from(id);
+
+    let closed_cfd = sqlx::query!(
+        r#"
+        SELECT
+            id
+        FROM
+            closed_cfds
+        WHERE
+            closed_cfds.order_id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let cfd_id = match closed_cfd {
+        Some(row) => row.id,
+        None => return Ok(None),
+    };
+
+    let entries = sqlx::query!(
+        r#"
+        SELECT
+            timestamp as "timestamp: models::Timestamp",
+            kind as "kind: models::FeeKind",
+            funding_rate as "funding_rate: models::FundingRate",
+            price as "price: models::Price",
+            fee as "fee: models::Fees",
+            balance as "balance: models::Fees"
+        FROM
+            closed_fee_ledger
+        WHERE
+            closed_fee_ledger.cfd_id = $1
+        ORDER BY
+            closed_fee_ledger.id
+        "#,
+        cfd_id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| FeeLedgerEntry {
+        timestamp: row.timestamp.into(),
+        kind: row.kind.into(),
+        funding_rate: row.funding_rate.map(Into::into),
+        price: row.price.into(),
+        fee: row.fee.into(),
+        balance: row.balance.into(),
+    })
+    .collect();
+
+    Ok(Some(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory;
+    use crate::tests::dummy_cfd;
+    use model::EventKind;
+    use model::FeeKind;
+    use model::Timestamp;
+
+    #[tokio::test]
+    async fn given_open_cfd_then_ledger_built_from_events() {
+        let db = memory().await.unwrap();
+
+        let cfd = dummy_cfd();
+        db.insert_cfd(&cfd).await.unwrap();
+        db.append_event(CfdEvent {
+            timestamp: Timestamp::now(),
+            id: cfd.id(),
+            event: EventKind::ContractSetupCompleted { dlc: None },
+        })
+        .await
+        .unwrap();
+
+        let ledger = db.load_fee_ledger(cfd.id()).await.unwrap().unwrap();
+        let kinds = ledger.iter().map(|entry| entry.kind).collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec![FeeKind::Opening, FeeKind::Funding]);
+    }
+
+    #[tokio::test]
+    async fn given_unknown_cfd_then_no_ledger() {
+        let db = memory().await.unwrap();
+
+        let ledger = db.load_fee_ledger(OrderId::default()).await.unwrap();
+
+        assert!(ledger.is_none());
+    }
+}
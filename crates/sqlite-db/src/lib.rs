@@ -8,6 +8,7 @@ use futures::future::BoxFuture;
 use futures::FutureExt;
 use futures::Stream;
 use model::libp2p::PeerId;
+use model::olivia::OracleConfig;
 use model::CfdEvent;
 use model::ContractSymbol;
 use model::Contracts;
@@ -23,6 +24,7 @@ use model::Price;
 use model::Role;
 use model::TxFeeRate;
 use sqlx::migrate::MigrateError;
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::Acquire;
 use sqlx::SqliteConnection;
@@ -34,13 +36,18 @@ use std::str::FromStr;
 use std::sync::Arc;
 use time::Duration;
 
+pub use backup::latest_migration_version;
+pub use backup::validate_snapshot;
 pub use closed::*;
 pub use failed::*;
 use model::EventKind::RolloverCompleted;
 
+mod backup;
+mod blocked_peers;
 pub mod closed;
 pub mod event_log;
 pub mod failed;
+mod fee_ledger;
 mod impls;
 mod models;
 mod rollover;
@@ -139,8 +146,10 @@ pub async fn memory() -> Result<Connection> {
     Ok(Connection::new(pool))
 }
 
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    sqlx::migrate!("./migrations")
+    MIGRATOR
         .run(pool)
         .await
         .context("Failed to run migrations")?;
@@ -168,6 +177,7 @@ impl Connection {
         let tx_fee_rate = models::TxFeeRate::from(cfd.initial_tx_fee_rate());
         let counterparty_peer_id = cfd.counterparty_peer_id().map(models::PeerId::from);
         let contract_symbol = models::ContractSymbol::from(cfd.contract_symbol());
+        let oracle = models::OracleConfig::from(cfd.oracle().clone());
 
         let query_result = sqlx::query(
             r#"
@@ -185,8 +195,9 @@ impl Connection {
             opening_fee,
             initial_funding_rate,
             initial_tx_fee_rate,
-            contract_symbol
-        ) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)"#,
+            contract_symbol,
+            oracle
+        ) values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)"#,
         )
         .bind(&order_id)
         .bind(&offer_id)
@@ -209,6 +220,7 @@ impl Connection {
         .bind(&initial_funding_rate)
         .bind(&tx_fee_rate)
         .bind(&contract_symbol)
+        .bind(&oracle)
         .execute(&mut conn)
         .await?;
 
@@ -459,13 +471,15 @@ impl Connection {
                 (
                     events.name = $1 or
                     events.name = $2 or
-                    events.name= $3
+                    events.name= $3 or
+                    events.name = $4
                 )
             )
             "#,
             EventKind::COLLABORATIVE_SETTLEMENT_CONFIRMED,
             EventKind::CET_CONFIRMED,
             EventKind::REFUND_CONFIRMED,
+            EventKind::PUNISH_CONFIRMED,
         )
         .fetch_all(&mut *conn)
         .await?
@@ -526,6 +540,7 @@ pub struct Cfd {
     pub initial_funding_rate: FundingRate,
     pub initial_tx_fee_rate: TxFeeRate,
     pub contract_symbol: ContractSymbol,
+    pub oracle: OracleConfig,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -583,7 +598,8 @@ async fn load_cfd_row(conn: &mut SqliteConnection, id: OrderId) -> Result<Cfd, E
                 opening_fee as "opening_fee: models::OpeningFee",
                 initial_funding_rate as "initial_funding_rate: models::FundingRate",
                 initial_tx_fee_rate as "initial_tx_fee_rate: models::TxFeeRate",
-                contract_symbol as "contract_symbol: models::ContractSymbol"
+                contract_symbol as "contract_symbol: models::ContractSymbol",
+                oracle as "oracle: models::OracleConfig"
             from
                 cfds
             where
@@ -620,6 +636,10 @@ async fn load_cfd_row(conn: &mut SqliteConnection, id: OrderId) -> Result<Cfd, E
         initial_funding_rate: cfd_row.initial_funding_rate.into(),
         initial_tx_fee_rate: cfd_row.initial_tx_fee_rate.into(),
         contract_symbol: cfd_row.contract_symbol.into(),
+        oracle: cfd_row
+            .oracle
+            .map(OracleConfig::from)
+            .unwrap_or_else(OracleConfig::olivia),
     })
 }
 
@@ -804,6 +824,7 @@ mod tests {
             initial_funding_rate,
             initial_tx_fee_rate,
             contract_symbol,
+            oracle,
         } = load_cfd_row(&mut *conn, cfd.id()).await.unwrap();
 
         assert_eq!(cfd.id(), id);
@@ -823,6 +844,7 @@ mod tests {
         assert_eq!(cfd.initial_funding_rate(), initial_funding_rate);
         assert_eq!(cfd.initial_tx_fee_rate(), initial_tx_fee_rate);
         assert_eq!(cfd.contract_symbol(), contract_symbol);
+        assert_eq!(cfd.oracle(), &oracle);
     }
 
     #[tokio::test]
@@ -932,6 +954,23 @@ mod tests {
         assert_eq!(None, counterparty_peer_id);
     }
 
+    #[tokio::test]
+    async fn given_cfd_without_oracle_then_olivia_loaded() {
+        let db = memory().await.unwrap();
+        let mut conn = db.inner.acquire().await.unwrap();
+
+        let cfd = dummy_cfd();
+        db.insert_cfd(&cfd).await.unwrap();
+        sqlx::query("update cfds set oracle = null")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let super::Cfd { oracle, .. } = load_cfd_row(&mut *conn, cfd.id()).await.unwrap();
+
+        assert_eq!(oracle, OracleConfig::olivia());
+    }
+
     pub fn dummy_cfd() -> Cfd {
         dummy_taker_with_legacy_identity(
             "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
@@ -955,7 +994,8 @@ mod tests {
             OpeningFee::new(Amount::from_sat(2000)),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         )
     }
 
@@ -974,7 +1014,8 @@ mod tests {
             OpeningFee::new(Amount::from_sat(2000)),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         )
     }
 
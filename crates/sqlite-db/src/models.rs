@@ -707,7 +707,7 @@ impl From<PeerId> for model::libp2p::PeerId {
 
 impl_sqlx_type_display_from_str!(PeerId);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BitMexPriceEventId {
     /// The timestamp this price event refers to.
     timestamp: OffsetDateTime,
@@ -775,21 +775,14 @@ impl From<BitMexPriceEventId> for model::olivia::BitMexPriceEventId {
 }
 
 impl_sqlx_type_display_from_str!(BitMexPriceEventId);
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-enum IndexPrice {
-    Bxbt,
-    Beth,
-}
+/// The index the oracle attests to for a contract symbol, stored by name, e.g. `BXBT`.
+/// The index of a contract symbol in the catalogue, stored by name, e.g. `BXBT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct IndexPrice(model::olivia::IndexPrice);
 
 impl fmt::Display for IndexPrice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            IndexPrice::Bxbt => "BXBT",
-            IndexPrice::Beth => "BETH",
-        };
-
-        s.fmt(f)
+        write!(f, "{}", self.0)
     }
 }
 
@@ -797,29 +790,23 @@ impl FromStr for IndexPrice {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "BXBT" => Self::Bxbt,
-            "BETH" => Self::Beth,
-            _ => bail!("Index price {s} not supported"),
-        })
+        let index = s
+            .parse::<model::olivia::IndexPrice>()
+            .with_context(|| format!("Index price {s} not supported"))?;
+
+        Ok(Self(index))
     }
 }
 
 impl From<model::olivia::IndexPrice> for IndexPrice {
     fn from(index: model::olivia::IndexPrice) -> Self {
-        match index {
-            model::olivia::IndexPrice::Bxbt => IndexPrice::Bxbt,
-            model::olivia::IndexPrice::Beth => IndexPrice::Beth,
-        }
+        Self(index)
     }
 }
 
 impl From<IndexPrice> for model::olivia::IndexPrice {
     fn from(index: IndexPrice) -> Self {
-        match index {
-            IndexPrice::Bxbt => model::olivia::IndexPrice::Bxbt,
-            IndexPrice::Beth => model::olivia::IndexPrice::Beth,
-        }
+        index.0
     }
 }
 
@@ -868,6 +855,63 @@ impl From<FailedKind> for model::FailedKind {
 
 impl_sqlx_type_display_from_str!(FailedKind);
 
+/// The kind of a fee ledger entry.
+#[derive(Debug, Clone, Copy)]
+pub enum FeeKind {
+    Opening,
+    Funding,
+    PartialSettlement,
+}
+
+impl fmt::Display for FeeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FeeKind::Opening => "Opening",
+            FeeKind::Funding => "Funding",
+            FeeKind::PartialSettlement => "PartialSettlement",
+        };
+
+        s.fmt(f)
+    }
+}
+
+impl FromStr for FeeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s {
+            "Opening" => FeeKind::Opening,
+            "Funding" => FeeKind::Funding,
+            "PartialSettlement" => FeeKind::PartialSettlement,
+            other => bail!("Not a fee kind: {other}"),
+        };
+
+        Ok(kind)
+    }
+}
+
+impl From<FeeKind> for model::FeeKind {
+    fn from(kind: FeeKind) -> Self {
+        match kind {
+            FeeKind::Opening => model::FeeKind::Opening,
+            FeeKind::Funding => model::FeeKind::Funding,
+            FeeKind::PartialSettlement => model::FeeKind::PartialSettlement,
+        }
+    }
+}
+
+impl From<model::FeeKind> for FeeKind {
+    fn from(kind: model::FeeKind) -> Self {
+        match kind {
+            model::FeeKind::Opening => FeeKind::Opening,
+            model::FeeKind::Funding => FeeKind::Funding,
+            model::FeeKind::PartialSettlement => FeeKind::PartialSettlement,
+        }
+    }
+}
+
+impl_sqlx_type_display_from_str!(FeeKind);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Settlement {
     Collaborative {
@@ -889,6 +933,12 @@ pub enum Settlement {
         vout: Vout,
         payout: Payout,
     },
+    Punish {
+        commit_txid: Txid,
+        txid: Txid,
+        vout: Vout,
+        payout: Payout,
+    },
 }
 
 impl From<Settlement> for model::Settlement {
@@ -929,6 +979,17 @@ impl From<Settlement> for model::Settlement {
                 vout: vout.into(),
                 payout: payout.into(),
             },
+            Settlement::Punish {
+                commit_txid,
+                txid,
+                vout,
+                payout,
+            } => model::Settlement::Punish {
+                commit_txid: commit_txid.into(),
+                txid: txid.into(),
+                vout: vout.into(),
+                payout: payout.into(),
+            },
         }
     }
 }
@@ -984,30 +1045,80 @@ pub fn into_complete_fee_and_flow(
 }
 
 /// Trading pair of the Cfd
-#[derive(Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
-pub enum ContractSymbol {
-    BtcUsd,
-    EthUsd,
+///
+/// Stored by the wire name of the contract symbol, see [`model::ContractSymbol::wire_name`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ContractSymbol(model::ContractSymbol);
+
+impl fmt::Display for ContractSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.wire_name())
+    }
+}
+
+impl FromStr for ContractSymbol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let contract_symbol = s
+            .parse::<model::ContractSymbol>()
+            .with_context(|| format!("Failed to parse contract symbol {s}"))?;
+
+        Ok(Self(contract_symbol))
+    }
 }
 
 impl From<model::ContractSymbol> for ContractSymbol {
     fn from(contract_symbol: model::ContractSymbol) -> Self {
-        match contract_symbol {
-            model::ContractSymbol::BtcUsd => ContractSymbol::BtcUsd,
-            model::ContractSymbol::EthUsd => ContractSymbol::EthUsd,
-        }
+        Self(contract_symbol)
     }
 }
 
 impl From<ContractSymbol> for model::ContractSymbol {
     fn from(contract_symbol: ContractSymbol) -> Self {
-        match contract_symbol {
-            ContractSymbol::BtcUsd => model::ContractSymbol::BtcUsd,
-            ContractSymbol::EthUsd => model::ContractSymbol::EthUsd,
-        }
+        contract_symbol.0
+    }
+}
+
+impl_sqlx_type_display_from_str!(ContractSymbol);
+
+/// The oracle attesting to the price events of a CFD
+///
+/// Stored as JSON, so that the base URL and event prefix can be stored alongside the public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleConfig(model::olivia::OracleConfig);
+
+impl fmt::Display for OracleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(&self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl FromStr for OracleConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let oracle = serde_json::from_str(s).context("Failed to deserialize oracle")?;
+
+        Ok(Self(oracle))
     }
 }
 
+impl From<model::olivia::OracleConfig> for OracleConfig {
+    fn from(oracle: model::olivia::OracleConfig) -> Self {
+        Self(oracle)
+    }
+}
+
+impl From<OracleConfig> for model::olivia::OracleConfig {
+    fn from(oracle: OracleConfig) -> Self {
+        oracle.0
+    }
+}
+
+impl_sqlx_type_display_from_str!(OracleConfig);
+
 #[derive(Debug)]
 pub struct User {
     pub id: u32,
@@ -15,6 +15,7 @@ mod tests {
     use bdk::bitcoin::Amount;
     use model::libp2p::PeerId;
     use model::olivia::BitMexPriceEventId;
+    use model::olivia::OracleConfig;
     use model::Cfd;
     use model::CfdEvent;
     use model::CompleteFee;
@@ -56,7 +57,8 @@ mod tests {
             OpeningFee::new(Amount::from_sat(2000)),
             FundingRate::default(),
             TxFeeRate::default(),
-            ContractSymbol::BtcUsd,
+            ContractSymbol::BTC_USD,
+            OracleConfig::olivia(),
         )
     }
 
@@ -121,6 +121,7 @@ async fn load_revoked_commit_transactions(
             SELECT
                 encsig_ours as "encsig_ours: models::AdaptorSignature",
                 publication_pk_theirs as "publication_pk_theirs: models::PublicKey",
+                publication_pk_ours as "publication_pk_ours: models::PublicKey",
                 revocation_sk_theirs as "revocation_sk_theirs: models::SecretKey",
                 revocation_sk_ours as "revocation_sk_ours: models::SecretKey",
                 script_pubkey,
@@ -147,6 +148,9 @@ async fn load_revoked_commit_transactions(
                 .map(|revocation_sk_ours| revocation_sk_ours.into()),
             revocation_sk_theirs: row.revocation_sk_theirs.into(),
             publication_pk_theirs: row.publication_pk_theirs.into(),
+            publication_pk_ours: row
+                .publication_pk_ours
+                .map(|publication_pk_ours| publication_pk_ours.into()),
             script_pubkey: Script::from_hex(row.script_pubkey.as_str())?,
             txid: row.txid.into(),
             settlement_event_id: row
@@ -172,6 +172,7 @@ async fn insert_revoked_commit_transaction(
     let revocation_sk_theirs = models::SecretKey::from(revoked.revocation_sk_theirs);
     let revocation_sk_ours = revoked.revocation_sk_ours.map(models::SecretKey::from);
     let publication_pk_theirs = models::PublicKey::from(revoked.publication_pk_theirs);
+    let publication_pk_ours = revoked.publication_pk_ours.map(models::PublicKey::from);
     let encsig_ours = models::AdaptorSignature::from(revoked.encsig_ours);
     let txid = models::Txid::from(revoked.txid);
     let settlement_event_id = revoked
@@ -192,8 +193,9 @@ async fn insert_revoked_commit_transaction(
                     settlement_event_id,
                     complete_fee,
                     complete_fee_flow,
-                    revocation_sk_ours
-                ) values ( (select id from cfds where cfds.order_id = $1), $2, $3, $4, $5, $6, $7, $8, $9, $10 )
+                    revocation_sk_ours,
+                    publication_pk_ours
+                ) values ( (select id from cfds where cfds.order_id = $1), $2, $3, $4, $5, $6, $7, $8, $9, $10, $11 )
             "#,
         order_id,
         encsig_ours,
@@ -204,7 +206,8 @@ async fn insert_revoked_commit_transaction(
         settlement_event_id,
         complete_fee,
         complete_fee_flow,
-        revocation_sk_ours
+        revocation_sk_ours,
+        publication_pk_ours
     )
     .execute(&mut *conn)
     .await?;
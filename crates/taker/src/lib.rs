@@ -1,14 +1,19 @@
 use crate::bitcoin::util::bip32::ExtendedPrivKey;
 use crate::routes::IdentityInfo;
 use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
 use daemon::bdk::bitcoin;
 use daemon::bdk::FeeRate;
-use daemon::libp2p_utils::create_connect_tcp_multiaddr;
+use daemon::cfd_backup;
+use daemon::fee_estimate;
+use daemon::libp2p_utils::create_connect_multiaddr_from_address;
 use daemon::monitor;
 use daemon::oracle;
+use daemon::position_metrics;
+use daemon::price_feed;
 use daemon::projection;
 use daemon::seed;
 use daemon::seed::AppSeed;
@@ -18,18 +23,26 @@ use daemon::seed::ThreadSafeSeed;
 use daemon::wallet;
 use daemon::wallet::TAKER_WALLET_ID;
 use daemon::Environment;
+use daemon::Maker;
 use daemon::TakerActorSystem;
 use daemon::N_PAYOUTS;
 use libp2p_core::PeerId;
-use model::olivia;
+use model::olivia::OracleConfig;
+use model::olivia::Oracles;
 use model::Identity;
 use model::Role;
+use model::TxFeeRate;
 use model::SETTLEMENT_INTERVAL;
 use rocket::async_trait;
 use rocket_cookie_auth::users::Users;
 use shared_bin::catchers::default_catchers;
+use shared_bin::cli::backup_passphrase;
+use shared_bin::cli::parse_oracle;
+use shared_bin::cli::resolve_contract_symbols;
+use shared_bin::cli::Bitcoind;
+use shared_bin::cli::Command;
 use shared_bin::cli::Network;
-use shared_bin::cli::Withdraw;
+use shared_bin::contracts::load_contracts;
 use shared_bin::fairings;
 use shared_bin::logger;
 use shared_bin::logger::LevelFilter;
@@ -41,12 +54,14 @@ use std::env;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
+use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio_extras::Tasks;
-use xtras::supervisor::always_restart;
+use xtra_bitmex_price_feed::Source;
+use xtra_libp2p::multiaddress_ext::MultiaddrExt;
 use xtras::supervisor::Supervisor;
 
 mod routes;
@@ -86,32 +101,86 @@ impl std::fmt::Display for Password {
 
 #[derive(Parser)]
 pub struct Opts {
-    /// The IP address or hostname of the other party (i.e. the maker).
+    /// The address of the other party (i.e. the maker).
+    ///
+    /// Given as `<host>:<port>` with an IP address or hostname as host, or as a multiaddr, e.g.
+    /// `/dns/<hostname>/tcp/<port>`. Several addresses of the same maker can be given separated by
+    /// commas, they are tried in order. An onion address (`<address>.onion:<port>`) can be given
+    /// in combination with `tor-socks-proxy`.
+    ///
+    /// Can be given multiple times to connect to several makers. The n-th `maker` belongs to the
+    /// n-th `maker-id` and `maker-peer-id`.
     ///
     /// If not specified it defaults to the itchysats maker for the mainnet or testnet.
     #[clap(long)]
-    maker: Option<String>,
+    maker: Vec<String>,
 
     /// The public key of the maker as a 32 byte hex string.
     ///
     /// If not specified it defaults to the itchysats maker-id for mainnet or testnet.
     #[clap(long, value_parser(parse_x25519_pubkey))]
-    maker_id: Option<x25519_dalek::PublicKey>,
+    maker_id: Vec<x25519_dalek::PublicKey>,
 
     /// Maker's peer id, required for establishing libp2p encrypted connection.
     ///
     /// If not specified it defaults to the itchysats maker-peer-id for mainnet or testnet.
     #[clap(long)]
-    maker_peer_id: Option<PeerId>,
+    maker_peer_id: Vec<PeerId>,
+
+    /// Where to get prices from, either `bitmex` or `file:<path>` to replay quotes from a file.
+    ///
+    /// Can be given multiple times, in which case the median over all sources is used.
+    #[clap(long, default_value = "bitmex")]
+    price_feed: Vec<Source>,
+
+    /// Use another oracle than Olivia for a contract symbol.
+    ///
+    /// Given as `<SYMBOL>=<PUBLIC_KEY>,<BASE_URL>[,<EVENT_PREFIX>]`, can be given once per symbol.
+    #[clap(long, value_parser(parse_oracle))]
+    oracle: Vec<(String, OracleConfig)>,
 
     /// The IP address to listen on for the HTTP API.
     #[clap(long, default_value = "127.0.0.1:8000")]
     http_address: SocketAddr,
 
+    /// The SOCKS5 proxy of a Tor daemon, e.g. `127.0.0.1:9050`.
+    ///
+    /// If specified, connections to the maker are made through Tor and the maker does not learn
+    /// our IP address.
+    #[clap(long)]
+    tor_socks_proxy: Option<SocketAddr>,
+
+    /// Estimate transaction fee rates targeting confirmation within this many blocks.
+    ///
+    /// Offers with a fee rate far off the estimate are ignored.
+    #[clap(long, default_value = "6")]
+    tx_fee_rate_target_blocks: u16,
+
+    /// The lowest transaction fee rate we estimate and accept for rollovers, in sat/vbyte.
+    #[clap(long, default_value = "1")]
+    min_tx_fee_rate: TxFeeRate,
+
+    /// The highest transaction fee rate we estimate and accept for rollovers, in sat/vbyte.
+    #[clap(long, default_value = "100")]
+    max_tx_fee_rate: TxFeeRate,
+
+    /// If enabled, settlements and rollovers the maker asks for are carried out right away.
+    ///
+    /// Otherwise they are shown on the CFD, waiting to be accepted or rejected.
+    #[clap(long)]
+    accept_maker_requests: bool,
+
     /// Where to permanently store data, defaults to the current working directory.
     #[clap(long)]
     data_dir: Option<PathBuf>,
 
+    /// Where to write the backups of open CFDs to, defaults to `cfd_backups` in the data dir.
+    ///
+    /// Together with the seed these backups are sufficient to recover open CFDs. The directory
+    /// should be on a different disk than the data dir.
+    #[clap(long)]
+    cfd_backup_dir: Option<PathBuf>,
+
     /// If enabled logs will be in json format
     #[clap(short, long)]
     json: bool,
@@ -191,11 +260,19 @@ impl Opts {
         let maker_peer_id = Self::maker_peer_id(&network);
 
         Ok(Self {
-            maker: Some(maker),
-            maker_id: Some(maker_id),
-            maker_peer_id: Some(maker_peer_id),
+            maker: vec![maker],
+            maker_id: vec![maker_id],
+            maker_peer_id: vec![maker_peer_id],
+            price_feed: vec![Source::Bitmex],
+            oracle: Vec::new(),
             http_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port),
+            tor_socks_proxy: None,
+            accept_maker_requests: false,
+            tx_fee_rate_target_blocks: 6,
+            min_tx_fee_rate: TxFeeRate::default(),
+            max_tx_fee_rate: TxFeeRate::new(NonZeroU32::new(100).expect("non-zero")),
             data_dir: Some(PathBuf::from(data_dir)),
+            cfd_backup_dir: None,
             json: false,
             json_span_list: false,
             instrumentation: false,
@@ -217,21 +294,58 @@ impl Opts {
         self.network.clone().unwrap_or_default()
     }
 
-    fn maker(&self) -> Result<(String, x25519_dalek::PublicKey, PeerId)> {
-        let network = PublicNetwork::try_from(self.network())?;
+    fn fee_estimate(&self) -> Result<fee_estimate::Config> {
+        fee_estimate::Config::new(
+            self.tx_fee_rate_target_blocks,
+            self.min_tx_fee_rate,
+            self.max_tx_fee_rate,
+        )
+    }
 
-        let maker_url = self
-            .maker
-            .clone()
-            .unwrap_or_else(|| Self::maker_url(&network));
+    fn makers(&self) -> Result<Vec<(String, x25519_dalek::PublicKey, PeerId)>> {
+        let n_makers = self.maker.len();
 
-        let maker_id = self.maker_id.unwrap_or_else(|| Self::maker_id(&network));
+        // A single maker may leave out any of its details, they default to the itchysats maker
+        if n_makers <= 1 && self.maker_id.len() <= 1 && self.maker_peer_id.len() <= 1 {
+            let network = PublicNetwork::try_from(self.network())?;
 
-        let maker_peer_id = self
-            .maker_peer_id
-            .unwrap_or_else(|| Self::maker_peer_id(&network));
+            let maker_url = self
+                .maker
+                .first()
+                .cloned()
+                .unwrap_or_else(|| Self::maker_url(&network));
+
+            let maker_id = self
+                .maker_id
+                .first()
+                .copied()
+                .unwrap_or_else(|| Self::maker_id(&network));
+
+            let maker_peer_id = self
+                .maker_peer_id
+                .first()
+                .copied()
+                .unwrap_or_else(|| Self::maker_peer_id(&network));
+
+            return Ok(vec![(maker_url, maker_id, maker_peer_id)]);
+        }
 
-        Ok((maker_url, maker_id, maker_peer_id))
+        ensure!(
+            self.maker_id.len() == n_makers && self.maker_peer_id.len() == n_makers,
+            "Every maker needs a `maker`, `maker-id` and `maker-peer-id`"
+        );
+
+        let makers = self
+            .maker
+            .iter()
+            .zip(self.maker_id.iter())
+            .zip(self.maker_peer_id.iter())
+            .map(|((maker_url, maker_id), maker_peer_id)| {
+                (maker_url.clone(), *maker_id, *maker_peer_id)
+            })
+            .collect();
+
+        Ok(makers)
     }
 
     fn maker_url(network: &PublicNetwork) -> String {
@@ -278,11 +392,13 @@ impl From<PublicNetwork> for Network {
         match public {
             PublicNetwork::Mainnet => Network::Mainnet {
                 electrum: MAINNET_ELECTRUM.to_string(),
-                withdraw: None,
+                bitcoind: Bitcoind::default(),
+                command: None,
             },
             PublicNetwork::Testnet => Network::Testnet {
                 electrum: TESTNET_ELECTRUM.to_string(),
-                withdraw: None,
+                bitcoind: Bitcoind::default(),
+                command: None,
             },
         }
     }
@@ -313,7 +429,7 @@ fn parse_app_seed(s: &str) -> Result<[u8; 32]> {
 }
 
 pub async fn run(opts: Opts) -> Result<()> {
-    let (maker_url, maker_id, maker_peer_id) = opts.maker()?;
+    let makers = opts.makers()?;
 
     let network = opts.network();
 
@@ -348,9 +464,35 @@ pub async fn run(opts: Opts) -> Result<()> {
         "CFDs created with this release will settle after {settlement_interval_hours} hours"
     );
 
-    let maker_identity = Identity::new(maker_id);
+    let catalogue = load_contracts(&data_dir)
+        .await
+        .context("Failed to load contract catalogue")?;
+    let contract_symbols = catalogue.symbols();
+    tracing::info!("Offering contract symbols: {contract_symbols:?}");
 
     let bitcoin_network = network.bitcoin_network();
+    let chain = network.chain()?;
+
+    if let Some(Command::RestoreBackup {
+        input,
+        passphrase_file,
+    }) = network.command()
+    {
+        let passphrase = backup_passphrase(passphrase_file.as_deref()).await?;
+        let backup = tokio::fs::read(input)
+            .await
+            .with_context(|| format!("Failed to read backup {}", input.display()))?;
+        daemon::backup::restore(
+            &backup,
+            &passphrase,
+            &data_dir,
+            "taker.sqlite",
+            &seed::TAKER_SEED_FILES,
+        )
+        .await?;
+
+        return Ok(());
+    }
 
     let wallet_seed_file = &data_dir.join(seed::TAKER_WALLET_SEED_FILE);
     let wallet_seed: Arc<ThreadSafeSeed> = match opts.app_seed {
@@ -384,18 +526,14 @@ pub async fn run(opts: Opts) -> Result<()> {
 
     let mut wallet_dir = data_dir.clone();
     wallet_dir.push(TAKER_WALLET_ID);
-    let (wallet, wallet_feed_receiver) = wallet::Actor::spawn(
-        network.electrum(),
-        ext_priv_key,
-        wallet_dir,
-        wallet_seed.is_managed(),
-    )?;
+    let (wallet, wallet_feed_receiver) =
+        wallet::Actor::spawn(&chain, ext_priv_key, wallet_dir, wallet_seed.is_managed())?;
 
-    if let Some(Withdraw::Withdraw {
+    if let Some(Command::Withdraw {
         amount,
         address,
         fee,
-    }) = network.withdraw()
+    }) = network.command()
     {
         wallet
             .send(wallet::Withdraw {
@@ -416,16 +554,88 @@ pub async fn run(opts: Opts) -> Result<()> {
 
     let db = sqlite_db::connect(data_dir.join("taker.sqlite"), true).await?;
 
+    if let Some(Command::ExportClosedCfds { format, output }) = network.command() {
+        daemon::export::write_closed_positions(&db, *format, output.as_deref()).await?;
+
+        return Ok(());
+    }
+
+    if let Some(Command::Backup {
+        output,
+        passphrase_file,
+    }) = network.command()
+    {
+        let passphrase = backup_passphrase(passphrase_file.as_deref()).await?;
+        let backup =
+            daemon::backup::create(&db, &data_dir, &seed::TAKER_SEED_FILES, &passphrase).await?;
+        tokio::fs::write(output, backup)
+            .await
+            .with_context(|| format!("Failed to write backup to {}", output.display()))?;
+
+        return Ok(());
+    }
+
+    let cfd_backup_dir = opts
+        .cfd_backup_dir
+        .clone()
+        .unwrap_or_else(|| {
+            let cfd_backup_dir = data_dir.join("cfd_backups");
+            tracing::warn!(
+                "No --cfd-backup-dir configured, writing CFD backups to {} in the data directory. Put them on a different disk to survive losing the database",
+                cfd_backup_dir.display()
+            );
+
+            cfd_backup_dir
+        });
+    let cfd_backup_sink = cfd_backup::LocalDirectory::new(cfd_backup_dir);
+    let cfd_backup_key = wallet_seed.derive_cfd_backup_key();
+
+    if let Some(Command::RecoverCfds) = network.command() {
+        let recovery = cfd_backup::recover(
+            &db,
+            &cfd_backup_sink,
+            &cfd_backup_key,
+            chain.backend(bitcoin_network)?,
+        )
+        .await?;
+        tracing::info!(
+            "Recovered {} CFDs: {:?}",
+            recovery.recovered.len(),
+            recovery.recovered
+        );
+        if recovery.skipped > 0 {
+            tracing::error!(
+                "Skipped {} CFD backups which could not be decrypted or are outdated",
+                recovery.skipped
+            );
+        }
+
+        return Ok(());
+    }
+
     // Create actors
 
-    let possible_addresses = resolve_maker_addresses(maker_url.as_str()).await?;
+    let mut maker_addresses = Vec::new();
+    for (maker_url, maker_id, maker_peer_id) in makers {
+        let maker_multiaddrs = maker_url
+            .split(',')
+            .map(|address| create_connect_multiaddr_from_address(address.trim(), maker_peer_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        if opts.tor_socks_proxy.is_none() {
+            for maker_multiaddr in &maker_multiaddrs {
+                ensure!(
+                    maker_multiaddr.is_tcp(),
+                    "Connecting to {maker_multiaddr} requires a Tor SOCKS proxy"
+                );
+            }
+        }
 
-    // Assume that the first resolved ipv4 address is good enough for libp2p.
-    let maker_libp2p_address = possible_addresses
-        .iter()
-        .find(|x| x.is_ipv4())
-        .context("Could not resolve maker URL")?;
-    let maker_multiaddr = create_connect_tcp_multiaddr(maker_libp2p_address, maker_peer_id)?;
+        maker_addresses.push(Maker {
+            identity: Identity::new(maker_id),
+            multiaddrs: maker_multiaddrs,
+        });
+    }
 
     let hex_pk = hex::encode(identities.identity_pk.to_bytes());
     let peer_id = identities.libp2p.public().to_peer_id().to_string();
@@ -442,16 +652,12 @@ pub async fn run(opts: Opts) -> Result<()> {
         Err(_) => Environment::new("binary"),
     };
 
-    let (supervisor, price_feed_actor) =
-        Supervisor::<_, xtra_bitmex_price_feed::Error>::with_policy(
-            {
-                let network = network.bitmex_network();
-                move || xtra_bitmex_price_feed::Actor::new(network)
-            },
-            always_restart(),
-        );
-
-    tasks.add(supervisor.run_log_summary());
+    let price_feed_actor = price_feed::spawn(
+        &opts.price_feed,
+        &contract_symbols,
+        network.bitmex_network(),
+        &mut tasks,
+    );
 
     let (feed_senders, feed_receivers) = projection::feeds();
     let feed_senders = Arc::new(feed_senders);
@@ -459,35 +665,42 @@ pub async fn run(opts: Opts) -> Result<()> {
     let (supervisor, projection_actor) = Supervisor::new({
         let db = db.clone();
         let price_feed = price_feed_actor.clone();
+        let contract_symbols = contract_symbols.clone();
         move || {
             projection::Actor::new(
                 db.clone(),
                 bitcoin_network,
                 price_feed.clone().into(),
                 Role::Taker,
+                contract_symbols.clone(),
                 feed_senders.clone(),
             )
         }
     });
     tasks.add(supervisor.run_log_summary());
 
+    let oracles = Oracles::new(resolve_contract_symbols(&catalogue, &opts.oracle)?);
     let taker = TakerActorSystem::new(
         db.clone(),
         wallet.clone(),
-        *olivia::PUBLIC_KEY,
+        oracles.clone(),
         identities,
-        |executor| oracle::Actor::new(db.clone(), executor),
-        |executor| {
-            let electrum = network.electrum().to_string();
-            monitor::Actor::new(db.clone(), electrum, executor)
-        },
+        |executor| oracle::Actor::new(db.clone(), executor, oracles, &contract_symbols),
+        |executor| monitor::Actor::new(db.clone(), chain.backend(bitcoin_network)?, executor),
         price_feed_actor,
         N_PAYOUTS,
         Duration::from_secs(10),
+        opts.tor_socks_proxy,
         projection_actor.clone(),
-        maker_identity,
-        maker_multiaddr,
+        maker_addresses,
         environment,
+        cfd_backup::Actor::new(db.clone(), Box::new(cfd_backup_sink), cfd_backup_key),
+        position_metrics::Actor::new(db.clone(), contract_symbols.clone()),
+        Some(fee_estimate::Actor::new(
+            chain.backend(bitcoin_network)?,
+            opts.fee_estimate()?,
+        )),
+        opts.accept_maker_requests,
     )?;
 
     if let Some(password) = opts.password {
@@ -515,6 +728,9 @@ pub async fn run(opts: Opts) -> Result<()> {
                 routes::feed,
                 routes::post_order_request,
                 routes::post_cfd_action,
+                routes::get_cfd_fees,
+                routes::get_closed_cfds_export,
+                routes::post_backup,
                 routes::post_withdraw_request,
                 routes::put_sync_wallet,
                 shared_bin::routes::get_health_check,
@@ -529,6 +745,7 @@ pub async fn run(opts: Opts) -> Result<()> {
         .register("/api", default_catchers())
         .manage(users)
         .manage(data_dir)
+        .manage(contract_symbols)
         .manage(network)
         .mount("/", rocket::routes![routes::dist, routes::index])
         .register("/", default_catchers())
@@ -551,19 +768,6 @@ pub async fn run(opts: Opts) -> Result<()> {
     Ok(())
 }
 
-async fn resolve_maker_addresses(maker_addr: &str) -> Result<Vec<SocketAddr>> {
-    let possible_addresses = tokio::net::lookup_host(maker_addr)
-        .await?
-        .collect::<Vec<_>>();
-
-    tracing::debug!(
-        "Resolved {} to [{}]",
-        maker_addr,
-        itertools::join(possible_addresses.iter(), ",")
-    );
-    Ok(possible_addresses)
-}
-
 struct RocketAuthDbConnection {
     inner: sqlite_db::Connection,
 }
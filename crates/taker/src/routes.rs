@@ -3,11 +3,13 @@
 use daemon::bdk;
 use daemon::bdk::bitcoin::Amount;
 use daemon::bdk::bitcoin::Network;
-use daemon::bdk::blockchain::ElectrumBlockchain;
+use daemon::bdk::blockchain::AnyBlockchain;
 use daemon::bdk::sled;
+use daemon::export;
 use daemon::identify;
 use daemon::online_status::ConnectionStatus;
 use daemon::oracle;
+use daemon::price_feed;
 use daemon::projection;
 use daemon::projection::CfdAction;
 use daemon::projection::FeedReceivers;
@@ -17,7 +19,9 @@ use daemon::wallet;
 use daemon::TakerActorSystem;
 use http_api_problem::HttpApiProblem;
 use http_api_problem::StatusCode;
+use model::ContractSymbol;
 use model::Contracts;
+use model::FeeLedgerEntry;
 use model::Leverage;
 use model::OrderId;
 use model::Price;
@@ -40,6 +44,7 @@ use rust_embed::RustEmbed;
 use rust_embed_rocket::EmbeddedFileExt;
 use serde::Deserialize;
 use serde::Serialize;
+use shared_bin::best_offer_sse_events;
 use shared_bin::ToSseEvent;
 use std::borrow::Cow;
 use std::path::PathBuf;
@@ -47,11 +52,8 @@ use tokio::select;
 use tokio::sync::watch;
 use tracing::instrument;
 
-type Taker = TakerActorSystem<
-    oracle::Actor,
-    wallet::Actor<ElectrumBlockchain, sled::Tree>,
-    xtra_bitmex_price_feed::Actor,
->;
+type Taker =
+    TakerActorSystem<oracle::Actor, wallet::Actor<AnyBlockchain, sled::Tree>, price_feed::Actor>;
 
 const HEARTBEAT_INTERVAL_SECS: u64 = 5;
 
@@ -70,9 +72,11 @@ pub async fn feed(
     rx_maker_status: &State<watch::Receiver<ConnectionStatus>>,
     rx_maker_identity: &State<watch::Receiver<Option<identify::PeerInfo>>>,
     identity_info: &State<IdentityInfo>,
+    contract_symbols: &State<Vec<ContractSymbol>>,
     _user: User,
 ) -> EventStream![] {
     let rx = rx.inner();
+    let contract_symbols = contract_symbols.inner().clone();
     let mut rx_cfds = rx.cfds.clone();
     let mut rx_offers = rx.offers.clone();
 
@@ -97,10 +101,10 @@ pub async fn feed(
         yield Event::json(&identity).event("identity");
 
         let offers = rx_offers.borrow().clone();
-        yield Event::json(&offers.btcusd_long).event("btcusd_long_offer");
-        yield Event::json(&offers.btcusd_short).event("btcusd_short_offer");
-        yield Event::json(&offers.ethusd_long).event("ethusd_long_offer");
-        yield Event::json(&offers.ethusd_short).event("ethusd_short_offer");
+        for event in best_offer_sse_events(&offers, &contract_symbols) {
+            yield event;
+        }
+        yield offers.to_sse_event();
 
         let cfds = rx_cfds.borrow().clone();
         if let Some(cfds) = cfds {
@@ -123,10 +127,10 @@ pub async fn feed(
                 },
                 Ok(()) = rx_offers.changed() => {
                     let offers = rx_offers.borrow().clone();
-                    yield Event::json(&offers.btcusd_long).event("btcusd_long_offer");
-                    yield Event::json(&offers.btcusd_short).event("btcusd_short_offer");
-                    yield Event::json(&offers.ethusd_long).event("ethusd_long_offer");
-                    yield Event::json(&offers.ethusd_short).event("ethusd_short_offer");
+                    for event in best_offer_sse_events(&offers, &contract_symbols) {
+                        yield event;
+                    }
+                    yield offers.to_sse_event();
                 }
                 Ok(()) = rx_cfds.changed() => {
                     let cfds = rx_cfds.borrow().clone();
@@ -187,11 +191,16 @@ pub async fn post_order_request(
     Ok(())
 }
 
-#[rocket::post("/cfd/<order_id>/<action>")]
+/// Invoke `action` on a CFD.
+///
+/// Settling accepts an optional `quantity` to only settle that many contracts; the remaining
+/// contracts stay open.
+#[rocket::post("/cfd/<order_id>/<action>?<quantity>")]
 #[instrument(name = "POST /cfd/<order_id>/<action>", skip(taker, _user), err)]
 pub async fn post_cfd_action(
     order_id: Uuid,
     action: String,
+    quantity: Option<u64>,
     taker: &State<Taker>,
     _user: User,
 ) -> Result<(), HttpApiProblem> {
@@ -204,12 +213,19 @@ pub async fn post_cfd_action(
         CfdAction::AcceptOrder
         | CfdAction::RejectOrder
         | CfdAction::AcceptSettlement
-        | CfdAction::RejectSettlement => {
+        | CfdAction::RejectSettlement
+        | CfdAction::RollOver => {
             return Err(HttpApiProblem::new(StatusCode::BAD_REQUEST)
                 .detail(format!("taker cannot invoke action {action}")));
         }
         CfdAction::Commit => taker.commit(order_id).await,
-        CfdAction::Settle => taker.propose_settlement(order_id).await,
+        CfdAction::Settle => {
+            taker
+                .propose_settlement(order_id, quantity.map(Contracts::new))
+                .await
+        }
+        CfdAction::AcceptMakerRequest => taker.accept_maker_request(order_id).await,
+        CfdAction::RejectMakerRequest => taker.reject_maker_request(order_id).await,
     };
 
     result.map_err(|e| {
@@ -221,6 +237,91 @@ pub async fn post_cfd_action(
     Ok(())
 }
 
+#[rocket::get("/cfd/<order_id>/fees")]
+#[instrument(name = "GET /cfd/<order_id>/fees", skip(taker, _user), err)]
+pub async fn get_cfd_fees(
+    order_id: Uuid,
+    taker: &State<Taker>,
+    _user: User,
+) -> Result<Json<Vec<FeeLedgerEntry>>, HttpApiProblem> {
+    let order_id = OrderId::from(order_id);
+
+    let ledger = taker.fee_ledger(order_id).await.map_err(|e| {
+        HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .title("Loading fees failed")
+            .detail(format!("{e:#}"))
+    })?;
+
+    match ledger {
+        Some(ledger) => Ok(Json(ledger)),
+        None => Err(HttpApiProblem::new(StatusCode::NOT_FOUND)
+            .title("CFD not found")
+            .detail(format!("No open or closed CFD with order id {order_id}"))),
+    }
+}
+
+/// Export all closed CFDs as CSV (default) or JSON.
+#[rocket::get("/cfds/closed/export?<format>")]
+#[instrument(name = "GET /cfds/closed/export", skip(taker, _user), err)]
+pub async fn get_closed_cfds_export(
+    format: Option<&str>,
+    taker: &State<Taker>,
+    _user: User,
+) -> Result<(ContentType, String), HttpApiProblem> {
+    let format = format
+        .map(str::parse::<export::Format>)
+        .transpose()
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::BAD_REQUEST)
+                .title("Invalid export format")
+                .detail(format!("{e:#}"))
+        })?
+        .unwrap_or(export::Format::Csv);
+
+    let export = taker
+        .closed_positions()
+        .await
+        .and_then(|positions| format.render(&positions))
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .title("Exporting closed CFDs failed")
+                .detail(format!("{e:#}"))
+        })?;
+
+    let content_type = match format {
+        export::Format::Csv => ContentType::CSV,
+        export::Format::Json => ContentType::JSON,
+    };
+
+    Ok((content_type, export))
+}
+
+#[derive(Clone, Deserialize)]
+pub struct BackupRequest {
+    passphrase: String,
+}
+
+/// Download an encrypted backup of the database and the seed files.
+#[rocket::post("/backup", data = "<backup_request>")]
+#[instrument(name = "POST /backup", skip_all, err)]
+pub async fn post_backup(
+    backup_request: Json<BackupRequest>,
+    taker: &State<Taker>,
+    data_dir: &State<PathBuf>,
+    _user: User,
+) -> Result<(ContentType, Vec<u8>), HttpApiProblem> {
+    let backup = taker
+        .backup(data_dir, &backup_request.passphrase)
+        .await
+        .map_err(|e| {
+            HttpApiProblem::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .title("Creating backup failed")
+                .detail(format!("{e:#}"))
+        })?;
+
+    Ok((ContentType::Binary, backup))
+}
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct MarginRequest {
     pub price: Price,
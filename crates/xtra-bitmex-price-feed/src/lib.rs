@@ -5,28 +5,74 @@ use futures::TryStreamExt;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 use time::OffsetDateTime;
 use tracing::Instrument;
+use xtra::Handler;
 use xtra_productivity::xtra_productivity;
 
+pub mod median;
+pub mod replay;
+
 pub const QUOTE_INTERVAL_MINUTES: i64 = 1;
 
-/// Subscribes to BitMEX and retrieves latest quotes for BTCUSD and ETHUSD.
+/// An actor that can be used as a price feed.
+///
+/// Implemented for every actor that hands out [`LatestQuotes`] and stops with an [`Error`].
+pub trait PriceFeed:
+    Handler<GetLatestQuotes, Return = LatestQuotes> + xtra::Actor<Stop = Error>
+{
+}
+
+impl<T> PriceFeed for T where
+    T: Handler<GetLatestQuotes, Return = LatestQuotes> + xtra::Actor<Stop = Error>
+{
+}
+
+/// The backends a price feed can be sourced from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Quotes streamed from the BitMEX websocket API.
+    Bitmex,
+    /// Quotes replayed from a file, see [`replay::Actor`].
+    File(PathBuf),
+}
+
+impl FromStr for Source {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s == "bitmex" => Ok(Source::Bitmex),
+            Some(("file", path)) if !path.is_empty() => Ok(Source::File(PathBuf::from(path))),
+            _ => {
+                anyhow::bail!("Unknown price feed source '{s}', expected 'bitmex' or 'file:<path>'")
+            }
+        }
+    }
+}
+
+/// Subscribes to BitMEX and retrieves latest quotes for the given instruments.
+///
+/// Other backends live in their own modules, see [`replay`]. Several feeds can be combined with
+/// [`median::Actor`].
 pub struct Actor {
     latest_quotes: LatestQuotes,
 
     /// Contains the reason we are stopping.
     stop_reason: Option<Error>,
     network: Network,
+    instruments: Vec<ContractSymbol>,
 }
 
 impl Actor {
-    pub fn new(network: Network) -> Self {
+    pub fn new(network: Network, instruments: Vec<ContractSymbol>) -> Self {
         Self {
             latest_quotes: HashMap::new(),
             stop_reason: None,
             network,
+            instruments,
         }
     }
 }
@@ -43,13 +89,13 @@ impl xtra::Actor for Actor {
             {
                 let this = this.clone();
                 let network = self.network;
+                let instruments = self.instruments.clone();
 
                 async move {
                     let mut stream = bitmex_stream::subscribe(
-                        [
-                            format!("quoteBin{QUOTE_INTERVAL_MINUTES}m:XBTUSD"),
-                            format!("quoteBin{QUOTE_INTERVAL_MINUTES}m:ETHUSD"),
-                        ],
+                        instruments.iter().map(|instrument| {
+                            format!("quoteBin{QUOTE_INTERVAL_MINUTES}m:{instrument}")
+                        }),
                         network,
                     );
 
@@ -58,7 +104,7 @@ impl xtra::Actor for Actor {
                         .await
                         .map_err(|e| Error::Failed { source: e })?
                     {
-                        let quote = Quote::from_str(&text)
+                        let quote = Quote::from_str(&text, &instruments)
                             .map_err(|e| Error::FailedToParseQuote { source: e })?;
 
                         match quote {
@@ -127,6 +173,8 @@ pub enum Error {
     StreamEnded,
     #[error("Failed to parse quote")]
     FailedToParseQuote { source: anyhow::Error },
+    #[error("Failed to replay quotes")]
+    Replay { source: anyhow::Error },
     #[error("Stop reason was not specified")]
     Unspecified,
 }
@@ -149,14 +197,81 @@ pub struct Quote {
     pub symbol: ContractSymbol,
 }
 
-#[derive(
-    Debug, Clone, Copy, strum_macros::EnumString, strum_macros::Display, PartialEq, Eq, Hash,
-)]
-pub enum ContractSymbol {
-    #[strum(serialize = "XBTUSD")]
-    BtcUsd,
-    #[strum(serialize = "ETHUSD")]
-    EthUsd,
+/// The maximum length of the name of an instrument.
+const MAX_INSTRUMENT_LEN: usize = 16;
+
+/// An instrument quoted by the price feed, e.g. `XBTUSD`.
+///
+/// The name is stored inline, so that quotes can be `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContractSymbol {
+    bytes: [u8; MAX_INSTRUMENT_LEN],
+    len: u8,
+}
+
+impl ContractSymbol {
+    pub const BTC_USD: ContractSymbol = ContractSymbol::from_static("XBTUSD");
+    pub const ETH_USD: ContractSymbol = ContractSymbol::from_static("ETHUSD");
+
+    /// Panics if `instrument` is not a valid name, to be used for constants only.
+    const fn from_static(instrument: &'static str) -> Self {
+        let instrument = instrument.as_bytes();
+        assert!(!instrument.is_empty() && instrument.len() <= MAX_INSTRUMENT_LEN);
+
+        let mut bytes = [0; MAX_INSTRUMENT_LEN];
+        let mut i = 0;
+        while i < instrument.len() {
+            bytes[i] = instrument[i];
+            i += 1;
+        }
+
+        Self {
+            bytes,
+            len: instrument.len() as u8,
+        }
+    }
+
+    pub fn new(instrument: &str) -> Result<Self> {
+        anyhow::ensure!(
+            !instrument.is_empty()
+                && instrument.len() <= MAX_INSTRUMENT_LEN
+                && instrument.chars().all(|c| c.is_ascii_alphanumeric()),
+            "Invalid instrument {instrument:?}"
+        );
+
+        let mut bytes = [0; MAX_INSTRUMENT_LEN];
+        bytes[..instrument.len()].copy_from_slice(instrument.as_bytes());
+
+        Ok(Self {
+            bytes,
+            len: instrument.len() as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).expect("instruments to be ASCII")
+    }
+
+    /// Find the instrument called `name` among the `instruments` we know about.
+    pub(crate) fn find(instruments: &[ContractSymbol], name: &str) -> Result<Self> {
+        instruments
+            .iter()
+            .copied()
+            .find(|instrument| instrument.as_str() == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown instrument {name}"))
+    }
+}
+
+impl fmt::Debug for ContractSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for ContractSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl fmt::Debug for Quote {
@@ -175,7 +290,7 @@ impl fmt::Debug for Quote {
 }
 
 impl Quote {
-    fn from_str(text: &str) -> Result<Option<Self>> {
+    fn from_str(text: &str, instruments: &[ContractSymbol]) -> Result<Option<Self>> {
         let table_message = match serde_json::from_str::<wire::TableMessage>(text) {
             Ok(table_message) => table_message,
             Err(_) => {
@@ -186,7 +301,14 @@ impl Quote {
 
         let [quote] = table_message.data;
 
-        let symbol = ContractSymbol::from_str(quote.symbol.as_str())?;
+        let symbol = match ContractSymbol::find(instruments, quote.symbol.as_str()) {
+            Ok(symbol) => symbol,
+            Err(_) => {
+                tracing::trace!(symbol = %quote.symbol, "Quote for unknown instrument, skipping...");
+                return Ok(None);
+            }
+        };
+
         Ok(Some(Self {
             timestamp: quote.timestamp,
             bid: quote.bid_price,
@@ -244,12 +366,19 @@ mod tests {
 
     #[test]
     fn can_deserialize_quote_message() {
-        let quote = Quote::from_str(r#"{"table":"quoteBin1m","action":"insert","data":[{"timestamp":"2021-09-21T02:40:00.000Z","symbol":"XBTUSD","bidSize":50200,"bidPrice":42640.5,"askPrice":42641,"askSize":363600}]}"#).unwrap().unwrap();
+        let quote = Quote::from_str(r#"{"table":"quoteBin1m","action":"insert","data":[{"timestamp":"2021-09-21T02:40:00.000Z","symbol":"XBTUSD","bidSize":50200,"bidPrice":42640.5,"askPrice":42641,"askSize":363600}]}"#, &[ContractSymbol::BTC_USD]).unwrap().unwrap();
 
         assert_eq!(quote.bid, dec!(42640.5));
         assert_eq!(quote.ask, dec!(42641));
         assert_eq!(quote.timestamp.unix_timestamp(), 1632192000);
-        assert_eq!(quote.symbol, ContractSymbol::BtcUsd)
+        assert_eq!(quote.symbol, ContractSymbol::BTC_USD)
+    }
+
+    #[test]
+    fn skips_quote_for_unknown_instrument() {
+        let quote = Quote::from_str(r#"{"table":"quoteBin1m","action":"insert","data":[{"timestamp":"2021-09-21T02:40:00.000Z","symbol":"SOLUSD","bidSize":100,"bidPrice":30.5,"askPrice":30.6,"askSize":100}]}"#, &[ContractSymbol::BTC_USD]).unwrap();
+
+        assert!(quote.is_none())
     }
 
     #[test]
@@ -270,12 +399,23 @@ mod tests {
         assert!(is_older)
     }
 
+    #[test]
+    fn can_parse_sources() {
+        assert_eq!(Source::from_str("bitmex").unwrap(), Source::Bitmex);
+        assert_eq!(
+            Source::from_str("file:/tmp/quotes.jsonl").unwrap(),
+            Source::File(PathBuf::from("/tmp/quotes.jsonl"))
+        );
+        assert!(Source::from_str("file:").is_err());
+        assert!(Source::from_str("kraken").is_err());
+    }
+
     fn dummy_quote_at(timestamp: OffsetDateTime) -> Quote {
         Quote {
             timestamp,
             bid: dec!(10),
             ask: dec!(10),
-            symbol: ContractSymbol::BtcUsd,
+            symbol: ContractSymbol::BTC_USD,
         }
     }
 }
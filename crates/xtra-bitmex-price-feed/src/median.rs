@@ -0,0 +1,209 @@
+use crate::ContractSymbol;
+use crate::Error;
+use crate::GetLatestQuotes;
+use crate::LatestQuotes;
+use crate::Quote;
+use crate::QUOTE_INTERVAL_MINUTES;
+use async_trait::async_trait;
+use futures::future;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::Duration;
+use time::ext::NumericalDuration;
+use tokio_extras::FutureExt;
+use xtra::prelude::MessageChannel;
+use xtra_productivity::xtra_productivity;
+
+/// How long we wait for a single source before leaving it out of the aggregate.
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Aggregates the quotes of several price feeds into a single quote per symbol.
+///
+/// The bid and ask of the aggregated quote are the medians over all sources, which means a single
+/// misbehaving source cannot move the price as long as the majority of the sources agree. Quotes
+/// that are older than [`QUOTE_INTERVAL_MINUTES`] * 2 are only considered if no source has a
+/// recent quote for the symbol.
+pub struct Actor {
+    sources: Vec<MessageChannel<GetLatestQuotes, LatestQuotes>>,
+}
+
+impl Actor {
+    pub fn new(sources: Vec<MessageChannel<GetLatestQuotes, LatestQuotes>>) -> Self {
+        Self { sources }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = Error;
+
+    async fn stopped(self) -> Self::Stop {
+        Error::Unspecified
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, _msg: GetLatestQuotes) -> LatestQuotes {
+        let responses = future::join_all(self.sources.iter().map(|source| {
+            source
+                .send(GetLatestQuotes)
+                .timeout(SOURCE_TIMEOUT, || tracing::debug_span!("get latest quotes"))
+        }))
+        .await;
+
+        let quotes = responses
+            .into_iter()
+            .filter_map(|response| match response {
+                Ok(Ok(quotes)) => Some(quotes),
+                Ok(Err(e)) => {
+                    tracing::trace!("Price feed source currently unreachable: {e:#}");
+                    None
+                }
+                Err(_) => {
+                    tracing::debug!(
+                        "Price feed source did not respond within {} seconds",
+                        SOURCE_TIMEOUT.as_secs()
+                    );
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        aggregate(quotes)
+    }
+}
+
+/// Aggregate the latest quotes of all sources into the median quote per symbol.
+fn aggregate(sources: Vec<LatestQuotes>) -> LatestQuotes {
+    let mut by_symbol = HashMap::<ContractSymbol, Vec<Quote>>::new();
+    for quote in sources.into_iter().flat_map(|quotes| quotes.into_values()) {
+        by_symbol.entry(quote.symbol).or_default().push(quote);
+    }
+
+    by_symbol
+        .into_iter()
+        .filter_map(|(symbol, quotes)| Some((symbol, median_quote(quotes)?)))
+        .collect()
+}
+
+fn median_quote(quotes: Vec<Quote>) -> Option<Quote> {
+    let threshold = (QUOTE_INTERVAL_MINUTES * 2).minutes();
+    let (recent, stale): (Vec<_>, Vec<_>) = quotes
+        .into_iter()
+        .partition(|quote| !quote.is_older_than(threshold));
+
+    let quotes = if recent.is_empty() {
+        // Better to show a stale price than none at all, consumers check the timestamp anyway
+        stale
+            .into_iter()
+            .max_by_key(|quote| quote.timestamp)
+            .into_iter()
+            .collect()
+    } else {
+        recent
+    };
+
+    let first = quotes.first()?;
+
+    Some(Quote {
+        // The aggregate is only as recent as the oldest quote it was computed from
+        timestamp: quotes.iter().map(|quote| quote.timestamp).min()?,
+        bid: median(quotes.iter().map(|quote| quote.bid).collect())?,
+        ask: median(quotes.iter().map(|quote| quote.ask).collect())?,
+        symbol: first.symbol,
+    })
+}
+
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    values.sort();
+
+    let n = values.len();
+    match n {
+        0 => None,
+        n if n % 2 == 1 => Some(values[n / 2]),
+        n => Some((values[n / 2 - 1] + values[n / 2]) / Decimal::TWO),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use time::OffsetDateTime;
+
+    #[test]
+    fn single_source_is_passed_through() {
+        let quote = quote(ContractSymbol::BTC_USD, dec!(100), dec!(101), 0);
+
+        let aggregate = aggregate(vec![quotes([quote])]);
+
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].bid, dec!(100));
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].ask, dec!(101));
+        assert_eq!(
+            aggregate[&ContractSymbol::BTC_USD].timestamp,
+            quote.timestamp
+        );
+    }
+
+    #[test]
+    fn outlier_does_not_move_the_price() {
+        let aggregate = aggregate(vec![
+            quotes([quote(ContractSymbol::BTC_USD, dec!(100), dec!(101), 0)]),
+            quotes([quote(ContractSymbol::BTC_USD, dec!(102), dec!(103), 0)]),
+            quotes([quote(ContractSymbol::BTC_USD, dec!(1), dec!(100000), 0)]),
+        ]);
+
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].bid, dec!(100));
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].ask, dec!(103));
+    }
+
+    #[test]
+    fn even_number_of_sources_averages_middle_quotes() {
+        let aggregate = aggregate(vec![
+            quotes([quote(ContractSymbol::ETH_USD, dec!(10), dec!(11), 0)]),
+            quotes([quote(ContractSymbol::ETH_USD, dec!(12), dec!(13), 0)]),
+        ]);
+
+        assert_eq!(aggregate[&ContractSymbol::ETH_USD].bid, dec!(11));
+        assert_eq!(aggregate[&ContractSymbol::ETH_USD].ask, dec!(12));
+    }
+
+    #[test]
+    fn stale_quotes_are_ignored_if_recent_ones_exist() {
+        let aggregate = aggregate(vec![
+            quotes([quote(ContractSymbol::BTC_USD, dec!(100), dec!(101), 0)]),
+            quotes([quote(ContractSymbol::BTC_USD, dec!(50), dec!(51), 60)]),
+        ]);
+
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].bid, dec!(100));
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].ask, dec!(101));
+    }
+
+    #[test]
+    fn symbols_are_aggregated_independently() {
+        let aggregate = aggregate(vec![
+            quotes([quote(ContractSymbol::BTC_USD, dec!(100), dec!(101), 0)]),
+            quotes([quote(ContractSymbol::ETH_USD, dec!(10), dec!(11), 0)]),
+        ]);
+
+        assert_eq!(aggregate[&ContractSymbol::BTC_USD].bid, dec!(100));
+        assert_eq!(aggregate[&ContractSymbol::ETH_USD].bid, dec!(10));
+    }
+
+    fn quotes<const N: usize>(quotes: [Quote; N]) -> LatestQuotes {
+        quotes
+            .into_iter()
+            .map(|quote| (quote.symbol, quote))
+            .collect()
+    }
+
+    fn quote(symbol: ContractSymbol, bid: Decimal, ask: Decimal, minutes_ago: i64) -> Quote {
+        Quote {
+            timestamp: OffsetDateTime::now_utc() - minutes_ago.minutes(),
+            bid,
+            ask,
+            symbol,
+        }
+    }
+}
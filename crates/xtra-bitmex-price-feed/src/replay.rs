@@ -0,0 +1,203 @@
+use crate::ContractSymbol;
+use crate::Error;
+use crate::GetLatestQuotes;
+use crate::LatestQuotes;
+use crate::Quote;
+use crate::QUOTE_INTERVAL_MINUTES;
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use time::OffsetDateTime;
+use xtra_productivity::xtra_productivity;
+
+/// Replays quotes from a file, one line per [`QUOTE_INTERVAL_MINUTES`].
+///
+/// Every line of the file is a JSON object of the form
+/// `{"symbol": "XBTUSD", "bid": 19000.5, "ask": 19001}`. Once the end of the file is reached we
+/// start over from the first line. Replayed quotes are timestamped at the time they are replayed,
+/// so they are never considered stale.
+pub struct Actor {
+    latest_quotes: LatestQuotes,
+
+    /// Contains the reason we are stopping.
+    stop_reason: Option<Error>,
+    path: PathBuf,
+    interval: Duration,
+    instruments: Vec<ContractSymbol>,
+}
+
+impl Actor {
+    pub fn new(path: PathBuf, instruments: Vec<ContractSymbol>) -> Self {
+        Self::with_interval(
+            path,
+            Duration::from_secs(QUOTE_INTERVAL_MINUTES as u64 * 60),
+            instruments,
+        )
+    }
+
+    pub fn with_interval(
+        path: PathBuf,
+        interval: Duration,
+        instruments: Vec<ContractSymbol>,
+    ) -> Self {
+        Self {
+            latest_quotes: HashMap::new(),
+            stop_reason: None,
+            path,
+            interval,
+            instruments,
+        }
+    }
+}
+
+#[async_trait]
+impl xtra::Actor for Actor {
+    type Stop = Error;
+
+    async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
+        let this = ctx.address().expect("we are alive");
+
+        tokio_extras::spawn_fallible(
+            &this.clone(),
+            {
+                let this = this.clone();
+                let path = self.path.clone();
+                let interval = self.interval;
+                let instruments = self.instruments.clone();
+
+                async move {
+                    let quotes = read_quotes(&path, &instruments)
+                        .map_err(|e| Error::Replay { source: e })?;
+
+                    for quote in quotes.iter().cycle() {
+                        let quote = Quote {
+                            timestamp: OffsetDateTime::now_utc(),
+                            ..*quote
+                        };
+
+                        // Our task should already be dead and the actor restarted if this
+                        // happens.
+                        if this.send(NewQuoteReceived(quote)).await.is_err() {
+                            return Ok(());
+                        }
+
+                        tokio_extras::time::sleep_silent(interval).await;
+                    }
+
+                    Err(Error::Replay {
+                        source: anyhow::anyhow!("No quotes in {}", path.display()),
+                    })
+                }
+            },
+            |err| async move {
+                let _: Result<(), xtra::Error> = this.send(err).await;
+            },
+        );
+    }
+
+    async fn stopped(self) -> Self::Stop {
+        self.stop_reason.unwrap_or(Error::Unspecified)
+    }
+}
+
+#[xtra_productivity]
+impl Actor {
+    async fn handle(&mut self, msg: Error, ctx: &mut xtra::Context<Self>) {
+        self.stop_reason = Some(msg);
+        ctx.stop_self();
+    }
+
+    async fn handle(&mut self, msg: NewQuoteReceived) {
+        self.latest_quotes.insert(msg.0.symbol, msg.0);
+    }
+
+    async fn handle(&mut self, _msg: GetLatestQuotes) -> LatestQuotes {
+        self.latest_quotes.clone()
+    }
+}
+
+/// Private message to update our internal state with the latest quote.
+#[derive(Debug)]
+struct NewQuoteReceived(Quote);
+
+fn read_quotes(path: &PathBuf, instruments: &[ContractSymbol]) -> Result<Vec<Quote>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read quotes from {}", path.display()))?;
+
+    parse_quotes(&content, instruments)
+}
+
+fn parse_quotes(content: &str, instruments: &[ContractSymbol]) -> Result<Vec<Quote>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line = serde_json::from_str::<Line>(line)
+                .with_context(|| format!("Failed to parse quote on line {}", i + 1))?;
+
+            Ok(Quote {
+                timestamp: OffsetDateTime::now_utc(),
+                bid: line.bid,
+                ask: line.ask,
+                symbol: ContractSymbol::find(instruments, &line.symbol)
+                    .with_context(|| format!("Unknown symbol on line {}", i + 1))?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct Line {
+    symbol: String,
+    #[serde(with = "rust_decimal::serde::float")]
+    bid: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    ask: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    /// The instruments of the built-in contract symbols and one only defined in a catalogue.
+    fn instruments() -> Vec<ContractSymbol> {
+        vec![
+            ContractSymbol::BTC_USD,
+            ContractSymbol::ETH_USD,
+            ContractSymbol::new("SOLUSD").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn can_parse_quotes() {
+        let quotes = parse_quotes(
+            r#"{"symbol":"XBTUSD","bid":19000.5,"ask":19001}
+
+{"symbol":"ETHUSD","bid":1300,"ask":1300.5}
+{"symbol":"SOLUSD","bid":30.5,"ask":30.6}"#,
+            &instruments(),
+        )
+        .unwrap();
+
+        assert_eq!(quotes.len(), 3);
+        assert_eq!(quotes[0].symbol, ContractSymbol::BTC_USD);
+        assert_eq!(quotes[0].bid, dec!(19000.5));
+        assert_eq!(quotes[1].symbol, ContractSymbol::ETH_USD);
+        assert_eq!(quotes[1].ask, dec!(1300.5));
+        assert_eq!(quotes[2].symbol.as_str(), "SOLUSD");
+    }
+
+    #[test]
+    fn rejects_unknown_symbol() {
+        let result = parse_quotes(r#"{"symbol":"DOGEUSD","bid":1,"ask":1}"#, &instruments());
+
+        assert!(result.is_err());
+    }
+}
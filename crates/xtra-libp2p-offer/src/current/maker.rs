@@ -5,6 +5,7 @@ use model::ContractSymbol;
 use model::Position;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
 use std::time::Duration;
 use tokio_extras::spawn_fallible;
 use tracing::Instrument;
@@ -18,15 +19,34 @@ use xtra_productivity::xtra_productivity;
 pub struct Actor {
     endpoint: xtra::Address<Endpoint>,
     connected_peers: HashSet<PeerId>,
-    current_offers: Offers,
+    current_offers: OrderBooks,
 }
 
 impl Actor {
-    pub fn new(endpoint: xtra::Address<Endpoint>) -> Self {
+    /// Create the actor with the group of each taker that is in one.
+    pub fn new(endpoint: xtra::Address<Endpoint>, groups: HashMap<PeerId, String>) -> Self {
         Self {
             endpoint,
             connected_peers: HashSet::default(),
-            current_offers: Offers::default(),
+            current_offers: OrderBooks::new(groups),
+        }
+    }
+
+    /// Send each connected taker in `audience` the offers meant for them.
+    async fn send_offers_to(&self, audience: &Audience, ctx: &mut xtra::Context<Self>) {
+        let quiet = quiet_spans::sometimes_quiet_children();
+        for peer_id in self.connected_peers.iter().copied() {
+            if !self.current_offers.includes(audience, peer_id) {
+                continue;
+            }
+
+            // Takers replace all offers of a maker with the ones they receive, so we always send
+            // the entire order book
+            let offers = self.current_offers.for_peer(peer_id);
+
+            self.send_offers(peer_id, offers, ctx)
+                .instrument(quiet.clone())
+                .await
         }
     }
 
@@ -74,18 +94,27 @@ impl Actor {
 #[xtra_productivity]
 impl Actor {
     async fn handle(&mut self, msg: NewOffers, ctx: &mut xtra::Context<Self>) {
-        self.current_offers.update(msg.0.clone());
+        let NewOffers { offers, audience } = msg;
 
-        let quiet = quiet_spans::sometimes_quiet_children();
-        for peer_id in self.connected_peers.iter().copied() {
-            self.send_offers(peer_id, msg.0.clone(), ctx)
-                .instrument(quiet.clone())
-                .await
-        }
+        self.current_offers.update(audience.clone(), offers);
+        self.send_offers_to(&audience, ctx).await;
     }
 
-    async fn handle(&mut self, _: GetLatestOffers) -> Vec<model::Offer> {
-        self.current_offers.to_vec()
+    async fn handle(&mut self, msg: RemoveOffers, ctx: &mut xtra::Context<Self>) {
+        let RemoveOffers {
+            contract_symbol,
+            audience,
+        } = msg;
+
+        self.current_offers.remove(&audience, contract_symbol);
+        self.send_offers_to(&audience, ctx).await;
+    }
+
+    async fn handle(&mut self, msg: GetLatestOffers) -> Vec<model::Offer> {
+        match msg.peer_id {
+            Some(peer_id) => self.current_offers.for_peer(peer_id),
+            None => self.current_offers.for_everyone(),
+        }
     }
 }
 
@@ -98,7 +127,7 @@ impl Actor {
     ) {
         tracing::trace!("Adding newly established connection: {:?}", msg.peer_id);
         self.connected_peers.insert(msg.peer_id);
-        self.send_offers(msg.peer_id, self.current_offers.to_vec(), ctx)
+        self.send_offers(msg.peer_id, self.current_offers.for_peer(msg.peer_id), ctx)
             .await;
     }
 
@@ -108,37 +137,169 @@ impl Actor {
     }
 }
 
-/// Instruct the `offer::maker::Actor` to broadcast to all
-/// connected peers an update to the current offers.
-pub struct NewOffers(Vec<model::Offer>);
+/// The takers an offer set is published to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Audience {
+    /// All takers, unless there is a more specific offer set for them.
+    Everyone,
+    /// The takers in a group.
+    Group(String),
+    /// A single taker.
+    Peer(PeerId),
+}
+
+impl fmt::Display for Audience {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Audience::Everyone => write!(f, "everyone"),
+            Audience::Group(group) => write!(f, "group {group}"),
+            Audience::Peer(peer_id) => write!(f, "peer {peer_id}"),
+        }
+    }
+}
+
+/// Instruct the `offer::maker::Actor` to send an update to the current offers to the connected
+/// peers in the audience.
+pub struct NewOffers {
+    offers: Vec<model::Offer>,
+    audience: Audience,
+}
 
 impl NewOffers {
     pub fn new(offers: Vec<model::Offer>) -> Self {
-        Self(offers)
+        Self::for_audience(offers, Audience::Everyone)
     }
+
+    pub fn for_audience(offers: Vec<model::Offer>, audience: Audience) -> Self {
+        Self { offers, audience }
+    }
+}
+
+/// Withdraw the offers on a contract symbol meant for an audience.
+///
+/// The takers in the audience fall back to less specific offers, if any.
+pub struct RemoveOffers {
+    pub contract_symbol: ContractSymbol,
+    pub audience: Audience,
 }
 
+/// Get the latest offers sent to a peer.
 #[derive(Clone, Copy)]
-pub struct GetLatestOffers;
+pub struct GetLatestOffers {
+    peer_id: Option<PeerId>,
+}
+
+impl GetLatestOffers {
+    /// The offers sent to `peer_id`, including the ones meant for its group or only for it.
+    pub fn for_peer(peer_id: PeerId) -> Self {
+        Self {
+            peer_id: Some(peer_id),
+        }
+    }
 
+    /// The offers sent to every taker without more specific offers.
+    pub fn for_everyone() -> Self {
+        Self { peer_id: None }
+    }
+}
+
+/// The price levels of each side of the order book, by contract symbol and position.
 #[derive(Clone, Default)]
-struct Offers(HashMap<(ContractSymbol, Position), model::Offer>);
+struct Offers(HashMap<(ContractSymbol, Position), Vec<model::Offer>>);
 
 impl Offers {
+    /// Replace all levels of each side of the order book for which there are new offers.
     fn update(&mut self, offers: Vec<model::Offer>) {
+        let mut sides = HashMap::<_, Vec<_>>::new();
         for offer in offers.into_iter() {
-            let key = (offer.contract_symbol, offer.position_maker);
+            sides
+                .entry((offer.contract_symbol, offer.position_maker))
+                .or_default()
+                .push(offer);
+        }
 
-            if let Some(offer) = self.0.remove(&key) {
+        for (key, offers) in sides.into_iter() {
+            for offer in self.0.insert(key, offers).into_iter().flatten() {
                 tracing::debug!(offer_id = %offer.id, "Replaced offer");
-            };
-
-            self.0.insert(key, offer);
+            }
         }
     }
 
+    fn remove(&mut self, contract_symbol: ContractSymbol) {
+        self.0.retain(|(symbol, _), _| *symbol != contract_symbol);
+    }
+
     fn to_vec(&self) -> Vec<model::Offer> {
-        self.0.iter().map(|(_, offer)| offer).cloned().collect()
+        self.0.values().flatten().cloned().collect()
+    }
+}
+
+/// The order books of all audiences.
+///
+/// A taker sees each side of the order book from the most specific audience it belongs to: its
+/// own offers first, then the ones of its group and the ones for everyone last.
+struct OrderBooks {
+    books: HashMap<Audience, Offers>,
+    groups: HashMap<PeerId, String>,
+}
+
+impl OrderBooks {
+    fn new(groups: HashMap<PeerId, String>) -> Self {
+        Self {
+            books: HashMap::default(),
+            groups,
+        }
+    }
+
+    fn update(&mut self, audience: Audience, offers: Vec<model::Offer>) {
+        if let Audience::Group(group) = &audience {
+            if !self.groups.values().any(|g| g == group) {
+                tracing::warn!(%group, "Offers for a group without takers");
+            }
+        }
+
+        self.books.entry(audience).or_default().update(offers);
+    }
+
+    fn remove(&mut self, audience: &Audience, contract_symbol: ContractSymbol) {
+        if let Some(book) = self.books.get_mut(audience) {
+            book.remove(contract_symbol);
+        }
+    }
+
+    fn includes(&self, audience: &Audience, peer_id: PeerId) -> bool {
+        match audience {
+            Audience::Everyone => true,
+            Audience::Group(group) => self.groups.get(&peer_id) == Some(group),
+            Audience::Peer(audience_peer_id) => *audience_peer_id == peer_id,
+        }
+    }
+
+    fn for_everyone(&self) -> Vec<model::Offer> {
+        self.books
+            .get(&Audience::Everyone)
+            .map(Offers::to_vec)
+            .unwrap_or_default()
+    }
+
+    fn for_peer(&self, peer_id: PeerId) -> Vec<model::Offer> {
+        let mut audiences = vec![Audience::Peer(peer_id)];
+        if let Some(group) = self.groups.get(&peer_id) {
+            audiences.push(Audience::Group(group.clone()));
+        }
+        audiences.push(Audience::Everyone);
+
+        let mut sides = HashMap::new();
+        for book in audiences
+            .iter()
+            .filter_map(|audience| self.books.get(audience))
+        {
+            for (side, offers) in book.0.iter() {
+                sides.entry(*side).or_insert(offers);
+            }
+        }
+
+        sides.into_values().flatten().cloned().collect()
     }
 }
 
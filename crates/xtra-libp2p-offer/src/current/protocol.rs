@@ -7,6 +7,7 @@ use futures::AsyncWriteExt;
 use futures::SinkExt;
 use futures::StreamExt;
 use model::olivia::BitMexPriceEventId;
+use model::olivia::OracleConfig;
 use model::ContractSymbol;
 use model::Contracts;
 use model::FundingRate;
@@ -18,6 +19,7 @@ use model::Position;
 use model::Price;
 use model::Timestamp;
 use model::TxFeeRate;
+use serde::de::Error as _;
 use serde::Deserialize;
 use serde::Serialize;
 use std::fmt;
@@ -47,9 +49,47 @@ where
     Ok(offers)
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Clone, Serialize, PartialEq, Debug)]
 pub(crate) struct Offers(Vec<Offer>);
 
+/// Offers on contract symbols we cannot decode are skipped, so that makers can offer contract
+/// symbols of later releases without breaking the offers of takers that don't know them.
+///
+/// Any other offer we fail to decode still fails the whole message.
+impl<'de> Deserialize<'de> for Offers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let offers = Vec::<serde_json::Value>::deserialize(deserializer)?;
+        let n_offers = offers.len();
+
+        let mut known = Vec::with_capacity(n_offers);
+        for offer in offers {
+            if let Some(symbol) = offer
+                .get("contract_symbol")
+                .and_then(|symbol| symbol.as_str())
+            {
+                if symbol.parse::<ContractSymbol>().is_err() {
+                    tracing::debug!(%symbol, "Skipping offer on unknown contract symbol");
+                    continue;
+                }
+            }
+
+            known.push(Offer::deserialize(offer).map_err(D::Error::custom)?);
+        }
+
+        if known.len() < n_offers {
+            tracing::debug!(
+                skipped = n_offers - known.len(),
+                "Skipped offers we cannot take"
+            );
+        }
+
+        Ok(Self(known))
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub(crate) struct Offer {
     id: OfferId,
@@ -62,6 +102,9 @@ pub(crate) struct Offer {
     creation_timestamp_maker: Timestamp,
     settlement_interval: Duration,
     oracle_event_id: BitMexPriceEventId,
+    /// Makers that predate configurable oracles always use Olivia
+    #[serde(default = "OracleConfig::olivia")]
+    oracle: OracleConfig,
     tx_fee_rate: TxFeeRate,
     funding_rate: FundingRate,
     opening_fee: OpeningFee,
@@ -81,6 +124,7 @@ impl From<model::Offer> for Offer {
             creation_timestamp_maker: offer.creation_timestamp_maker,
             settlement_interval: offer.settlement_interval,
             oracle_event_id: offer.oracle_event_id,
+            oracle: offer.oracle,
             tx_fee_rate: offer.tx_fee_rate,
             funding_rate: offer.funding_rate,
             opening_fee: offer.opening_fee,
@@ -102,6 +146,7 @@ impl From<Offer> for model::Offer {
             creation_timestamp_maker: offer.creation_timestamp_maker,
             settlement_interval: offer.settlement_interval,
             oracle_event_id: offer.oracle_event_id,
+            oracle: offer.oracle,
             tx_fee_rate: offer.tx_fee_rate,
             funding_rate: offer.funding_rate,
             opening_fee: offer.opening_fee,
@@ -175,4 +220,27 @@ mod tests {
         assert!(send_res.is_ok());
         assert_eq!(maker_offers, Vec::<model::Offer>::from(recv_res.unwrap()))
     }
+
+    #[test]
+    fn skip_offers_on_unknown_contract_symbols() {
+        let offers = Offers::from(dummy_offers());
+        let mut json = serde_json::to_value(&offers).unwrap();
+        json[0]["contract_symbol"] = serde_json::json!("DOGEUSD");
+
+        let received = serde_json::from_value::<Offers>(json).unwrap();
+
+        assert_eq!(received.0.len(), offers.0.len() - 1);
+        assert_eq!(received.0[..], offers.0[1..]);
+    }
+
+    #[test]
+    fn fail_on_malformed_offers_on_known_contract_symbols() {
+        let offers = Offers::from(dummy_offers());
+        let mut json = serde_json::to_value(&offers).unwrap();
+        json[0]["price"] = serde_json::json!("not a price");
+
+        let result = serde_json::from_value::<Offers>(json);
+
+        assert!(result.is_err());
+    }
 }
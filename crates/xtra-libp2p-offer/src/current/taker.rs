@@ -2,6 +2,7 @@ use crate::current::protocol;
 use async_trait::async_trait;
 use tracing::Instrument;
 use xtra::prelude::MessageChannel;
+use xtra_libp2p::libp2p::PeerId;
 use xtra_libp2p::NewInboundSubstream;
 use xtra_productivity::xtra_productivity;
 
@@ -30,7 +31,10 @@ impl Actor {
 
             let span = tracing::debug_span!("Received new offers from maker", %peer_id);
             maker_offers
-                .send(LatestOffers(offers.into()))
+                .send(LatestOffers {
+                    maker_peer_id: peer_id,
+                    offers: offers.into(),
+                })
                 .instrument(span)
                 .await?;
 
@@ -45,9 +49,12 @@ impl Actor {
     }
 }
 
-/// Message used to inform other actors about the maker's latest
-/// offers.
-pub struct LatestOffers(pub Vec<model::Offer>);
+/// Message used to inform other actors about the latest offers of
+/// the maker identified by `maker_peer_id`.
+pub struct LatestOffers {
+    pub maker_peer_id: PeerId,
+    pub offers: Vec<model::Offer>,
+}
 
 #[async_trait]
 impl xtra::Actor for Actor {
@@ -4,6 +4,7 @@ use asynchronous_codec::JsonCodecError;
 use futures::AsyncWriteExt;
 use futures::SinkExt;
 use model::olivia::BitMexPriceEventId;
+use model::olivia::OracleConfig;
 use model::ContractSymbol;
 use model::Contracts;
 use model::FundingRate;
@@ -91,10 +92,13 @@ impl MakerOffers {
         // field is redundant across offers
         let tx_fee_rate = offers.first().tx_fee_rate;
 
-        // This version of the protocol caters to takers that only support BTCUSD CFDs
+        // This version of the protocol caters to takers that only support BTCUSD CFDs attested by
+        // Olivia
+        let olivia = OracleConfig::olivia();
         let mut offers = offers
             .iter()
-            .filter(|offer| offer.contract_symbol == ContractSymbol::BtcUsd);
+            .filter(|offer| offer.contract_symbol == ContractSymbol::BTC_USD)
+            .filter(|offer| offer.oracle == olivia);
 
         let long = offers.find_map(|offer| {
             (offer.position_maker == Position::Long).then(|| Offer::from(offer.clone()))
@@ -10,6 +10,7 @@ mod tests {
     use async_trait::async_trait;
     use futures::Future;
     use model::olivia::BitMexPriceEventId;
+    use model::olivia::OracleConfig;
     use model::ContractSymbol;
     use model::Contracts;
     use model::FundingRate;
@@ -21,6 +22,7 @@ mod tests {
     use model::TxFeeRate;
     use rust_decimal::Decimal;
     use rust_decimal_macros::dec;
+    use std::collections::HashMap;
     use std::collections::HashSet;
     use std::sync::Arc;
     use std::time::Duration;
@@ -49,8 +51,9 @@ mod tests {
             .set_default();
 
         let (maker_peer_id, maker_offer_addr, maker_endpoint_addr) =
-            create_endpoint_with_offer_maker();
-        let (offer_receiver_addr, taker_endpoint_addr) = create_endpoint_with_offer_taker();
+            create_endpoint_with_offer_maker(HashMap::default());
+        let (offer_receiver_addr, taker_endpoint_addr) =
+            create_endpoint_with_offer_taker(Keypair::generate_ed25519());
 
         maker_endpoint_addr
             .send(ListenOn(Multiaddr::empty().with(Protocol::Memory(1000))))
@@ -104,15 +107,16 @@ mod tests {
             .set_default();
 
         let (maker_peer_id, maker_offer_addr, maker_endpoint_addr) =
-            create_endpoint_with_offer_maker();
-        let (offer_receiver_addr, taker_endpoint_addr) = create_endpoint_with_offer_taker();
+            create_endpoint_with_offer_maker(HashMap::default());
+        let (offer_receiver_addr, taker_endpoint_addr) =
+            create_endpoint_with_offer_taker(Keypair::generate_ed25519());
 
         maker_endpoint_addr
             .send(ListenOn(Multiaddr::empty().with(Protocol::Memory(1000))))
             .await
             .unwrap();
 
-        let offer_btc_usd_long = dummy_offer(ContractSymbol::BtcUsd, Position::Long);
+        let offer_btc_usd_long = dummy_offer(ContractSymbol::BTC_USD, Position::Long);
         maker_offer_addr
             .send(crate::maker::NewOffers::new(vec![
                 offer_btc_usd_long.clone()
@@ -120,7 +124,7 @@ mod tests {
             .await
             .unwrap();
 
-        let offer_eth_usd_short = dummy_offer(ContractSymbol::EthUsd, Position::Short);
+        let offer_eth_usd_short = dummy_offer(ContractSymbol::ETH_USD, Position::Short);
         maker_offer_addr
             .send(crate::maker::NewOffers::new(vec![
                 offer_eth_usd_short.clone()
@@ -151,12 +155,85 @@ mod tests {
         assert!(received_offers.contains(&offer_eth_usd_short));
     }
 
+    #[tokio::test]
+    async fn given_offers_for_group_then_only_takers_in_group_receive_them() {
+        let _g = tracing_subscriber::fmt()
+            .with_env_filter("xtra_libp2p_offer=trace")
+            .with_test_writer()
+            .set_default();
+
+        let vip_id = Keypair::generate_ed25519();
+        let (maker_peer_id, maker_offer_addr, maker_endpoint_addr) =
+            create_endpoint_with_offer_maker(HashMap::from([(
+                vip_id.public().to_peer_id(),
+                "vip".to_owned(),
+            )]));
+        let (vip_receiver_addr, vip_endpoint_addr) = create_endpoint_with_offer_taker(vip_id);
+        let (other_receiver_addr, other_endpoint_addr) =
+            create_endpoint_with_offer_taker(Keypair::generate_ed25519());
+
+        maker_endpoint_addr
+            .send(ListenOn(Multiaddr::empty().with(Protocol::Memory(1000))))
+            .await
+            .unwrap();
+
+        let general_offers = dummy_offers();
+        maker_offer_addr
+            .send(crate::maker::NewOffers::new(general_offers.clone()))
+            .await
+            .unwrap();
+
+        let vip_offer = model::Offer {
+            price: Price::new(dec!(1001)).unwrap(),
+            ..dummy_offer(ContractSymbol::BTC_USD, Position::Long)
+        };
+        maker_offer_addr
+            .send(crate::maker::NewOffers::for_audience(
+                vec![vip_offer.clone()],
+                crate::maker::Audience::Group("vip".to_owned()),
+            ))
+            .await
+            .unwrap();
+
+        for taker_endpoint_addr in [&vip_endpoint_addr, &other_endpoint_addr] {
+            taker_endpoint_addr
+                .send(Connect(
+                    Multiaddr::empty()
+                        .with(Protocol::Memory(1000))
+                        .with(Protocol::P2p(maker_peer_id.into())),
+                ))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        let vip_offers = retry_until_some(|| {
+            let vip_receiver_addr = vip_receiver_addr.clone();
+            async move { vip_receiver_addr.send(GetLatestOffers).await.unwrap() }
+        })
+        .await;
+        let other_offers = retry_until_some(|| {
+            let other_receiver_addr = other_receiver_addr.clone();
+            async move { other_receiver_addr.send(GetLatestOffers).await.unwrap() }
+        })
+        .await;
+
+        // The group offer replaces the general one on the same side only
+        assert_eq!(vip_offers.len(), 2);
+        assert!(vip_offers.contains(&vip_offer));
+        assert!(vip_offers.contains(&general_offers[1]));
+        assert_eq!(other_offers.len(), 2);
+        assert!(other_offers.contains(&general_offers[0]));
+        assert!(other_offers.contains(&general_offers[1]));
+    }
+
     fn create_endpoint_with_offer_maker(
+        groups: HashMap<PeerId, String>,
     ) -> (PeerId, Address<crate::maker::Actor>, Address<Endpoint>) {
         let (endpoint_addr, endpoint_context) = Context::new(None);
 
         let id = Keypair::generate_ed25519();
-        let offer_maker_addr = crate::maker::Actor::new(endpoint_addr.clone())
+        let offer_maker_addr = crate::maker::Actor::new(endpoint_addr.clone(), groups)
             .create(None)
             .spawn_global();
 
@@ -180,7 +257,9 @@ mod tests {
         (id.public().to_peer_id(), offer_maker_addr, endpoint_addr)
     }
 
-    fn create_endpoint_with_offer_taker() -> (Address<OffersReceiver>, Address<Endpoint>) {
+    fn create_endpoint_with_offer_taker(
+        id: Keypair,
+    ) -> (Address<OffersReceiver>, Address<Endpoint>) {
         let offers_receiver_addr = OffersReceiver::new().create(None).spawn_global();
 
         let offer_taker_addr = crate::taker::Actor::new(offers_receiver_addr.clone().into())
@@ -189,7 +268,7 @@ mod tests {
 
         let endpoint_addr = Endpoint::new(
             Box::new(MemoryTransport::default),
-            Keypair::generate_ed25519(),
+            id,
             Duration::from_secs(10),
             [(PROTOCOL, offer_taker_addr.into())],
             Subscribers::default(),
@@ -221,7 +300,7 @@ mod tests {
     #[xtra_productivity]
     impl OffersReceiver {
         async fn handle(&mut self, msg: LatestOffers) {
-            self.offers = msg.0;
+            self.offers = msg.offers;
         }
     }
 
@@ -252,8 +331,8 @@ mod tests {
 
     pub fn dummy_offers() -> Vec<model::Offer> {
         vec![
-            dummy_offer(ContractSymbol::BtcUsd, Position::Long),
-            dummy_offer(ContractSymbol::BtcUsd, Position::Short),
+            dummy_offer(ContractSymbol::BTC_USD, Position::Long),
+            dummy_offer(ContractSymbol::BTC_USD, Position::Short),
         ]
     }
 
@@ -272,6 +351,7 @@ mod tests {
                 datetime!(2021-10-04 22:00:00).assume_utc(),
                 contract_symbol,
             ),
+            oracle: OracleConfig::olivia(),
             tx_fee_rate: TxFeeRate::default(),
             funding_rate: FundingRate::new(Decimal::ONE).unwrap(),
             opening_fee: Default::default(),
@@ -1,4 +1,5 @@
 use crate::current::protocol::*;
+use crate::policy::Policy;
 use anyhow::Context;
 use async_trait::async_trait;
 use asynchronous_codec::Framed;
@@ -7,7 +8,6 @@ use bdk_ext::keypair;
 use futures::SinkExt;
 use futures::StreamExt;
 use libp2p_core::PeerId;
-use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use model::Dlc;
 use model::ExecuteOnCfd;
 use model::Position;
@@ -23,29 +23,21 @@ use xtra_productivity::xtra_productivity;
 /// There is only one instance of this actor for all connections, meaning we must always spawn a
 /// task whenever we interact with a substream to not block the execution of other connections.
 pub struct Actor<E, O, R> {
-    oracle_pk: XOnlyPublicKey,
     oracle: O,
     n_payouts: usize,
     executor: E,
     rates: R,
-    is_accepting_rollovers: bool,
+    policy: Policy,
 }
 
 impl<E, O, R> Actor<E, O, R> {
-    pub fn new(
-        executor: E,
-        oracle_pk: XOnlyPublicKey,
-        oracle: O,
-        rates: R,
-        n_payouts: usize,
-    ) -> Self {
+    pub fn new(executor: E, oracle: O, rates: R, n_payouts: usize) -> Self {
         Self {
-            oracle_pk,
             oracle,
             n_payouts,
             executor,
             rates,
-            is_accepting_rollovers: true,
+            policy: Policy::default(),
         }
     }
 }
@@ -67,10 +59,10 @@ impl<E, O, R> Actor<E, O, R>
 where
     E: ExecuteOnCfd + Clone + Send + Sync + 'static,
     O: GetAnnouncements + Clone + Send + Sync + 'static,
-    R: GetRates + Clone + Send + Sync + 'static,
+    R: GetRates + ApproveRollover + Clone + Send + Sync + 'static,
 {
     async fn handle(&mut self, msg: UpdateConfiguration) {
-        self.is_accepting_rollovers = msg.is_accepting_rollovers;
+        self.policy = msg.policy;
     }
 }
 
@@ -79,7 +71,7 @@ impl<E, O, R> Actor<E, O, R>
 where
     E: ExecuteOnCfd + Clone + Send + Sync + 'static,
     O: GetAnnouncements + Clone + Send + Sync + 'static,
-    R: GetRates + Clone + Send + Sync + 'static,
+    R: GetRates + ApproveRollover + Clone + Send + Sync + 'static,
 {
     async fn handle(&mut self, msg: NewInboundSubstream, ctx: &mut xtra::Context<Self>) {
         let NewInboundSubstream { peer_id, stream } = msg;
@@ -122,7 +114,7 @@ where
         } = msg;
         let order_id = propose.order_id;
 
-        let (base_dlc_params, contract_symbol) = match self
+        let (base_dlc_params, contract_symbol, oracle_config, cfd_position, quantity) = match self
             .executor
             .execute(order_id, |cfd| {
                 cfd.verify_counterparty_peer_id(&peer_id.into())?;
@@ -131,7 +123,14 @@ where
                     cfd.start_rollover_maker(propose.from_commit_txid)?;
                 let contract_symbol = cfd.contract_symbol();
 
-                Ok((event, base_dlc_params, contract_symbol))
+                Ok((
+                    event,
+                    base_dlc_params,
+                    contract_symbol,
+                    cfd.oracle().clone(),
+                    cfd.position(),
+                    cfd.quantity(),
+                ))
             })
             .await
             .context("Rollover failed after handling taker proposal")
@@ -148,8 +147,24 @@ where
         };
 
         let this = ctx.address().expect("we are alive");
-        if !self.is_accepting_rollovers {
-            emit_rejected(order_id, &self.executor).await;
+        let approval = match self
+            .policy
+            .check_proposal(contract_symbol, peer_id.into(), quantity)
+        {
+            Ok(()) => self
+                .rates
+                .approve_rollover(order_id, contract_symbol, peer_id.into())
+                .await
+                .map_err(|e| RejectReason::RiskLimit {
+                    message: format!("{e:#}"),
+                }),
+            Err(reason) => Err(reason),
+        };
+
+        if let Err(reason) = approval {
+            tracing::info!(%order_id, %peer_id, %reason, "Rejecting rollover");
+
+            emit_rejected(order_id, Some(reason.clone()), &self.executor).await;
 
             tokio_extras::spawn_fallible(
                 &this,
@@ -157,6 +172,7 @@ where
                     framed
                         .send(ListenerMessage::Decision(Decision::Reject(Reject {
                             order_id,
+                            reason: Some(reason),
                         })))
                         .await
                 },
@@ -176,7 +192,8 @@ where
             let executor = self.executor.clone();
             let oracle = self.oracle.clone();
             let rates = self.rates.clone();
-            let oracle_pk = self.oracle_pk;
+            let policy = self.policy.clone();
+            let oracle_pk = oracle_config.public_key;
             let n_payouts = self.n_payouts;
             async move {
                 let Rates {
@@ -184,17 +201,33 @@ where
                     funding_rate_short,
                     tx_fee_rate,
                 } = rates
-                    .get_rates(contract_symbol)
+                    .get_rates(contract_symbol, peer_id.into())
                     .await
                     .context("Failed to get rates")?;
 
-                let (rollover_params, dlc, position, oracle_event_ids, funding_rate) = executor
-                    .execute(order_id, |cfd| {
-                        let funding_rate = match cfd.position() {
-                            Position::Long => funding_rate_long,
-                            Position::Short => funding_rate_short,
-                        };
+                let funding_rate = match cfd_position {
+                    Position::Long => funding_rate_long,
+                    Position::Short => funding_rate_short,
+                };
+
+                if let Err(reason) = policy.check_funding_rate(funding_rate) {
+                    tracing::info!(%order_id, %peer_id, %reason, "Rejecting rollover");
+
+                    emit_rejected(order_id, Some(reason.clone()), &executor).await;
+
+                    framed
+                        .send(ListenerMessage::Decision(Decision::Reject(Reject {
+                            order_id,
+                            reason: Some(reason),
+                        })))
+                        .await
+                        .context("Failed to send rollover rejection message")?;
 
+                    return Ok(());
+                }
+
+                let (rollover_params, dlc, position, oracle_event_ids) = executor
+                    .execute(order_id, |cfd| {
                         let (event, params, dlc, position, oracle_event_ids) = cfd
                             .accept_rollover_proposal(
                                 tx_fee_rate,
@@ -205,7 +238,7 @@ where
                                 )),
                             )?;
 
-                        Ok((event, params, dlc, position, oracle_event_ids, funding_rate))
+                        Ok((event, params, dlc, position, oracle_event_ids))
                     })
                     .await?;
 
@@ -226,7 +259,7 @@ where
                     .context("Failed to send rollover confirmation message")?;
 
                 let announcements = oracle
-                    .get_announcements(oracle_event_ids)
+                    .get_announcements(oracle_config, oracle_event_ids)
                     .await
                     .context("Failed to get announcement")?;
                 let settlement_event_id = announcements.last().context("Empty to_event_ids")?.id;
@@ -390,16 +423,14 @@ where
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct UpdateConfiguration {
-    is_accepting_rollovers: bool,
+    policy: Policy,
 }
 
 impl UpdateConfiguration {
-    pub fn new(is_accepting_rollovers: bool) -> Self {
-        Self {
-            is_accepting_rollovers,
-        }
+    pub fn new(policy: Policy) -> Self {
+        Self { policy }
     }
 }
 
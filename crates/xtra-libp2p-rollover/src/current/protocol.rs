@@ -20,18 +20,20 @@ use maia_core::CfdTransactions;
 use maia_core::PartyParams;
 use model::olivia;
 use model::olivia::BitMexPriceEventId;
-use model::payout_curve::ETHUSD_MULTIPLIER;
+use model::olivia::OracleConfig;
 use model::shared_protocol::verify_adaptor_signature;
 use model::shared_protocol::verify_cets;
 use model::shared_protocol::verify_signature;
 use model::Cet;
 use model::ContractSymbol;
+use model::Contracts;
 use model::Dlc;
 use model::ExecuteOnCfd;
 use model::FundingFee;
 use model::FundingRate;
 use model::OraclePayouts;
 use model::OrderId;
+use model::PayoutCurve;
 use model::Payouts;
 use model::Position;
 use model::Role;
@@ -125,9 +127,42 @@ pub struct Confirm {
     pub complete_fee: CompleteFee,
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Reject {
     pub order_id: OrderId,
+    /// Why the maker rejected the rollover.
+    ///
+    /// Not sent by older makers.
+    #[serde(default)]
+    pub reason: Option<RejectReason>,
+}
+
+/// Why the maker rejected a rollover.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "type", content = "payload")]
+pub enum RejectReason {
+    #[error("Maker is not accepting rollovers")]
+    NotAcceptingRollovers,
+    #[error("Maker is not accepting rollovers for {contract_symbol}")]
+    ContractSymbolPaused { contract_symbol: ContractSymbol },
+    #[error("Maker is not accepting rollovers from this taker")]
+    TakerNotAllowed,
+    #[error("Position of {quantity} contracts exceeds the maximum of {max_quantity}")]
+    PositionTooLarge {
+        quantity: Contracts,
+        max_quantity: Contracts,
+    },
+    #[error("Funding rate of {funding_rate} is below the minimum of {min_funding_rate}")]
+    FundingRateTooLow {
+        funding_rate: FundingRate,
+        min_funding_rate: FundingRate,
+    },
+    #[error("Rollover would breach a risk limit: {message}")]
+    RiskLimit { message: String },
+    /// A reason introduced by a newer maker.
+    #[serde(other)]
+    #[error("Unknown reason")]
+    Unknown,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -165,20 +200,20 @@ impl RolloverMsg {
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
-pub(crate) struct RolloverMsg0 {
+pub struct RolloverMsg0 {
     pub revocation_pk: PublicKey,
     pub publish_pk: PublicKey,
 }
 
 #[derive(Serialize, Deserialize)]
-pub(crate) struct RolloverMsg1 {
+pub struct RolloverMsg1 {
     pub commit: EcdsaAdaptorSignature,
     pub cets: HashMap<String, Vec<(RangeInclusive<u64>, EcdsaAdaptorSignature)>>,
     pub refund: Signature,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy)]
-pub(crate) struct RolloverMsg2 {
+pub struct RolloverMsg2 {
     pub revocation_sk: SecretKey,
 }
 
@@ -260,14 +295,17 @@ pub(crate) async fn emit_completed<E>(
     }
 }
 
-pub(crate) async fn emit_rejected<E>(order_id: OrderId, executor: &E)
+pub(crate) async fn emit_rejected<E>(order_id: OrderId, reason: Option<RejectReason>, executor: &E)
 where
     E: ExecuteOnCfd,
 {
+    let reason = match reason {
+        Some(reason) => anyhow!("maker decision: {reason}"),
+        None => anyhow!("maker decision"),
+    };
+
     if let Err(e) = executor
-        .execute(order_id, |cfd| {
-            Ok(cfd.reject_rollover(anyhow!("maker decision")))
-        })
+        .execute(order_id, |cfd| Ok(cfd.reject_rollover(reason)))
         .await
     {
         tracing::error!(%order_id, "Failed to execute rollover rejected: {e:#}")
@@ -287,13 +325,13 @@ where
 }
 
 #[derive(Debug, Copy, Clone)]
-pub(crate) struct PunishParams {
-    pub(crate) maker: maia_core::PunishParams,
-    pub(crate) taker: maia_core::PunishParams,
+pub struct PunishParams {
+    pub maker: maia_core::PunishParams,
+    pub taker: maia_core::PunishParams,
 }
 
 impl PunishParams {
-    pub(crate) fn new(
+    pub fn new(
         maker_revocation: PublicKey,
         taker_revocation: PublicKey,
         maker_publish: PublicKey,
@@ -313,7 +351,7 @@ impl PunishParams {
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn build_own_cfd_transactions(
+pub async fn build_own_cfd_transactions(
     dlc: &Dlc,
     rollover_params: RolloverParams,
     announcements: Vec<olivia::Announcement>,
@@ -330,8 +368,8 @@ pub(crate) async fn build_own_cfd_transactions(
     let maker_lock_amount = dlc.maker_lock_amount;
     let taker_lock_amount = dlc.taker_lock_amount;
 
-    let payouts = match contract_symbol {
-        ContractSymbol::BtcUsd => Payouts::new_inverse_olivia_max(
+    let payouts = match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => Payouts::new_inverse_olivia_max(
             (our_position, role),
             rollover_params.price,
             rollover_params.quantity,
@@ -342,7 +380,7 @@ pub(crate) async fn build_own_cfd_transactions(
             n_payouts,
             complete_fee,
         )?,
-        ContractSymbol::EthUsd => Payouts::new_quanto(
+        PayoutCurve::Quanto => Payouts::new_quanto(
             (our_position, role),
             rollover_params.price.to_u64(),
             rollover_params.quantity.to_u64(),
@@ -351,7 +389,7 @@ pub(crate) async fn build_own_cfd_transactions(
                 rollover_params.short_leverage,
             ),
             n_payouts,
-            ETHUSD_MULTIPLIER,
+            contract_symbol.multiplier(),
             complete_fee,
         )?,
     };
@@ -408,7 +446,7 @@ pub(crate) async fn build_own_cfd_transactions(
     Ok(own_cfd_txs)
 }
 
-pub(crate) fn build_commit_descriptor(
+pub fn build_commit_descriptor(
     maker_identity: PublicKey,
     taker_identity: PublicKey,
     punish_params: PunishParams,
@@ -428,7 +466,7 @@ pub(crate) fn build_commit_descriptor(
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn build_and_verify_cets_and_refund(
+pub async fn build_and_verify_cets_and_refund(
     dlc: &Dlc,
     oracle_pk: XOnlyPublicKey,
     publish_pk: PublicKey,
@@ -556,13 +594,38 @@ pub(crate) async fn build_and_verify_cets_and_refund(
 pub trait GetAnnouncements {
     async fn get_announcements(
         &self,
+        oracle: OracleConfig,
         events: Vec<BitMexPriceEventId>,
     ) -> Result<Vec<olivia::Announcement>>;
 }
 
+/// The taker's own estimate of the transaction fee rate, to check the maker's fee rate against.
+#[async_trait]
+pub trait EstimateTxFeeRate {
+    /// The latest estimate, if there is one yet.
+    async fn estimate_tx_fee_rate(&self) -> Option<TxFeeRate>;
+}
+
 #[async_trait]
 pub trait GetRates {
-    async fn get_rates(&self, contract_symbol: ContractSymbol) -> Result<Rates>;
+    /// The rates at which the maker rolls over CFDs on `contract_symbol` with `counterparty`.
+    async fn get_rates(
+        &self,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<Rates>;
+}
+
+/// Decides whether the maker accepts a particular rollover proposal.
+#[async_trait]
+pub trait ApproveRollover {
+    /// Returns an error explaining why the rollover of the CFD with `order_id` must be rejected.
+    async fn approve_rollover(
+        &self,
+        order_id: OrderId,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<()>;
 }
 
 /// Set of rates needed to accept rollover proposals.
@@ -1,5 +1,6 @@
 use crate::current;
 use crate::current::protocol::*;
+use anyhow::ensure;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -7,7 +8,6 @@ use bdk::bitcoin::Txid;
 use bdk_ext::keypair;
 use futures::SinkExt;
 use futures::StreamExt;
-use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use model::libp2p::PeerId;
 use model::olivia::BitMexPriceEventId;
 use model::Dlc;
@@ -15,13 +15,16 @@ use model::ExecuteOnCfd;
 use model::OrderId;
 use model::Role;
 use model::Timestamp;
+use model::TxFeeRate;
 use std::time::Duration;
 use tokio_extras::FutureExt;
+use xtra::prelude::MessageChannel;
 use xtra::Address;
 use xtra_libp2p::Endpoint;
 use xtra_libp2p::OpenSubstream;
 use xtra_libp2p::Substream;
 use xtra_productivity::xtra_productivity;
+use xtras::SendAsyncSafe;
 
 /// The duration that the taker waits until a decision (accept/reject) is expected from the maker
 ///
@@ -30,19 +33,25 @@ use xtra_productivity::xtra_productivity;
 const DECISION_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// One actor to rule all the rollovers
-pub struct Actor<E, O> {
+pub struct Actor<E, O, F> {
     endpoint: Address<Endpoint>,
-    oracle_pk: XOnlyPublicKey,
     oracle: O,
     n_payouts: usize,
     executor: E,
+    rejections: MessageChannel<Rejected, ()>,
+    /// Our own fee rate estimate, the maker's fee rate has to be within a band around it.
+    tx_fee_rate_estimate: F,
+    /// The lowest and highest transaction fee rate we accept from the maker while we have no
+    /// estimate.
+    tx_fee_rate_limits: (TxFeeRate, TxFeeRate),
 }
 
 #[async_trait]
-impl<E, O> xtra::Actor for Actor<E, O>
+impl<E, O, F> xtra::Actor for Actor<E, O, F>
 where
     E: Send + Sync + 'static,
     O: Send + Sync + 'static,
+    F: Send + Sync + 'static,
 {
     type Stop = ();
 
@@ -57,25 +66,37 @@ pub struct ProposeRollover {
     pub from_settlement_event_id: BitMexPriceEventId,
 }
 
-impl<E, O> Actor<E, O> {
+/// Sent to the subscriber of rejections when the maker rejected one of our rollover proposals.
+#[derive(Debug, Clone)]
+pub struct Rejected {
+    pub order_id: OrderId,
+    /// Why the maker rejected, if it told us.
+    pub reason: Option<RejectReason>,
+}
+
+impl<E, O, F> Actor<E, O, F> {
     pub fn new(
         endpoint: Address<Endpoint>,
         executor: E,
-        oracle_pk: XOnlyPublicKey,
         get_announcement: O,
         n_payouts: usize,
+        rejections: MessageChannel<Rejected, ()>,
+        tx_fee_rate_estimate: F,
+        tx_fee_rate_limits: (TxFeeRate, TxFeeRate),
     ) -> Self {
         Self {
             endpoint,
             executor,
             oracle: get_announcement,
-            oracle_pk,
             n_payouts,
+            rejections,
+            tx_fee_rate_estimate,
+            tx_fee_rate_limits,
         }
     }
 }
 
-impl<E, O> Actor<E, O> {
+impl<E, O, F> Actor<E, O, F> {
     async fn open_substream(&self, peer_id: PeerId) -> Result<Substream> {
         let substream = self
             .endpoint
@@ -94,10 +115,11 @@ impl<E, O> Actor<E, O> {
 }
 
 #[xtra_productivity]
-impl<E, O> Actor<E, O>
+impl<E, O, F> Actor<E, O, F>
 where
     E: ExecuteOnCfd + Clone + Send + Sync + 'static,
     O: GetAnnouncements + Clone + Send + Sync + 'static,
+    F: EstimateTxFeeRate + Clone + Send + Sync + 'static,
 {
     pub async fn handle(&mut self, msg: ProposeRollover, ctx: &mut xtra::Context<Self>) {
         let ProposeRollover {
@@ -124,22 +146,25 @@ where
             {
                 let executor = self.executor.clone();
                 let oracle = self.oracle.clone();
-                let oracle_pk = self.oracle_pk;
                 let n_payouts = self.n_payouts;
+                let rejections = self.rejections.clone();
+                let tx_fee_rate_estimate = self.tx_fee_rate_estimate.clone();
+                let tx_fee_rate_limits = self.tx_fee_rate_limits;
                 async move {
                     let mut framed = asynchronous_codec::Framed::new(
                         substream,
                         asynchronous_codec::JsonCodec::<DialerMessage, ListenerMessage>::new(),
                     );
 
-                    let contract_symbol = executor
+                    let (contract_symbol, oracle_config) = executor
                         .execute(order_id, |cfd| {
                             let event = cfd.start_rollover_taker()?;
                             let contract_symbol = cfd.contract_symbol();
 
-                            Ok((event, contract_symbol))
+                            Ok((event, (contract_symbol, cfd.oracle().clone())))
                         })
                         .await?;
+                    let oracle_pk = oracle_config.public_key;
 
                     framed
                         .send(DialerMessage::Propose(Propose {
@@ -173,6 +198,12 @@ where
                             funding_rate,
                             complete_fee,
                         }) => {
+                            ensure_tx_fee_rate_acceptable(
+                                tx_fee_rate,
+                                tx_fee_rate_estimate.estimate_tx_fee_rate().await,
+                                tx_fee_rate_limits,
+                            )?;
+
                             let (rollover_params, dlc, position) = executor
                                 .execute(order_id, |cfd| {
                                     cfd.handle_rollover_accepted_taker(
@@ -185,7 +216,7 @@ where
                                 .await?;
 
                             let announcements = oracle
-                                .get_announcements(oracle_event_ids)
+                                .get_announcements(oracle_config, oracle_event_ids)
                                 .await
                                 .context("Failed to get announcement")?;
                             let settlement_event_id =
@@ -350,8 +381,18 @@ where
                             )
                             .await;
                         }
-                        Decision::Reject(_) => {
-                            emit_rejected(order_id, &executor).await;
+                        Decision::Reject(Reject { reason, .. }) => {
+                            emit_rejected(order_id, reason.clone(), &executor).await;
+
+                            if let Err(e) = rejections
+                                .send_async_safe(Rejected { order_id, reason })
+                                .await
+                            {
+                                tracing::warn!(
+                                    %order_id,
+                                    "Failed to notify about rejected rollover: {e:#}"
+                                );
+                            }
                         }
                     }
                     Ok(())
@@ -366,3 +407,55 @@ where
         );
     }
 }
+
+/// Check the fee rate the maker proposed against our own `estimate`.
+///
+/// Without an estimate we fall back to our fee rate `limits`.
+fn ensure_tx_fee_rate_acceptable(
+    proposed: TxFeeRate,
+    estimate: Option<TxFeeRate>,
+    (min_tx_fee_rate, max_tx_fee_rate): (TxFeeRate, TxFeeRate),
+) -> Result<()> {
+    match estimate {
+        Some(estimate) => ensure!(
+            proposed.is_within_band(estimate),
+            "Maker proposed tx fee rate of {proposed} sat/vbyte, \
+             too far off our estimate of {estimate} sat/vbyte"
+        ),
+        None => ensure!(
+            (min_tx_fee_rate.to_u32()..=max_tx_fee_rate.to_u32()).contains(&proposed.to_u32()),
+            "Maker proposed tx fee rate of {proposed} sat/vbyte, \
+             expected between {min_tx_fee_rate} and {max_tx_fee_rate}"
+        ),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroU32;
+
+    fn fee_rate(sat_per_vbyte: u32) -> TxFeeRate {
+        TxFeeRate::new(NonZeroU32::new(sat_per_vbyte).unwrap())
+    }
+
+    #[test]
+    fn tx_fee_rate_is_checked_against_estimate() {
+        let limits = (fee_rate(1), fee_rate(100));
+
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(20), Some(fee_rate(10)), limits).is_ok());
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(90), Some(fee_rate(10)), limits).is_err());
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(2), Some(fee_rate(10)), limits).is_err());
+    }
+
+    #[test]
+    fn tx_fee_rate_is_checked_against_limits_without_estimate() {
+        let limits = (fee_rate(2), fee_rate(50));
+
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(40), None, limits).is_ok());
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(90), None, limits).is_err());
+        assert!(ensure_tx_fee_rate_acceptable(fee_rate(1), None, limits).is_err());
+    }
+}
@@ -20,7 +20,7 @@ use maia_core::CfdTransactions;
 use maia_core::PartyParams;
 use model::olivia;
 use model::olivia::BitMexPriceEventId;
-use model::payout_curve::ETHUSD_MULTIPLIER;
+use model::olivia::OracleConfig;
 use model::shared_protocol::verify_adaptor_signature;
 use model::shared_protocol::verify_cets;
 use model::shared_protocol::verify_signature;
@@ -32,6 +32,7 @@ use model::FundingFee;
 use model::FundingRate;
 use model::OraclePayouts;
 use model::OrderId;
+use model::PayoutCurve;
 use model::Payouts;
 use model::Position;
 use model::Role;
@@ -330,8 +331,8 @@ pub(crate) async fn build_own_cfd_transactions(
     let maker_lock_amount = dlc.maker_lock_amount;
     let taker_lock_amount = dlc.taker_lock_amount;
 
-    let payouts = match contract_symbol {
-        ContractSymbol::BtcUsd => Payouts::new_inverse_double_initial(
+    let payouts = match contract_symbol.payout_curve() {
+        PayoutCurve::Inverse => Payouts::new_inverse_double_initial(
             (our_position, role),
             rollover_params.price,
             rollover_params.quantity,
@@ -342,7 +343,7 @@ pub(crate) async fn build_own_cfd_transactions(
             n_payouts,
             complete_fee,
         )?,
-        ContractSymbol::EthUsd => Payouts::new_quanto(
+        PayoutCurve::Quanto => Payouts::new_quanto(
             (our_position, role),
             rollover_params.price.to_u64(),
             rollover_params.quantity.to_u64(),
@@ -351,7 +352,7 @@ pub(crate) async fn build_own_cfd_transactions(
                 rollover_params.short_leverage,
             ),
             n_payouts,
-            ETHUSD_MULTIPLIER,
+            contract_symbol.multiplier(),
             complete_fee,
         )?,
     };
@@ -556,13 +557,31 @@ pub(crate) async fn build_and_verify_cets_and_refund(
 pub trait GetAnnouncements {
     async fn get_announcements(
         &self,
+        oracle: OracleConfig,
         events: Vec<BitMexPriceEventId>,
     ) -> Result<Vec<olivia::Announcement>>;
 }
 
 #[async_trait]
 pub trait GetRates {
-    async fn get_rates(&self, contract_symbol: ContractSymbol) -> Result<Rates>;
+    /// The rates at which the maker rolls over CFDs on `contract_symbol` with `counterparty`.
+    async fn get_rates(
+        &self,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<Rates>;
+}
+
+/// Decides whether the maker accepts a particular rollover proposal.
+#[async_trait]
+pub trait ApproveRollover {
+    /// Returns an error explaining why the rollover of the CFD with `order_id` must be rejected.
+    async fn approve_rollover(
+        &self,
+        order_id: OrderId,
+        contract_symbol: ContractSymbol,
+        counterparty: model::libp2p::PeerId,
+    ) -> Result<()>;
 }
 
 /// Set of rates needed to accept rollover proposals.
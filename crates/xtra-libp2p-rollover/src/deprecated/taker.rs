@@ -7,7 +7,6 @@ use bdk::bitcoin::Txid;
 use bdk_ext::keypair;
 use futures::SinkExt;
 use futures::StreamExt;
-use maia_core::secp256k1_zkp::XOnlyPublicKey;
 use model::libp2p::PeerId;
 use model::olivia::BitMexPriceEventId;
 use model::Dlc;
@@ -32,7 +31,6 @@ const DECISION_TIMEOUT: Duration = Duration::from_secs(30);
 /// One actor to rule all the rollovers
 pub struct Actor<E, O> {
     endpoint: Address<Endpoint>,
-    oracle_pk: XOnlyPublicKey,
     oracle: O,
     n_payouts: usize,
     executor: E,
@@ -61,7 +59,6 @@ impl<E, O> Actor<E, O> {
     pub fn new(
         endpoint: Address<Endpoint>,
         executor: E,
-        oracle_pk: XOnlyPublicKey,
         get_announcement: O,
         n_payouts: usize,
     ) -> Self {
@@ -69,7 +66,6 @@ impl<E, O> Actor<E, O> {
             endpoint,
             executor,
             oracle: get_announcement,
-            oracle_pk,
             n_payouts,
         }
     }
@@ -124,7 +120,6 @@ where
             {
                 let executor = self.executor.clone();
                 let oracle = self.oracle.clone();
-                let oracle_pk = self.oracle_pk;
                 let n_payouts = self.n_payouts;
                 async move {
                     let mut framed = asynchronous_codec::Framed::new(
@@ -132,14 +127,15 @@ where
                         asynchronous_codec::JsonCodec::<DialerMessage, ListenerMessage>::new(),
                     );
 
-                    let contract_symbol = executor
+                    let (contract_symbol, oracle_config) = executor
                         .execute(order_id, |cfd| {
                             let event = cfd.start_rollover_taker()?;
                             let contract_symbol = cfd.contract_symbol();
 
-                            Ok((event, contract_symbol))
+                            Ok((event, (contract_symbol, cfd.oracle().clone())))
                         })
                         .await?;
+                    let oracle_pk = oracle_config.public_key;
 
                     framed
                         .send(DialerMessage::Propose(Propose {
@@ -185,7 +181,7 @@ where
                                 .await?;
 
                             let announcements = oracle
-                                .get_announcements(oracle_event_ids)
+                                .get_announcements(oracle_config, oracle_event_ids)
                                 .await
                                 .context("Failed to get announcement")?;
                             let settlement_event_id =
@@ -1,4 +1,5 @@
 mod current;
 pub mod deprecated;
+pub mod policy;
 
 pub use current::*;
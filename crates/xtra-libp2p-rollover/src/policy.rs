@@ -0,0 +1,173 @@
+use crate::current::protocol::RejectReason;
+use model::libp2p::PeerId;
+use model::ContractSymbol;
+use model::Contracts;
+use model::FundingRate;
+use std::collections::HashSet;
+
+/// Which rollover proposals the maker accepts.
+///
+/// The default policy accepts all rollovers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+    /// Reject all rollovers.
+    pub paused: bool,
+    /// Reject rollovers of CFDs on these contract symbols.
+    pub paused_contract_symbols: HashSet<ContractSymbol>,
+    /// Reject rollovers proposed by these takers.
+    pub blocked_takers: HashSet<PeerId>,
+    /// Reject rollovers of CFDs with more contracts than this.
+    pub max_quantity: Option<Contracts>,
+    /// Reject rollovers if the funding rate charged for the position is lower than this.
+    pub min_funding_rate: Option<FundingRate>,
+}
+
+impl Policy {
+    /// Check the parts of a proposal that are known before the rates are.
+    pub(crate) fn check_proposal(
+        &self,
+        contract_symbol: ContractSymbol,
+        taker: PeerId,
+        quantity: Contracts,
+    ) -> Result<(), RejectReason> {
+        if self.paused {
+            return Err(RejectReason::NotAcceptingRollovers);
+        }
+
+        if self.paused_contract_symbols.contains(&contract_symbol) {
+            return Err(RejectReason::ContractSymbolPaused { contract_symbol });
+        }
+
+        if self.blocked_takers.contains(&taker) {
+            return Err(RejectReason::TakerNotAllowed);
+        }
+
+        if let Some(max_quantity) = self.max_quantity {
+            if quantity > max_quantity {
+                return Err(RejectReason::PositionTooLarge {
+                    quantity,
+                    max_quantity,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_funding_rate(&self, funding_rate: FundingRate) -> Result<(), RejectReason> {
+        if let Some(min_funding_rate) = self.min_funding_rate {
+            if funding_rate.to_decimal() < min_funding_rate.to_decimal() {
+                return Err(RejectReason::FundingRateTooLow {
+                    funding_rate,
+                    min_funding_rate,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn default_policy_accepts_everything() {
+        let policy = Policy::default();
+
+        assert!(policy
+            .check_proposal(
+                ContractSymbol::BTC_USD,
+                PeerId::random(),
+                Contracts::new(100)
+            )
+            .is_ok());
+        assert!(policy
+            .check_funding_rate(FundingRate::new(dec!(-0.01)).unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_paused_contract_symbol_only() {
+        let policy = Policy {
+            paused_contract_symbols: HashSet::from([ContractSymbol::ETH_USD]),
+            ..Policy::default()
+        };
+
+        assert_eq!(
+            policy.check_proposal(
+                ContractSymbol::ETH_USD,
+                PeerId::random(),
+                Contracts::new(100)
+            ),
+            Err(RejectReason::ContractSymbolPaused {
+                contract_symbol: ContractSymbol::ETH_USD
+            })
+        );
+        assert!(policy
+            .check_proposal(
+                ContractSymbol::BTC_USD,
+                PeerId::random(),
+                Contracts::new(100)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_large_positions() {
+        let policy = Policy {
+            max_quantity: Some(Contracts::new(100)),
+            ..Policy::default()
+        };
+
+        assert!(policy
+            .check_proposal(
+                ContractSymbol::BTC_USD,
+                PeerId::random(),
+                Contracts::new(100)
+            )
+            .is_ok());
+        assert_eq!(
+            policy.check_proposal(
+                ContractSymbol::BTC_USD,
+                PeerId::random(),
+                Contracts::new(101)
+            ),
+            Err(RejectReason::PositionTooLarge {
+                quantity: Contracts::new(101),
+                max_quantity: Contracts::new(100)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_blocked_taker() {
+        let taker = PeerId::random();
+        let policy = Policy {
+            blocked_takers: HashSet::from([taker]),
+            ..Policy::default()
+        };
+
+        assert_eq!(
+            policy.check_proposal(ContractSymbol::BTC_USD, taker, Contracts::new(100)),
+            Err(RejectReason::TakerNotAllowed)
+        );
+    }
+
+    #[test]
+    fn rejects_funding_rate_below_minimum() {
+        let policy = Policy {
+            min_funding_rate: Some(FundingRate::new(dec!(0.0001)).unwrap()),
+            ..Policy::default()
+        };
+
+        assert!(policy
+            .check_funding_rate(FundingRate::new(dec!(0.0002)).unwrap())
+            .is_ok());
+        assert!(policy
+            .check_funding_rate(FundingRate::new(dec!(0.00005)).unwrap())
+            .is_err());
+    }
+}
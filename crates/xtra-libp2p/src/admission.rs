@@ -0,0 +1,213 @@
+use libp2p_core::PeerId;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Limits on how often a single peer may connect and open substreams.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimits {
+    /// How many inbound connections a peer may establish within `period`.
+    pub connections: u32,
+    /// How many substreams a peer may open on a connection within `period`.
+    pub substreams: u32,
+    pub period: Duration,
+}
+
+/// Decides which peers may connect to the endpoint.
+pub(crate) struct Admission {
+    blocked_peers: HashSet<PeerId>,
+    allowed_peers: Option<HashSet<PeerId>>,
+    rate_limits: Option<RateLimits>,
+    connections: HashMap<PeerId, Window>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    Blocked,
+    NotAllowed,
+    RateLimited,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Blocked => write!(f, "peer is blocked"),
+            Rejection::NotAllowed => write!(f, "peer is not on the allowlist"),
+            Rejection::RateLimited => write!(f, "peer connects too often"),
+        }
+    }
+}
+
+impl Admission {
+    pub(crate) fn new(blocked_peers: HashSet<PeerId>) -> Self {
+        Self {
+            blocked_peers,
+            allowed_peers: None,
+            rate_limits: None,
+            connections: HashMap::default(),
+        }
+    }
+
+    pub(crate) fn set_allowed_peers(&mut self, allowed_peers: HashSet<PeerId>) {
+        self.allowed_peers = Some(allowed_peers);
+    }
+
+    pub(crate) fn set_rate_limits(&mut self, rate_limits: RateLimits) {
+        self.rate_limits = Some(rate_limits);
+    }
+
+    pub(crate) fn block(&mut self, peer_id: PeerId) {
+        self.blocked_peers.insert(peer_id);
+    }
+
+    pub(crate) fn unblock(&mut self, peer_id: PeerId) {
+        self.blocked_peers.remove(&peer_id);
+    }
+
+    /// A window limiting the substreams of a new connection, if substreams are limited.
+    pub(crate) fn substream_window(&self) -> Option<Window> {
+        self.rate_limits
+            .map(|limits| Window::new(limits.substreams, limits.period))
+    }
+
+    /// Decide whether to accept an inbound connection from `peer_id`.
+    ///
+    /// Rejected connection attempts count against the rate limit as well.
+    pub(crate) fn admit(&mut self, peer_id: PeerId, now: Instant) -> Result<(), Rejection> {
+        if self.blocked_peers.contains(&peer_id) {
+            return Err(Rejection::Blocked);
+        }
+
+        if let Some(allowed_peers) = &self.allowed_peers {
+            if !allowed_peers.contains(&peer_id) {
+                return Err(Rejection::NotAllowed);
+            }
+        }
+
+        let limits = match self.rate_limits {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+
+        self.connections.retain(|_, window| !window.is_empty(now));
+        let within_limit = self
+            .connections
+            .entry(peer_id)
+            .or_insert_with(|| Window::new(limits.connections, limits.period))
+            .try_acquire(now);
+
+        if !within_limit {
+            return Err(Rejection::RateLimited);
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts events within a sliding window of time.
+pub(crate) struct Window {
+    limit: u32,
+    period: Duration,
+    events: VecDeque<Instant>,
+}
+
+impl Window {
+    fn new(limit: u32, period: Duration) -> Self {
+        Self {
+            limit,
+            period,
+            events: VecDeque::default(),
+        }
+    }
+
+    /// Record an event at `now` if there are fewer than `limit` events within the window.
+    pub(crate) fn try_acquire(&mut self, now: Instant) -> bool {
+        self.expire(now);
+
+        if self.events.len() >= self.limit as usize {
+            return false;
+        }
+
+        self.events.push_back(now);
+        true
+    }
+
+    fn is_empty(&mut self, now: Instant) -> bool {
+        self.expire(now);
+
+        self.events.is_empty()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(event) = self.events.front() {
+            if now.saturating_duration_since(*event) < self.period {
+                break;
+            }
+
+            self.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: RateLimits = RateLimits {
+        connections: 2,
+        substreams: 10,
+        period: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn connections_are_limited_per_peer_within_period() {
+        let mut admission = Admission::new(HashSet::default());
+        admission.set_rate_limits(LIMITS);
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+        let start = Instant::now();
+
+        assert_eq!(admission.admit(peer_id, start), Ok(()));
+        assert_eq!(admission.admit(peer_id, start), Ok(()));
+        assert_eq!(
+            admission.admit(peer_id, start + Duration::from_secs(59)),
+            Err(Rejection::RateLimited)
+        );
+        assert_eq!(admission.admit(other_peer_id, start), Ok(()));
+        assert_eq!(
+            admission.admit(peer_id, start + Duration::from_secs(60)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn only_peers_on_allowlist_are_admitted() {
+        let peer_id = PeerId::random();
+        let mut admission = Admission::new(HashSet::default());
+        admission.set_allowed_peers(HashSet::from([peer_id]));
+
+        assert_eq!(admission.admit(peer_id, Instant::now()), Ok(()));
+        assert_eq!(
+            admission.admit(PeerId::random(), Instant::now()),
+            Err(Rejection::NotAllowed)
+        );
+    }
+
+    #[test]
+    fn unblocked_peer_is_admitted_again() {
+        let peer_id = PeerId::random();
+        let mut admission = Admission::new(HashSet::from([peer_id]));
+
+        assert_eq!(
+            admission.admit(peer_id, Instant::now()),
+            Err(Rejection::Blocked)
+        );
+
+        admission.unblock(peer_id);
+
+        assert_eq!(admission.admit(peer_id, Instant::now()), Ok(()));
+    }
+}
@@ -4,12 +4,13 @@ use crate::Connect;
 use crate::Endpoint;
 use crate::GetConnectionStats;
 use anyhow::anyhow;
-use anyhow::ensure;
+use anyhow::bail;
 use anyhow::Result;
 use async_trait::async_trait;
 use libp2p_core::Multiaddr;
 use libp2p_core::PeerId;
 use std::time::Duration;
+use std::time::Instant;
 use tracing::instrument;
 use xtra::Address;
 use xtra_productivity::xtra_productivity;
@@ -18,32 +19,51 @@ use xtras::SendAsyncNext;
 /// If we're not connected by this time, stop the actor.
 pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// How often to check whether a failed dialing attempt was given up by the endpoint.
+const DIAL_FINISHED_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The longest we try the addresses of the other Endpoint in one go.
+///
+/// Dialing blocks the mailbox of the actor, so it must not take arbitrarily long. Addresses not
+/// tried by then are skipped and the supervisor restarts the dialer.
+const DIAL_DEADLINE: Duration = Duration::from_secs(30);
+
 /// xtra actor that takes care of dialing (connecting) to an Endpoint.
 ///
 /// Polls Endpoint at startup to check whether connection got established correctly, and
 /// then listens for ConnectionDropped message to stop itself.
 /// Should be used in conjunction with supervisor maintaining resilient connection.
+///
+/// If the other Endpoint is reachable on several addresses, they are tried in order until a
+/// connection is established.
 pub struct Actor {
     endpoint: Address<Endpoint>,
-    connect_address: Multiaddr,
+    connect_addresses: Vec<Multiaddr>,
     listener_peer_id: Option<PeerId>,
     stop_reason: Option<Error>,
 }
 
 impl Actor {
     pub fn new(endpoint: Address<Endpoint>, connect_address: Multiaddr) -> Self {
+        Self::with_addresses(endpoint, vec![connect_address])
+    }
+
+    /// Dial the first of the `connect_addresses` that works.
+    ///
+    /// All addresses have to end in the same peer id.
+    pub fn with_addresses(endpoint: Address<Endpoint>, connect_addresses: Vec<Multiaddr>) -> Self {
         Self {
             endpoint,
-            connect_address,
+            connect_addresses,
             listener_peer_id: None,
             stop_reason: None,
         }
     }
 
     #[instrument(skip(self))]
-    async fn connect(&self) -> Result<(), Error> {
+    async fn connect(&self, connect_address: &Multiaddr) -> Result<(), Error> {
         self.endpoint
-            .send(Connect(self.connect_address.clone()))
+            .send(Connect(connect_address.clone()))
             .await
             .map_err(|_| Error::NoEndpoint)?
             .map_err(|e| Error::Failed { source: anyhow!(e) })
@@ -63,12 +83,7 @@ impl xtra::Actor for Actor {
     #[tracing::instrument("Start dialer actor", skip_all)]
     async fn started(&mut self, ctx: &mut xtra::Context<Self>) {
         tracing::debug!("Starting dialer actor");
-        match self
-            .connect_address
-            .clone()
-            .extract_peer_id()
-            .ok_or(Error::InvalidPeerId)
-        {
+        match listener_peer_id(&self.connect_addresses) {
             Ok(peer_id) => self.listener_peer_id = Some(peer_id),
             Err(e) => {
                 self.stop_with_error(e, ctx);
@@ -100,6 +115,16 @@ impl Actor {
             .contains(&self.peer_id()))
     }
 
+    #[instrument(skip(self), err)]
+    async fn is_dial_in_progress(&self) -> Result<bool> {
+        Ok(self
+            .endpoint
+            .send(GetConnectionStats)
+            .await?
+            .connecting_peers
+            .contains(&self.peer_id()))
+    }
+
     #[instrument(skip(self), err)]
     async fn dial(&self) -> Result<()> {
         if self.is_connection_established().await? {
@@ -107,19 +132,60 @@ impl Actor {
             return Ok(());
         }
 
-        if let Err(e) = self.connect().await {
-            tracing::warn!("Failed to request connection from endpoint: {e:#}");
+        let deadline = Instant::now() + DIAL_DEADLINE;
+
+        for (i, connect_address) in self.connect_addresses.iter().enumerate() {
+            if i > 0 {
+                // The endpoint only dials a peer once at a time
+                while self.is_dial_in_progress().await? {
+                    if Instant::now() >= deadline {
+                        bail!(
+                            "Previous dialing attempt did not finish within {} seconds",
+                            DIAL_DEADLINE.as_secs()
+                        );
+                    }
+
+                    tokio_extras::time::sleep(DIAL_FINISHED_POLL_INTERVAL).await;
+                }
+            }
+
+            if Instant::now() + CONNECTION_TIMEOUT > deadline {
+                bail!(
+                    "No connection within {} seconds, not dialing {connect_address}",
+                    DIAL_DEADLINE.as_secs()
+                );
+            }
+
+            if let Err(e) = self.connect(connect_address).await {
+                tracing::warn!(%connect_address, "Failed to request connection from endpoint: {e:#}");
+            }
+
+            // Only check the connection again after it had enough time to be established
+            tokio_extras::time::sleep(CONNECTION_TIMEOUT).await;
+
+            if self.is_connection_established().await? {
+                return Ok(());
+            }
+
+            tracing::debug!(%connect_address, "No connection after dialing attempt");
         }
 
-        // Only check the connection again after it had enough time to be established
-        tokio_extras::time::sleep(CONNECTION_TIMEOUT).await;
+        bail!("No connection after dialing attempt");
+    }
+}
+
+/// The peer id shared by all addresses.
+fn listener_peer_id(connect_addresses: &[Multiaddr]) -> Result<PeerId, Error> {
+    let mut peer_ids = connect_addresses
+        .iter()
+        .map(|connect_address| connect_address.clone().extract_peer_id());
 
-        ensure!(
-            self.is_connection_established().await?,
-            "No connection after dialing attempt",
-        );
-        Ok(())
+    let peer_id = peer_ids.next().flatten().ok_or(Error::InvalidPeerId)?;
+    if !peer_ids.all(|other| other == Some(peer_id)) {
+        return Err(Error::InvalidPeerId);
     }
+
+    Ok(peer_id)
 }
 
 #[xtra_productivity]
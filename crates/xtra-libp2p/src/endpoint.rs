@@ -1,3 +1,5 @@
+use crate::admission::Admission;
+use crate::admission::RateLimits;
 use crate::multiaddress_ext::MultiaddrExt as _;
 use crate::upgrade;
 use crate::Connection;
@@ -27,6 +29,7 @@ use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 use thiserror::Error;
 use tokio_extras::Tasks;
 use tracing::instrument;
@@ -55,13 +58,18 @@ use xtras::SendAsyncSafe;
 /// connection. Any incoming substream will - assuming the protocol is supported by the endpoint -
 /// trigger a [`NewInboundSubstream`] message to the actor provided in the constructor.
 /// Opening a new substream can be achieved by sending the [`OpenSubstream`] message.
+///
+/// Inbound connections from blocked peers are dropped. Peers can be blocked and unblocked at
+/// runtime by sending [`BlockPeer`] and [`UnblockPeer`]. Optionally, only peers on an allowlist are
+/// accepted (see [`Endpoint::with_allowlist`]) and the connections and substreams per peer are
+/// rate limited (see [`Endpoint::with_rate_limits`]).
 pub struct Endpoint {
     transport_fn: Box<dyn Fn() -> Boxed<Connection> + Send + 'static>,
     controls: HashMap<PeerId, (yamux::Control, Tasks)>,
     inbound_substream_channels: HashMap<&'static str, MessageChannel<NewInboundSubstream, ()>>,
     listen_addresses: HashSet<Multiaddr>,
     inflight_connections: HashSet<PeerId>,
-    blocked_peers: Arc<HashSet<PeerId>>,
+    admission: Admission,
     connection_timeout: Duration,
     subscribers: Subscribers,
     peer_listen_protocols: HashMap<PeerId, HashSet<String>>,
@@ -132,6 +140,16 @@ pub struct Connect(pub Multiaddr);
 #[derive(Clone, Copy, Debug)]
 pub struct Disconnect(pub PeerId);
 
+/// Refuse inbound connections from the given peer.
+///
+/// An existing connection to the peer is dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockPeer(pub PeerId);
+
+/// Accept inbound connections from the given peer again.
+#[derive(Clone, Copy, Debug)]
+pub struct UnblockPeer(pub PeerId);
+
 /// Listen on the provided [`Multiaddr`].
 ///
 /// For this to work, the [`Endpoint`] needs to be constructed with a compatible transport.
@@ -146,6 +164,7 @@ pub struct GetConnectionStats;
 #[derive(Debug, Default)]
 pub struct ConnectionStats {
     pub connected_peers: HashSet<PeerId>,
+    pub connecting_peers: HashSet<PeerId>,
     pub listen_addresses: HashSet<Multiaddr>,
 }
 
@@ -260,13 +279,27 @@ impl Endpoint {
             controls: HashMap::default(),
             listen_addresses: HashSet::default(),
             inflight_connections: HashSet::default(),
-            blocked_peers,
+            admission: Admission::new(blocked_peers.as_ref().clone()),
             connection_timeout,
             subscribers,
             peer_listen_protocols: HashMap::default(),
         }
     }
 
+    /// Only accept inbound connections from the given peers.
+    pub fn with_allowlist(mut self, allowed_peers: HashSet<PeerId>) -> Self {
+        self.admission.set_allowed_peers(allowed_peers);
+        self
+    }
+
+    /// Limit how often a peer may connect and open substreams.
+    ///
+    /// Connections exceeding the limit are dropped, substreams exceeding the limit are closed.
+    pub fn with_rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.admission.set_rate_limits(rate_limits);
+        self
+    }
+
     fn does_peer_listen_for(&self, peer_id: PeerId, protocols: &[&str]) -> Result<(), Error> {
         let listen_protocols = match self.peer_listen_protocols.get(&peer_id) {
             Some(listen_protocols) => listen_protocols,
@@ -348,8 +381,22 @@ impl Endpoint {
             control,
             mut incoming_substreams,
             worker,
+            inbound,
         } = msg;
 
+        if inbound {
+            if let Err(rejection) = self.admission.admit(peer_id, Instant::now()) {
+                tracing::debug!(
+                    target: "blocked_peers",
+                    peer_id = %peer_id, // Weird but required
+                    "Refused inbound connection: {rejection}"
+                );
+                return; // Dropping the connection closes it
+            }
+        }
+
+        let mut substream_window = self.admission.substream_window();
+
         let mut tasks = Tasks::default();
         tasks.add(worker);
         tasks.add_fallible(
@@ -376,6 +423,13 @@ impl Endpoint {
                             Err(e) => bail!(e),
                         };
 
+                        if let Some(window) = substream_window.as_mut() {
+                            if !window.try_acquire(Instant::now()) {
+                                tracing::debug!(%peer_id, %protocol, "Closing substream over rate limit");
+                                continue;
+                            }
+                        }
+
                         let channel = inbound_substream_channels
                             .get(&protocol)
                             .expect("Cannot negotiate a protocol that we don't support");
@@ -432,6 +486,7 @@ impl Endpoint {
     async fn handle(&mut self, _: GetConnectionStats) -> ConnectionStats {
         ConnectionStats {
             connected_peers: self.controls.keys().copied().collect(),
+            connecting_peers: self.inflight_connections.clone(),
             listen_addresses: self.listen_addresses.clone(),
         }
     }
@@ -473,6 +528,7 @@ impl Endpoint {
                         control,
                         incoming_substreams,
                         worker,
+                        inbound: false,
                     })
                     .await;
 
@@ -495,6 +551,22 @@ impl Endpoint {
             .await;
     }
 
+    async fn handle(&mut self, msg: BlockPeer, ctx: &mut Context<Self>) {
+        let peer_id = msg.0;
+        tracing::info!(%peer_id, "Blocking peer");
+
+        self.admission.block(peer_id);
+        self.drop_connection(&ctx.address().expect("self to be alive"), &peer_id)
+            .await;
+    }
+
+    async fn handle(&mut self, msg: UnblockPeer) {
+        let peer_id = msg.0;
+        tracing::info!(%peer_id, "Unblocking peer");
+
+        self.admission.unblock(peer_id);
+    }
+
     async fn handle(&mut self, msg: ListenOn, ctx: &mut Context<Self>) {
         let this = ctx.address().expect("we are alive");
         let listen_address = msg.0.clone();
@@ -504,7 +576,6 @@ impl Endpoint {
         tokio_extras::spawn_fallible::<_, _, _, (), _, _, _>(
             &this.clone(),
             {
-                let blocked_peers = self.blocked_peers.clone();
                 let this = this.clone();
                 let listen_address = listen_address.clone();
 
@@ -528,7 +599,6 @@ impl Endpoint {
                                 remote_addr,
                                 ..
                             }) => {
-                                let blocked_peers = blocked_peers.clone();
                                 let this = this.clone();
                                 tasks.add_fallible(
                                     async move {
@@ -542,20 +612,12 @@ impl Endpoint {
                                                 }
                                             })?;
 
-                                        if blocked_peers.contains(&peer_id) {
-                                            tracing::trace!(
-                                                target: "blocked_peers",
-                                                peer_id = %peer_id, // Weird but required
-                                                "Blocked peer from connecting"
-                                            );
-                                            return Ok(()); // Skip this peer
-                                        }
-
                                         this.send_async_next(NewConnection {
                                             peer_id,
                                             control,
                                             incoming_substreams,
                                             worker,
+                                            inbound: true,
                                         })
                                         .await;
                                         Ok(())
@@ -782,6 +844,8 @@ struct NewConnection {
         >,
     >,
     worker: BoxFuture<'static, ()>,
+    /// Whether the peer dialed us, only these connections are subject to admission control.
+    inbound: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -1,3 +1,5 @@
+pub use crate::admission::RateLimits;
+pub use crate::endpoint::BlockPeer;
 pub use crate::endpoint::Connect;
 pub use crate::endpoint::ConnectionStats;
 pub use crate::endpoint::Disconnect;
@@ -9,6 +11,7 @@ pub use crate::endpoint::Multiple;
 pub use crate::endpoint::NewInboundSubstream;
 pub use crate::endpoint::OpenSubstream;
 pub use crate::endpoint::Single;
+pub use crate::endpoint::UnblockPeer;
 pub use crate::substream::Substream;
 pub use libp2p_core as libp2p;
 pub use multistream_select::NegotiationError;
@@ -18,6 +21,7 @@ use futures::stream::BoxStream;
 use libp2p_core::Negotiated;
 use libp2p_core::PeerId;
 
+mod admission;
 pub mod dialer;
 pub mod endpoint;
 pub mod listener;
@@ -4,6 +4,10 @@ use libp2p_core::PeerId;
 
 pub trait MultiaddrExt {
     fn extract_peer_id(self) -> Option<PeerId>;
+
+    /// Whether this is a TCP address, i.e. `/<ip4|ip6|dns|dns4|dns6>/<host>/tcp/<port>` with an
+    /// optional `/p2p/<peer-id>` at the end.
+    fn is_tcp(&self) -> bool;
 }
 
 impl MultiaddrExt for Multiaddr {
@@ -15,4 +19,70 @@ impl MultiaddrExt for Multiaddr {
 
         Some(peer_id)
     }
+
+    fn is_tcp(&self) -> bool {
+        let mut protocols = self.iter();
+
+        let is_host = matches!(
+            protocols.next(),
+            Some(
+                Protocol::Ip4(_)
+                    | Protocol::Ip6(_)
+                    | Protocol::Dns(_)
+                    | Protocol::Dns4(_)
+                    | Protocol::Dns6(_)
+            )
+        );
+        let is_port = matches!(protocols.next(), Some(Protocol::Tcp(_)));
+        let is_end = matches!(protocols.next(), None | Some(Protocol::P2p(_)));
+
+        is_host && is_port && is_end && protocols.next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEER_ID: &str = "12D3KooWP3BN6bq9jPy8cP7Grj1QyUBfr7U6BeQFgMwfTTu12wuY";
+
+    #[test]
+    fn extract_peer_id_from_dns_address() {
+        let multiaddr = format!("/dns/mainnet.itchysats.network/tcp/10001/p2p/{PEER_ID}")
+            .parse::<Multiaddr>()
+            .unwrap();
+
+        assert_eq!(multiaddr.extract_peer_id(), Some(PEER_ID.parse().unwrap()));
+    }
+
+    #[test]
+    fn tcp_addresses() {
+        for multiaddr in [
+            "/ip4/127.0.0.1/tcp/10000".to_owned(),
+            "/ip6/::1/tcp/10000".to_owned(),
+            "/dns/mainnet.itchysats.network/tcp/10001".to_owned(),
+            "/dns4/mainnet.itchysats.network/tcp/10001".to_owned(),
+            format!("/dns6/mainnet.itchysats.network/tcp/10001/p2p/{PEER_ID}"),
+        ] {
+            assert!(
+                multiaddr.parse::<Multiaddr>().unwrap().is_tcp(),
+                "{multiaddr} is a TCP address"
+            );
+        }
+    }
+
+    #[test]
+    fn no_tcp_addresses() {
+        for multiaddr in [
+            "/ip4/127.0.0.1/udp/10000",
+            "/memory/10000",
+            "/ip4/127.0.0.1",
+            "/ip4/127.0.0.1/tcp/10000/ws",
+        ] {
+            assert!(
+                !multiaddr.parse::<Multiaddr>().unwrap().is_tcp(),
+                "{multiaddr} is not a TCP address"
+            );
+        }
+    }
 }
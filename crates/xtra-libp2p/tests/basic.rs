@@ -20,6 +20,7 @@ use xtra::Context;
 use xtra_libp2p::endpoint;
 use xtra_libp2p::endpoint::RegisterListenProtocols;
 use xtra_libp2p::libp2p::PeerId;
+use xtra_libp2p::BlockPeer;
 use xtra_libp2p::Connect;
 use xtra_libp2p::Disconnect;
 use xtra_libp2p::GetConnectionStats;
@@ -108,6 +109,17 @@ async fn blocked_peers_cannot_connect() {
     assert!(bob_to_alice.is_err());
 }
 
+#[tokio::test]
+async fn blocking_connected_peer_drops_connection() {
+    let (alice, bob, _) = alice_and_bob([], []).await;
+
+    alice.endpoint.send(BlockPeer(bob.peer_id)).await.unwrap();
+
+    let alice_stats = alice.endpoint.send(GetConnectionStats).await.unwrap();
+
+    assert_eq!(alice_stats.connected_peers, HashSet::from([]));
+}
+
 #[tokio::test]
 async fn after_connect_see_each_other_as_connected() {
     let (alice, bob, _) = alice_and_bob([], []).await;
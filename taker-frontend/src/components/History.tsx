@@ -1,6 +1,7 @@
 import { ExternalLinkIcon, InfoIcon } from "@chakra-ui/icons";
 import {
     Badge,
+    Button,
     Center,
     GridItem,
     Heading,
@@ -25,7 +26,7 @@ import {
     VStack,
 } from "@chakra-ui/react";
 import * as React from "react";
-import { Cfd, ConnectionStatus, isClosed, StateKey, Tx, TxLabel } from "../types";
+import { Cfd, ConnectionStatus, isClosed, MakerRequest, StateKey, Tx, TxLabel } from "../types";
 import usePostRequest from "../usePostRequest";
 import BitcoinAmount from "./BitcoinAmount";
 import CloseButton from "./CloseButton";
@@ -87,9 +88,17 @@ const CfdDetails = ({ cfd, connectedToMaker, displayCloseButton, showExtraInfo }
     const txRefund = cfd.details.tx_url_list.find((tx) => tx.label === TxLabel.Refund);
     const txCet = cfd.details.tx_url_list.find((tx) => tx.label === TxLabel.Cet);
     const txSettled = cfd.details.tx_url_list.find((tx) => tx.label === TxLabel.Collaborative);
+    const txPunish = cfd.details.tx_url_list.find((tx) => tx.label === TxLabel.Punish);
+    const txFeeBumps = cfd.details.tx_url_list.filter((tx) => tx.label === TxLabel.FeeBump);
 
     let [settle, isSettling] = usePostRequest(`/api/cfd/${cfd.order_id}/settle`);
     let [commit, isCommiting] = usePostRequest(`/api/cfd/${cfd.order_id}/commit`);
+    let [acceptMakerRequest, isAcceptingMakerRequest] = usePostRequest(
+        `/api/cfd/${cfd.order_id}/acceptMakerRequest`,
+    );
+    let [rejectMakerRequest, isRejectingMakerRequest] = usePostRequest(
+        `/api/cfd/${cfd.order_id}/rejectMakerRequest`,
+    );
 
     const closeButton = connectedToMaker.online
         ? (
@@ -270,6 +279,12 @@ const CfdDetails = ({ cfd, connectedToMaker, displayCloseButton, showExtraInfo }
                     >
                         {cfd.state.getLabel()}
                     </Badge>
+                    {cfd.maker_request
+                        && (
+                            <Badge variant={"solid"} ml={1} fontSize="sm" colorScheme={"orange"}>
+                                {makerRequestLabel(cfd.maker_request)}
+                            </Badge>
+                        )}
                     <Table size="sm" variant={"unstyled"}>
                         <Tbody>
                             <Tr>
@@ -280,7 +295,18 @@ const CfdDetails = ({ cfd, connectedToMaker, displayCloseButton, showExtraInfo }
                                     <TxIcon tx={txLock} />
                                 </Td>
                             </Tr>
-                            {txRefund
+                            {txPunish
+                                ? (
+                                    <Tr>
+                                        <Td>
+                                            <Text>Punish</Text>
+                                        </Td>
+                                        <Td>
+                                            <TxIcon tx={txPunish} />
+                                        </Td>
+                                    </Tr>
+                                )
+                                : txRefund
                                 ? (
                                     <Tr>
                                         <Td>
@@ -322,8 +348,45 @@ const CfdDetails = ({ cfd, connectedToMaker, displayCloseButton, showExtraInfo }
                                         </Td>
                                     </Tr>
                                 )}
+                            {txFeeBumps.length > 0
+                                ? (
+                                    <Tr>
+                                        <Td>
+                                            <Text>Fee bump</Text>
+                                        </Td>
+                                        <Td>
+                                            <HStack spacing={1}>
+                                                {txFeeBumps.map((tx) => <TxIcon key={tx.url} tx={tx} />)}
+                                            </HStack>
+                                        </Td>
+                                    </Tr>
+                                )
+                                : <></>}
                         </Tbody>
                     </Table>
+                    {cfd.maker_request
+                        ? (
+                            <HStack width={"100%"} justifyContent={"flex-end"}>
+                                <Button
+                                    size="sm"
+                                    colorScheme={"green"}
+                                    onClick={() => acceptMakerRequest([{}])}
+                                    isLoading={isAcceptingMakerRequest}
+                                    disabled={!connectedToMaker.online}
+                                >
+                                    Accept
+                                </Button>
+                                <Button
+                                    size="sm"
+                                    colorScheme={"red"}
+                                    onClick={() => rejectMakerRequest([{}])}
+                                    isLoading={isRejectingMakerRequest}
+                                >
+                                    Reject
+                                </Button>
+                            </HStack>
+                        )
+                        : <></>}
                     {displayCloseButton
                         ? (
                             <HStack width={"100%"} paddingBottom={2} justifyContent={"flex-end"}>
@@ -363,6 +426,15 @@ const TxIcon = ({ tx }: TxIconProps) => {
     }
 };
 
+function makerRequestLabel(request: MakerRequest) {
+    switch (request) {
+        case MakerRequest.Settlement:
+            return "Maker asks to close";
+        case MakerRequest.Rollover:
+            return "Maker asks to roll over";
+    }
+}
+
 function timeConverter(timestamp: number) {
     const a = new Date(timestamp * 1000);
     const year = a.getFullYear();
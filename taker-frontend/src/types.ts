@@ -46,6 +46,7 @@ export interface MakerOffer {
     funding_rate_annualized_percent: number; // e.g. "18.5" (does not include % char)
     funding_rate_hourly_percent: number; // e.g. "0.002345" (does not include % char)
     creation_timestamp: number;
+    maker_peer_id?: string;
 }
 
 export interface LeverageDetails {
@@ -98,6 +99,14 @@ export interface Cfd {
     counterparty: string;
 
     accumulated_fees: number;
+
+    // settlement or rollover the maker asked for, awaiting our approval
+    maker_request?: MakerRequest;
+}
+
+export enum MakerRequest {
+    Settlement = "settlement",
+    Rollover = "rollover",
 }
 
 export function isClosed(cfd: Cfd): boolean {
@@ -119,6 +128,8 @@ export enum TxLabel {
     Cet = "Cet",
     Refund = "Refund",
     Collaborative = "Collaborative",
+    Punish = "Punish",
+    FeeBump = "FeeBump",
 }
 
 export class State {